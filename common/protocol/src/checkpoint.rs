@@ -0,0 +1,277 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Checkpoints of long-running protocol sessions, so that a crashed party
+//! can resume from its last checkpoint instead of restarting the session.
+//!
+//! A checkpoint records the party state together with the number of
+//! messages handled so far, which tells the peers where to resume sending.
+//! Checkpoints are sealed by a MAC with a key only known by the party, so
+//! that tampered checkpoint files are rejected on loading.
+
+use crate::ProtocolParty;
+use std::{fs, io::Write, path::Path};
+use wedpr_l_utils::{error::WedprError, traits::Mac};
+
+/// Magic bytes at the beginning of a sealed checkpoint.
+const CHECKPOINT_MAGIC: &[u8] = b"WeDPR_CHECKPOINT";
+/// Version of the checkpoint format.
+const CHECKPOINT_VERSION: u32 = 1;
+
+/// Party whose state can be saved and restored.
+pub trait ResumableParty: ProtocolParty {
+    /// Saves the current state.
+    fn save_state(&self) -> Vec<u8>;
+
+    /// Restores a state returned by save_state.
+    fn restore_state(&mut self, state: &[u8]) -> Result<(), WedprError>;
+}
+
+/// Snapshot of a party in a session.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub session_id: Vec<u8>,
+    pub party_index: u64,
+    /// Number of messages handled by the party before the snapshot.
+    pub handled_messages: u64,
+    pub state: Vec<u8>,
+}
+
+impl Checkpoint {
+    /// Captures the current state of a party.
+    pub fn capture<P: ResumableParty + ?Sized>(
+        party: &P,
+        session_id: &[u8],
+        party_index: u64,
+        handled_messages: u64,
+    ) -> Self {
+        Checkpoint {
+            session_id: session_id.to_vec(),
+            party_index,
+            handled_messages,
+            state: party.save_state(),
+        }
+    }
+
+    /// Restores a party of the expected session and index, and returns the
+    /// number of messages handled before the checkpoint.
+    pub fn restore<P: ResumableParty + ?Sized>(
+        &self,
+        party: &mut P,
+        session_id: &[u8],
+        party_index: u64,
+    ) -> Result<u64, WedprError> {
+        if self.session_id != session_id || self.party_index != party_index {
+            wedpr_println!("Checkpoint belongs to another session or party");
+            return Err(WedprError::VerificationError);
+        }
+        party.restore_state(&self.state)?;
+        Ok(self.handled_messages)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut output = CHECKPOINT_MAGIC.to_vec();
+        output.extend_from_slice(&CHECKPOINT_VERSION.to_be_bytes());
+        output.extend_from_slice(&(self.session_id.len() as u64).to_be_bytes());
+        output.extend_from_slice(&self.session_id);
+        output.extend_from_slice(&self.party_index.to_be_bytes());
+        output.extend_from_slice(&self.handled_messages.to_be_bytes());
+        output.extend_from_slice(&(self.state.len() as u64).to_be_bytes());
+        output.extend_from_slice(&self.state);
+        output
+    }
+
+    fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        if !input.starts_with(CHECKPOINT_MAGIC) {
+            wedpr_println!("Checkpoint has an unknown format");
+            return Err(WedprError::FormatError);
+        }
+        let mut offset = CHECKPOINT_MAGIC.len();
+        let mut version_buffer = [0u8; 4];
+        version_buffer.copy_from_slice(read_fixed(input, &mut offset, 4)?);
+        if u32::from_be_bytes(version_buffer) != CHECKPOINT_VERSION {
+            wedpr_println!("Checkpoint version is not supported");
+            return Err(WedprError::FormatError);
+        }
+        let session_id_length = read_u64(input, &mut offset)? as usize;
+        let session_id =
+            read_fixed(input, &mut offset, session_id_length)?.to_vec();
+        let party_index = read_u64(input, &mut offset)?;
+        let handled_messages = read_u64(input, &mut offset)?;
+        let state_length = read_u64(input, &mut offset)? as usize;
+        let state = read_fixed(input, &mut offset, state_length)?.to_vec();
+        if offset != input.len() {
+            wedpr_println!("Checkpoint has trailing bytes");
+            return Err(WedprError::FormatError);
+        }
+        Ok(Checkpoint {
+            session_id,
+            party_index,
+            handled_messages,
+            state,
+        })
+    }
+
+    /// Seals the checkpoint with a MAC key for storing.
+    pub fn seal<M: Mac>(&self, mac: &M, key: &[u8]) -> Vec<u8> {
+        let content = self.encode();
+        let mut output = (content.len() as u64).to_be_bytes().to_vec();
+        let mut tag = mac.mac(key, &content[..]);
+        output.extend_from_slice(&content);
+        output.append(&mut tag);
+        output
+    }
+
+    /// Opens a sealed checkpoint, which fails if it has been tampered.
+    pub fn open<M: Mac>(
+        mac: &M,
+        key: &[u8],
+        sealed: &[u8],
+    ) -> Result<Self, WedprError> {
+        let mut offset = 0;
+        let content_length = read_u64(sealed, &mut offset)? as usize;
+        let content = read_fixed(sealed, &mut offset, content_length)?;
+        let tag = &sealed[offset..];
+        if !mac.verify_mac(key, content, tag) {
+            wedpr_println!("Checkpoint integrity check failed");
+            return Err(WedprError::VerificationError);
+        }
+        Checkpoint::decode(content)
+    }
+}
+
+fn read_fixed<'a>(
+    input: &'a [u8],
+    offset: &mut usize,
+    length: usize,
+) -> Result<&'a [u8], WedprError> {
+    if input.len() - *offset < length {
+        wedpr_println!("Checkpoint is truncated");
+        return Err(WedprError::FormatError);
+    }
+    let field = &input[*offset..*offset + length];
+    *offset += length;
+    Ok(field)
+}
+
+fn read_u64(input: &[u8], offset: &mut usize) -> Result<u64, WedprError> {
+    let mut buffer = [0u8; 8];
+    buffer.copy_from_slice(read_fixed(input, offset, 8)?);
+    Ok(u64::from_be_bytes(buffer))
+}
+
+/// Writes a sealed checkpoint to a file, through a temporary file renamed
+/// over it, so that a crash while writing keeps the previous checkpoint.
+pub fn write_checkpoint_file<P: AsRef<Path>>(
+    path: P,
+    sealed: &[u8],
+) -> Result<(), WedprError> {
+    let path = path.as_ref();
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let write_result = fs::File::create(&temp_path).and_then(|mut file| {
+        file.write_all(sealed)?;
+        file.sync_all()
+    });
+    if write_result.is_err() || fs::rename(&temp_path, path).is_err() {
+        wedpr_println!("Checkpoint cannot be written");
+        return Err(WedprError::StorageError);
+    }
+    Ok(())
+}
+
+/// Reads a sealed checkpoint from a file.
+pub fn read_checkpoint_file<P: AsRef<Path>>(
+    path: P,
+) -> Result<Vec<u8>, WedprError> {
+    match fs::read(path) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Checkpoint cannot be read");
+            Err(WedprError::StorageError)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProtocolMessage;
+    use wedpr_l_crypto_hash_sha256::WedprSha256;
+    use wedpr_l_crypto_mac_hmac::{WedprHmac, HMAC_SHA256_BLOCK_SIZE_IN_BYTES};
+
+    /// Party summing the received bytes.
+    #[derive(Default)]
+    struct SumParty {
+        sum: u64,
+    }
+
+    impl ProtocolParty for SumParty {
+        fn start(&mut self) -> Result<Vec<ProtocolMessage>, WedprError> {
+            Ok(Vec::new())
+        }
+
+        fn handle(
+            &mut self,
+            message: &ProtocolMessage,
+        ) -> Result<Vec<ProtocolMessage>, WedprError> {
+            self.sum += message.payload.iter().map(|v| *v as u64).sum::<u64>();
+            Ok(Vec::new())
+        }
+
+        fn is_finished(&self) -> bool {
+            false
+        }
+    }
+
+    impl ResumableParty for SumParty {
+        fn save_state(&self) -> Vec<u8> {
+            self.sum.to_be_bytes().to_vec()
+        }
+
+        fn restore_state(&mut self, state: &[u8]) -> Result<(), WedprError> {
+            let mut buffer = [0u8; 8];
+            if state.len() != buffer.len() {
+                return Err(WedprError::FormatError);
+            }
+            buffer.copy_from_slice(state);
+            self.sum = u64::from_be_bytes(buffer);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let hmac = WedprHmac::new(
+            WedprSha256::default(),
+            HMAC_SHA256_BLOCK_SIZE_IN_BYTES,
+        );
+        let key = [3u8; 32];
+        let mut party = SumParty::default();
+        party.handle(&ProtocolMessage::new(0, 1, &[5, 6])).unwrap();
+        let sealed =
+            Checkpoint::capture(&party, b"session-1", 1, 1).seal(&hmac, &key);
+
+        let path = std::env::temp_dir().join("wedpr_checkpoint_test");
+        write_checkpoint_file(&path, &sealed).unwrap();
+        let loaded = read_checkpoint_file(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let checkpoint = Checkpoint::open(&hmac, &key, &loaded).unwrap();
+        let mut resumed_party = SumParty::default();
+        assert_eq!(
+            checkpoint
+                .restore(&mut resumed_party, b"session-1", 1)
+                .unwrap(),
+            1
+        );
+        assert_eq!(resumed_party.sum, 11);
+        assert!(checkpoint
+            .restore(&mut resumed_party, b"session-2", 1)
+            .is_err());
+
+        let mut tampered = sealed.clone();
+        let last = tampered.len() - 40;
+        tampered[last] ^= 1;
+        assert!(Checkpoint::open(&hmac, &key, &tampered).is_err());
+        assert!(Checkpoint::open(&hmac, &[4u8; 32], &sealed).is_err());
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Library of shared harnesses for interactive protocols, e.g. OT, PSI or
+//! DKG sessions, whose parties are written as message-driven state machines.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+pub mod checkpoint;
+pub mod simulation;
+pub mod transport;
+
+use wedpr_l_utils::error::WedprError;
+
+/// Message sent between parties of an interactive protocol, where parties
+/// are identified by their indexes in a session.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolMessage {
+    pub from: usize,
+    pub to: usize,
+    pub payload: Vec<u8>,
+}
+
+impl ProtocolMessage {
+    /// Creates a message.
+    pub fn new(from: usize, to: usize, payload: &[u8]) -> Self {
+        ProtocolMessage {
+            from,
+            to,
+            payload: payload.to_vec(),
+        }
+    }
+}
+
+/// Party of an interactive protocol as a message-driven state machine.
+pub trait ProtocolParty {
+    /// Starts the protocol, and returns the first messages to send.
+    fn start(&mut self) -> Result<Vec<ProtocolMessage>, WedprError>;
+
+    /// Handles a received message, and returns the messages to send.
+    fn handle(
+        &mut self,
+        message: &ProtocolMessage,
+    ) -> Result<Vec<ProtocolMessage>, WedprError>;
+
+    /// Checks whether the party has finished the protocol.
+    fn is_finished(&self) -> bool;
+}
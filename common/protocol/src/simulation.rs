@@ -0,0 +1,342 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Deterministic simulation of protocol sessions without real networking.
+//!
+//! A simulation delivers messages in FIFO order, applies faults injected at
+//! given send sequence numbers (drops, duplicates, corruption and
+//! reordering), and records all sent and delivered messages. A recording can
+//! be replayed against a single party for regression tests, as long as the
+//! party is deterministic, e.g. with a seeded random number generator.
+
+use crate::{ProtocolMessage, ProtocolParty};
+use std::collections::{BTreeMap, VecDeque};
+use wedpr_l_utils::error::WedprError;
+
+/// Fault injected to a sent message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Never delivers the message.
+    Drop,
+    /// Delivers the message twice.
+    Duplicate,
+    /// XORs the payload byte at offset with mask, if it exists.
+    Corrupt { offset: usize, mask: u8 },
+    /// Delivers the message after up to positions messages already queued.
+    Delay { positions: usize },
+}
+
+/// Faults to inject, keyed by the send sequence numbers of messages, which
+/// count from 0 over all messages sent in a session.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct FaultPlan {
+    faults: BTreeMap<u64, Fault>,
+}
+
+impl FaultPlan {
+    /// Creates a plan without faults.
+    pub fn new() -> Self {
+        FaultPlan::default()
+    }
+
+    /// Injects a fault to the message of a send sequence number.
+    pub fn inject(mut self, sequence: u64, fault: Fault) -> Self {
+        self.faults.insert(sequence, fault);
+        self
+    }
+}
+
+/// Messages of a session, in their sent and delivered orders.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct SessionRecording {
+    pub sent: Vec<ProtocolMessage>,
+    pub delivered: Vec<ProtocolMessage>,
+}
+
+impl SessionRecording {
+    /// Encodes a recording to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        for messages in &[&self.sent, &self.delivered] {
+            output.extend_from_slice(&(messages.len() as u64).to_be_bytes());
+            for message in messages.iter() {
+                output.extend_from_slice(&(message.from as u64).to_be_bytes());
+                output.extend_from_slice(&(message.to as u64).to_be_bytes());
+                output.extend_from_slice(
+                    &(message.payload.len() as u64).to_be_bytes(),
+                );
+                output.extend_from_slice(&message.payload);
+            }
+        }
+        output
+    }
+
+    /// Decodes a recording from bytes.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        let mut offset = 0;
+        let sent = decode_messages(input, &mut offset)?;
+        let delivered = decode_messages(input, &mut offset)?;
+        if offset != input.len() {
+            wedpr_println!("Session recording has trailing bytes");
+            return Err(WedprError::FormatError);
+        }
+        Ok(SessionRecording { sent, delivered })
+    }
+}
+
+fn read_fixed<'a>(
+    input: &'a [u8],
+    offset: &mut usize,
+    length: usize,
+) -> Result<&'a [u8], WedprError> {
+    if input.len() - *offset < length {
+        wedpr_println!("Session recording is truncated");
+        return Err(WedprError::FormatError);
+    }
+    let field = &input[*offset..*offset + length];
+    *offset += length;
+    Ok(field)
+}
+
+fn read_u64(input: &[u8], offset: &mut usize) -> Result<u64, WedprError> {
+    let mut buffer = [0u8; 8];
+    buffer.copy_from_slice(read_fixed(input, offset, 8)?);
+    Ok(u64::from_be_bytes(buffer))
+}
+
+fn decode_messages(
+    input: &[u8],
+    offset: &mut usize,
+) -> Result<Vec<ProtocolMessage>, WedprError> {
+    let count = read_u64(input, offset)?;
+    let mut messages = Vec::new();
+    for _ in 0..count {
+        let from = read_u64(input, offset)? as usize;
+        let to = read_u64(input, offset)? as usize;
+        let payload_length = read_u64(input, offset)? as usize;
+        let payload = read_fixed(input, offset, payload_length)?.to_vec();
+        messages.push(ProtocolMessage { from, to, payload });
+    }
+    Ok(messages)
+}
+
+/// Result of a simulated session.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct SimulationOutcome {
+    pub recording: SessionRecording,
+    /// Errors returned by parties with their indexes, where a failed party
+    /// receives no further messages.
+    pub errors: Vec<(usize, WedprError)>,
+    /// Whether each party has finished the protocol.
+    pub finished: Vec<bool>,
+}
+
+/// Queue of in-flight messages, which applies the fault plan on sending.
+struct MessageQueue<'a> {
+    plan: &'a FaultPlan,
+    next_sequence: u64,
+    in_flight: VecDeque<ProtocolMessage>,
+    recording: SessionRecording,
+}
+
+impl<'a> MessageQueue<'a> {
+    fn send(&mut self, messages: Vec<ProtocolMessage>) {
+        for mut message in messages {
+            self.recording.sent.push(message.clone());
+            let fault = self.plan.faults.get(&self.next_sequence).copied();
+            self.next_sequence += 1;
+            match fault {
+                None => self.in_flight.push_back(message),
+                Some(Fault::Drop) => (),
+                Some(Fault::Duplicate) => {
+                    self.in_flight.push_back(message.clone());
+                    self.in_flight.push_back(message);
+                },
+                Some(Fault::Corrupt { offset, mask }) => {
+                    if let Some(v) = message.payload.get_mut(offset) {
+                        *v ^= mask;
+                    }
+                    self.in_flight.push_back(message);
+                },
+                Some(Fault::Delay { positions }) => {
+                    let index = positions.min(self.in_flight.len());
+                    self.in_flight.insert(index, message);
+                },
+            }
+        }
+    }
+}
+
+/// Runs a session between parties, where parties[i] is the party with
+/// index i, until no message is in flight or max_deliveries messages have
+/// been delivered.
+pub fn simulate(
+    parties: &mut [&mut dyn ProtocolParty],
+    plan: &FaultPlan,
+    max_deliveries: usize,
+) -> SimulationOutcome {
+    let mut queue = MessageQueue {
+        plan,
+        next_sequence: 0,
+        in_flight: VecDeque::new(),
+        recording: SessionRecording::default(),
+    };
+    let mut errors = Vec::new();
+    let mut failed = vec![false; parties.len()];
+    for (index, party) in parties.iter_mut().enumerate() {
+        match party.start() {
+            Ok(v) => queue.send(v),
+            Err(e) => {
+                failed[index] = true;
+                errors.push((index, e));
+            },
+        }
+    }
+    while queue.recording.delivered.len() < max_deliveries {
+        let message = match queue.in_flight.pop_front() {
+            Some(v) => v,
+            None => break,
+        };
+        queue.recording.delivered.push(message.clone());
+        if message.to >= parties.len() {
+            wedpr_println!("Message to unknown party {}", message.to);
+            continue;
+        }
+        if failed[message.to] {
+            continue;
+        }
+        match parties[message.to].handle(&message) {
+            Ok(v) => queue.send(v),
+            Err(e) => {
+                failed[message.to] = true;
+                errors.push((message.to, e));
+            },
+        }
+    }
+    SimulationOutcome {
+        recording: queue.recording,
+        errors,
+        finished: parties.iter().map(|party| party.is_finished()).collect(),
+    }
+}
+
+/// Replays the delivered messages of a recording to a party with an index,
+/// and checks that it sends exactly the recorded messages.
+pub fn replay_party(
+    party: &mut dyn ProtocolParty,
+    index: usize,
+    recording: &SessionRecording,
+) -> Result<(), WedprError> {
+    let mut sent = party.start()?;
+    for message in recording.delivered.iter().filter(|v| v.to == index) {
+        sent.append(&mut party.handle(message)?);
+    }
+    let recorded_sent: Vec<&ProtocolMessage> =
+        recording.sent.iter().filter(|v| v.from == index).collect();
+    if sent.len() != recorded_sent.len()
+        || sent.iter().zip(recorded_sent.iter()).any(|(v, w)| v != *w)
+    {
+        wedpr_println!("Party {} diverges from the recording", index);
+        return Err(WedprError::VerificationError);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COUNTER_LIMIT: u8 = 4;
+
+    /// Party counting up alternately with its peer, starting from 0 by the
+    /// party 0.
+    struct CounterParty {
+        index: usize,
+        expected: u8,
+        finished: bool,
+    }
+
+    impl CounterParty {
+        fn new(index: usize) -> Self {
+            CounterParty {
+                index,
+                expected: 0,
+                finished: false,
+            }
+        }
+    }
+
+    impl ProtocolParty for CounterParty {
+        fn start(&mut self) -> Result<Vec<ProtocolMessage>, WedprError> {
+            if self.index != 0 {
+                return Ok(Vec::new());
+            }
+            self.expected = 1;
+            Ok(vec![ProtocolMessage::new(0, 1, &[0])])
+        }
+
+        fn handle(
+            &mut self,
+            message: &ProtocolMessage,
+        ) -> Result<Vec<ProtocolMessage>, WedprError> {
+            if message.payload != [self.expected] {
+                return Err(WedprError::VerificationError);
+            }
+            let next = self.expected + 1;
+            self.expected += 2;
+            self.finished = next >= COUNTER_LIMIT;
+            if next > COUNTER_LIMIT {
+                return Ok(Vec::new());
+            }
+            Ok(vec![ProtocolMessage::new(self.index, 1 - self.index, &[
+                next,
+            ])])
+        }
+
+        fn is_finished(&self) -> bool {
+            self.finished
+        }
+    }
+
+    fn run(plan: &FaultPlan) -> SimulationOutcome {
+        let mut party0 = CounterParty::new(0);
+        let mut party1 = CounterParty::new(1);
+        simulate(
+            &mut [&mut party0 as &mut dyn ProtocolParty, &mut party1],
+            plan,
+            100,
+        )
+    }
+
+    #[test]
+    fn test_simulation() {
+        let outcome = run(&FaultPlan::new());
+        assert_eq!(outcome.finished, vec![true, true]);
+        assert!(outcome.errors.is_empty());
+        assert_eq!(outcome.recording.delivered.len(), 5);
+
+        let dropped = run(&FaultPlan::new().inject(2, Fault::Drop));
+        assert_eq!(dropped.finished, vec![false, false]);
+        assert!(dropped.errors.is_empty());
+        let corrupted = run(&FaultPlan::new().inject(1, Fault::Corrupt {
+            offset: 0,
+            mask: 0x80,
+        }));
+        assert_eq!(corrupted.errors, vec![(0, WedprError::VerificationError)]);
+        let duplicated = run(&FaultPlan::new().inject(0, Fault::Duplicate));
+        assert_eq!(duplicated.errors, vec![(1, WedprError::VerificationError)]);
+
+        // Replays the recording against a fresh party.
+        let recording =
+            SessionRecording::decode(&outcome.recording.encode()).unwrap();
+        assert_eq!(recording, outcome.recording);
+        assert!(replay_party(&mut CounterParty::new(1), 1, &recording).is_ok());
+        let mut diverged_recording = recording.clone();
+        diverged_recording.sent[1].payload = vec![9];
+        assert!(replay_party(
+            &mut CounterParty::new(1),
+            1,
+            &diverged_recording
+        )
+        .is_err());
+    }
+}
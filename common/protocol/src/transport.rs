@@ -0,0 +1,269 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Transports of framed messages for running interactive protocols end to
+//! end, with an in-memory implementation for tests and a TCP reference
+//! implementation.
+
+use crate::{ProtocolMessage, ProtocolParty};
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+    time::Duration,
+};
+use wedpr_l_utils::error::WedprError;
+
+/// Maximum size of a frame accepted by TcpTransport.
+pub const MAX_FRAME_SIZE_IN_BYTES: usize = 64 * 1024 * 1024;
+
+/// Bidirectional channel of framed messages to a peer.
+pub trait Transport {
+    /// Sends a framed message.
+    fn send(&mut self, payload: &[u8]) -> Result<(), WedprError>;
+
+    /// Receives a framed message, which fails if no message arrives within
+    /// timeout.
+    fn recv(&mut self, timeout: Duration) -> Result<Vec<u8>, WedprError>;
+}
+
+/// Transport between two endpoints in the same process.
+#[derive(Debug)]
+pub struct InMemoryTransport {
+    sender: Sender<Vec<u8>>,
+    receiver: Receiver<Vec<u8>>,
+}
+
+impl InMemoryTransport {
+    /// Creates two connected endpoints.
+    pub fn pair() -> (Self, Self) {
+        let (sender_a, receiver_b) = channel();
+        let (sender_b, receiver_a) = channel();
+        (
+            InMemoryTransport {
+                sender: sender_a,
+                receiver: receiver_a,
+            },
+            InMemoryTransport {
+                sender: sender_b,
+                receiver: receiver_b,
+            },
+        )
+    }
+}
+
+impl Transport for InMemoryTransport {
+    fn send(&mut self, payload: &[u8]) -> Result<(), WedprError> {
+        match self.sender.send(payload.to_vec()) {
+            Ok(_) => Ok(()),
+            Err(_) => {
+                wedpr_println!("In-memory peer is disconnected");
+                Err(WedprError::TransportError)
+            },
+        }
+    }
+
+    fn recv(&mut self, timeout: Duration) -> Result<Vec<u8>, WedprError> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(v) => Ok(v),
+            Err(RecvTimeoutError::Timeout) => {
+                wedpr_println!("In-memory receiving timed out");
+                Err(WedprError::TransportError)
+            },
+            Err(RecvTimeoutError::Disconnected) => {
+                wedpr_println!("In-memory peer is disconnected");
+                Err(WedprError::TransportError)
+            },
+        }
+    }
+}
+
+/// Transport over a TCP stream, where each frame is prefixed by its 4-byte
+/// big-endian length. A timed out receiving may leave a partial frame in the
+/// stream, so the connection should be dropped after it.
+#[derive(Debug)]
+pub struct TcpTransport {
+    stream: TcpStream,
+}
+
+impl TcpTransport {
+    /// Wraps a connected TCP stream.
+    pub fn new(stream: TcpStream) -> Result<Self, WedprError> {
+        if stream.set_nodelay(true).is_err() {
+            return Err(WedprError::TransportError);
+        }
+        Ok(TcpTransport { stream })
+    }
+
+    /// Connects to a listening peer.
+    pub fn connect<A: ToSocketAddrs>(address: A) -> Result<Self, WedprError> {
+        match TcpStream::connect(address) {
+            Ok(v) => TcpTransport::new(v),
+            Err(_) => {
+                wedpr_println!("TCP connection failed");
+                Err(WedprError::TransportError)
+            },
+        }
+    }
+
+    /// Accepts a connection from a peer.
+    pub fn accept(listener: &TcpListener) -> Result<Self, WedprError> {
+        match listener.accept() {
+            Ok((stream, _)) => TcpTransport::new(stream),
+            Err(_) => {
+                wedpr_println!("TCP accepting failed");
+                Err(WedprError::TransportError)
+            },
+        }
+    }
+
+    fn read_exact(&mut self, buffer: &mut [u8]) -> Result<(), WedprError> {
+        match self.stream.read_exact(buffer) {
+            Ok(_) => Ok(()),
+            Err(e)
+                if e.kind() == ErrorKind::WouldBlock
+                    || e.kind() == ErrorKind::TimedOut =>
+            {
+                wedpr_println!("TCP receiving timed out");
+                Err(WedprError::TransportError)
+            },
+            Err(_) => {
+                wedpr_println!("TCP receiving failed");
+                Err(WedprError::TransportError)
+            },
+        }
+    }
+}
+
+impl Transport for TcpTransport {
+    fn send(&mut self, payload: &[u8]) -> Result<(), WedprError> {
+        if payload.len() > MAX_FRAME_SIZE_IN_BYTES {
+            return Err(WedprError::ArgumentError);
+        }
+        let mut frame = (payload.len() as u32).to_be_bytes().to_vec();
+        frame.extend_from_slice(payload);
+        match self.stream.write_all(&frame) {
+            Ok(_) => Ok(()),
+            Err(_) => {
+                wedpr_println!("TCP sending failed");
+                Err(WedprError::TransportError)
+            },
+        }
+    }
+
+    fn recv(&mut self, timeout: Duration) -> Result<Vec<u8>, WedprError> {
+        // A zero timeout would mean blocking forever for TcpStream.
+        let read_timeout = timeout.max(Duration::from_millis(1));
+        if self.stream.set_read_timeout(Some(read_timeout)).is_err() {
+            return Err(WedprError::TransportError);
+        }
+        let mut length_buffer = [0u8; 4];
+        self.read_exact(&mut length_buffer)?;
+        let length = u32::from_be_bytes(length_buffer) as usize;
+        if length > MAX_FRAME_SIZE_IN_BYTES {
+            wedpr_println!("TCP frame is too large, length={}", length);
+            return Err(WedprError::FormatError);
+        }
+        let mut payload = vec![0u8; length];
+        self.read_exact(&mut payload)?;
+        Ok(payload)
+    }
+}
+
+/// Runs a party of a two-party protocol with the index against its peer over
+/// a transport, until the party finishes or any step fails.
+pub fn run_two_party_protocol<T: Transport>(
+    party: &mut dyn ProtocolParty,
+    index: usize,
+    peer: usize,
+    transport: &mut T,
+    timeout: Duration,
+) -> Result<(), WedprError> {
+    let mut outgoing = party.start()?;
+    loop {
+        for message in &outgoing {
+            if message.to != peer {
+                wedpr_println!("Message to unknown party {}", message.to);
+                return Err(WedprError::ArgumentError);
+            }
+            transport.send(&message.payload)?;
+        }
+        if party.is_finished() {
+            return Ok(());
+        }
+        let payload = transport.recv(timeout)?;
+        outgoing =
+            party.handle(&ProtocolMessage::new(peer, index, &payload))?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Party 0 sends a ping, and party 1 answers it with a pong.
+    struct PingParty {
+        index: usize,
+        finished: bool,
+    }
+
+    impl ProtocolParty for PingParty {
+        fn start(&mut self) -> Result<Vec<ProtocolMessage>, WedprError> {
+            match self.index {
+                0 => Ok(vec![ProtocolMessage::new(0, 1, b"ping")]),
+                _ => Ok(Vec::new()),
+            }
+        }
+
+        fn handle(
+            &mut self,
+            message: &ProtocolMessage,
+        ) -> Result<Vec<ProtocolMessage>, WedprError> {
+            self.finished = true;
+            match (self.index, &message.payload[..]) {
+                (0, b"pong") => Ok(Vec::new()),
+                (1, b"ping") => Ok(vec![ProtocolMessage::new(1, 0, b"pong")]),
+                _ => Err(WedprError::VerificationError),
+            }
+        }
+
+        fn is_finished(&self) -> bool {
+            self.finished
+        }
+    }
+
+    fn run_ping<T: Transport + Send + 'static>(transport0: T, transport1: T) {
+        let peer = thread::spawn(move || {
+            let mut transport1 = transport1;
+            let mut party1 = PingParty {
+                index: 1,
+                finished: false,
+            };
+            run_two_party_protocol(&mut party1, 1, 0, &mut transport1, TIMEOUT)
+        });
+        let mut transport0 = transport0;
+        let mut party0 = PingParty {
+            index: 0,
+            finished: false,
+        };
+        run_two_party_protocol(&mut party0, 0, 1, &mut transport0, TIMEOUT)
+            .unwrap();
+        peer.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_transport() {
+        let (transport0, mut transport1) = InMemoryTransport::pair();
+        assert!(transport1.recv(Duration::from_millis(1)).is_err());
+        run_ping(transport0, transport1);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = thread::spawn(move || TcpTransport::connect(address));
+        let transport0 = TcpTransport::accept(&listener).unwrap();
+        let transport1 = client.join().unwrap().unwrap();
+        run_ping(transport0, transport1);
+    }
+}
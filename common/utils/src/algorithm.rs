@@ -0,0 +1,104 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! WeDPR algorithm metadata for introspection by generic tooling.
+
+use crate::error::WedprError;
+
+/// Metadata of an algorithm implementation, where sizes are in bytes and None
+/// means the property does not apply to the algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlgorithmMetadata {
+    /// Human readable name of the algorithm.
+    pub name: &'static str,
+    /// Standard object identifier in dotted notation, if registered.
+    pub oid: Option<&'static str>,
+    /// Approximate security level in bits.
+    pub security_bits: u32,
+    pub private_key_size: Option<usize>,
+    /// All accepted public key sizes, e.g. uncompressed and compressed.
+    pub public_key_sizes: &'static [usize],
+    /// Size of a signature or proof.
+    pub signature_size: Option<usize>,
+    /// Size of a hash or VRF output.
+    pub output_size: Option<usize>,
+}
+
+impl AlgorithmMetadata {
+    /// Checks the size of a private key.
+    pub fn check_private_key(
+        &self,
+        private_key: &[u8],
+    ) -> Result<(), WedprError> {
+        check_size(self.private_key_size, private_key.len(), "private key")
+    }
+
+    /// Checks the size of a public key.
+    pub fn check_public_key(
+        &self,
+        public_key: &[u8],
+    ) -> Result<(), WedprError> {
+        if !self.public_key_sizes.is_empty()
+            && !self.public_key_sizes.contains(&public_key.len())
+        {
+            wedpr_println!("Invalid public key size for {}", self.name);
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(())
+    }
+
+    /// Checks the size of a signature or proof.
+    pub fn check_signature(&self, signature: &[u8]) -> Result<(), WedprError> {
+        check_size(self.signature_size, signature.len(), "signature")
+    }
+}
+
+fn check_size(
+    expected: Option<usize>,
+    actual: usize,
+    artifact: &str,
+) -> Result<(), WedprError> {
+    match expected {
+        Some(v) if v != actual => {
+            wedpr_println!(
+                "Invalid {} size {}, expecting {}",
+                artifact,
+                actual,
+                v
+            );
+            Err(WedprError::ArgumentError)
+        },
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_algorithm_metadata() {
+        let metadata = AlgorithmMetadata {
+            name: "test",
+            oid: None,
+            security_bits: 128,
+            private_key_size: Some(32),
+            public_key_sizes: &[65, 33],
+            signature_size: Some(65),
+            output_size: None,
+        };
+        assert!(metadata.check_private_key(&[0u8; 32]).is_ok());
+        assert!(metadata.check_private_key(&[0u8; 31]).is_err());
+        assert!(metadata.check_public_key(&[0u8; 33]).is_ok());
+        assert!(metadata.check_public_key(&[0u8; 64]).is_err());
+        assert!(metadata.check_signature(&[0u8; 65]).is_ok());
+        assert!(metadata.check_signature(&[0u8; 64]).is_err());
+
+        let unsized_metadata = AlgorithmMetadata {
+            private_key_size: None,
+            public_key_sizes: &[],
+            ..metadata
+        };
+        assert!(unsized_metadata.check_private_key(&[0u8; 7]).is_ok());
+        assert!(unsized_metadata.check_public_key(&[0u8; 7]).is_ok());
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! WeDPR constant-time helper functions for handling secret data.
+
+use subtle::{
+    Choice, ConditionallySelectable, ConstantTimeEq, ConstantTimeLess,
+};
+
+/// Compares two big-endian encoded integers of the same length, and returns
+/// whether left < right without branching on their content.
+pub fn ct_less_than(left: &[u8], right: &[u8]) -> Choice {
+    let mut less = Choice::from(0u8);
+    // Scans from the least significant byte, so that the most significant
+    // differing byte decides the final result.
+    for (l, r) in left.iter().zip(right.iter()).rev() {
+        less = Choice::conditional_select(&l.ct_lt(r), &less, l.ct_eq(r));
+    }
+    less
+}
+
+/// Checks whether all bytes of the input are zero without returning early.
+pub fn ct_is_zero(input: &[u8]) -> Choice {
+    input.iter().fold(0u8, |acc, byte| acc | byte).ct_eq(&0u8)
+}
+
+/// Checks whether a big-endian encoded secret scalar belongs to [1, order).
+/// Only the input length is treated as public information, the scalar content
+/// is validated without any early return.
+pub fn is_valid_secret_scalar(scalar: &[u8], order: &[u8]) -> bool {
+    if scalar.len() != order.len() {
+        return false;
+    }
+    bool::from(ct_less_than(scalar, order) & !ct_is_zero(scalar))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_secret_scalar() {
+        let order = [0x80u8, 0x00, 0x00, 0x10];
+        assert!(is_valid_secret_scalar(&[0x00, 0x00, 0x00, 0x01], &order));
+        assert!(is_valid_secret_scalar(&[0x80, 0x00, 0x00, 0x0f], &order));
+        assert!(is_valid_secret_scalar(&[0x7f, 0xff, 0xff, 0xff], &order));
+        // Zero, the order itself and values above the order are invalid.
+        assert!(!is_valid_secret_scalar(&[0x00, 0x00, 0x00, 0x00], &order));
+        assert!(!is_valid_secret_scalar(&order, &order));
+        assert!(!is_valid_secret_scalar(&[0x80, 0x00, 0x01, 0x00], &order));
+        assert!(!is_valid_secret_scalar(&[0xff, 0xff, 0xff, 0xff], &order));
+        // Length mismatch is rejected.
+        assert!(!is_valid_secret_scalar(&[0x01], &order));
+    }
+}
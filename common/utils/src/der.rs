@@ -0,0 +1,320 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Minimal ASN.1 DER encoding and decoding functions, covering the subset
+//! used by standard formats such as CMS envelopes and GM/T 0009 structures.
+
+use crate::error::WedprError;
+
+pub const TAG_BOOLEAN: u8 = 0x01;
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_BIT_STRING: u8 = 0x03;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_NULL: u8 = 0x05;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_SET: u8 = 0x31;
+
+/// Gets the tag of a constructed context-specific field with the given number.
+pub fn context_tag(number: u8) -> u8 {
+    0xa0 | number
+}
+
+/// Encodes a DER type-length-value field.
+pub fn encode_tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut output = vec![tag];
+    let length = content.len();
+    if length < 0x80 {
+        output.push(length as u8);
+    } else {
+        let length_bytes: Vec<u8> = length
+            .to_be_bytes()
+            .iter()
+            .cloned()
+            .skip_while(|byte| *byte == 0)
+            .collect();
+        output.push(0x80 | length_bytes.len() as u8);
+        output.extend_from_slice(&length_bytes);
+    }
+    output.extend_from_slice(content);
+    output
+}
+
+/// Encodes a sequence from already encoded fields.
+pub fn encode_sequence(fields: &[Vec<u8>]) -> Vec<u8> {
+    encode_tlv(TAG_SEQUENCE, &fields.concat())
+}
+
+/// Encodes a non-negative integer from its big-endian bytes.
+pub fn encode_unsigned_integer(value: &[u8]) -> Vec<u8> {
+    let mut content: Vec<u8> = value
+        .iter()
+        .cloned()
+        .skip_while(|byte| *byte == 0)
+        .collect();
+    // A leading zero byte keeps the value positive in two's complement.
+    if content.is_empty() || content[0] & 0x80 != 0 {
+        content.insert(0, 0);
+    }
+    encode_tlv(TAG_INTEGER, &content)
+}
+
+/// Encodes a u64 value as an integer.
+pub fn encode_u64(value: u64) -> Vec<u8> {
+    encode_unsigned_integer(&value.to_be_bytes())
+}
+
+/// Encodes an OID in dotted notation to the content of its DER field.
+pub fn encode_oid_content(dotted: &str) -> Result<Vec<u8>, WedprError> {
+    let mut arcs = Vec::new();
+    for arc in dotted.split('.') {
+        match arc.parse::<u64>() {
+            Ok(v) => arcs.push(v),
+            Err(_) => return Err(WedprError::FormatError),
+        }
+    }
+    if arcs.len() < 2 || arcs[0] > 2 || (arcs[0] < 2 && arcs[1] > 39) {
+        return Err(WedprError::FormatError);
+    }
+    // The first two arcs are combined into one subidentifier.
+    let mut subidentifiers = vec![arcs[0] * 40 + arcs[1]];
+    subidentifiers.extend_from_slice(&arcs[2..]);
+    let mut content = Vec::new();
+    for subidentifier in subidentifiers {
+        // Encodes base-128 digits, setting the high bit except the last one.
+        let mut digits = vec![(subidentifier & 0x7f) as u8];
+        let mut rest = subidentifier >> 7;
+        while rest > 0 {
+            digits.push(0x80 | (rest & 0x7f) as u8);
+            rest >>= 7;
+        }
+        digits.reverse();
+        content.extend_from_slice(&digits);
+    }
+    Ok(content)
+}
+
+/// Decodes the content of a non-negative integer to a u64 value.
+pub fn decode_u64(content: &[u8]) -> Result<u64, WedprError> {
+    if content.is_empty() || content[0] & 0x80 != 0 {
+        return Err(WedprError::FormatError);
+    }
+    let value: Vec<u8> = content
+        .iter()
+        .cloned()
+        .skip_while(|byte| *byte == 0)
+        .collect();
+    if value.len() > 8 {
+        return Err(WedprError::FormatError);
+    }
+    Ok(value
+        .iter()
+        .fold(0u64, |acc, byte| (acc << 8) | (*byte as u64)))
+}
+
+/// Decodes the content of a non-negative integer to big-endian bytes of a
+/// fixed size.
+pub fn decode_unsigned_integer(
+    content: &[u8],
+    size: usize,
+) -> Result<Vec<u8>, WedprError> {
+    if content.is_empty() || content[0] & 0x80 != 0 {
+        return Err(WedprError::FormatError);
+    }
+    let value: Vec<u8> = content
+        .iter()
+        .cloned()
+        .skip_while(|byte| *byte == 0)
+        .collect();
+    if value.len() > size {
+        return Err(WedprError::FormatError);
+    }
+    let mut output = vec![0u8; size - value.len()];
+    output.extend_from_slice(&value);
+    Ok(output)
+}
+
+/// Encodes a raw r || s signature of two scalars of the same size, e.g. of
+/// ECDSA or SM2, to ECDSA-Sig-Value ::= SEQUENCE { r INTEGER, s INTEGER }.
+pub fn encode_ecdsa_signature(signature: &[u8]) -> Result<Vec<u8>, WedprError> {
+    if signature.is_empty() || signature.len() % 2 == 1 {
+        wedpr_println!("Invalid raw r || s signature size");
+        return Err(WedprError::ArgumentError);
+    }
+    let (r, s) = signature.split_at(signature.len() / 2);
+    Ok(encode_sequence(&[
+        encode_unsigned_integer(r),
+        encode_unsigned_integer(s),
+    ]))
+}
+
+/// Decodes an ECDSA-Sig-Value to the raw r || s signature, where both
+/// scalars take scalar_size bytes.
+pub fn decode_ecdsa_signature(
+    input: &[u8],
+    scalar_size: usize,
+) -> Result<Vec<u8>, WedprError> {
+    let mut reader = DerReader::new(input);
+    let sequence = reader.read_expected(TAG_SEQUENCE)?;
+    if !reader.is_empty() {
+        return Err(WedprError::FormatError);
+    }
+    let mut fields = DerReader::new(sequence.content);
+    let mut signature = decode_unsigned_integer(
+        fields.read_expected(TAG_INTEGER)?.content,
+        scalar_size,
+    )?;
+    signature.extend_from_slice(&decode_unsigned_integer(
+        fields.read_expected(TAG_INTEGER)?.content,
+        scalar_size,
+    )?);
+    if !fields.is_empty() {
+        return Err(WedprError::FormatError);
+    }
+    Ok(signature)
+}
+
+/// Reader of consecutive DER fields.
+pub struct DerReader<'a> {
+    input: &'a [u8],
+    offset: usize,
+}
+
+/// A decoded DER field, keeping its full encoding for signature checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DerField<'a> {
+    pub tag: u8,
+    pub content: &'a [u8],
+    pub encoded: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        DerReader { input, offset: 0 }
+    }
+
+    /// Checks whether all fields have been read.
+    pub fn is_empty(&self) -> bool {
+        self.offset >= self.input.len()
+    }
+
+    /// Gets the tag of the next field without reading it.
+    pub fn peek_tag(&self) -> Option<u8> {
+        self.input.get(self.offset).cloned()
+    }
+
+    /// Reads the next field.
+    pub fn read(&mut self) -> Result<DerField<'a>, WedprError> {
+        let start = self.offset;
+        let remaining = &self.input[start..];
+        if remaining.len() < 2 {
+            return Err(WedprError::FormatError);
+        }
+        let tag = remaining[0];
+        let (length, header_length) = if remaining[1] < 0x80 {
+            (remaining[1] as usize, 2)
+        } else {
+            let length_size = (remaining[1] & 0x7f) as usize;
+            if length_size == 0
+                || length_size > 4
+                || remaining.len() < 2 + length_size
+            {
+                return Err(WedprError::FormatError);
+            }
+            let length = remaining[2..2 + length_size]
+                .iter()
+                .fold(0usize, |acc, byte| (acc << 8) | (*byte as usize));
+            (length, 2 + length_size)
+        };
+        if remaining.len() - header_length < length {
+            return Err(WedprError::FormatError);
+        }
+        self.offset = start + header_length + length;
+        Ok(DerField {
+            tag,
+            content: &remaining[header_length..header_length + length],
+            encoded: &remaining[..header_length + length],
+        })
+    }
+
+    /// Reads the next field and checks its tag.
+    pub fn read_expected(
+        &mut self,
+        tag: u8,
+    ) -> Result<DerField<'a>, WedprError> {
+        let field = self.read()?;
+        if field.tag != tag {
+            wedpr_println!(
+                "Unexpected DER tag {:#x}, expecting {:#x}",
+                field.tag,
+                tag
+            );
+            return Err(WedprError::FormatError);
+        }
+        Ok(field)
+    }
+
+    /// Reads the next field if it has the given tag.
+    pub fn read_optional(
+        &mut self,
+        tag: u8,
+    ) -> Result<Option<DerField<'a>>, WedprError> {
+        match self.peek_tag() {
+            Some(v) if v == tag => Ok(Some(self.read()?)),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_der_encoding() {
+        assert_eq!(encode_u64(0), vec![0x02, 0x01, 0x00]);
+        assert_eq!(encode_u64(0x80), vec![0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(decode_u64(&[0x00, 0x80]).unwrap(), 0x80);
+        assert!(decode_u64(&[0x80]).is_err());
+        assert_eq!(encode_oid_content("1.2.156.10197.1.401").unwrap(), vec![
+            0x2a, 0x81, 0x1c, 0xcf, 0x55, 0x01, 0x83, 0x11
+        ]);
+        assert!(encode_oid_content("3.1").is_err());
+
+        let long_content = vec![7u8; 300];
+        let encoded = encode_sequence(&[
+            encode_tlv(TAG_OCTET_STRING, &long_content),
+            encode_u64(42),
+        ]);
+        assert_eq!(&encoded[..4], &[0x30, 0x82, 0x01, 0x33]);
+
+        let mut reader = DerReader::new(&encoded);
+        let sequence = reader.read_expected(TAG_SEQUENCE).unwrap();
+        assert!(reader.is_empty());
+        let mut fields = DerReader::new(sequence.content);
+        assert_eq!(
+            fields.read_expected(TAG_OCTET_STRING).unwrap().content,
+            &long_content[..]
+        );
+        assert!(fields.read_optional(TAG_BOOLEAN).unwrap().is_none());
+        let integer = fields.read_expected(TAG_INTEGER).unwrap();
+        assert_eq!(decode_u64(integer.content).unwrap(), 42);
+        assert!(fields.read().is_err());
+
+        assert!(DerReader::new(&encoded[..encoded.len() - 1])
+            .read()
+            .is_err());
+
+        let mut raw_signature = vec![0u8; 31];
+        raw_signature.push(1);
+        raw_signature.extend_from_slice(&[0xff; 32]);
+        let encoded = encode_ecdsa_signature(&raw_signature).unwrap();
+        assert_eq!(&encoded[..5], &[0x30, 0x26, 0x02, 0x01, 0x01]);
+        assert_eq!(
+            decode_ecdsa_signature(&encoded, 32).unwrap(),
+            raw_signature
+        );
+        assert!(decode_ecdsa_signature(&encoded, 31).is_err());
+        assert!(encode_ecdsa_signature(&raw_signature[1..]).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! WeDPR message digest configuration for signing raw messages.
+
+use crate::traits::Hash;
+
+/// Digest applied to a raw message before signing, e.g. Keccak256 for
+/// FISCO-BCOS, SM3 for GM chains, or SHA-256 for interoperability.
+#[derive(Default, Debug, Clone)]
+pub struct MessageDigest<H: Hash> {
+    pub hash: H,
+    /// Domain separator hashed before the message, where an empty domain
+    /// gives the plain hash of the message for compatibility with existing
+    /// chains.
+    pub domain: Vec<u8>,
+}
+
+impl<H: Hash> MessageDigest<H> {
+    /// Creates a plain digest without domain separation.
+    pub fn new(hash: H) -> Self {
+        MessageDigest {
+            hash,
+            domain: Vec::new(),
+        }
+    }
+
+    /// Creates a digest separated by a domain, so that signatures for one
+    /// application cannot be replayed in another.
+    pub fn with_domain(hash: H, domain: &[u8]) -> Self {
+        MessageDigest {
+            hash,
+            domain: domain.to_vec(),
+        }
+    }
+
+    /// Computes the digest of a raw message.
+    pub fn digest(&self, message: &[u8]) -> Vec<u8> {
+        if self.domain.is_empty() {
+            return self.hash.hash(message);
+        }
+        // The domain length is prefixed to keep the encoding unambiguous.
+        let mut hash_vec = (self.domain.len() as u32).to_be_bytes().to_vec();
+        hash_vec.extend_from_slice(&self.domain);
+        hash_vec.extend_from_slice(message);
+        self.hash.hash(&hash_vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default, Debug, Clone)]
+    struct IdentityHash {}
+
+    impl Hash for IdentityHash {
+        fn hash<T: ?Sized + AsRef<[u8]>>(&self, input: &T) -> Vec<u8> {
+            input.as_ref().to_vec()
+        }
+    }
+
+    #[test]
+    fn test_message_digest() {
+        let plain_digest = MessageDigest::new(IdentityHash::default());
+        assert_eq!(plain_digest.digest(b"message"), b"message".to_vec());
+
+        let domain_digest =
+            MessageDigest::with_domain(IdentityHash::default(), b"app");
+        assert_eq!(
+            domain_digest.digest(b"message"),
+            b"\0\0\0\x03appmessage".to_vec()
+        );
+    }
+}
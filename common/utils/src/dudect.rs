@@ -0,0 +1,120 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! WeDPR dudect-style timing leakage detection (used for testing only).
+//!
+//! The measured function is executed with two classes of inputs (a fixed input
+//! against random inputs), and Welch's t-test is applied to the timing samples
+//! of both classes. A large t-statistic indicates that the execution time
+//! depends on the input content.
+
+use std::time::Instant;
+
+/// Absolute t-statistic above which the timing is considered leaking.
+pub const DUDECT_T_THRESHOLD: f64 = 10.0;
+
+/// Number of executions accumulated in a single timing sample to reduce the
+/// impact of the timer resolution.
+const EXECUTIONS_PER_SAMPLE: usize = 32;
+
+/// Percentile used to drop outlier samples caused by interrupts or context
+/// switches.
+const CROP_PERCENTILE: f64 = 0.9;
+
+/// Runs a fixed-vs-random timing test for a target function, and returns the
+/// absolute Welch's t-statistic of its execution time.
+pub fn fixed_vs_random_t_statistic<F, G>(
+    target: F,
+    fixed_input: &[u8],
+    mut random_input: G,
+    sample_count: usize,
+) -> f64
+where
+    F: Fn(&[u8]) -> bool,
+    G: FnMut() -> Vec<u8>,
+{
+    // Prepares all inputs before measuring to keep the input generation out of
+    // the timing samples.
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    let inputs: Vec<(bool, Vec<u8>)> = (0..sample_count)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            if seed & 1 == 0 {
+                (true, fixed_input.to_vec())
+            } else {
+                (false, random_input())
+            }
+        })
+        .collect();
+
+    let mut fixed_samples = Vec::new();
+    let mut random_samples = Vec::new();
+    for (is_fixed, input) in inputs.iter() {
+        let start = Instant::now();
+        for _ in 0..EXECUTIONS_PER_SAMPLE {
+            std::hint::black_box(target(std::hint::black_box(input)));
+        }
+        let elapsed = start.elapsed().as_nanos() as f64;
+        if *is_fixed {
+            fixed_samples.push(elapsed);
+        } else {
+            random_samples.push(elapsed);
+        }
+    }
+    let threshold = crop_threshold(&fixed_samples, &random_samples);
+    welch_t_statistic(
+        &crop_samples(&fixed_samples, threshold),
+        &crop_samples(&random_samples, threshold),
+    )
+    .abs()
+}
+
+/// Computes Welch's t-statistic of two sample sets.
+pub fn welch_t_statistic(left: &[f64], right: &[f64]) -> f64 {
+    let (left_mean, left_variance) = mean_and_variance(left);
+    let (right_mean, right_variance) = mean_and_variance(right);
+    let denominator = (left_variance / left.len() as f64
+        + right_variance / right.len() as f64)
+        .sqrt();
+    if denominator == 0.0 {
+        return 0.0;
+    }
+    (left_mean - right_mean) / denominator
+}
+
+fn mean_and_variance(samples: &[f64]) -> (f64, f64) {
+    let count = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / count;
+    let variance = samples.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>()
+        / (count - 1.0);
+    (mean, variance)
+}
+
+fn crop_threshold(left: &[f64], right: &[f64]) -> f64 {
+    let mut all_samples: Vec<f64> = left.iter().chain(right).cloned().collect();
+    all_samples.sort_by(|a, b| a.partial_cmp(b).expect("NaN timing sample"));
+    let index = (all_samples.len() as f64 * CROP_PERCENTILE) as usize;
+    all_samples[index.min(all_samples.len() - 1)]
+}
+
+fn crop_samples(samples: &[f64], threshold: f64) -> Vec<f64> {
+    samples
+        .iter()
+        .cloned()
+        .filter(|v| *v <= threshold)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_welch_t_statistic() {
+        let left = vec![1.0, 2.0, 3.0, 4.0];
+        assert_eq!(welch_t_statistic(&left, &left), 0.0);
+        let shifted: Vec<f64> = left.iter().map(|v| v + 100.0).collect();
+        assert!(welch_t_statistic(&left, &shifted).abs() > DUDECT_T_THRESHOLD);
+    }
+}
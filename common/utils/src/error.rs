@@ -2,16 +2,155 @@
 
 //! WeDPR errors definitions.
 
-#[derive(Fail, Clone, Debug, Eq, PartialEq)]
+use std::fmt;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum WedprError {
-    #[fail(display = "Verification failed")]
     VerificationError,
-    #[fail(display = "Argument is invalid")]
     ArgumentError,
-    #[fail(display = "Data cannot be parsed")]
     FormatError,
-    #[fail(display = "Data cannot be decoded")]
     DecodeError,
-    #[fail(display = "Indy Crypto error.")]
     IndyCryptoError,
+    StorageError,
+    TransportError,
+}
+
+/// All errors, for mapping FFI status codes back to errors.
+const WEDPR_ERRORS: [WedprError; 7] = [
+    WedprError::VerificationError,
+    WedprError::ArgumentError,
+    WedprError::FormatError,
+    WedprError::DecodeError,
+    WedprError::IndyCryptoError,
+    WedprError::StorageError,
+    WedprError::TransportError,
+];
+
+impl WedprError {
+    /// Gets the FFI status code of the error, which is distinct from the
+    /// default success code 0 and failure code -1 of C/C++ functions.
+    pub fn ffi_code(&self) -> i8 {
+        match self {
+            WedprError::VerificationError => -2,
+            WedprError::ArgumentError => -3,
+            WedprError::FormatError => -4,
+            WedprError::DecodeError => -5,
+            WedprError::IndyCryptoError => -6,
+            WedprError::StorageError => -7,
+            WedprError::TransportError => -8,
+        }
+    }
+
+    /// Gets the error of an FFI status code, or None if the code is not an
+    /// error code.
+    pub fn from_ffi_code(code: i8) -> Option<WedprError> {
+        WEDPR_ERRORS
+            .iter()
+            .find(|error| error.ffi_code() == code)
+            .cloned()
+    }
+}
+
+impl fmt::Display for WedprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            WedprError::VerificationError => "Verification failed",
+            WedprError::ArgumentError => "Argument is invalid",
+            WedprError::FormatError => "Data cannot be parsed",
+            WedprError::DecodeError => "Data cannot be decoded",
+            WedprError::IndyCryptoError => "Indy Crypto error.",
+            WedprError::StorageError => "Storage cannot be accessed",
+            WedprError::TransportError => "Transport failed or timed out",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl std::error::Error for WedprError {}
+
+impl From<std::io::Error> for WedprError {
+    fn from(_: std::io::Error) -> Self {
+        WedprError::StorageError
+    }
+}
+
+impl From<std::str::Utf8Error> for WedprError {
+    fn from(_: std::str::Utf8Error) -> Self {
+        WedprError::DecodeError
+    }
+}
+
+impl From<std::string::FromUtf8Error> for WedprError {
+    fn from(_: std::string::FromUtf8Error) -> Self {
+        WedprError::DecodeError
+    }
+}
+
+impl From<std::num::ParseIntError> for WedprError {
+    fn from(_: std::num::ParseIntError) -> Self {
+        WedprError::FormatError
+    }
+}
+
+impl From<std::array::TryFromSliceError> for WedprError {
+    fn from(_: std::array::TryFromSliceError) -> Self {
+        WedprError::FormatError
+    }
+}
+
+#[cfg(feature = "wedpr_f_secp256k1")]
+impl From<secp256k1::Error> for WedprError {
+    fn from(error: secp256k1::Error) -> Self {
+        match error {
+            secp256k1::Error::IncorrectSignature => {
+                WedprError::VerificationError
+            },
+            _ => WedprError::FormatError,
+        }
+    }
+}
+
+#[cfg(feature = "wedpr_f_hex")]
+impl From<hex::FromHexError> for WedprError {
+    fn from(_: hex::FromHexError) -> Self {
+        WedprError::DecodeError
+    }
+}
+
+#[cfg(feature = "wedpr_f_base64")]
+impl From<base64::DecodeError> for WedprError {
+    fn from(_: base64::DecodeError) -> Self {
+        WedprError::DecodeError
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_counter(input: &[u8]) -> Result<u32, WedprError> {
+        let text = std::str::from_utf8(input)?;
+        Ok(text.parse::<u32>()?)
+    }
+
+    #[test]
+    fn test_error_conversions() {
+        assert_eq!(read_counter(b"42"), Ok(42));
+        assert_eq!(read_counter(&[0xff]), Err(WedprError::DecodeError));
+        assert_eq!(read_counter(b"x"), Err(WedprError::FormatError));
+        let error: Box<dyn std::error::Error> =
+            Box::new(WedprError::ArgumentError);
+        assert_eq!(error.to_string(), "Argument is invalid");
+
+        assert_eq!(WedprError::ArgumentError.ffi_code(), -3);
+        for error in WEDPR_ERRORS.iter() {
+            assert!(error.ffi_code() < -1);
+            assert_eq!(
+                WedprError::from_ffi_code(error.ffi_code()).as_ref(),
+                Some(error)
+            );
+        }
+        assert_eq!(WedprError::from_ffi_code(0), None);
+        assert_eq!(WedprError::from_ffi_code(-1), None);
+    }
 }
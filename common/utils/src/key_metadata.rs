@@ -0,0 +1,331 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! WeDPR key metadata management, which labels keys with their algorithms,
+//! creation times and allowed usages for operational tooling.
+//!
+//! Keys are identified by string IDs, e.g. their fingerprints, and metadata
+//! is kept in a replaceable storage backend, so that embedded databases can
+//! be plugged in besides the provided in-memory and file backends.
+
+use crate::error::WedprError;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Usage flag of signing.
+pub const KEY_USAGE_SIGN: u32 = 1;
+/// Usage flag of encryption.
+pub const KEY_USAGE_ENCRYPT: u32 = 1 << 1;
+/// Usage flag of key agreement.
+pub const KEY_USAGE_KEY_AGREEMENT: u32 = 1 << 2;
+/// Usage flag of proving and VRF evaluation.
+pub const KEY_USAGE_PROVE: u32 = 1 << 3;
+
+/// File extension of key metadata files.
+const KEY_METADATA_FILE_EXTENSION: &str = "meta";
+
+/// Metadata of a key.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct KeyMetadata {
+    pub label: String,
+    /// Creation time in seconds since the Unix epoch.
+    pub created_at: u64,
+    /// Algorithm name as in AlgorithmMetadata.
+    pub algorithm: String,
+    /// Bitwise OR of KEY_USAGE_* flags.
+    pub usage_flags: u32,
+}
+
+impl KeyMetadata {
+    /// Checks whether all the usage flags are allowed.
+    pub fn allows(&self, usage_flags: u32) -> bool {
+        self.usage_flags & usage_flags == usage_flags
+    }
+
+    /// Encodes key metadata to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        output.extend_from_slice(&self.created_at.to_be_bytes());
+        output.extend_from_slice(&self.usage_flags.to_be_bytes());
+        append_string(&mut output, &self.algorithm);
+        append_string(&mut output, &self.label);
+        output
+    }
+
+    /// Decodes key metadata from bytes.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        if input.len() < 12 {
+            return Err(WedprError::FormatError);
+        }
+        let mut created_at = [0u8; 8];
+        created_at.copy_from_slice(&input[..8]);
+        let mut usage_flags = [0u8; 4];
+        usage_flags.copy_from_slice(&input[8..12]);
+        let mut offset = 12;
+        let algorithm = read_string(input, &mut offset)?;
+        let label = read_string(input, &mut offset)?;
+        if offset != input.len() {
+            return Err(WedprError::FormatError);
+        }
+        Ok(KeyMetadata {
+            label,
+            created_at: u64::from_be_bytes(created_at),
+            algorithm,
+            usage_flags: u32::from_be_bytes(usage_flags),
+        })
+    }
+}
+
+fn append_string(output: &mut Vec<u8>, value: &str) {
+    output.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    output.extend_from_slice(value.as_bytes());
+}
+
+fn read_string(input: &[u8], offset: &mut usize) -> Result<String, WedprError> {
+    if input.len() < *offset + 4 {
+        return Err(WedprError::FormatError);
+    }
+    let mut length = [0u8; 4];
+    length.copy_from_slice(&input[*offset..*offset + 4]);
+    let start = *offset + 4;
+    let end = start + u32::from_be_bytes(length) as usize;
+    if input.len() < end {
+        return Err(WedprError::FormatError);
+    }
+    *offset = end;
+    match String::from_utf8(input[start..end].to_vec()) {
+        Ok(v) => Ok(v),
+        Err(_) => Err(WedprError::FormatError),
+    }
+}
+
+/// Trait of a replaceable storage backend for key metadata.
+pub trait KeyMetadataStore {
+    /// Loads the metadata of a key, or None if the key is unknown.
+    fn load(&self, key_id: &str) -> Result<Option<KeyMetadata>, WedprError>;
+
+    /// Saves the metadata of a key.
+    fn save(
+        &mut self,
+        key_id: &str,
+        metadata: &KeyMetadata,
+    ) -> Result<(), WedprError>;
+
+    /// Removes the metadata of a key, and returns whether it existed.
+    fn remove(&mut self, key_id: &str) -> Result<bool, WedprError>;
+
+    /// Lists IDs of all keys in ascending order.
+    fn list(&self) -> Result<Vec<String>, WedprError>;
+
+    /// Finds IDs of keys with a label.
+    fn find_by_label(&self, label: &str) -> Result<Vec<String>, WedprError> {
+        let mut key_ids = Vec::new();
+        for key_id in self.list()? {
+            if let Some(metadata) = self.load(&key_id)? {
+                if metadata.label == label {
+                    key_ids.push(key_id);
+                }
+            }
+        }
+        Ok(key_ids)
+    }
+}
+
+/// Implements an in-memory KeyMetadataStore, whose metadata is lost on exit.
+#[derive(Default, Debug, Clone)]
+pub struct MemoryKeyMetadataStore {
+    entries: BTreeMap<String, KeyMetadata>,
+}
+
+impl KeyMetadataStore for MemoryKeyMetadataStore {
+    fn load(&self, key_id: &str) -> Result<Option<KeyMetadata>, WedprError> {
+        Ok(self.entries.get(key_id).cloned())
+    }
+
+    fn save(
+        &mut self,
+        key_id: &str,
+        metadata: &KeyMetadata,
+    ) -> Result<(), WedprError> {
+        self.entries.insert(key_id.to_string(), metadata.clone());
+        Ok(())
+    }
+
+    fn remove(&mut self, key_id: &str) -> Result<bool, WedprError> {
+        Ok(self.entries.remove(key_id).is_some())
+    }
+
+    fn list(&self) -> Result<Vec<String>, WedprError> {
+        Ok(self.entries.keys().cloned().collect())
+    }
+}
+
+/// Implements a KeyMetadataStore persisting the metadata of each key to a
+/// separate file under a directory.
+#[derive(Debug, Clone)]
+pub struct FileKeyMetadataStore {
+    directory: PathBuf,
+}
+
+impl FileKeyMetadataStore {
+    /// Creates a file store under a directory, which will be created if it
+    /// does not exist.
+    pub fn new<P: AsRef<Path>>(directory: P) -> Result<Self, WedprError> {
+        if fs::create_dir_all(directory.as_ref()).is_err() {
+            wedpr_println!("Creating key metadata directory failed");
+            return Err(WedprError::StorageError);
+        }
+        Ok(FileKeyMetadataStore {
+            directory: directory.as_ref().to_path_buf(),
+        })
+    }
+
+    fn key_path(&self, key_id: &str) -> PathBuf {
+        // Hex file names keep any key ID a safe file name.
+        let file_name: String = key_id
+            .as_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        self.directory
+            .join(format!("{}.{}", file_name, KEY_METADATA_FILE_EXTENSION))
+    }
+}
+
+/// Decodes a key ID from a hex file name.
+fn decode_file_name(file_name: &str) -> Option<String> {
+    if !file_name.len().is_multiple_of(2) {
+        return None;
+    }
+    let bytes = (0..file_name.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(file_name.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+impl KeyMetadataStore for FileKeyMetadataStore {
+    fn load(&self, key_id: &str) -> Result<Option<KeyMetadata>, WedprError> {
+        let path = self.key_path(key_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = match fs::read(&path) {
+            Ok(v) => v,
+            Err(_) => {
+                wedpr_println!("Reading key metadata file failed");
+                return Err(WedprError::StorageError);
+            },
+        };
+        Ok(Some(KeyMetadata::decode(&content)?))
+    }
+
+    fn save(
+        &mut self,
+        key_id: &str,
+        metadata: &KeyMetadata,
+    ) -> Result<(), WedprError> {
+        // Writes to a temporary file first, so that a crash never leaves
+        // partially written metadata behind.
+        let path = self.key_path(key_id);
+        let temp_path = path.with_extension("meta.tmp");
+        if fs::write(&temp_path, metadata.encode()).is_err()
+            || fs::rename(&temp_path, &path).is_err()
+        {
+            wedpr_println!("Writing key metadata file failed");
+            return Err(WedprError::StorageError);
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, key_id: &str) -> Result<bool, WedprError> {
+        let path = self.key_path(key_id);
+        if !path.exists() {
+            return Ok(false);
+        }
+        if fs::remove_file(&path).is_err() {
+            wedpr_println!("Removing key metadata file failed");
+            return Err(WedprError::StorageError);
+        }
+        Ok(true)
+    }
+
+    fn list(&self) -> Result<Vec<String>, WedprError> {
+        let entries = match fs::read_dir(&self.directory) {
+            Ok(v) => v,
+            Err(_) => {
+                wedpr_println!("Reading key metadata directory failed");
+                return Err(WedprError::StorageError);
+            },
+        };
+        let mut key_ids = Vec::new();
+        for entry in entries {
+            let path = match entry {
+                Ok(v) => v.path(),
+                Err(_) => return Err(WedprError::StorageError),
+            };
+            if path.extension().and_then(|v| v.to_str())
+                != Some(KEY_METADATA_FILE_EXTENSION)
+            {
+                continue;
+            }
+            if let Some(key_id) = path
+                .file_stem()
+                .and_then(|v| v.to_str())
+                .and_then(decode_file_name)
+            {
+                key_ids.push(key_id);
+            }
+        }
+        key_ids.sort();
+        Ok(key_ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_metadata_store() {
+        let metadata = KeyMetadata {
+            label: "payment signer".to_string(),
+            created_at: 1_600_000_000,
+            algorithm: "secp256k1".to_string(),
+            usage_flags: KEY_USAGE_SIGN | KEY_USAGE_PROVE,
+        };
+        assert!(metadata.allows(KEY_USAGE_SIGN));
+        assert!(!metadata.allows(KEY_USAGE_SIGN | KEY_USAGE_ENCRYPT));
+        let encoded = metadata.encode();
+        assert_eq!(KeyMetadata::decode(&encoded).unwrap(), metadata);
+        assert!(KeyMetadata::decode(&encoded[1..]).is_err());
+
+        let directory = std::env::temp_dir()
+            .join(format!("wedpr_key_metadata_test_{}", std::process::id()));
+        let mut memory_store = MemoryKeyMetadataStore::default();
+        let mut file_store = FileKeyMetadataStore::new(&directory).unwrap();
+        let stores: [&mut dyn KeyMetadataStore; 2] =
+            [&mut memory_store, &mut file_store];
+        for store in stores {
+            store.save("ed25519:bkey2", &metadata).unwrap();
+            store.save("secp256k1:bkey1", &metadata).unwrap();
+            assert_eq!(
+                store.load("secp256k1:bkey1").unwrap(),
+                Some(metadata.clone())
+            );
+            assert_eq!(store.load("unknown").unwrap(), None);
+            assert_eq!(store.list().unwrap(), vec![
+                "ed25519:bkey2",
+                "secp256k1:bkey1"
+            ]);
+            assert!(store.remove("ed25519:bkey2").unwrap());
+            assert!(!store.remove("ed25519:bkey2").unwrap());
+            assert_eq!(store.find_by_label("payment signer").unwrap(), vec![
+                "secp256k1:bkey1"
+            ]);
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -3,9 +3,24 @@
 //! Library of shared utilities.
 
 #[macro_use]
-extern crate failure;
+extern crate wedpr_l_macros;
 
+pub mod algorithm;
 pub mod constant;
+pub mod constant_time;
+pub mod der;
+pub mod digest;
+#[cfg(feature = "wedpr_f_dudect")]
+pub mod dudect;
 pub mod error;
+pub mod key_metadata;
+pub mod migration;
+pub mod nonce;
+pub mod params;
+pub mod policy;
+pub mod profile;
+pub mod scalar;
+pub mod telemetry;
 pub mod tool;
 pub mod traits;
+pub mod verification_cache;
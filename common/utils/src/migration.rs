@@ -0,0 +1,343 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! WeDPR migration shims, which accept legacy artifacts, e.g. old encoding
+//! versions and non-canonical signatures, while a deployment upgrades its
+//! formats gradually.
+//!
+//! In strict mode only current artifacts are accepted. In migration mode a
+//! legacy artifact is accepted after converting it to the current form, and
+//! a deprecation event is reported to a listener, so that the remaining
+//! legacy producers can be tracked down.
+
+use crate::{
+    error::WedprError,
+    traits::{AlgorithmInfo, Signature},
+};
+
+/// Whether legacy artifacts are accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompatibilityMode {
+    Strict,
+    Migration,
+}
+
+/// Kind of an accepted legacy artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LegacyArtifact {
+    /// Encoded data of an old format version.
+    EncodingVersion,
+    NonCanonicalSignature,
+    NonCanonicalPublicKey,
+}
+
+/// Event of an accepted legacy artifact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeprecationEvent {
+    pub artifact: LegacyArtifact,
+    /// Algorithm or format name.
+    pub algorithm: &'static str,
+    pub detail: String,
+}
+
+/// Trait of a listener of deprecation events.
+pub trait DeprecationListener {
+    /// Handles the event of an accepted legacy artifact.
+    fn on_deprecation(&self, event: &DeprecationEvent);
+}
+
+impl<F: Fn(&DeprecationEvent)> DeprecationListener for F {
+    fn on_deprecation(&self, event: &DeprecationEvent) {
+        self(event)
+    }
+}
+
+/// Trait of converting legacy artifacts of an algorithm to their canonical
+/// forms, which return None if an artifact cannot be converted or is already
+/// canonical.
+pub trait LegacyCanonicalizer {
+    /// Converts a non-canonical signature, e.g. with a high s value.
+    fn canonicalize_signature(&self, signature: &[u8]) -> Option<Vec<u8>>;
+
+    /// Converts a non-canonical public key, e.g. in a hybrid form.
+    fn canonicalize_public_key(&self, _public_key: &[u8]) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+/// Decodes data by the current decoder, or by the legacy decoder in
+/// migration mode with a deprecation event of the format.
+pub fn decode_with_migration<V, C, D, L>(
+    mode: CompatibilityMode,
+    listener: &L,
+    format: &'static str,
+    input: &[u8],
+    current_decoder: C,
+    legacy_decoder: D,
+) -> Result<V, WedprError>
+where
+    C: FnOnce(&[u8]) -> Result<V, WedprError>,
+    D: FnOnce(&[u8]) -> Result<V, WedprError>,
+    L: DeprecationListener,
+{
+    let error = match current_decoder(input) {
+        Ok(v) => return Ok(v),
+        Err(e) => e,
+    };
+    if mode == CompatibilityMode::Strict {
+        return Err(error);
+    }
+    let value = legacy_decoder(input)?;
+    listener.on_deprecation(&DeprecationEvent {
+        artifact: LegacyArtifact::EncodingVersion,
+        algorithm: format,
+        detail: format!("{} data of a legacy version", format),
+    });
+    Ok(value)
+}
+
+/// Wraps a Signature instance to verify legacy signatures and public keys in
+/// migration mode, where signing always produces canonical signatures.
+#[derive(Debug, Clone)]
+pub struct MigratingSignature<S, L>
+where
+    S: Signature + AlgorithmInfo + LegacyCanonicalizer,
+    L: DeprecationListener,
+{
+    pub signature: S,
+    pub mode: CompatibilityMode,
+    pub listener: L,
+}
+
+impl<S, L> MigratingSignature<S, L>
+where
+    S: Signature + AlgorithmInfo + LegacyCanonicalizer,
+    L: DeprecationListener,
+{
+    /// Creates a migrating Signature instance.
+    pub fn new(signature: S, mode: CompatibilityMode, listener: L) -> Self {
+        MigratingSignature {
+            signature,
+            mode,
+            listener,
+        }
+    }
+
+    fn report(&self, artifact: LegacyArtifact, detail: &str) {
+        self.listener.on_deprecation(&DeprecationEvent {
+            artifact,
+            algorithm: S::algorithm_info().name,
+            detail: detail.to_string(),
+        });
+    }
+}
+
+impl<S, L> Signature for MigratingSignature<S, L>
+where
+    S: Signature + AlgorithmInfo + LegacyCanonicalizer,
+    L: DeprecationListener,
+{
+    fn sign<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        self.signature.sign(private_key, msg_hash)
+    }
+
+    fn sign_deterministic<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        self.signature.sign_deterministic(private_key, msg_hash)
+    }
+
+    fn verify<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        msg_hash: &T,
+        signature: &T,
+    ) -> bool {
+        if self.signature.verify(public_key, msg_hash, signature) {
+            return true;
+        }
+        if self.mode == CompatibilityMode::Strict {
+            return false;
+        }
+        let legacy_public_key =
+            self.signature.canonicalize_public_key(public_key.as_ref());
+        let legacy_signature =
+            self.signature.canonicalize_signature(signature.as_ref());
+        if legacy_public_key.is_none() && legacy_signature.is_none() {
+            return false;
+        }
+        let canonical_public_key =
+            legacy_public_key.as_deref().unwrap_or(public_key.as_ref());
+        let canonical_signature =
+            legacy_signature.as_deref().unwrap_or(signature.as_ref());
+        if !self.signature.verify(
+            canonical_public_key,
+            msg_hash.as_ref(),
+            canonical_signature,
+        ) {
+            return false;
+        }
+        if legacy_public_key.is_some() {
+            self.report(
+                LegacyArtifact::NonCanonicalPublicKey,
+                "public key in a legacy form",
+            );
+        }
+        if legacy_signature.is_some() {
+            self.report(
+                LegacyArtifact::NonCanonicalSignature,
+                "signature in a legacy form",
+            );
+        }
+        true
+    }
+
+    fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+        self.signature.generate_keypair()
+    }
+
+    fn try_generate_keypair(&self) -> Result<(Vec<u8>, Vec<u8>), WedprError> {
+        self.signature.try_generate_keypair()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::AlgorithmMetadata;
+    use std::cell::RefCell;
+
+    /// Signature instance whose signature is the message hash itself, and
+    /// whose legacy signature is the reversed message hash.
+    #[derive(Default, Debug)]
+    struct ReversibleSignature {}
+
+    impl AlgorithmInfo for ReversibleSignature {
+        fn algorithm_info() -> AlgorithmMetadata {
+            AlgorithmMetadata {
+                name: "reversible",
+                oid: None,
+                security_bits: 0,
+                private_key_size: None,
+                public_key_sizes: &[],
+                signature_size: None,
+                output_size: None,
+            }
+        }
+    }
+
+    impl LegacyCanonicalizer for ReversibleSignature {
+        fn canonicalize_signature(&self, signature: &[u8]) -> Option<Vec<u8>> {
+            Some(signature.iter().rev().cloned().collect())
+        }
+    }
+
+    impl Signature for ReversibleSignature {
+        fn sign<T: ?Sized + AsRef<[u8]>>(
+            &self,
+            _private_key: &T,
+            msg_hash: &T,
+        ) -> Result<Vec<u8>, WedprError> {
+            Ok(msg_hash.as_ref().to_vec())
+        }
+
+        fn verify<T: ?Sized + AsRef<[u8]>>(
+            &self,
+            _public_key: &T,
+            msg_hash: &T,
+            signature: &T,
+        ) -> bool {
+            msg_hash.as_ref() == signature.as_ref()
+        }
+
+        fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+            (vec![1], vec![2])
+        }
+    }
+
+    #[test]
+    fn test_migration() {
+        let events = RefCell::new(Vec::new());
+        let listener =
+            |event: &DeprecationEvent| events.borrow_mut().push(event.clone());
+        let msg_hash = vec![1u8, 2, 3];
+        let legacy_signature = vec![3u8, 2, 1];
+        let public_key = vec![1u8];
+
+        let strict = MigratingSignature::new(
+            ReversibleSignature::default(),
+            CompatibilityMode::Strict,
+            &listener,
+        );
+        assert!(strict.verify(&public_key, &msg_hash, &msg_hash));
+        assert!(!strict.verify(&public_key, &msg_hash, &legacy_signature));
+        let migrating = MigratingSignature::new(
+            ReversibleSignature::default(),
+            CompatibilityMode::Migration,
+            &listener,
+        );
+        assert!(migrating.verify(&public_key, &msg_hash, &msg_hash));
+        assert!(events.borrow().is_empty());
+        assert!(migrating.verify(&public_key, &msg_hash, &legacy_signature));
+        assert!(!migrating.verify(&public_key, &msg_hash, &public_key));
+        assert_eq!(events.borrow().len(), 1);
+        assert_eq!(
+            events.borrow()[0].artifact,
+            LegacyArtifact::NonCanonicalSignature
+        );
+
+        // Version 2 data starts with 2, and version 1 data has no version.
+        let current_decoder = |input: &[u8]| match input.split_first() {
+            Some((2, rest)) => Ok(rest.to_vec()),
+            _ => Err(WedprError::FormatError),
+        };
+        let legacy_decoder = |input: &[u8]| -> Result<Vec<u8>, WedprError> {
+            Ok(input.to_vec())
+        };
+        for mode in [CompatibilityMode::Strict, CompatibilityMode::Migration] {
+            assert_eq!(
+                decode_with_migration(
+                    mode,
+                    &listener,
+                    "test",
+                    &[2, 7],
+                    current_decoder,
+                    legacy_decoder
+                )
+                .unwrap(),
+                vec![7]
+            );
+        }
+        assert!(decode_with_migration(
+            CompatibilityMode::Strict,
+            &listener,
+            "test",
+            &[7],
+            current_decoder,
+            legacy_decoder
+        )
+        .is_err());
+        assert_eq!(events.borrow().len(), 1);
+        assert_eq!(
+            decode_with_migration(
+                CompatibilityMode::Migration,
+                &listener,
+                "test",
+                &[7],
+                current_decoder,
+                legacy_decoder
+            )
+            .unwrap(),
+            vec![7]
+        );
+        assert_eq!(
+            events.borrow()[1].artifact,
+            LegacyArtifact::EncodingVersion
+        );
+    }
+}
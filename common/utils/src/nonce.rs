@@ -0,0 +1,523 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! WeDPR replay-protection nonce management.
+//!
+//! Every key has its own nonce sequence. Issued nonces increase monotonically,
+//! and incoming nonces are accepted at most once, as long as they are not older
+//! than a sliding window behind the highest accepted nonce. The same
+//! sequences also derive AEAD nonces, so that messages of a key never reuse a
+//! nonce.
+
+use crate::{error::WedprError, traits::Aead};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Maximum size of the sliding window for validating incoming nonces.
+pub const MAX_NONCE_WINDOW_SIZE: u64 = 64;
+
+/// Size of AEAD nonces derived from sequence numbers, i.e. the 96-bit nonces
+/// of AES-GCM, SM4-GCM and ChaCha20-Poly1305.
+pub const AEAD_NONCE_SIZE_IN_BYTES: usize = 12;
+
+/// Serialized data size of a nonce state.
+const NONCE_STATE_SIZE_IN_BYTES: usize = 24;
+
+/// Persistent nonce state of a key.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonceState {
+    /// The last nonce issued for the key, where 0 means none has been issued.
+    pub last_issued: u64,
+    /// The highest incoming nonce accepted for the key.
+    pub highest_accepted: u64,
+    /// Bit i is set if nonce (highest_accepted - i) has been accepted.
+    pub accepted_bitmap: u64,
+}
+
+impl NonceState {
+    /// Encodes a nonce state to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::with_capacity(NONCE_STATE_SIZE_IN_BYTES);
+        output.extend_from_slice(&self.last_issued.to_be_bytes());
+        output.extend_from_slice(&self.highest_accepted.to_be_bytes());
+        output.extend_from_slice(&self.accepted_bitmap.to_be_bytes());
+        output
+    }
+
+    /// Decodes a nonce state from bytes.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        if input.len() != NONCE_STATE_SIZE_IN_BYTES {
+            return Err(WedprError::FormatError);
+        }
+        let mut fields = [0u64; 3];
+        for (i, field) in fields.iter_mut().enumerate() {
+            let mut buffer = [0u8; 8];
+            buffer.copy_from_slice(&input[i * 8..(i + 1) * 8]);
+            *field = u64::from_be_bytes(buffer);
+        }
+        Ok(NonceState {
+            last_issued: fields[0],
+            highest_accepted: fields[1],
+            accepted_bitmap: fields[2],
+        })
+    }
+}
+
+/// Trait of a replaceable storage backend for nonce states.
+pub trait NonceStore {
+    /// Loads the nonce state of a key, or None if the key is unknown.
+    fn load(&self, key: &[u8]) -> Result<Option<NonceState>, WedprError>;
+
+    /// Saves the nonce state of a key.
+    fn save(
+        &mut self,
+        key: &[u8],
+        state: &NonceState,
+    ) -> Result<(), WedprError>;
+}
+
+/// Implements an in-memory NonceStore, whose states are lost on exit.
+#[derive(Default, Debug)]
+pub struct MemoryNonceStore {
+    states: HashMap<Vec<u8>, NonceState>,
+}
+
+impl NonceStore for MemoryNonceStore {
+    fn load(&self, key: &[u8]) -> Result<Option<NonceState>, WedprError> {
+        Ok(self.states.get(key).cloned())
+    }
+
+    fn save(
+        &mut self,
+        key: &[u8],
+        state: &NonceState,
+    ) -> Result<(), WedprError> {
+        self.states.insert(key.to_vec(), *state);
+        Ok(())
+    }
+}
+
+/// Implements a NonceStore persisting the state of each key to a separate
+/// file under a directory.
+#[derive(Debug, Clone)]
+pub struct FileNonceStore {
+    directory: PathBuf,
+}
+
+impl FileNonceStore {
+    /// Creates a file store under a directory, which will be created if it
+    /// does not exist.
+    pub fn new<P: AsRef<Path>>(directory: P) -> Result<Self, WedprError> {
+        if fs::create_dir_all(directory.as_ref()).is_err() {
+            wedpr_println!("Creating nonce directory failed");
+            return Err(WedprError::StorageError);
+        }
+        Ok(FileNonceStore {
+            directory: directory.as_ref().to_path_buf(),
+        })
+    }
+
+    /// Gets the state file of a key, named by the SHA-256 hash of the key so
+    /// that file names have a fixed length for keys of any size.
+    fn key_path(&self, key: &[u8]) -> PathBuf {
+        let file_name: String = Sha256::digest(key)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        self.directory.join(format!("{}.nonce", file_name))
+    }
+
+    /// Writes a state file durably, i.e. fsyncs the temporary file before
+    /// renaming it, and the directory after renaming it.
+    fn write_durably(&self, path: &Path, content: &[u8]) -> bool {
+        let temp_path = path.with_extension("nonce.tmp");
+        let written = fs::File::create(&temp_path).and_then(|mut file| {
+            file.write_all(content)?;
+            file.sync_all()
+        });
+        if written.is_err() || fs::rename(&temp_path, path).is_err() {
+            return false;
+        }
+        // Directories can only be opened for syncing on Unix.
+        #[cfg(unix)]
+        {
+            if fs::File::open(&self.directory)
+                .and_then(|directory| directory.sync_all())
+                .is_err()
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl NonceStore for FileNonceStore {
+    fn load(&self, key: &[u8]) -> Result<Option<NonceState>, WedprError> {
+        let path = self.key_path(key);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = match fs::read(&path) {
+            Ok(v) => v,
+            Err(_) => {
+                wedpr_println!("Reading nonce file failed");
+                return Err(WedprError::StorageError);
+            },
+        };
+        Ok(Some(NonceState::decode(&content)?))
+    }
+
+    fn save(
+        &mut self,
+        key: &[u8],
+        state: &NonceState,
+    ) -> Result<(), WedprError> {
+        // Writes to a temporary file first, so that a crash never leaves a
+        // partially written state behind, nor an older state after the save
+        // returns.
+        let path = self.key_path(key);
+        if !self.write_durably(&path, &state.encode()) {
+            wedpr_println!("Writing nonce file failed");
+            return Err(WedprError::StorageError);
+        }
+        Ok(())
+    }
+}
+
+/// Issues and validates nonces per key on top of a NonceStore. It is not
+/// Clone, since copies over the same states would issue the same nonces.
+#[derive(Debug)]
+pub struct NonceManager<S: NonceStore> {
+    store: S,
+    window_size: u64,
+}
+
+impl<S: NonceStore> NonceManager<S> {
+    /// Creates a nonce manager, where window_size is the number of most recent
+    /// nonces that can still be accepted out of order, which should belong to
+    /// [1, MAX_NONCE_WINDOW_SIZE].
+    pub fn new(store: S, window_size: u64) -> Result<Self, WedprError> {
+        if window_size == 0 || window_size > MAX_NONCE_WINDOW_SIZE {
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(NonceManager { store, window_size })
+    }
+
+    /// Issues the next nonce for a key, starting from 1.
+    pub fn issue(&mut self, key: &[u8]) -> Result<u64, WedprError> {
+        let mut state = self.store.load(key)?.unwrap_or_default();
+        state.last_issued = match state.last_issued.checked_add(1) {
+            Some(v) => v,
+            None => return Err(WedprError::ArgumentError),
+        };
+        self.store.save(key, &state)?;
+        Ok(state.last_issued)
+    }
+
+    /// Validates an incoming nonce for a key, and records it if accepted.
+    /// It returns false if the nonce is 0, has already been accepted, or is
+    /// older than the validation window.
+    pub fn validate(
+        &mut self,
+        key: &[u8],
+        nonce: u64,
+    ) -> Result<bool, WedprError> {
+        if nonce == 0 {
+            return Ok(false);
+        }
+        let mut state = self.store.load(key)?.unwrap_or_default();
+        if nonce > state.highest_accepted {
+            let shift = nonce - state.highest_accepted;
+            state.accepted_bitmap = if shift >= MAX_NONCE_WINDOW_SIZE {
+                1
+            } else {
+                (state.accepted_bitmap << shift) | 1
+            };
+            state.highest_accepted = nonce;
+        } else {
+            let offset = state.highest_accepted - nonce;
+            if offset >= self.window_size
+                || (state.accepted_bitmap >> offset) & 1 == 1
+            {
+                return Ok(false);
+            }
+            state.accepted_bitmap |= 1 << offset;
+        }
+        self.store.save(key, &state)?;
+        Ok(true)
+    }
+
+    /// Gets the underlying store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+}
+
+/// Derives the AEAD nonce of a sequence number, i.e. the 64-bit big-endian
+/// sequence number left-padded to AEAD_NONCE_SIZE_IN_BYTES and XORed with a
+/// per-key nonce mask as in TLS 1.3, where the mask can be derived from the
+/// key and a context, or be all zeros.
+pub fn derive_aead_nonce(
+    nonce_mask: &[u8],
+    sequence: u64,
+) -> Result<Vec<u8>, WedprError> {
+    if nonce_mask.len() != AEAD_NONCE_SIZE_IN_BYTES {
+        return Err(WedprError::ArgumentError);
+    }
+    let mut nonce = nonce_mask.to_vec();
+    let offset = AEAD_NONCE_SIZE_IN_BYTES - 8;
+    for (byte, sequence_byte) in nonce[offset..]
+        .iter_mut()
+        .zip(sequence.to_be_bytes().iter())
+    {
+        *byte ^= sequence_byte;
+    }
+    Ok(nonce)
+}
+
+/// Seals and opens messages by an Aead instance with nonces derived from
+/// per-key sequence numbers of a NonceManager, so that a nonce is never
+/// reused with a key as long as the NonceStore persists issued sequences.
+///
+/// Keys are tracked in the store by key IDs instead of the keys themselves.
+#[derive(Debug)]
+pub struct SequencedAead<A: Aead, S: NonceStore> {
+    aead: A,
+    nonces: NonceManager<S>,
+}
+
+impl<A: Aead, S: NonceStore> SequencedAead<A, S> {
+    /// Creates an instance over an Aead instance and a nonce manager.
+    pub fn new(aead: A, nonces: NonceManager<S>) -> Self {
+        SequencedAead { aead, nonces }
+    }
+
+    /// Encrypts a message with the next sequence number of a key, which
+    /// returns the sequence number and the ciphertext. The sequence number is
+    /// persisted before encryption.
+    pub fn seal(
+        &mut self,
+        key_id: &[u8],
+        key: &[u8],
+        nonce_mask: &[u8],
+        message: &[u8],
+        aad: &[u8],
+    ) -> Result<(u64, Vec<u8>), WedprError> {
+        if nonce_mask.len() != AEAD_NONCE_SIZE_IN_BYTES {
+            return Err(WedprError::ArgumentError);
+        }
+        let sequence = self.nonces.issue(key_id)?;
+        let nonce = derive_aead_nonce(nonce_mask, sequence)?;
+        let ciphertext =
+            self.aead.encrypt_with_aad(message, key, &nonce[..], aad)?;
+        Ok((sequence, ciphertext))
+    }
+
+    /// Decrypts a ciphertext of a sequence number, which fails if the
+    /// sequence number of the key has been accepted before or is out of the
+    /// validation window.
+    pub fn open(
+        &mut self,
+        key_id: &[u8],
+        key: &[u8],
+        nonce_mask: &[u8],
+        sequence: u64,
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        let nonce = derive_aead_nonce(nonce_mask, sequence)?;
+        let message =
+            self.aead
+                .decrypt_with_aad(ciphertext, key, &nonce[..], aad)?;
+        // Records the sequence number only after authentication succeeds.
+        if !self.nonces.validate(key_id, sequence)? {
+            wedpr_println!("Replayed AEAD sequence number {}", sequence);
+            return Err(WedprError::VerificationError);
+        }
+        Ok(message)
+    }
+
+    /// Gets the underlying nonce manager.
+    pub fn nonces(&self) -> &NonceManager<S> {
+        &self.nonces
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonce_manager() {
+        let mut manager =
+            NonceManager::new(MemoryNonceStore::default(), 4).unwrap();
+        let key = b"key";
+        assert_eq!(manager.issue(key).unwrap(), 1);
+        assert_eq!(manager.issue(key).unwrap(), 2);
+        assert_eq!(manager.issue(b"another key").unwrap(), 1);
+
+        assert!(!manager.validate(key, 0).unwrap());
+        assert!(manager.validate(key, 5).unwrap());
+        assert!(!manager.validate(key, 5).unwrap());
+        // Out of order nonces within the window are accepted once.
+        assert!(manager.validate(key, 3).unwrap());
+        assert!(!manager.validate(key, 3).unwrap());
+        assert!(manager.validate(key, 2).unwrap());
+        // Nonces older than the window are rejected.
+        assert!(!manager.validate(key, 1).unwrap());
+        assert!(manager.validate(key, 100).unwrap());
+        assert!(!manager.validate(key, 5).unwrap());
+        assert!(manager.validate(key, 99).unwrap());
+
+        assert!(NonceManager::new(MemoryNonceStore::default(), 0).is_err());
+        assert!(NonceManager::new(MemoryNonceStore::default(), 65).is_err());
+    }
+
+    #[test]
+    fn test_file_nonce_store() {
+        let directory = std::env::temp_dir()
+            .join(format!("wedpr_nonce_test_{}", std::process::id()));
+        let key = b"key";
+        let long_key = [7u8; 1024];
+        {
+            let store = FileNonceStore::new(&directory).unwrap();
+            let mut manager = NonceManager::new(store, 8).unwrap();
+            assert_eq!(manager.issue(key).unwrap(), 1);
+            assert!(manager.validate(key, 10).unwrap());
+            // Key IDs of any size fit in file names.
+            assert_eq!(manager.issue(&long_key).unwrap(), 1);
+        }
+        // States survive a restart.
+        let store = FileNonceStore::new(&directory).unwrap();
+        let mut manager = NonceManager::new(store, 8).unwrap();
+        assert_eq!(manager.issue(key).unwrap(), 2);
+        assert!(!manager.validate(key, 10).unwrap());
+        assert!(manager.validate(key, 9).unwrap());
+        assert_eq!(manager.issue(&long_key).unwrap(), 2);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    /// Aead mock whose ciphertext is the nonce, the AAD and the message.
+    #[derive(Debug, Clone)]
+    struct NonceEchoAead {}
+
+    impl crate::traits::BlockCipher for NonceEchoAead {
+        fn encrypt<T: ?Sized + AsRef<[u8]>>(
+            &self,
+            message: &T,
+            _key: &T,
+            iv: &T,
+        ) -> Result<Vec<u8>, WedprError> {
+            Ok([iv.as_ref(), message.as_ref()].concat())
+        }
+
+        fn decrypt<T: ?Sized + AsRef<[u8]>>(
+            &self,
+            _ciphertext: &T,
+            _key: &T,
+            _iv: &T,
+        ) -> Result<Vec<u8>, WedprError> {
+            Err(WedprError::DecodeError)
+        }
+    }
+
+    impl Aead for NonceEchoAead {
+        fn encrypt_with_aad<T: ?Sized + AsRef<[u8]>>(
+            &self,
+            message: &T,
+            _key: &T,
+            nonce: &T,
+            aad: &T,
+        ) -> Result<Vec<u8>, WedprError> {
+            Ok([nonce.as_ref(), aad.as_ref(), message.as_ref()].concat())
+        }
+
+        fn decrypt_with_aad<T: ?Sized + AsRef<[u8]>>(
+            &self,
+            ciphertext: &T,
+            _key: &T,
+            nonce: &T,
+            aad: &T,
+        ) -> Result<Vec<u8>, WedprError> {
+            let prefix = [nonce.as_ref(), aad.as_ref()].concat();
+            match ciphertext.as_ref().strip_prefix(prefix.as_slice()) {
+                Some(v) => Ok(v.to_vec()),
+                None => Err(WedprError::DecodeError),
+            }
+        }
+
+        fn generate_key(&self) -> Vec<u8> {
+            vec![0u8; 32]
+        }
+
+        fn generate_nonce(&self) -> Vec<u8> {
+            vec![0u8; AEAD_NONCE_SIZE_IN_BYTES]
+        }
+    }
+
+    #[test]
+    fn test_sequenced_aead() {
+        let mask: Vec<u8> = (0u8..12).collect();
+        assert_eq!(derive_aead_nonce(&mask, 0x0102).unwrap(), vec![
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 11, 9
+        ]);
+        assert!(derive_aead_nonce(&mask[1..], 1).is_err());
+
+        let nonces = NonceManager::new(MemoryNonceStore::default(), 4).unwrap();
+        let mut aead = SequencedAead::new(NonceEchoAead {}, nonces);
+        let (sequence1, ciphertext1) = aead
+            .seal(b"key id", b"key", &mask, b"message", b"aad")
+            .unwrap();
+        let (sequence2, ciphertext2) = aead
+            .seal(b"key id", b"key", &mask, b"message", b"aad")
+            .unwrap();
+        assert_eq!((sequence1, sequence2), (1, 2));
+        // Nonces of a key are never reused.
+        assert_eq!(
+            &ciphertext1[..12],
+            &derive_aead_nonce(&mask, 1).unwrap()[..]
+        );
+        assert_ne!(ciphertext1, ciphertext2);
+
+        let receiver =
+            NonceManager::new(MemoryNonceStore::default(), 4).unwrap();
+        let mut receiver = SequencedAead::new(NonceEchoAead {}, receiver);
+        assert_eq!(
+            receiver
+                .open(b"key id", b"key", &mask, 2, &ciphertext2, b"aad")
+                .unwrap(),
+            b"message"
+        );
+        // A wrong sequence number fails authentication without being
+        // recorded.
+        assert!(receiver
+            .open(b"key id", b"key", &mask, 2, &ciphertext1, b"aad")
+            .is_err());
+        assert!(receiver
+            .open(b"key id", b"key", &mask, 1, &ciphertext1, b"aad")
+            .is_ok());
+        // Replays are rejected.
+        assert_eq!(
+            receiver
+                .open(b"key id", b"key", &mask, 1, &ciphertext1, b"aad")
+                .unwrap_err(),
+            WedprError::VerificationError
+        );
+    }
+
+    #[test]
+    fn test_nonce_state_encoding() {
+        let state = NonceState {
+            last_issued: 1,
+            highest_accepted: 2,
+            accepted_bitmap: 3,
+        };
+        assert_eq!(NonceState::decode(&state.encode()).unwrap(), state);
+        assert!(NonceState::decode(&[0u8; 23]).is_err());
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! WeDPR public parameters, e.g. Bulletproof generators, KZG structured
+//! reference strings or ABE public parameters, which are loaded from files
+//! or embedded blobs and verified against pinned digests, instead of being
+//! regenerated at every startup.
+
+use crate::{error::WedprError, traits::Hash};
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use subtle::ConstantTimeEq;
+
+/// Magic bytes at the beginning of an encoded parameter blob.
+const PARAMS_MAGIC: &[u8] = b"WeDPR_PARAMS";
+
+/// Named and versioned public parameters.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PublicParams {
+    pub name: String,
+    pub version: u32,
+    pub data: Vec<u8>,
+}
+
+/// Expected identity of public parameters pinned by an application, where
+/// digest is computed by PublicParams::digest.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ParamsSpec {
+    pub name: String,
+    pub version: u32,
+    pub digest: Vec<u8>,
+}
+
+impl PublicParams {
+    /// Creates public parameters.
+    pub fn new(name: &str, version: u32, data: &[u8]) -> Self {
+        PublicParams {
+            name: name.to_string(),
+            version,
+            data: data.to_vec(),
+        }
+    }
+
+    /// Encodes public parameters to a blob.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = PARAMS_MAGIC.to_vec();
+        output.extend_from_slice(&(self.name.len() as u32).to_be_bytes());
+        output.extend_from_slice(self.name.as_bytes());
+        output.extend_from_slice(&self.version.to_be_bytes());
+        output.extend_from_slice(&(self.data.len() as u64).to_be_bytes());
+        output.extend_from_slice(&self.data);
+        output
+    }
+
+    /// Decodes public parameters from a blob.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        if !input.starts_with(PARAMS_MAGIC) {
+            wedpr_println!("Public parameters have an unknown format");
+            return Err(WedprError::FormatError);
+        }
+        let mut offset = PARAMS_MAGIC.len();
+        let name_length = read_u32(input, &mut offset)? as usize;
+        let name = match String::from_utf8(
+            read_fixed(input, &mut offset, name_length)?.to_vec(),
+        ) {
+            Ok(v) => v,
+            Err(_) => return Err(WedprError::DecodeError),
+        };
+        let version = read_u32(input, &mut offset)?;
+        let mut buffer = [0u8; 8];
+        buffer.copy_from_slice(read_fixed(input, &mut offset, 8)?);
+        let data_length = u64::from_be_bytes(buffer) as usize;
+        let data = read_fixed(input, &mut offset, data_length)?.to_vec();
+        if offset != input.len() {
+            wedpr_println!("Public parameters have trailing bytes");
+            return Err(WedprError::FormatError);
+        }
+        Ok(PublicParams {
+            name,
+            version,
+            data,
+        })
+    }
+
+    /// Computes the digest of the encoded parameters.
+    pub fn digest<H: Hash>(&self, hash: &H) -> Vec<u8> {
+        hash.hash(&self.encode())
+    }
+
+    /// Creates the spec pinning these parameters.
+    pub fn spec<H: Hash>(&self, hash: &H) -> ParamsSpec {
+        ParamsSpec {
+            name: self.name.clone(),
+            version: self.version,
+            digest: self.digest(hash),
+        }
+    }
+}
+
+fn read_fixed<'a>(
+    input: &'a [u8],
+    offset: &mut usize,
+    length: usize,
+) -> Result<&'a [u8], WedprError> {
+    if input.len() - *offset < length {
+        wedpr_println!("Public parameters are truncated");
+        return Err(WedprError::FormatError);
+    }
+    let field = &input[*offset..*offset + length];
+    *offset += length;
+    Ok(field)
+}
+
+fn read_u32(input: &[u8], offset: &mut usize) -> Result<u32, WedprError> {
+    let mut buffer = [0u8; 4];
+    buffer.copy_from_slice(read_fixed(input, offset, 4)?);
+    Ok(u32::from_be_bytes(buffer))
+}
+
+/// Loads public parameters from a blob, e.g. one embedded by include_bytes!,
+/// and verifies them against spec.
+pub fn load_params_from_bytes<H: Hash>(
+    hash: &H,
+    spec: &ParamsSpec,
+    blob: &[u8],
+) -> Result<PublicParams, WedprError> {
+    let params = PublicParams::decode(blob)?;
+    if params.name != spec.name || params.version != spec.version {
+        wedpr_println!(
+            "Public parameters mismatch, expected={} v{}, actual={} v{}",
+            spec.name,
+            spec.version,
+            params.name,
+            params.version
+        );
+        return Err(WedprError::VerificationError);
+    }
+    let digest = hash.hash(blob);
+    if digest.len() != spec.digest.len()
+        || !bool::from(digest.ct_eq(&spec.digest))
+    {
+        wedpr_println!("Public parameters digest mismatch, name={}", spec.name);
+        return Err(WedprError::VerificationError);
+    }
+    Ok(params)
+}
+
+/// Loads public parameters from a file, and verifies them against spec.
+pub fn load_params_from_file<H: Hash, P: AsRef<Path>>(
+    hash: &H,
+    spec: &ParamsSpec,
+    path: P,
+) -> Result<PublicParams, WedprError> {
+    let blob = match fs::read(path) {
+        Ok(v) => v,
+        Err(_) => {
+            wedpr_println!(
+                "Public parameters cannot be read, name={}",
+                spec.name
+            );
+            return Err(WedprError::StorageError);
+        },
+    };
+    load_params_from_bytes(hash, spec, &blob)
+}
+
+/// Cache of verified public parameters, keyed by their names and versions,
+/// which can be shared between threads.
+#[derive(Default, Debug)]
+pub struct ParamsCache {
+    entries: Mutex<HashMap<(String, u32), Arc<PublicParams>>>,
+}
+
+impl ParamsCache {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        ParamsCache::default()
+    }
+
+    /// Gets the parameters of spec from the cache, or reads their blob with
+    /// read_blob, verifies and caches them if absent.
+    pub fn get_or_load<H: Hash, F: FnOnce() -> Result<Vec<u8>, WedprError>>(
+        &self,
+        hash: &H,
+        spec: &ParamsSpec,
+        read_blob: F,
+    ) -> Result<Arc<PublicParams>, WedprError> {
+        let key = (spec.name.clone(), spec.version);
+        if let Some(params) = self.get(&spec.name, spec.version) {
+            return Ok(params);
+        }
+        let params =
+            Arc::new(load_params_from_bytes(hash, spec, &read_blob()?)?);
+        let mut entries = match self.entries.lock() {
+            Ok(v) => v,
+            Err(_) => return Err(WedprError::StorageError),
+        };
+        Ok(entries.entry(key).or_insert(params).clone())
+    }
+
+    /// Gets cached parameters by their name and version.
+    pub fn get(&self, name: &str, version: u32) -> Option<Arc<PublicParams>> {
+        match self.entries.lock() {
+            Ok(v) => v.get(&(name.to_string(), version)).cloned(),
+            Err(_) => None,
+        }
+    }
+
+    /// Removes all cached parameters.
+    pub fn clear(&self) {
+        if let Ok(mut v) = self.entries.lock() {
+            v.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hash function which XOR-folds the input into 8 bytes.
+    #[derive(Default, Debug, Clone)]
+    struct FoldHash {}
+
+    impl Hash for FoldHash {
+        fn hash<T: ?Sized + AsRef<[u8]>>(&self, input: &T) -> Vec<u8> {
+            let mut output = vec![0u8; 8];
+            for (i, byte) in input.as_ref().iter().enumerate() {
+                output[i % 8] ^= byte.wrapping_add(i as u8);
+            }
+            output
+        }
+    }
+
+    #[test]
+    fn test_params() {
+        let hash = FoldHash::default();
+        let params = PublicParams::new("bulletproof_gens", 2, b"generators");
+        let blob = params.encode();
+        let spec = params.spec(&hash);
+        assert_eq!(
+            load_params_from_bytes(&hash, &spec, &blob).unwrap(),
+            params
+        );
+
+        let mut tampered_blob = blob.clone();
+        let last = tampered_blob.len() - 1;
+        tampered_blob[last] ^= 1;
+        assert!(load_params_from_bytes(&hash, &spec, &tampered_blob).is_err());
+        let mut old_spec = spec.clone();
+        old_spec.version = 1;
+        assert!(load_params_from_bytes(&hash, &old_spec, &blob).is_err());
+        assert!(PublicParams::decode(&blob[..blob.len() - 1]).is_err());
+
+        let cache = ParamsCache::new();
+        let loaded = cache
+            .get_or_load(&hash, &spec, || Ok(blob.clone()))
+            .unwrap();
+        // Cached parameters are not read again.
+        let cached = cache
+            .get_or_load(&hash, &spec, || Err(WedprError::StorageError))
+            .unwrap();
+        assert!(Arc::ptr_eq(&loaded, &cached));
+        assert!(cache.get("bulletproof_gens", 1).is_none());
+        cache.clear();
+        assert!(cache.get("bulletproof_gens", 2).is_none());
+        assert!(load_params_from_file(&hash, &spec, "/nonexistent").is_err());
+    }
+}
@@ -0,0 +1,310 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! WeDPR security policy, which algorithm modules consult to reject weak
+//! parameters and deprecated algorithms.
+//!
+//! Each deployment configures one global policy at startup, which a thread
+//! can override for the duration of a call by with_scoped_policy. A call may
+//! still bypass a violated rule with a PolicyWaiver, which acknowledges the
+//! reason explicitly and is logged.
+
+use crate::{
+    algorithm::AlgorithmMetadata, error::WedprError, traits::AlgorithmInfo,
+};
+use std::{
+    cell::RefCell,
+    sync::{Arc, RwLock},
+};
+
+/// Global policy, where None means the default policy.
+static GLOBAL_SECURITY_POLICY: RwLock<Option<Arc<SecurityPolicy>>> =
+    RwLock::new(None);
+
+thread_local! {
+    /// Policy overriding the global policy on the current thread.
+    static SCOPED_SECURITY_POLICY: RefCell<Option<Arc<SecurityPolicy>>> =
+        const { RefCell::new(None) };
+}
+
+/// Security policy of a deployment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityPolicy {
+    /// Minimum security level of algorithms in bits.
+    pub min_security_bits: u32,
+    pub min_rsa_key_size_in_bits: usize,
+    /// Names of banned algorithms as in AlgorithmMetadata.
+    pub banned_algorithms: Vec<String>,
+    /// Names of allowed algorithms as in AlgorithmMetadata, where an empty
+    /// list allows all algorithms which are not banned.
+    pub allowed_algorithms: Vec<String>,
+    /// Whether ECDSA signatures should have low s values, which are produced
+    /// when signing and required when verifying, to prevent malleability.
+    pub require_low_s: bool,
+}
+
+impl Default for SecurityPolicy {
+    /// Creates a policy compatible with existing deployments, which bans
+    /// MD5 and SHA-1.
+    fn default() -> Self {
+        SecurityPolicy {
+            min_security_bits: 112,
+            min_rsa_key_size_in_bits: 2048,
+            banned_algorithms: vec!["md5".to_string(), "sha1".to_string()],
+            allowed_algorithms: Vec::new(),
+            require_low_s: false,
+        }
+    }
+}
+
+/// Explicit acknowledgment for bypassing the security policy in a call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyWaiver {
+    reason: String,
+}
+
+impl PolicyWaiver {
+    /// Acknowledges bypassing the policy for a non-empty reason, e.g. reading
+    /// legacy data.
+    pub fn acknowledge(reason: &str) -> Result<Self, WedprError> {
+        if reason.trim().is_empty() {
+            wedpr_println!("Policy waivers should have reasons");
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(PolicyWaiver {
+            reason: reason.to_string(),
+        })
+    }
+
+    /// Gets the acknowledged reason.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+/// Rejects a policy violation, unless it is waived.
+fn check_violation(
+    violation: String,
+    waiver: Option<&PolicyWaiver>,
+) -> Result<(), WedprError> {
+    match waiver {
+        Some(v) => {
+            wedpr_println!(
+                "Waived policy violation: {}, for {}",
+                violation,
+                v.reason
+            );
+            Ok(())
+        },
+        None => {
+            wedpr_println!("Policy violation: {}", violation);
+            Err(WedprError::ArgumentError)
+        },
+    }
+}
+
+impl SecurityPolicy {
+    /// Checks whether an algorithm is allowed.
+    pub fn check_algorithm(
+        &self,
+        metadata: &AlgorithmMetadata,
+        waiver: Option<&PolicyWaiver>,
+    ) -> Result<(), WedprError> {
+        self.check_algorithm_name(metadata.name, waiver)?;
+        if metadata.security_bits < self.min_security_bits {
+            return check_violation(
+                format!(
+                    "{} has {} security bits below {}",
+                    metadata.name,
+                    metadata.security_bits,
+                    self.min_security_bits
+                ),
+                waiver,
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks whether an algorithm name is allowed by the banned and allowed
+    /// lists, e.g. for runtime providers without metadata.
+    pub fn check_algorithm_name(
+        &self,
+        name: &str,
+        waiver: Option<&PolicyWaiver>,
+    ) -> Result<(), WedprError> {
+        if self.banned_algorithms.iter().any(|v| v == name) {
+            return check_violation(format!("{} is banned", name), waiver);
+        }
+        if !self.allowed_algorithms.is_empty()
+            && !self.allowed_algorithms.iter().any(|v| v == name)
+        {
+            return check_violation(format!("{} is not allowed", name), waiver);
+        }
+        Ok(())
+    }
+
+    /// Checks whether an RSA key size is allowed.
+    pub fn check_rsa_key_size(
+        &self,
+        key_size_in_bits: usize,
+        waiver: Option<&PolicyWaiver>,
+    ) -> Result<(), WedprError> {
+        if key_size_in_bits < self.min_rsa_key_size_in_bits {
+            return check_violation(
+                format!(
+                    "RSA key size {} is below {}",
+                    key_size_in_bits, self.min_rsa_key_size_in_bits
+                ),
+                waiver,
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Sets the global policy of the deployment.
+pub fn set_global_policy(policy: SecurityPolicy) {
+    match GLOBAL_SECURITY_POLICY.write() {
+        Ok(mut v) => *v = Some(Arc::new(policy)),
+        Err(e) => *e.into_inner() = Some(Arc::new(policy)),
+    }
+}
+
+/// Restores the previous scoped policy of the current thread when dropped.
+struct ScopedPolicyGuard {
+    previous: Option<Arc<SecurityPolicy>>,
+}
+
+impl Drop for ScopedPolicyGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        SCOPED_SECURITY_POLICY.with(|v| *v.borrow_mut() = previous);
+    }
+}
+
+/// Runs a function with a policy overriding the global policy on the current
+/// thread only, e.g. to apply a stricter policy to a request, or to test
+/// policy enforcement without affecting other threads.
+pub fn with_scoped_policy<T, F: FnOnce() -> T>(
+    policy: SecurityPolicy,
+    run: F,
+) -> T {
+    let previous =
+        SCOPED_SECURITY_POLICY.with(|v| v.replace(Some(Arc::new(policy))));
+    let _guard = ScopedPolicyGuard { previous };
+    run()
+}
+
+/// Gets the policy in effect on the current thread, i.e. the scoped policy
+/// if any, or the global policy, which is the default policy if not set.
+pub fn global_policy() -> Arc<SecurityPolicy> {
+    if let Some(v) = SCOPED_SECURITY_POLICY.with(|v| v.borrow().clone()) {
+        return v;
+    }
+    let current = match GLOBAL_SECURITY_POLICY.read() {
+        Ok(v) => v.clone(),
+        Err(e) => e.into_inner().clone(),
+    };
+    current.unwrap_or_else(|| Arc::new(SecurityPolicy::default()))
+}
+
+/// Checks whether an algorithm is allowed by the global policy, which
+/// algorithm modules call at their entry points.
+pub fn check_global_algorithm<A: AlgorithmInfo>() -> Result<(), WedprError> {
+    global_policy().check_algorithm(&A::algorithm_info(), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_security_policy() {
+        let sha1 = AlgorithmMetadata {
+            name: "sha1",
+            oid: Some("1.3.14.3.2.26"),
+            security_bits: 80,
+            private_key_size: None,
+            public_key_sizes: &[],
+            signature_size: None,
+            output_size: Some(20),
+        };
+        let sha256 = AlgorithmMetadata {
+            name: "sha256",
+            security_bits: 128,
+            output_size: Some(32),
+            ..sha1
+        };
+        let default_policy = SecurityPolicy::default();
+        assert!(default_policy.check_algorithm(&sha1, None).is_err());
+        assert!(default_policy.check_algorithm(&sha256, None).is_ok());
+
+        let policy = SecurityPolicy {
+            min_security_bits: 192,
+            min_rsa_key_size_in_bits: 3072,
+            ..SecurityPolicy::default()
+        };
+        let waiver =
+            PolicyWaiver::acknowledge("verifying legacy data").unwrap();
+        with_scoped_policy(policy.clone(), || {
+            assert_eq!(*global_policy(), policy);
+            assert!(global_policy().check_algorithm(&sha256, None).is_err());
+            assert!(global_policy().check_rsa_key_size(2048, None).is_err());
+            assert!(global_policy().check_rsa_key_size(4096, None).is_ok());
+            assert!(global_policy()
+                .check_rsa_key_size(2048, Some(&waiver))
+                .is_ok());
+            assert!(global_policy()
+                .check_algorithm(&sha1, Some(&waiver))
+                .is_ok());
+            // Other threads keep the global policy.
+            let scoped_policy = policy.clone();
+            std::thread::spawn(move || {
+                assert_ne!(*global_policy(), scoped_policy)
+            })
+            .join()
+            .unwrap();
+        });
+        assert_ne!(*global_policy(), policy);
+        assert_eq!(waiver.reason(), "verifying legacy data");
+        assert!(PolicyWaiver::acknowledge(" ").is_err());
+
+        let allowlist_policy = SecurityPolicy {
+            allowed_algorithms: vec!["sha256".to_string()],
+            ..SecurityPolicy::default()
+        };
+        assert!(allowlist_policy.check_algorithm(&sha256, None).is_ok());
+        assert!(allowlist_policy
+            .check_algorithm(
+                &AlgorithmMetadata {
+                    name: "sm3",
+                    ..sha256
+                },
+                None
+            )
+            .is_err());
+        assert!(allowlist_policy.check_algorithm_name("sm3", None).is_err());
+        assert!(SecurityPolicy::default()
+            .check_algorithm_name("md5", None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_global_policy() {
+        // Only bans an algorithm which no other test uses, since the global
+        // policy is shared by tests running in parallel.
+        let policy = SecurityPolicy {
+            banned_algorithms: vec!["test-global-policy".to_string()],
+            ..SecurityPolicy::default()
+        };
+        set_global_policy(policy.clone());
+        assert_eq!(*global_policy(), policy);
+        assert!(global_policy()
+            .check_algorithm_name("test-global-policy", None)
+            .is_err());
+        with_scoped_policy(SecurityPolicy::default(), || {
+            assert_eq!(*global_policy(), SecurityPolicy::default())
+        });
+        assert_eq!(*global_policy(), policy);
+        set_global_policy(SecurityPolicy::default());
+    }
+}
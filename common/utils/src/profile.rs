@@ -0,0 +1,437 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! WeDPR crypto profiles, which are signed security policy files that
+//! operators distribute to control algorithm usage across services.
+//!
+//! A profile file consists of "name=value" lines of a serial number and the
+//! policy fields, followed by a "signature=<hex>" line signed over all
+//! preceding text by the operator root key. Loading a profile verifies its
+//! signature and rejects serial numbers older than the loaded profile before
+//! applying its policy as the global policy.
+
+use crate::{
+    digest::MessageDigest,
+    error::WedprError,
+    policy::{set_global_policy, SecurityPolicy},
+    traits::{AlgorithmInfo, Hash, Signature},
+};
+use std::{
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+/// First line of crypto profile files.
+pub const CRYPTO_PROFILE_HEADER: &str = "wedpr-crypto-profile v1";
+/// Recommended domain of the MessageDigest for signing profiles.
+pub const CRYPTO_PROFILE_DOMAIN: &[u8] = b"WeDPR_CRYPTO_PROFILE";
+
+const SIGNATURE_LINE_PREFIX: &str = "\nsignature=";
+
+/// Serial number of the loaded global profile, where 0 means none.
+static LOADED_PROFILE_SERIAL: AtomicU64 = AtomicU64::new(0);
+/// Serializes profile loading, so that the serial check and the policy swap
+/// of a load are not interleaved with another load.
+static PROFILE_LOAD_LOCK: Mutex<()> = Mutex::new(());
+
+/// Crypto profile of a security policy.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct CryptoProfile {
+    /// Serial number, which should increase with every issued profile.
+    pub serial: u64,
+    pub policy: SecurityPolicy,
+}
+
+impl CryptoProfile {
+    /// Encodes the profile as unsigned profile text.
+    pub fn encode(&self) -> String {
+        format!(
+            concat!(
+                "{}\nserial={}\nmin_security_bits={}\n",
+                "min_rsa_key_size_in_bits={}\nrequire_low_s={}\n",
+                "banned_algorithms={}\nallowed_algorithms={}\n",
+            ),
+            CRYPTO_PROFILE_HEADER,
+            self.serial,
+            self.policy.min_security_bits,
+            self.policy.min_rsa_key_size_in_bits,
+            self.policy.require_low_s,
+            self.policy.banned_algorithms.join(","),
+            self.policy.allowed_algorithms.join(","),
+        )
+    }
+
+    /// Parses unsigned profile text, where empty lines and comment lines
+    /// starting with "#" are ignored, and omitted policy fields take the
+    /// default values.
+    pub fn parse(text: &str) -> Result<Self, WedprError> {
+        let mut lines = text.lines();
+        if lines.next() != Some(CRYPTO_PROFILE_HEADER) {
+            wedpr_println!("Unsupported crypto profile version");
+            return Err(WedprError::FormatError);
+        }
+        let mut serial = None;
+        let mut policy = SecurityPolicy::default();
+        for line in lines.map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, value) = match line.split_once('=') {
+                Some(v) => v,
+                None => return Err(WedprError::FormatError),
+            };
+            match name {
+                "serial" => serial = Some(parse_field(value)?),
+                "min_security_bits" => {
+                    policy.min_security_bits = parse_field(value)?
+                },
+                "min_rsa_key_size_in_bits" => {
+                    policy.min_rsa_key_size_in_bits = parse_field(value)?
+                },
+                "require_low_s" => policy.require_low_s = parse_field(value)?,
+                "banned_algorithms" => {
+                    policy.banned_algorithms = parse_list(value)
+                },
+                "allowed_algorithms" => {
+                    policy.allowed_algorithms = parse_list(value)
+                },
+                _ => {
+                    // Unknown fields are rejected instead of being silently
+                    // ignored by older services.
+                    wedpr_println!("Unknown crypto profile field {}", name);
+                    return Err(WedprError::FormatError);
+                },
+            }
+        }
+        match serial {
+            Some(serial) if serial > 0 => Ok(CryptoProfile { serial, policy }),
+            _ => {
+                wedpr_println!("Crypto profile serial is missing");
+                Err(WedprError::FormatError)
+            },
+        }
+    }
+
+    /// Signs the profile by the operator root key, which returns the signed
+    /// profile file content.
+    pub fn sign<S: Signature, H: Hash + AlgorithmInfo>(
+        &self,
+        root_private_key: &[u8],
+        signature: &S,
+        digest: &MessageDigest<H>,
+    ) -> Result<String, WedprError> {
+        let text = self.encode();
+        let profile_signature = signature.sign_message(
+            root_private_key,
+            text.as_bytes(),
+            digest,
+        )?;
+        let signature_hex: String = profile_signature
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        Ok(format!("{}signature={}\n", text, signature_hex))
+    }
+}
+
+fn parse_field<F: std::str::FromStr>(value: &str) -> Result<F, WedprError> {
+    match value.parse() {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Invalid crypto profile value {}", value);
+            Err(WedprError::FormatError)
+        },
+    }
+}
+
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string())
+        .collect()
+}
+
+fn decode_hex(input: &str) -> Result<Vec<u8>, WedprError> {
+    if !input.len().is_multiple_of(2) || !input.is_ascii() {
+        return Err(WedprError::DecodeError);
+    }
+    (0..input.len())
+        .step_by(2)
+        .map(|i| match u8::from_str_radix(&input[i..i + 2], 16) {
+            Ok(v) => Ok(v),
+            Err(_) => Err(WedprError::DecodeError),
+        })
+        .collect()
+}
+
+/// Verifies signed profile file content with the operator root public key,
+/// and checks that its serial number is at least min_serial.
+pub fn verify_signed_profile<S: Signature, H: Hash + AlgorithmInfo>(
+    content: &str,
+    min_serial: u64,
+    root_public_key: &[u8],
+    signature: &S,
+    digest: &MessageDigest<H>,
+) -> Result<CryptoProfile, WedprError> {
+    let index = match content.rfind(SIGNATURE_LINE_PREFIX) {
+        Some(v) => v,
+        None => {
+            wedpr_println!("Crypto profile is not signed");
+            return Err(WedprError::FormatError);
+        },
+    };
+    // The signed text includes the newline before the signature line.
+    let (text, signature_line) = content.split_at(index + 1);
+    let profile_signature = decode_hex(
+        signature_line["signature=".len()..].trim_end_matches('\n'),
+    )?;
+    if !signature.verify_message(
+        root_public_key,
+        text.as_bytes(),
+        &profile_signature,
+        digest,
+    ) {
+        wedpr_println!("Crypto profile signature is invalid");
+        return Err(WedprError::VerificationError);
+    }
+    let profile = CryptoProfile::parse(text)?;
+    if profile.serial < min_serial {
+        wedpr_println!(
+            "Crypto profile serial {} is older than {}",
+            profile.serial,
+            min_serial
+        );
+        return Err(WedprError::VerificationError);
+    }
+    Ok(profile)
+}
+
+/// Verifies signed profile file content, and applies its policy as the
+/// global policy, which rejects profiles older than the loaded one. Concurrent
+/// loads are serialized, so the newest loaded profile always stays in effect.
+pub fn load_signed_profile<S: Signature, H: Hash + AlgorithmInfo>(
+    content: &str,
+    root_public_key: &[u8],
+    signature: &S,
+    digest: &MessageDigest<H>,
+) -> Result<CryptoProfile, WedprError> {
+    let _guard = match PROFILE_LOAD_LOCK.lock() {
+        Ok(v) => v,
+        Err(e) => e.into_inner(),
+    };
+    let profile = verify_signed_profile(
+        content,
+        loaded_profile_serial(),
+        root_public_key,
+        signature,
+        digest,
+    )?;
+    set_global_policy(profile.policy.clone());
+    LOADED_PROFILE_SERIAL.store(profile.serial, Ordering::SeqCst);
+    Ok(profile)
+}
+
+/// Loads a signed profile file as by load_signed_profile.
+pub fn load_signed_profile_file<
+    P: AsRef<Path>,
+    S: Signature,
+    H: Hash + AlgorithmInfo,
+>(
+    path: P,
+    root_public_key: &[u8],
+    signature: &S,
+    digest: &MessageDigest<H>,
+) -> Result<CryptoProfile, WedprError> {
+    let content = match fs::read_to_string(path.as_ref()) {
+        Ok(v) => v,
+        Err(_) => {
+            wedpr_println!("Reading crypto profile failed");
+            return Err(WedprError::StorageError);
+        },
+    };
+    load_signed_profile(&content, root_public_key, signature, digest)
+}
+
+/// Gets the serial number of the loaded global profile, or 0 if none has
+/// been loaded.
+pub fn loaded_profile_serial() -> u64 {
+    LOADED_PROFILE_SERIAL.load(Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::AlgorithmMetadata;
+
+    #[derive(Default, Debug, Clone)]
+    struct IdentityHash {}
+
+    impl Hash for IdentityHash {
+        fn hash<T: ?Sized + AsRef<[u8]>>(&self, input: &T) -> Vec<u8> {
+            input.as_ref().to_vec()
+        }
+    }
+
+    impl AlgorithmInfo for IdentityHash {
+        fn algorithm_info() -> AlgorithmMetadata {
+            AlgorithmMetadata {
+                name: "identity",
+                oid: None,
+                security_bits: 256,
+                private_key_size: None,
+                public_key_sizes: &[],
+                signature_size: None,
+                output_size: None,
+            }
+        }
+    }
+
+    /// Identity hash named as a hash banned by the default policy.
+    #[derive(Default, Debug, Clone)]
+    struct BannedHash {}
+
+    impl Hash for BannedHash {
+        fn hash<T: ?Sized + AsRef<[u8]>>(&self, input: &T) -> Vec<u8> {
+            input.as_ref().to_vec()
+        }
+    }
+
+    impl AlgorithmInfo for BannedHash {
+        fn algorithm_info() -> AlgorithmMetadata {
+            AlgorithmMetadata {
+                name: "sha1",
+                security_bits: 80,
+                ..IdentityHash::algorithm_info()
+            }
+        }
+    }
+
+    /// Signature mock whose key pairs have the same keys, and whose
+    /// signatures are the keys followed by the signed hashes.
+    #[derive(Default, Debug, Clone)]
+    struct EchoSignature {}
+
+    impl Signature for EchoSignature {
+        fn sign<T: ?Sized + AsRef<[u8]>>(
+            &self,
+            private_key: &T,
+            msg_hash: &T,
+        ) -> Result<Vec<u8>, WedprError> {
+            Ok([private_key.as_ref(), msg_hash.as_ref()].concat())
+        }
+
+        fn verify<T: ?Sized + AsRef<[u8]>>(
+            &self,
+            public_key: &T,
+            msg_hash: &T,
+            signature: &T,
+        ) -> bool {
+            signature.as_ref()
+                == [public_key.as_ref(), msg_hash.as_ref()].concat().as_slice()
+        }
+
+        fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+            (b"root".to_vec(), b"root".to_vec())
+        }
+    }
+
+    #[test]
+    fn test_crypto_profile() {
+        let digest =
+            MessageDigest::with_domain(IdentityHash {}, CRYPTO_PROFILE_DOMAIN);
+        let profile = CryptoProfile {
+            serial: 2,
+            policy: SecurityPolicy {
+                min_security_bits: 128,
+                allowed_algorithms: vec!["sm2".to_string(), "sm3".to_string()],
+                ..SecurityPolicy::default()
+            },
+        };
+        assert_eq!(CryptoProfile::parse(&profile.encode()).unwrap(), profile);
+        let content =
+            profile.sign(b"root", &EchoSignature {}, &digest).unwrap();
+        assert!(content.starts_with(&profile.encode()));
+        assert_eq!(
+            verify_signed_profile(
+                &content,
+                2,
+                b"root",
+                &EchoSignature {},
+                &digest
+            )
+            .unwrap(),
+            profile
+        );
+        // Rollbacks, other root keys and tampered profiles are rejected.
+        assert!(verify_signed_profile(
+            &content,
+            3,
+            b"root",
+            &EchoSignature {},
+            &digest
+        )
+        .is_err());
+        assert!(verify_signed_profile(
+            &content,
+            0,
+            b"other",
+            &EchoSignature {},
+            &digest
+        )
+        .is_err());
+        let tampered = content.replace("sm2,sm3", "sm2,sm3,md5");
+        assert!(verify_signed_profile(
+            &tampered,
+            0,
+            b"root",
+            &EchoSignature {},
+            &digest
+        )
+        .is_err());
+        assert!(verify_signed_profile(
+            &profile.encode(),
+            0,
+            b"root",
+            &EchoSignature {},
+            &digest
+        )
+        .is_err());
+        // Hashes banned by the policy are rejected.
+        let banned_digest =
+            MessageDigest::with_domain(BannedHash {}, CRYPTO_PROFILE_DOMAIN);
+        assert!(profile
+            .sign(b"root", &EchoSignature {}, &banned_digest)
+            .is_err());
+        assert!(verify_signed_profile(
+            &content,
+            0,
+            b"root",
+            &EchoSignature {},
+            &banned_digest
+        )
+        .is_err());
+
+        let parsed = CryptoProfile::parse(
+            &[
+                "wedpr-crypto-profile v1\n",
+                "# comment\n",
+                "serial=1\n",
+                "banned_algorithms=\n",
+            ]
+            .concat(),
+        )
+        .unwrap();
+        assert_eq!(parsed.serial, 1);
+        assert!(parsed.policy.banned_algorithms.is_empty());
+        assert!(CryptoProfile::parse("wedpr-crypto-profile v1\n").is_err());
+        assert!(CryptoProfile::parse(
+            "wedpr-crypto-profile v1\nserial=1\nunknown=1\n"
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,424 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! WeDPR constant-time modular arithmetic of fixed-width big integers, for
+//! scalars of curves, Paillier and sigma protocols.
+//!
+//! Unlike num-bigint, whose running time depends on the values, operations
+//! here run in Montgomery form on a fixed number of limbs without branching
+//! on secret data. Only the modulus and the input sizes are treated as
+//! public information.
+
+use crate::error::WedprError;
+use crypto_bigint::{
+    modular::runtime_mod::{DynResidue, DynResidueParams},
+    Integer, NonZero, RandomMod, Uint, U2048, U256, U4096,
+};
+use rand_core::OsRng;
+use subtle::{
+    Choice, ConditionallySelectable, ConstantTimeEq, ConstantTimeLess,
+};
+
+/// Field of 256-bit moduli, e.g. curve orders.
+pub type Field256 = ScalarField<{ U256::LIMBS }>;
+/// Field of 2048-bit moduli, e.g. Paillier n of 2048-bit keys.
+pub type Field2048 = ScalarField<{ U2048::LIMBS }>;
+/// Field of 4096-bit moduli, e.g. Paillier n^2 of 2048-bit keys.
+pub type Field4096 = ScalarField<{ U4096::LIMBS }>;
+/// Element of a Field256, e.g. a curve scalar.
+pub type Field256Element = FieldElement<{ U256::LIMBS }>;
+
+/// Maximum window size of exponentiation in bits.
+pub const MAX_WINDOW_SIZE_IN_BITS: usize = 8;
+
+/// Integers modulo an odd modulus of at most LIMBS limbs.
+#[derive(Debug, Clone)]
+pub struct ScalarField<const LIMBS: usize> {
+    modulus: Uint<LIMBS>,
+    params: DynResidueParams<LIMBS>,
+    size_in_bytes: usize,
+}
+
+/// Element of a ScalarField, which is always reduced.
+#[derive(Debug, Clone)]
+pub struct FieldElement<const LIMBS: usize> {
+    value: DynResidue<LIMBS>,
+    size_in_bytes: usize,
+}
+
+impl<const LIMBS: usize> ScalarField<LIMBS> {
+    /// Creates a field of a big-endian encoded modulus, which must be odd
+    /// and greater than 1 for Montgomery arithmetic.
+    pub fn new(modulus: &[u8]) -> Result<Self, WedprError> {
+        let modulus_value = to_uint::<LIMBS>(modulus)?;
+        if !bool::from(modulus_value.is_odd())
+            || modulus_value == Uint::<LIMBS>::ONE
+        {
+            wedpr_println!("Modulus must be odd and greater than 1");
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(ScalarField {
+            modulus: modulus_value,
+            params: DynResidueParams::new(&modulus_value),
+            size_in_bytes: modulus_value.bits().div_ceil(8),
+        })
+    }
+
+    /// Gets the size of encoded elements in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        self.size_in_bytes
+    }
+
+    /// Gets the element of a small value.
+    pub fn element_from_u64(&self, value: u64) -> FieldElement<LIMBS> {
+        self.new_element(&Uint::from_u64(value))
+    }
+
+    /// Decodes a big-endian encoded element, which fails if the value is not
+    /// less than the modulus.
+    pub fn decode(
+        &self,
+        input: &[u8],
+    ) -> Result<FieldElement<LIMBS>, WedprError> {
+        let value = to_uint::<LIMBS>(input)?;
+        if !bool::from(value.ct_lt(&self.modulus)) {
+            return Err(WedprError::FormatError);
+        }
+        Ok(self.new_element(&value))
+    }
+
+    /// Decodes a big-endian encoded integer of at most LIMBS limbs, and
+    /// reduces it modulo the modulus, e.g. for hashing to a scalar.
+    pub fn decode_reduced(
+        &self,
+        input: &[u8],
+    ) -> Result<FieldElement<LIMBS>, WedprError> {
+        Ok(self.new_element(&to_uint::<LIMBS>(input)?))
+    }
+
+    /// Samples an element uniformly from [0, modulus).
+    pub fn random(&self) -> FieldElement<LIMBS> {
+        let modulus = NonZero::new(self.modulus).unwrap();
+        self.new_element(&Uint::random_mod(&mut OsRng, &modulus))
+    }
+
+    /// Samples an element uniformly from [1, modulus).
+    pub fn random_nonzero(&self) -> FieldElement<LIMBS> {
+        loop {
+            let element = self.random();
+            if !bool::from(element.is_zero()) {
+                return element;
+            }
+        }
+    }
+
+    fn new_element(&self, value: &Uint<LIMBS>) -> FieldElement<LIMBS> {
+        FieldElement {
+            value: DynResidue::new(value, self.params),
+            size_in_bytes: self.size_in_bytes,
+        }
+    }
+}
+
+impl<const LIMBS: usize> FieldElement<LIMBS> {
+    /// Computes self + other.
+    pub fn add(&self, other: &Self) -> Self {
+        self.with_value(self.value.add(&other.value))
+    }
+
+    /// Computes self - other.
+    pub fn sub(&self, other: &Self) -> Self {
+        self.with_value(self.value.sub(&other.value))
+    }
+
+    /// Computes self * other.
+    pub fn mul(&self, other: &Self) -> Self {
+        self.with_value(self.value.mul(&other.value))
+    }
+
+    /// Computes -self.
+    pub fn neg(&self) -> Self {
+        self.with_value(self.value.neg())
+    }
+
+    /// Computes self^exponent, whose running time only depends on the
+    /// number of limbs of the exponent.
+    pub fn pow(&self, exponent: &Self) -> Self {
+        self.with_value(self.value.pow(&exponent.value.retrieve()))
+    }
+
+    /// Computes self^exponent for a big-endian encoded exponent of any size
+    /// by fixed-window Montgomery exponentiation, whose running time only
+    /// depends on the exponent size and window_bits.
+    pub fn pow_bytes(
+        &self,
+        exponent: &[u8],
+        window_bits: usize,
+    ) -> Result<Self, WedprError> {
+        check_window_bits(window_bits)?;
+        let powers = power_row(self, window_bits);
+        let mut result = self.one();
+        for index in (0..window_count(exponent, window_bits)).rev() {
+            for _ in 0..window_bits {
+                result = result.with_value(result.value.square());
+            }
+            let digit = window_digit(exponent, index, window_bits);
+            result = result.mul(&select(&powers, digit));
+        }
+        Ok(result)
+    }
+
+    /// Precomputes a table of self as a fixed base for exponents of at most
+    /// exponent_size_in_bits bits, which then only takes multiplications.
+    /// The table holds ceil(exponent_size_in_bits / window_bits) rows of
+    /// 2^window_bits elements.
+    pub fn precompute(
+        &self,
+        window_bits: usize,
+        exponent_size_in_bits: usize,
+    ) -> Result<FixedBaseTable<LIMBS>, WedprError> {
+        check_window_bits(window_bits)?;
+        if exponent_size_in_bits == 0 {
+            return Err(WedprError::ArgumentError);
+        }
+        let row_count = exponent_size_in_bits.div_ceil(window_bits);
+        let mut rows = Vec::with_capacity(row_count);
+        // Row i holds powers of base_i = self^(2^(i * window_bits)).
+        let mut row_base = self.clone();
+        for _ in 0..row_count {
+            let row = power_row(&row_base, window_bits);
+            row_base = row[row.len() - 1].mul(&row_base);
+            rows.push(row);
+        }
+        Ok(FixedBaseTable { window_bits, rows })
+    }
+
+    /// Computes the inverse, which fails if self is not coprime to the
+    /// modulus. Only the result of the check is revealed by timing.
+    pub fn inverse(&self) -> Result<Self, WedprError> {
+        let (inverse, is_some) = self.value.invert();
+        if !bool::from(is_some) {
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(self.with_value(inverse))
+    }
+
+    /// Checks whether self is zero in constant time.
+    pub fn is_zero(&self) -> Choice {
+        self.value.retrieve().ct_eq(&Uint::ZERO)
+    }
+
+    /// Checks whether self equals other in constant time.
+    pub fn ct_eq(&self, other: &Self) -> Choice {
+        self.value.retrieve().ct_eq(&other.value.retrieve())
+    }
+
+    /// Encodes self as big-endian bytes of the modulus size.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut output = Vec::with_capacity(Uint::<LIMBS>::BYTES);
+        for word in self.value.retrieve().as_words().iter().rev() {
+            output.extend_from_slice(&word.to_be_bytes());
+        }
+        output.split_off(output.len() - self.size_in_bytes)
+    }
+
+    fn one(&self) -> Self {
+        self.with_value(DynResidue::one(*self.value.params()))
+    }
+
+    fn with_value(&self, value: DynResidue<LIMBS>) -> Self {
+        FieldElement {
+            value,
+            size_in_bytes: self.size_in_bytes,
+        }
+    }
+}
+
+/// Precomputed table of a fixed base for faster exponentiation, e.g. of a
+/// generator or a public key used by many operations.
+#[derive(Debug, Clone)]
+pub struct FixedBaseTable<const LIMBS: usize> {
+    window_bits: usize,
+    rows: Vec<Vec<FieldElement<LIMBS>>>,
+}
+
+impl<const LIMBS: usize> FixedBaseTable<LIMBS> {
+    /// Computes base^exponent for a big-endian encoded exponent, which fails
+    /// if the exponent exceeds the size of the table. The running time only
+    /// depends on the table size.
+    pub fn pow(
+        &self,
+        exponent: &[u8],
+    ) -> Result<FieldElement<LIMBS>, WedprError> {
+        let needed_rows = window_count(exponent, self.window_bits);
+        // Rows beyond the exponent size must all take digit 0.
+        for index in self.rows.len()..needed_rows {
+            if window_digit(exponent, index, self.window_bits) != 0 {
+                wedpr_println!("Exponent is too large for the table");
+                return Err(WedprError::ArgumentError);
+            }
+        }
+        let mut result = self.rows[0][0].clone();
+        for (index, row) in self.rows.iter().enumerate() {
+            let digit = window_digit(exponent, index, self.window_bits);
+            result = result.mul(&select(row, digit));
+        }
+        Ok(result)
+    }
+}
+
+/// Chooses the window size of exponentiation for an exponent size, which
+/// balances the table size against the number of multiplications.
+pub fn default_window_bits(exponent_size_in_bits: usize) -> usize {
+    match exponent_size_in_bits {
+        0..=32 => 2,
+        33..=256 => 4,
+        257..=1024 => 5,
+        _ => 6,
+    }
+}
+
+fn check_window_bits(window_bits: usize) -> Result<(), WedprError> {
+    if window_bits == 0 || window_bits > MAX_WINDOW_SIZE_IN_BITS {
+        wedpr_println!("Invalid window size {}", window_bits);
+        return Err(WedprError::ArgumentError);
+    }
+    Ok(())
+}
+
+/// Computes base^0, base^1, ..., base^(2^window_bits - 1).
+fn power_row<const LIMBS: usize>(
+    base: &FieldElement<LIMBS>,
+    window_bits: usize,
+) -> Vec<FieldElement<LIMBS>> {
+    let mut power = base.one();
+    let mut row = Vec::with_capacity(1 << window_bits);
+    for _ in 0..(1 << window_bits) {
+        row.push(power.clone());
+        power = power.mul(base);
+    }
+    row
+}
+
+/// Gets the number of windows of a big-endian encoded exponent.
+fn window_count(exponent: &[u8], window_bits: usize) -> usize {
+    (exponent.len() * 8).div_ceil(window_bits)
+}
+
+/// Gets the window of an exponent at index, counted from the least
+/// significant bits, without branching on the exponent content.
+fn window_digit(exponent: &[u8], index: usize, window_bits: usize) -> usize {
+    let mut digit = 0usize;
+    for offset in 0..window_bits {
+        let bit_index = index * window_bits + offset;
+        if bit_index < exponent.len() * 8 {
+            let byte = exponent[exponent.len() - 1 - bit_index / 8];
+            digit |= (((byte >> (bit_index % 8)) & 1) as usize) << offset;
+        }
+    }
+    digit
+}
+
+/// Selects table[index] by scanning every entry in constant time.
+fn select<const LIMBS: usize>(
+    table: &[FieldElement<LIMBS>],
+    index: usize,
+) -> FieldElement<LIMBS> {
+    let mut result = table[0].clone();
+    for (i, entry) in table.iter().enumerate() {
+        result.value = DynResidue::conditional_select(
+            &result.value,
+            &entry.value,
+            (i as u64).ct_eq(&(index as u64)),
+        );
+    }
+    result
+}
+
+/// Decodes a big-endian encoded integer, left-padded to LIMBS limbs.
+fn to_uint<const LIMBS: usize>(
+    input: &[u8],
+) -> Result<Uint<LIMBS>, WedprError> {
+    if input.len() > Uint::<LIMBS>::BYTES {
+        wedpr_println!("Integer is too large for {} limbs", LIMBS);
+        return Err(WedprError::ArgumentError);
+    }
+    let mut padded = vec![0u8; Uint::<LIMBS>::BYTES - input.len()];
+    padded.extend_from_slice(input);
+    Ok(Uint::from_be_slice(&padded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scalar_field() {
+        let p = 1_000_003u64;
+        let field = Field256::new(&p.to_be_bytes()).unwrap();
+        assert_eq!(field.size_in_bytes(), 3);
+        let a = field.element_from_u64(123_456);
+        let b = field.element_from_u64(999_999);
+        let value = |e: &Field256Element| {
+            e.to_bytes()
+                .iter()
+                .fold(0u64, |acc, v| (acc << 8) | *v as u64)
+        };
+        assert_eq!(value(&a.add(&b)), (123_456 + 999_999) % p);
+        assert_eq!(value(&a.sub(&b)), 123_456 + p - 999_999);
+        assert_eq!(value(&a.mul(&b)), 123_456 * 999_999 % p);
+        assert_eq!(value(&a.neg()), p - 123_456);
+        assert_eq!(value(&a.mul(&a.inverse().unwrap())), 1);
+        assert_eq!(value(&a.pow(&field.element_from_u64(p - 1))), 1);
+        assert!(bool::from(a.sub(&a).is_zero()));
+        assert!(field.element_from_u64(0).inverse().is_err());
+        assert!(bool::from(
+            field
+                .decode_reduced(&(p + 5).to_be_bytes())
+                .unwrap()
+                .ct_eq(&field.element_from_u64(5))
+        ));
+        assert!(field.decode(&(p - 1).to_be_bytes()).is_ok());
+        assert!(field.decode(&p.to_be_bytes()).is_err());
+        assert!(field.decode(&[1u8; 33]).is_err());
+        for _ in 0..16 {
+            assert!(value(&field.random_nonzero()) < p);
+        }
+
+        // Windowed and fixed-base exponentiation match the generic one.
+        let exponent = [0x12u8, 0x34, 0x56, 0x78, 0x9a];
+        let expected = a.pow_bytes(&exponent, 1).unwrap();
+        for window_bits in 2..=MAX_WINDOW_SIZE_IN_BITS {
+            assert!(bool::from(
+                a.pow_bytes(&exponent, window_bits)
+                    .unwrap()
+                    .ct_eq(&expected)
+            ));
+        }
+        assert!(bool::from(
+            a.pow(&field.element_from_u64(3906))
+                .ct_eq(&a.pow_bytes(&[0x0f, 0x42], 3).unwrap())
+        ));
+        assert!(a.pow_bytes(&exponent, 0).is_err());
+        let table = a.precompute(4, 40).unwrap();
+        assert!(bool::from(table.pow(&exponent).unwrap().ct_eq(&expected)));
+        let mut padded = vec![0u8; 3];
+        padded.extend_from_slice(&exponent);
+        assert!(bool::from(table.pow(&padded).unwrap().ct_eq(&expected)));
+        assert!(table.pow(&[1, 0, 0, 0, 0, 0]).is_err());
+        assert_eq!(value(&table.pow(&[]).unwrap()), 1);
+
+        // Even moduli are rejected, and composite moduli fail to invert
+        // elements sharing a factor.
+        assert!(Field256::new(&[0x10]).is_err());
+        assert!(Field256::new(&[0x01]).is_err());
+        let composite = Field256::new(&[15]).unwrap();
+        assert!(composite.element_from_u64(5).inverse().is_err());
+        assert_eq!(
+            composite
+                .element_from_u64(7)
+                .mul(&composite.element_from_u64(7).inverse().unwrap())
+                .to_bytes(),
+            vec![1]
+        );
+    }
+}
@@ -0,0 +1,321 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! WeDPR telemetry hooks, which report every crypto operation with its
+//! algorithm, duration and outcome to a listener, so that embedders can
+//! export metrics, e.g. to Prometheus, without patching algorithm crates.
+
+use crate::{
+    error::WedprError,
+    traits::{AlgorithmInfo, Ecies, Signature},
+};
+use std::time::{Duration, Instant};
+
+/// Kind of a reported crypto operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CryptoOperation {
+    Sign,
+    Verify,
+    BatchVerify,
+    Keygen,
+    Encrypt,
+    Decrypt,
+}
+
+/// Event of a finished crypto operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CryptoEvent {
+    pub operation: CryptoOperation,
+    /// Algorithm name as in AlgorithmMetadata.
+    pub algorithm: &'static str,
+    pub duration: Duration,
+    /// Whether the operation succeeded, i.e. returned Ok or a successful
+    /// verification.
+    pub success: bool,
+}
+
+/// Trait of a listener of crypto events, which should return quickly since
+/// it runs on the thread of the operation.
+pub trait CryptoEventListener {
+    /// Handles the event of a finished operation.
+    fn on_event(&self, event: &CryptoEvent);
+}
+
+impl<F: Fn(&CryptoEvent)> CryptoEventListener for F {
+    fn on_event(&self, event: &CryptoEvent) {
+        self(event)
+    }
+}
+
+fn observe<R, L: CryptoEventListener, F: FnOnce() -> R>(
+    listener: &L,
+    operation: CryptoOperation,
+    algorithm: &'static str,
+    is_success: fn(&R) -> bool,
+    run: F,
+) -> R {
+    let start = Instant::now();
+    let result = run();
+    listener.on_event(&CryptoEvent {
+        operation,
+        algorithm,
+        duration: start.elapsed(),
+        success: is_success(&result),
+    });
+    result
+}
+
+fn is_ok<V>(result: &Result<V, WedprError>) -> bool {
+    result.is_ok()
+}
+
+/// Wraps a Signature instance to report its operations to a listener.
+#[derive(Debug, Clone)]
+pub struct ObservedSignature<
+    S: Signature + AlgorithmInfo,
+    L: CryptoEventListener,
+> {
+    pub signature: S,
+    pub listener: L,
+}
+
+impl<S: Signature + AlgorithmInfo, L: CryptoEventListener>
+    ObservedSignature<S, L>
+{
+    /// Creates an observed Signature instance.
+    pub fn new(signature: S, listener: L) -> Self {
+        ObservedSignature {
+            signature,
+            listener,
+        }
+    }
+}
+
+impl<S: Signature + AlgorithmInfo, L: CryptoEventListener> Signature
+    for ObservedSignature<S, L>
+{
+    fn sign<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        observe(
+            &self.listener,
+            CryptoOperation::Sign,
+            S::algorithm_info().name,
+            is_ok,
+            || self.signature.sign(private_key, msg_hash),
+        )
+    }
+
+    fn sign_deterministic<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        observe(
+            &self.listener,
+            CryptoOperation::Sign,
+            S::algorithm_info().name,
+            is_ok,
+            || self.signature.sign_deterministic(private_key, msg_hash),
+        )
+    }
+
+    fn verify<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        msg_hash: &T,
+        signature: &T,
+    ) -> bool {
+        observe(
+            &self.listener,
+            CryptoOperation::Verify,
+            S::algorithm_info().name,
+            |v| *v,
+            || self.signature.verify(public_key, msg_hash, signature),
+        )
+    }
+
+    /// Batch verifies by the wrapped instance, which is reported as one
+    /// event, so that its faster batch algorithm is kept.
+    fn batch_verify<B: AsRef<[u8]> + Sync>(
+        &self,
+        public_keys: &[B],
+        msg_hashes: &[B],
+        signatures: &[B],
+    ) -> bool {
+        observe(
+            &self.listener,
+            CryptoOperation::BatchVerify,
+            S::algorithm_info().name,
+            |v| *v,
+            || {
+                self.signature
+                    .batch_verify(public_keys, msg_hashes, signatures)
+            },
+        )
+    }
+
+    fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+        observe(
+            &self.listener,
+            CryptoOperation::Keygen,
+            S::algorithm_info().name,
+            |_| true,
+            || self.signature.generate_keypair(),
+        )
+    }
+
+    fn try_generate_keypair(&self) -> Result<(Vec<u8>, Vec<u8>), WedprError> {
+        observe(
+            &self.listener,
+            CryptoOperation::Keygen,
+            S::algorithm_info().name,
+            is_ok,
+            || self.signature.try_generate_keypair(),
+        )
+    }
+}
+
+/// Wraps an Ecies instance to report its operations to a listener.
+#[derive(Debug, Clone)]
+pub struct ObservedEcies<E: Ecies + AlgorithmInfo, L: CryptoEventListener> {
+    pub ecies: E,
+    pub listener: L,
+}
+
+impl<E: Ecies + AlgorithmInfo, L: CryptoEventListener> ObservedEcies<E, L> {
+    /// Creates an observed Ecies instance.
+    pub fn new(ecies: E, listener: L) -> Self {
+        ObservedEcies { ecies, listener }
+    }
+}
+
+impl<E: Ecies + AlgorithmInfo, L: CryptoEventListener> Ecies
+    for ObservedEcies<E, L>
+{
+    fn encrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        message: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        observe(
+            &self.listener,
+            CryptoOperation::Encrypt,
+            E::algorithm_info().name,
+            is_ok,
+            || self.ecies.encrypt(public_key, message),
+        )
+    }
+
+    fn decrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        ciphertext: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        observe(
+            &self.listener,
+            CryptoOperation::Decrypt,
+            E::algorithm_info().name,
+            is_ok,
+            || self.ecies.decrypt(private_key, ciphertext),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::AlgorithmMetadata;
+    use std::sync::Mutex;
+
+    /// Signature and Ecies instance which works on bytes as they are.
+    #[derive(Default, Debug)]
+    struct IdentityAlgorithm {}
+
+    impl AlgorithmInfo for IdentityAlgorithm {
+        fn algorithm_info() -> AlgorithmMetadata {
+            AlgorithmMetadata {
+                name: "identity",
+                oid: None,
+                security_bits: 0,
+                private_key_size: None,
+                public_key_sizes: &[],
+                signature_size: None,
+                output_size: None,
+            }
+        }
+    }
+
+    impl Signature for IdentityAlgorithm {
+        fn sign<T: ?Sized + AsRef<[u8]>>(
+            &self,
+            _private_key: &T,
+            msg_hash: &T,
+        ) -> Result<Vec<u8>, WedprError> {
+            Ok(msg_hash.as_ref().to_vec())
+        }
+
+        fn verify<T: ?Sized + AsRef<[u8]>>(
+            &self,
+            _public_key: &T,
+            msg_hash: &T,
+            signature: &T,
+        ) -> bool {
+            msg_hash.as_ref() == signature.as_ref()
+        }
+
+        fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+            (vec![1], vec![2])
+        }
+    }
+
+    impl Ecies for IdentityAlgorithm {
+        fn encrypt<T: ?Sized + AsRef<[u8]>>(
+            &self,
+            _public_key: &T,
+            message: &T,
+        ) -> Result<Vec<u8>, WedprError> {
+            Ok(message.as_ref().to_vec())
+        }
+
+        fn decrypt<T: ?Sized + AsRef<[u8]>>(
+            &self,
+            _private_key: &T,
+            _ciphertext: &T,
+        ) -> Result<Vec<u8>, WedprError> {
+            Err(WedprError::DecodeError)
+        }
+    }
+
+    #[test]
+    fn test_observed_operations() {
+        let events = Mutex::new(Vec::new());
+        let listener = |event: &CryptoEvent| {
+            events.lock().unwrap().push((
+                event.operation,
+                event.algorithm,
+                event.success,
+            ))
+        };
+        let signature =
+            ObservedSignature::new(IdentityAlgorithm::default(), &listener);
+        let (public_key, private_key) = signature.generate_keypair();
+        let msg_hash = vec![7u8; 32];
+        let signature_bytes = signature.sign(&private_key, &msg_hash).unwrap();
+        assert!(signature.verify(&public_key, &msg_hash, &signature_bytes));
+        assert!(!signature.verify(&public_key, &msg_hash, &public_key));
+        let ecies = ObservedEcies::new(IdentityAlgorithm::default(), &listener);
+        assert!(ecies.encrypt(&public_key, &msg_hash).is_ok());
+        assert!(ecies.decrypt(&private_key, &msg_hash).is_err());
+
+        assert_eq!(*events.lock().unwrap(), vec![
+            (CryptoOperation::Keygen, "identity", true),
+            (CryptoOperation::Sign, "identity", true),
+            (CryptoOperation::Verify, "identity", true),
+            (CryptoOperation::Verify, "identity", false),
+            (CryptoOperation::Encrypt, "identity", true),
+            (CryptoOperation::Decrypt, "identity", false),
+        ]);
+    }
+}
@@ -2,7 +2,17 @@
 
 //! WeDPR simple tool functions.
 
+use rand_core::{OsRng, RngCore};
+
 /// Decodes an string to a bytes vector without decoding.
 pub fn string_to_bytes_utf8(message: &str) -> Vec<u8> {
     message.as_bytes().to_vec()
 }
+
+/// Generates random bytes of the given size from the OS random source, e.g.
+/// for keys, nonces and salts.
+pub fn random_bytes(size: usize) -> Vec<u8> {
+    let mut output = vec![0u8; size];
+    OsRng.fill_bytes(&mut output);
+    output
+}
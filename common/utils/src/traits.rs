@@ -2,7 +2,19 @@
 
 //! WeDPR traits definitions for replaceable algorithms.
 
-use crate::error::WedprError;
+use subtle::ConstantTimeEq;
+
+use crate::{
+    algorithm::AlgorithmMetadata, digest::MessageDigest, error::WedprError,
+    policy::check_global_algorithm,
+};
+
+/// Trait of algorithm metadata introspection, so that generic tooling can
+/// present and validate artifacts without hard-coded tables.
+pub trait AlgorithmInfo {
+    /// Gets the metadata of the algorithm.
+    fn algorithm_info() -> AlgorithmMetadata;
+}
 
 /// Trait of a replaceable hash algorithm.
 pub trait Hash {
@@ -11,6 +23,51 @@ pub trait Hash {
     fn hash<T: ?Sized + AsRef<[u8]>>(&self, input: &T) -> Vec<u8>;
 }
 
+/// Trait of an incremental hash computation, so that large inputs can be
+/// hashed chunk by chunk without buffering them in memory.
+pub trait HashStream {
+    /// Feeds a chunk of input.
+    fn update<T: ?Sized + AsRef<[u8]>>(&mut self, input: &T);
+
+    /// Consumes the stream and gets the hash of all fed chunks.
+    fn finalize(self) -> Vec<u8>;
+}
+
+/// Trait of a hash algorithm supporting incremental hashing, whose streams
+/// produce the same hash as hashing the concatenated chunks at once.
+pub trait StreamingHash: Hash {
+    type Stream: HashStream;
+
+    /// Starts a new hash stream.
+    fn stream(&self) -> Self::Stream;
+
+    /// Hashes all data of a reader, e.g. a large file.
+    fn hash_reader<R: std::io::Read>(
+        &self,
+        reader: &mut R,
+    ) -> Result<Vec<u8>, WedprError> {
+        let mut stream = self.stream();
+        let mut buffer = [0u8; HASH_READER_BUFFER_SIZE];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => stream.update(&buffer[..n]),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+                    continue
+                },
+                Err(_) => {
+                    wedpr_println!("Reading hash input failed");
+                    return Err(WedprError::StorageError);
+                },
+            }
+        }
+        Ok(stream.finalize())
+    }
+}
+
+/// Buffer size of StreamingHash::hash_reader.
+const HASH_READER_BUFFER_SIZE: usize = 64 * 1024;
+
 /// Trait of a replaceable coder algorithm.
 pub trait Coder {
     /// Converts bytes to an encoded string.
@@ -37,6 +94,135 @@ pub trait Ecies {
     ) -> Result<Vec<u8>, WedprError>;
 }
 
+/// Trait of a replaceable block cipher algorithm.
+pub trait BlockCipher {
+    /// Encrypts a message with a symmetric key and an initialization vector
+    /// (IV).
+    fn encrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        message: &T,
+        key: &T,
+        iv: &T,
+    ) -> Result<Vec<u8>, WedprError>;
+
+    /// Decrypts a ciphertext with a symmetric key and an initialization
+    /// vector (IV).
+    fn decrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        ciphertext: &T,
+        key: &T,
+        iv: &T,
+    ) -> Result<Vec<u8>, WedprError>;
+}
+
+/// Trait of a replaceable authenticated encryption with associated data
+/// (AEAD) algorithm, whose BlockCipher functions take empty associated data
+/// and the IV as the nonce.
+pub trait Aead: BlockCipher {
+    /// Encrypts a message with a symmetric key and a nonce, where the
+    /// associated data (AAD) is authenticated but not encrypted.
+    fn encrypt_with_aad<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        message: &T,
+        key: &T,
+        nonce: &T,
+        aad: &T,
+    ) -> Result<Vec<u8>, WedprError>;
+
+    /// Decrypts a ciphertext with a symmetric key, a nonce and the AAD used
+    /// in encryption, which fails if authentication fails.
+    fn decrypt_with_aad<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        ciphertext: &T,
+        key: &T,
+        nonce: &T,
+        aad: &T,
+    ) -> Result<Vec<u8>, WedprError>;
+
+    /// Generates a random symmetric key.
+    fn generate_key(&self) -> Vec<u8>;
+
+    /// Generates a random nonce, which must never be reused with a key.
+    fn generate_nonce(&self) -> Vec<u8>;
+}
+
+/// Trait of a replaceable message authentication code (MAC) algorithm.
+pub trait Mac {
+    /// Computes the authentication tag of a message with a symmetric key.
+    fn mac<T: ?Sized + AsRef<[u8]>>(&self, key: &T, message: &T) -> Vec<u8>;
+
+    /// Verifies the authentication tag of a message in constant time.
+    fn verify_mac<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        key: &T,
+        message: &T,
+        tag: &T,
+    ) -> bool {
+        let expected = self.mac(key, message);
+        expected.len() == tag.as_ref().len()
+            && bool::from(expected.ct_eq(tag.as_ref()))
+    }
+}
+
+/// Trait of a replaceable key derivation function (KDF).
+pub trait Kdf {
+    /// Derives a key of output_size bytes from input keying material, a salt
+    /// and context info, where different info gives independent keys.
+    fn derive_key<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        input_key: &T,
+        salt: &T,
+        info: &T,
+        output_size: usize,
+    ) -> Result<Vec<u8>, WedprError>;
+}
+
+/// Trait of a replaceable homomorphic encryption scheme, which supports
+/// adding ciphertexts and multiplying them by plaintext scalars.
+pub trait HomomorphicEncryption {
+    type PublicKey;
+    type PrivateKey;
+    type Plaintext;
+    type Ciphertext;
+    type Scalar;
+
+    /// Generates a new key pair, where the first part is public key, the
+    /// second part is private key.
+    fn generate_keypair(
+        &self,
+    ) -> Result<(Self::PublicKey, Self::PrivateKey), WedprError>;
+
+    /// Encrypts a plaintext with the public key.
+    fn encrypt(
+        &self,
+        public_key: &Self::PublicKey,
+        message: &Self::Plaintext,
+    ) -> Result<Self::Ciphertext, WedprError>;
+
+    /// Decrypts a ciphertext with the private key.
+    fn decrypt(
+        &self,
+        private_key: &Self::PrivateKey,
+        ciphertext: &Self::Ciphertext,
+    ) -> Result<Self::Plaintext, WedprError>;
+
+    /// Computes a ciphertext of the sum of two plaintexts.
+    fn add(
+        &self,
+        public_key: &Self::PublicKey,
+        ciphertext1: &Self::Ciphertext,
+        ciphertext2: &Self::Ciphertext,
+    ) -> Result<Self::Ciphertext, WedprError>;
+
+    /// Computes a ciphertext of the product of a plaintext and a scalar.
+    fn mul_scalar(
+        &self,
+        public_key: &Self::PublicKey,
+        ciphertext: &Self::Ciphertext,
+        scalar: &Self::Scalar,
+    ) -> Result<Self::Ciphertext, WedprError>;
+}
+
 /// Trait of a replaceable signature algorithm.
 pub trait Signature {
     /// Signs a message hash with the private key.
@@ -46,6 +232,20 @@ pub trait Signature {
         msg_hash: &T,
     ) -> Result<Vec<u8>, WedprError>;
 
+    /// Signs a message hash with the private key deterministically, where
+    /// the nonce is derived from the private key and the message hash by the
+    /// algorithm, e.g. per RFC 6979, so that signing the same message hash
+    /// with the same key always outputs the same signature. The default
+    /// implementation fails for algorithms without deterministic signing.
+    fn sign_deterministic<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        _private_key: &T,
+        _msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        wedpr_println!("Deterministic signing is not supported");
+        Err(WedprError::ArgumentError)
+    }
+
     /// Verifies a message hash with the public key.
     fn verify<T: ?Sized + AsRef<[u8]>>(
         &self,
@@ -54,11 +254,72 @@ pub trait Signature {
         signature: &T,
     ) -> bool;
 
+    /// Verifies multiple message hashes with their public keys and
+    /// signatures, which succeeds only if all of them are valid. The default
+    /// implementation verifies them one by one, which may be replaced by a
+    /// faster batch algorithm.
+    fn batch_verify<S: AsRef<[u8]> + Sync>(
+        &self,
+        public_keys: &[S],
+        msg_hashes: &[S],
+        signatures: &[S],
+    ) -> bool {
+        if public_keys.len() != msg_hashes.len()
+            || public_keys.len() != signatures.len()
+        {
+            wedpr_println!("Batch verification inputs have different lengths");
+            return false;
+        }
+        public_keys.iter().zip(msg_hashes).zip(signatures).all(
+            |((public_key, msg_hash), signature)| {
+                self.verify(
+                    public_key.as_ref(),
+                    msg_hash.as_ref(),
+                    signature.as_ref(),
+                )
+            },
+        )
+    }
+
     /// Generates a new key pair for signature algorithm,
     /// where the first part is public key,
     /// the second part is private key.
     // TODO: Replace output list with a struct or protobuf.
     fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>);
+
+    /// Generates a new key pair as by generate_keypair, which fails instead
+    /// for algorithms whose keys are managed elsewhere, e.g. inside HSMs.
+    fn try_generate_keypair(&self) -> Result<(Vec<u8>, Vec<u8>), WedprError> {
+        Ok(self.generate_keypair())
+    }
+
+    /// Signs a raw message, which is digested by the configured digest first,
+    /// and fails if the hash algorithm is not allowed by the global policy.
+    fn sign_message<H: Hash + AlgorithmInfo>(
+        &self,
+        private_key: &[u8],
+        message: &[u8],
+        digest: &MessageDigest<H>,
+    ) -> Result<Vec<u8>, WedprError> {
+        check_global_algorithm::<H>()?;
+        self.sign(private_key, &digest.digest(message)[..])
+    }
+
+    /// Verifies a raw message, which is digested by the configured digest
+    /// first, and fails if the hash algorithm is not allowed by the global
+    /// policy.
+    fn verify_message<H: Hash + AlgorithmInfo>(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+        digest: &MessageDigest<H>,
+    ) -> bool {
+        if check_global_algorithm::<H>().is_err() {
+            return false;
+        }
+        self.verify(public_key, &digest.digest(message)[..], signature)
+    }
 }
 
 /// Trait of a replaceable verifiable random function (VRF) algorithm, which is
@@ -0,0 +1,325 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! WeDPR verification cache for long-running services, e.g. gateway nodes
+//! which re-verify identical gossip signatures or proofs many times.
+//!
+//! Only successful verifications are cached, so that invalid inputs cannot
+//! evict valid entries by flooding the cache.
+
+use crate::{error::WedprError, traits::Signature};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy)]
+struct CacheEntry {
+    inserted_at: Instant,
+    last_used: u64,
+}
+
+/// LRU cache of successfully verified inputs, where each entry also expires
+/// after a time to live (TTL).
+#[derive(Debug, Clone)]
+pub struct VerificationCache {
+    capacity: usize,
+    ttl: Duration,
+    entries: HashMap<Vec<u8>, CacheEntry>,
+    /// Cache keys ordered by their last use, the least recent first.
+    recency: BTreeMap<u64, Vec<u8>>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl VerificationCache {
+    /// Creates a cache holding at most capacity entries, each of which
+    /// expires after ttl.
+    pub fn new(capacity: usize, ttl: Duration) -> Result<Self, WedprError> {
+        if capacity == 0 {
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(VerificationCache {
+            capacity,
+            ttl,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            hits: 0,
+            misses: 0,
+        })
+    }
+
+    /// Builds a cache key from multiple parts, e.g. (public key, message
+    /// hash, signature), or a single proof digest.
+    pub fn key(parts: &[&[u8]]) -> Vec<u8> {
+        let mut key = Vec::new();
+        for part in parts {
+            // Length prefixes keep the key unambiguous.
+            key.extend_from_slice(&(part.len() as u32).to_be_bytes());
+            key.extend_from_slice(part);
+        }
+        key
+    }
+
+    /// Checks whether a key has been verified and is not expired, and marks
+    /// it as recently used if so.
+    pub fn contains(&mut self, key: &[u8]) -> bool {
+        let entry = match self.entries.get(key) {
+            Some(v) => *v,
+            None => {
+                self.misses += 1;
+                return false;
+            },
+        };
+        self.recency.remove(&entry.last_used);
+        if entry.inserted_at.elapsed() >= self.ttl {
+            self.entries.remove(key);
+            self.misses += 1;
+            return false;
+        }
+        self.tick += 1;
+        self.recency.insert(self.tick, key.to_vec());
+        if let Some(v) = self.entries.get_mut(key) {
+            v.last_used = self.tick;
+        }
+        self.hits += 1;
+        true
+    }
+
+    /// Records a key as successfully verified, evicting the least recently
+    /// used entry if the cache is full.
+    pub fn insert(&mut self, key: &[u8]) {
+        if let Some(v) = self.entries.remove(key) {
+            self.recency.remove(&v.last_used);
+        }
+        while self.entries.len() >= self.capacity {
+            let oldest_tick = match self.recency.keys().next() {
+                Some(v) => *v,
+                None => break,
+            };
+            if let Some(oldest_key) = self.recency.remove(&oldest_tick) {
+                self.entries.remove(&oldest_key);
+            }
+        }
+        self.tick += 1;
+        self.recency.insert(self.tick, key.to_vec());
+        self.entries.insert(key.to_vec(), CacheEntry {
+            inserted_at: Instant::now(),
+            last_used: self.tick,
+        });
+    }
+
+    /// Verifies a key with the given function unless it is already cached,
+    /// and caches it if the verification succeeds.
+    pub fn verify_with<F: FnOnce() -> bool>(
+        &mut self,
+        key: &[u8],
+        verify: F,
+    ) -> bool {
+        if self.contains(key) {
+            return true;
+        }
+        let result = verify();
+        if result {
+            self.insert(key);
+        }
+        result
+    }
+
+    /// Gets the number of cached entries, including expired ones which have
+    /// not been visited yet.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes all cached entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    /// Gets the numbers of cache hits and misses so far.
+    pub fn stats(&self) -> (u64, u64) {
+        (self.hits, self.misses)
+    }
+}
+
+/// Wraps a Signature instance to cache its successful verifications, which
+/// can be shared between threads of a verification service.
+#[derive(Debug)]
+pub struct CachedSignature<S: Signature> {
+    pub signature: S,
+    cache: Mutex<VerificationCache>,
+}
+
+impl<S: Signature> CachedSignature<S> {
+    /// Creates a cached Signature instance with a cache of the given capacity
+    /// and TTL.
+    pub fn new(
+        signature: S,
+        capacity: usize,
+        ttl: Duration,
+    ) -> Result<Self, WedprError> {
+        Ok(CachedSignature {
+            signature,
+            cache: Mutex::new(VerificationCache::new(capacity, ttl)?),
+        })
+    }
+
+    /// Gets the numbers of cache hits and misses so far.
+    pub fn stats(&self) -> (u64, u64) {
+        match self.cache.lock() {
+            Ok(v) => v.stats(),
+            Err(_) => (0, 0),
+        }
+    }
+}
+
+impl<S: Signature> Signature for CachedSignature<S> {
+    fn sign<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        self.signature.sign(private_key, msg_hash)
+    }
+
+    fn sign_deterministic<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        self.signature.sign_deterministic(private_key, msg_hash)
+    }
+
+    fn verify<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        msg_hash: &T,
+        signature: &T,
+    ) -> bool {
+        let key = VerificationCache::key(&[
+            public_key.as_ref(),
+            msg_hash.as_ref(),
+            signature.as_ref(),
+        ]);
+        // The lock is not held during verification, so that concurrent
+        // verifications of different inputs are not serialized.
+        if let Ok(mut v) = self.cache.lock() {
+            if v.contains(&key) {
+                return true;
+            }
+        }
+        let result = self.signature.verify(public_key, msg_hash, signature);
+        if result {
+            if let Ok(mut v) = self.cache.lock() {
+                v.insert(&key);
+            }
+        }
+        result
+    }
+
+    fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+        self.signature.generate_keypair()
+    }
+
+    fn try_generate_keypair(&self) -> Result<(Vec<u8>, Vec<u8>), WedprError> {
+        self.signature.try_generate_keypair()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Signature instance whose signature is the message hash itself.
+    #[derive(Default, Debug)]
+    struct CountingSignature {
+        verify_count: AtomicUsize,
+    }
+
+    impl Signature for CountingSignature {
+        fn sign<T: ?Sized + AsRef<[u8]>>(
+            &self,
+            _private_key: &T,
+            msg_hash: &T,
+        ) -> Result<Vec<u8>, WedprError> {
+            Ok(msg_hash.as_ref().to_vec())
+        }
+
+        fn verify<T: ?Sized + AsRef<[u8]>>(
+            &self,
+            _public_key: &T,
+            msg_hash: &T,
+            signature: &T,
+        ) -> bool {
+            self.verify_count.fetch_add(1, Ordering::Relaxed);
+            msg_hash.as_ref() == signature.as_ref()
+        }
+
+        fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+            (vec![1], vec![2])
+        }
+    }
+
+    #[test]
+    fn test_verification_cache() {
+        let mut cache =
+            VerificationCache::new(2, Duration::from_secs(60)).unwrap();
+        assert!(cache.verify_with(b"a", || true));
+        assert!(cache.verify_with(b"b", || true));
+        // Cached keys are not verified again.
+        assert!(cache.verify_with(b"a", || false));
+        // Evicts the least recently used key "b".
+        assert!(!cache.verify_with(b"c", || false));
+        assert!(cache.verify_with(b"d", || true));
+        assert!(cache.contains(b"a"));
+        assert!(!cache.contains(b"b"));
+        assert_eq!(cache.len(), 2);
+
+        let mut expired_cache =
+            VerificationCache::new(2, Duration::from_secs(0)).unwrap();
+        expired_cache.insert(b"a");
+        assert!(!expired_cache.contains(b"a"));
+        assert!(expired_cache.is_empty());
+        assert!(VerificationCache::new(0, Duration::from_secs(60)).is_err());
+    }
+
+    #[test]
+    fn test_cached_signature() {
+        let cached_signature = CachedSignature::new(
+            CountingSignature::default(),
+            16,
+            Duration::from_secs(60),
+        )
+        .unwrap();
+        let (public_key, private_key) = cached_signature.generate_keypair();
+        let msg_hash = vec![7u8; 32];
+        let signature = cached_signature.sign(&private_key, &msg_hash).unwrap();
+        for _ in 0..10 {
+            assert!(cached_signature.verify(
+                &public_key,
+                &msg_hash,
+                &signature
+            ));
+        }
+        assert!(!cached_signature.verify(&public_key, &msg_hash, &public_key));
+        assert!(!cached_signature.verify(&public_key, &msg_hash, &public_key));
+        assert_eq!(
+            cached_signature
+                .signature
+                .verify_count
+                .load(Ordering::Relaxed),
+            3
+        );
+        assert_eq!(cached_signature.stats(), (9, 3));
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Bech32 encoding functions of BIP-173 for age keys, without the length
+//! limit of 90 characters.
+
+use wedpr_l_utils::error::WedprError;
+
+const BECH32_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_GENERATORS: [u32; 5] =
+    [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+const BECH32_CHECKSUM_SIZE: usize = 6;
+
+/// Encodes data with a lowercase human-readable part.
+pub fn encode(hrp: &str, data: &[u8]) -> String {
+    let values = convert_bits(data, 8, 5, true).unwrap_or_default();
+    let mut checksum_input = expand_hrp(hrp);
+    checksum_input.extend_from_slice(&values);
+    checksum_input.extend_from_slice(&[0u8; BECH32_CHECKSUM_SIZE]);
+    let checksum = polymod(&checksum_input) ^ 1;
+    let mut output = format!("{}1", hrp);
+    for value in values.iter().cloned().chain(
+        (0..BECH32_CHECKSUM_SIZE)
+            .map(|i| ((checksum >> (5 * (5 - i))) & 0x1f) as u8),
+    ) {
+        output.push(BECH32_CHARSET[value as usize] as char);
+    }
+    output
+}
+
+/// Decodes a string of a single case to its lowercase human-readable part
+/// and data.
+pub fn decode(input: &str) -> Result<(String, Vec<u8>), WedprError> {
+    let lowercase = input.to_lowercase();
+    if lowercase != input && input.to_uppercase() != input {
+        wedpr_println!("Bech32 string has mixed cases");
+        return Err(WedprError::DecodeError);
+    }
+    let separator = match lowercase.rfind('1') {
+        Some(v) if v > 0 && v + 1 + BECH32_CHECKSUM_SIZE <= lowercase.len() => {
+            v
+        },
+        _ => return Err(WedprError::DecodeError),
+    };
+    let hrp = &lowercase[..separator];
+    if hrp.bytes().any(|byte| !(33..=126).contains(&byte)) {
+        return Err(WedprError::DecodeError);
+    }
+    let mut values = Vec::new();
+    for byte in lowercase[separator + 1..].bytes() {
+        match BECH32_CHARSET.iter().position(|c| *c == byte) {
+            Some(v) => values.push(v as u8),
+            None => return Err(WedprError::DecodeError),
+        }
+    }
+    let mut checksum_input = expand_hrp(hrp);
+    checksum_input.extend_from_slice(&values);
+    if polymod(&checksum_input) != 1 {
+        wedpr_println!("Bech32 checksum mismatched");
+        return Err(WedprError::DecodeError);
+    }
+    values.truncate(values.len() - BECH32_CHECKSUM_SIZE);
+    let data = convert_bits(&values, 5, 8, false)?;
+    Ok((hrp.to_string(), data))
+}
+
+fn polymod(values: &[u8]) -> u32 {
+    let mut checksum = 1u32;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ffffff) << 5) ^ (*value as u32);
+        for (i, generator) in BECH32_GENERATORS.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+fn expand_hrp(hrp: &str) -> Vec<u8> {
+    let mut output: Vec<u8> = hrp.bytes().map(|byte| byte >> 5).collect();
+    output.push(0);
+    output.extend(hrp.bytes().map(|byte| byte & 0x1f));
+    output
+}
+
+/// Regroups bits of values, where the padding bits should be zeros when
+/// decoding.
+fn convert_bits(
+    values: &[u8],
+    from_bits: u32,
+    to_bits: u32,
+    pad: bool,
+) -> Result<Vec<u8>, WedprError> {
+    let mut accumulator = 0u32;
+    let mut bits = 0u32;
+    let max_value = (1u32 << to_bits) - 1;
+    let mut output = Vec::new();
+    for value in values {
+        accumulator = (accumulator << from_bits) | (*value as u32);
+        bits += from_bits;
+        while bits >= to_bits {
+            bits -= to_bits;
+            output.push(((accumulator >> bits) & max_value) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            output.push(((accumulator << (to_bits - bits)) & max_value) as u8);
+        }
+    } else if bits >= from_bits
+        || (accumulator << (to_bits - bits)) & max_value != 0
+    {
+        wedpr_println!("Invalid bech32 padding");
+        return Err(WedprError::DecodeError);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bech32() {
+        // Valid checksum test vectors of BIP-173.
+        assert_eq!(decode("A12UEL5L").unwrap(), ("a".to_string(), vec![]));
+        assert_eq!(
+            decode("abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw").unwrap(),
+            ("abcdef".to_string(), vec![
+                0x00, 0x44, 0x32, 0x14, 0xc7, 0x42, 0x54, 0xb6, 0x35, 0xcf,
+                0x84, 0x65, 0x3a, 0x56, 0xd7, 0xc6, 0x75, 0xbe, 0x77, 0xdf
+            ])
+        );
+        assert_eq!(encode("a", &[]), "a12uel5l");
+        assert!(decode("a12UEL5L").is_err());
+        assert!(decode("a12uel5m").is_err());
+
+        let encoded = encode("age", &[7u8; 32]);
+        assert_eq!(decode(&encoded).unwrap(), ("age".to_string(), vec![7; 32]));
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! age header functions of recipient stanzas and the header MAC line.
+
+use wedpr_l_utils::error::WedprError;
+
+/// Version line of the age v1 format.
+pub const AGE_VERSION_LINE: &str = "age-encryption.org/v1";
+
+const STANZA_PREFIX: &str = "-> ";
+const MAC_PREFIX: &str = "---";
+/// Number of base64 characters of each full stanza body line.
+const STANZA_BODY_LINE_SIZE: usize = 64;
+
+/// Recipient stanza of a type, arguments and a body.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct AgeStanza {
+    pub stanza_type: String,
+    pub arguments: Vec<String>,
+    pub body: Vec<u8>,
+}
+
+/// Parsed header, whose MAC is computed over the header text up to and
+/// including the "---" of the MAC line.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct AgeHeader {
+    pub stanzas: Vec<AgeStanza>,
+    pub mac_input: Vec<u8>,
+    pub mac: Vec<u8>,
+}
+
+/// Encodes bytes in the canonical base64 of age without padding.
+pub fn encode_base64(input: &[u8]) -> String {
+    base64::encode_config(input, base64::STANDARD_NO_PAD)
+}
+
+/// Decodes canonical base64 without padding.
+pub fn decode_base64(input: &str) -> Result<Vec<u8>, WedprError> {
+    match base64::decode_config(input, base64::STANDARD_NO_PAD) {
+        Ok(v) if encode_base64(&v) == input => Ok(v),
+        _ => {
+            wedpr_println!("Invalid age base64 encoding");
+            Err(WedprError::DecodeError)
+        },
+    }
+}
+
+/// Encodes the version line and the stanzas as the MAC input, which ends
+/// with "---".
+pub fn encode_mac_input(stanzas: &[AgeStanza]) -> Vec<u8> {
+    let mut output = format!("{}\n", AGE_VERSION_LINE);
+    for stanza in stanzas {
+        output.push_str(STANZA_PREFIX);
+        output.push_str(&stanza.stanza_type);
+        for argument in &stanza.arguments {
+            output.push(' ');
+            output.push_str(argument);
+        }
+        output.push('\n');
+        let body = encode_base64(&stanza.body);
+        // The last line is always shorter than a full line, so it may be
+        // empty.
+        let mut offset = 0;
+        loop {
+            let end = std::cmp::min(offset + STANZA_BODY_LINE_SIZE, body.len());
+            output.push_str(&body[offset..end]);
+            output.push('\n');
+            if end - offset < STANZA_BODY_LINE_SIZE {
+                break;
+            }
+            offset = end;
+        }
+    }
+    output.push_str(MAC_PREFIX);
+    output.into_bytes()
+}
+
+/// Parses a header from the start of a file, which returns the header and
+/// the size of the header in bytes.
+pub fn parse_header(input: &[u8]) -> Result<(AgeHeader, usize), WedprError> {
+    let mut reader = LineReader { input, offset: 0 };
+    if reader.read_line()? != AGE_VERSION_LINE {
+        wedpr_println!("Unsupported age version");
+        return Err(WedprError::FormatError);
+    }
+    let mut stanzas = Vec::new();
+    loop {
+        let line_offset = reader.offset;
+        let line = reader.read_line()?;
+        if line.starts_with(MAC_PREFIX) {
+            let mac_input = input[..line_offset + MAC_PREFIX.len()].to_vec();
+            let mac = match line.strip_prefix("--- ") {
+                Some(v) => decode_base64(v)?,
+                None => return Err(WedprError::FormatError),
+            };
+            let header = AgeHeader {
+                stanzas,
+                mac_input,
+                mac,
+            };
+            return Ok((header, reader.offset));
+        }
+        let mut fields = match line.strip_prefix(STANZA_PREFIX) {
+            Some(v) => v.split(' '),
+            None => {
+                wedpr_println!("Invalid age stanza line");
+                return Err(WedprError::FormatError);
+            },
+        };
+        let stanza_type = fields.next().unwrap_or("").to_string();
+        let arguments: Vec<String> = fields.map(|v| v.to_string()).collect();
+        if stanza_type.is_empty() || arguments.iter().any(|v| v.is_empty()) {
+            return Err(WedprError::FormatError);
+        }
+        let mut body = String::new();
+        loop {
+            let body_line = reader.read_line()?;
+            if body_line.len() > STANZA_BODY_LINE_SIZE {
+                return Err(WedprError::FormatError);
+            }
+            body.push_str(body_line);
+            if body_line.len() < STANZA_BODY_LINE_SIZE {
+                break;
+            }
+        }
+        stanzas.push(AgeStanza {
+            stanza_type,
+            arguments,
+            body: decode_base64(&body)?,
+        });
+    }
+}
+
+/// Reader of header lines ended by "\n".
+struct LineReader<'a> {
+    input: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> LineReader<'a> {
+    fn read_line(&mut self) -> Result<&'a str, WedprError> {
+        let rest = &self.input[self.offset..];
+        let end = match rest.iter().position(|byte| *byte == b'\n') {
+            Some(v) => v,
+            None => {
+                wedpr_println!("age header is truncated");
+                return Err(WedprError::FormatError);
+            },
+        };
+        self.offset += end + 1;
+        match std::str::from_utf8(&rest[..end]) {
+            Ok(v) if v.bytes().all(|byte| (0x20..0x7f).contains(&byte)) => {
+                Ok(v)
+            },
+            _ => Err(WedprError::FormatError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header() {
+        let stanzas = vec![
+            AgeStanza {
+                stanza_type: "X25519".to_string(),
+                arguments: vec!["argument".to_string()],
+                body: vec![1u8; 32],
+            },
+            // A body of exactly one full line is followed by an empty line.
+            AgeStanza {
+                stanza_type: "grease".to_string(),
+                arguments: vec![],
+                body: vec![2u8; 48],
+            },
+        ];
+        let mut encoded = encode_mac_input(&stanzas);
+        let mac_input = encoded.clone();
+        encoded.extend_from_slice(b" AAAA\npayload");
+        let (header, header_size) = parse_header(&encoded).unwrap();
+        assert_eq!(header.stanzas, stanzas);
+        assert_eq!(header.mac_input, mac_input);
+        assert_eq!(header.mac, vec![0u8; 3]);
+        assert_eq!(&encoded[header_size..], b"payload");
+
+        assert!(parse_header(&encoded[1..]).is_err());
+        assert!(parse_header(&mac_input).is_err());
+        assert!(decode_base64("AAAA=").is_err());
+        assert!(decode_base64("AAB").is_err());
+    }
+}
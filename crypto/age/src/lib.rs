@@ -0,0 +1,563 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! age v1 file encryption functions, compatible with the age tool, where a
+//! file can be decrypted by any of multiple X25519 recipients, or by a single
+//! scrypt passphrase recipient.
+//!
+//! A random file key is wrapped for each recipient by ChaCha20-Poly1305, and
+//! the file is encrypted in 64 KiB chunks by ChaCha20-Poly1305 under a key
+//! derived from the file key by HKDF-SHA256.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+pub mod bech32;
+pub mod header;
+
+use curve25519_dalek::{
+    constants::X25519_BASEPOINT, montgomery::MontgomeryPoint, scalar::Scalar,
+};
+use header::{
+    decode_base64, encode_base64, encode_mac_input, parse_header, AgeStanza,
+};
+use wedpr_l_crypto_block_cipher_chacha20::{
+    WedprChaCha20Poly1305, CHACHA20_NONCE_SIZE_IN_BYTES,
+    POLY1305_TAG_SIZE_IN_BYTES,
+};
+use wedpr_l_crypto_kdf::{
+    hmac_sha256,
+    password::{PasswordKdf, WedprScrypt},
+    WedprHkdfSha256,
+};
+use wedpr_l_utils::{
+    constant_time::ct_is_zero,
+    error::WedprError,
+    tool::random_bytes,
+    traits::{BlockCipher, Kdf, Mac},
+};
+
+/// Human-readable part of X25519 recipients.
+pub const AGE_RECIPIENT_HRP: &str = "age";
+/// Human-readable part of X25519 identities, which are uppercased.
+pub const AGE_IDENTITY_HRP: &str = "age-secret-key-";
+/// Default scrypt work factor log2(N) of passphrase recipients.
+pub const DEFAULT_SCRYPT_LOG_N: u8 = 18;
+/// Maximum scrypt work factor log2(N) accepted by default when decrypting.
+pub const DEFAULT_MAX_SCRYPT_LOG_N: u8 = 22;
+
+const X25519_KEY_SIZE_IN_BYTES: usize = 32;
+const FILE_KEY_SIZE_IN_BYTES: usize = 16;
+const PAYLOAD_NONCE_SIZE_IN_BYTES: usize = 16;
+const SCRYPT_SALT_SIZE_IN_BYTES: usize = 16;
+const WRAP_KEY_SIZE_IN_BYTES: usize = 32;
+/// Size of each plaintext chunk of the payload.
+const CHUNK_SIZE_IN_BYTES: usize = 64 * 1024;
+const X25519_STANZA_TYPE: &str = "X25519";
+const SCRYPT_STANZA_TYPE: &str = "scrypt";
+const X25519_INFO: &[u8] = b"age-encryption.org/v1/X25519";
+const SCRYPT_SALT_LABEL: &[u8] = b"age-encryption.org/v1/scrypt";
+const HEADER_INFO: &[u8] = b"header";
+const PAYLOAD_INFO: &[u8] = b"payload";
+
+/// Recipient that a file is encrypted to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AgeRecipient {
+    /// X25519 public key of 32 bytes.
+    X25519(Vec<u8>),
+    /// Passphrase with the scrypt work factor log2(N).
+    Scrypt { passphrase: Vec<u8>, log_n: u8 },
+}
+
+/// Identity that a file is decrypted with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AgeIdentity {
+    /// X25519 secret key of 32 bytes.
+    X25519(Vec<u8>),
+    /// Passphrase with the maximum accepted scrypt work factor log2(N).
+    Scrypt { passphrase: Vec<u8>, max_log_n: u8 },
+}
+
+impl AgeRecipient {
+    /// Parses an "age1..." X25519 recipient.
+    pub fn parse(recipient: &str) -> Result<Self, WedprError> {
+        match bech32::decode(recipient)? {
+            (hrp, public_key)
+                if hrp == AGE_RECIPIENT_HRP
+                    && public_key.len() == X25519_KEY_SIZE_IN_BYTES =>
+            {
+                Ok(AgeRecipient::X25519(public_key))
+            },
+            _ => {
+                wedpr_println!("Invalid age recipient");
+                Err(WedprError::DecodeError)
+            },
+        }
+    }
+}
+
+impl AgeIdentity {
+    /// Parses an "AGE-SECRET-KEY-1..." X25519 identity.
+    pub fn parse(identity: &str) -> Result<Self, WedprError> {
+        match bech32::decode(identity)? {
+            (hrp, secret_key)
+                if hrp == AGE_IDENTITY_HRP
+                    && secret_key.len() == X25519_KEY_SIZE_IN_BYTES =>
+            {
+                Ok(AgeIdentity::X25519(secret_key))
+            },
+            _ => {
+                wedpr_println!("Invalid age identity");
+                Err(WedprError::DecodeError)
+            },
+        }
+    }
+
+    /// Parses the identities of an identity file, where empty lines and
+    /// comment lines starting with "#" are ignored.
+    pub fn parse_file(content: &str) -> Result<Vec<Self>, WedprError> {
+        content
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(Self::parse)
+            .collect()
+    }
+}
+
+/// Generates an X25519 key pair encoded as (recipient, identity) strings.
+pub fn generate_x25519_identity() -> (String, String) {
+    let secret_key = random_bytes(X25519_KEY_SIZE_IN_BYTES);
+    let public_key = x25519(&secret_key, &X25519_BASEPOINT.to_bytes());
+    (
+        bech32::encode(AGE_RECIPIENT_HRP, &public_key),
+        bech32::encode(AGE_IDENTITY_HRP, &secret_key).to_uppercase(),
+    )
+}
+
+/// Derives the "age1..." recipient of an X25519 identity.
+pub fn derive_x25519_recipient(
+    identity: &AgeIdentity,
+) -> Result<String, WedprError> {
+    match identity {
+        AgeIdentity::X25519(secret_key)
+            if secret_key.len() == X25519_KEY_SIZE_IN_BYTES =>
+        {
+            let public_key = x25519(secret_key, &X25519_BASEPOINT.to_bytes());
+            Ok(bech32::encode(AGE_RECIPIENT_HRP, &public_key))
+        },
+        _ => Err(WedprError::ArgumentError),
+    }
+}
+
+/// Encrypts a file to recipients, where a scrypt recipient should be the
+/// only recipient.
+pub fn encrypt(
+    recipients: &[AgeRecipient],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let has_scrypt_recipient = recipients
+        .iter()
+        .any(|recipient| matches!(recipient, AgeRecipient::Scrypt { .. }));
+    if recipients.is_empty() || (has_scrypt_recipient && recipients.len() > 1) {
+        wedpr_println!("age needs X25519 recipients or one scrypt recipient");
+        return Err(WedprError::ArgumentError);
+    }
+    let file_key = random_bytes(FILE_KEY_SIZE_IN_BYTES);
+    let stanzas = recipients
+        .iter()
+        .map(|recipient| wrap_file_key(recipient, &file_key))
+        .collect::<Result<Vec<AgeStanza>, WedprError>>()?;
+    let mut output = encode_mac_input(&stanzas);
+    let mac = hmac_sha256().mac(&header_key(&file_key)?[..], &output[..]);
+    output.extend_from_slice(format!(" {}\n", encode_base64(&mac)).as_bytes());
+
+    let nonce = random_bytes(PAYLOAD_NONCE_SIZE_IN_BYTES);
+    let payload_key = hkdf(&file_key, &nonce, PAYLOAD_INFO)?;
+    output.extend_from_slice(&nonce);
+    let chunk_count =
+        std::cmp::max(1, plaintext.len().div_ceil(CHUNK_SIZE_IN_BYTES));
+    let cipher = WedprChaCha20Poly1305::default();
+    for i in 0..chunk_count {
+        let start = i * CHUNK_SIZE_IN_BYTES;
+        let end = std::cmp::min(start + CHUNK_SIZE_IN_BYTES, plaintext.len());
+        let chunk_iv = chunk_nonce(i as u64, i + 1 == chunk_count);
+        output.extend_from_slice(&cipher.encrypt(
+            &plaintext[start..end],
+            &payload_key[..],
+            &chunk_iv[..],
+        )?);
+    }
+    Ok(output)
+}
+
+/// Decrypts a file with any matching identity.
+pub fn decrypt(
+    identities: &[AgeIdentity],
+    input: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let (header, header_size) = parse_header(input)?;
+    let has_scrypt_stanza = header
+        .stanzas
+        .iter()
+        .any(|stanza| stanza.stanza_type == SCRYPT_STANZA_TYPE);
+    if has_scrypt_stanza && header.stanzas.len() > 1 {
+        wedpr_println!("age scrypt stanza should be the only stanza");
+        return Err(WedprError::FormatError);
+    }
+    let file_key = match identities.iter().find_map(|identity| {
+        header
+            .stanzas
+            .iter()
+            .find_map(|stanza| unwrap_file_key(identity, stanza))
+    }) {
+        Some(v) => v,
+        None => {
+            wedpr_println!("No age identity matched");
+            return Err(WedprError::VerificationError);
+        },
+    };
+    if !hmac_sha256().verify_mac(
+        &header_key(&file_key)?[..],
+        &header.mac_input[..],
+        &header.mac[..],
+    ) {
+        wedpr_println!("age header MAC mismatched");
+        return Err(WedprError::VerificationError);
+    }
+
+    let payload = &input[header_size..];
+    if payload.len() < PAYLOAD_NONCE_SIZE_IN_BYTES {
+        return Err(WedprError::FormatError);
+    }
+    let (nonce, ciphertext) = payload.split_at(PAYLOAD_NONCE_SIZE_IN_BYTES);
+    let payload_key = hkdf(&file_key, nonce, PAYLOAD_INFO)?;
+    let cipher = WedprChaCha20Poly1305::default();
+    let chunks: Vec<&[u8]> = ciphertext
+        .chunks(CHUNK_SIZE_IN_BYTES + POLY1305_TAG_SIZE_IN_BYTES)
+        .collect();
+    if chunks.is_empty() {
+        return Err(WedprError::FormatError);
+    }
+    let mut plaintext = Vec::with_capacity(ciphertext.len());
+    for (i, chunk) in chunks.iter().enumerate() {
+        let is_last = i + 1 == chunks.len();
+        let chunk_plaintext = cipher.decrypt(
+            *chunk,
+            &payload_key[..],
+            &chunk_nonce(i as u64, is_last)[..],
+        )?;
+        // Only a sole last chunk may be empty.
+        if is_last && chunk_plaintext.is_empty() && i > 0 {
+            return Err(WedprError::FormatError);
+        }
+        plaintext.extend_from_slice(&chunk_plaintext);
+    }
+    Ok(plaintext)
+}
+
+/// Wraps the file key for a recipient as a stanza.
+fn wrap_file_key(
+    recipient: &AgeRecipient,
+    file_key: &[u8],
+) -> Result<AgeStanza, WedprError> {
+    match recipient {
+        AgeRecipient::X25519(public_key) => {
+            if public_key.len() != X25519_KEY_SIZE_IN_BYTES {
+                return Err(WedprError::ArgumentError);
+            }
+            let ephemeral_secret_key = random_bytes(X25519_KEY_SIZE_IN_BYTES);
+            let ephemeral_share =
+                x25519(&ephemeral_secret_key, &X25519_BASEPOINT.to_bytes());
+            let wrap_key = x25519_wrap_key(
+                &x25519(&ephemeral_secret_key, public_key),
+                &ephemeral_share,
+                public_key,
+            )?;
+            Ok(AgeStanza {
+                stanza_type: X25519_STANZA_TYPE.to_string(),
+                arguments: vec![encode_base64(&ephemeral_share)],
+                body: wrap(&wrap_key, file_key)?,
+            })
+        },
+        AgeRecipient::Scrypt { passphrase, log_n } => {
+            let salt = random_bytes(SCRYPT_SALT_SIZE_IN_BYTES);
+            let wrap_key = scrypt_wrap_key(passphrase, &salt, *log_n)?;
+            Ok(AgeStanza {
+                stanza_type: SCRYPT_STANZA_TYPE.to_string(),
+                arguments: vec![encode_base64(&salt), log_n.to_string()],
+                body: wrap(&wrap_key, file_key)?,
+            })
+        },
+    }
+}
+
+/// Unwraps the file key from a stanza of the identity, or None if the stanza
+/// does not match the identity.
+fn unwrap_file_key(
+    identity: &AgeIdentity,
+    stanza: &AgeStanza,
+) -> Option<Vec<u8>> {
+    let wrap_key = match (identity, stanza.stanza_type.as_str()) {
+        (AgeIdentity::X25519(secret_key), X25519_STANZA_TYPE) => {
+            if secret_key.len() != X25519_KEY_SIZE_IN_BYTES
+                || stanza.arguments.len() != 1
+            {
+                return None;
+            }
+            let ephemeral_share = decode_base64(&stanza.arguments[0]).ok()?;
+            if ephemeral_share.len() != X25519_KEY_SIZE_IN_BYTES {
+                return None;
+            }
+            let public_key = x25519(secret_key, &X25519_BASEPOINT.to_bytes());
+            x25519_wrap_key(
+                &x25519(secret_key, &ephemeral_share),
+                &ephemeral_share,
+                &public_key,
+            )
+            .ok()?
+        },
+        (
+            AgeIdentity::Scrypt {
+                passphrase,
+                max_log_n,
+            },
+            SCRYPT_STANZA_TYPE,
+        ) => {
+            if stanza.arguments.len() != 2 {
+                return None;
+            }
+            let salt = decode_base64(&stanza.arguments[0]).ok()?;
+            let log_n: u8 = stanza.arguments[1].parse().ok()?;
+            // Rejects leading zeros and too large work factors.
+            if salt.len() != SCRYPT_SALT_SIZE_IN_BYTES
+                || log_n.to_string() != stanza.arguments[1]
+                || log_n > *max_log_n
+            {
+                wedpr_println!("Invalid or too large age scrypt work factor");
+                return None;
+            }
+            scrypt_wrap_key(passphrase, &salt, log_n).ok()?
+        },
+        _ => return None,
+    };
+    if stanza.body.len() != FILE_KEY_SIZE_IN_BYTES + POLY1305_TAG_SIZE_IN_BYTES
+    {
+        return None;
+    }
+    WedprChaCha20Poly1305::default()
+        .decrypt(
+            &stanza.body[..],
+            &wrap_key[..],
+            &[0u8; CHACHA20_NONCE_SIZE_IN_BYTES][..],
+        )
+        .ok()
+}
+
+/// Encrypts a file key under a wrap key with the zero nonce.
+fn wrap(wrap_key: &[u8], file_key: &[u8]) -> Result<Vec<u8>, WedprError> {
+    WedprChaCha20Poly1305::default().encrypt(
+        file_key,
+        wrap_key,
+        &[0u8; CHACHA20_NONCE_SIZE_IN_BYTES][..],
+    )
+}
+
+/// Computes the scalar multiplication of X25519 with a clamped secret key.
+fn x25519(secret_key: &[u8], point: &[u8]) -> Vec<u8> {
+    let mut scalar_bytes = [0u8; X25519_KEY_SIZE_IN_BYTES];
+    scalar_bytes.copy_from_slice(secret_key);
+    scalar_bytes[0] &= 248;
+    scalar_bytes[31] &= 127;
+    scalar_bytes[31] |= 64;
+    let mut point_bytes = [0u8; X25519_KEY_SIZE_IN_BYTES];
+    point_bytes.copy_from_slice(point);
+    (MontgomeryPoint(point_bytes) * Scalar::from_bits(scalar_bytes))
+        .to_bytes()
+        .to_vec()
+}
+
+fn x25519_wrap_key(
+    shared_secret: &[u8],
+    ephemeral_share: &[u8],
+    public_key: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    // Rejects low order points.
+    if bool::from(ct_is_zero(shared_secret)) {
+        wedpr_println!("age X25519 shared secret is zero");
+        return Err(WedprError::ArgumentError);
+    }
+    let mut salt = ephemeral_share.to_vec();
+    salt.extend_from_slice(public_key);
+    hkdf(shared_secret, &salt, X25519_INFO)
+}
+
+fn scrypt_wrap_key(
+    passphrase: &[u8],
+    salt: &[u8],
+    log_n: u8,
+) -> Result<Vec<u8>, WedprError> {
+    let mut scrypt_salt = SCRYPT_SALT_LABEL.to_vec();
+    scrypt_salt.extend_from_slice(salt);
+    WedprScrypt::new(log_n, 8, 1)?.derive_from_password(
+        passphrase,
+        &scrypt_salt,
+        WRAP_KEY_SIZE_IN_BYTES,
+    )
+}
+
+fn header_key(file_key: &[u8]) -> Result<Vec<u8>, WedprError> {
+    hkdf(file_key, &[], HEADER_INFO)
+}
+
+fn hkdf(
+    input_key: &[u8],
+    salt: &[u8],
+    info: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    WedprHkdfSha256::default().derive_key(
+        input_key,
+        salt,
+        info,
+        WRAP_KEY_SIZE_IN_BYTES,
+    )
+}
+
+/// Gets the nonce of a payload chunk, i.e. an 11-byte big-endian counter and
+/// a flag of the last chunk.
+fn chunk_nonce(index: u64, is_last: bool) -> Vec<u8> {
+    let mut nonce = vec![0u8; 3];
+    nonce.extend_from_slice(&index.to_be_bytes());
+    nonce.push(is_last as u8);
+    nonce
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_common_coder_hex::WedprHex;
+    use wedpr_l_utils::traits::Coder;
+
+    #[test]
+    fn test_age() {
+        let (recipient1, identity1) = generate_x25519_identity();
+        let (recipient2, identity2) = generate_x25519_identity();
+        let identity1 = AgeIdentity::parse(&identity1).unwrap();
+        let identity2 = AgeIdentity::parse(&identity2).unwrap();
+        assert_eq!(derive_x25519_recipient(&identity1).unwrap(), recipient1);
+        let recipients = vec![
+            AgeRecipient::parse(&recipient1).unwrap(),
+            AgeRecipient::parse(&recipient2).unwrap(),
+        ];
+
+        for size in &[0usize, 1, CHUNK_SIZE_IN_BYTES, CHUNK_SIZE_IN_BYTES + 1] {
+            let plaintext = vec![7u8; *size];
+            let encrypted = encrypt(&recipients, &plaintext).unwrap();
+            assert_eq!(
+                decrypt(std::slice::from_ref(&identity2), &encrypted).unwrap(),
+                plaintext
+            );
+            assert_eq!(
+                decrypt(std::slice::from_ref(&identity1), &encrypted).unwrap(),
+                plaintext
+            );
+            // Truncating the last chunk is detected.
+            if *size == CHUNK_SIZE_IN_BYTES + 1 {
+                assert!(decrypt(
+                    std::slice::from_ref(&identity1),
+                    &encrypted[..encrypted.len() - 17]
+                )
+                .is_err());
+            }
+        }
+        let (_, other_identity) = generate_x25519_identity();
+        let encrypted = encrypt(&recipients, b"message").unwrap();
+        assert!(decrypt(
+            &AgeIdentity::parse_file(&format!("# key\n{}\n", other_identity))
+                .unwrap(),
+            &encrypted
+        )
+        .is_err());
+
+        let encrypted = encrypt(
+            &[AgeRecipient::Scrypt {
+                passphrase: b"passphrase".to_vec(),
+                log_n: 10,
+            }],
+            b"message",
+        )
+        .unwrap();
+        let scrypt_identity = AgeIdentity::Scrypt {
+            passphrase: b"passphrase".to_vec(),
+            max_log_n: DEFAULT_MAX_SCRYPT_LOG_N,
+        };
+        assert_eq!(
+            decrypt(std::slice::from_ref(&scrypt_identity), &encrypted)
+                .unwrap(),
+            b"message"
+        );
+        assert!(decrypt(
+            &[AgeIdentity::Scrypt {
+                passphrase: b"passphrase".to_vec(),
+                max_log_n: 9,
+            }],
+            &encrypted
+        )
+        .is_err());
+
+        // Files encrypted by an independent implementation of the age v1
+        // specification.
+        let identity = AgeIdentity::parse(
+            &[
+                "AGE-SECRET-KEY-19EM3YPTZU2CVSFG53M40SWESU8GZW29Z55HYGCU3",
+                "ANXY0GK7MHYSQRGPK2",
+            ]
+            .concat(),
+        )
+        .unwrap();
+        assert_eq!(
+            derive_x25519_recipient(&identity).unwrap(),
+            [
+                "age12x746rn5gv9ctdx6pjl7afz3559jhqw50tmfkdet73wgcpw2se8q",
+                "9h5kmr"
+            ]
+            .concat()
+        );
+        let hex = WedprHex::default();
+        let payload = hex
+            .decode(
+                &[
+                    "101112131415161718191a1b1c1d1e1fe4de5afe54b77995bcf362",
+                    "4c1cc45f0bfb42610e544c155b21f29e76024914eddcc96c48026e",
+                    "518a5b4de11e81c7a5",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let mut encrypted = [
+            "age-encryption.org/v1\n",
+            "-> X25519 EC50wBIv4H+r7p9cUqsnC4bH1Sdq9Lg111tXR+wzmxQ\n",
+            "UqzDlXMg/bcwShGkHqm3hTfjwhX9tbvNbhaOEn5AMzM\n",
+            "--- fdzCFjFa61tRErsQXUbIHc69AR0wvwC4emonfrzICtg\n",
+        ]
+        .concat()
+        .into_bytes();
+        encrypted.extend_from_slice(&payload);
+        assert_eq!(
+            decrypt(&[identity], &encrypted).unwrap(),
+            b"age file exchanged by operators"
+        );
+        let mut encrypted = [
+            "age-encryption.org/v1\n",
+            "-> scrypt ICEiIyQlJicoKSorLC0uLw 10\n",
+            "Eb01YF6cCVYX5qUT2jGZmciKMYOHUdxL6FenoaT0vqc\n",
+            "--- RI9r63e5UoEhtN2OFV7SDJ4oc4SqgihwJsgRuQTvzIY\n",
+        ]
+        .concat()
+        .into_bytes();
+        encrypted.extend_from_slice(&payload);
+        assert_eq!(
+            decrypt(&[scrypt_identity], &encrypted).unwrap(),
+            b"age file exchanged by operators"
+        );
+    }
+}
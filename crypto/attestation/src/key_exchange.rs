@@ -0,0 +1,245 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Attested key exchange functions, where an enclave binds its ephemeral ECDH
+//! public key to an SGX quote, so that the derived session key is only shared
+//! with genuine enclaves accepted by the verifier.
+
+use crate::sgx::{is_public_key_bound, verify_sgx_quote, SgxQuote};
+use wedpr_l_crypto_ecies_secp256k1::ecdh::{
+    compute_shared_secret, generate_keypair,
+};
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{Hash, Signature},
+};
+
+/// Domain separator of the session key derivation.
+const SESSION_KEY_DOMAIN: &[u8] = b"WeDPR_ATTESTED_KEY_EXCHANGE";
+/// Serialized data size of SGX report data.
+const REPORT_DATA_SIZE: usize = 64;
+
+/// Enclave identity accepted by a verifier, where None accepts any value.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct EnclavePolicy {
+    pub mr_enclave: Option<Vec<u8>>,
+    pub mr_signer: Option<Vec<u8>>,
+    pub isv_prod_id: Option<u16>,
+    pub min_isv_svn: u16,
+}
+
+impl EnclavePolicy {
+    /// Checks whether the enclave of a quote satisfies the policy.
+    pub fn is_satisfied_by(&self, quote: &SgxQuote) -> bool {
+        let report = &quote.report;
+        self.mr_enclave
+            .as_ref()
+            .map_or(true, |v| *v == report.mr_enclave)
+            && self
+                .mr_signer
+                .as_ref()
+                .map_or(true, |v| *v == report.mr_signer)
+            && self.isv_prod_id.map_or(true, |v| v == report.isv_prod_id)
+            && report.isv_svn >= self.min_isv_svn
+    }
+}
+
+/// One side of an attested key exchange with an ephemeral ECDH key pair.
+#[derive(Debug, Clone)]
+pub struct AttestedKeyExchange {
+    public_key: Vec<u8>,
+    private_key: Vec<u8>,
+}
+
+impl AttestedKeyExchange {
+    /// Creates a side with a new ephemeral key pair.
+    pub fn new() -> Self {
+        let (public_key, private_key) = generate_keypair();
+        AttestedKeyExchange {
+            public_key,
+            private_key,
+        }
+    }
+
+    /// Gets the ephemeral public key sent to the peer.
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// Computes the report data which an enclave should include in its quote
+    /// to bind its ephemeral public key.
+    pub fn report_data<H: Hash>(
+        &self,
+        hash: &H,
+    ) -> Result<Vec<u8>, WedprError> {
+        let mut report_data = hash.hash(&self.public_key);
+        if report_data.len() > REPORT_DATA_SIZE {
+            return Err(WedprError::ArgumentError);
+        }
+        report_data.resize(REPORT_DATA_SIZE, 0);
+        Ok(report_data)
+    }
+
+    /// Derives the session key with a peer, which is used by the enclave side
+    /// after sending its quote.
+    pub fn derive_session_key<H: Hash>(
+        &self,
+        hash: &H,
+        peer_public_key: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        let shared_secret =
+            compute_shared_secret(&self.private_key, peer_public_key)?;
+        // Public keys are sorted so that both sides derive the same key.
+        let (first_key, second_key) =
+            if self.public_key.as_slice() <= peer_public_key {
+                (self.public_key.as_slice(), peer_public_key)
+            } else {
+                (peer_public_key, self.public_key.as_slice())
+            };
+        let mut hash_vec = SESSION_KEY_DOMAIN.to_vec();
+        hash_vec.extend_from_slice(&shared_secret);
+        hash_vec.extend_from_slice(first_key);
+        hash_vec.extend_from_slice(second_key);
+        Ok(hash.hash(&hash_vec))
+    }
+
+    /// Verifies the quote of an enclave peer and its binding to the peer
+    /// ephemeral public key, and derives the session key if the enclave is
+    /// accepted by the policy.
+    pub fn accept_enclave<S: Signature, H: Hash>(
+        &self,
+        signature: &S,
+        hash: &H,
+        pck_public_key: &[u8],
+        quote: &SgxQuote,
+        enclave_public_key: &[u8],
+        policy: &EnclavePolicy,
+    ) -> Result<Vec<u8>, WedprError> {
+        if !verify_sgx_quote(signature, hash, pck_public_key, quote)
+            || !is_public_key_bound(hash, quote, enclave_public_key)
+        {
+            wedpr_println!("Enclave quote verification failed");
+            return Err(WedprError::VerificationError);
+        }
+        if !policy.is_satisfied_by(quote) {
+            wedpr_println!("Enclave is not accepted by the policy");
+            return Err(WedprError::VerificationError);
+        }
+        self.derive_session_key(hash, enclave_public_key)
+    }
+}
+
+impl Default for AttestedKeyExchange {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sgx::SgxReportBody;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+    use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+
+    // Builds a parsed quote signed with secp256k1 keys in place of P-256 keys,
+    // which is enough for the handshake logic.
+    fn build_quote(
+        pck_private_key: &[u8],
+        report_data: Vec<u8>,
+        mr_enclave: Vec<u8>,
+    ) -> SgxQuote {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let keccak256 = WedprKeccak256::default();
+        let (ak_public_key, ak_private_key) = secp256k1.generate_keypair();
+        let signed_data = [report_data.clone(), mr_enclave.clone()].concat();
+        let mut qe_report_data = vec![0u8; 64];
+        let mut binding_data = ak_public_key[1..].to_vec();
+        binding_data.extend_from_slice(b"auth");
+        qe_report_data[..32].copy_from_slice(&keccak256.hash(&binding_data));
+        let qe_report_signature = secp256k1
+            .sign(&pck_private_key.to_vec(), &keccak256.hash(&qe_report_data))
+            .unwrap();
+        SgxQuote {
+            report: SgxReportBody {
+                mr_enclave,
+                report_data,
+                ..Default::default()
+            },
+            report_signature: secp256k1
+                .sign(&ak_private_key, &keccak256.hash(&signed_data))
+                .unwrap(),
+            signed_data,
+            attestation_key: ak_public_key,
+            qe_report: SgxReportBody {
+                report_data: qe_report_data.clone(),
+                ..Default::default()
+            },
+            qe_report_data,
+            qe_report_signature,
+            qe_auth_data: b"auth".to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_attested_key_exchange() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let keccak256 = WedprKeccak256::default();
+        let (pck_public_key, pck_private_key) = secp256k1.generate_keypair();
+
+        let enclave = AttestedKeyExchange::new();
+        let client = AttestedKeyExchange::default();
+        let quote = build_quote(
+            &pck_private_key,
+            enclave.report_data(&keccak256).unwrap(),
+            vec![1u8; 32],
+        );
+        let policy = EnclavePolicy {
+            mr_enclave: Some(vec![1u8; 32]),
+            ..Default::default()
+        };
+
+        let client_session_key = client
+            .accept_enclave(
+                &secp256k1,
+                &keccak256,
+                &pck_public_key,
+                &quote,
+                enclave.public_key(),
+                &policy,
+            )
+            .unwrap();
+        let enclave_session_key = enclave
+            .derive_session_key(&keccak256, client.public_key())
+            .unwrap();
+        assert_eq!(client_session_key, enclave_session_key);
+
+        // A man in the middle cannot replace the enclave public key.
+        let attacker = AttestedKeyExchange::new();
+        assert!(client
+            .accept_enclave(
+                &secp256k1,
+                &keccak256,
+                &pck_public_key,
+                &quote,
+                attacker.public_key(),
+                &policy,
+            )
+            .is_err());
+        // An unexpected enclave is rejected.
+        let other_policy = EnclavePolicy {
+            mr_enclave: Some(vec![2u8; 32]),
+            ..Default::default()
+        };
+        assert!(client
+            .accept_enclave(
+                &secp256k1,
+                &keccak256,
+                &pck_public_key,
+                &quote,
+                enclave.public_key(),
+                &other_policy,
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Key attestation verification functions, which check whether a public key is
+//! bound to genuine secure hardware, including TPM 2.0 key certification and
+//! SGX DCAP quotes.
+//!
+//! Hardware vendors use algorithms such as ECDSA P-256 with SHA-256, thus the
+//! signature and hash algorithms are replaceable and provided by callers.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+pub mod key_exchange;
+pub mod sgx;
+pub mod tpm;
+
+use wedpr_l_utils::error::WedprError;
+
+/// Reader of fixed size fields in a hardware structure.
+struct StructReader<'a> {
+    input: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> StructReader<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        StructReader { input, offset: 0 }
+    }
+
+    fn read_fixed(&mut self, length: usize) -> Result<&'a [u8], WedprError> {
+        if self.input.len() - self.offset < length {
+            wedpr_println!("Attestation structure is truncated");
+            return Err(WedprError::FormatError);
+        }
+        let field = &self.input[self.offset..self.offset + length];
+        self.offset += length;
+        Ok(field)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, WedprError> {
+        Ok(self.read_fixed(1)?[0])
+    }
+
+    fn read_u16_be(&mut self) -> Result<u16, WedprError> {
+        let field = self.read_fixed(2)?;
+        Ok(u16::from_be_bytes([field[0], field[1]]))
+    }
+
+    fn read_u32_be(&mut self) -> Result<u32, WedprError> {
+        let mut buffer = [0u8; 4];
+        buffer.copy_from_slice(self.read_fixed(4)?);
+        Ok(u32::from_be_bytes(buffer))
+    }
+
+    fn read_u64_be(&mut self) -> Result<u64, WedprError> {
+        let mut buffer = [0u8; 8];
+        buffer.copy_from_slice(self.read_fixed(8)?);
+        Ok(u64::from_be_bytes(buffer))
+    }
+
+    fn read_u16_le(&mut self) -> Result<u16, WedprError> {
+        let field = self.read_fixed(2)?;
+        Ok(u16::from_le_bytes([field[0], field[1]]))
+    }
+
+    fn read_u32_le(&mut self) -> Result<u32, WedprError> {
+        let mut buffer = [0u8; 4];
+        buffer.copy_from_slice(self.read_fixed(4)?);
+        Ok(u32::from_le_bytes(buffer))
+    }
+
+    /// Reads a field prefixed by its big-endian u16 size, e.g. TPM2B types.
+    fn read_sized_u16_be(&mut self) -> Result<&'a [u8], WedprError> {
+        let length = self.read_u16_be()? as usize;
+        self.read_fixed(length)
+    }
+
+    /// Reads a field prefixed by its little-endian u16 size.
+    fn read_sized_u16_le(&mut self) -> Result<&'a [u8], WedprError> {
+        let length = self.read_u16_le()? as usize;
+        self.read_fixed(length)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.offset >= self.input.len()
+    }
+}
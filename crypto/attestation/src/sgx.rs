@@ -0,0 +1,306 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! SGX DCAP quote functions, verifying a version 3 ECDSA quote generated by
+//! the quoting enclave (QE), and the binding between the quote and a key.
+
+use crate::StructReader;
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{Hash, Signature},
+};
+
+/// Supported quote version.
+pub const SGX_QUOTE_VERSION: u16 = 3;
+/// Attestation key type of ECDSA-256-with-P-256 curve.
+pub const SGX_ATTESTATION_KEY_TYPE_ECDSA_P256: u16 = 2;
+
+/// Serialized data size of a quote header.
+const QUOTE_HEADER_SIZE: usize = 48;
+/// Serialized data size of a report body.
+const REPORT_BODY_SIZE: usize = 384;
+/// Serialized data size of a raw ECDSA P-256 signature or public key.
+const ECDSA_P256_SIZE: usize = 64;
+/// Prefix of an uncompressed SEC1 public key.
+const UNCOMPRESSED_PUBLIC_KEY_PREFIX: u8 = 0x04;
+
+/// Parsed SGX report body.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct SgxReportBody {
+    pub cpu_svn: Vec<u8>,
+    pub misc_select: u32,
+    pub attributes: Vec<u8>,
+    pub mr_enclave: Vec<u8>,
+    pub mr_signer: Vec<u8>,
+    pub isv_prod_id: u16,
+    pub isv_svn: u16,
+    /// Data chosen by the enclave, e.g. the hash of its public key.
+    pub report_data: Vec<u8>,
+}
+
+/// Parsed SGX DCAP quote.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct SgxQuote {
+    pub version: u16,
+    pub attestation_key_type: u16,
+    pub qe_vendor_id: Vec<u8>,
+    pub report: SgxReportBody,
+    /// Quote header and report body signed by the attestation key.
+    pub signed_data: Vec<u8>,
+    pub report_signature: Vec<u8>,
+    /// Attestation key in uncompressed SEC1 format.
+    pub attestation_key: Vec<u8>,
+    pub qe_report: SgxReportBody,
+    /// QE report body signed by the PCK key.
+    pub qe_report_data: Vec<u8>,
+    pub qe_report_signature: Vec<u8>,
+    pub qe_auth_data: Vec<u8>,
+    pub qe_certification_data_type: u16,
+    /// QE certification data, e.g. the PEM encoded PCK certificate chain.
+    pub qe_certification_data: Vec<u8>,
+}
+
+fn parse_report_body(body: &[u8]) -> Result<SgxReportBody, WedprError> {
+    let mut reader = StructReader::new(body);
+    let cpu_svn = reader.read_fixed(16)?.to_vec();
+    let misc_select = reader.read_u32_le()?;
+    reader.read_fixed(28)?;
+    let attributes = reader.read_fixed(16)?.to_vec();
+    let mr_enclave = reader.read_fixed(32)?.to_vec();
+    reader.read_fixed(32)?;
+    let mr_signer = reader.read_fixed(32)?.to_vec();
+    reader.read_fixed(96)?;
+    let isv_prod_id = reader.read_u16_le()?;
+    let isv_svn = reader.read_u16_le()?;
+    reader.read_fixed(60)?;
+    let report_data = reader.read_fixed(64)?.to_vec();
+    Ok(SgxReportBody {
+        cpu_svn,
+        misc_select,
+        attributes,
+        mr_enclave,
+        mr_signer,
+        isv_prod_id,
+        isv_svn,
+        report_data,
+    })
+}
+
+/// Converts a raw ECDSA P-256 public key (x || y) to uncompressed SEC1 format.
+fn to_uncompressed_public_key(raw_key: &[u8]) -> Vec<u8> {
+    let mut public_key = vec![UNCOMPRESSED_PUBLIC_KEY_PREFIX];
+    public_key.extend_from_slice(raw_key);
+    public_key
+}
+
+/// Parses a version 3 SGX DCAP quote.
+pub fn parse_sgx_quote(quote: &[u8]) -> Result<SgxQuote, WedprError> {
+    let mut reader = StructReader::new(quote);
+    let header = reader.read_fixed(QUOTE_HEADER_SIZE)?;
+    let mut header_reader = StructReader::new(header);
+    let version = header_reader.read_u16_le()?;
+    let attestation_key_type = header_reader.read_u16_le()?;
+    if version != SGX_QUOTE_VERSION
+        || attestation_key_type != SGX_ATTESTATION_KEY_TYPE_ECDSA_P256
+    {
+        wedpr_println!("SGX quote version or key type is not supported");
+        return Err(WedprError::FormatError);
+    }
+    // Skips TEE type, QE SVN and PCE SVN.
+    header_reader.read_fixed(8)?;
+    let qe_vendor_id = header_reader.read_fixed(16)?.to_vec();
+    let report = parse_report_body(reader.read_fixed(REPORT_BODY_SIZE)?)?;
+    let signed_data = quote[..QUOTE_HEADER_SIZE + REPORT_BODY_SIZE].to_vec();
+
+    let signature_data_length = reader.read_u32_le()? as usize;
+    let mut signature_reader =
+        StructReader::new(reader.read_fixed(signature_data_length)?);
+    if !reader.is_empty() {
+        return Err(WedprError::FormatError);
+    }
+    let report_signature =
+        signature_reader.read_fixed(ECDSA_P256_SIZE)?.to_vec();
+    let attestation_key = to_uncompressed_public_key(
+        signature_reader.read_fixed(ECDSA_P256_SIZE)?,
+    );
+    let qe_report_data =
+        signature_reader.read_fixed(REPORT_BODY_SIZE)?.to_vec();
+    let qe_report = parse_report_body(&qe_report_data)?;
+    let qe_report_signature =
+        signature_reader.read_fixed(ECDSA_P256_SIZE)?.to_vec();
+    let qe_auth_data = signature_reader.read_sized_u16_le()?.to_vec();
+    let qe_certification_data_type = signature_reader.read_u16_le()?;
+    let certification_data_length = signature_reader.read_u32_le()? as usize;
+    let qe_certification_data = signature_reader
+        .read_fixed(certification_data_length)?
+        .to_vec();
+    Ok(SgxQuote {
+        version,
+        attestation_key_type,
+        qe_vendor_id,
+        report,
+        signed_data,
+        report_signature,
+        attestation_key,
+        qe_report,
+        qe_report_data,
+        qe_report_signature,
+        qe_auth_data,
+        qe_certification_data_type,
+        qe_certification_data,
+    })
+}
+
+/// Verifies the signature chain of a quote, i.e. the QE report is signed by
+/// the PCK key, the QE report binds the attestation key, and the enclave
+/// report is signed by the attestation key. Callers still need to check the
+/// PCK certificate chain, and the enclave identity against their policy.
+pub fn verify_sgx_quote<S: Signature, H: Hash>(
+    signature: &S,
+    hash: &H,
+    pck_public_key: &[u8],
+    quote: &SgxQuote,
+) -> bool {
+    let qe_report_hash = hash.hash(&quote.qe_report_data);
+    if !signature.verify(
+        pck_public_key,
+        &qe_report_hash[..],
+        &quote.qe_report_signature[..],
+    ) {
+        wedpr_println!("SGX QE report signature is invalid");
+        return false;
+    }
+    // The first half of QE report data is the hash of the raw attestation key
+    // and QE authentication data, and the second half is zero.
+    let mut binding_data = match quote.attestation_key.get(1..) {
+        Some(v) => v.to_vec(),
+        None => return false,
+    };
+    binding_data.extend_from_slice(&quote.qe_auth_data);
+    let binding_hash = hash.hash(&binding_data);
+    let report_data = &quote.qe_report.report_data;
+    if binding_hash.len() > report_data.len()
+        || report_data[..binding_hash.len()] != binding_hash[..]
+        || report_data[binding_hash.len()..]
+            .iter()
+            .any(|byte| *byte != 0)
+    {
+        wedpr_println!("SGX attestation key is not bound to the QE");
+        return false;
+    }
+    let signed_data_hash = hash.hash(&quote.signed_data);
+    signature.verify(
+        &quote.attestation_key[..],
+        &signed_data_hash[..],
+        &quote.report_signature[..],
+    )
+}
+
+/// Checks whether the enclave report of a quote binds a public key, where the
+/// report data starts with the hash of the public key.
+pub fn is_public_key_bound<H: Hash>(
+    hash: &H,
+    quote: &SgxQuote,
+    public_key: &[u8],
+) -> bool {
+    let public_key_hash = hash.hash(public_key);
+    quote.report.report_data.starts_with(&public_key_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+    use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+
+    fn build_report_body(mr_enclave: u8, report_data: &[u8]) -> Vec<u8> {
+        let mut body = vec![0u8; REPORT_BODY_SIZE];
+        body[64..96].copy_from_slice(&[mr_enclave; 32]);
+        body[320..320 + report_data.len()].copy_from_slice(report_data);
+        body
+    }
+
+    // Builds a quote in the same layout as the QE, while secp256k1 keys are
+    // used in place of P-256 keys. Recoverable secp256k1 signatures are one
+    // byte longer than P-256 ones, thus the recovery ids are dropped and
+    // restored around the quote.
+    #[test]
+    fn test_sgx_quote() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let keccak256 = WedprKeccak256::default();
+        let (pck_public_key, pck_private_key) = secp256k1.generate_keypair();
+        let (ak_public_key, ak_private_key) = secp256k1.generate_keypair();
+        let (enclave_public_key, _) = secp256k1.generate_keypair();
+
+        let mut header = vec![0u8; QUOTE_HEADER_SIZE];
+        header[0..2].copy_from_slice(&SGX_QUOTE_VERSION.to_le_bytes());
+        header[2..4].copy_from_slice(
+            &SGX_ATTESTATION_KEY_TYPE_ECDSA_P256.to_le_bytes(),
+        );
+        let report_body =
+            build_report_body(1, &keccak256.hash(&enclave_public_key));
+        let mut signed_data = header.clone();
+        signed_data.extend_from_slice(&report_body);
+        let mut report_signature = secp256k1
+            .sign(&ak_private_key, &keccak256.hash(&signed_data))
+            .unwrap();
+        let report_recovery_id = report_signature.pop().unwrap();
+
+        let qe_auth_data = b"auth".to_vec();
+        let mut binding_data = ak_public_key[1..].to_vec();
+        binding_data.extend_from_slice(&qe_auth_data);
+        let qe_report_body =
+            build_report_body(2, &keccak256.hash(&binding_data));
+        let mut qe_report_signature = secp256k1
+            .sign(&pck_private_key, &keccak256.hash(&qe_report_body))
+            .unwrap();
+        let qe_recovery_id = qe_report_signature.pop().unwrap();
+
+        let mut signature_data = report_signature.clone();
+        signature_data.extend_from_slice(&ak_public_key[1..]);
+        signature_data.extend_from_slice(&qe_report_body);
+        signature_data.extend_from_slice(&qe_report_signature);
+        signature_data
+            .extend_from_slice(&(qe_auth_data.len() as u16).to_le_bytes());
+        signature_data.extend_from_slice(&qe_auth_data);
+        signature_data.extend_from_slice(&5u16.to_le_bytes());
+        signature_data.extend_from_slice(&4u32.to_le_bytes());
+        signature_data.extend_from_slice(b"cert");
+        let mut quote_bytes = signed_data.clone();
+        quote_bytes
+            .extend_from_slice(&(signature_data.len() as u32).to_le_bytes());
+        quote_bytes.extend_from_slice(&signature_data);
+
+        let mut quote = parse_sgx_quote(&quote_bytes).unwrap();
+        assert_eq!(quote.report.mr_enclave, vec![1u8; 32]);
+        assert_eq!(quote.qe_report.mr_enclave, vec![2u8; 32]);
+        assert_eq!(quote.qe_certification_data, b"cert".to_vec());
+        assert_eq!(quote.attestation_key, ak_public_key);
+        assert!(is_public_key_bound(&keccak256, &quote, &enclave_public_key));
+        assert!(!is_public_key_bound(&keccak256, &quote, &ak_public_key));
+
+        quote.report_signature.push(report_recovery_id);
+        quote.qe_report_signature.push(qe_recovery_id);
+        assert!(verify_sgx_quote(
+            &secp256k1,
+            &keccak256,
+            &pck_public_key,
+            &quote
+        ));
+        assert!(!verify_sgx_quote(
+            &secp256k1,
+            &keccak256,
+            &ak_public_key,
+            &quote
+        ));
+        let mut tampered_quote = quote.clone();
+        tampered_quote.signed_data[100] ^= 1;
+        assert!(!verify_sgx_quote(
+            &secp256k1,
+            &keccak256,
+            &pck_public_key,
+            &tampered_quote
+        ));
+
+        assert!(parse_sgx_quote(&quote_bytes[..quote_bytes.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! TPM 2.0 key attestation functions, verifying a TPMS_ATTEST structure of
+//! TPM2_Certify signed by an attestation key (AK).
+
+use crate::StructReader;
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{Hash, Signature},
+};
+
+/// TPM_GENERATED_VALUE indicating the structure is generated by a TPM.
+pub const TPM_GENERATED_VALUE: u32 = 0xff54_4347;
+/// TPM_ST_ATTEST_CERTIFY structure tag.
+pub const TPM_ST_ATTEST_CERTIFY: u16 = 0x8017;
+
+/// Parsed TPMS_ATTEST structure of TPM2_Certify.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct TpmCertifyInfo {
+    pub qualified_signer: Vec<u8>,
+    /// Caller-provided data, usually a nonce for freshness.
+    pub extra_data: Vec<u8>,
+    pub clock: u64,
+    pub reset_count: u32,
+    pub restart_count: u32,
+    pub safe: bool,
+    pub firmware_version: u64,
+    /// TPM name of the certified key, i.e. nameAlg || H(TPMT_PUBLIC).
+    pub certified_name: Vec<u8>,
+    pub certified_qualified_name: Vec<u8>,
+}
+
+/// TPM2_Certify output, including the TPMS_ATTEST structure and its
+/// signature by the attestation key.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct TpmKeyAttestation {
+    pub attest: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+/// Parses a TPMS_ATTEST structure produced by TPM2_Certify.
+pub fn parse_tpm_certify_info(
+    attest: &[u8],
+) -> Result<TpmCertifyInfo, WedprError> {
+    let mut reader = StructReader::new(attest);
+    if reader.read_u32_be()? != TPM_GENERATED_VALUE {
+        wedpr_println!("TPM attestation is not generated by a TPM");
+        return Err(WedprError::FormatError);
+    }
+    if reader.read_u16_be()? != TPM_ST_ATTEST_CERTIFY {
+        wedpr_println!("TPM attestation is not a key certification");
+        return Err(WedprError::FormatError);
+    }
+    let info = TpmCertifyInfo {
+        qualified_signer: reader.read_sized_u16_be()?.to_vec(),
+        extra_data: reader.read_sized_u16_be()?.to_vec(),
+        clock: reader.read_u64_be()?,
+        reset_count: reader.read_u32_be()?,
+        restart_count: reader.read_u32_be()?,
+        safe: reader.read_u8()? != 0,
+        firmware_version: reader.read_u64_be()?,
+        certified_name: reader.read_sized_u16_be()?.to_vec(),
+        certified_qualified_name: reader.read_sized_u16_be()?.to_vec(),
+    };
+    if !reader.is_empty() {
+        return Err(WedprError::FormatError);
+    }
+    Ok(info)
+}
+
+/// Computes the TPM name of a key from its TPMT_PUBLIC area.
+pub fn tpm_key_name<H: Hash>(
+    hash: &H,
+    name_algorithm: u16,
+    public_area: &[u8],
+) -> Vec<u8> {
+    let mut name = name_algorithm.to_be_bytes().to_vec();
+    name.append(&mut hash.hash(public_area));
+    name
+}
+
+/// Verifies that the key described by public_area is certified by a TPM
+/// attestation key, where the hash algorithm should match name_algorithm (e.g.
+/// 0x000b for SHA-256), and the attestation should carry the expected nonce.
+/// Callers still need to check the trust chain of attestation_key.
+pub fn verify_tpm_key_attestation<S: Signature, H: Hash>(
+    signature: &S,
+    hash: &H,
+    attestation_key: &[u8],
+    attestation: &TpmKeyAttestation,
+    name_algorithm: u16,
+    public_area: &[u8],
+    expected_nonce: &[u8],
+) -> bool {
+    let info = match parse_tpm_certify_info(&attestation.attest) {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+    if info.extra_data != expected_nonce {
+        wedpr_println!("TPM attestation nonce mismatched");
+        return false;
+    }
+    if info.certified_name != tpm_key_name(hash, name_algorithm, public_area) {
+        wedpr_println!("TPM attestation is not for the given key");
+        return false;
+    }
+    let attest_hash = hash.hash(&attestation.attest);
+    signature.verify(
+        attestation_key,
+        &attest_hash[..],
+        &attestation.signature[..],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+    use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+
+    fn append_sized(buffer: &mut Vec<u8>, value: &[u8]) {
+        buffer.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        buffer.extend_from_slice(value);
+    }
+
+    #[test]
+    fn test_tpm_key_attestation() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let keccak256 = WedprKeccak256::default();
+        let (ak_public_key, ak_private_key) = secp256k1.generate_keypair();
+        let public_area = b"TPMT_PUBLIC of the certified key";
+        let nonce = b"nonce";
+        let name_algorithm = 0x000b;
+
+        let mut attest = TPM_GENERATED_VALUE.to_be_bytes().to_vec();
+        attest.extend_from_slice(&TPM_ST_ATTEST_CERTIFY.to_be_bytes());
+        append_sized(&mut attest, b"signer");
+        append_sized(&mut attest, nonce);
+        attest.extend_from_slice(&100u64.to_be_bytes());
+        attest.extend_from_slice(&1u32.to_be_bytes());
+        attest.extend_from_slice(&2u32.to_be_bytes());
+        attest.push(1);
+        attest.extend_from_slice(&3u64.to_be_bytes());
+        append_sized(
+            &mut attest,
+            &tpm_key_name(&keccak256, name_algorithm, public_area),
+        );
+        append_sized(&mut attest, b"qualified name");
+        let attestation = TpmKeyAttestation {
+            signature: secp256k1
+                .sign(&ak_private_key, &keccak256.hash(&attest))
+                .unwrap(),
+            attest,
+        };
+
+        let info = parse_tpm_certify_info(&attestation.attest).unwrap();
+        assert_eq!(info.clock, 100);
+        assert!(info.safe);
+        let verify = |public_area: &[u8], nonce: &[u8]| {
+            verify_tpm_key_attestation(
+                &secp256k1,
+                &keccak256,
+                &ak_public_key,
+                &attestation,
+                name_algorithm,
+                public_area,
+                nonce,
+            )
+        };
+        assert!(verify(public_area, nonce));
+        assert!(!verify(b"another key", nonce));
+        assert!(!verify(public_area, b"stale nonce"));
+        let attest = &attestation.attest;
+        assert!(parse_tpm_certify_info(&attest[..attest.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! AES-256-GCM authenticated encryption functions of NIST SP 800-38D.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+use aes_gcm::{
+    aead::{Aead as _, KeyInit, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    error::WedprError,
+    tool::random_bytes,
+    traits::{Aead, AlgorithmInfo, BlockCipher},
+};
+
+/// Size of an AES-256 key in bytes.
+pub const AES256_KEY_SIZE_IN_BYTES: usize = 32;
+/// Size of a GCM nonce, i.e. the IV, in bytes.
+pub const AES_GCM_NONCE_SIZE_IN_BYTES: usize = 12;
+/// Size of a GCM authentication tag in bytes.
+pub const AES_GCM_TAG_SIZE_IN_BYTES: usize = 16;
+
+/// Implements AES-256-GCM as a BlockCipher instance, where a ciphertext is
+/// the encrypted message followed by the authentication tag.
+///
+/// A nonce MUST NOT be reused with the same key, which reveals the XOR of
+/// messages and allows forging tags. Random nonces of generate_nonce are
+/// safe for up to 2^32 messages per key.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct WedprAes256Gcm {}
+
+impl AlgorithmInfo for WedprAes256Gcm {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "aes-256-gcm",
+            oid: Some("2.16.840.1.101.3.4.1.46"),
+            security_bits: 256,
+            private_key_size: Some(AES256_KEY_SIZE_IN_BYTES),
+            public_key_sizes: &[],
+            signature_size: None,
+            output_size: None,
+        }
+    }
+}
+
+impl BlockCipher for WedprAes256Gcm {
+    fn encrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        message: &T,
+        key: &T,
+        iv: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        seal(message.as_ref(), key.as_ref(), iv.as_ref(), &[])
+    }
+
+    fn decrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        ciphertext: &T,
+        key: &T,
+        iv: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        open(ciphertext.as_ref(), key.as_ref(), iv.as_ref(), &[])
+    }
+}
+
+impl Aead for WedprAes256Gcm {
+    fn encrypt_with_aad<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        message: &T,
+        key: &T,
+        nonce: &T,
+        aad: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        seal(message.as_ref(), key.as_ref(), nonce.as_ref(), aad.as_ref())
+    }
+
+    fn decrypt_with_aad<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        ciphertext: &T,
+        key: &T,
+        nonce: &T,
+        aad: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        open(
+            ciphertext.as_ref(),
+            key.as_ref(),
+            nonce.as_ref(),
+            aad.as_ref(),
+        )
+    }
+
+    fn generate_key(&self) -> Vec<u8> {
+        random_bytes(AES256_KEY_SIZE_IN_BYTES)
+    }
+
+    fn generate_nonce(&self) -> Vec<u8> {
+        random_bytes(AES_GCM_NONCE_SIZE_IN_BYTES)
+    }
+}
+
+fn seal(
+    message: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let cipher = new_cipher(key, nonce)?;
+    match cipher
+        .encrypt(Nonce::from_slice(nonce), Payload { msg: message, aad })
+    {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("AES-GCM encrypt failed");
+            Err(WedprError::ArgumentError)
+        },
+    }
+}
+
+fn open(
+    ciphertext: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let cipher = new_cipher(key, nonce)?;
+    if ciphertext.len() < AES_GCM_TAG_SIZE_IN_BYTES {
+        wedpr_println!("AES-GCM ciphertext is too short");
+        return Err(WedprError::FormatError);
+    }
+    match cipher.decrypt(Nonce::from_slice(nonce), Payload {
+        msg: ciphertext,
+        aad,
+    }) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("AES-GCM authentication failed");
+            Err(WedprError::VerificationError)
+        },
+    }
+}
+
+fn new_cipher(key: &[u8], nonce: &[u8]) -> Result<Aes256Gcm, WedprError> {
+    if key.len() != AES256_KEY_SIZE_IN_BYTES
+        || nonce.len() != AES_GCM_NONCE_SIZE_IN_BYTES
+    {
+        wedpr_println!("Invalid AES-GCM key or nonce size");
+        return Err(WedprError::ArgumentError);
+    }
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_common_coder_hex::WedprHex;
+    use wedpr_l_utils::traits::Coder;
+
+    #[test]
+    fn test_aes_256_gcm() {
+        let aes = WedprAes256Gcm::default();
+        let key = aes.generate_key();
+        let nonce = aes.generate_nonce();
+        let message = b"message of AES-256-GCM".to_vec();
+        let ciphertext = aes.encrypt(&message, &key, &nonce).unwrap();
+        assert_eq!(ciphertext.len(), message.len() + AES_GCM_TAG_SIZE_IN_BYTES);
+        assert_eq!(aes.decrypt(&ciphertext, &key, &nonce).unwrap(), message);
+        let mut tampered = ciphertext.clone();
+        tampered[0] ^= 1;
+        assert_eq!(
+            aes.decrypt(&tampered, &key, &nonce),
+            Err(WedprError::VerificationError)
+        );
+        assert!(aes.encrypt(&message, &key[1..].to_vec(), &nonce).is_err());
+        assert!(aes.encrypt(&message, &key, &key).is_err());
+        assert!(aes
+            .decrypt(&ciphertext[..15].to_vec(), &key, &nonce)
+            .is_err());
+
+        let aad = b"header".to_vec();
+        let ciphertext =
+            aes.encrypt_with_aad(&message, &key, &nonce, &aad).unwrap();
+        assert_eq!(
+            aes.decrypt_with_aad(&ciphertext, &key, &nonce, &aad)
+                .unwrap(),
+            message
+        );
+        assert!(aes
+            .decrypt_with_aad(&ciphertext, &key, &nonce, &b"other".to_vec())
+            .is_err());
+        assert!(aes.decrypt(&ciphertext, &key, &nonce).is_err());
+
+        // Test case 16 of the GCM specification by McGrew and Viega.
+        let hex = WedprHex::default();
+        let key = hex
+            .decode(
+                &[
+                    "feffe9928665731c6d6a8f9467308308",
+                    "feffe9928665731c6d6a8f9467308308",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let nonce = hex.decode("cafebabefacedbaddecaf888").unwrap();
+        let message = hex
+            .decode(
+                &[
+                    "d9313225f88406e5a55909c5aff5269a",
+                    "86a7a9531534f7da2e4c303d8a318a72",
+                    "1c3c0c95956809532fcf0e2449a6b525",
+                    "b16aedf5aa0de657ba637b39",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let aad = hex
+            .decode("feedfacedeadbeeffeedfacedeadbeefabaddad2")
+            .unwrap();
+        let expected_ciphertext = hex
+            .decode(
+                &[
+                    "522dc1f099567d07f47f37a32a84427d",
+                    "643a8cdcbfe5c0c97598a2bd2555d1aa",
+                    "8cb08e48590dbb3da7b08b1056828838",
+                    "c5f61e6393ba7a0abcc9f662",
+                    "76fc6ece0f4e1768cddf8853bb2d551b",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        assert_eq!(
+            aes.encrypt_with_aad(&message, &key, &nonce, &aad).unwrap(),
+            expected_ciphertext
+        );
+        assert!(WedprAes256Gcm::algorithm_info()
+            .check_private_key(&key)
+            .is_ok());
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! ChaCha20-Poly1305 authenticated encryption functions of RFC 8439, which
+//! run fast in software on platforms without AES hardware acceleration.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+use chacha20poly1305::{
+    aead::{Aead as _, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    error::WedprError,
+    tool::random_bytes,
+    traits::{Aead, AlgorithmInfo, BlockCipher},
+};
+
+/// Size of a ChaCha20 key in bytes.
+pub const CHACHA20_KEY_SIZE_IN_BYTES: usize = 32;
+/// Size of a ChaCha20-Poly1305 nonce in bytes.
+pub const CHACHA20_NONCE_SIZE_IN_BYTES: usize = 12;
+/// Size of a Poly1305 authentication tag in bytes.
+pub const POLY1305_TAG_SIZE_IN_BYTES: usize = 16;
+
+/// Implements ChaCha20-Poly1305 as an Aead instance, with the same key,
+/// nonce and tag sizes as WedprAes256Gcm, so that either can replace the
+/// other. A nonce MUST NOT be reused with the same key.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct WedprChaCha20Poly1305 {}
+
+impl AlgorithmInfo for WedprChaCha20Poly1305 {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "chacha20-poly1305",
+            oid: Some("1.2.840.113549.1.9.16.3.18"),
+            security_bits: 256,
+            private_key_size: Some(CHACHA20_KEY_SIZE_IN_BYTES),
+            public_key_sizes: &[],
+            signature_size: None,
+            output_size: None,
+        }
+    }
+}
+
+impl BlockCipher for WedprChaCha20Poly1305 {
+    fn encrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        message: &T,
+        key: &T,
+        iv: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        seal(message.as_ref(), key.as_ref(), iv.as_ref(), &[])
+    }
+
+    fn decrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        ciphertext: &T,
+        key: &T,
+        iv: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        open(ciphertext.as_ref(), key.as_ref(), iv.as_ref(), &[])
+    }
+}
+
+impl Aead for WedprChaCha20Poly1305 {
+    fn encrypt_with_aad<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        message: &T,
+        key: &T,
+        nonce: &T,
+        aad: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        seal(message.as_ref(), key.as_ref(), nonce.as_ref(), aad.as_ref())
+    }
+
+    fn decrypt_with_aad<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        ciphertext: &T,
+        key: &T,
+        nonce: &T,
+        aad: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        open(
+            ciphertext.as_ref(),
+            key.as_ref(),
+            nonce.as_ref(),
+            aad.as_ref(),
+        )
+    }
+
+    fn generate_key(&self) -> Vec<u8> {
+        random_bytes(CHACHA20_KEY_SIZE_IN_BYTES)
+    }
+
+    fn generate_nonce(&self) -> Vec<u8> {
+        random_bytes(CHACHA20_NONCE_SIZE_IN_BYTES)
+    }
+}
+
+fn seal(
+    message: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let cipher = new_cipher(key, nonce)?;
+    match cipher
+        .encrypt(Nonce::from_slice(nonce), Payload { msg: message, aad })
+    {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("ChaCha20-Poly1305 encrypt failed");
+            Err(WedprError::ArgumentError)
+        },
+    }
+}
+
+fn open(
+    ciphertext: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let cipher = new_cipher(key, nonce)?;
+    if ciphertext.len() < POLY1305_TAG_SIZE_IN_BYTES {
+        wedpr_println!("ChaCha20-Poly1305 ciphertext is too short");
+        return Err(WedprError::FormatError);
+    }
+    match cipher.decrypt(Nonce::from_slice(nonce), Payload {
+        msg: ciphertext,
+        aad,
+    }) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("ChaCha20-Poly1305 authentication failed");
+            Err(WedprError::VerificationError)
+        },
+    }
+}
+
+fn new_cipher(
+    key: &[u8],
+    nonce: &[u8],
+) -> Result<ChaCha20Poly1305, WedprError> {
+    if key.len() != CHACHA20_KEY_SIZE_IN_BYTES
+        || nonce.len() != CHACHA20_NONCE_SIZE_IN_BYTES
+    {
+        wedpr_println!("Invalid ChaCha20-Poly1305 key or nonce size");
+        return Err(WedprError::ArgumentError);
+    }
+    Ok(ChaCha20Poly1305::new(Key::from_slice(key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_common_coder_hex::WedprHex;
+    use wedpr_l_crypto_block_cipher_aes::WedprAes256Gcm;
+    use wedpr_l_utils::traits::Coder;
+
+    /// Seals and opens a message by any Aead backend.
+    fn round_trip<A: Aead>(aead: &A) {
+        let key = aead.generate_key();
+        let nonce = aead.generate_nonce();
+        let message = b"message of any AEAD backend".to_vec();
+        let aad = b"header".to_vec();
+        let ciphertext =
+            aead.encrypt_with_aad(&message, &key, &nonce, &aad).unwrap();
+        assert_eq!(
+            aead.decrypt_with_aad(&ciphertext, &key, &nonce, &aad)
+                .unwrap(),
+            message
+        );
+        let mut tampered = ciphertext.clone();
+        tampered[0] ^= 1;
+        assert_eq!(
+            aead.decrypt_with_aad(&tampered, &key, &nonce, &aad),
+            Err(WedprError::VerificationError)
+        );
+        assert!(aead.decrypt(&ciphertext, &key, &nonce).is_err());
+    }
+
+    #[test]
+    fn test_chacha20_poly1305() {
+        let chacha20 = WedprChaCha20Poly1305::default();
+        round_trip(&chacha20);
+        round_trip(&WedprAes256Gcm::default());
+        let key = chacha20.generate_key();
+        assert!(chacha20.encrypt(&key, &key, &key).is_err());
+
+        // Test vector in section 2.8.2 of RFC 8439.
+        let hex = WedprHex::default();
+        let key = hex
+            .decode(
+                &[
+                    "808182838485868788898a8b8c8d8e8f",
+                    "909192939495969798999a9b9c9d9e9f",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let nonce = hex.decode("070000004041424344454647").unwrap();
+        let aad = hex.decode("50515253c0c1c2c3c4c5c6c7").unwrap();
+        let message = b"Ladies and Gentlemen of the class of '99: If I could \
+                        offer you only one tip for the future, sunscreen \
+                        would be it."
+            .to_vec();
+        let expected_ciphertext = hex
+            .decode(
+                &[
+                    "d31a8d34648e60db7b86afbc53ef7ec2",
+                    "a4aded51296e08fea9e2b5a736ee62d6",
+                    "3dbea45e8ca9671282fafb69da92728b",
+                    "1a71de0a9e060b2905d6a5b67ecd3b36",
+                    "92ddbd7f2d778b8c9803aee328091b58",
+                    "fab324e4fad675945585808b4831d7bc",
+                    "3ff4def08e4b7a9de576d26586cec64b",
+                    "6116",
+                    "1ae10b594f09e26a7e902ecbd0600691",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        assert_eq!(
+            chacha20
+                .encrypt_with_aad(&message, &key, &nonce, &aad)
+                .unwrap(),
+            expected_ciphertext
+        );
+    }
+}
@@ -0,0 +1,334 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! SM4 block cipher functions of GB/T 32907, in GCM mode of RFC 8998 and in
+//! CBC mode with PKCS#7 padding.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+use aes_gcm::{
+    aead::{consts::U12, Aead as _, KeyInit, Payload},
+    AesGcm, Nonce,
+};
+use cbc::cipher::{
+    block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit,
+};
+use sm4::Sm4;
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    error::WedprError,
+    tool::random_bytes,
+    traits::{Aead, AlgorithmInfo, BlockCipher},
+};
+
+/// Size of a SM4 key in bytes.
+pub const SM4_KEY_SIZE_IN_BYTES: usize = 16;
+/// Size of a SM4 block, i.e. the CBC IV, in bytes.
+pub const SM4_BLOCK_SIZE_IN_BYTES: usize = 16;
+/// Size of a GCM nonce in bytes.
+pub const SM4_GCM_NONCE_SIZE_IN_BYTES: usize = 12;
+/// Size of a GCM authentication tag in bytes.
+pub const SM4_GCM_TAG_SIZE_IN_BYTES: usize = 16;
+
+type Sm4Gcm = AesGcm<Sm4, U12>;
+
+/// Implements SM4-GCM as a BlockCipher instance, where a ciphertext is the
+/// encrypted message followed by the authentication tag. A nonce MUST NOT be
+/// reused with the same key.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct WedprSm4Gcm {}
+
+impl AlgorithmInfo for WedprSm4Gcm {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "sm4-gcm",
+            oid: Some("1.2.156.10197.1.104.8"),
+            security_bits: 128,
+            private_key_size: Some(SM4_KEY_SIZE_IN_BYTES),
+            public_key_sizes: &[],
+            signature_size: None,
+            output_size: None,
+        }
+    }
+}
+
+impl BlockCipher for WedprSm4Gcm {
+    fn encrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        message: &T,
+        key: &T,
+        iv: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        gcm_seal(message.as_ref(), key.as_ref(), iv.as_ref(), &[])
+    }
+
+    fn decrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        ciphertext: &T,
+        key: &T,
+        iv: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        gcm_open(ciphertext.as_ref(), key.as_ref(), iv.as_ref(), &[])
+    }
+}
+
+impl Aead for WedprSm4Gcm {
+    fn encrypt_with_aad<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        message: &T,
+        key: &T,
+        nonce: &T,
+        aad: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        gcm_seal(message.as_ref(), key.as_ref(), nonce.as_ref(), aad.as_ref())
+    }
+
+    fn decrypt_with_aad<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        ciphertext: &T,
+        key: &T,
+        nonce: &T,
+        aad: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        gcm_open(
+            ciphertext.as_ref(),
+            key.as_ref(),
+            nonce.as_ref(),
+            aad.as_ref(),
+        )
+    }
+
+    fn generate_key(&self) -> Vec<u8> {
+        random_bytes(SM4_KEY_SIZE_IN_BYTES)
+    }
+
+    fn generate_nonce(&self) -> Vec<u8> {
+        random_bytes(SM4_GCM_NONCE_SIZE_IN_BYTES)
+    }
+}
+
+/// Implements SM4-CBC with PKCS#7 padding as a BlockCipher instance, for
+/// interoperating with existing guomi deployments. CBC does not authenticate
+/// ciphertexts, thus WedprSm4Gcm is preferred for new protocols.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct WedprSm4Cbc {}
+
+impl AlgorithmInfo for WedprSm4Cbc {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "sm4-cbc",
+            oid: Some("1.2.156.10197.1.104.2"),
+            security_bits: 128,
+            private_key_size: Some(SM4_KEY_SIZE_IN_BYTES),
+            public_key_sizes: &[],
+            signature_size: None,
+            output_size: None,
+        }
+    }
+}
+
+impl BlockCipher for WedprSm4Cbc {
+    fn encrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        message: &T,
+        key: &T,
+        iv: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        match cbc::Encryptor::<Sm4>::new_from_slices(key.as_ref(), iv.as_ref())
+        {
+            Ok(v) => Ok(v.encrypt_padded_vec_mut::<Pkcs7>(message.as_ref())),
+            Err(_) => {
+                wedpr_println!("Invalid SM4-CBC key or IV size");
+                Err(WedprError::ArgumentError)
+            },
+        }
+    }
+
+    fn decrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        ciphertext: &T,
+        key: &T,
+        iv: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        let cipher = match cbc::Decryptor::<Sm4>::new_from_slices(
+            key.as_ref(),
+            iv.as_ref(),
+        ) {
+            Ok(v) => v,
+            Err(_) => {
+                wedpr_println!("Invalid SM4-CBC key or IV size");
+                return Err(WedprError::ArgumentError);
+            },
+        };
+        match cipher.decrypt_padded_vec_mut::<Pkcs7>(ciphertext.as_ref()) {
+            Ok(v) => Ok(v),
+            Err(_) => {
+                wedpr_println!("SM4-CBC decrypt failed");
+                Err(WedprError::DecodeError)
+            },
+        }
+    }
+}
+
+impl WedprSm4Cbc {
+    /// Generates a random key.
+    pub fn generate_key(&self) -> Vec<u8> {
+        random_bytes(SM4_KEY_SIZE_IN_BYTES)
+    }
+
+    /// Generates a random IV, which must be unpredictable for every message.
+    pub fn generate_iv(&self) -> Vec<u8> {
+        random_bytes(SM4_BLOCK_SIZE_IN_BYTES)
+    }
+}
+
+fn gcm_seal(
+    message: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let cipher = new_gcm_cipher(key, nonce)?;
+    match cipher
+        .encrypt(Nonce::from_slice(nonce), Payload { msg: message, aad })
+    {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("SM4-GCM encrypt failed");
+            Err(WedprError::ArgumentError)
+        },
+    }
+}
+
+fn gcm_open(
+    ciphertext: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let cipher = new_gcm_cipher(key, nonce)?;
+    if ciphertext.len() < SM4_GCM_TAG_SIZE_IN_BYTES {
+        wedpr_println!("SM4-GCM ciphertext is too short");
+        return Err(WedprError::FormatError);
+    }
+    match cipher.decrypt(Nonce::from_slice(nonce), Payload {
+        msg: ciphertext,
+        aad,
+    }) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("SM4-GCM authentication failed");
+            Err(WedprError::VerificationError)
+        },
+    }
+}
+
+fn new_gcm_cipher(key: &[u8], nonce: &[u8]) -> Result<Sm4Gcm, WedprError> {
+    if nonce.len() != SM4_GCM_NONCE_SIZE_IN_BYTES {
+        wedpr_println!("Invalid SM4-GCM nonce size");
+        return Err(WedprError::ArgumentError);
+    }
+    match Sm4Gcm::new_from_slice(key) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Invalid SM4-GCM key size");
+            Err(WedprError::ArgumentError)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_common_coder_hex::WedprHex;
+    use wedpr_l_utils::traits::Coder;
+
+    #[test]
+    fn test_sm4() {
+        let gcm = WedprSm4Gcm::default();
+        let cbc = WedprSm4Cbc::default();
+        let key = gcm.generate_key();
+        let nonce = gcm.generate_nonce();
+        let iv = cbc.generate_iv();
+        let message = b"message of SM4".to_vec();
+
+        let ciphertext = gcm.encrypt(&message, &key, &nonce).unwrap();
+        assert_eq!(gcm.decrypt(&ciphertext, &key, &nonce).unwrap(), message);
+        let mut tampered = ciphertext.clone();
+        tampered[0] ^= 1;
+        assert_eq!(
+            gcm.decrypt(&tampered, &key, &nonce),
+            Err(WedprError::VerificationError)
+        );
+        assert!(gcm.encrypt(&message, &key, &iv).is_err());
+
+        let ciphertext = cbc.encrypt(&message, &key, &iv).unwrap();
+        assert_eq!(ciphertext.len(), SM4_BLOCK_SIZE_IN_BYTES);
+        assert_eq!(cbc.decrypt(&ciphertext, &key, &iv).unwrap(), message);
+        assert!(cbc.encrypt(&message, &key, &nonce).is_err());
+        assert!(cbc.decrypt(&ciphertext[1..].to_vec(), &key, &iv).is_err());
+
+        // Test vectors of SM4-GCM in appendix A.1 of RFC 8998, where the
+        // CBC ciphertext of the same message is computed by OpenSSL.
+        let hex = WedprHex::default();
+        let key = hex.decode("0123456789abcdeffedcba9876543210").unwrap();
+        let message = hex
+            .decode(
+                &[
+                    "aaaaaaaaaaaaaaaabbbbbbbbbbbbbbbb",
+                    "ccccccccccccccccdddddddddddddddd",
+                    "eeeeeeeeeeeeeeeeffffffffffffffff",
+                    "eeeeeeeeeeeeeeeeaaaaaaaaaaaaaaaa",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let nonce = hex.decode("00001234567800000000abcd").unwrap();
+        let aad = hex
+            .decode("feedfacedeadbeeffeedfacedeadbeefabaddad2")
+            .unwrap();
+        let expected_ciphertext = hex
+            .decode(
+                &[
+                    "17f399f08c67d5ee19d0dc9969c4bb7d",
+                    "5fd46fd3756489069157b282bb200735",
+                    "d82710ca5c22f0ccfa7cbf93d496ac15",
+                    "a56834cbcf98c397b4024a2691233b8d",
+                    "83de3541e4c2b58177e065a9bf7b62ec",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        assert_eq!(
+            gcm.encrypt_with_aad(&message, &key, &nonce, &aad).unwrap(),
+            expected_ciphertext
+        );
+        assert_eq!(
+            gcm.decrypt_with_aad(&expected_ciphertext, &key, &nonce, &aad)
+                .unwrap(),
+            message
+        );
+
+        let iv = hex.decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let expected_ciphertext = hex
+            .decode(
+                &[
+                    "9554bcddf2d371452bffd93df8d46187",
+                    "2360664050b1ae28e3e25ab2539ededb",
+                    "ec17435cee4d9e7c413b774acf6ad121",
+                    "aa16d86ff8e97ed458e1746bfa7bb74c",
+                    "bf45ec165f8b0fd62a2b84465bca03af",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        assert_eq!(
+            cbc.encrypt(&message, &key, &iv).unwrap(),
+            expected_ciphertext
+        );
+        assert_eq!(
+            cbc.decrypt(&expected_ciphertext, &key, &iv).unwrap(),
+            message
+        );
+    }
+}
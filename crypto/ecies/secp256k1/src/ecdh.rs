@@ -0,0 +1,61 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Secp256k1 ECDH key agreement functions.
+
+use secp256k1::{ecdh::SharedSecret, PublicKey, Secp256k1, SecretKey};
+use wedpr_l_utils::error::WedprError;
+
+/// Generates an ephemeral key pair for key agreement, where the first part is
+/// the uncompressed public key, and the second part is the private key.
+pub fn generate_keypair() -> (Vec<u8>, Vec<u8>) {
+    let secp256k1 = Secp256k1::signing_only();
+    let (secret_key, public_key) =
+        secp256k1.generate_keypair(&mut rand::thread_rng());
+    (
+        public_key.serialize_uncompressed().to_vec(),
+        secret_key.as_ref().to_vec(),
+    )
+}
+
+/// Computes the 32-byte shared secret between a private key and a peer public
+/// key, i.e. the SHA-256 hash of the compressed shared point.
+pub fn compute_shared_secret(
+    private_key: &[u8],
+    peer_public_key: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let secret_key = match SecretKey::from_slice(private_key) {
+        Ok(v) => v,
+        Err(_) => {
+            wedpr_println!("Parsing private key failed");
+            return Err(WedprError::FormatError);
+        },
+    };
+    let public_key = match PublicKey::from_slice(peer_public_key) {
+        Ok(v) => v,
+        Err(_) => {
+            wedpr_println!("Parsing peer public key failed");
+            return Err(WedprError::FormatError);
+        },
+    };
+    Ok(SharedSecret::new(&public_key, &secret_key)
+        .as_ref()
+        .to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ecdh() {
+        let (public_key1, private_key1) = generate_keypair();
+        let (public_key2, private_key2) = generate_keypair();
+        let shared_secret1 =
+            compute_shared_secret(&private_key1, &public_key2).unwrap();
+        let shared_secret2 =
+            compute_shared_secret(&private_key2, &public_key1).unwrap();
+        assert_eq!(shared_secret1, shared_secret2);
+        assert_eq!(shared_secret1.len(), 32);
+        assert!(compute_shared_secret(&private_key1, &private_key2).is_err());
+    }
+}
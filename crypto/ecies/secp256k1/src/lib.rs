@@ -2,15 +2,35 @@
 
 //! Secp256k1 ECIES functions.
 
-use wedpr_l_utils::{error::WedprError, traits::Ecies};
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    error::WedprError,
+    traits::{AlgorithmInfo, Ecies},
+};
 
 #[macro_use]
 extern crate wedpr_l_macros;
 
+pub mod ecdh;
+
 /// Implements a ECIES instance on Secp256k1 curve.
 #[derive(Default, Debug, Clone)]
 pub struct WedprSecp256k1Ecies {}
 
+impl AlgorithmInfo for WedprSecp256k1Ecies {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "secp256k1-ecies",
+            oid: None,
+            security_bits: 128,
+            private_key_size: Some(32),
+            public_key_sizes: &[65, 33],
+            signature_size: None,
+            output_size: None,
+        }
+    }
+}
+
 impl Ecies for WedprSecp256k1Ecies {
     fn encrypt<T: ?Sized + AsRef<[u8]>>(
         &self,
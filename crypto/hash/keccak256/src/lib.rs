@@ -5,17 +5,76 @@
 extern crate sha3;
 use sha3::{Digest, Keccak256};
 
-use wedpr_l_utils::traits::Hash;
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    traits::{AlgorithmInfo, Hash, HashStream, StreamingHash},
+};
 
-/// Implements Keccak256 as a Hash instance.
+/// Size of a Keccak256 digest in bytes.
+pub const KECCAK256_HASH_SIZE_IN_BYTES: usize = 32;
+
+/// Implements Keccak256 as a Hash instance, which uses the original Keccak
+/// padding instead of SHA3-256, and is the message hash of FISCO-BCOS and
+/// Ethereum transactions.
 #[derive(Default, Debug, Clone)]
 pub struct WedprKeccak256 {}
 
+impl AlgorithmInfo for WedprKeccak256 {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "keccak256",
+            oid: None,
+            security_bits: 128,
+            private_key_size: None,
+            public_key_sizes: &[],
+            signature_size: None,
+            output_size: Some(KECCAK256_HASH_SIZE_IN_BYTES),
+        }
+    }
+}
+
 impl Hash for WedprKeccak256 {
     fn hash<T: ?Sized + AsRef<[u8]>>(&self, input: &T) -> Vec<u8> {
+        self.hash_to_array(input).to_vec()
+    }
+}
+
+impl WedprKeccak256 {
+    /// Hashes an input as by hash into a fixed-size digest, which is not
+    /// allocated on the heap, e.g. for hashing transactions in hot paths.
+    pub fn hash_to_array<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        input: &T,
+    ) -> [u8; KECCAK256_HASH_SIZE_IN_BYTES] {
         let mut hash_algorithm = Keccak256::default();
         hash_algorithm.input(input);
-        hash_algorithm.result().to_vec()
+        let mut digest = [0u8; KECCAK256_HASH_SIZE_IN_BYTES];
+        digest.copy_from_slice(&hash_algorithm.result());
+        digest
+    }
+}
+
+/// Incremental Keccak256 hash computation.
+#[derive(Default, Clone)]
+pub struct WedprKeccak256Stream {
+    hash_algorithm: Keccak256,
+}
+
+impl HashStream for WedprKeccak256Stream {
+    fn update<T: ?Sized + AsRef<[u8]>>(&mut self, input: &T) {
+        self.hash_algorithm.input(input);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        self.hash_algorithm.result().to_vec()
+    }
+}
+
+impl StreamingHash for WedprKeccak256 {
+    type Stream = WedprKeccak256Stream;
+
+    fn stream(&self) -> Self::Stream {
+        WedprKeccak256Stream::default()
     }
 }
 
@@ -36,5 +95,28 @@ mod tests {
             expected_hash.to_vec(),
             keccak256.hash(&BASE64_ENCODED_TEST_MESSAGE)
         );
+        assert_eq!(
+            expected_hash,
+            keccak256.hash_to_array(&BASE64_ENCODED_TEST_MESSAGE)
+        );
+
+        // Keccak256 of the empty input differs from SHA3-256 of it.
+        let empty_hash: [u8; 32] = [
+            197, 210, 70, 1, 134, 247, 35, 60, 146, 126, 125, 178, 220, 199, 3,
+            192, 229, 0, 182, 83, 202, 130, 39, 59, 123, 250, 216, 4, 93, 133,
+            164, 112,
+        ];
+        assert_eq!(empty_hash.to_vec(), keccak256.hash(""));
+
+        let mut stream = keccak256.stream();
+        stream.update(&BASE64_ENCODED_TEST_MESSAGE[..10]);
+        stream.update(&BASE64_ENCODED_TEST_MESSAGE[10..]);
+        assert_eq!(expected_hash.to_vec(), stream.finalize());
+        assert_eq!(empty_hash.to_vec(), keccak256.stream().finalize());
+        let mut reader = std::io::Cursor::new(BASE64_ENCODED_TEST_MESSAGE);
+        assert_eq!(
+            expected_hash.to_vec(),
+            keccak256.hash_reader(&mut reader).unwrap()
+        );
     }
 }
@@ -0,0 +1,93 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! SHA-256 hash functions.
+
+extern crate sha2;
+use sha2::{Digest, Sha256};
+
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    traits::{AlgorithmInfo, Hash, HashStream, StreamingHash},
+};
+
+/// Implements SHA-256 as a Hash instance.
+#[derive(Default, Debug, Clone)]
+pub struct WedprSha256 {}
+
+impl AlgorithmInfo for WedprSha256 {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "sha256",
+            oid: Some("2.16.840.1.101.3.4.2.1"),
+            security_bits: 128,
+            private_key_size: None,
+            public_key_sizes: &[],
+            signature_size: None,
+            output_size: Some(32),
+        }
+    }
+}
+
+impl Hash for WedprSha256 {
+    fn hash<T: ?Sized + AsRef<[u8]>>(&self, input: &T) -> Vec<u8> {
+        let mut hash_algorithm = Sha256::default();
+        hash_algorithm.input(input);
+        hash_algorithm.result().to_vec()
+    }
+}
+
+/// Incremental SHA-256 hash computation.
+#[derive(Default, Clone)]
+pub struct WedprSha256Stream {
+    hash_algorithm: Sha256,
+}
+
+impl HashStream for WedprSha256Stream {
+    fn update<T: ?Sized + AsRef<[u8]>>(&mut self, input: &T) {
+        self.hash_algorithm.input(input);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        self.hash_algorithm.result().to_vec()
+    }
+}
+
+impl StreamingHash for WedprSha256 {
+    type Stream = WedprSha256Stream;
+
+    fn stream(&self) -> Self::Stream {
+        WedprSha256Stream::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_utils::tool::string_to_bytes_utf8;
+
+    #[test]
+    fn test_sha256() {
+        let sha256 = WedprSha256::default();
+        let expected_hash: [u8; 32] = [
+            186, 120, 22, 191, 143, 1, 207, 234, 65, 65, 64, 222, 93, 174, 34,
+            35, 176, 3, 97, 163, 150, 23, 122, 156, 180, 16, 255, 97, 242, 0,
+            21, 173,
+        ];
+        assert_eq!(
+            expected_hash.to_vec(),
+            sha256.hash(&string_to_bytes_utf8("abc"))
+        );
+
+        let mut stream = sha256.stream();
+        stream.update("a");
+        stream.update("bc");
+        assert_eq!(expected_hash.to_vec(), stream.finalize());
+        // Inputs larger than the reader buffer.
+        let large_input = vec![7u8; 200_000];
+        let mut reader = std::io::Cursor::new(&large_input);
+        assert_eq!(
+            sha256.hash(&large_input),
+            sha256.hash_reader(&mut reader).unwrap()
+        );
+    }
+}
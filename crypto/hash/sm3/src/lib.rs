@@ -1,14 +1,31 @@
 // Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
 
-//! SM3 hash functions.
+//! SM3 hash functions of GM/T 0004-2012, used by FISCO-BCOS GM version.
 
 use wedpr_l_libsm::sm3::hash::Sm3Hash;
-use wedpr_l_utils::traits::Hash;
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    traits::{AlgorithmInfo, Hash},
+};
 
 /// Implements SM3 as a Hash instance.
 #[derive(Default, Debug, Clone)]
 pub struct WedprSm3 {}
 
+impl AlgorithmInfo for WedprSm3 {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "sm3",
+            oid: Some("1.2.156.10197.1.401"),
+            security_bits: 128,
+            private_key_size: None,
+            public_key_sizes: &[],
+            signature_size: None,
+            output_size: Some(32),
+        }
+    }
+}
+
 impl Hash for WedprSm3 {
     fn hash<T: ?Sized + AsRef<[u8]>>(&self, input: &T) -> Vec<u8> {
         let mut hash_algorithm = Sm3Hash::new(input.as_ref());
@@ -33,5 +50,19 @@ mod tests {
             expected_hash.to_vec(),
             sm3.hash(&BASE64_ENCODED_TEST_MESSAGE)
         );
+
+        // Examples 1 and 2 of GM/T 0004-2012.
+        let abc_hash: [u8; 32] = [
+            102, 199, 240, 244, 98, 238, 237, 217, 209, 242, 212, 107, 220, 16,
+            228, 226, 65, 103, 196, 135, 92, 242, 247, 162, 41, 125, 160, 43,
+            143, 75, 168, 224,
+        ];
+        assert_eq!(abc_hash.to_vec(), sm3.hash("abc"));
+        let block_hash: [u8; 32] = [
+            222, 190, 159, 249, 34, 117, 184, 161, 56, 96, 72, 137, 193, 142,
+            90, 77, 111, 219, 112, 229, 56, 126, 87, 101, 41, 61, 203, 163,
+            156, 12, 87, 50,
+        ];
+        assert_eq!(block_hash.to_vec(), sm3.hash(&"abcd".repeat(16)));
     }
 }
@@ -0,0 +1,241 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! HMAC and HKDF functions of RFC 5869 over SHA-256 and SM3, for deriving
+//! ECIES and session keys from shared secrets, and password-based key
+//! derivation functions.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+pub mod password;
+
+use wedpr_l_crypto_hash_sha256::WedprSha256;
+use wedpr_l_crypto_hash_sm3::WedprSm3;
+use wedpr_l_crypto_mac_hmac::{WedprHmac, HMAC_SHA256_BLOCK_SIZE_IN_BYTES};
+use wedpr_l_utils::{
+    error::WedprError,
+    policy::check_global_algorithm,
+    traits::{AlgorithmInfo, Hash, Kdf, Mac},
+};
+
+/// Output size of SHA-256 and SM3 in bytes.
+pub const HKDF_SHA256_HASH_SIZE_IN_BYTES: usize = 32;
+/// Maximum number of expanded output blocks of HKDF.
+const HKDF_MAX_OUTPUT_BLOCKS: usize = 255;
+
+/// Creates an HMAC-SHA256 instance.
+pub fn hmac_sha256() -> WedprHmac<WedprSha256> {
+    WedprHmac::new(WedprSha256::default(), HMAC_SHA256_BLOCK_SIZE_IN_BYTES)
+}
+
+/// Creates an HMAC-SM3 instance.
+pub fn hmac_sm3() -> WedprHmac<WedprSm3> {
+    WedprHmac::new(WedprSm3::default(), HMAC_SHA256_BLOCK_SIZE_IN_BYTES)
+}
+
+/// Implements HKDF as a Kdf instance over an HMAC instance.
+#[derive(Debug, Clone)]
+pub struct WedprHkdf<H: Hash> {
+    hmac: WedprHmac<H>,
+    hash_size_in_bytes: usize,
+}
+
+/// HKDF over SHA-256.
+pub type WedprHkdfSha256 = WedprHkdf<WedprSha256>;
+/// HKDF over SM3.
+pub type WedprHkdfSm3 = WedprHkdf<WedprSm3>;
+
+impl Default for WedprHkdf<WedprSha256> {
+    fn default() -> Self {
+        WedprHkdf::new(hmac_sha256(), HKDF_SHA256_HASH_SIZE_IN_BYTES)
+    }
+}
+
+impl Default for WedprHkdf<WedprSm3> {
+    fn default() -> Self {
+        WedprHkdf::new(hmac_sm3(), HKDF_SHA256_HASH_SIZE_IN_BYTES)
+    }
+}
+
+impl<H: Hash> WedprHkdf<H> {
+    /// Creates an HKDF instance, where hash_size_in_bytes is the output size
+    /// of the hash algorithm of the HMAC.
+    pub fn new(hmac: WedprHmac<H>, hash_size_in_bytes: usize) -> Self {
+        WedprHkdf {
+            hmac,
+            hash_size_in_bytes,
+        }
+    }
+
+    /// Extracts a pseudorandom key (PRK) from input keying material, where an
+    /// empty salt is replaced by zeros of the hash size.
+    pub fn extract(&self, salt: &[u8], input_key: &[u8]) -> Vec<u8> {
+        if salt.is_empty() {
+            let zero_salt = vec![0u8; self.hash_size_in_bytes];
+            return self.hmac.mac(&zero_salt[..], input_key);
+        }
+        self.hmac.mac(salt, input_key)
+    }
+
+    /// Expands a pseudorandom key (PRK) to output_size bytes bound to info,
+    /// where output_size should not exceed 255 times the hash size.
+    pub fn expand(
+        &self,
+        prk: &[u8],
+        info: &[u8],
+        output_size: usize,
+    ) -> Result<Vec<u8>, WedprError> {
+        if prk.len() < self.hash_size_in_bytes
+            || output_size > HKDF_MAX_OUTPUT_BLOCKS * self.hash_size_in_bytes
+        {
+            wedpr_println!("Invalid HKDF PRK or output size");
+            return Err(WedprError::ArgumentError);
+        }
+        let mut output = Vec::with_capacity(output_size);
+        let mut block: Vec<u8> = Vec::new();
+        let mut counter = 1u8;
+        while output.len() < output_size {
+            let mut hmac_input = block;
+            hmac_input.extend_from_slice(info);
+            hmac_input.push(counter);
+            block = self.hmac.mac(prk, &hmac_input[..]);
+            output.extend_from_slice(&block);
+            counter = counter.wrapping_add(1);
+        }
+        output.truncate(output_size);
+        Ok(output)
+    }
+}
+
+impl<H: Hash + AlgorithmInfo> Kdf for WedprHkdf<H> {
+    /// Derives a key as by extract and expand, which fails if the hash
+    /// algorithm is not allowed by the global policy.
+    fn derive_key<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        input_key: &T,
+        salt: &T,
+        info: &T,
+        output_size: usize,
+    ) -> Result<Vec<u8>, WedprError> {
+        check_global_algorithm::<H>()?;
+        let prk = self.extract(salt.as_ref(), input_key.as_ref());
+        self.expand(&prk, info.as_ref(), output_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_common_coder_hex::WedprHex;
+    use wedpr_l_utils::traits::Coder;
+
+    #[test]
+    fn test_hkdf() {
+        // Test cases 1 and 3 of RFC 5869.
+        let hex = WedprHex::default();
+        let hkdf = WedprHkdfSha256::default();
+        let input_key = vec![0x0bu8; 22];
+        let salt = hex.decode("000102030405060708090a0b0c").unwrap();
+        let info = hex.decode("f0f1f2f3f4f5f6f7f8f9").unwrap();
+        let prk = hkdf.extract(&salt, &input_key);
+        assert_eq!(
+            prk,
+            hex.decode(
+                &[
+                    "077709362c2e32df0ddc3f0dc47bba63",
+                    "90b6c73bb50f9c3122ec844ad7c2b3e5",
+                ]
+                .concat()
+            )
+            .unwrap()
+        );
+        let expected_key = hex
+            .decode(
+                &[
+                    "3cb25f25faacd57a90434f64d0362f2a",
+                    "2d2d0a90cf1a5a4c5db02d56ecc4c5bf",
+                    "34007208d5b887185865",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        assert_eq!(
+            hkdf.derive_key(&input_key, &salt, &info, 42).unwrap(),
+            expected_key
+        );
+        let empty: &[u8] = &[];
+        let expected_key = hex
+            .decode(
+                &[
+                    "8da4e775a563c18f715f802a063c5a31",
+                    "b8a11f5c5ee1879ec3454e5f3c738d2d",
+                    "9d201395faa4b61a96c8",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        assert_eq!(
+            hkdf.derive_key(&input_key[..], empty, empty, 42).unwrap(),
+            expected_key
+        );
+        assert!(hkdf.expand(&prk, &info, 255 * 32 + 1).is_err());
+        assert!(hkdf.expand(&prk[1..], &info, 32).is_err());
+    }
+
+    #[test]
+    fn test_hmac_hkdf_sm3() {
+        // Inputs of test cases 1, 2 and 6 of RFC 4231, with tags computed by
+        // the HMAC-SM3 of OpenSSL 3.5.
+        let hex = WedprHex::default();
+        let hmac = hmac_sm3();
+        let test_cases = [
+            (vec![0x0bu8; 20], b"Hi There".to_vec(), [
+                "51b00d1fb49832bfb01c3ce27848e59f",
+                "871d9ba938dc563b338ca964755cce70",
+            ]),
+            (
+                b"Jefe".to_vec(),
+                b"what do ya want for nothing?".to_vec(),
+                [
+                    "2e87f1d16862e6d964b50a5200bf2b10",
+                    "b764faa9680a296a2405f24bec39f882",
+                ],
+            ),
+            (
+                vec![0xaau8; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+                    .to_vec(),
+                [
+                    "b4fd844e13342002f0b2e0690ea7741f",
+                    "1497d993a70494cea601e657bedf67a0",
+                ],
+            ),
+        ];
+        for (key, message, expected_tag) in test_cases.iter() {
+            let expected_tag = hex.decode(&expected_tag.concat()).unwrap();
+            assert_eq!(hmac.mac(key, message), expected_tag);
+            assert!(hmac.verify_mac(key, message, &expected_tag));
+        }
+
+        // Inputs of test case 1 of RFC 5869, with the key derived by the
+        // HKDF-SM3 of OpenSSL 3.5.
+        let hkdf = WedprHkdfSm3::default();
+        let input_key = vec![0x0bu8; 22];
+        let salt = hex.decode("000102030405060708090a0b0c").unwrap();
+        let info = hex.decode("f0f1f2f3f4f5f6f7f8f9").unwrap();
+        let expected_key = hex
+            .decode(
+                &[
+                    "c69fe91b7aaee2dd5718d72dcaee0cce",
+                    "93f1b8e41f792da51261b6a517e68b36",
+                    "ed2c595572b01dfa359b",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        assert_eq!(
+            hkdf.derive_key(&input_key, &salt, &info, 42).unwrap(),
+            expected_key
+        );
+    }
+}
@@ -0,0 +1,294 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Password-based key derivation by PBKDF2 of RFC 8018 and scrypt of RFC
+//! 7914, with work factors tunable by calibration helpers, e.g. for wallets
+//! deriving secp256k1 secret keys from user passphrases.
+
+use std::time::{Duration, Instant};
+use wedpr_l_crypto_hash_sha256::WedprSha256;
+use wedpr_l_crypto_mac_hmac::WedprHmac;
+use wedpr_l_utils::{
+    error::WedprError,
+    policy::check_global_algorithm,
+    traits::{AlgorithmInfo, Hash, Mac},
+};
+
+/// Minimum accepted PBKDF2 iterations.
+pub const MIN_PBKDF2_ITERATIONS: u32 = 10_000;
+/// Minimum accepted scrypt cost parameter log2(N).
+pub const MIN_SCRYPT_LOG_N: u8 = 10;
+/// Maximum scrypt cost parameter log2(N) chosen by calibration.
+pub const MAX_SCRYPT_LOG_N: u8 = 20;
+
+/// Iterations measured when calibrating PBKDF2.
+const PBKDF2_CALIBRATION_ITERATIONS: u32 = 1_000;
+/// Domain separator of secp256k1 secret key derivation.
+const SECP256K1_SECRET_KEY_DOMAIN: &[u8] = b"WeDPR_PASSWORD_SECP256K1_KEY";
+/// Order of the secp256k1 curve in big endian.
+const SECP256K1_CURVE_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xfe, 0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b,
+    0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+/// Trait of a password-based key derivation function.
+pub trait PasswordKdf {
+    /// Derives a key of output_size bytes from a password and a salt.
+    fn derive_from_password(
+        &self,
+        password: &[u8],
+        salt: &[u8],
+        output_size: usize,
+    ) -> Result<Vec<u8>, WedprError>;
+}
+
+/// Implements PBKDF2 as a PasswordKdf instance over an HMAC instance.
+#[derive(Debug, Clone)]
+pub struct WedprPbkdf2<H: Hash> {
+    hmac: WedprHmac<H>,
+    iterations: u32,
+}
+
+impl<H: Hash> WedprPbkdf2<H> {
+    /// Creates a PBKDF2 instance, where iterations should be at least
+    /// MIN_PBKDF2_ITERATIONS.
+    pub fn new(
+        hmac: WedprHmac<H>,
+        iterations: u32,
+    ) -> Result<Self, WedprError> {
+        if iterations < MIN_PBKDF2_ITERATIONS {
+            wedpr_println!("Too few PBKDF2 iterations {}", iterations);
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(WedprPbkdf2 { hmac, iterations })
+    }
+
+    fn derive(
+        &self,
+        password: &[u8],
+        salt: &[u8],
+        iterations: u32,
+        output_size: usize,
+    ) -> Vec<u8> {
+        let mut output = Vec::with_capacity(output_size);
+        let mut block_index = 1u32;
+        while output.len() < output_size {
+            let mut block_input = salt.to_vec();
+            block_input.extend_from_slice(&block_index.to_be_bytes());
+            let mut u = self.hmac.mac(password, &block_input[..]);
+            let mut block = u.clone();
+            for _ in 1..iterations {
+                u = self.hmac.mac(password, &u[..]);
+                for (byte, value) in block.iter_mut().zip(&u) {
+                    *byte ^= value;
+                }
+            }
+            output.extend_from_slice(&block);
+            block_index += 1;
+        }
+        output.truncate(output_size);
+        output
+    }
+}
+
+impl<H: Hash + AlgorithmInfo> PasswordKdf for WedprPbkdf2<H> {
+    /// Derives a key, which fails if the hash algorithm is not allowed by the
+    /// global policy.
+    fn derive_from_password(
+        &self,
+        password: &[u8],
+        salt: &[u8],
+        output_size: usize,
+    ) -> Result<Vec<u8>, WedprError> {
+        check_global_algorithm::<H>()?;
+        if output_size == 0 {
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(self.derive(password, salt, self.iterations, output_size))
+    }
+}
+
+/// Implements scrypt as a PasswordKdf instance.
+#[derive(Debug, Clone, Copy)]
+pub struct WedprScrypt {
+    params: scrypt::Params,
+}
+
+impl WedprScrypt {
+    /// Creates a scrypt instance with cost N = 2^log_n, block size r and
+    /// parallelism p, where log_n should be at least MIN_SCRYPT_LOG_N.
+    pub fn new(log_n: u8, r: u32, p: u32) -> Result<Self, WedprError> {
+        if log_n < MIN_SCRYPT_LOG_N {
+            wedpr_println!("Too small scrypt cost 2^{}", log_n);
+            return Err(WedprError::ArgumentError);
+        }
+        Self::new_unchecked(log_n, r, p)
+    }
+
+    fn new_unchecked(log_n: u8, r: u32, p: u32) -> Result<Self, WedprError> {
+        // The output size is set by each derivation.
+        match scrypt::Params::new(log_n, r, p, scrypt::Params::RECOMMENDED_LEN)
+        {
+            Ok(params) => Ok(WedprScrypt { params }),
+            Err(_) => {
+                wedpr_println!("Invalid scrypt parameters");
+                Err(WedprError::ArgumentError)
+            },
+        }
+    }
+}
+
+impl PasswordKdf for WedprScrypt {
+    fn derive_from_password(
+        &self,
+        password: &[u8],
+        salt: &[u8],
+        output_size: usize,
+    ) -> Result<Vec<u8>, WedprError> {
+        let mut output = vec![0u8; output_size];
+        match scrypt::scrypt(password, salt, &self.params, &mut output) {
+            Ok(_) => Ok(output),
+            Err(_) => Err(WedprError::ArgumentError),
+        }
+    }
+}
+
+/// Derives a secp256k1 secret key from a passphrase and a salt, e.g. a
+/// wallet ID, where out of range candidates are rejected and re-derived.
+pub fn derive_secp256k1_secret_key<P: PasswordKdf>(
+    kdf: &P,
+    passphrase: &[u8],
+    salt: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let mut stretched_key = kdf.derive_from_password(passphrase, salt, 32)?;
+    let hash = WedprSha256::default();
+    for counter in 0u32.. {
+        let mut hash_input = SECP256K1_SECRET_KEY_DOMAIN.to_vec();
+        hash_input.extend_from_slice(&counter.to_be_bytes());
+        hash_input.extend_from_slice(&stretched_key);
+        let candidate = hash.hash(&hash_input);
+        if candidate.iter().any(|v| *v != 0)
+            && candidate[..] < SECP256K1_CURVE_ORDER[..]
+        {
+            stretched_key.iter_mut().for_each(|v| *v = 0);
+            return Ok(candidate);
+        }
+    }
+    unreachable!()
+}
+
+/// Picks PBKDF2 iterations taking about target_duration on this machine,
+/// which are at least MIN_PBKDF2_ITERATIONS.
+pub fn calibrate_pbkdf2_iterations<H: Hash>(
+    hmac: WedprHmac<H>,
+    target_duration: Duration,
+) -> u32 {
+    let pbkdf2 = WedprPbkdf2 {
+        hmac,
+        iterations: MIN_PBKDF2_ITERATIONS,
+    };
+    let start = Instant::now();
+    pbkdf2.derive(b"password", b"salt", PBKDF2_CALIBRATION_ITERATIONS, 32);
+    let elapsed = start.elapsed().as_nanos().max(1);
+    let iterations = target_duration.as_nanos()
+        * PBKDF2_CALIBRATION_ITERATIONS as u128
+        / elapsed;
+    iterations.clamp(MIN_PBKDF2_ITERATIONS as u128, u32::MAX as u128) as u32
+}
+
+/// Picks the largest scrypt cost log2(N) in [MIN_SCRYPT_LOG_N,
+/// MAX_SCRYPT_LOG_N] taking at most about target_duration on this machine
+/// with block size r and parallelism p.
+pub fn calibrate_scrypt_log_n(
+    r: u32,
+    p: u32,
+    target_duration: Duration,
+) -> Result<u8, WedprError> {
+    let scrypt = WedprScrypt::new(MIN_SCRYPT_LOG_N, r, p)?;
+    let start = Instant::now();
+    scrypt.derive_from_password(b"password", b"salt", 32)?;
+    // The cost doubles with each increment of log2(N).
+    let mut estimated = start.elapsed();
+    let mut log_n = MIN_SCRYPT_LOG_N;
+    while log_n < MAX_SCRYPT_LOG_N && estimated * 2 <= target_duration {
+        estimated *= 2;
+        log_n += 1;
+    }
+    Ok(log_n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hmac_sha256;
+    use wedpr_l_common_coder_hex::WedprHex;
+    use wedpr_l_utils::traits::Coder;
+
+    #[test]
+    fn test_password_kdf() {
+        // Test vectors of section 11 and 12 of RFC 7914.
+        let hex = WedprHex::default();
+        let pbkdf2 = WedprPbkdf2 {
+            hmac: hmac_sha256(),
+            iterations: 1,
+        };
+        let expected_key = hex
+            .decode(
+                &[
+                    "55ac046e56e3089fec1691c22544b605",
+                    "f94185216dde0465e68b9d57c20dacbc",
+                    "49ca9cccf179b645991664b39d77ef31",
+                    "7c71b845b1e30bd509112041d3a19783",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        assert_eq!(
+            pbkdf2.derive_from_password(b"passwd", b"salt", 64).unwrap(),
+            expected_key
+        );
+        let scrypt = WedprScrypt::new_unchecked(4, 1, 1).unwrap();
+        let expected_key = hex
+            .decode(
+                &[
+                    "77d6576238657b203b19ca42c18a0497",
+                    "f16b4844e3074ae8dfdffa3fede21442",
+                    "fcd0069ded0948f8326a753a0fc81f17",
+                    "e8d3e0fb2e0d3628cf35e20c38d18906",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        assert_eq!(
+            scrypt.derive_from_password(b"", b"", 64).unwrap(),
+            expected_key
+        );
+        assert!(WedprScrypt::new(4, 1, 1).is_err());
+        assert!(WedprPbkdf2::new(hmac_sha256(), 1).is_err());
+
+        let scrypt = WedprScrypt::new(MIN_SCRYPT_LOG_N, 8, 1).unwrap();
+        let secret_key =
+            derive_secp256k1_secret_key(&scrypt, b"passphrase", b"wallet")
+                .unwrap();
+        assert_eq!(secret_key.len(), 32);
+        assert_eq!(
+            secret_key,
+            derive_secp256k1_secret_key(&scrypt, b"passphrase", b"wallet")
+                .unwrap()
+        );
+        assert_ne!(
+            secret_key,
+            derive_secp256k1_secret_key(&scrypt, b"passphrase", b"other")
+                .unwrap()
+        );
+
+        assert_eq!(
+            calibrate_pbkdf2_iterations(hmac_sha256(), Duration::from_secs(0)),
+            MIN_PBKDF2_ITERATIONS
+        );
+        assert_eq!(
+            calibrate_scrypt_log_n(8, 1, Duration::from_secs(0)).unwrap(),
+            MIN_SCRYPT_LOG_N
+        );
+    }
+}
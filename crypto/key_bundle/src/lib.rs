@@ -0,0 +1,346 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Encrypted export and import of key bundles, for controlled migration of
+//! keys with their metadata between environments.
+//!
+//! Bundles are encrypted by AES-256-GCM with the bundle header as associated
+//! data, under either:
+//! 1) A key derived from a password by PBKDF2-HMAC-SHA256.
+//! 2) A random key split into Shamir shares for custodians, where any threshold
+//!    custodians together can import the bundle.
+//!
+//! Importing fails on wrong passwords, insufficient or mismatched shares,
+//! tampered bundles and malformed or duplicate entries.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+pub mod sharing;
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use sha2::Sha256;
+use wedpr_l_utils::{
+    error::WedprError, key_metadata::KeyMetadata, tool::random_bytes,
+};
+
+/// Minimum PBKDF2 iterations accepted for password-encrypted bundles.
+pub const MIN_PBKDF2_ITERATIONS: u32 = 10_000;
+/// Recommended PBKDF2 iterations for password-encrypted bundles.
+pub const DEFAULT_PBKDF2_ITERATIONS: u32 = 600_000;
+
+const BUNDLE_MAGIC: &[u8] = b"WKB1";
+const BUNDLE_MODE_PASSWORD: u8 = 1;
+const BUNDLE_MODE_SHARDED: u8 = 2;
+const SALT_SIZE_IN_BYTES: usize = 16;
+const NONCE_SIZE_IN_BYTES: usize = 12;
+const BUNDLE_KEY_SIZE_IN_BYTES: usize = 32;
+/// Bundle ID, threshold, index and the share of the bundle key.
+const CUSTODIAN_SHARE_SIZE_IN_BYTES: usize =
+    NONCE_SIZE_IN_BYTES + 2 + BUNDLE_KEY_SIZE_IN_BYTES;
+
+/// Key with its metadata in a bundle.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct KeyEntry {
+    pub key_id: String,
+    pub metadata: KeyMetadata,
+    pub private_key: Vec<u8>,
+}
+
+fn append_bytes(output: &mut Vec<u8>, value: &[u8]) {
+    output.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    output.extend_from_slice(value);
+}
+
+fn read_bytes<'a>(
+    input: &'a [u8],
+    offset: &mut usize,
+) -> Result<&'a [u8], WedprError> {
+    if input.len() < *offset + 4 {
+        return Err(WedprError::FormatError);
+    }
+    let mut length = [0u8; 4];
+    length.copy_from_slice(&input[*offset..*offset + 4]);
+    let start = *offset + 4;
+    let end = start + u32::from_be_bytes(length) as usize;
+    if input.len() < end {
+        return Err(WedprError::FormatError);
+    }
+    *offset = end;
+    Ok(&input[start..end])
+}
+
+/// Encodes entries, which fails on empty or duplicate key IDs.
+fn encode_entries(entries: &[KeyEntry]) -> Result<Vec<u8>, WedprError> {
+    check_entries(entries)?;
+    let mut output = (entries.len() as u32).to_be_bytes().to_vec();
+    for entry in entries {
+        append_bytes(&mut output, entry.key_id.as_bytes());
+        append_bytes(&mut output, &entry.metadata.encode());
+        append_bytes(&mut output, &entry.private_key);
+    }
+    Ok(output)
+}
+
+fn decode_entries(input: &[u8]) -> Result<Vec<KeyEntry>, WedprError> {
+    if input.len() < 4 {
+        return Err(WedprError::FormatError);
+    }
+    let mut count = [0u8; 4];
+    count.copy_from_slice(&input[..4]);
+    let mut offset = 4;
+    let mut entries = Vec::new();
+    for _ in 0..u32::from_be_bytes(count) {
+        let key_id =
+            match String::from_utf8(read_bytes(input, &mut offset)?.to_vec()) {
+                Ok(v) => v,
+                Err(_) => return Err(WedprError::FormatError),
+            };
+        let metadata = KeyMetadata::decode(read_bytes(input, &mut offset)?)?;
+        let private_key = read_bytes(input, &mut offset)?.to_vec();
+        entries.push(KeyEntry {
+            key_id,
+            metadata,
+            private_key,
+        });
+    }
+    if offset != input.len() {
+        return Err(WedprError::FormatError);
+    }
+    check_entries(&entries)?;
+    Ok(entries)
+}
+
+fn check_entries(entries: &[KeyEntry]) -> Result<(), WedprError> {
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.key_id.is_empty()
+            || entry.private_key.is_empty()
+            || entries[..i].iter().any(|v| v.key_id == entry.key_id)
+        {
+            wedpr_println!("Invalid or duplicate key bundle entries");
+            return Err(WedprError::ArgumentError);
+        }
+    }
+    Ok(())
+}
+
+/// Encrypts a payload with the header as associated data, and returns the
+/// bundle.
+fn seal(
+    key: &[u8],
+    header: Vec<u8>,
+    nonce: &[u8],
+    payload: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let ciphertext = match cipher.encrypt(Nonce::from_slice(nonce), Payload {
+        msg: payload,
+        aad: &header,
+    }) {
+        Ok(v) => v,
+        Err(_) => return Err(WedprError::ArgumentError),
+    };
+    let mut bundle = header;
+    bundle.extend_from_slice(&ciphertext);
+    Ok(bundle)
+}
+
+fn open(
+    key: &[u8],
+    header: &[u8],
+    nonce: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<KeyEntry>, WedprError> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let payload = match cipher.decrypt(Nonce::from_slice(nonce), Payload {
+        msg: ciphertext,
+        aad: header,
+    }) {
+        Ok(v) => v,
+        Err(_) => {
+            wedpr_println!("Decrypting key bundle failed");
+            return Err(WedprError::VerificationError);
+        },
+    };
+    decode_entries(&payload)
+}
+
+/// Parses the mode of a bundle.
+fn bundle_mode(bundle: &[u8]) -> Result<u8, WedprError> {
+    if bundle.len() <= BUNDLE_MAGIC.len() || !bundle.starts_with(BUNDLE_MAGIC) {
+        wedpr_println!("Invalid key bundle");
+        return Err(WedprError::FormatError);
+    }
+    Ok(bundle[BUNDLE_MAGIC.len()])
+}
+
+/// Exports entries to a bundle encrypted by a password, where iterations
+/// of PBKDF2 should be at least MIN_PBKDF2_ITERATIONS.
+pub fn export_with_password(
+    entries: &[KeyEntry],
+    password: &[u8],
+    iterations: u32,
+) -> Result<Vec<u8>, WedprError> {
+    if password.is_empty() || iterations < MIN_PBKDF2_ITERATIONS {
+        wedpr_println!("Empty password or too few PBKDF2 iterations");
+        return Err(WedprError::ArgumentError);
+    }
+    let payload = encode_entries(entries)?;
+    let salt = random_bytes(SALT_SIZE_IN_BYTES);
+    let nonce = random_bytes(NONCE_SIZE_IN_BYTES);
+    let mut header = BUNDLE_MAGIC.to_vec();
+    header.push(BUNDLE_MODE_PASSWORD);
+    header.extend_from_slice(&iterations.to_be_bytes());
+    header.extend_from_slice(&salt);
+    header.extend_from_slice(&nonce);
+    let mut key = [0u8; BUNDLE_KEY_SIZE_IN_BYTES];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password, &salt, iterations, &mut key);
+    seal(&key, header, &nonce, &payload)
+}
+
+/// Imports entries from a bundle encrypted by a password.
+pub fn import_with_password(
+    bundle: &[u8],
+    password: &[u8],
+) -> Result<Vec<KeyEntry>, WedprError> {
+    let iterations_offset = BUNDLE_MAGIC.len() + 1;
+    let salt_offset = iterations_offset + 4;
+    let nonce_offset = salt_offset + SALT_SIZE_IN_BYTES;
+    let header_size = nonce_offset + NONCE_SIZE_IN_BYTES;
+    if bundle_mode(bundle)? != BUNDLE_MODE_PASSWORD
+        || bundle.len() < header_size
+    {
+        return Err(WedprError::FormatError);
+    }
+    let mut iterations = [0u8; 4];
+    iterations.copy_from_slice(&bundle[iterations_offset..salt_offset]);
+    let iterations = u32::from_be_bytes(iterations);
+    if iterations < MIN_PBKDF2_ITERATIONS {
+        wedpr_println!("Too few PBKDF2 iterations in key bundle");
+        return Err(WedprError::FormatError);
+    }
+    let mut key = [0u8; BUNDLE_KEY_SIZE_IN_BYTES];
+    pbkdf2::pbkdf2_hmac::<Sha256>(
+        password,
+        &bundle[salt_offset..nonce_offset],
+        iterations,
+        &mut key,
+    );
+    open(
+        &key,
+        &bundle[..header_size],
+        &bundle[nonce_offset..header_size],
+        &bundle[header_size..],
+    )
+}
+
+/// Exports entries to a bundle encrypted by a random key, which is split
+/// into custodian_count custodian shares, where any threshold shares can
+/// import the bundle. Returns the bundle and the custodian shares.
+pub fn export_sharded(
+    entries: &[KeyEntry],
+    threshold: u8,
+    custodian_count: u8,
+) -> Result<(Vec<u8>, Vec<Vec<u8>>), WedprError> {
+    let payload = encode_entries(entries)?;
+    let key = random_bytes(BUNDLE_KEY_SIZE_IN_BYTES);
+    let key_shares = sharing::split_secret(&key, threshold, custodian_count)?;
+    let nonce = random_bytes(NONCE_SIZE_IN_BYTES);
+    let mut header = BUNDLE_MAGIC.to_vec();
+    header.push(BUNDLE_MODE_SHARDED);
+    header.push(threshold);
+    header.extend_from_slice(&nonce);
+    // The nonce also identifies the bundle of custodian shares.
+    let custodian_shares = key_shares
+        .into_iter()
+        .map(|(index, share)| {
+            let mut output = nonce.clone();
+            output.push(threshold);
+            output.push(index);
+            output.extend_from_slice(&share);
+            output
+        })
+        .collect();
+    Ok((seal(&key, header, &nonce, &payload)?, custodian_shares))
+}
+
+/// Imports entries from a sharded bundle with at least threshold custodian
+/// shares of the bundle.
+pub fn import_sharded(
+    bundle: &[u8],
+    custodian_shares: &[Vec<u8>],
+) -> Result<Vec<KeyEntry>, WedprError> {
+    let nonce_offset = BUNDLE_MAGIC.len() + 2;
+    let header_size = nonce_offset + NONCE_SIZE_IN_BYTES;
+    if bundle_mode(bundle)? != BUNDLE_MODE_SHARDED || bundle.len() < header_size
+    {
+        return Err(WedprError::FormatError);
+    }
+    let threshold = bundle[nonce_offset - 1];
+    let nonce = &bundle[nonce_offset..header_size];
+    let mut key_shares = Vec::with_capacity(custodian_shares.len());
+    for share in custodian_shares {
+        if share.len() != CUSTODIAN_SHARE_SIZE_IN_BYTES
+            || &share[..NONCE_SIZE_IN_BYTES] != nonce
+            || share[NONCE_SIZE_IN_BYTES] != threshold
+        {
+            wedpr_println!("Custodian share does not match the key bundle");
+            return Err(WedprError::ArgumentError);
+        }
+        key_shares.push((
+            share[NONCE_SIZE_IN_BYTES + 1],
+            share[NONCE_SIZE_IN_BYTES + 2..].to_vec(),
+        ));
+    }
+    if key_shares.len() < threshold as usize {
+        wedpr_println!("Insufficient custodian shares");
+        return Err(WedprError::ArgumentError);
+    }
+    let key = sharing::recover_secret(&key_shares)?;
+    open(&key, &bundle[..header_size], nonce, &bundle[header_size..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_utils::key_metadata::KEY_USAGE_SIGN;
+
+    #[test]
+    fn test_key_bundle() {
+        let entries: Vec<KeyEntry> = (0..2)
+            .map(|i| KeyEntry {
+                key_id: format!("secp256k1:bkey{}", i),
+                metadata: KeyMetadata {
+                    label: format!("signer {}", i),
+                    created_at: 1_600_000_000 + i,
+                    algorithm: "secp256k1".to_string(),
+                    usage_flags: KEY_USAGE_SIGN,
+                },
+                private_key: vec![i as u8 + 1; 32],
+            })
+            .collect();
+
+        let password = b"correct horse battery staple";
+        let bundle =
+            export_with_password(&entries, password, MIN_PBKDF2_ITERATIONS)
+                .unwrap();
+        assert_eq!(import_with_password(&bundle, password).unwrap(), entries);
+        assert!(import_with_password(&bundle, b"wrong password").is_err());
+        let mut tampered = bundle.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(import_with_password(&tampered, password).is_err());
+        assert!(import_sharded(&bundle, &[]).is_err());
+        assert!(export_with_password(&entries, password, 1).is_err());
+
+        let (bundle, shares) = export_sharded(&entries, 2, 3).unwrap();
+        assert_eq!(import_sharded(&bundle, &shares[1..]).unwrap(), entries);
+        assert!(import_sharded(&bundle, &shares[..1]).is_err());
+        let (_, other_shares) = export_sharded(&entries, 2, 3).unwrap();
+        assert!(import_sharded(&bundle, &other_shares).is_err());
+
+        let duplicate = vec![entries[0].clone(), entries[0].clone()];
+        assert!(export_sharded(&duplicate, 2, 3).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Shamir secret sharing of byte strings over GF(2^8), which splits a secret
+//! into shares for custodians, where any threshold shares recover it.
+
+use rand::RngCore;
+use wedpr_l_utils::error::WedprError;
+
+/// Reduction polynomial x^8 + x^4 + x^3 + x + 1 of GF(2^8).
+const GF256_REDUCTION: u8 = 0x1b;
+
+fn gf256_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= GF256_REDUCTION;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// Computes a^-1 as a^254, where a should not be 0.
+fn gf256_inverse(a: u8) -> u8 {
+    let mut result = 1u8;
+    for _ in 0..254 {
+        result = gf256_mul(result, a);
+    }
+    result
+}
+
+/// Splits a secret into share_count shares of (index, share bytes), where
+/// indexes start from 1 and any threshold shares recover the secret.
+pub fn split_secret(
+    secret: &[u8],
+    threshold: u8,
+    share_count: u8,
+) -> Result<Vec<(u8, Vec<u8>)>, WedprError> {
+    if threshold == 0 || threshold > share_count {
+        wedpr_println!("Invalid threshold {} of {}", threshold, share_count);
+        return Err(WedprError::ArgumentError);
+    }
+    let mut shares: Vec<(u8, Vec<u8>)> = (1..=share_count)
+        .map(|index| (index, Vec::with_capacity(secret.len())))
+        .collect();
+    let mut coefficients = vec![0u8; threshold as usize];
+    for byte in secret {
+        coefficients[0] = *byte;
+        rand::thread_rng().fill_bytes(&mut coefficients[1..]);
+        for (index, share) in shares.iter_mut() {
+            // Evaluates the polynomial by Horner's method.
+            let value = coefficients
+                .iter()
+                .rev()
+                .fold(0u8, |acc, c| gf256_mul(acc, *index) ^ c);
+            share.push(value);
+        }
+    }
+    Ok(shares)
+}
+
+/// Recovers a secret from shares of (index, share bytes) by Lagrange
+/// interpolation at 0, where all shares should have distinct non-zero
+/// indexes and the same length.
+pub fn recover_secret(shares: &[(u8, Vec<u8>)]) -> Result<Vec<u8>, WedprError> {
+    let length = match shares.first() {
+        Some((_, v)) => v.len(),
+        None => return Err(WedprError::ArgumentError),
+    };
+    for (i, (index, share)) in shares.iter().enumerate() {
+        if *index == 0
+            || share.len() != length
+            || shares[..i].iter().any(|(other, _)| other == index)
+        {
+            wedpr_println!("Invalid or duplicate secret shares");
+            return Err(WedprError::ArgumentError);
+        }
+    }
+    let mut secret = vec![0u8; length];
+    for (index, share) in shares {
+        // Subtraction is XOR in GF(2^8).
+        let mut numerator = 1u8;
+        let mut denominator = 1u8;
+        for (other, _) in shares {
+            if other != index {
+                numerator = gf256_mul(numerator, *other);
+                denominator = gf256_mul(denominator, other ^ index);
+            }
+        }
+        let coefficient = gf256_mul(numerator, gf256_inverse(denominator));
+        for (byte, value) in secret.iter_mut().zip(share) {
+            *byte ^= gf256_mul(coefficient, *value);
+        }
+    }
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_sharing() {
+        assert_eq!(gf256_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf256_mul(0x53, gf256_inverse(0x53)), 1);
+
+        let secret = b"secret of the key bundle".to_vec();
+        let shares = split_secret(&secret, 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+        assert_eq!(recover_secret(&shares[..3]).unwrap(), secret);
+        assert_eq!(recover_secret(&shares[2..]).unwrap(), secret);
+        assert_eq!(recover_secret(&shares).unwrap(), secret);
+        assert_ne!(recover_secret(&shares[..2]).unwrap(), secret);
+
+        let duplicate = vec![shares[0].clone(), shares[0].clone()];
+        assert!(recover_secret(&duplicate).is_err());
+        assert!(split_secret(&secret, 4, 3).is_err());
+        assert!(split_secret(&secret, 0, 3).is_err());
+    }
+}
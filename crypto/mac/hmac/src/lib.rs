@@ -0,0 +1,85 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! HMAC functions built on a replaceable Hash algorithm, following RFC 2104.
+
+use wedpr_l_utils::traits::{Hash, Mac};
+
+/// Block size of SHA-256 and SM3 in bytes.
+pub const HMAC_SHA256_BLOCK_SIZE_IN_BYTES: usize = 64;
+/// Block size, i.e. the rate, of Keccak-256 in bytes.
+pub const HMAC_KECCAK256_BLOCK_SIZE_IN_BYTES: usize = 136;
+
+const HMAC_INNER_PAD: u8 = 0x36;
+const HMAC_OUTER_PAD: u8 = 0x5c;
+
+/// Implements HMAC as a Mac instance over a Hash instance with the given
+/// block size.
+#[derive(Debug, Clone)]
+pub struct WedprHmac<H: Hash> {
+    hash: H,
+    block_size_in_bytes: usize,
+}
+
+impl<H: Hash> WedprHmac<H> {
+    /// Creates an HMAC instance, where block_size_in_bytes is the input
+    /// block size of the hash algorithm.
+    pub fn new(hash: H, block_size_in_bytes: usize) -> Self {
+        WedprHmac {
+            hash,
+            block_size_in_bytes,
+        }
+    }
+
+    fn padded_key(&self, key: &[u8], pad: u8) -> Vec<u8> {
+        let mut padded_key = if key.len() > self.block_size_in_bytes {
+            self.hash.hash(key)
+        } else {
+            key.to_vec()
+        };
+        padded_key.resize(self.block_size_in_bytes, 0);
+        padded_key.iter().map(|byte| byte ^ pad).collect()
+    }
+}
+
+impl<H: Hash> Mac for WedprHmac<H> {
+    fn mac<T: ?Sized + AsRef<[u8]>>(&self, key: &T, message: &T) -> Vec<u8> {
+        let mut inner_input = self.padded_key(key.as_ref(), HMAC_INNER_PAD);
+        inner_input.extend_from_slice(message.as_ref());
+        let mut outer_input = self.padded_key(key.as_ref(), HMAC_OUTER_PAD);
+        outer_input.append(&mut self.hash.hash(&inner_input));
+        self.hash.hash(&outer_input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_common_coder_hex::WedprHex;
+    use wedpr_l_crypto_hash_sha256::WedprSha256;
+    use wedpr_l_utils::traits::Coder;
+
+    #[test]
+    fn test_hmac_sha256() {
+        let hmac = WedprHmac::new(
+            WedprSha256::default(),
+            HMAC_SHA256_BLOCK_SIZE_IN_BYTES,
+        );
+        // Test case 2 of RFC 4231.
+        let key = b"Jefe".to_vec();
+        let message = b"what do ya want for nothing?".to_vec();
+        let expected_tag = WedprHex::default()
+            .decode(
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            )
+            .unwrap();
+        assert_eq!(hmac.mac(&key, &message), expected_tag);
+        assert!(hmac.verify_mac(&key, &message, &expected_tag));
+        assert!(!hmac.verify_mac(&key, &message, &expected_tag[1..].to_vec()));
+
+        // Keys longer than a block are hashed first.
+        let long_key = vec![0xaau8; 131];
+        let tag = hmac.mac(&long_key, &message);
+        assert!(hmac.verify_mac(&long_key, &message, &tag));
+        assert!(!hmac.verify_mac(&key, &message, &tag));
+    }
+}
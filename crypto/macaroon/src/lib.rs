@@ -0,0 +1,363 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Macaroon-style bearer tokens for decentralized authorization, built on
+//! replaceable Mac, Signature and Hash algorithms.
+//!
+//! A token is minted with the tag MAC(root_key, identifier), and every caveat
+//! chains the tag as MAC(tag, caveat), so that any holder can attenuate a
+//! token by appending caveats but cannot remove them. First-party caveats
+//! are predicates checked by the verifier. Third-party caveats name a
+//! public key, whose holder checks the predicate and signs a discharge bound
+//! to the token identifier.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{Hash, Mac, Signature},
+};
+
+/// Domain separator of the signed content of a discharge.
+const DISCHARGE_DOMAIN: &[u8] = b"WeDPR_MACAROON_DISCHARGE";
+const CAVEAT_TYPE_FIRST_PARTY: u8 = 0;
+const CAVEAT_TYPE_THIRD_PARTY: u8 = 1;
+
+/// Restriction of a token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Caveat {
+    /// Predicate checked by the verifier.
+    FirstParty { predicate: String },
+    /// Predicate checked by the holder of public_key at location, which
+    /// signs a discharge if the predicate holds.
+    ThirdParty {
+        location: String,
+        public_key: Vec<u8>,
+        predicate: String,
+    },
+}
+
+impl Caveat {
+    fn encode(&self, output: &mut Vec<u8>) {
+        match self {
+            Caveat::FirstParty { predicate } => {
+                output.push(CAVEAT_TYPE_FIRST_PARTY);
+                append_bytes(output, predicate.as_bytes());
+            },
+            Caveat::ThirdParty {
+                location,
+                public_key,
+                predicate,
+            } => {
+                output.push(CAVEAT_TYPE_THIRD_PARTY);
+                append_bytes(output, location.as_bytes());
+                append_bytes(output, public_key);
+                append_bytes(output, predicate.as_bytes());
+            },
+        }
+    }
+
+    fn decode(reader: &mut FieldReader) -> Result<Self, WedprError> {
+        match reader.read_fixed(1)?[0] {
+            CAVEAT_TYPE_FIRST_PARTY => Ok(Caveat::FirstParty {
+                predicate: reader.read_string()?,
+            }),
+            CAVEAT_TYPE_THIRD_PARTY => Ok(Caveat::ThirdParty {
+                location: reader.read_string()?,
+                public_key: reader.read_bytes()?.to_vec(),
+                predicate: reader.read_string()?,
+            }),
+            _ => {
+                wedpr_println!("Unknown caveat type");
+                Err(WedprError::DecodeError)
+            },
+        }
+    }
+}
+
+/// Bearer token with chained caveats.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Macaroon {
+    pub location: String,
+    pub identifier: Vec<u8>,
+    pub caveats: Vec<Caveat>,
+    pub tag: Vec<u8>,
+}
+
+impl Macaroon {
+    /// Mints a token with a root key, which is only known by the minting
+    /// and verifying service.
+    pub fn mint<M: Mac>(
+        mac: &M,
+        root_key: &[u8],
+        location: &str,
+        identifier: &[u8],
+    ) -> Self {
+        Macaroon {
+            location: location.to_string(),
+            identifier: identifier.to_vec(),
+            caveats: Vec::new(),
+            tag: mac.mac(root_key, identifier),
+        }
+    }
+
+    fn add_caveat<M: Mac>(&mut self, mac: &M, caveat: Caveat) {
+        let mut encoded_caveat = Vec::new();
+        caveat.encode(&mut encoded_caveat);
+        self.tag = mac.mac(&self.tag[..], &encoded_caveat[..]);
+        self.caveats.push(caveat);
+    }
+
+    /// Attenuates the token with a predicate checked by the verifier.
+    pub fn add_first_party_caveat<M: Mac>(&mut self, mac: &M, predicate: &str) {
+        self.add_caveat(mac, Caveat::FirstParty {
+            predicate: predicate.to_string(),
+        });
+    }
+
+    /// Attenuates the token with a predicate checked by a third party.
+    pub fn add_third_party_caveat<M: Mac>(
+        &mut self,
+        mac: &M,
+        location: &str,
+        public_key: &[u8],
+        predicate: &str,
+    ) {
+        self.add_caveat(mac, Caveat::ThirdParty {
+            location: location.to_string(),
+            public_key: public_key.to_vec(),
+            predicate: predicate.to_string(),
+        });
+    }
+
+    /// Encodes a token to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        append_bytes(&mut output, self.location.as_bytes());
+        append_bytes(&mut output, &self.identifier);
+        output.extend_from_slice(&(self.caveats.len() as u32).to_be_bytes());
+        for caveat in &self.caveats {
+            caveat.encode(&mut output);
+        }
+        append_bytes(&mut output, &self.tag);
+        output
+    }
+
+    /// Decodes a token from bytes.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        let mut reader = FieldReader { input, offset: 0 };
+        let location = reader.read_string()?;
+        let identifier = reader.read_bytes()?.to_vec();
+        let caveat_count = reader.read_u32()?;
+        let mut caveats = Vec::new();
+        for _ in 0..caveat_count {
+            caveats.push(Caveat::decode(&mut reader)?);
+        }
+        let tag = reader.read_bytes()?.to_vec();
+        if reader.offset != input.len() {
+            wedpr_println!("Macaroon has trailing bytes");
+            return Err(WedprError::FormatError);
+        }
+        Ok(Macaroon {
+            location,
+            identifier,
+            caveats,
+            tag,
+        })
+    }
+}
+
+/// Appends bytes to a buffer with a 4-byte big-endian length prefix.
+fn append_bytes(buffer: &mut Vec<u8>, value: &[u8]) {
+    buffer.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    buffer.extend_from_slice(value);
+}
+
+/// Reader of the fields appended by append_bytes.
+struct FieldReader<'a> {
+    input: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> FieldReader<'a> {
+    fn read_fixed(&mut self, length: usize) -> Result<&'a [u8], WedprError> {
+        if self.input.len() - self.offset < length {
+            wedpr_println!("Macaroon data is truncated");
+            return Err(WedprError::FormatError);
+        }
+        let field = &self.input[self.offset..self.offset + length];
+        self.offset += length;
+        Ok(field)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, WedprError> {
+        let mut buffer = [0u8; 4];
+        buffer.copy_from_slice(self.read_fixed(4)?);
+        Ok(u32::from_be_bytes(buffer))
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8], WedprError> {
+        let length = self.read_u32()? as usize;
+        self.read_fixed(length)
+    }
+
+    fn read_string(&mut self) -> Result<String, WedprError> {
+        match String::from_utf8(self.read_bytes()?.to_vec()) {
+            Ok(v) => Ok(v),
+            Err(_) => {
+                wedpr_println!("Macaroon string is not valid UTF-8");
+                Err(WedprError::DecodeError)
+            },
+        }
+    }
+}
+
+/// Computes the hash actually signed by a discharge.
+fn discharge_hash<H: Hash>(
+    hash: &H,
+    identifier: &[u8],
+    caveat: &Caveat,
+) -> Vec<u8> {
+    let mut hash_vec = DISCHARGE_DOMAIN.to_vec();
+    append_bytes(&mut hash_vec, identifier);
+    caveat.encode(&mut hash_vec);
+    hash.hash(&hash_vec)
+}
+
+/// Signs a discharge of the third-party caveat at caveat_index, which should
+/// be called by the third party after checking the predicate.
+pub fn discharge_third_party_caveat<S: Signature, H: Hash>(
+    signature: &S,
+    hash: &H,
+    private_key: &[u8],
+    macaroon: &Macaroon,
+    caveat_index: usize,
+) -> Result<Vec<u8>, WedprError> {
+    match macaroon.caveats.get(caveat_index) {
+        Some(caveat @ Caveat::ThirdParty { .. }) => {
+            let signed_hash =
+                discharge_hash(hash, &macaroon.identifier, caveat);
+            signature.sign(private_key, &signed_hash[..])
+        },
+        _ => {
+            wedpr_println!(
+                "Caveat {} is not a third-party caveat",
+                caveat_index
+            );
+            Err(WedprError::ArgumentError)
+        },
+    }
+}
+
+/// Verifies a token minted with root_key, where every first-party predicate
+/// should be accepted by check_predicate, and discharges holds the
+/// discharges of all third-party caveats in their order in the token.
+pub fn verify_macaroon<M: Mac, S: Signature, H: Hash, F: Fn(&str) -> bool>(
+    mac: &M,
+    signature: &S,
+    hash: &H,
+    root_key: &[u8],
+    macaroon: &Macaroon,
+    discharges: &[Vec<u8>],
+    check_predicate: F,
+) -> bool {
+    // Keeps the key and the message of the last chaining step, so that the
+    // final tag is checked by verify_mac in constant time.
+    let mut key = root_key.to_vec();
+    let mut message = macaroon.identifier.clone();
+    let mut discharge_iter = discharges.iter();
+    for caveat in &macaroon.caveats {
+        let satisfied = match caveat {
+            Caveat::FirstParty { predicate } => check_predicate(predicate),
+            Caveat::ThirdParty { public_key, .. } => {
+                match discharge_iter.next() {
+                    Some(discharge) => signature.verify(
+                        &public_key[..],
+                        &discharge_hash(hash, &macaroon.identifier, caveat)[..],
+                        &discharge[..],
+                    ),
+                    None => false,
+                }
+            },
+        };
+        if !satisfied {
+            wedpr_println!("Macaroon caveat is not satisfied");
+            return false;
+        }
+        key = mac.mac(&key[..], &message[..]);
+        message.clear();
+        caveat.encode(&mut message);
+    }
+    discharge_iter.next().is_none()
+        && mac.verify_mac(&key[..], &message[..], &macaroon.tag[..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+    use wedpr_l_crypto_hash_sha256::WedprSha256;
+    use wedpr_l_crypto_mac_hmac::{WedprHmac, HMAC_SHA256_BLOCK_SIZE_IN_BYTES};
+    use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+
+    #[test]
+    fn test_macaroon() {
+        let hmac = WedprHmac::new(
+            WedprSha256::default(),
+            HMAC_SHA256_BLOCK_SIZE_IN_BYTES,
+        );
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let keccak256 = WedprKeccak256::default();
+        let (auth_public_key, auth_private_key) = secp256k1.generate_keypair();
+        let root_key = [7u8; 32];
+
+        let mut macaroon =
+            Macaroon::mint(&hmac, &root_key, "wedpr.storage", b"token-1");
+        macaroon.add_first_party_caveat(&hmac, "op = read");
+        macaroon.add_third_party_caveat(
+            &hmac,
+            "wedpr.auth",
+            &auth_public_key,
+            "user = alice",
+        );
+        let discharge = discharge_third_party_caveat(
+            &secp256k1,
+            &keccak256,
+            &auth_private_key,
+            &macaroon,
+            1,
+        )
+        .unwrap();
+        let verify = |macaroon: &Macaroon, discharges: &[Vec<u8>], op| {
+            verify_macaroon(
+                &hmac,
+                &secp256k1,
+                &keccak256,
+                &root_key,
+                macaroon,
+                discharges,
+                |predicate| predicate == op,
+            )
+        };
+        let discharges = vec![discharge];
+        assert!(verify(&macaroon, &discharges, "op = read"));
+        assert!(!verify(&macaroon, &discharges, "op = write"));
+        assert!(!verify(&macaroon, &[], "op = read"));
+
+        // Removing a caveat invalidates the tag.
+        let mut stripped_macaroon = macaroon.clone();
+        stripped_macaroon.caveats.remove(0);
+        assert!(!verify(&stripped_macaroon, &discharges, "op = read"));
+
+        let decoded_macaroon = Macaroon::decode(&macaroon.encode()).unwrap();
+        assert_eq!(decoded_macaroon, macaroon);
+        assert!(discharge_third_party_caveat(
+            &secp256k1,
+            &keccak256,
+            &auth_private_key,
+            &macaroon,
+            0
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,323 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! 1-out-of-n oblivious transfer (OT) functions over Ristretto, where a
+//! receiver learns exactly one of n messages held by a sender, and the sender
+//! learns nothing about which one, e.g. for anonymous queries.
+//!
+//! The protocol follows the simplest OT of Chou and Orlandi in three rounds:
+//! 1) The sender sends A = a * G.
+//! 2) The receiver of choice c sends B = b * G + c * A.
+//! 3) The sender encrypts the i-th message with a key derived from
+//! a * (B - i * A), where only the key of i = c equals the receiver key
+//! derived from b * A.
+//! 1-out-of-2 OT is the case of n = 2 with a choice bit.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+use curve25519_dalek::{ristretto::RistrettoPoint, scalar::Scalar};
+use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+use wedpr_l_crypto_zkp_utils::{
+    bytes_to_point, get_random_scalar, point_to_bytes, BASEPOINT_G1,
+};
+use wedpr_l_utils::{error::WedprError, traits::Hash};
+
+/// Domain separator of deriving message keys.
+const OT_KEY_DOMAIN: &[u8] = b"WeDPR_OT_KEY";
+/// Size of the big-endian length prefixes in encoded transfer messages.
+const LENGTH_SIZE_IN_BYTES: usize = 4;
+
+/// First round message from the sender.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtSenderSetup {
+    pub point: RistrettoPoint,
+}
+
+/// Second round message from the receiver, which hides its choice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtReceiverChoice {
+    pub point: RistrettoPoint,
+}
+
+/// Third round message from the sender, which contains all encrypted
+/// messages in order.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct OtSenderTransfer {
+    pub ciphertexts: Vec<Vec<u8>>,
+}
+
+impl OtSenderSetup {
+    /// Encodes the message for sending to the receiver.
+    pub fn encode(&self) -> Vec<u8> {
+        point_to_bytes(&self.point)
+    }
+
+    /// Decodes a message encoded by encode.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        Ok(OtSenderSetup {
+            point: bytes_to_point(input)?,
+        })
+    }
+}
+
+impl OtReceiverChoice {
+    /// Encodes the message for sending to the sender.
+    pub fn encode(&self) -> Vec<u8> {
+        point_to_bytes(&self.point)
+    }
+
+    /// Decodes a message encoded by encode.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        Ok(OtReceiverChoice {
+            point: bytes_to_point(input)?,
+        })
+    }
+}
+
+impl OtSenderTransfer {
+    /// Encodes the message as the ciphertext count followed by each
+    /// ciphertext with its length.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = (self.ciphertexts.len() as u32).to_be_bytes().to_vec();
+        for ciphertext in &self.ciphertexts {
+            output.extend_from_slice(&(ciphertext.len() as u32).to_be_bytes());
+            output.extend_from_slice(ciphertext);
+        }
+        output
+    }
+
+    /// Decodes a message encoded by encode.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        let mut remaining = input;
+        let count = read_length(&mut remaining)?;
+        // Every ciphertext takes at least its length prefix.
+        if count > remaining.len() / LENGTH_SIZE_IN_BYTES {
+            return Err(WedprError::FormatError);
+        }
+        let mut ciphertexts = Vec::with_capacity(count);
+        for _ in 0..count {
+            let length = read_length(&mut remaining)?;
+            if length > remaining.len() {
+                return Err(WedprError::FormatError);
+            }
+            let (ciphertext, rest) = remaining.split_at(length);
+            ciphertexts.push(ciphertext.to_vec());
+            remaining = rest;
+        }
+        if !remaining.is_empty() {
+            wedpr_println!("OT transfer message has trailing data");
+            return Err(WedprError::FormatError);
+        }
+        Ok(OtSenderTransfer { ciphertexts })
+    }
+}
+
+fn read_length(input: &mut &[u8]) -> Result<usize, WedprError> {
+    if input.len() < LENGTH_SIZE_IN_BYTES {
+        return Err(WedprError::FormatError);
+    }
+    let (length, rest) = input.split_at(LENGTH_SIZE_IN_BYTES);
+    let mut buffer = [0u8; LENGTH_SIZE_IN_BYTES];
+    buffer.copy_from_slice(length);
+    *input = rest;
+    Ok(u32::from_be_bytes(buffer) as usize)
+}
+
+/// Derives the key of a message index from the shared point.
+fn derive_key(
+    setup_point: &RistrettoPoint,
+    choice_point: &RistrettoPoint,
+    index: usize,
+    shared_point: &RistrettoPoint,
+) -> Vec<u8> {
+    let mut hash_vec = OT_KEY_DOMAIN.to_vec();
+    hash_vec.append(&mut point_to_bytes(setup_point));
+    hash_vec.append(&mut point_to_bytes(choice_point));
+    hash_vec.extend_from_slice(&(index as u64).to_be_bytes());
+    hash_vec.append(&mut point_to_bytes(shared_point));
+    WedprKeccak256::default().hash(&hash_vec)
+}
+
+/// Encrypts or decrypts an input by XOR with the key stream of hashing the
+/// key and a block counter.
+fn apply_key_stream(key: &[u8], input: &[u8]) -> Vec<u8> {
+    let hash = WedprKeccak256::default();
+    let mut output = Vec::with_capacity(input.len());
+    for (counter, block) in input.chunks(32).enumerate() {
+        let stream =
+            hash.hash(&[key, &(counter as u64).to_be_bytes()].concat());
+        output.extend(block.iter().zip(stream).map(|(x, y)| x ^ y));
+    }
+    output
+}
+
+/// Sender of an OT session, which should not be reused across sessions.
+#[derive(Debug, Clone)]
+pub struct OtSender {
+    secret: Scalar,
+    setup_point: RistrettoPoint,
+}
+
+impl OtSender {
+    /// Creates a sender with a new random secret.
+    pub fn new() -> Self {
+        let secret = get_random_scalar();
+        OtSender {
+            secret,
+            setup_point: secret * *BASEPOINT_G1,
+        }
+    }
+
+    /// Gets the first round message.
+    pub fn setup_message(&self) -> OtSenderSetup {
+        OtSenderSetup {
+            point: self.setup_point,
+        }
+    }
+
+    /// Encrypts at least 2 messages for the receiver choice, where the
+    /// receiver can only decrypt the chosen one.
+    pub fn transfer<T: AsRef<[u8]>>(
+        &self,
+        choice: &OtReceiverChoice,
+        messages: &[T],
+    ) -> Result<OtSenderTransfer, WedprError> {
+        if messages.len() < 2 {
+            wedpr_println!("OT needs at least 2 messages");
+            return Err(WedprError::ArgumentError);
+        }
+        let secret_choice_point = self.secret * choice.point;
+        let secret_setup_point = self.secret * self.setup_point;
+        let ciphertexts = messages
+            .iter()
+            .enumerate()
+            .map(|(index, message)| {
+                let shared_point = secret_choice_point
+                    - Scalar::from(index as u64) * secret_setup_point;
+                let key = derive_key(
+                    &self.setup_point,
+                    &choice.point,
+                    index,
+                    &shared_point,
+                );
+                apply_key_stream(&key, message.as_ref())
+            })
+            .collect();
+        Ok(OtSenderTransfer { ciphertexts })
+    }
+}
+
+impl Default for OtSender {
+    fn default() -> Self {
+        OtSender::new()
+    }
+}
+
+/// Receiver of an OT session with its choice.
+#[derive(Debug, Clone)]
+pub struct OtReceiver {
+    secret: Scalar,
+    choice: usize,
+    setup_point: RistrettoPoint,
+    choice_point: RistrettoPoint,
+}
+
+impl OtReceiver {
+    /// Creates a receiver choosing the message of index choice, which
+    /// returns the receiver and its second round message.
+    pub fn new(
+        setup: &OtSenderSetup,
+        choice: usize,
+    ) -> (Self, OtReceiverChoice) {
+        let secret = get_random_scalar();
+        let choice_point =
+            secret * *BASEPOINT_G1 + Scalar::from(choice as u64) * setup.point;
+        let receiver = OtReceiver {
+            secret,
+            choice,
+            setup_point: setup.point,
+            choice_point,
+        };
+        (receiver, OtReceiverChoice {
+            point: choice_point,
+        })
+    }
+
+    /// Creates a receiver of 1-out-of-2 OT choosing the second message if
+    /// choice_bit is true, as by new.
+    pub fn with_choice_bit(
+        setup: &OtSenderSetup,
+        choice_bit: bool,
+    ) -> (Self, OtReceiverChoice) {
+        OtReceiver::new(setup, choice_bit as usize)
+    }
+
+    /// Decrypts the chosen message from the third round message.
+    pub fn receive(
+        &self,
+        transfer: &OtSenderTransfer,
+    ) -> Result<Vec<u8>, WedprError> {
+        let ciphertext = match transfer.ciphertexts.get(self.choice) {
+            Some(v) => v,
+            None => {
+                wedpr_println!("OT choice {} is out of range", self.choice);
+                return Err(WedprError::ArgumentError);
+            },
+        };
+        let key = derive_key(
+            &self.setup_point,
+            &self.choice_point,
+            self.choice,
+            &(self.secret * self.setup_point),
+        );
+        Ok(apply_key_stream(&key, ciphertext))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oblivious_transfer() {
+        let messages: Vec<Vec<u8>> = (0..5)
+            .map(|i| format!("message {} of a longer length", i).into_bytes())
+            .collect();
+        for choice in 0..messages.len() {
+            let sender = OtSender::new();
+            let setup = OtSenderSetup::decode(&sender.setup_message().encode())
+                .unwrap();
+            let (receiver, choice_message) = OtReceiver::new(&setup, choice);
+            let choice_message =
+                OtReceiverChoice::decode(&choice_message.encode()).unwrap();
+            let transfer = sender.transfer(&choice_message, &messages).unwrap();
+            let transfer =
+                OtSenderTransfer::decode(&transfer.encode()).unwrap();
+            assert_eq!(receiver.receive(&transfer).unwrap(), messages[choice]);
+            // Other messages stay encrypted.
+            for (index, ciphertext) in transfer.ciphertexts.iter().enumerate() {
+                if index != choice {
+                    assert_ne!(ciphertext, &messages[index]);
+                }
+            }
+        }
+
+        // 1-out-of-2 OT.
+        let sender = OtSender::new();
+        let (receiver, choice_message) =
+            OtReceiver::with_choice_bit(&sender.setup_message(), true);
+        let transfer = sender
+            .transfer(&choice_message, &[b"zero".to_vec(), b"one".to_vec()])
+            .unwrap();
+        assert_eq!(receiver.receive(&transfer).unwrap(), b"one".to_vec());
+
+        let (receiver, choice_message) =
+            OtReceiver::new(&sender.setup_message(), 2);
+        let transfer =
+            sender.transfer(&choice_message, &messages[..2]).unwrap();
+        assert!(receiver.receive(&transfer).is_err());
+        assert!(sender.transfer(&choice_message, &messages[..1]).is_err());
+        let truncated = [0u8, 0, 0, 1, 0, 0, 0, 2, 1];
+        assert!(OtSenderTransfer::decode(&truncated).is_err());
+    }
+}
@@ -0,0 +1,347 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! OpenPGP public-key encrypted message functions, where the message is a
+//! literal data packet in a symmetrically encrypted and integrity protected
+//! data (SEIPD) packet of AES-256, and the session key is transported to
+//! every recipient in a public-key encrypted session key (PKESK) packet.
+
+use crate::{
+    packet::{
+        decode_packets, encode_mpi, encode_packet, PacketReader, PgpPacket,
+        PGP_TAG_COMPRESSED_DATA, PGP_TAG_LITERAL_DATA, PGP_TAG_PKESK,
+        PGP_TAG_SEIPD,
+    },
+    pad_rsa_value, public_key_algorithm_id, PGP_ALGORITHM_RSA,
+    PGP_KEY_ID_SIZE_IN_BYTES, PGP_SYMMETRIC_AES256,
+};
+use aes::Aes256;
+use cfb_mode::{
+    cipher::{AsyncStreamCipher, KeyIvInit},
+    Decryptor, Encryptor,
+};
+use flate2::read::{DeflateDecoder, ZlibDecoder};
+use sha1::{Digest, Sha1};
+use std::io::Read;
+use wedpr_l_utils::{
+    error::WedprError,
+    tool::random_bytes,
+    traits::{AlgorithmInfo, Ecies},
+};
+
+/// Literal data format of binary data.
+pub const PGP_LITERAL_FORMAT_BINARY: u8 = b'b';
+
+const PGP_PKESK_VERSION: u8 = 3;
+const PGP_SEIPD_VERSION: u8 = 1;
+const AES256_KEY_SIZE_IN_BYTES: usize = 32;
+const AES_BLOCK_SIZE_IN_BYTES: usize = 16;
+/// Header of the modification detection code (MDC) packet, followed by a
+/// SHA-1 hash.
+const MDC_HEADER: [u8; 2] = [0xd3, 0x14];
+const MDC_SIZE_IN_BYTES: usize = 22;
+const PGP_COMPRESSION_NONE: u8 = 0;
+const PGP_COMPRESSION_ZIP: u8 = 1;
+const PGP_COMPRESSION_ZLIB: u8 = 2;
+
+/// Recipient of an encrypted message.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PgpRecipient {
+    pub key_id: Vec<u8>,
+    pub public_key: Vec<u8>,
+}
+
+/// Literal data decrypted from a message.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PgpLiteralData {
+    pub format: u8,
+    pub file_name: Vec<u8>,
+    /// Modification date in Unix seconds.
+    pub date: u32,
+    pub data: Vec<u8>,
+}
+
+/// Encrypts binary data with the modification date to the recipients, where
+/// the Ecies backend should be RSAES-PKCS1-v1_5.
+pub fn encrypt_message<E: Ecies + AlgorithmInfo>(
+    ecies: &E,
+    recipients: &[PgpRecipient],
+    data: &[u8],
+    date: u32,
+) -> Result<Vec<u8>, WedprError> {
+    let public_key_algorithm = public_key_algorithm_id::<E>()?;
+    if recipients.is_empty() || public_key_algorithm != PGP_ALGORITHM_RSA {
+        wedpr_println!("OpenPGP encryption needs RSA recipients");
+        return Err(WedprError::ArgumentError);
+    }
+    let session_key = random_bytes(AES256_KEY_SIZE_IN_BYTES);
+    let mut session_key_payload = vec![PGP_SYMMETRIC_AES256];
+    session_key_payload.extend_from_slice(&session_key);
+    session_key_payload.extend_from_slice(&checksum(&session_key));
+
+    let mut output = Vec::new();
+    for recipient in recipients {
+        if recipient.key_id.len() != PGP_KEY_ID_SIZE_IN_BYTES {
+            wedpr_println!("Invalid OpenPGP key ID size");
+            return Err(WedprError::ArgumentError);
+        }
+        let encrypted_session_key = ecies
+            .encrypt(&recipient.public_key[..], &session_key_payload[..])?;
+        let mut body = vec![PGP_PKESK_VERSION];
+        body.extend_from_slice(&recipient.key_id);
+        body.push(public_key_algorithm);
+        body.extend_from_slice(&encode_mpi(&encrypted_session_key));
+        output.extend_from_slice(&encode_packet(PGP_TAG_PKESK, &body));
+    }
+
+    let mut literal_body = vec![PGP_LITERAL_FORMAT_BINARY, 0];
+    literal_body.extend_from_slice(&date.to_be_bytes());
+    literal_body.extend_from_slice(data);
+    // The random prefix repeats its last two bytes for a quick key check.
+    let mut plaintext = random_bytes(AES_BLOCK_SIZE_IN_BYTES);
+    let quick_check = plaintext[AES_BLOCK_SIZE_IN_BYTES - 2..].to_vec();
+    plaintext.extend_from_slice(&quick_check);
+    plaintext
+        .extend_from_slice(&encode_packet(PGP_TAG_LITERAL_DATA, &literal_body));
+    plaintext.extend_from_slice(&MDC_HEADER);
+    let mdc = Sha1::digest(&plaintext);
+    plaintext.extend_from_slice(&mdc);
+    match Encryptor::<Aes256>::new_from_slices(
+        &session_key,
+        &[0u8; AES_BLOCK_SIZE_IN_BYTES],
+    ) {
+        Ok(v) => v.encrypt(&mut plaintext),
+        Err(_) => return Err(WedprError::ArgumentError),
+    };
+    let mut body = vec![PGP_SEIPD_VERSION];
+    body.extend_from_slice(&plaintext);
+    output.extend_from_slice(&encode_packet(PGP_TAG_SEIPD, &body));
+    Ok(output)
+}
+
+/// Decrypts a message with the private key of any recipient, which fails if
+/// the integrity check fails.
+pub fn decrypt_message<E: Ecies + AlgorithmInfo>(
+    ecies: &E,
+    private_key: &[u8],
+    message: &[u8],
+) -> Result<PgpLiteralData, WedprError> {
+    let public_key_algorithm = public_key_algorithm_id::<E>()?;
+    let packets = decode_packets(message)?;
+    let session_key = match packets
+        .iter()
+        .filter(|packet| packet.tag == PGP_TAG_PKESK)
+        .find_map(|packet| {
+            decrypt_session_key(
+                ecies,
+                public_key_algorithm,
+                private_key,
+                &packet.body,
+            )
+            .ok()
+        }) {
+        Some(v) => v,
+        None => {
+            wedpr_println!("No OpenPGP session key is decryptable");
+            return Err(WedprError::DecodeError);
+        },
+    };
+    let encrypted_data =
+        match packets.iter().find(|packet| packet.tag == PGP_TAG_SEIPD) {
+            Some(v) => &v.body,
+            None => {
+                wedpr_println!("OpenPGP encrypted data packet is missing");
+                return Err(WedprError::FormatError);
+            },
+        };
+    if encrypted_data.len()
+        < 1 + AES_BLOCK_SIZE_IN_BYTES + 2 + MDC_SIZE_IN_BYTES
+        || encrypted_data[0] != PGP_SEIPD_VERSION
+    {
+        return Err(WedprError::FormatError);
+    }
+    let mut plaintext = encrypted_data[1..].to_vec();
+    match Decryptor::<Aes256>::new_from_slices(
+        &session_key,
+        &[0u8; AES_BLOCK_SIZE_IN_BYTES],
+    ) {
+        Ok(v) => v.decrypt(&mut plaintext),
+        Err(_) => return Err(WedprError::FormatError),
+    };
+    let (content, mdc) =
+        plaintext.split_at(plaintext.len() - MDC_SIZE_IN_BYTES + 2);
+    if plaintext[plaintext.len() - MDC_SIZE_IN_BYTES..][..2] != MDC_HEADER
+        || Sha1::digest(content).as_slice() != mdc
+    {
+        wedpr_println!("OpenPGP modification detection failed");
+        return Err(WedprError::VerificationError);
+    }
+    let packets = decode_packets(
+        &plaintext
+            [AES_BLOCK_SIZE_IN_BYTES + 2..plaintext.len() - MDC_SIZE_IN_BYTES],
+    )?;
+    parse_literal_data(&decompress(packets)?)
+}
+
+fn decrypt_session_key<E: Ecies>(
+    ecies: &E,
+    public_key_algorithm: u8,
+    private_key: &[u8],
+    body: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let mut reader = PacketReader::new(body);
+    if reader.read_u8()? != PGP_PKESK_VERSION {
+        return Err(WedprError::FormatError);
+    }
+    reader.read_fixed(PGP_KEY_ID_SIZE_IN_BYTES)?;
+    if reader.read_u8()? != public_key_algorithm {
+        return Err(WedprError::FormatError);
+    }
+    let encrypted_session_key = pad_rsa_value(reader.read_mpi()?)?;
+    let payload = ecies.decrypt(private_key, &encrypted_session_key[..])?;
+    if payload.len() != 1 + AES256_KEY_SIZE_IN_BYTES + 2
+        || payload[0] != PGP_SYMMETRIC_AES256
+    {
+        wedpr_println!("Only AES-256 OpenPGP session keys are supported");
+        return Err(WedprError::FormatError);
+    }
+    let session_key = &payload[1..1 + AES256_KEY_SIZE_IN_BYTES];
+    if checksum(session_key)[..] != payload[1 + AES256_KEY_SIZE_IN_BYTES..] {
+        return Err(WedprError::DecodeError);
+    }
+    Ok(session_key.to_vec())
+}
+
+/// Unwraps the packets of a compressed data packet, if any.
+fn decompress(packets: Vec<PgpPacket>) -> Result<Vec<PgpPacket>, WedprError> {
+    let packet = match packets.first() {
+        Some(v) if v.tag == PGP_TAG_COMPRESSED_DATA => v,
+        _ => return Ok(packets),
+    };
+    let (algorithm, compressed) = match packet.body.split_first() {
+        Some(v) => v,
+        None => return Err(WedprError::FormatError),
+    };
+    let mut decompressed = Vec::new();
+    let result = match *algorithm {
+        PGP_COMPRESSION_NONE => {
+            decompressed.extend_from_slice(compressed);
+            Ok(0)
+        },
+        PGP_COMPRESSION_ZIP => {
+            DeflateDecoder::new(compressed).read_to_end(&mut decompressed)
+        },
+        PGP_COMPRESSION_ZLIB => {
+            ZlibDecoder::new(compressed).read_to_end(&mut decompressed)
+        },
+        _ => {
+            wedpr_println!("Unsupported OpenPGP compression algorithm");
+            return Err(WedprError::FormatError);
+        },
+    };
+    if result.is_err() {
+        wedpr_println!("OpenPGP decompression failed");
+        return Err(WedprError::DecodeError);
+    }
+    decode_packets(&decompressed)
+}
+
+fn parse_literal_data(
+    packets: &[PgpPacket],
+) -> Result<PgpLiteralData, WedprError> {
+    let packet = match packets
+        .iter()
+        .find(|packet| packet.tag == PGP_TAG_LITERAL_DATA)
+    {
+        Some(v) => v,
+        None => {
+            wedpr_println!("OpenPGP literal data packet is missing");
+            return Err(WedprError::FormatError);
+        },
+    };
+    let mut reader = PacketReader::new(&packet.body);
+    let format = reader.read_u8()?;
+    let file_name_length = reader.read_u8()? as usize;
+    let file_name = reader.read_fixed(file_name_length)?.to_vec();
+    let date = reader.read_u32_be()?;
+    Ok(PgpLiteralData {
+        format,
+        file_name,
+        date,
+        data: reader.read_rest().to_vec(),
+    })
+}
+
+/// Computes the sum of all bytes modulo 65536.
+fn checksum(value: &[u8]) -> [u8; 2] {
+    value
+        .iter()
+        .fold(0u16, |acc, byte| acc.wrapping_add(*byte as u16))
+        .to_be_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::ZlibEncoder, Compression};
+    use std::io::Write;
+    use wedpr_l_crypto_rsa::{
+        generate_rsa_keypair, WedprRsaPkcs1v15Encryption,
+        DEFAULT_RSA_KEY_SIZE_IN_BITS,
+    };
+
+    #[test]
+    fn test_encryption() {
+        let rsa = WedprRsaPkcs1v15Encryption::default();
+        let (public_key, private_key) =
+            generate_rsa_keypair(DEFAULT_RSA_KEY_SIZE_IN_BITS).unwrap();
+        let (other_public_key, other_private_key) =
+            generate_rsa_keypair(DEFAULT_RSA_KEY_SIZE_IN_BITS).unwrap();
+        let recipients = vec![
+            PgpRecipient {
+                key_id: vec![1u8; PGP_KEY_ID_SIZE_IN_BYTES],
+                public_key,
+            },
+            PgpRecipient {
+                key_id: vec![2u8; PGP_KEY_ID_SIZE_IN_BYTES],
+                public_key: other_public_key,
+            },
+        ];
+        let data = b"file exchanged by OpenPGP".to_vec();
+        let message =
+            encrypt_message(&rsa, &recipients, &data, 1600000000).unwrap();
+        for key in &[&private_key, &other_private_key] {
+            let literal_data = decrypt_message(&rsa, key, &message).unwrap();
+            assert_eq!(literal_data.data, data);
+            assert_eq!(literal_data.date, 1600000000);
+            assert_eq!(literal_data.format, PGP_LITERAL_FORMAT_BINARY);
+        }
+        let mut tampered = message.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt_message(&rsa, &private_key, &tampered).is_err());
+        assert!(encrypt_message(&rsa, &[], &data, 0).is_err());
+
+        // Literal data compressed by ZLIB as GnuPG does by default.
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(&encode_packet(PGP_TAG_LITERAL_DATA, &[
+                PGP_LITERAL_FORMAT_BINARY,
+                1,
+                b'a',
+                0,
+                0,
+                0,
+                0,
+                7,
+            ]))
+            .unwrap();
+        let mut compressed_body = vec![PGP_COMPRESSION_ZLIB];
+        compressed_body.extend_from_slice(&encoder.finish().unwrap());
+        let packets = decode_packets(&encode_packet(
+            PGP_TAG_COMPRESSED_DATA,
+            &compressed_body,
+        ))
+        .unwrap();
+        let literal_data = parse_literal_data(&decompress(packets).unwrap());
+        assert_eq!(literal_data.unwrap().file_name, b"a".to_vec());
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! OpenPGP functions of RFC 4880 for a practical subset, including detached
+//! signatures and public-key encrypted messages, built on the RSA and Ed25519
+//! backends of this repository.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+pub mod encryption;
+pub mod packet;
+pub mod signature;
+
+use wedpr_l_crypto_rsa::RSA_KEY_SIZES_IN_BITS;
+use wedpr_l_utils::{error::WedprError, traits::AlgorithmInfo};
+
+/// Public-key algorithm ID of RSA.
+pub const PGP_ALGORITHM_RSA: u8 = 1;
+/// Public-key algorithm ID of EdDSA, i.e. the legacy Ed25519 signature.
+pub const PGP_ALGORITHM_EDDSA: u8 = 22;
+/// Hash algorithm ID of SHA-256.
+pub const PGP_HASH_SHA256: u8 = 8;
+/// Symmetric algorithm ID of AES-256.
+pub const PGP_SYMMETRIC_AES256: u8 = 9;
+/// Size of a key ID, i.e. the low 64 bits of a key fingerprint, in bytes.
+pub const PGP_KEY_ID_SIZE_IN_BYTES: usize = 8;
+
+/// Gets the public-key algorithm ID of a signature or encryption backend.
+pub fn public_key_algorithm_id<A: AlgorithmInfo>() -> Result<u8, WedprError> {
+    match A::algorithm_info().name {
+        "rsassa-pkcs1-v1_5-sha256" | "rsaes-pkcs1-v1_5" => {
+            Ok(PGP_ALGORITHM_RSA)
+        },
+        "ed25519" => Ok(PGP_ALGORITHM_EDDSA),
+        name => {
+            wedpr_println!("{} is not supported by OpenPGP", name);
+            Err(WedprError::ArgumentError)
+        },
+    }
+}
+
+/// Gets the hash algorithm ID of a hash backend.
+pub fn hash_algorithm_id<A: AlgorithmInfo>() -> Result<u8, WedprError> {
+    match A::algorithm_info().name {
+        "sha256" => Ok(PGP_HASH_SHA256),
+        name => {
+            wedpr_println!("{} is not supported by OpenPGP", name);
+            Err(WedprError::ArgumentError)
+        },
+    }
+}
+
+/// Restores the leading zeros dropped by an MPI from an RSA value, by
+/// padding it to the smallest supported key size.
+fn pad_rsa_value(value: &[u8]) -> Result<Vec<u8>, WedprError> {
+    let size = match RSA_KEY_SIZES_IN_BITS
+        .iter()
+        .map(|bits| bits / 8)
+        .find(|size| *size >= value.len())
+    {
+        Some(v) => v,
+        None => return Err(WedprError::FormatError),
+    };
+    let mut output = vec![0u8; size - value.len()];
+    output.extend_from_slice(value);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_sha256::WedprSha256;
+    use wedpr_l_crypto_rsa::{WedprRsaOaep, WedprRsaPkcs1v15Encryption};
+    use wedpr_l_crypto_signature_ed25519::WedprEd25519;
+
+    #[test]
+    fn test_algorithm_id() {
+        assert_eq!(
+            public_key_algorithm_id::<WedprEd25519>().unwrap(),
+            PGP_ALGORITHM_EDDSA
+        );
+        assert_eq!(
+            public_key_algorithm_id::<WedprRsaPkcs1v15Encryption>().unwrap(),
+            PGP_ALGORITHM_RSA
+        );
+        assert!(public_key_algorithm_id::<WedprRsaOaep>().is_err());
+        assert_eq!(hash_algorithm_id::<WedprSha256>().unwrap(), 8);
+        assert_eq!(pad_rsa_value(&[1u8; 255]).unwrap()[..2], [0, 1]);
+        assert!(pad_rsa_value(&[1u8; 513]).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! OpenPGP packet framing and multiprecision integer (MPI) functions.
+
+use wedpr_l_utils::error::WedprError;
+
+/// Tag of a public-key encrypted session key packet.
+pub const PGP_TAG_PKESK: u8 = 1;
+/// Tag of a signature packet.
+pub const PGP_TAG_SIGNATURE: u8 = 2;
+/// Tag of a compressed data packet.
+pub const PGP_TAG_COMPRESSED_DATA: u8 = 8;
+/// Tag of a literal data packet.
+pub const PGP_TAG_LITERAL_DATA: u8 = 11;
+/// Tag of a symmetrically encrypted and integrity protected data packet.
+pub const PGP_TAG_SEIPD: u8 = 18;
+
+/// OpenPGP packet with its body decoded from any length format.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PgpPacket {
+    pub tag: u8,
+    pub body: Vec<u8>,
+}
+
+/// Encodes a packet in the new format.
+pub fn encode_packet(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut output = vec![0xc0 | tag];
+    let length = body.len();
+    if length < 192 {
+        output.push(length as u8);
+    } else if length < 8384 {
+        let shifted_length = length - 192;
+        output.push((shifted_length >> 8) as u8 + 192);
+        output.push(shifted_length as u8);
+    } else {
+        output.push(0xff);
+        output.extend_from_slice(&(length as u32).to_be_bytes());
+    }
+    output.extend_from_slice(body);
+    output
+}
+
+/// Decodes consecutive packets in either the old or the new format, where
+/// partial body lengths are joined.
+pub fn decode_packets(input: &[u8]) -> Result<Vec<PgpPacket>, WedprError> {
+    let mut reader = PacketReader::new(input);
+    let mut packets = Vec::new();
+    while !reader.is_empty() {
+        let header = reader.read_u8()?;
+        if header & 0x80 == 0 {
+            wedpr_println!("Invalid OpenPGP packet header");
+            return Err(WedprError::FormatError);
+        }
+        let packet = if header & 0x40 == 0 {
+            let tag = (header >> 2) & 0x0f;
+            let body = match header & 0x03 {
+                0 => {
+                    let length = reader.read_u8()? as usize;
+                    reader.read_fixed(length)?
+                },
+                1 => {
+                    let length = reader.read_u16_be()? as usize;
+                    reader.read_fixed(length)?
+                },
+                2 => {
+                    let length = reader.read_u32_be()? as usize;
+                    reader.read_fixed(length)?
+                },
+                // The indeterminate length extends to the end of the input.
+                _ => reader.read_rest(),
+            };
+            PgpPacket {
+                tag,
+                body: body.to_vec(),
+            }
+        } else {
+            let tag = header & 0x3f;
+            let mut body = Vec::new();
+            loop {
+                let first = reader.read_u8()? as usize;
+                match first {
+                    0..=191 => {
+                        body.extend_from_slice(reader.read_fixed(first)?);
+                        break;
+                    },
+                    192..=223 => {
+                        let second = reader.read_u8()? as usize;
+                        let length = ((first - 192) << 8) + second + 192;
+                        body.extend_from_slice(reader.read_fixed(length)?);
+                        break;
+                    },
+                    255 => {
+                        let length = reader.read_u32_be()? as usize;
+                        body.extend_from_slice(reader.read_fixed(length)?);
+                        break;
+                    },
+                    _ => {
+                        let length = 1 << (first & 0x1f);
+                        body.extend_from_slice(reader.read_fixed(length)?);
+                    },
+                }
+            }
+            PgpPacket { tag, body }
+        };
+        packets.push(packet);
+    }
+    Ok(packets)
+}
+
+/// Encodes a non-negative big-endian value as an MPI.
+pub fn encode_mpi(value: &[u8]) -> Vec<u8> {
+    let value: Vec<u8> = value
+        .iter()
+        .cloned()
+        .skip_while(|byte| *byte == 0)
+        .collect();
+    let bit_count = match value.first() {
+        Some(v) => (value.len() - 1) * 8 + (8 - v.leading_zeros() as usize),
+        None => 0,
+    };
+    let mut output = (bit_count as u16).to_be_bytes().to_vec();
+    output.extend_from_slice(&value);
+    output
+}
+
+/// Reader of fields in a packet body.
+pub(crate) struct PacketReader<'a> {
+    input: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> PacketReader<'a> {
+    pub(crate) fn new(input: &'a [u8]) -> Self {
+        PacketReader { input, offset: 0 }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.offset >= self.input.len()
+    }
+
+    pub(crate) fn read_fixed(
+        &mut self,
+        length: usize,
+    ) -> Result<&'a [u8], WedprError> {
+        if self.input.len() - self.offset < length {
+            wedpr_println!("OpenPGP packet is truncated");
+            return Err(WedprError::FormatError);
+        }
+        let field = &self.input[self.offset..self.offset + length];
+        self.offset += length;
+        Ok(field)
+    }
+
+    pub(crate) fn read_rest(&mut self) -> &'a [u8] {
+        let field = &self.input[self.offset..];
+        self.offset = self.input.len();
+        field
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Result<u8, WedprError> {
+        Ok(self.read_fixed(1)?[0])
+    }
+
+    pub(crate) fn read_u16_be(&mut self) -> Result<u16, WedprError> {
+        let field = self.read_fixed(2)?;
+        Ok(u16::from_be_bytes([field[0], field[1]]))
+    }
+
+    pub(crate) fn read_u32_be(&mut self) -> Result<u32, WedprError> {
+        let mut buffer = [0u8; 4];
+        buffer.copy_from_slice(self.read_fixed(4)?);
+        Ok(u32::from_be_bytes(buffer))
+    }
+
+    /// Reads an MPI value without its bit count.
+    pub(crate) fn read_mpi(&mut self) -> Result<&'a [u8], WedprError> {
+        let bit_count = self.read_u16_be()? as usize;
+        self.read_fixed(bit_count.div_ceil(8))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packet() {
+        assert_eq!(encode_mpi(&[0, 0, 1]), vec![0, 1, 1]);
+        assert_eq!(encode_mpi(&[0x01, 0xff]), vec![0, 9, 0x01, 0xff]);
+        assert_eq!(encode_mpi(&[]), vec![0, 0]);
+
+        let mut encoded = Vec::new();
+        for length in &[0usize, 191, 192, 8383, 8384] {
+            encoded.extend_from_slice(&encode_packet(
+                PGP_TAG_LITERAL_DATA,
+                &vec![7u8; *length],
+            ));
+        }
+        assert_eq!(&encoded[..2], &[0xcb, 0x00]);
+        let packets = decode_packets(&encoded).unwrap();
+        assert_eq!(packets.len(), 5);
+        assert_eq!(packets[4].body.len(), 8384);
+        assert!(decode_packets(&encoded[..encoded.len() - 1]).is_err());
+
+        // An old format packet of 1-byte length, a new format packet of
+        // partial lengths and an old format packet of indeterminate length.
+        let mut encoded = vec![0x88, 0x02, 0xaa, 0xbb];
+        encoded.extend_from_slice(&[0xd2, 0xe1, 0x01, 0x02, 0x01, 0x03]);
+        encoded.extend_from_slice(&[0xa3, 0x04, 0x05]);
+        let packets = decode_packets(&encoded).unwrap();
+        assert_eq!(packets[0].tag, PGP_TAG_SIGNATURE);
+        assert_eq!(packets[0].body, vec![0xaa, 0xbb]);
+        assert_eq!(packets[1].tag, PGP_TAG_SEIPD);
+        assert_eq!(packets[1].body, vec![0x01, 0x02, 0x03]);
+        assert_eq!(packets[2].tag, PGP_TAG_COMPRESSED_DATA);
+        assert_eq!(packets[2].body, vec![0x04, 0x05]);
+    }
+}
@@ -0,0 +1,322 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! OpenPGP detached signature functions with version 4 signature packets.
+
+use crate::{
+    hash_algorithm_id,
+    packet::{
+        decode_packets, encode_mpi, encode_packet, PacketReader,
+        PGP_TAG_SIGNATURE,
+    },
+    pad_rsa_value, public_key_algorithm_id, PGP_ALGORITHM_EDDSA,
+    PGP_ALGORITHM_RSA, PGP_KEY_ID_SIZE_IN_BYTES,
+};
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{AlgorithmInfo, Hash, Signature},
+};
+
+/// Signature type of a binary document.
+pub const PGP_SIGNATURE_TYPE_BINARY: u8 = 0x00;
+
+const PGP_SIGNATURE_VERSION: u8 = 4;
+const PGP_SUBPACKET_CREATION_TIME: u8 = 2;
+const PGP_SUBPACKET_ISSUER: u8 = 16;
+/// Size of each Ed25519 signature component in bytes.
+const EDDSA_COMPONENT_SIZE_IN_BYTES: usize = 32;
+
+/// Signature parsed from a version 4 signature packet.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PgpSignature {
+    pub signature_type: u8,
+    pub public_key_algorithm: u8,
+    pub hash_algorithm: u8,
+    /// Creation time in Unix seconds, if present.
+    pub creation_time: Option<u32>,
+    pub issuer_key_id: Option<Vec<u8>>,
+    /// Version, types and hashed subpackets, which are hashed after the
+    /// signed data.
+    pub hashed_part: Vec<u8>,
+    /// The left 16 bits of the signed hash.
+    pub hash_prefix: Vec<u8>,
+    /// Signature bytes in the format of the Signature backend.
+    pub signature: Vec<u8>,
+}
+
+/// Creates a binary detached signature packet of the data, signed at the
+/// creation time by the key of the issuer key ID.
+pub fn create_detached_signature<
+    S: Signature + AlgorithmInfo,
+    H: Hash + AlgorithmInfo,
+>(
+    signature: &S,
+    hash: &H,
+    private_key: &[u8],
+    issuer_key_id: &[u8],
+    creation_time: u32,
+    data: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    if issuer_key_id.len() != PGP_KEY_ID_SIZE_IN_BYTES {
+        wedpr_println!("Invalid OpenPGP key ID size");
+        return Err(WedprError::ArgumentError);
+    }
+    let public_key_algorithm = public_key_algorithm_id::<S>()?;
+    let mut hashed_subpackets = vec![5, PGP_SUBPACKET_CREATION_TIME];
+    hashed_subpackets.extend_from_slice(&creation_time.to_be_bytes());
+    let mut hashed_part = vec![
+        PGP_SIGNATURE_VERSION,
+        PGP_SIGNATURE_TYPE_BINARY,
+        public_key_algorithm,
+        hash_algorithm_id::<H>()?,
+    ];
+    hashed_part
+        .extend_from_slice(&(hashed_subpackets.len() as u16).to_be_bytes());
+    hashed_part.extend_from_slice(&hashed_subpackets);
+    let signed_hash = hash.hash(&signed_input(data, &hashed_part));
+    let signature_bytes = signature.sign(private_key, &signed_hash[..])?;
+
+    let mut unhashed_subpackets = vec![9, PGP_SUBPACKET_ISSUER];
+    unhashed_subpackets.extend_from_slice(issuer_key_id);
+    let mut body = hashed_part;
+    body.extend_from_slice(&(unhashed_subpackets.len() as u16).to_be_bytes());
+    body.extend_from_slice(&unhashed_subpackets);
+    body.extend_from_slice(&signed_hash[..2]);
+    if public_key_algorithm == PGP_ALGORITHM_EDDSA {
+        let (r, s) = signature_bytes.split_at(EDDSA_COMPONENT_SIZE_IN_BYTES);
+        body.extend_from_slice(&encode_mpi(r));
+        body.extend_from_slice(&encode_mpi(s));
+    } else {
+        body.extend_from_slice(&encode_mpi(&signature_bytes));
+    }
+    Ok(encode_packet(PGP_TAG_SIGNATURE, &body))
+}
+
+/// Parses the first signature packet of a binary detached signature.
+pub fn parse_detached_signature(
+    input: &[u8],
+) -> Result<PgpSignature, WedprError> {
+    let packet = match decode_packets(input)?
+        .into_iter()
+        .find(|packet| packet.tag == PGP_TAG_SIGNATURE)
+    {
+        Some(v) => v,
+        None => {
+            wedpr_println!("OpenPGP signature packet is missing");
+            return Err(WedprError::FormatError);
+        },
+    };
+    let mut reader = PacketReader::new(&packet.body);
+    if reader.read_u8()? != PGP_SIGNATURE_VERSION {
+        wedpr_println!("Only version 4 OpenPGP signatures are supported");
+        return Err(WedprError::FormatError);
+    }
+    let signature_type = reader.read_u8()?;
+    let public_key_algorithm = reader.read_u8()?;
+    let hash_algorithm = reader.read_u8()?;
+    let hashed_length = reader.read_u16_be()? as usize;
+    let hashed_subpackets = reader.read_fixed(hashed_length)?;
+    let hashed_part = packet.body[..6 + hashed_length].to_vec();
+    let unhashed_length = reader.read_u16_be()? as usize;
+    let unhashed_subpackets = reader.read_fixed(unhashed_length)?;
+    let hash_prefix = reader.read_fixed(2)?.to_vec();
+    let signature = match public_key_algorithm {
+        PGP_ALGORITHM_RSA => pad_rsa_value(reader.read_mpi()?)?,
+        PGP_ALGORITHM_EDDSA => {
+            let mut signature = pad_component(reader.read_mpi()?)?;
+            signature.extend_from_slice(&pad_component(reader.read_mpi()?)?);
+            signature
+        },
+        _ => {
+            wedpr_println!("Unsupported OpenPGP public-key algorithm");
+            return Err(WedprError::FormatError);
+        },
+    };
+
+    let mut creation_time = None;
+    let mut issuer_key_id = None;
+    for subpackets in &[hashed_subpackets, unhashed_subpackets] {
+        let mut subpacket_reader = PacketReader::new(subpackets);
+        while !subpacket_reader.is_empty() {
+            let subpacket = read_subpacket(&mut subpacket_reader)?;
+            // Ignores the critical bit.
+            match (subpacket[0] & 0x7f, subpacket.len()) {
+                (PGP_SUBPACKET_CREATION_TIME, 5) => {
+                    let mut buffer = [0u8; 4];
+                    buffer.copy_from_slice(&subpacket[1..]);
+                    creation_time = Some(u32::from_be_bytes(buffer));
+                },
+                (PGP_SUBPACKET_ISSUER, 9) => {
+                    issuer_key_id = Some(subpacket[1..].to_vec())
+                },
+                _ => (),
+            }
+        }
+    }
+    Ok(PgpSignature {
+        signature_type,
+        public_key_algorithm,
+        hash_algorithm,
+        creation_time,
+        issuer_key_id,
+        hashed_part,
+        hash_prefix,
+        signature,
+    })
+}
+
+/// Verifies a detached signature of the data with the public key, where the
+/// algorithms should match the ones declared in the signature.
+pub fn verify_detached_signature<
+    S: Signature + AlgorithmInfo,
+    H: Hash + AlgorithmInfo,
+>(
+    signature: &S,
+    hash: &H,
+    public_key: &[u8],
+    pgp_signature: &PgpSignature,
+    data: &[u8],
+) -> bool {
+    match (public_key_algorithm_id::<S>(), hash_algorithm_id::<H>()) {
+        (Ok(public_key_algorithm), Ok(hash_algorithm))
+            if public_key_algorithm == pgp_signature.public_key_algorithm
+                && hash_algorithm == pgp_signature.hash_algorithm => {},
+        _ => {
+            wedpr_println!("OpenPGP signature algorithms mismatched");
+            return false;
+        },
+    }
+    let signed_hash =
+        hash.hash(&signed_input(data, &pgp_signature.hashed_part));
+    if signed_hash[..2] != pgp_signature.hash_prefix[..] {
+        wedpr_println!("OpenPGP signature hash prefix mismatched");
+        return false;
+    }
+    signature.verify(public_key, &signed_hash[..], &pgp_signature.signature[..])
+}
+
+/// Gets the hash input of the data and the hashed part with its trailer.
+fn signed_input(data: &[u8], hashed_part: &[u8]) -> Vec<u8> {
+    let mut input = data.to_vec();
+    input.extend_from_slice(hashed_part);
+    input.extend_from_slice(&[PGP_SIGNATURE_VERSION, 0xff]);
+    input.extend_from_slice(&(hashed_part.len() as u32).to_be_bytes());
+    input
+}
+
+/// Reads a subpacket of its type and data.
+fn read_subpacket<'a>(
+    reader: &mut PacketReader<'a>,
+) -> Result<&'a [u8], WedprError> {
+    let first = reader.read_u8()? as usize;
+    let length = match first {
+        0..=191 => first,
+        192..=254 => {
+            let second = reader.read_u8()? as usize;
+            ((first - 192) << 8) + second + 192
+        },
+        _ => reader.read_u32_be()? as usize,
+    };
+    if length == 0 {
+        return Err(WedprError::FormatError);
+    }
+    reader.read_fixed(length)
+}
+
+fn pad_component(value: &[u8]) -> Result<Vec<u8>, WedprError> {
+    if value.len() > EDDSA_COMPONENT_SIZE_IN_BYTES {
+        return Err(WedprError::FormatError);
+    }
+    let mut output = vec![0u8; EDDSA_COMPONENT_SIZE_IN_BYTES - value.len()];
+    output.extend_from_slice(value);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_common_coder_hex::WedprHex;
+    use wedpr_l_crypto_hash_sha256::WedprSha256;
+    use wedpr_l_crypto_signature_ed25519::WedprEd25519;
+    use wedpr_l_utils::traits::Coder;
+
+    #[test]
+    fn test_detached_signature() {
+        let ed25519 = WedprEd25519::default();
+        let sha256 = WedprSha256::default();
+        let (public_key, private_key) = ed25519.generate_keypair();
+        let key_id = vec![1u8; PGP_KEY_ID_SIZE_IN_BYTES];
+        let data = b"file exchanged by OpenPGP".to_vec();
+
+        let encoded = create_detached_signature(
+            &ed25519,
+            &sha256,
+            &private_key,
+            &key_id,
+            1600000000,
+            &data,
+        )
+        .unwrap();
+        let signature = parse_detached_signature(&encoded).unwrap();
+        assert_eq!(signature.creation_time, Some(1600000000));
+        assert_eq!(signature.issuer_key_id, Some(key_id.clone()));
+        assert!(verify_detached_signature(
+            &ed25519,
+            &sha256,
+            &public_key,
+            &signature,
+            &data
+        ));
+        assert!(!verify_detached_signature(
+            &ed25519,
+            &sha256,
+            &public_key,
+            &signature,
+            &data[1..]
+        ));
+        assert!(create_detached_signature(
+            &ed25519,
+            &sha256,
+            &private_key,
+            &key_id[1..],
+            0,
+            &data,
+        )
+        .is_err());
+
+        // Detached signature created by GnuPG 2.4 with an Ed25519 key.
+        let hex = WedprHex::default();
+        let public_key = hex
+            .decode(
+                &[
+                    "342caebd10487154f5b51efe723308a1",
+                    "4e91af5a76bbba21231f014641f23b8b",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let encoded = hex
+            .decode(
+                &[
+                    "887504001608001d162104e152fff4c551b23d3f2078e4508f743a",
+                    "f0e04d7c05026ad05125000a0910508f743af0e04d7ca07b0100a1",
+                    "787bb0b508aed0c8fc4742868d7305d80655eecaa275bd927539de",
+                    "b5f636bc01008b6c99d688cd558c3e54c26f76b27adf30dc69fc46",
+                    "d4d4cbd1b5a0fdd7ecc80c",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let signature = parse_detached_signature(&encoded).unwrap();
+        assert_eq!(
+            signature.issuer_key_id,
+            Some(hex.decode("508f743af0e04d7c").unwrap())
+        );
+        assert!(verify_detached_signature(
+            &ed25519,
+            &sha256,
+            &public_key,
+            &signature,
+            b"message signed by gpg"
+        ));
+    }
+}
@@ -0,0 +1,256 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Optimized Paillier functions for encrypting and decrypting large batches
+//! of values.
+//!
+//! Decryption computes modulo p^2 and q^2 separately and combines the results
+//! by the Chinese remainder theorem (CRT). Encryption replaces r^n with h^a
+//! for a fixed n-th residue h and a random exponent a of half the modulus
+//! size, where powers of h are precomputed in a fixed-base window table.
+//! Batch functions run in parallel with the feature wedpr_f_parallel.
+
+use crate::{
+    mod_add, mod_inverse, mod_mul, mod_pow, random_bits, random_unit,
+    PaillierPrivateKey, PaillierPublicKey,
+};
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use wedpr_l_utils::error::WedprError;
+
+#[cfg(feature = "wedpr_f_parallel")]
+use rayon::prelude::*;
+
+/// Bits of the exponent covered by each row of the fixed-base table.
+const FIXED_BASE_WINDOW_SIZE_IN_BITS: usize = 4;
+
+/// Paillier decryption key with precomputed CRT parameters.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct CrtDecryptionKey {
+    p: BigUint,
+    q: BigUint,
+    p_squared: BigUint,
+    q_squared: BigUint,
+    /// L_p((n + 1)^(p - 1) mod p^2)^-1 mod p.
+    hp: BigUint,
+    /// L_q((n + 1)^(q - 1) mod q^2)^-1 mod q.
+    hq: BigUint,
+    /// q^-1 mod p.
+    q_inverse: BigUint,
+    public_key: PaillierPublicKey,
+}
+
+/// Computes L_p(x) = (x - 1) / p.
+fn l_function(x: &BigUint, p: &BigUint) -> BigUint {
+    (x - BigUint::one()) / p
+}
+
+/// Computes the h parameter of a prime factor, i.e.
+/// L_p((n + 1)^(p - 1) mod p^2)^-1 mod p.
+fn factor_h(
+    prime: &BigUint,
+    prime_squared: &BigUint,
+    n: &BigUint,
+) -> Option<BigUint> {
+    let g = (n + BigUint::one()) % prime_squared;
+    let g_power = mod_pow(&g, &(prime - BigUint::one()), prime_squared);
+    mod_inverse(&l_function(&g_power, prime), prime)
+}
+
+impl CrtDecryptionKey {
+    /// Precomputes the CRT parameters of a private key.
+    pub fn new(private_key: &PaillierPrivateKey) -> Result<Self, WedprError> {
+        let p = private_key.p.clone();
+        let q = private_key.q.clone();
+        let n = &private_key.public_key.n;
+        let p_squared = &p * &p;
+        let q_squared = &q * &q;
+        let (hp, hq, q_inverse) = match (
+            factor_h(&p, &p_squared, n),
+            factor_h(&q, &q_squared, n),
+            mod_inverse(&q, &p),
+        ) {
+            (Some(hp), Some(hq), Some(q_inverse)) => (hp, hq, q_inverse),
+            _ => {
+                wedpr_println!("Invalid Paillier private key for CRT");
+                return Err(WedprError::ArgumentError);
+            },
+        };
+        Ok(CrtDecryptionKey {
+            p,
+            q,
+            p_squared,
+            q_squared,
+            hp,
+            hq,
+            q_inverse,
+            public_key: private_key.public_key.clone(),
+        })
+    }
+
+    /// Decrypts a ciphertext to a plaintext in [0, n).
+    pub fn decrypt(&self, ciphertext: &BigUint) -> Result<BigUint, WedprError> {
+        if ciphertext >= &self.public_key.n_squared || ciphertext.is_zero() {
+            wedpr_println!("Paillier ciphertext is out of range");
+            return Err(WedprError::ArgumentError);
+        }
+        let one = BigUint::one();
+        let cp = mod_pow(ciphertext, &(&self.p - &one), &self.p_squared);
+        let mp = mod_mul(&l_function(&cp, &self.p), &self.hp, &self.p);
+        let cq = mod_pow(ciphertext, &(&self.q - &one), &self.q_squared);
+        let mq = mod_mul(&l_function(&cq, &self.q), &self.hq, &self.q);
+        // m = mq + q * ((mp - mq) * q^-1 mod p).
+        let difference = mod_add(&mp, &(&self.p - (&mq % &self.p)), &self.p);
+        Ok(mq + &self.q * mod_mul(&difference, &self.q_inverse, &self.p))
+    }
+
+    /// Decrypts a batch of ciphertexts.
+    pub fn decrypt_batch(
+        &self,
+        ciphertexts: &[BigUint],
+    ) -> Result<Vec<BigUint>, WedprError> {
+        #[cfg(feature = "wedpr_f_parallel")]
+        let ciphertext_iter = ciphertexts.par_iter();
+        #[cfg(not(feature = "wedpr_f_parallel"))]
+        let ciphertext_iter = ciphertexts.iter();
+        ciphertext_iter
+            .map(|ciphertext| self.decrypt(ciphertext))
+            .collect()
+    }
+}
+
+/// Paillier encryption key with a precomputed fixed-base table of a random
+/// n-th residue h.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct FixedBaseEncryptionKey {
+    public_key: PaillierPublicKey,
+    exponent_size_in_bits: usize,
+    /// table[i][j] = h^(j * 2^(w * i)) mod n^2 for window size w.
+    table: Vec<Vec<BigUint>>,
+}
+
+impl FixedBaseEncryptionKey {
+    /// Precomputes the fixed-base table of a public key, which takes about
+    /// (2^w - 1) * n.bits() / (2 * w) multiplications modulo n^2.
+    pub fn new(public_key: &PaillierPublicKey) -> Self {
+        let n_squared = &public_key.n_squared;
+        let exponent_size_in_bits = public_key.n.bits() as usize / 2;
+        let windows = (exponent_size_in_bits + FIXED_BASE_WINDOW_SIZE_IN_BITS
+            - 1)
+            / FIXED_BASE_WINDOW_SIZE_IN_BITS;
+        let mut base =
+            random_unit(&public_key.n).modpow(&public_key.n, n_squared);
+        let mut table = Vec::with_capacity(windows);
+        for _ in 0..windows {
+            let mut row =
+                Vec::with_capacity(1 << FIXED_BASE_WINDOW_SIZE_IN_BITS);
+            row.push(BigUint::one());
+            for j in 1..(1 << FIXED_BASE_WINDOW_SIZE_IN_BITS) {
+                let power: BigUint = (&row[j - 1] * &base) % n_squared;
+                row.push(power);
+            }
+            base = (&row[(1 << FIXED_BASE_WINDOW_SIZE_IN_BITS) - 1] * &base)
+                % n_squared;
+            table.push(row);
+        }
+        FixedBaseEncryptionKey {
+            public_key: public_key.clone(),
+            exponent_size_in_bits,
+            table,
+        }
+    }
+
+    /// Computes h^exponent mod n^2 from the table.
+    fn fixed_base_power(&self, exponent: &BigUint) -> BigUint {
+        let n_squared = &self.public_key.n_squared;
+        let mut result = BigUint::one();
+        for (i, row) in self.table.iter().enumerate() {
+            let mut digit = 0;
+            for k in 0..FIXED_BASE_WINDOW_SIZE_IN_BITS {
+                let bit = (i * FIXED_BASE_WINDOW_SIZE_IN_BITS + k) as u64;
+                if exponent.bit(bit) {
+                    digit |= 1 << k;
+                }
+            }
+            if digit != 0 {
+                result = (result * &row[digit]) % n_squared;
+            }
+        }
+        result
+    }
+
+    /// Encrypts a plaintext in [0, n).
+    pub fn encrypt(&self, message: &BigUint) -> Result<BigUint, WedprError> {
+        let public_key = &self.public_key;
+        if message >= &public_key.n {
+            wedpr_println!("Paillier plaintext is out of range");
+            return Err(WedprError::ArgumentError);
+        }
+        let n_squared = &public_key.n_squared;
+        let g_m = (BigUint::one() + message * &public_key.n) % n_squared;
+        let exponent = random_bits(self.exponent_size_in_bits);
+        Ok((g_m * self.fixed_base_power(&exponent)) % n_squared)
+    }
+
+    /// Encrypts a batch of plaintexts.
+    pub fn encrypt_batch(
+        &self,
+        messages: &[BigUint],
+    ) -> Result<Vec<BigUint>, WedprError> {
+        #[cfg(feature = "wedpr_f_parallel")]
+        let message_iter = messages.par_iter();
+        #[cfg(not(feature = "wedpr_f_parallel"))]
+        let message_iter = messages.iter();
+        message_iter.map(|message| self.encrypt(message)).collect()
+    }
+
+    /// Gets the public key of this encryption key.
+    pub fn public_key(&self) -> &PaillierPublicKey {
+        &self.public_key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_keypair;
+
+    #[test]
+    fn test_batch_paillier() {
+        let (public_key, private_key) = generate_keypair(512).unwrap();
+        let decryption_key = CrtDecryptionKey::new(&private_key).unwrap();
+        let encryption_key = FixedBaseEncryptionKey::new(&public_key);
+
+        let message = BigUint::from(123456u32);
+        let ciphertext = public_key.encrypt(&message).unwrap();
+        assert_eq!(decryption_key.decrypt(&ciphertext).unwrap(), message);
+        let large_message = &public_key.n - BigUint::one();
+        let ciphertext = public_key.encrypt(&large_message).unwrap();
+        assert_eq!(decryption_key.decrypt(&ciphertext).unwrap(), large_message);
+
+        let messages: Vec<BigUint> =
+            (0..32u32).map(|i| BigUint::from(i * 1000)).collect();
+        let ciphertexts = encryption_key.encrypt_batch(&messages).unwrap();
+        assert_ne!(
+            ciphertexts[1],
+            encryption_key.encrypt(&messages[1]).unwrap()
+        );
+        for (ciphertext, message) in ciphertexts.iter().zip(messages.iter()) {
+            assert_eq!(&private_key.decrypt(ciphertext).unwrap(), message);
+        }
+        assert_eq!(
+            decryption_key.decrypt_batch(&ciphertexts).unwrap(),
+            messages
+        );
+
+        // Ciphertexts remain homomorphic.
+        let sum = public_key.add(&ciphertexts[2], &ciphertexts[3]);
+        assert_eq!(
+            decryption_key.decrypt(&sum).unwrap(),
+            BigUint::from(5000u32)
+        );
+        assert!(encryption_key.encrypt(&public_key.n).is_err());
+        assert!(decryption_key
+            .decrypt_batch(&[ciphertexts[0].clone(), BigUint::zero()])
+            .is_err());
+    }
+}
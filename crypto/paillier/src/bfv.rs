@@ -0,0 +1,137 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! BFV lattice homomorphic encryption for integer vectors.
+//!
+//! A plaintext is a vector of at most N integers modulo t, encoded as the
+//! coefficients of a polynomial scaled by floor(q / t). Ciphertexts can be
+//! added together and multiplied by scalars coefficient-wise.
+
+use crate::lattice::{
+    self, RlweCiphertext, RlwePrivateKey, RlwePublicKey,
+    LATTICE_CIPHERTEXT_MODULUS, LATTICE_RING_DEGREE,
+};
+use wedpr_l_utils::{error::WedprError, traits::HomomorphicEncryption};
+
+/// Plaintext modulus t.
+pub const BFV_PLAINTEXT_MODULUS: u64 = 65537;
+/// Scaling factor floor(q / t) of plaintexts.
+const BFV_DELTA: u64 = LATTICE_CIPHERTEXT_MODULUS / BFV_PLAINTEXT_MODULUS;
+
+/// BFV ciphertext with the number of encrypted values.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct BfvCiphertext {
+    pub rlwe: RlweCiphertext,
+    pub length: usize,
+}
+
+/// Implements BFV as a HomomorphicEncryption instance.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WedprBfv {}
+
+impl HomomorphicEncryption for WedprBfv {
+    type Ciphertext = BfvCiphertext;
+    type Plaintext = Vec<u64>;
+    type PrivateKey = RlwePrivateKey;
+    type PublicKey = RlwePublicKey;
+    type Scalar = u64;
+
+    fn generate_keypair(
+        &self,
+    ) -> Result<(Self::PublicKey, Self::PrivateKey), WedprError> {
+        Ok(lattice::generate_keypair())
+    }
+
+    fn encrypt(
+        &self,
+        public_key: &Self::PublicKey,
+        message: &Self::Plaintext,
+    ) -> Result<Self::Ciphertext, WedprError> {
+        if message.len() > LATTICE_RING_DEGREE
+            || message.iter().any(|v| *v >= BFV_PLAINTEXT_MODULUS)
+        {
+            wedpr_println!("BFV plaintext is out of range");
+            return Err(WedprError::ArgumentError);
+        }
+        let mut encoded = vec![0u64; LATTICE_RING_DEGREE];
+        for (coefficient, value) in encoded.iter_mut().zip(message.iter()) {
+            *coefficient = value * BFV_DELTA;
+        }
+        Ok(BfvCiphertext {
+            rlwe: lattice::encrypt_polynomial(public_key, &encoded)?,
+            length: message.len(),
+        })
+    }
+
+    fn decrypt(
+        &self,
+        private_key: &Self::PrivateKey,
+        ciphertext: &Self::Ciphertext,
+    ) -> Result<Self::Plaintext, WedprError> {
+        if ciphertext.length > LATTICE_RING_DEGREE {
+            return Err(WedprError::FormatError);
+        }
+        let decrypted =
+            lattice::decrypt_polynomial(private_key, &ciphertext.rlwe)?;
+        let q = LATTICE_CIPHERTEXT_MODULUS as u128;
+        let t = BFV_PLAINTEXT_MODULUS as u128;
+        // m = round(t * x / q) mod t.
+        Ok(decrypted[..ciphertext.length]
+            .iter()
+            .map(|x| (((*x as u128 * t + q / 2) / q) % t) as u64)
+            .collect())
+    }
+
+    fn add(
+        &self,
+        _public_key: &Self::PublicKey,
+        ciphertext1: &Self::Ciphertext,
+        ciphertext2: &Self::Ciphertext,
+    ) -> Result<Self::Ciphertext, WedprError> {
+        Ok(BfvCiphertext {
+            rlwe: lattice::add_ciphertexts(
+                &ciphertext1.rlwe,
+                &ciphertext2.rlwe,
+            )?,
+            length: ciphertext1.length.max(ciphertext2.length),
+        })
+    }
+
+    fn mul_scalar(
+        &self,
+        _public_key: &Self::PublicKey,
+        ciphertext: &Self::Ciphertext,
+        scalar: &Self::Scalar,
+    ) -> Result<Self::Ciphertext, WedprError> {
+        Ok(BfvCiphertext {
+            rlwe: lattice::mul_ciphertext_scalar(
+                &ciphertext.rlwe,
+                scalar % BFV_PLAINTEXT_MODULUS,
+            )?,
+            length: ciphertext.length,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bfv() {
+        let bfv = WedprBfv::default();
+        let (public_key, private_key) = bfv.generate_keypair().unwrap();
+        let message1 = vec![1u64, 2, 65536, 0];
+        let message2 = vec![5u64, 65535, 1];
+        let ciphertext1 = bfv.encrypt(&public_key, &message1).unwrap();
+        let ciphertext2 = bfv.encrypt(&public_key, &message2).unwrap();
+        assert_eq!(bfv.decrypt(&private_key, &ciphertext1).unwrap(), message1);
+
+        let sum = bfv.add(&public_key, &ciphertext1, &ciphertext2).unwrap();
+        assert_eq!(bfv.decrypt(&private_key, &sum).unwrap(), vec![6, 0, 0, 0]);
+        let product = bfv.mul_scalar(&public_key, &sum, &1000).unwrap();
+        assert_eq!(bfv.decrypt(&private_key, &product).unwrap(), vec![
+            6000, 0, 0, 0
+        ]);
+        assert!(bfv.encrypt(&public_key, &vec![65537]).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! CKKS lattice homomorphic encryption for approximate real vectors.
+//!
+//! A plaintext is a vector of at most N / 2 real values, encoded into a
+//! polynomial by the inverse canonical embedding and scaled by 2^26.
+//! Decrypted values carry a small approximation error, and every scalar
+//! multiplication rounds the scalar to 12 fractional bits and multiplies the
+//! scale of the ciphertext by 2^12.
+
+use crate::lattice::{
+    self, from_signed, to_signed, RlweCiphertext, RlwePrivateKey,
+    RlwePublicKey, LATTICE_CIPHERTEXT_MODULUS, LATTICE_RING_DEGREE,
+};
+use std::f64::consts::PI;
+use wedpr_l_utils::{error::WedprError, traits::HomomorphicEncryption};
+
+/// Maximum number of values in a plaintext.
+pub const CKKS_SLOT_COUNT: usize = LATTICE_RING_DEGREE / 2;
+/// Bits of the scale of fresh ciphertexts.
+pub const CKKS_SCALE_IN_BITS: u32 = 26;
+/// Bits of the scale of scalars in multiplications.
+pub const CKKS_SCALAR_SCALE_IN_BITS: u32 = 12;
+
+/// CKKS ciphertext with the number of encrypted values and the scale.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct CkksCiphertext {
+    pub rlwe: RlweCiphertext,
+    pub length: usize,
+    pub scale: f64,
+}
+
+/// Computes cos(pi * k / N) for k in [0, 2N).
+fn cosine_table() -> Vec<f64> {
+    (0..2 * LATTICE_RING_DEGREE)
+        .map(|k| (PI * k as f64 / LATTICE_RING_DEGREE as f64).cos())
+        .collect()
+}
+
+/// Computes the exponents 5^j mod 2N of the slot roots of unity.
+fn slot_exponents() -> Vec<usize> {
+    let mut exponent = 1;
+    (0..CKKS_SLOT_COUNT)
+        .map(|_| {
+            let current = exponent;
+            exponent = exponent * 5 % (2 * LATTICE_RING_DEGREE);
+            current
+        })
+        .collect()
+}
+
+/// Encodes real values to polynomial coefficients, computing
+/// m_i = round(scale * 2 / N * sum_j z_j * cos(pi * e_j * i / N)).
+fn encode(values: &[f64], scale: f64) -> Result<Vec<u64>, WedprError> {
+    let cosine = cosine_table();
+    let exponents = slot_exponents();
+    let modulus = 2 * LATTICE_RING_DEGREE;
+    let limit = (LATTICE_CIPHERTEXT_MODULUS / 4) as f64;
+    let mut encoded = Vec::with_capacity(LATTICE_RING_DEGREE);
+    for i in 0..LATTICE_RING_DEGREE {
+        let sum: f64 = values
+            .iter()
+            .zip(exponents.iter())
+            .map(|(z, e)| z * cosine[e * i % modulus])
+            .sum();
+        let coefficient =
+            (scale * 2.0 * sum / LATTICE_RING_DEGREE as f64).round();
+        if !coefficient.is_finite() || coefficient.abs() >= limit {
+            wedpr_println!("CKKS plaintext is out of range");
+            return Err(WedprError::ArgumentError);
+        }
+        encoded.push(from_signed(coefficient as i64));
+    }
+    Ok(encoded)
+}
+
+/// Decodes the first length values from polynomial coefficients, computing
+/// z_j = sum_i m_i * cos(pi * e_j * i / N) / scale.
+fn decode(coefficients: &[u64], length: usize, scale: f64) -> Vec<f64> {
+    let cosine = cosine_table();
+    let exponents = slot_exponents();
+    let modulus = 2 * LATTICE_RING_DEGREE;
+    exponents[..length]
+        .iter()
+        .map(|e| {
+            let sum: f64 = coefficients
+                .iter()
+                .enumerate()
+                .map(|(i, m)| to_signed(*m) as f64 * cosine[e * i % modulus])
+                .sum();
+            sum / scale
+        })
+        .collect()
+}
+
+/// Implements CKKS as a HomomorphicEncryption instance.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WedprCkks {}
+
+impl HomomorphicEncryption for WedprCkks {
+    type Ciphertext = CkksCiphertext;
+    type Plaintext = Vec<f64>;
+    type PrivateKey = RlwePrivateKey;
+    type PublicKey = RlwePublicKey;
+    type Scalar = f64;
+
+    fn generate_keypair(
+        &self,
+    ) -> Result<(Self::PublicKey, Self::PrivateKey), WedprError> {
+        Ok(lattice::generate_keypair())
+    }
+
+    fn encrypt(
+        &self,
+        public_key: &Self::PublicKey,
+        message: &Self::Plaintext,
+    ) -> Result<Self::Ciphertext, WedprError> {
+        if message.len() > CKKS_SLOT_COUNT {
+            wedpr_println!("Too many values for a CKKS plaintext");
+            return Err(WedprError::ArgumentError);
+        }
+        let scale = (1u64 << CKKS_SCALE_IN_BITS) as f64;
+        Ok(CkksCiphertext {
+            rlwe: lattice::encrypt_polynomial(
+                public_key,
+                &encode(message, scale)?,
+            )?,
+            length: message.len(),
+            scale,
+        })
+    }
+
+    fn decrypt(
+        &self,
+        private_key: &Self::PrivateKey,
+        ciphertext: &Self::Ciphertext,
+    ) -> Result<Self::Plaintext, WedprError> {
+        if ciphertext.length > CKKS_SLOT_COUNT || ciphertext.scale <= 0.0 {
+            return Err(WedprError::FormatError);
+        }
+        let decrypted =
+            lattice::decrypt_polynomial(private_key, &ciphertext.rlwe)?;
+        Ok(decode(&decrypted, ciphertext.length, ciphertext.scale))
+    }
+
+    fn add(
+        &self,
+        _public_key: &Self::PublicKey,
+        ciphertext1: &Self::Ciphertext,
+        ciphertext2: &Self::Ciphertext,
+    ) -> Result<Self::Ciphertext, WedprError> {
+        if (ciphertext1.scale - ciphertext2.scale).abs() > f64::EPSILON {
+            wedpr_println!("CKKS ciphertexts have different scales");
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(CkksCiphertext {
+            rlwe: lattice::add_ciphertexts(
+                &ciphertext1.rlwe,
+                &ciphertext2.rlwe,
+            )?,
+            length: ciphertext1.length.max(ciphertext2.length),
+            scale: ciphertext1.scale,
+        })
+    }
+
+    fn mul_scalar(
+        &self,
+        _public_key: &Self::PublicKey,
+        ciphertext: &Self::Ciphertext,
+        scalar: &Self::Scalar,
+    ) -> Result<Self::Ciphertext, WedprError> {
+        let scalar_scale = (1u64 << CKKS_SCALAR_SCALE_IN_BITS) as f64;
+        let scale = ciphertext.scale * scalar_scale;
+        let encoded_scalar = (scalar * scalar_scale).round();
+        if !encoded_scalar.is_finite()
+            || encoded_scalar.abs() >= (1u64 << 20) as f64
+            || scale >= (LATTICE_CIPHERTEXT_MODULUS >> 8) as f64
+        {
+            wedpr_println!("CKKS scalar multiplication is out of range");
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(CkksCiphertext {
+            rlwe: lattice::mul_ciphertext_scalar(
+                &ciphertext.rlwe,
+                from_signed(encoded_scalar as i64),
+            )?,
+            length: ciphertext.length,
+            scale,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_approx_eq(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, b) in actual.iter().zip(expected.iter()) {
+            assert!((a - b).abs() < 1e-2, "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_ckks() {
+        let ckks = WedprCkks::default();
+        let (public_key, private_key) = ckks.generate_keypair().unwrap();
+        let message1 = vec![1.5f64, -2.25, 100.0];
+        let message2 = vec![0.5f64, 3.0, -0.125];
+        let ciphertext1 = ckks.encrypt(&public_key, &message1).unwrap();
+        let ciphertext2 = ckks.encrypt(&public_key, &message2).unwrap();
+        assert_approx_eq(
+            &ckks.decrypt(&private_key, &ciphertext1).unwrap(),
+            &message1,
+        );
+
+        let sum = ckks.add(&public_key, &ciphertext1, &ciphertext2).unwrap();
+        assert_approx_eq(&ckks.decrypt(&private_key, &sum).unwrap(), &[
+            2.0, 0.75, 99.875,
+        ]);
+        let product = ckks.mul_scalar(&public_key, &sum, &-0.5).unwrap();
+        assert_approx_eq(&ckks.decrypt(&private_key, &product).unwrap(), &[
+            -1.0, -0.375, -49.9375,
+        ]);
+        // Ciphertexts of different scales cannot be added.
+        assert!(ckks.add(&public_key, &sum, &product).is_err());
+        assert!(ckks
+            .encrypt(&public_key, &vec![0.0; CKKS_SLOT_COUNT + 1])
+            .is_err());
+    }
+}
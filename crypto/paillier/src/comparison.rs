@@ -0,0 +1,267 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Secure comparison of two Paillier-encrypted values (DGK/Veugen protocol),
+//! outputting an encrypted bit [[a >= b]].
+//!
+//! The evaluator holds [[a]] and [[b]] encrypted under the public key of the
+//! key holder, where 0 <= a, b < 2^l. The protocol runs as:
+//! 1) The evaluator blinds d = 2^l + a - b + r with a random r.
+//! 2) The key holder decrypts d, and returns [[d / 2^l]] and the encrypted
+//! bits of d mod 2^l.
+//! 3) The evaluator computes masked DGK values, which are zero only if
+//! d mod 2^l and r mod 2^l compare in the way hidden by a random coin.
+//! 4) The key holder tests whether any value is zero, and returns the result
+//! as an encrypted bit.
+//! 5) The evaluator combines the results to [[a >= b]].
+//!
+//! Neither party learns the comparison result, which assumes semi-honest
+//! parties.
+
+use crate::{random_bits, random_unit, PaillierPrivateKey, PaillierPublicKey};
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use rand::{seq::SliceRandom, Rng};
+use wedpr_l_utils::error::WedprError;
+
+/// Statistical security parameter of the blinding value in bits.
+pub const COMPARISON_STATISTICAL_SECURITY_IN_BITS: usize = 40;
+
+/// Message from the key holder with the decomposed blinded difference.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct DecomposedDifference {
+    /// [[d / 2^l]].
+    pub high: BigUint,
+    /// [[i-th bit of d]] for i in [0, l).
+    pub bits: Vec<BigUint>,
+}
+
+/// Evaluator state of a comparison.
+#[derive(Debug, Clone)]
+pub struct ComparisonEvaluator {
+    public_key: PaillierPublicKey,
+    bit_length: usize,
+    blinding: BigUint,
+    coin: bool,
+}
+
+impl ComparisonEvaluator {
+    /// Starts comparing [[a]] and [[b]] of at most bit_length bits, and
+    /// returns the evaluator state with the blinded difference for the key
+    /// holder.
+    pub fn new(
+        public_key: &PaillierPublicKey,
+        bit_length: usize,
+        ciphertext_a: &BigUint,
+        ciphertext_b: &BigUint,
+    ) -> Result<(Self, BigUint), WedprError> {
+        if bit_length == 0
+            || bit_length + COMPARISON_STATISTICAL_SECURITY_IN_BITS + 2
+                >= public_key.n.bits() as usize
+        {
+            wedpr_println!("Comparison bit length is out of range");
+            return Err(WedprError::ArgumentError);
+        }
+        let blinding =
+            random_bits(bit_length + COMPARISON_STATISTICAL_SECURITY_IN_BITS);
+        let offset = (BigUint::one() << bit_length) + &blinding;
+        let blinded_difference =
+            public_key.rerandomize(&public_key.add_plaintext(
+                &public_key.sub(ciphertext_a, ciphertext_b),
+                &offset,
+            ));
+        Ok((
+            ComparisonEvaluator {
+                public_key: public_key.clone(),
+                bit_length,
+                blinding,
+                coin: rand::thread_rng().gen(),
+            },
+            blinded_difference,
+        ))
+    }
+
+    /// Computes the masked and shuffled DGK values for the key holder.
+    pub fn mask(
+        &self,
+        decomposed: &DecomposedDifference,
+    ) -> Result<Vec<BigUint>, WedprError> {
+        if decomposed.bits.len() != self.bit_length {
+            return Err(WedprError::ArgumentError);
+        }
+        let public_key = &self.public_key;
+        let n = &public_key.n;
+        let encrypted_one = public_key.encrypt(&BigUint::one())?;
+        // s = 1 tests d mod 2^l < r mod 2^l, and s = -1 tests the opposite.
+        let s = if self.coin {
+            n - BigUint::one()
+        } else {
+            BigUint::one()
+        };
+
+        // w_i = [[d_i xor r_i]].
+        let xor_bits: Vec<BigUint> = decomposed
+            .bits
+            .iter()
+            .enumerate()
+            .map(|(i, bit)| {
+                if self.blinding.bit(i as u64) {
+                    public_key.sub(&encrypted_one, bit)
+                } else {
+                    bit.clone()
+                }
+            })
+            .collect();
+
+        // c_i = [[d_i - r_i + s + 3 * sum_{j > i} w_j]].
+        let mut values = Vec::with_capacity(self.bit_length + 1);
+        let mut higher_xor_sum = public_key.encrypt(&BigUint::zero())?;
+        for i in (0..self.bit_length).rev() {
+            let r_i = if self.blinding.bit(i as u64) {
+                BigUint::one()
+            } else {
+                BigUint::zero()
+            };
+            let constant = (&s + n - r_i) % n;
+            let value = public_key.add(
+                &public_key.add_plaintext(&decomposed.bits[i], &constant),
+                &public_key
+                    .mul_plaintext(&higher_xor_sum, &BigUint::from(3u32)),
+            );
+            values.push(value);
+            higher_xor_sum = public_key.add(&higher_xor_sum, &xor_bits[i]);
+        }
+        // With s = -1, equality also needs to yield a zero, which is tested by
+        // the sum of all w_i. Otherwise a non-zero dummy value is used.
+        values.push(if self.coin {
+            higher_xor_sum
+        } else {
+            encrypted_one
+        });
+
+        let mut masked_values: Vec<BigUint> = values
+            .iter()
+            .map(|value| {
+                public_key.rerandomize(
+                    &public_key.mul_plaintext(value, &random_unit(n)),
+                )
+            })
+            .collect();
+        masked_values.shuffle(&mut rand::thread_rng());
+        Ok(masked_values)
+    }
+
+    /// Combines the zero test result of the key holder to [[a >= b]].
+    pub fn finish(
+        &self,
+        decomposed: &DecomposedDifference,
+        zero_test_result: &BigUint,
+    ) -> Result<BigUint, WedprError> {
+        let public_key = &self.public_key;
+        // [[t]] = [[(d mod 2^l) < (r mod 2^l)]].
+        let less_than = if self.coin {
+            public_key
+                .sub(&public_key.encrypt(&BigUint::one())?, zero_test_result)
+        } else {
+            zero_test_result.clone()
+        };
+        // a >= b if and only if (d / 2^l) - (r / 2^l) - t = 1.
+        let blinding_high = &self.blinding >> self.bit_length;
+        let result = public_key.sub(
+            &public_key
+                .sub(&decomposed.high, &public_key.encrypt(&blinding_high)?),
+            &less_than,
+        );
+        Ok(public_key.rerandomize(&result))
+    }
+}
+
+/// Decrypts and decomposes the blinded difference, which is run by the key
+/// holder.
+pub fn decompose_difference(
+    private_key: &PaillierPrivateKey,
+    bit_length: usize,
+    blinded_difference: &BigUint,
+) -> Result<DecomposedDifference, WedprError> {
+    let public_key = &private_key.public_key;
+    let difference = private_key.decrypt(blinded_difference)?;
+    let bits = (0..bit_length)
+        .map(|i| {
+            let bit = if difference.bit(i as u64) {
+                BigUint::one()
+            } else {
+                BigUint::zero()
+            };
+            public_key.encrypt(&bit)
+        })
+        .collect::<Result<Vec<BigUint>, WedprError>>()?;
+    Ok(DecomposedDifference {
+        high: public_key.encrypt(&(difference >> bit_length))?,
+        bits,
+    })
+}
+
+/// Tests whether any masked value decrypts to zero, and returns the result as
+/// an encrypted bit, which is run by the key holder.
+pub fn zero_test(
+    private_key: &PaillierPrivateKey,
+    masked_values: &[BigUint],
+) -> Result<BigUint, WedprError> {
+    let mut has_zero = false;
+    for value in masked_values {
+        if private_key.decrypt(value)?.is_zero() {
+            has_zero = true;
+        }
+    }
+    let result = if has_zero {
+        BigUint::one()
+    } else {
+        BigUint::zero()
+    };
+    private_key.public_key.encrypt(&result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_keypair;
+
+    #[test]
+    fn test_secure_comparison() {
+        let (public_key, private_key) = generate_keypair(512).unwrap();
+        let bit_length = 16;
+        for (a, b) in &[(5u32, 3u32), (3, 5), (7, 7), (0, 65535), (65535, 0)] {
+            let ciphertext_a = public_key.encrypt(&BigUint::from(*a)).unwrap();
+            let ciphertext_b = public_key.encrypt(&BigUint::from(*b)).unwrap();
+            let (evaluator, blinded_difference) = ComparisonEvaluator::new(
+                &public_key,
+                bit_length,
+                &ciphertext_a,
+                &ciphertext_b,
+            )
+            .unwrap();
+            let decomposed = decompose_difference(
+                &private_key,
+                bit_length,
+                &blinded_difference,
+            )
+            .unwrap();
+            let masked_values = evaluator.mask(&decomposed).unwrap();
+            let zero_test_result =
+                zero_test(&private_key, &masked_values).unwrap();
+            let result =
+                evaluator.finish(&decomposed, &zero_test_result).unwrap();
+            let expected = if a >= b { 1u32 } else { 0u32 };
+            assert_eq!(
+                private_key.decrypt(&result).unwrap(),
+                BigUint::from(expected)
+            );
+        }
+        assert!(ComparisonEvaluator::new(
+            &public_key,
+            512,
+            &BigUint::one(),
+            &BigUint::one()
+        )
+        .is_err());
+    }
+}
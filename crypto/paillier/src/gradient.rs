@@ -0,0 +1,328 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Helpers of federated learning (FL) for aggregating model gradient vectors
+//! without revealing the gradient of any participant.
+//!
+//! Gradients are encoded as fixed-point integers, which fail on values out
+//! of range instead of silently wrapping. Two aggregation methods are
+//! provided:
+//! 1) Vectorized Paillier, where gradients are packed into Paillier plaintext
+//!    slots, summed homomorphically and decrypted by the key holder.
+//! 2) Masking-based secure aggregation, where each pair of participants derives
+//!    a mask from their shared seed, which one adds and the other subtracts, so
+//!    that all masks cancel out in the sum.
+
+use crate::{
+    packing::{PackedCiphertext, PackingScheme},
+    PaillierPrivateKey, PaillierPublicKey,
+};
+use num_traits::ToPrimitive;
+use wedpr_l_utils::{error::WedprError, traits::Hash};
+
+/// Domain separator of pairwise gradient masks.
+const GRADIENT_MASK_DOMAIN: &[u8] = b"WeDPR_FL_GRADIENT_MASK";
+
+/// Fixed-point codec of gradient values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedPointCodec {
+    fractional_bits: u32,
+    value_size_in_bits: usize,
+}
+
+impl FixedPointCodec {
+    /// Creates a codec scaling values by 2^fractional_bits, where encoded
+    /// values including the sign take value_size_in_bits bits.
+    pub fn new(
+        fractional_bits: u32,
+        value_size_in_bits: usize,
+    ) -> Result<Self, WedprError> {
+        if value_size_in_bits < 2
+            || value_size_in_bits > 63
+            || fractional_bits as usize >= value_size_in_bits
+        {
+            wedpr_println!("Invalid fixed-point parameters");
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(FixedPointCodec {
+            fractional_bits,
+            value_size_in_bits,
+        })
+    }
+
+    fn scale(&self) -> f64 {
+        (1u64 << self.fractional_bits) as f64
+    }
+
+    /// Gets the offset added to encoded values for unsigned encoding.
+    fn offset(&self) -> u64 {
+        1u64 << (self.value_size_in_bits - 1)
+    }
+
+    /// Encodes a value, which fails if it is not finite or out of range.
+    pub fn encode(&self, value: f64) -> Result<i64, WedprError> {
+        let scaled = (value * self.scale()).round();
+        let limit = self.offset() as f64;
+        if !scaled.is_finite() || scaled >= limit || scaled < -limit {
+            wedpr_println!("Gradient value {} is out of range", value);
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(scaled as i64)
+    }
+
+    /// Decodes a value or a sum of values.
+    pub fn decode(&self, value: i64) -> f64 {
+        value as f64 / self.scale()
+    }
+
+    /// Encodes a value as an unsigned integer of value_size_in_bits bits.
+    fn encode_unsigned(&self, value: f64) -> Result<u64, WedprError> {
+        Ok((self.encode(value)? + self.offset() as i64) as u64)
+    }
+}
+
+/// Encryptor of gradients by vectorized Paillier.
+#[derive(Debug, Clone)]
+pub struct GradientEncryptor {
+    scheme: PackingScheme,
+    codec: FixedPointCodec,
+}
+
+impl GradientEncryptor {
+    /// Creates an encryptor whose slots have headroom for summing gradients
+    /// of max_participants participants.
+    pub fn new(
+        public_key: &PaillierPublicKey,
+        codec: FixedPointCodec,
+        max_participants: usize,
+    ) -> Result<Self, WedprError> {
+        if max_participants == 0 {
+            return Err(WedprError::ArgumentError);
+        }
+        // Sums of max_participants values take these more bits.
+        let headroom_bits = std::mem::size_of::<usize>() * 8
+            - max_participants.leading_zeros() as usize;
+        Ok(GradientEncryptor {
+            scheme: PackingScheme::new(
+                public_key,
+                codec.value_size_in_bits,
+                codec.value_size_in_bits + headroom_bits,
+            )?,
+            codec,
+        })
+    }
+
+    /// Encrypts a gradient into packed ciphertexts.
+    pub fn encrypt(
+        &self,
+        gradient: &[f64],
+    ) -> Result<Vec<PackedCiphertext>, WedprError> {
+        let encoded = gradient
+            .iter()
+            .map(|v| self.codec.encode_unsigned(*v))
+            .collect::<Result<Vec<u64>, WedprError>>()?;
+        encoded
+            .chunks(self.scheme.slots_per_ciphertext())
+            .map(|chunk| self.scheme.encrypt(chunk))
+            .collect()
+    }
+
+    /// Aggregates encrypted gradients of participants, which fails if they
+    /// have different lengths or the sum may overflow.
+    pub fn aggregate(
+        &self,
+        encrypted_gradients: &[Vec<PackedCiphertext>],
+    ) -> Result<Vec<PackedCiphertext>, WedprError> {
+        let (first, rest) = match encrypted_gradients.split_first() {
+            Some(v) => v,
+            None => return Err(WedprError::ArgumentError),
+        };
+        let mut result = first.clone();
+        for encrypted_gradient in rest {
+            if encrypted_gradient.len() != result.len() {
+                wedpr_println!("Encrypted gradients have different lengths");
+                return Err(WedprError::ArgumentError);
+            }
+            for (sum, packed) in result.iter_mut().zip(encrypted_gradient) {
+                if sum.slot_count != packed.slot_count {
+                    return Err(WedprError::ArgumentError);
+                }
+                *sum = self.scheme.add(sum, packed)?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Decrypts the sum of gradients of participant_count participants.
+    pub fn decrypt_sum(
+        &self,
+        private_key: &PaillierPrivateKey,
+        aggregated: &[PackedCiphertext],
+        participant_count: usize,
+    ) -> Result<Vec<f64>, WedprError> {
+        let total_offset =
+            self.codec.offset() as i128 * participant_count as i128;
+        let mut gradient = Vec::new();
+        for packed in aggregated {
+            for slot in self.scheme.decrypt(private_key, packed)? {
+                let sum = match slot.to_i128() {
+                    Some(v) => v - total_offset,
+                    None => return Err(WedprError::FormatError),
+                };
+                if sum < i64::MIN as i128 || sum > i64::MAX as i128 {
+                    wedpr_println!("Gradient sum is out of range");
+                    return Err(WedprError::FormatError);
+                }
+                gradient.push(self.codec.decode(sum as i64));
+            }
+        }
+        Ok(gradient)
+    }
+}
+
+/// Derives the mask between two participants for a training round.
+fn pairwise_mask<H: Hash>(
+    hash: &H,
+    seed: &[u8],
+    round: u64,
+    length: usize,
+) -> Vec<u64> {
+    let mut mask = Vec::with_capacity(length);
+    let mut counter = 0u64;
+    while mask.len() < length {
+        let mut hash_input = GRADIENT_MASK_DOMAIN.to_vec();
+        hash_input.extend_from_slice(seed);
+        hash_input.extend_from_slice(&round.to_be_bytes());
+        hash_input.extend_from_slice(&counter.to_be_bytes());
+        for chunk in hash.hash(&hash_input).chunks_exact(8) {
+            let mut buffer = [0u8; 8];
+            buffer.copy_from_slice(chunk);
+            mask.push(u64::from_be_bytes(buffer));
+        }
+        counter += 1;
+    }
+    mask.truncate(length);
+    mask
+}
+
+/// Masks the gradient of a participant for secure aggregation, where
+/// peer_seeds holds the index of each peer and the seed shared with it, e.g.
+/// by a key agreement. The mask is added for peers of larger indexes and
+/// subtracted for peers of smaller indexes.
+pub fn mask_gradient<H: Hash>(
+    hash: &H,
+    codec: &FixedPointCodec,
+    gradient: &[f64],
+    own_index: usize,
+    peer_seeds: &[(usize, &[u8])],
+    round: u64,
+) -> Result<Vec<u64>, WedprError> {
+    let mut masked = gradient
+        .iter()
+        .map(|v| Ok(codec.encode(*v)? as u64))
+        .collect::<Result<Vec<u64>, WedprError>>()?;
+    for (peer_index, seed) in peer_seeds {
+        if *peer_index == own_index {
+            return Err(WedprError::ArgumentError);
+        }
+        let mask = pairwise_mask(hash, seed, round, masked.len());
+        for (value, mask_value) in masked.iter_mut().zip(mask) {
+            *value = if *peer_index > own_index {
+                value.wrapping_add(mask_value)
+            } else {
+                value.wrapping_sub(mask_value)
+            };
+        }
+    }
+    Ok(masked)
+}
+
+/// Sums the masked gradients of all participants, and decodes the sum of
+/// their gradients, which fails if the sum may overflow.
+pub fn unmask_sum(
+    codec: &FixedPointCodec,
+    masked_gradients: &[Vec<u64>],
+) -> Result<Vec<f64>, WedprError> {
+    let length = match masked_gradients.first() {
+        Some(v) => v.len(),
+        None => return Err(WedprError::ArgumentError),
+    };
+    // Every encoded value is within [-offset, offset).
+    if codec.offset() as u128 * masked_gradients.len() as u128
+        > i64::MAX as u128
+    {
+        wedpr_println!("Too many participants for the fixed-point codec");
+        return Err(WedprError::ArgumentError);
+    }
+    let mut sum = vec![0u64; length];
+    for masked in masked_gradients {
+        if masked.len() != length {
+            wedpr_println!("Masked gradients have different lengths");
+            return Err(WedprError::ArgumentError);
+        }
+        for (total, value) in sum.iter_mut().zip(masked) {
+            *total = total.wrapping_add(*value);
+        }
+    }
+    Ok(sum.iter().map(|v| codec.decode(*v as i64)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_keypair;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+
+    #[test]
+    fn test_gradient_aggregation() {
+        let codec = FixedPointCodec::new(16, 32).unwrap();
+        assert!((codec.decode(codec.encode(-1.5).unwrap()) + 1.5).abs() < 1e-9);
+        assert!(codec.encode(32768.0).is_err());
+        assert!(codec.encode(f64::NAN).is_err());
+        let gradients = vec![
+            vec![0.5, -1.25, 3.0, 0.0, -0.75],
+            vec![-0.25, 2.0, -1.0, 1.5, 0.25],
+            vec![1.0, 0.5, 0.25, -2.5, 0.5],
+        ];
+        let expected = vec![1.25, 1.25, 2.25, -1.0, 0.0];
+
+        let (public_key, private_key) = generate_keypair(512).unwrap();
+        let encryptor = GradientEncryptor::new(&public_key, codec, 3).unwrap();
+        let encrypted: Vec<Vec<PackedCiphertext>> = gradients
+            .iter()
+            .map(|v| encryptor.encrypt(v).unwrap())
+            .collect();
+        let aggregated = encryptor.aggregate(&encrypted).unwrap();
+        assert_eq!(
+            encryptor.decrypt_sum(&private_key, &aggregated, 3).unwrap(),
+            expected
+        );
+        // Slots have no headroom for 5 participants.
+        let mut too_many = encrypted.clone();
+        too_many.extend_from_slice(&encrypted[..2]);
+        assert!(encryptor.aggregate(&too_many).is_err());
+
+        let hash = WedprKeccak256::default();
+        let seeds: [&[u8]; 3] = [b"seed-01", b"seed-02", b"seed-12"];
+        let peer_seeds = [
+            vec![(1, seeds[0]), (2, seeds[1])],
+            vec![(0, seeds[0]), (2, seeds[2])],
+            vec![(0, seeds[1]), (1, seeds[2])],
+        ];
+        let masked: Vec<Vec<u64>> = (0..3)
+            .map(|i| {
+                mask_gradient(
+                    &hash,
+                    &codec,
+                    &gradients[i],
+                    i,
+                    &peer_seeds[i],
+                    7,
+                )
+                .unwrap()
+            })
+            .collect();
+        assert_ne!(masked[0][0], codec.encode(0.5).unwrap() as u64);
+        assert_eq!(unmask_sum(&codec, &masked).unwrap(), expected);
+        assert!(unmask_sum(&codec, &masked[..2]).unwrap() != expected);
+    }
+}
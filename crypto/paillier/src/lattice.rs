@@ -0,0 +1,249 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Ring learning with errors (RLWE) functions shared by the lattice schemes
+//! BFV and CKKS.
+//!
+//! Polynomials live in Z_q[X] / (X^N + 1) with N = 2048 and q = 2^54, which
+//! targets 128-bit security for ternary secrets according to the homomorphic
+//! encryption standard. Multiplication is computed by the schoolbook method,
+//! which is suitable for additive workloads without relinearization.
+
+use rand::Rng;
+use wedpr_l_utils::error::WedprError;
+
+/// Degree N of the polynomial ring.
+pub const LATTICE_RING_DEGREE: usize = 2048;
+/// Ciphertext modulus q.
+pub const LATTICE_CIPHERTEXT_MODULUS: u64 = 1 << 54;
+/// Parameter k of the centered binomial noise, whose standard deviation is
+/// sqrt(k / 2), about 3.2.
+const NOISE_BINOMIAL_PARAMETER: u32 = 21;
+
+/// Polynomial of N coefficients in [0, q).
+pub type Polynomial = Vec<u64>;
+
+/// RLWE public key (b, a), where b = -a * s + e.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct RlwePublicKey {
+    pub b: Polynomial,
+    pub a: Polynomial,
+}
+
+/// RLWE private key with a ternary secret s.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct RlwePrivateKey {
+    pub s: Polynomial,
+}
+
+/// RLWE ciphertext (c0, c1), where c0 + c1 * s = m + e.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct RlweCiphertext {
+    pub c0: Polynomial,
+    pub c1: Polynomial,
+}
+
+/// Converts a signed integer to a coefficient in [0, q).
+pub fn from_signed(value: i64) -> u64 {
+    (value.rem_euclid(LATTICE_CIPHERTEXT_MODULUS as i64)) as u64
+}
+
+/// Converts a coefficient in [0, q) to a signed integer in [-q / 2, q / 2).
+pub fn to_signed(value: u64) -> i64 {
+    if value >= LATTICE_CIPHERTEXT_MODULUS / 2 {
+        value as i64 - LATTICE_CIPHERTEXT_MODULUS as i64
+    } else {
+        value as i64
+    }
+}
+
+/// Adds two polynomials.
+pub fn poly_add(x: &[u64], y: &[u64]) -> Polynomial {
+    x.iter()
+        .zip(y.iter())
+        .map(|(a, b)| (a + b) % LATTICE_CIPHERTEXT_MODULUS)
+        .collect()
+}
+
+/// Multiplies a polynomial by a scalar in [0, q).
+pub fn poly_mul_scalar(x: &[u64], scalar: u64) -> Polynomial {
+    x.iter()
+        .map(|a| {
+            ((*a as u128 * scalar as u128) % LATTICE_CIPHERTEXT_MODULUS as u128)
+                as u64
+        })
+        .collect()
+}
+
+/// Multiplies two polynomials modulo X^N + 1.
+pub fn poly_mul(x: &[u64], y: &[u64]) -> Polynomial {
+    let n = x.len();
+    // Products are below 2^108, so that N of them can be summed up in u128
+    // without reduction.
+    let mut positive = vec![0u128; n];
+    let mut negative = vec![0u128; n];
+    for (i, a) in x.iter().enumerate() {
+        if *a == 0 {
+            continue;
+        }
+        for (j, b) in y.iter().enumerate() {
+            let product = *a as u128 * *b as u128;
+            if i + j < n {
+                positive[i + j] += product;
+            } else {
+                negative[i + j - n] += product;
+            }
+        }
+    }
+    let q = LATTICE_CIPHERTEXT_MODULUS as u128;
+    positive
+        .iter()
+        .zip(negative.iter())
+        .map(|(p, m)| ((p % q + q - m % q) % q) as u64)
+        .collect()
+}
+
+fn sample_uniform() -> Polynomial {
+    let mut rng = rand::thread_rng();
+    (0..LATTICE_RING_DEGREE)
+        .map(|_| rng.gen_range(0, LATTICE_CIPHERTEXT_MODULUS))
+        .collect()
+}
+
+fn sample_ternary() -> Polynomial {
+    let mut rng = rand::thread_rng();
+    (0..LATTICE_RING_DEGREE)
+        .map(|_| from_signed(rng.gen_range(-1, 2)))
+        .collect()
+}
+
+fn sample_noise() -> Polynomial {
+    let mut rng = rand::thread_rng();
+    let mask = (1u32 << NOISE_BINOMIAL_PARAMETER) - 1;
+    (0..LATTICE_RING_DEGREE)
+        .map(|_| {
+            let positive = (rng.gen::<u32>() & mask).count_ones() as i64;
+            let negative = (rng.gen::<u32>() & mask).count_ones() as i64;
+            from_signed(positive - negative)
+        })
+        .collect()
+}
+
+/// Checks that a polynomial has N coefficients in [0, q).
+pub fn check_polynomial(x: &[u64]) -> Result<(), WedprError> {
+    if x.len() != LATTICE_RING_DEGREE
+        || x.iter().any(|a| *a >= LATTICE_CIPHERTEXT_MODULUS)
+    {
+        wedpr_println!("Invalid RLWE polynomial");
+        return Err(WedprError::FormatError);
+    }
+    Ok(())
+}
+
+/// Checks the polynomials of a ciphertext.
+pub fn check_ciphertext(ciphertext: &RlweCiphertext) -> Result<(), WedprError> {
+    check_polynomial(&ciphertext.c0)?;
+    check_polynomial(&ciphertext.c1)
+}
+
+/// Generates an RLWE key pair.
+pub fn generate_keypair() -> (RlwePublicKey, RlwePrivateKey) {
+    let s = sample_ternary();
+    let a = sample_uniform();
+    let a_s = poly_mul(&a, &s);
+    let b = poly_add(
+        &a_s.iter()
+            .map(|v| {
+                (LATTICE_CIPHERTEXT_MODULUS - v) % LATTICE_CIPHERTEXT_MODULUS
+            })
+            .collect::<Polynomial>(),
+        &sample_noise(),
+    );
+    (RlwePublicKey { b, a }, RlwePrivateKey { s })
+}
+
+/// Encrypts an encoded polynomial m, computing
+/// (b * u + e1 + m, a * u + e2) for a ternary u.
+pub fn encrypt_polynomial(
+    public_key: &RlwePublicKey,
+    message: &[u64],
+) -> Result<RlweCiphertext, WedprError> {
+    check_polynomial(&public_key.a)?;
+    check_polynomial(&public_key.b)?;
+    check_polynomial(message)?;
+    let u = sample_ternary();
+    let c0 = poly_add(
+        &poly_add(&poly_mul(&public_key.b, &u), &sample_noise()),
+        message,
+    );
+    let c1 = poly_add(&poly_mul(&public_key.a, &u), &sample_noise());
+    Ok(RlweCiphertext { c0, c1 })
+}
+
+/// Decrypts a ciphertext to its noisy encoded polynomial c0 + c1 * s.
+pub fn decrypt_polynomial(
+    private_key: &RlwePrivateKey,
+    ciphertext: &RlweCiphertext,
+) -> Result<Polynomial, WedprError> {
+    check_polynomial(&private_key.s)?;
+    check_ciphertext(ciphertext)?;
+    Ok(poly_add(
+        &ciphertext.c0,
+        &poly_mul(&ciphertext.c1, &private_key.s),
+    ))
+}
+
+/// Adds two ciphertexts.
+pub fn add_ciphertexts(
+    ciphertext1: &RlweCiphertext,
+    ciphertext2: &RlweCiphertext,
+) -> Result<RlweCiphertext, WedprError> {
+    check_ciphertext(ciphertext1)?;
+    check_ciphertext(ciphertext2)?;
+    Ok(RlweCiphertext {
+        c0: poly_add(&ciphertext1.c0, &ciphertext2.c0),
+        c1: poly_add(&ciphertext1.c1, &ciphertext2.c1),
+    })
+}
+
+/// Multiplies a ciphertext by a scalar in [0, q).
+pub fn mul_ciphertext_scalar(
+    ciphertext: &RlweCiphertext,
+    scalar: u64,
+) -> Result<RlweCiphertext, WedprError> {
+    check_ciphertext(ciphertext)?;
+    Ok(RlweCiphertext {
+        c0: poly_mul_scalar(&ciphertext.c0, scalar),
+        c1: poly_mul_scalar(&ciphertext.c1, scalar),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rlwe() {
+        // (1 + X) * X^(N - 1) = X^(N - 1) - 1 modulo X^N + 1.
+        let mut x = vec![0u64; LATTICE_RING_DEGREE];
+        x[0] = 1;
+        x[1] = 1;
+        let mut y = vec![0u64; LATTICE_RING_DEGREE];
+        y[LATTICE_RING_DEGREE - 1] = 1;
+        let product = poly_mul(&x, &y);
+        assert_eq!(product[0], LATTICE_CIPHERTEXT_MODULUS - 1);
+        assert_eq!(product[LATTICE_RING_DEGREE - 1], 1);
+        assert_eq!(to_signed(from_signed(-5)), -5);
+
+        let (public_key, private_key) = generate_keypair();
+        let message = poly_mul_scalar(&x, 1 << 40);
+        let ciphertext = encrypt_polynomial(&public_key, &message).unwrap();
+        let decrypted = decrypt_polynomial(&private_key, &ciphertext).unwrap();
+        for (value, expected) in decrypted.iter().zip(message.iter()) {
+            let noise = to_signed(from_signed(
+                to_signed(*value) - to_signed(*expected),
+            ));
+            assert!(noise.abs() < 1 << 20);
+        }
+        assert!(encrypt_polynomial(&public_key, &x[1..]).is_err());
+    }
+}
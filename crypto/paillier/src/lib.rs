@@ -0,0 +1,555 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Paillier homomorphic encryption functions.
+//!
+//! Ciphertexts are integers modulo n^2, where the generator is fixed to
+//! g = n + 1. Ciphertexts can be added together or multiplied by plaintext
+//! constants without decryption.
+//!
+//! Lattice schemes BFV and CKKS are available with the feature
+//! wedpr_f_lattice_fhe, sharing the HomomorphicEncryption trait with
+//! Paillier.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use rand::RngCore;
+use wedpr_l_utils::{
+    error::WedprError,
+    scalar::{default_window_bits, Field2048, Field4096, ScalarField},
+    traits::HomomorphicEncryption,
+};
+
+pub mod batch;
+#[cfg(feature = "wedpr_f_lattice_fhe")]
+pub mod bfv;
+#[cfg(feature = "wedpr_f_lattice_fhe")]
+pub mod ckks;
+pub mod comparison;
+pub mod gradient;
+#[cfg(feature = "wedpr_f_lattice_fhe")]
+pub mod lattice;
+pub mod packing;
+pub mod threshold;
+
+/// Default size of the modulus n.
+pub const DEFAULT_PAILLIER_KEY_SIZE_IN_BITS: usize = 2048;
+/// Minimum size of the modulus n, where 2048 bits or more are recommended for
+/// production use.
+pub const MIN_PAILLIER_KEY_SIZE_IN_BITS: usize = 512;
+
+/// Rounds of Miller-Rabin tests for generating primes.
+const MILLER_RABIN_ROUNDS: usize = 40;
+const SMALL_PRIMES: [u32; 15] =
+    [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47];
+
+/// Paillier public key.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PaillierPublicKey {
+    pub n: BigUint,
+    pub n_squared: BigUint,
+}
+
+/// Paillier private key.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PaillierPrivateKey {
+    pub p: BigUint,
+    pub q: BigUint,
+    /// lcm(p - 1, q - 1).
+    pub lambda: BigUint,
+    /// lambda^-1 mod n.
+    pub mu: BigUint,
+    pub public_key: PaillierPublicKey,
+}
+
+/// Generates a random integer of at most the given number of bits.
+pub fn random_bits(bits: usize) -> BigUint {
+    let mut bytes = vec![0u8; (bits + 7) / 8];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    BigUint::from_bytes_be(&bytes) >> (bytes.len() * 8 - bits)
+}
+
+/// Generates a random integer in [0, bound).
+pub fn random_below(bound: &BigUint) -> BigUint {
+    let bits = bound.bits() as usize;
+    loop {
+        let candidate = random_bits(bits);
+        if &candidate < bound {
+            return candidate;
+        }
+    }
+}
+
+/// Generates a random integer in [1, n) coprime to n.
+pub fn random_unit(n: &BigUint) -> BigUint {
+    loop {
+        let candidate = random_below(n);
+        if !candidate.is_zero() && candidate.gcd(n).is_one() {
+            return candidate;
+        }
+    }
+}
+
+/// Modular operation, which runs in constant time by Montgomery arithmetic
+/// for odd moduli of at most 4096 bits, which covers n^2 of keys up to 2048
+/// bits, and by num-bigint otherwise.
+enum ModularOperation<'a> {
+    Add(&'a BigUint, &'a BigUint),
+    Mul(&'a BigUint, &'a BigUint),
+    Pow(&'a BigUint, &'a BigUint),
+    Inverse(&'a BigUint),
+}
+
+impl<'a> ModularOperation<'a> {
+    /// Runs the operation, which is None only if an inverse does not exist.
+    fn run(&self, modulus: &BigUint) -> Option<BigUint> {
+        let modulus_bytes = modulus.to_bytes_be();
+        // Creating a field fails for even moduli.
+        let result = match modulus.bits() {
+            2..=2048 => Field2048::new(&modulus_bytes)
+                .ok()
+                .map(|v| self.run_montgomery(&v, modulus)),
+            2049..=4096 => Field4096::new(&modulus_bytes)
+                .ok()
+                .map(|v| self.run_montgomery(&v, modulus)),
+            _ => None,
+        };
+        match result {
+            Some(v) => v,
+            None => self.run_bigint(modulus),
+        }
+    }
+
+    fn run_montgomery<const LIMBS: usize>(
+        &self,
+        field: &ScalarField<LIMBS>,
+        modulus: &BigUint,
+    ) -> Option<BigUint> {
+        let decode = |value: &BigUint| {
+            field.decode(&(value % modulus).to_bytes_be()).ok()
+        };
+        let result = match self {
+            ModularOperation::Add(a, b) => decode(a)?.add(&decode(b)?),
+            ModularOperation::Mul(a, b) => decode(a)?.mul(&decode(b)?),
+            ModularOperation::Pow(base, exponent) => {
+                let exponent_bytes = exponent.to_bytes_be();
+                let window_bits = default_window_bits(exponent_bytes.len() * 8);
+                decode(base)?.pow_bytes(&exponent_bytes, window_bits).ok()?
+            },
+            ModularOperation::Inverse(a) => decode(a)?.inverse().ok()?,
+        };
+        Some(BigUint::from_bytes_be(&result.to_bytes()))
+    }
+
+    fn run_bigint(&self, modulus: &BigUint) -> Option<BigUint> {
+        match self {
+            ModularOperation::Add(a, b) => Some((*a + *b) % modulus),
+            ModularOperation::Mul(a, b) => Some((*a * *b) % modulus),
+            ModularOperation::Pow(base, exponent) => {
+                Some(base.modpow(exponent, modulus))
+            },
+            ModularOperation::Inverse(a) => {
+                let signed_modulus = BigInt::from(modulus.clone());
+                let extended_gcd =
+                    BigInt::from((*a).clone()).extended_gcd(&signed_modulus);
+                if !extended_gcd.gcd.is_one() {
+                    return None;
+                }
+                extended_gcd.x.mod_floor(&signed_modulus).to_biguint()
+            },
+        }
+    }
+}
+
+/// Computes a + b mod modulus, in constant time for odd moduli of at most
+/// 4096 bits.
+pub fn mod_add(a: &BigUint, b: &BigUint, modulus: &BigUint) -> BigUint {
+    ModularOperation::Add(a, b).run(modulus).unwrap_or_default()
+}
+
+/// Computes a * b mod modulus, in constant time for odd moduli of at most
+/// 4096 bits.
+pub fn mod_mul(a: &BigUint, b: &BigUint, modulus: &BigUint) -> BigUint {
+    ModularOperation::Mul(a, b).run(modulus).unwrap_or_default()
+}
+
+/// Computes the inverse of a modulo m, if it exists, in constant time for
+/// odd moduli of at most 4096 bits.
+pub fn mod_inverse(a: &BigUint, m: &BigUint) -> Option<BigUint> {
+    ModularOperation::Inverse(a).run(m)
+}
+
+/// Computes base^exponent mod modulus, in constant time for odd moduli of at
+/// most 4096 bits, where the running time only depends on the exponent size.
+pub fn mod_pow(
+    base: &BigUint,
+    exponent: &BigUint,
+    modulus: &BigUint,
+) -> BigUint {
+    ModularOperation::Pow(base, exponent)
+        .run(modulus)
+        .unwrap_or_default()
+}
+
+/// Checks whether a number is prime by Miller-Rabin tests.
+fn is_probable_prime(candidate: &BigUint) -> bool {
+    for small_prime in SMALL_PRIMES.iter() {
+        let small_prime = BigUint::from(*small_prime);
+        if candidate == &small_prime {
+            return true;
+        }
+        if (candidate % &small_prime).is_zero() {
+            return false;
+        }
+    }
+    if candidate < &BigUint::from(2u32) {
+        return false;
+    }
+    let one = BigUint::one();
+    let two = BigUint::from(2u32);
+    let candidate_minus_one = candidate - &one;
+    let mut d = candidate_minus_one.clone();
+    let mut s = 0;
+    while d.is_even() {
+        d >>= 1;
+        s += 1;
+    }
+    'witness: for _ in 0..MILLER_RABIN_ROUNDS {
+        // Witness in [2, candidate - 2].
+        let witness = &two + random_below(&(candidate - 3u32));
+        let mut x = witness.modpow(&d, candidate);
+        if x == one || x == candidate_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&two, candidate);
+            if x == candidate_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// Generates a prime of exactly the given number of bits, whose two highest
+/// bits are set so that the product of two such primes has twice the bits.
+fn generate_prime(bits: usize) -> BigUint {
+    let high_bits =
+        (BigUint::one() << (bits - 1)) | (BigUint::one() << (bits - 2));
+    loop {
+        let candidate = random_bits(bits) | &high_bits | BigUint::one();
+        if is_probable_prime(&candidate) {
+            return candidate;
+        }
+    }
+}
+
+/// L function of Paillier, computing (x - 1) / n.
+fn l_function(x: &BigUint, n: &BigUint) -> BigUint {
+    (x - BigUint::one()) / n
+}
+
+/// Generates a Paillier key pair whose modulus n has the given size.
+pub fn generate_keypair(
+    key_size_in_bits: usize,
+) -> Result<(PaillierPublicKey, PaillierPrivateKey), WedprError> {
+    if key_size_in_bits < MIN_PAILLIER_KEY_SIZE_IN_BITS
+        || key_size_in_bits % 2 != 0
+    {
+        wedpr_println!("Invalid Paillier key size");
+        return Err(WedprError::ArgumentError);
+    }
+    let p = generate_prime(key_size_in_bits / 2);
+    let q = loop {
+        let candidate = generate_prime(key_size_in_bits / 2);
+        if candidate != p {
+            break candidate;
+        }
+    };
+    keypair_from_primes(p, q)
+}
+
+/// Derives a Paillier key pair from two distinct primes of the same size.
+pub fn keypair_from_primes(
+    p: BigUint,
+    q: BigUint,
+) -> Result<(PaillierPublicKey, PaillierPrivateKey), WedprError> {
+    let n = &p * &q;
+    let one = BigUint::one();
+    let lambda = (&p - &one).lcm(&(&q - &one));
+    let mu = match mod_inverse(&lambda, &n) {
+        Some(v) => v,
+        None => {
+            wedpr_println!("Invalid Paillier primes");
+            return Err(WedprError::ArgumentError);
+        },
+    };
+    let public_key = PaillierPublicKey {
+        n_squared: &n * &n,
+        n,
+    };
+    Ok((public_key.clone(), PaillierPrivateKey {
+        p,
+        q,
+        lambda,
+        mu,
+        public_key,
+    }))
+}
+
+impl PaillierPublicKey {
+    /// Encrypts a plaintext in [0, n).
+    pub fn encrypt(&self, message: &BigUint) -> Result<BigUint, WedprError> {
+        if message >= &self.n {
+            wedpr_println!("Paillier plaintext is out of range");
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(self.encrypt_with_randomness(message, &random_unit(&self.n)))
+    }
+
+    /// Encrypts a plaintext with the given randomness r, computing
+    /// (1 + m * n) * r^n mod n^2.
+    pub fn encrypt_with_randomness(
+        &self,
+        message: &BigUint,
+        randomness: &BigUint,
+    ) -> BigUint {
+        let g_m = BigUint::one() + mod_mul(message, &self.n, &self.n_squared);
+        mod_mul(
+            &g_m,
+            &mod_pow(randomness, &self.n, &self.n_squared),
+            &self.n_squared,
+        )
+    }
+
+    /// Computes a ciphertext of the sum of two plaintexts.
+    pub fn add(&self, ciphertext1: &BigUint, ciphertext2: &BigUint) -> BigUint {
+        mod_mul(ciphertext1, ciphertext2, &self.n_squared)
+    }
+
+    /// Computes a ciphertext of the sum of its plaintext and a constant.
+    pub fn add_plaintext(
+        &self,
+        ciphertext: &BigUint,
+        value: &BigUint,
+    ) -> BigUint {
+        let g_m = BigUint::one() + mod_mul(value, &self.n, &self.n_squared);
+        mod_mul(ciphertext, &g_m, &self.n_squared)
+    }
+
+    /// Computes a ciphertext of the product of its plaintext and a constant.
+    pub fn mul_plaintext(
+        &self,
+        ciphertext: &BigUint,
+        value: &BigUint,
+    ) -> BigUint {
+        mod_pow(ciphertext, value, &self.n_squared)
+    }
+
+    /// Computes a ciphertext of the negation of its plaintext modulo n.
+    pub fn neg(&self, ciphertext: &BigUint) -> BigUint {
+        self.mul_plaintext(ciphertext, &(&self.n - BigUint::one()))
+    }
+
+    /// Computes a ciphertext of the difference of two plaintexts modulo n.
+    pub fn sub(&self, ciphertext1: &BigUint, ciphertext2: &BigUint) -> BigUint {
+        self.add(ciphertext1, &self.neg(ciphertext2))
+    }
+
+    /// Refreshes the randomness of a ciphertext without changing its
+    /// plaintext.
+    pub fn rerandomize(&self, ciphertext: &BigUint) -> BigUint {
+        let r_n = mod_pow(&random_unit(&self.n), &self.n, &self.n_squared);
+        mod_mul(ciphertext, &r_n, &self.n_squared)
+    }
+}
+
+impl PaillierPrivateKey {
+    /// Decrypts a ciphertext to a plaintext in [0, n).
+    pub fn decrypt(&self, ciphertext: &BigUint) -> Result<BigUint, WedprError> {
+        let n = &self.public_key.n;
+        if ciphertext >= &self.public_key.n_squared || ciphertext.is_zero() {
+            wedpr_println!("Paillier ciphertext is out of range");
+            return Err(WedprError::ArgumentError);
+        }
+        let u = mod_pow(ciphertext, &self.lambda, &self.public_key.n_squared);
+        Ok(mod_mul(&l_function(&u, n), &self.mu, n))
+    }
+}
+
+/// Implements Paillier as a HomomorphicEncryption instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WedprPaillier {
+    pub key_size_in_bits: usize,
+}
+
+impl Default for WedprPaillier {
+    fn default() -> Self {
+        WedprPaillier {
+            key_size_in_bits: DEFAULT_PAILLIER_KEY_SIZE_IN_BITS,
+        }
+    }
+}
+
+impl HomomorphicEncryption for WedprPaillier {
+    type Ciphertext = BigUint;
+    type Plaintext = BigUint;
+    type PrivateKey = PaillierPrivateKey;
+    type PublicKey = PaillierPublicKey;
+    type Scalar = BigUint;
+
+    fn generate_keypair(
+        &self,
+    ) -> Result<(Self::PublicKey, Self::PrivateKey), WedprError> {
+        generate_keypair(self.key_size_in_bits)
+    }
+
+    fn encrypt(
+        &self,
+        public_key: &Self::PublicKey,
+        message: &Self::Plaintext,
+    ) -> Result<Self::Ciphertext, WedprError> {
+        public_key.encrypt(message)
+    }
+
+    fn decrypt(
+        &self,
+        private_key: &Self::PrivateKey,
+        ciphertext: &Self::Ciphertext,
+    ) -> Result<Self::Plaintext, WedprError> {
+        private_key.decrypt(ciphertext)
+    }
+
+    fn add(
+        &self,
+        public_key: &Self::PublicKey,
+        ciphertext1: &Self::Ciphertext,
+        ciphertext2: &Self::Ciphertext,
+    ) -> Result<Self::Ciphertext, WedprError> {
+        Ok(public_key.add(ciphertext1, ciphertext2))
+    }
+
+    fn mul_scalar(
+        &self,
+        public_key: &Self::PublicKey,
+        ciphertext: &Self::Ciphertext,
+        scalar: &Self::Scalar,
+    ) -> Result<Self::Ciphertext, WedprError> {
+        Ok(public_key.mul_plaintext(ciphertext, scalar))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paillier() {
+        let (public_key, private_key) = generate_keypair(512).unwrap();
+        assert_eq!(public_key.n.bits(), 512);
+
+        let m1 = BigUint::from(123456u32);
+        let m2 = BigUint::from(654321u32);
+        let c1 = public_key.encrypt(&m1).unwrap();
+        let c2 = public_key.encrypt(&m2).unwrap();
+        assert_eq!(private_key.decrypt(&c1).unwrap(), m1);
+        assert_ne!(public_key.encrypt(&m1).unwrap(), c1);
+
+        assert_eq!(
+            private_key.decrypt(&public_key.add(&c1, &c2)).unwrap(),
+            &m1 + &m2
+        );
+        assert_eq!(
+            private_key.decrypt(&public_key.sub(&c2, &c1)).unwrap(),
+            &m2 - &m1
+        );
+        assert_eq!(
+            private_key
+                .decrypt(&public_key.mul_plaintext(&c1, &BigUint::from(3u32)))
+                .unwrap(),
+            &m1 * 3u32
+        );
+        assert_eq!(
+            private_key
+                .decrypt(&public_key.add_plaintext(&c1, &m2))
+                .unwrap(),
+            &m1 + &m2
+        );
+        assert_eq!(
+            private_key.decrypt(&public_key.rerandomize(&c1)).unwrap(),
+            m1
+        );
+        assert!(public_key.encrypt(&public_key.n).is_err());
+        assert!(generate_keypair(256).is_err());
+        assert!(is_probable_prime(&BigUint::from(65537u32)));
+        assert!(!is_probable_prime(&BigUint::from(65537u32 * 3)));
+
+        // Montgomery arithmetic matches num-bigint, which still handles even
+        // moduli.
+        let base = random_bits(1100);
+        let exponent = random_bits(1000);
+        for modulus in &[
+            public_key.n_squared.clone(),
+            &public_key.n_squared - BigUint::one(),
+            BigUint::from(1u32),
+        ] {
+            assert_eq!(
+                mod_pow(&base, &exponent, modulus),
+                base.modpow(&exponent, modulus)
+            );
+            assert_eq!(
+                mod_add(&base, &exponent, modulus),
+                (&base + &exponent) % modulus
+            );
+            assert_eq!(
+                mod_mul(&base, &exponent, modulus),
+                (&base * &exponent) % modulus
+            );
+        }
+        let n = &public_key.n;
+        let inverse = mod_inverse(&private_key.lambda, n).unwrap();
+        assert_eq!(inverse, private_key.mu);
+        assert!(mod_mul(&inverse, &private_key.lambda, n).is_one());
+        assert_eq!(mod_inverse(&private_key.p, n), None);
+        assert_eq!(mod_inverse(&BigUint::zero(), n), None);
+        assert_eq!(
+            mod_inverse(&BigUint::from(3u32), &BigUint::from(8u32)),
+            Some(BigUint::from(3u32))
+        );
+    }
+
+    /// Sums plaintexts with any HomomorphicEncryption instance.
+    fn homomorphic_sum<E: HomomorphicEncryption>(
+        scheme: &E,
+        public_key: &E::PublicKey,
+        messages: &[E::Plaintext],
+    ) -> E::Ciphertext {
+        let mut sum = scheme.encrypt(public_key, &messages[0]).unwrap();
+        for message in &messages[1..] {
+            let ciphertext = scheme.encrypt(public_key, message).unwrap();
+            sum = scheme.add(public_key, &sum, &ciphertext).unwrap();
+        }
+        sum
+    }
+
+    #[test]
+    fn test_homomorphic_encryption() {
+        let paillier = WedprPaillier {
+            key_size_in_bits: 512,
+        };
+        let (public_key, private_key) = paillier.generate_keypair().unwrap();
+        let messages: Vec<BigUint> = (1..=4u32).map(BigUint::from).collect();
+        let sum = homomorphic_sum(&paillier, &public_key, &messages);
+        let product = paillier
+            .mul_scalar(&public_key, &sum, &BigUint::from(3u32))
+            .unwrap();
+        assert_eq!(
+            paillier.decrypt(&private_key, &product).unwrap(),
+            BigUint::from(30u32)
+        );
+    }
+}
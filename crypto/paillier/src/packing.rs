@@ -0,0 +1,247 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Packed plaintext encoding for Paillier, which places multiple small
+//! integers into fixed-size slots of one plaintext.
+//!
+//! Each slot holds a value of value_size_in_bits bits, plus headroom bits
+//! absorbing carries of homomorphic additions and scalar multiplications.
+//! Every packed ciphertext tracks an upper bound of its slot values, and
+//! operations fail instead of letting a slot overflow into its neighbor.
+//! E.g. 2048-bit keys hold 31 slots of 64 bits, where 32-bit values allow
+//! 2^32 - 1 additions, which cuts the ciphertext bandwidth by about 31 times
+//! compared to encrypting each value separately.
+
+use crate::{PaillierPrivateKey, PaillierPublicKey};
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use wedpr_l_utils::error::WedprError;
+
+/// Packed ciphertext with the upper bound of its slot values.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PackedCiphertext {
+    pub ciphertext: BigUint,
+    /// Number of slots in use.
+    pub slot_count: usize,
+    /// Upper bound of every slot value, inclusive.
+    pub slot_bound: BigUint,
+}
+
+/// Scheme packing values into slots of Paillier plaintexts.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PackingScheme {
+    public_key: PaillierPublicKey,
+    value_size_in_bits: usize,
+    slot_size_in_bits: usize,
+    slots_per_ciphertext: usize,
+}
+
+impl PackingScheme {
+    /// Creates a packing scheme for values of at most value_size_in_bits
+    /// bits in slots of slot_size_in_bits bits.
+    pub fn new(
+        public_key: &PaillierPublicKey,
+        value_size_in_bits: usize,
+        slot_size_in_bits: usize,
+    ) -> Result<Self, WedprError> {
+        // The top bit of n is reserved so that a packed plaintext is below n.
+        let slots_per_ciphertext = match slot_size_in_bits {
+            0 => 0,
+            _ => (public_key.n.bits() as usize - 1) / slot_size_in_bits,
+        };
+        if value_size_in_bits == 0
+            || value_size_in_bits > 64
+            || value_size_in_bits > slot_size_in_bits
+            || slots_per_ciphertext == 0
+        {
+            wedpr_println!("Invalid Paillier packing parameters");
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(PackingScheme {
+            public_key: public_key.clone(),
+            value_size_in_bits,
+            slot_size_in_bits,
+            slots_per_ciphertext,
+        })
+    }
+
+    /// Gets the maximum number of values packed in one ciphertext.
+    pub fn slots_per_ciphertext(&self) -> usize {
+        self.slots_per_ciphertext
+    }
+
+    /// Gets the largest value a slot can hold.
+    fn slot_limit(&self) -> BigUint {
+        (BigUint::one() << self.slot_size_in_bits) - BigUint::one()
+    }
+
+    /// Checks the slot bound of a result, and fails on a possible overflow.
+    fn checked_result(
+        &self,
+        ciphertext: BigUint,
+        slot_count: usize,
+        slot_bound: BigUint,
+    ) -> Result<PackedCiphertext, WedprError> {
+        if slot_bound > self.slot_limit() {
+            wedpr_println!("Packed slot value may overflow");
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(PackedCiphertext {
+            ciphertext,
+            slot_count,
+            slot_bound,
+        })
+    }
+
+    /// Packs values into one plaintext, where the first value takes the
+    /// lowest slot.
+    pub fn pack(&self, values: &[u64]) -> Result<BigUint, WedprError> {
+        if values.is_empty() || values.len() > self.slots_per_ciphertext {
+            wedpr_println!("Invalid number of values to pack");
+            return Err(WedprError::ArgumentError);
+        }
+        let mut plaintext = BigUint::zero();
+        for value in values.iter().rev() {
+            if self.value_size_in_bits < 64
+                && *value >> self.value_size_in_bits != 0
+            {
+                wedpr_println!("Value is too large to pack");
+                return Err(WedprError::ArgumentError);
+            }
+            plaintext = (plaintext << self.slot_size_in_bits) + *value;
+        }
+        Ok(plaintext)
+    }
+
+    /// Unpacks slot_count slot values from a plaintext.
+    pub fn unpack(
+        &self,
+        plaintext: &BigUint,
+        slot_count: usize,
+    ) -> Vec<BigUint> {
+        let limit = self.slot_limit();
+        (0..slot_count)
+            .map(|i| (plaintext >> (i * self.slot_size_in_bits)) & &limit)
+            .collect()
+    }
+
+    /// Packs and encrypts values.
+    pub fn encrypt(
+        &self,
+        values: &[u64],
+    ) -> Result<PackedCiphertext, WedprError> {
+        let plaintext = self.pack(values)?;
+        Ok(PackedCiphertext {
+            ciphertext: self.public_key.encrypt(&plaintext)?,
+            slot_count: values.len(),
+            slot_bound: (BigUint::one() << self.value_size_in_bits)
+                - BigUint::one(),
+        })
+    }
+
+    /// Decrypts and unpacks the slot values of a packed ciphertext.
+    pub fn decrypt(
+        &self,
+        private_key: &PaillierPrivateKey,
+        packed: &PackedCiphertext,
+    ) -> Result<Vec<BigUint>, WedprError> {
+        if private_key.public_key != self.public_key {
+            return Err(WedprError::ArgumentError);
+        }
+        let plaintext = private_key.decrypt(&packed.ciphertext)?;
+        Ok(self.unpack(&plaintext, packed.slot_count))
+    }
+
+    /// Adds two packed ciphertexts slot by slot.
+    pub fn add(
+        &self,
+        packed1: &PackedCiphertext,
+        packed2: &PackedCiphertext,
+    ) -> Result<PackedCiphertext, WedprError> {
+        self.checked_result(
+            self.public_key
+                .add(&packed1.ciphertext, &packed2.ciphertext),
+            packed1.slot_count.max(packed2.slot_count),
+            &packed1.slot_bound + &packed2.slot_bound,
+        )
+    }
+
+    /// Multiplies every slot of a packed ciphertext by a scalar.
+    pub fn mul_scalar(
+        &self,
+        packed: &PackedCiphertext,
+        scalar: u64,
+    ) -> Result<PackedCiphertext, WedprError> {
+        let scalar = BigUint::from(scalar);
+        self.checked_result(
+            self.public_key.mul_plaintext(&packed.ciphertext, &scalar),
+            packed.slot_count,
+            &packed.slot_bound * &scalar,
+        )
+    }
+
+    /// Sums packed ciphertexts slot by slot, e.g. aggregating the vectors of
+    /// all participants.
+    pub fn sum(
+        &self,
+        packed_list: &[PackedCiphertext],
+    ) -> Result<PackedCiphertext, WedprError> {
+        let (first, rest) = match packed_list.split_first() {
+            Some(v) => v,
+            None => return Err(WedprError::ArgumentError),
+        };
+        let mut result = first.clone();
+        for packed in rest {
+            result = self.add(&result, packed)?;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_keypair;
+
+    #[test]
+    fn test_packing() {
+        let (public_key, private_key) = generate_keypair(512).unwrap();
+        let scheme = PackingScheme::new(&public_key, 16, 24).unwrap();
+        assert_eq!(scheme.slots_per_ciphertext(), 21);
+
+        let values1 = [1u64, 65535, 0, 42];
+        let values2 = [2u64, 65535, 7];
+        let packed1 = scheme.encrypt(&values1).unwrap();
+        let packed2 = scheme.encrypt(&values2).unwrap();
+        assert_eq!(
+            scheme.decrypt(&private_key, &packed1).unwrap(),
+            values1
+                .iter()
+                .map(|v| BigUint::from(*v))
+                .collect::<Vec<_>>()
+        );
+
+        let sum = scheme.add(&packed1, &packed2).unwrap();
+        let expected: Vec<BigUint> = [3u64, 131070, 7, 42]
+            .iter()
+            .map(|v| BigUint::from(*v))
+            .collect();
+        assert_eq!(scheme.decrypt(&private_key, &sum).unwrap(), expected);
+        let product = scheme.mul_scalar(&sum, 3).unwrap();
+        assert_eq!(
+            scheme.decrypt(&private_key, &product).unwrap(),
+            expected.iter().map(|v| v * 3u32).collect::<Vec<_>>()
+        );
+
+        // 8 bits of headroom allow 256 values, but not more.
+        let packed_list = vec![packed1.clone(); 256];
+        assert!(scheme.sum(&packed_list).is_ok());
+        let packed_list = vec![packed1; 257];
+        assert!(scheme.sum(&packed_list).is_err());
+        assert!(scheme.mul_scalar(&packed2, 1 << 9).is_err());
+
+        assert!(scheme.encrypt(&[1 << 16]).is_err());
+        assert!(scheme.encrypt(&[1; 22]).is_err());
+        assert!(PackingScheme::new(&public_key, 16, 8).is_err());
+        assert!(PackingScheme::new(&public_key, 16, 512).is_err());
+    }
+}
@@ -0,0 +1,438 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Threshold Paillier decryption functions, following the Damgard-Jurik
+//! scheme with a trusted dealer.
+//!
+//! The modulus n = p * q is generated from safe primes p = 2p' + 1 and
+//! q = 2q' + 1. The decryption exponent d, where d = 0 mod p'q' and
+//! d = 1 mod n, is shared by a random polynomial of degree t - 1 among l
+//! parties, so that any t of them can decrypt, while fewer learn nothing.
+//! Every partial decryption carries a proof of its correctness against the
+//! verification key of its share.
+
+use crate::{
+    is_probable_prime, mod_add, mod_inverse, mod_mul, mod_pow, random_below,
+    random_bits, random_unit, PaillierPublicKey, MIN_PAILLIER_KEY_SIZE_IN_BITS,
+};
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::{One, Zero};
+use std::collections::BTreeSet;
+use wedpr_l_utils::{error::WedprError, traits::Hash};
+
+/// Statistical security parameter of the proof responses in bits.
+const SHARE_PROOF_STATISTICAL_SECURITY_IN_BITS: usize = 128;
+
+/// Public parameters of threshold Paillier decryption.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ThresholdPublicKey {
+    pub public_key: PaillierPublicKey,
+    pub threshold: usize,
+    pub share_count: usize,
+    /// Generator v of the squares modulo n^2.
+    pub verification_base: BigUint,
+    /// v^(delta * s_i) mod n^2 for the i-th share s_i.
+    pub verification_keys: Vec<BigUint>,
+}
+
+/// Private key share of a party, whose index starts from 1.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ThresholdKeyShare {
+    pub index: usize,
+    pub share: BigUint,
+}
+
+/// Proof of log_{c^4}(c_i^2) = log_v(v_i).
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ShareProof {
+    pub ciphertext_commitment: BigUint,
+    pub verification_commitment: BigUint,
+    pub response: BigUint,
+}
+
+/// Partial decryption c_i = c^(2 * delta * s_i) mod n^2 of a party.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PartialDecryption {
+    pub index: usize,
+    pub value: BigUint,
+    pub proof: ShareProof,
+}
+
+/// Computes delta = l!.
+fn factorial(value: usize) -> BigUint {
+    (1..=value).fold(BigUint::one(), |product, i| product * i)
+}
+
+/// Generates a safe prime p = 2p' + 1 of the given number of bits.
+fn generate_safe_prime(bits: usize) -> BigUint {
+    let high_bits =
+        (BigUint::one() << (bits - 2)) | (BigUint::one() << (bits - 3));
+    loop {
+        let sophie_germain =
+            random_bits(bits - 1) | &high_bits | BigUint::one();
+        let candidate = (&sophie_germain << 1) + BigUint::one();
+        if is_probable_prime(&sophie_germain) && is_probable_prime(&candidate) {
+            return candidate;
+        }
+    }
+}
+
+/// Generates threshold Paillier keys whose modulus n has the given size,
+/// where any threshold of share_count parties can decrypt.
+pub fn generate_threshold_keys(
+    key_size_in_bits: usize,
+    threshold: usize,
+    share_count: usize,
+) -> Result<(ThresholdPublicKey, Vec<ThresholdKeyShare>), WedprError> {
+    if key_size_in_bits < MIN_PAILLIER_KEY_SIZE_IN_BITS
+        || key_size_in_bits % 2 != 0
+    {
+        wedpr_println!("Invalid Paillier key size");
+        return Err(WedprError::ArgumentError);
+    }
+    let p = generate_safe_prime(key_size_in_bits / 2);
+    let q = loop {
+        let candidate = generate_safe_prime(key_size_in_bits / 2);
+        if candidate != p {
+            break candidate;
+        }
+    };
+    threshold_keys_from_safe_primes(&p, &q, threshold, share_count)
+}
+
+/// Derives threshold Paillier keys from two distinct safe primes of the same
+/// size.
+pub fn threshold_keys_from_safe_primes(
+    p: &BigUint,
+    q: &BigUint,
+    threshold: usize,
+    share_count: usize,
+) -> Result<(ThresholdPublicKey, Vec<ThresholdKeyShare>), WedprError> {
+    let n = p * q;
+    if threshold == 0
+        || threshold > share_count
+        || p == q
+        || (n.bits() as usize) < MIN_PAILLIER_KEY_SIZE_IN_BITS
+    {
+        wedpr_println!("Invalid threshold Paillier parameters");
+        return Err(WedprError::ArgumentError);
+    }
+    let n_squared = &n * &n;
+    let m = (p >> 1) * (q >> 1);
+    let n_m = &n * &m;
+    // d = 0 mod m and d = 1 mod n.
+    let d = match mod_inverse(&m, &n) {
+        Some(v) => mod_mul(&m, &v, &n_m),
+        None => {
+            wedpr_println!("Invalid safe primes");
+            return Err(WedprError::ArgumentError);
+        },
+    };
+    let coefficients: Vec<BigUint> =
+        (1..threshold).map(|_| random_below(&n_m)).collect();
+    let delta = factorial(share_count);
+    let verification_base =
+        mod_pow(&random_unit(&n_squared), &BigUint::from(2u32), &n_squared);
+
+    let mut shares = Vec::with_capacity(share_count);
+    let mut verification_keys = Vec::with_capacity(share_count);
+    for index in 1..=share_count {
+        // f(index) by Horner's method, whose constant coefficient is d.
+        let x = BigUint::from(index);
+        let share = coefficients.iter().rev().chain(std::iter::once(&d)).fold(
+            BigUint::zero(),
+            |value, coefficient| {
+                mod_add(&mod_mul(&value, &x, &n_m), coefficient, &n_m)
+            },
+        );
+        verification_keys.push(mod_pow(
+            &verification_base,
+            &(&delta * &share),
+            &n_squared,
+        ));
+        shares.push(ThresholdKeyShare { index, share });
+    }
+    Ok((
+        ThresholdPublicKey {
+            public_key: PaillierPublicKey { n, n_squared },
+            threshold,
+            share_count,
+            verification_base,
+            verification_keys,
+        },
+        shares,
+    ))
+}
+
+/// Computes the Fiat-Shamir challenge of a share proof.
+fn share_proof_challenge<H: Hash>(
+    hash: &H,
+    public_key: &ThresholdPublicKey,
+    index: usize,
+    ciphertext: &BigUint,
+    value: &BigUint,
+    proof: &ShareProof,
+) -> BigUint {
+    let mut hash_vec = Vec::new();
+    for item in &[
+        &public_key.public_key.n,
+        ciphertext,
+        value,
+        &public_key.verification_base,
+        &public_key.verification_keys[index - 1],
+        &proof.ciphertext_commitment,
+        &proof.verification_commitment,
+    ] {
+        let bytes = item.to_bytes_be();
+        hash_vec.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        hash_vec.extend_from_slice(&bytes);
+    }
+    BigUint::from_bytes_be(&hash.hash(&hash_vec))
+}
+
+fn check_element(value: &BigUint, public_key: &PaillierPublicKey) -> bool {
+    !value.is_zero() && value < &public_key.n_squared
+}
+
+/// Computes the partial decryption of a ciphertext with a key share, along
+/// with the proof of its correctness.
+pub fn partial_decrypt<H: Hash>(
+    hash: &H,
+    public_key: &ThresholdPublicKey,
+    key_share: &ThresholdKeyShare,
+    ciphertext: &BigUint,
+) -> Result<PartialDecryption, WedprError> {
+    let n_squared = &public_key.public_key.n_squared;
+    if key_share.index == 0
+        || key_share.index > public_key.share_count
+        || public_key.verification_keys.len() != public_key.share_count
+        || !check_element(ciphertext, &public_key.public_key)
+    {
+        wedpr_println!("Invalid partial decryption input");
+        return Err(WedprError::ArgumentError);
+    }
+    let delta = factorial(public_key.share_count);
+    let exponent = &delta * &key_share.share;
+    let value = mod_pow(ciphertext, &(&exponent << 1), n_squared);
+
+    // The proof shows that c_i^2 = (c^4)^x and v_i = v^x for x = delta * s_i.
+    let nonce = random_bits(
+        n_squared.bits() as usize
+            + exponent.bits() as usize
+            + SHARE_PROOF_STATISTICAL_SECURITY_IN_BITS,
+    );
+    let ciphertext_4 = ciphertext.modpow(&BigUint::from(4u32), n_squared);
+    let mut proof = ShareProof {
+        ciphertext_commitment: mod_pow(&ciphertext_4, &nonce, n_squared),
+        verification_commitment: mod_pow(
+            &public_key.verification_base,
+            &nonce,
+            n_squared,
+        ),
+        response: BigUint::zero(),
+    };
+    let challenge = share_proof_challenge(
+        hash,
+        public_key,
+        key_share.index,
+        ciphertext,
+        &value,
+        &proof,
+    );
+    proof.response = nonce + challenge * exponent;
+    Ok(PartialDecryption {
+        index: key_share.index,
+        value,
+        proof,
+    })
+}
+
+/// Verifies the proof of a partial decryption of a ciphertext.
+pub fn verify_partial_decryption<H: Hash>(
+    hash: &H,
+    public_key: &ThresholdPublicKey,
+    ciphertext: &BigUint,
+    partial: &PartialDecryption,
+) -> bool {
+    let paillier_public_key = &public_key.public_key;
+    let n_squared = &paillier_public_key.n_squared;
+    let proof = &partial.proof;
+    if partial.index == 0
+        || partial.index > public_key.share_count
+        || public_key.verification_keys.len() != public_key.share_count
+        || !check_element(ciphertext, paillier_public_key)
+        || !check_element(&partial.value, paillier_public_key)
+        || !check_element(&proof.ciphertext_commitment, paillier_public_key)
+        || !check_element(&proof.verification_commitment, paillier_public_key)
+    {
+        return false;
+    }
+    let challenge = share_proof_challenge(
+        hash,
+        public_key,
+        partial.index,
+        ciphertext,
+        &partial.value,
+        proof,
+    );
+    // (c^4)^z = a * (c_i^2)^e and v^z = b * v_i^e.
+    let ciphertext_4 = ciphertext.modpow(&BigUint::from(4u32), n_squared);
+    let expected_ciphertext = (&proof.ciphertext_commitment
+        * partial.value.modpow(&(&challenge << 1), n_squared))
+        % n_squared;
+    let expected_verification = (&proof.verification_commitment
+        * public_key.verification_keys[partial.index - 1]
+            .modpow(&challenge, n_squared))
+        % n_squared;
+    ciphertext_4.modpow(&proof.response, n_squared) == expected_ciphertext
+        && public_key
+            .verification_base
+            .modpow(&proof.response, n_squared)
+            == expected_verification
+}
+
+/// Combines verified partial decryptions of a ciphertext from at least
+/// threshold distinct parties to its plaintext.
+pub fn combine_partial_decryptions<H: Hash>(
+    hash: &H,
+    public_key: &ThresholdPublicKey,
+    ciphertext: &BigUint,
+    partials: &[PartialDecryption],
+) -> Result<BigUint, WedprError> {
+    let mut indices = BTreeSet::new();
+    for partial in partials {
+        if !verify_partial_decryption(hash, public_key, ciphertext, partial) {
+            wedpr_println!("Invalid partial decryption");
+            return Err(WedprError::VerificationError);
+        }
+        indices.insert(partial.index);
+    }
+    if indices.len() != partials.len() || partials.len() < public_key.threshold
+    {
+        wedpr_println!("Not enough distinct partial decryptions");
+        return Err(WedprError::ArgumentError);
+    }
+    let n = &public_key.public_key.n;
+    let n_squared = &public_key.public_key.n_squared;
+    let delta = factorial(public_key.share_count);
+    let selected = &partials[..public_key.threshold];
+
+    // c' = prod c_i^(2 * lambda_i), where
+    // lambda_i = delta * prod_{j != i} j / (j - i) is an integer.
+    let mut combined = BigUint::one();
+    for partial in selected {
+        let i = partial.index as i64;
+        let mut numerator = BigInt::from_biguint(Sign::Plus, delta.clone());
+        let mut denominator = BigInt::one();
+        for other in selected {
+            let j = other.index as i64;
+            if j != i {
+                numerator *= j;
+                denominator *= j - i;
+            }
+        }
+        let lambda = numerator / denominator;
+        let base = match lambda.sign() {
+            Sign::Minus => match mod_inverse(&partial.value, n_squared) {
+                Some(v) => v,
+                None => return Err(WedprError::ArgumentError),
+            },
+            _ => partial.value.clone(),
+        };
+        let exponent = lambda.magnitude() << 1;
+        combined = (combined * base.modpow(&exponent, n_squared)) % n_squared;
+    }
+    // c' = (1 + n)^(4 * delta^2 * m), so m = L(c') / (4 * delta^2) mod n.
+    let scale = (&delta * &delta * 4u32) % n;
+    let scale_inverse = match mod_inverse(&scale, n) {
+        Some(v) => v,
+        None => return Err(WedprError::ArgumentError),
+    };
+    let l_value = (combined - BigUint::one()) / n;
+    Ok((l_value * scale_inverse) % n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+
+    #[test]
+    fn test_threshold_paillier() {
+        let keccak256 = WedprKeccak256::default();
+        let p = BigUint::parse_bytes(
+            b"87627604596565782789416294156391008067360155677206985470208934164398536083499",
+            10,
+        )
+        .unwrap();
+        let q = BigUint::parse_bytes(
+            b"106126624712233989656790008497155024890647596251002268262881506875972493710359",
+            10,
+        )
+        .unwrap();
+        let (public_key, shares) =
+            threshold_keys_from_safe_primes(&p, &q, 2, 3).unwrap();
+        let paillier_public_key = &public_key.public_key;
+
+        let ciphertext = paillier_public_key.add(
+            &paillier_public_key.encrypt(&BigUint::from(40u32)).unwrap(),
+            &paillier_public_key.encrypt(&BigUint::from(2u32)).unwrap(),
+        );
+        let partials: Vec<PartialDecryption> = shares
+            .iter()
+            .map(|share| {
+                partial_decrypt(&keccak256, &public_key, share, &ciphertext)
+                    .unwrap()
+            })
+            .collect();
+        for partial in &partials {
+            assert!(verify_partial_decryption(
+                &keccak256,
+                &public_key,
+                &ciphertext,
+                partial
+            ));
+        }
+
+        // Any two parties can decrypt.
+        for selected in &[[0, 1], [1, 2], [2, 0]] {
+            let selected_partials: Vec<PartialDecryption> =
+                selected.iter().map(|i| partials[*i].clone()).collect();
+            assert_eq!(
+                combine_partial_decryptions(
+                    &keccak256,
+                    &public_key,
+                    &ciphertext,
+                    &selected_partials
+                )
+                .unwrap(),
+                BigUint::from(42u32)
+            );
+        }
+
+        // A single party cannot decrypt, and invalid shares are detected.
+        assert!(combine_partial_decryptions(
+            &keccak256,
+            &public_key,
+            &ciphertext,
+            &partials[..1]
+        )
+        .is_err());
+        let mut forged_partial = partials[0].clone();
+        forged_partial.value = paillier_public_key
+            .mul_plaintext(&forged_partial.value, &BigUint::from(2u32));
+        assert!(!verify_partial_decryption(
+            &keccak256,
+            &public_key,
+            &ciphertext,
+            &forged_partial
+        ));
+        assert!(combine_partial_decryptions(
+            &keccak256,
+            &public_key,
+            &ciphertext,
+            &[forged_partial, partials[1].clone()]
+        )
+        .is_err());
+        assert!(threshold_keys_from_safe_primes(&p, &q, 4, 3).is_err());
+    }
+}
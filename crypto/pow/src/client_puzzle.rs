@@ -0,0 +1,322 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Client-puzzle protocol rate-limiting requests to proving services.
+//!
+//! The server issues a signed PoW challenge bound to the public key of a
+//! client, the client solves it and signs the response, and the server redeems
+//! the response at most once before it expires. Each puzzle moves from issued
+//! to either redeemed or expired, and a client can only hold a limited number
+//! of outstanding puzzles.
+
+use crate::{generate_challenge, solve, verify, PowChallenge};
+use std::collections::HashMap;
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{Hash, Signature},
+};
+
+/// Domain separator of the signed content of a puzzle.
+const PUZZLE_DOMAIN: &[u8] = b"WeDPR_CLIENT_PUZZLE";
+/// Domain separator of the signed content of a puzzle response.
+const PUZZLE_RESPONSE_DOMAIN: &[u8] = b"WeDPR_CLIENT_PUZZLE_RESPONSE";
+
+/// Configuration of puzzle issuance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PuzzleConfig {
+    /// PoW difficulty in leading zero bits.
+    pub difficulty: u32,
+    /// Time to live of a puzzle in seconds.
+    pub ttl_seconds: u64,
+    /// Maximum number of unexpired puzzles issued to a client but not yet
+    /// redeemed.
+    pub max_outstanding_per_client: usize,
+}
+
+/// Puzzle issued by the server to a client.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct SignedPuzzle {
+    pub challenge: PowChallenge,
+    pub client_public_key: Vec<u8>,
+    /// Expiry time in Unix seconds.
+    pub expires_at: u64,
+    pub server_signature: Vec<u8>,
+}
+
+/// Solved puzzle signed by the client.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PuzzleResponse {
+    pub puzzle: SignedPuzzle,
+    pub nonce: u64,
+    pub client_signature: Vec<u8>,
+}
+
+/// Computes the hash signed by the server for a puzzle.
+fn puzzle_hash<H: Hash>(
+    hash: &H,
+    challenge: &PowChallenge,
+    client_public_key: &[u8],
+    expires_at: u64,
+) -> Vec<u8> {
+    let mut hash_vec = PUZZLE_DOMAIN.to_vec();
+    hash_vec.extend_from_slice(&challenge.encode());
+    hash_vec.extend_from_slice(&(client_public_key.len() as u32).to_be_bytes());
+    hash_vec.extend_from_slice(client_public_key);
+    hash_vec.extend_from_slice(&expires_at.to_be_bytes());
+    hash.hash(&hash_vec)
+}
+
+/// Computes the hash signed by the client for a puzzle response.
+fn response_hash<H: Hash>(
+    hash: &H,
+    puzzle: &SignedPuzzle,
+    nonce: u64,
+) -> Vec<u8> {
+    let mut hash_vec = PUZZLE_RESPONSE_DOMAIN.to_vec();
+    hash_vec.extend_from_slice(&puzzle_hash(
+        hash,
+        &puzzle.challenge,
+        &puzzle.client_public_key,
+        puzzle.expires_at,
+    ));
+    hash_vec.extend_from_slice(&nonce.to_be_bytes());
+    hash.hash(&hash_vec)
+}
+
+/// Verifies the server signature of a puzzle, which is used by clients before
+/// spending work on it.
+pub fn verify_puzzle<S: Signature, H: Hash>(
+    signature: &S,
+    hash: &H,
+    server_public_key: &[u8],
+    puzzle: &SignedPuzzle,
+) -> bool {
+    let puzzle_hash = puzzle_hash(
+        hash,
+        &puzzle.challenge,
+        &puzzle.client_public_key,
+        puzzle.expires_at,
+    );
+    signature.verify(
+        server_public_key,
+        &puzzle_hash[..],
+        &puzzle.server_signature[..],
+    )
+}
+
+/// Solves a puzzle and signs the response with the client private key.
+pub fn solve_puzzle<S: Signature, H: Hash>(
+    signature: &S,
+    hash: &H,
+    client_private_key: &[u8],
+    puzzle: &SignedPuzzle,
+    max_attempts: u64,
+) -> Result<PuzzleResponse, WedprError> {
+    let nonce = solve(hash, &puzzle.challenge, max_attempts)?;
+    let response_hash = response_hash(hash, puzzle, nonce);
+    Ok(PuzzleResponse {
+        puzzle: puzzle.clone(),
+        nonce,
+        client_signature: signature
+            .sign(client_private_key, &response_hash[..])?,
+    })
+}
+
+/// Server-side state machine issuing and redeeming puzzles.
+#[derive(Debug, Clone)]
+pub struct PuzzleIssuer<S: Signature, H: Hash> {
+    signature: S,
+    hash: H,
+    server_private_key: Vec<u8>,
+    server_public_key: Vec<u8>,
+    config: PuzzleConfig,
+    /// Seeds and expiry times of outstanding puzzles per client public key.
+    outstanding: HashMap<Vec<u8>, Vec<(Vec<u8>, u64)>>,
+}
+
+impl<S: Signature, H: Hash> PuzzleIssuer<S, H> {
+    /// Creates a puzzle issuer with the key pair of the server.
+    pub fn new(
+        signature: S,
+        hash: H,
+        server_private_key: &[u8],
+        server_public_key: &[u8],
+        config: PuzzleConfig,
+    ) -> Result<Self, WedprError> {
+        if config.max_outstanding_per_client == 0 {
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(PuzzleIssuer {
+            signature,
+            hash,
+            server_private_key: server_private_key.to_vec(),
+            server_public_key: server_public_key.to_vec(),
+            config,
+            outstanding: HashMap::new(),
+        })
+    }
+
+    /// Removes expired puzzles of a client, and returns the remaining ones.
+    fn prune(
+        &mut self,
+        client_public_key: &[u8],
+        now: u64,
+    ) -> &mut Vec<(Vec<u8>, u64)> {
+        let puzzles = self
+            .outstanding
+            .entry(client_public_key.to_vec())
+            .or_insert_with(Vec::new);
+        puzzles.retain(|(_, expires_at)| *expires_at >= now);
+        puzzles
+    }
+
+    /// Issues a puzzle to a client at the given time, which fails if the
+    /// client already holds the maximum number of outstanding puzzles.
+    pub fn issue(
+        &mut self,
+        client_public_key: &[u8],
+        now: u64,
+    ) -> Result<SignedPuzzle, WedprError> {
+        let max_outstanding = self.config.max_outstanding_per_client;
+        if self.prune(client_public_key, now).len() >= max_outstanding {
+            wedpr_println!("Too many outstanding puzzles for the client");
+            return Err(WedprError::VerificationError);
+        }
+        let challenge = generate_challenge(self.config.difficulty)?;
+        let expires_at = now.saturating_add(self.config.ttl_seconds);
+        let puzzle_hash =
+            puzzle_hash(&self.hash, &challenge, client_public_key, expires_at);
+        let server_signature = self
+            .signature
+            .sign(&self.server_private_key[..], &puzzle_hash[..])?;
+        self.prune(client_public_key, now)
+            .push((challenge.seed.clone(), expires_at));
+        Ok(SignedPuzzle {
+            challenge,
+            client_public_key: client_public_key.to_vec(),
+            expires_at,
+            server_signature,
+        })
+    }
+
+    /// Redeems a puzzle response at the given time, which succeeds at most
+    /// once for each issued puzzle.
+    pub fn redeem(
+        &mut self,
+        response: &PuzzleResponse,
+        now: u64,
+    ) -> Result<(), WedprError> {
+        let puzzle = &response.puzzle;
+        if !verify_puzzle(
+            &self.signature,
+            &self.hash,
+            &self.server_public_key,
+            puzzle,
+        ) {
+            wedpr_println!("Puzzle was not issued by this server");
+            return Err(WedprError::VerificationError);
+        }
+        let response_hash = response_hash(&self.hash, puzzle, response.nonce);
+        if !self.signature.verify(
+            &puzzle.client_public_key[..],
+            &response_hash[..],
+            &response.client_signature[..],
+        ) || !verify(&self.hash, &puzzle.challenge, response.nonce)
+        {
+            wedpr_println!("Puzzle response is invalid");
+            return Err(WedprError::VerificationError);
+        }
+        let puzzles = self.prune(&puzzle.client_public_key, now);
+        match puzzles
+            .iter()
+            .position(|(seed, _)| *seed == puzzle.challenge.seed)
+        {
+            Some(v) => {
+                puzzles.remove(v);
+                Ok(())
+            },
+            None => {
+                wedpr_println!("Puzzle has expired or been redeemed");
+                Err(WedprError::VerificationError)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+    use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+
+    #[test]
+    fn test_client_puzzle() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let keccak256 = WedprKeccak256::default();
+        let (server_public_key, server_private_key) =
+            secp256k1.generate_keypair();
+        let (client_public_key, client_private_key) =
+            secp256k1.generate_keypair();
+        let config = PuzzleConfig {
+            difficulty: 4,
+            ttl_seconds: 60,
+            max_outstanding_per_client: 2,
+        };
+        let mut issuer = PuzzleIssuer::new(
+            secp256k1,
+            keccak256.clone(),
+            &server_private_key,
+            &server_public_key,
+            config,
+        )
+        .unwrap();
+
+        let puzzle = issuer.issue(&client_public_key, 1000).unwrap();
+        assert!(verify_puzzle(
+            &secp256k1,
+            &keccak256,
+            &server_public_key,
+            &puzzle
+        ));
+        let _ = issuer.issue(&client_public_key, 1000).unwrap();
+        // Issuance is rate limited per client.
+        assert!(issuer.issue(&client_public_key, 1000).is_err());
+
+        let response = solve_puzzle(
+            &secp256k1,
+            &keccak256,
+            &client_private_key,
+            &puzzle,
+            1 << 16,
+        )
+        .unwrap();
+        assert!(issuer.redeem(&response, 1010).is_ok());
+        // Responses cannot be replayed.
+        assert!(issuer.redeem(&response, 1010).is_err());
+
+        // Responses signed by another client are rejected.
+        let (_, other_private_key) = secp256k1.generate_keypair();
+        let other_puzzle = issuer.issue(&client_public_key, 1020).unwrap();
+        let forged_response = solve_puzzle(
+            &secp256k1,
+            &keccak256,
+            &other_private_key,
+            &other_puzzle,
+            1 << 16,
+        )
+        .unwrap();
+        assert!(issuer.redeem(&forged_response, 1020).is_err());
+
+        // Expired puzzles cannot be redeemed, and no longer count towards the
+        // limit.
+        let expired_response = solve_puzzle(
+            &secp256k1,
+            &keccak256,
+            &client_private_key,
+            &other_puzzle,
+            1 << 16,
+        )
+        .unwrap();
+        assert!(issuer.redeem(&expired_response, 2000).is_err());
+        assert!(issuer.issue(&client_public_key, 2000).is_ok());
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Hashcash-style proof-of-work (PoW) puzzle functions, for anti-spam gating
+//! of requests to WeDPR services.
+//!
+//! A solution to a challenge is a nonce such that
+//! H(domain || seed || difficulty || nonce) has at least `difficulty` leading
+//! zero bits.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+use rand::RngCore;
+use wedpr_l_utils::{error::WedprError, traits::Hash};
+
+pub mod client_puzzle;
+
+/// Maximum difficulty in leading zero bits.
+pub const MAX_POW_DIFFICULTY: u32 = 64;
+/// Size of the random seed of a challenge.
+pub const POW_SEED_SIZE_IN_BYTES: usize = 32;
+
+/// Domain separator of the hashed content of a PoW puzzle.
+const POW_DOMAIN: &[u8] = b"WeDPR_POW";
+
+/// PoW challenge issued to a client.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PowChallenge {
+    pub seed: Vec<u8>,
+    pub difficulty: u32,
+}
+
+impl PowChallenge {
+    /// Encodes a challenge to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = self.difficulty.to_be_bytes().to_vec();
+        output.extend_from_slice(&self.seed);
+        output
+    }
+
+    /// Decodes a challenge from bytes.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        if input.len() != 4 + POW_SEED_SIZE_IN_BYTES {
+            return Err(WedprError::FormatError);
+        }
+        let mut difficulty_bytes = [0u8; 4];
+        difficulty_bytes.copy_from_slice(&input[..4]);
+        let difficulty = u32::from_be_bytes(difficulty_bytes);
+        if difficulty > MAX_POW_DIFFICULTY {
+            return Err(WedprError::FormatError);
+        }
+        Ok(PowChallenge {
+            seed: input[4..].to_vec(),
+            difficulty,
+        })
+    }
+}
+
+/// Generates a challenge with a random seed, where difficulty is the number of
+/// leading zero bits required, taking 2^difficulty hashes on average to solve.
+pub fn generate_challenge(difficulty: u32) -> Result<PowChallenge, WedprError> {
+    if difficulty > MAX_POW_DIFFICULTY {
+        wedpr_println!("PoW difficulty is too high");
+        return Err(WedprError::ArgumentError);
+    }
+    let mut seed = vec![0u8; POW_SEED_SIZE_IN_BYTES];
+    rand::thread_rng().fill_bytes(&mut seed);
+    Ok(PowChallenge { seed, difficulty })
+}
+
+/// Counts the leading zero bits of bytes.
+pub fn leading_zero_bits(input: &[u8]) -> u32 {
+    let mut count = 0;
+    for byte in input {
+        if *byte != 0 {
+            return count + byte.leading_zeros();
+        }
+        count += 8;
+    }
+    count
+}
+
+fn puzzle_hash<H: Hash>(
+    hash: &H,
+    challenge: &PowChallenge,
+    nonce: u64,
+) -> Vec<u8> {
+    let mut hash_vec = POW_DOMAIN.to_vec();
+    hash_vec.extend_from_slice(&challenge.encode());
+    hash_vec.extend_from_slice(&nonce.to_be_bytes());
+    hash.hash(&hash_vec)
+}
+
+/// Solves a challenge by searching nonces from 0, and fails if no solution is
+/// found within max_attempts hashes.
+pub fn solve<H: Hash>(
+    hash: &H,
+    challenge: &PowChallenge,
+    max_attempts: u64,
+) -> Result<u64, WedprError> {
+    if challenge.difficulty > MAX_POW_DIFFICULTY {
+        return Err(WedprError::ArgumentError);
+    }
+    for nonce in 0..max_attempts {
+        let hash_bytes = puzzle_hash(hash, challenge, nonce);
+        if leading_zero_bits(&hash_bytes) >= challenge.difficulty {
+            return Ok(nonce);
+        }
+    }
+    wedpr_println!("PoW solution not found within the attempt limit");
+    Err(WedprError::VerificationError)
+}
+
+/// Verifies a solution of a challenge with a single hash.
+pub fn verify<H: Hash>(hash: &H, challenge: &PowChallenge, nonce: u64) -> bool {
+    if challenge.difficulty > MAX_POW_DIFFICULTY {
+        return false;
+    }
+    leading_zero_bits(&puzzle_hash(hash, challenge, nonce))
+        >= challenge.difficulty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+
+    #[test]
+    fn test_pow() {
+        let keccak256 = WedprKeccak256::default();
+        let challenge = generate_challenge(8).unwrap();
+        let nonce = solve(&keccak256, &challenge, 1 << 20).unwrap();
+        assert!(verify(&keccak256, &challenge, nonce));
+        let decoded_challenge =
+            PowChallenge::decode(&challenge.encode()).unwrap();
+        assert_eq!(decoded_challenge, challenge);
+
+        // A solution is bound to its challenge.
+        let mut harder_challenge = challenge.clone();
+        harder_challenge.difficulty = 32;
+        assert!(!verify(&keccak256, &harder_challenge, nonce));
+        assert!(solve(&keccak256, &harder_challenge, 16).is_err());
+
+        assert_eq!(leading_zero_bits(&[0, 0x10, 0xff]), 11);
+        assert_eq!(leading_zero_bits(&[0, 0]), 16);
+        assert!(generate_challenge(MAX_POW_DIFFICULTY + 1).is_err());
+    }
+}
@@ -0,0 +1,719 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Runtime algorithm provider plugins over a C ABI, so that alternative
+//! implementations, e.g. hardware-accelerated SM4 or vendor HSM SM2, can be
+//! loaded into the provider registry and used through the Signature and
+//! BlockCipher traits without recompiling dependents. Providers are subject to
+//! the global security policy by their algorithm names, both when registered
+//! and when resolved, and loading a signed crypto profile by
+//! load_signed_profile unregisters the providers which its policy disallows.
+//!
+//! A provider library exports a WedprProviderEntry function named
+//! WEDPR_PROVIDER_ENTRY_SYMBOL, which returns a table of algorithm providers.
+//! Functions writing outputs return WEDPR_PROVIDER_BUFFER_TOO_SMALL with the
+//! required size in output_len if the output capacity is too small, and are
+//! called again with a large enough buffer.
+
+#[macro_use]
+extern crate lazy_static;
+#[macro_use]
+extern crate wedpr_l_macros;
+
+use std::{
+    collections::BTreeMap,
+    ffi::CStr,
+    os::raw::{c_char, c_int},
+    sync::{Arc, RwLock},
+};
+use wedpr_l_utils::{
+    digest::MessageDigest,
+    error::WedprError,
+    policy::global_policy,
+    profile::{self, CryptoProfile},
+    traits::{AlgorithmInfo, BlockCipher, Hash, Signature},
+};
+
+/// Version of the provider ABI, which providers should set in their tables.
+pub const WEDPR_PROVIDER_ABI_VERSION: u32 = 1;
+/// Name of the entry function exported by provider libraries.
+pub const WEDPR_PROVIDER_ENTRY_SYMBOL: &str = "wedpr_provider_entry";
+/// Status of success, or of a valid signature for verification.
+pub const WEDPR_PROVIDER_OK: c_int = 0;
+/// Status of an output buffer which is too small.
+pub const WEDPR_PROVIDER_BUFFER_TOO_SMALL: c_int = 1;
+/// Status of a failure, or of an invalid signature for verification.
+pub const WEDPR_PROVIDER_ERROR: c_int = 2;
+
+/// Output capacity of the first call of a provider function.
+const DEFAULT_OUTPUT_CAPACITY: usize = 1024;
+
+/// Hashes an input.
+pub type WedprProviderHashFn = unsafe extern "C" fn(
+    input: *const u8,
+    input_len: usize,
+    output: *mut u8,
+    output_capacity: usize,
+    output_len: *mut usize,
+) -> c_int;
+
+/// Signs a message hash with a private key, which can also be a key handle
+/// of an HSM.
+pub type WedprProviderSignFn = unsafe extern "C" fn(
+    private_key: *const u8,
+    private_key_len: usize,
+    msg_hash: *const u8,
+    msg_hash_len: usize,
+    output: *mut u8,
+    output_capacity: usize,
+    output_len: *mut usize,
+) -> c_int;
+
+/// Verifies a signature of a message hash with a public key.
+pub type WedprProviderVerifyFn = unsafe extern "C" fn(
+    public_key: *const u8,
+    public_key_len: usize,
+    msg_hash: *const u8,
+    msg_hash_len: usize,
+    signature: *const u8,
+    signature_len: usize,
+) -> c_int;
+
+/// Encrypts or decrypts an input with a symmetric key and an IV.
+pub type WedprProviderCipherFn = unsafe extern "C" fn(
+    input: *const u8,
+    input_len: usize,
+    key: *const u8,
+    key_len: usize,
+    iv: *const u8,
+    iv_len: usize,
+    output: *mut u8,
+    output_capacity: usize,
+    output_len: *mut usize,
+) -> c_int;
+
+/// Provider of an algorithm, where unsupported functions are null.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WedprAlgorithmProvider {
+    pub abi_version: u32,
+    /// NUL-terminated algorithm name as in AlgorithmMetadata.
+    pub name: *const c_char,
+    pub hash: Option<WedprProviderHashFn>,
+    pub sign: Option<WedprProviderSignFn>,
+    pub verify: Option<WedprProviderVerifyFn>,
+    pub encrypt: Option<WedprProviderCipherFn>,
+    pub decrypt: Option<WedprProviderCipherFn>,
+}
+
+// Provider tables are immutable function tables, which are shared by all
+// threads.
+unsafe impl Send for WedprAlgorithmProvider {}
+unsafe impl Sync for WedprAlgorithmProvider {}
+
+/// Entry function of a provider library, which returns its provider table
+/// and sets the number of providers in the table.
+pub type WedprProviderEntry =
+    unsafe extern "C" fn(
+        provider_count: *mut usize,
+    ) -> *const WedprAlgorithmProvider;
+
+/// Registered providers by algorithm names.
+type ProviderRegistry = BTreeMap<String, Arc<WedprAlgorithmProvider>>;
+
+lazy_static! {
+    static ref PROVIDER_REGISTRY: RwLock<ProviderRegistry> =
+        RwLock::new(BTreeMap::new());
+}
+
+/// Registers the providers of an entry function, which replace registered
+/// providers of the same algorithms, and returns the registered algorithm
+/// names. It fails without registering any provider if an algorithm is not
+/// allowed by the global security policy.
+///
+/// # Safety
+///
+/// The entry function and the provider functions should follow the provider
+/// ABI, and stay valid for the lifetime of the process.
+pub unsafe fn register_providers(
+    entry: WedprProviderEntry,
+) -> Result<Vec<String>, WedprError> {
+    let mut provider_count = 0usize;
+    let table = entry(&mut provider_count);
+    if table.is_null() || provider_count == 0 {
+        wedpr_println!("Provider entry returned no providers");
+        return Err(WedprError::ArgumentError);
+    }
+    let mut providers = Vec::with_capacity(provider_count);
+    for provider in std::slice::from_raw_parts(table, provider_count) {
+        if provider.abi_version != WEDPR_PROVIDER_ABI_VERSION
+            || provider.name.is_null()
+        {
+            wedpr_println!("Unsupported provider ABI {}", provider.abi_version);
+            return Err(WedprError::ArgumentError);
+        }
+        let name = match CStr::from_ptr(provider.name).to_str() {
+            Ok(v) if !v.is_empty() => v.to_string(),
+            _ => return Err(WedprError::ArgumentError),
+        };
+        global_policy().check_algorithm_name(&name, None)?;
+        providers.push((name, *provider));
+    }
+    let mut registry = match PROVIDER_REGISTRY.write() {
+        Ok(v) => v,
+        Err(e) => e.into_inner(),
+    };
+    let names = providers.iter().map(|(name, _)| name.clone()).collect();
+    for (name, provider) in providers {
+        wedpr_println!("Registered algorithm provider {}", name);
+        registry.insert(name, Arc::new(provider));
+    }
+    Ok(names)
+}
+
+/// Loads a provider library, and registers its providers as by
+/// register_providers. The library is never unloaded.
+///
+/// # Safety
+///
+/// Loading a library runs its initialization code, so the library should be
+/// trusted and follow the provider ABI.
+#[cfg(unix)]
+pub unsafe fn load_provider_library<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<Vec<String>, WedprError> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = match CString::new(path.as_ref().as_os_str().as_bytes()) {
+        Ok(v) => v,
+        Err(_) => return Err(WedprError::ArgumentError),
+    };
+    let handle = libc::dlopen(path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL);
+    if handle.is_null() {
+        wedpr_println!("Loading provider library failed");
+        return Err(WedprError::StorageError);
+    }
+    let symbol = CString::new(WEDPR_PROVIDER_ENTRY_SYMBOL)
+        .expect("WEDPR_PROVIDER_ENTRY_SYMBOL should not contain NUL");
+    let entry = libc::dlsym(handle, symbol.as_ptr());
+    if entry.is_null() {
+        wedpr_println!("Provider library has no entry function");
+        libc::dlclose(handle);
+        return Err(WedprError::ArgumentError);
+    }
+    register_providers(std::mem::transmute::<
+        *mut libc::c_void,
+        WedprProviderEntry,
+    >(entry))
+}
+
+/// Unregisters the provider of an algorithm, which returns false if it is
+/// not registered.
+pub fn unregister_provider(name: &str) -> bool {
+    match PROVIDER_REGISTRY.write() {
+        Ok(mut v) => v.remove(name).is_some(),
+        Err(e) => e.into_inner().remove(name).is_some(),
+    }
+}
+
+/// Gets the names of algorithms with registered providers.
+pub fn registered_providers() -> Vec<String> {
+    match PROVIDER_REGISTRY.read() {
+        Ok(v) => v.keys().cloned().collect(),
+        Err(e) => e.into_inner().keys().cloned().collect(),
+    }
+}
+
+/// Unregisters the providers of algorithms which are not allowed by the
+/// global security policy, and returns their names.
+pub fn unregister_disallowed_providers() -> Vec<String> {
+    let policy = global_policy();
+    let mut registry = match PROVIDER_REGISTRY.write() {
+        Ok(v) => v,
+        Err(e) => e.into_inner(),
+    };
+    let names: Vec<String> = registry
+        .keys()
+        .filter(|name| policy.check_algorithm_name(name, None).is_err())
+        .cloned()
+        .collect();
+    for name in &names {
+        wedpr_println!("Unregistered algorithm provider {}", name);
+        registry.remove(name);
+    }
+    names
+}
+
+/// Verifies signed crypto profile content and applies its policy as by
+/// wedpr_l_utils::profile::load_signed_profile, then unregisters the
+/// providers which the policy disallows.
+pub fn load_signed_profile<S: Signature, H: Hash + AlgorithmInfo>(
+    content: &str,
+    root_public_key: &[u8],
+    signature: &S,
+    digest: &MessageDigest<H>,
+) -> Result<CryptoProfile, WedprError> {
+    let profile = profile::load_signed_profile(
+        content,
+        root_public_key,
+        signature,
+        digest,
+    )?;
+    unregister_disallowed_providers();
+    Ok(profile)
+}
+
+/// Gets the registered provider of an algorithm, which fails if the
+/// algorithm is no longer allowed by the global security policy.
+fn find_provider(
+    name: &str,
+) -> Result<Arc<WedprAlgorithmProvider>, WedprError> {
+    global_policy().check_algorithm_name(name, None)?;
+    let provider = match PROVIDER_REGISTRY.read() {
+        Ok(v) => v.get(name).cloned(),
+        Err(e) => e.into_inner().get(name).cloned(),
+    };
+    match provider {
+        Some(v) => Ok(v),
+        None => {
+            wedpr_println!("No provider of {}", name);
+            Err(WedprError::ArgumentError)
+        },
+    }
+}
+
+/// Gets a function of the registered provider of an algorithm, which fails
+/// if the provider does not support it.
+fn find_provider_fn<F>(
+    name: &str,
+    select: fn(&WedprAlgorithmProvider) -> Option<F>,
+) -> Result<F, WedprError> {
+    match select(find_provider(name)?.as_ref()) {
+        Some(v) => Ok(v),
+        None => {
+            wedpr_println!("Provider {} does not support the function", name);
+            Err(WedprError::ArgumentError)
+        },
+    }
+}
+
+/// Calls a provider function writing an output, and retries once with the
+/// required output size.
+fn call_with_output<F: Fn(*mut u8, usize, *mut usize) -> c_int>(
+    call: F,
+) -> Result<Vec<u8>, WedprError> {
+    let mut output = vec![0u8; DEFAULT_OUTPUT_CAPACITY];
+    let mut output_len = 0usize;
+    let mut status = call(output.as_mut_ptr(), output.len(), &mut output_len);
+    if status == WEDPR_PROVIDER_BUFFER_TOO_SMALL {
+        output = vec![0u8; output_len];
+        status = call(output.as_mut_ptr(), output.len(), &mut output_len);
+    }
+    if status != WEDPR_PROVIDER_OK || output_len > output.len() {
+        return Err(WedprError::ArgumentError);
+    }
+    output.truncate(output_len);
+    Ok(output)
+}
+
+/// Hashes by a registered provider, which does not implement the Hash trait
+/// since provider calls may fail. The provider is resolved again on every
+/// call, so an instance stops working once its provider is unregistered or
+/// disallowed by the global security policy.
+#[derive(Debug, Clone)]
+pub struct ProviderHash {
+    name: String,
+}
+
+impl ProviderHash {
+    /// Creates an instance of the registered provider of an algorithm.
+    pub fn new(name: &str) -> Result<Self, WedprError> {
+        find_provider_fn(name, |provider| provider.hash)?;
+        Ok(ProviderHash {
+            name: name.to_string(),
+        })
+    }
+
+    /// Hashes an input, which fails if the provider fails.
+    pub fn hash<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        input: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        let hash = find_provider_fn(&self.name, |provider| provider.hash)?;
+        let input = input.as_ref();
+        let result = call_with_output(|output, capacity, output_len| unsafe {
+            hash(input.as_ptr(), input.len(), output, capacity, output_len)
+        });
+        if result.is_err() {
+            wedpr_println!("Provider {} failed to hash", self.name);
+        }
+        result
+    }
+}
+
+/// Implements a Signature instance by a registered provider, which is
+/// resolved again on every call as for ProviderHash.
+#[derive(Debug, Clone)]
+pub struct ProviderSignature {
+    name: String,
+}
+
+impl ProviderSignature {
+    /// Creates an instance of the registered provider of an algorithm.
+    pub fn new(name: &str) -> Result<Self, WedprError> {
+        find_provider_fn(name, |provider| provider.sign)?;
+        find_provider_fn(name, |provider| provider.verify)?;
+        Ok(ProviderSignature {
+            name: name.to_string(),
+        })
+    }
+}
+
+impl Signature for ProviderSignature {
+    fn sign<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        let sign = find_provider_fn(&self.name, |provider| provider.sign)?;
+        let (private_key, msg_hash) = (private_key.as_ref(), msg_hash.as_ref());
+        call_with_output(|output, capacity, output_len| unsafe {
+            sign(
+                private_key.as_ptr(),
+                private_key.len(),
+                msg_hash.as_ptr(),
+                msg_hash.len(),
+                output,
+                capacity,
+                output_len,
+            )
+        })
+    }
+
+    fn verify<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        msg_hash: &T,
+        signature: &T,
+    ) -> bool {
+        let verify =
+            match find_provider_fn(&self.name, |provider| provider.verify) {
+                Ok(v) => v,
+                Err(_) => return false,
+            };
+        let (public_key, msg_hash, signature) =
+            (public_key.as_ref(), msg_hash.as_ref(), signature.as_ref());
+        unsafe {
+            verify(
+                public_key.as_ptr(),
+                public_key.len(),
+                msg_hash.as_ptr(),
+                msg_hash.len(),
+                signature.as_ptr(),
+                signature.len(),
+            ) == WEDPR_PROVIDER_OK
+        }
+    }
+
+    /// Keys of providers are managed by the providers, e.g. generated inside
+    /// HSMs, so try_generate_keypair should be used instead.
+    ///
+    /// # Panics
+    ///
+    /// Always panics, since no key pair can be returned.
+    fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+        panic!(
+            "Provider {} does not generate keys, use try_generate_keypair",
+            self.name
+        )
+    }
+
+    /// Always fails, since keys of providers are managed by the providers.
+    fn try_generate_keypair(&self) -> Result<(Vec<u8>, Vec<u8>), WedprError> {
+        wedpr_println!("Provider {} does not generate keys", self.name);
+        Err(WedprError::ArgumentError)
+    }
+}
+
+/// Implements a BlockCipher instance by a registered provider, which is
+/// resolved again on every call as for ProviderHash.
+#[derive(Debug, Clone)]
+pub struct ProviderBlockCipher {
+    name: String,
+}
+
+impl ProviderBlockCipher {
+    /// Creates an instance of the registered provider of an algorithm.
+    pub fn new(name: &str) -> Result<Self, WedprError> {
+        find_provider_fn(name, |provider| provider.encrypt)?;
+        find_provider_fn(name, |provider| provider.decrypt)?;
+        Ok(ProviderBlockCipher {
+            name: name.to_string(),
+        })
+    }
+}
+
+/// Calls a cipher function of a provider.
+fn call_cipher(
+    cipher: WedprProviderCipherFn,
+    input: &[u8],
+    key: &[u8],
+    iv: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    call_with_output(|output, capacity, output_len| unsafe {
+        cipher(
+            input.as_ptr(),
+            input.len(),
+            key.as_ptr(),
+            key.len(),
+            iv.as_ptr(),
+            iv.len(),
+            output,
+            capacity,
+            output_len,
+        )
+    })
+}
+
+impl BlockCipher for ProviderBlockCipher {
+    fn encrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        message: &T,
+        key: &T,
+        iv: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        let encrypt =
+            find_provider_fn(&self.name, |provider| provider.encrypt)?;
+        call_cipher(encrypt, message.as_ref(), key.as_ref(), iv.as_ref())
+    }
+
+    fn decrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        ciphertext: &T,
+        key: &T,
+        iv: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        let decrypt =
+            find_provider_fn(&self.name, |provider| provider.decrypt)?;
+        call_cipher(decrypt, ciphertext.as_ref(), key.as_ref(), iv.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_sha256::WedprSha256;
+    use wedpr_l_utils::policy::{
+        set_global_policy, with_scoped_policy, SecurityPolicy,
+    };
+
+    /// Writes an output or requests a larger buffer.
+    unsafe fn write_output(
+        data: &[u8],
+        output: *mut u8,
+        output_capacity: usize,
+        output_len: *mut usize,
+    ) -> c_int {
+        *output_len = data.len();
+        if output_capacity < data.len() {
+            return WEDPR_PROVIDER_BUFFER_TOO_SMALL;
+        }
+        std::ptr::copy_nonoverlapping(data.as_ptr(), output, data.len());
+        WEDPR_PROVIDER_OK
+    }
+
+    /// Hashes an input to its reverse.
+    unsafe extern "C" fn reverse_hash(
+        input: *const u8,
+        input_len: usize,
+        output: *mut u8,
+        output_capacity: usize,
+        output_len: *mut usize,
+    ) -> c_int {
+        let mut data = std::slice::from_raw_parts(input, input_len).to_vec();
+        data.reverse();
+        write_output(&data, output, output_capacity, output_len)
+    }
+
+    /// Signs with the key followed by the message hash.
+    unsafe extern "C" fn echo_sign(
+        private_key: *const u8,
+        private_key_len: usize,
+        msg_hash: *const u8,
+        msg_hash_len: usize,
+        output: *mut u8,
+        output_capacity: usize,
+        output_len: *mut usize,
+    ) -> c_int {
+        let data = [
+            std::slice::from_raw_parts(private_key, private_key_len),
+            std::slice::from_raw_parts(msg_hash, msg_hash_len),
+        ]
+        .concat();
+        write_output(&data, output, output_capacity, output_len)
+    }
+
+    unsafe extern "C" fn echo_verify(
+        public_key: *const u8,
+        public_key_len: usize,
+        msg_hash: *const u8,
+        msg_hash_len: usize,
+        signature: *const u8,
+        signature_len: usize,
+    ) -> c_int {
+        let expected = [
+            std::slice::from_raw_parts(public_key, public_key_len),
+            std::slice::from_raw_parts(msg_hash, msg_hash_len),
+        ]
+        .concat();
+        if std::slice::from_raw_parts(signature, signature_len)
+            == expected.as_slice()
+        {
+            WEDPR_PROVIDER_OK
+        } else {
+            WEDPR_PROVIDER_ERROR
+        }
+    }
+
+    /// Encrypts and decrypts by XOR with the first key byte.
+    unsafe extern "C" fn xor_cipher(
+        input: *const u8,
+        input_len: usize,
+        key: *const u8,
+        key_len: usize,
+        _iv: *const u8,
+        _iv_len: usize,
+        output: *mut u8,
+        output_capacity: usize,
+        output_len: *mut usize,
+    ) -> c_int {
+        if key_len == 0 {
+            return WEDPR_PROVIDER_ERROR;
+        }
+        let data: Vec<u8> = std::slice::from_raw_parts(input, input_len)
+            .iter()
+            .map(|byte| byte ^ *key)
+            .collect();
+        write_output(&data, output, output_capacity, output_len)
+    }
+
+    static TEST_PROVIDERS: [WedprAlgorithmProvider; 2] = [
+        WedprAlgorithmProvider {
+            abi_version: WEDPR_PROVIDER_ABI_VERSION,
+            name: b"test-reverse-hash\0" as *const u8 as *const c_char,
+            hash: Some(reverse_hash),
+            sign: None,
+            verify: None,
+            encrypt: None,
+            decrypt: None,
+        },
+        WedprAlgorithmProvider {
+            abi_version: WEDPR_PROVIDER_ABI_VERSION,
+            name: b"test-echo-xor\0" as *const u8 as *const c_char,
+            hash: None,
+            sign: Some(echo_sign),
+            verify: Some(echo_verify),
+            encrypt: Some(xor_cipher),
+            decrypt: Some(xor_cipher),
+        },
+    ];
+
+    unsafe extern "C" fn test_entry(
+        provider_count: *mut usize,
+    ) -> *const WedprAlgorithmProvider {
+        *provider_count = TEST_PROVIDERS.len();
+        TEST_PROVIDERS.as_ptr()
+    }
+
+    #[test]
+    fn test_provider_registry() {
+        assert!(ProviderHash::new("test-reverse-hash").is_err());
+        let names = unsafe { register_providers(test_entry) }.unwrap();
+        assert_eq!(names, vec!["test-reverse-hash", "test-echo-xor"]);
+        assert!(registered_providers().contains(&names[0]));
+
+        let hash = ProviderHash::new("test-reverse-hash").unwrap();
+        assert_eq!(hash.hash(b"abc").unwrap(), b"cba".to_vec());
+        // Outputs larger than the default capacity are retried.
+        let large_input = vec![7u8; DEFAULT_OUTPUT_CAPACITY + 1];
+        assert_eq!(hash.hash(&large_input).unwrap(), large_input);
+        assert!(ProviderSignature::new("test-reverse-hash").is_err());
+
+        let signature = ProviderSignature::new("test-echo-xor").unwrap();
+        let key = b"hsm key handle".to_vec();
+        let msg_hash = b"message hash".to_vec();
+        let signed = signature.sign(&key[..], &msg_hash[..]).unwrap();
+        assert!(signature.verify(&key[..], &msg_hash[..], &signed[..]));
+        assert!(!signature.verify(&key[..], &msg_hash[1..], &signed[..]));
+        assert!(signature.try_generate_keypair().is_err());
+
+        let cipher = ProviderBlockCipher::new("test-echo-xor").unwrap();
+        let ciphertext =
+            cipher.encrypt(&msg_hash[..], &key[..], &[][..]).unwrap();
+        assert_ne!(ciphertext, msg_hash);
+        assert_eq!(
+            cipher.decrypt(&ciphertext[..], &key[..], &[][..]).unwrap(),
+            msg_hash
+        );
+        assert!(cipher.encrypt(&msg_hash[..], &[][..], &[][..]).is_err());
+
+        // Providers of algorithms out of the policy are neither registered
+        // nor resolved.
+        let policy = SecurityPolicy {
+            banned_algorithms: vec!["test-echo-xor".to_string()],
+            ..SecurityPolicy::default()
+        };
+        with_scoped_policy(policy, || {
+            assert!(ProviderBlockCipher::new("test-echo-xor").is_err());
+            // Existing instances are resolved again on every call.
+            assert!(cipher.encrypt(&msg_hash[..], &key[..], &[][..]).is_err());
+            assert!(!signature.verify(&key[..], &msg_hash[..], &signed[..]));
+            assert!(unsafe { register_providers(test_entry) }.is_err());
+        });
+        assert!(ProviderBlockCipher::new("test-echo-xor").is_ok());
+        assert!(cipher.encrypt(&msg_hash[..], &key[..], &[][..]).is_ok());
+
+        // Loading a signed profile unregisters the disallowed providers. It
+        // changes the global policy, which no other test of this crate uses.
+        let profile = CryptoProfile {
+            serial: 1,
+            policy: SecurityPolicy {
+                banned_algorithms: vec!["test-reverse-hash".to_string()],
+                ..SecurityPolicy::default()
+            },
+        };
+        let digest = MessageDigest::new(WedprSha256::default());
+        let content = profile.sign(&key, &signature, &digest).unwrap();
+        assert!(
+            load_signed_profile(&content, &msg_hash, &signature, &digest)
+                .is_err()
+        );
+        assert!(registered_providers().contains(&names[0]));
+        assert_eq!(
+            load_signed_profile(&content, &key, &signature, &digest).unwrap(),
+            profile
+        );
+        assert!(!registered_providers().contains(&names[0]));
+        assert!(ProviderHash::new("test-reverse-hash").is_err());
+        assert!(hash.hash(b"abc").is_err());
+        assert!(unregister_disallowed_providers().is_empty());
+        set_global_policy(SecurityPolicy::default());
+
+        assert!(unregister_provider("test-echo-xor"));
+        assert!(!unregister_provider("test-echo-xor"));
+        assert!(ProviderBlockCipher::new("test-echo-xor").is_err());
+        assert!(signature.sign(&key[..], &msg_hash[..]).is_err());
+        assert!(cipher.decrypt(&ciphertext[..], &key[..], &[][..]).is_err());
+        #[cfg(unix)]
+        assert!(unsafe { load_provider_library("/nonexistent/provider.so") }
+            .is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_provider_generate_keypair() {
+        let signature = ProviderSignature {
+            name: "test-echo-xor".to_string(),
+        };
+        signature.generate_keypair();
+    }
+}
@@ -0,0 +1,238 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! PSI with computed outputs, which reveals only the intersection
+//! cardinality (PSI-CA) or the sum of payload values associated with
+//! intersecting IDs (PSI-sum), but not the intersecting IDs themselves.
+//!
+//! The session runs as follows:
+//! 1) The client blinds its IDs and sends them to the server.
+//! 2) The server reblinds the client elements, blinds its own IDs, and returns
+//!    both lists shuffled, where PSI-sum attaches a Paillier ciphertext of the
+//!    payload value to each server element.
+//! 3) The client reblinds the server elements and matches both lists, which
+//!    only tells how many elements match because of the shuffling. For PSI-sum,
+//!    it also adds the matched ciphertexts homomorphically, and returns the
+//!    rerandomized sum to the server.
+//! 4) For PSI-sum, the server decrypts the sum.
+
+use crate::{BlindedElement, PsiParty};
+use num_bigint::BigUint;
+use rand::seq::SliceRandom;
+use std::collections::HashSet;
+use wedpr_l_crypto_paillier::{
+    generate_keypair, PaillierPrivateKey, PaillierPublicKey,
+};
+use wedpr_l_utils::error::WedprError;
+
+/// Server response of PSI-CA.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PsiCardinalityResponse {
+    /// Client elements reblinded by the server, in a shuffled order.
+    pub double_blinded: Vec<BlindedElement>,
+    /// Server elements in a shuffled order.
+    pub server_blinded: Vec<BlindedElement>,
+}
+
+/// Server response of PSI-sum.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PsiSumResponse {
+    /// Client elements reblinded by the server, in a shuffled order.
+    pub double_blinded: Vec<BlindedElement>,
+    /// Server elements with the ciphertexts of their payload values, in a
+    /// shuffled order.
+    pub server_items: Vec<(BlindedElement, BigUint)>,
+    pub public_key: PaillierPublicKey,
+}
+
+/// Client of PSI-CA and PSI-sum, which learns the intersection cardinality.
+#[derive(Debug, Clone, Default)]
+pub struct PsiComputationClient {
+    party: PsiParty,
+}
+
+impl PsiComputationClient {
+    /// Creates a client with a new blinding key.
+    pub fn new() -> Self {
+        PsiComputationClient::default()
+    }
+
+    /// Blinds the client IDs.
+    pub fn blind<T: AsRef<[u8]>>(&self, ids: &[T]) -> Vec<BlindedElement> {
+        ids.iter().map(|id| self.party.blind(id.as_ref())).collect()
+    }
+
+    fn matches<'a, I: Iterator<Item = &'a BlindedElement>>(
+        &self,
+        double_blinded: &[BlindedElement],
+        server_blinded: I,
+    ) -> Result<Vec<bool>, WedprError> {
+        let own_elements: HashSet<&BlindedElement> =
+            double_blinded.iter().collect();
+        server_blinded
+            .map(|element| {
+                Ok(own_elements.contains(&self.party.reblind(element)?))
+            })
+            .collect()
+    }
+
+    /// Computes the intersection cardinality from a PSI-CA response.
+    pub fn intersection_cardinality(
+        &self,
+        response: &PsiCardinalityResponse,
+    ) -> Result<u64, WedprError> {
+        let matches = self.matches(
+            &response.double_blinded,
+            response.server_blinded.iter(),
+        )?;
+        Ok(matches.iter().filter(|v| **v).count() as u64)
+    }
+
+    /// Computes the intersection cardinality and the encrypted sum of
+    /// payload values from a PSI-sum response, where the sum is returned to
+    /// the server for decryption.
+    pub fn intersection_sum(
+        &self,
+        response: &PsiSumResponse,
+    ) -> Result<(u64, BigUint), WedprError> {
+        let matches = self.matches(
+            &response.double_blinded,
+            response.server_items.iter().map(|(element, _)| element),
+        )?;
+        let public_key = &response.public_key;
+        let mut cardinality = 0u64;
+        let mut encrypted_sum = public_key.encrypt(&BigUint::from(0u32))?;
+        for ((_, ciphertext), matched) in
+            response.server_items.iter().zip(matches)
+        {
+            if matched {
+                cardinality += 1;
+                encrypted_sum = public_key.add(&encrypted_sum, ciphertext);
+            }
+        }
+        // Hides which ciphertexts are summed from the server.
+        Ok((cardinality, public_key.rerandomize(&encrypted_sum)))
+    }
+}
+
+/// Server of PSI-CA.
+#[derive(Debug, Clone, Default)]
+pub struct PsiCardinalityServer {
+    party: PsiParty,
+}
+
+impl PsiCardinalityServer {
+    /// Creates a server with a new blinding key.
+    pub fn new() -> Self {
+        PsiCardinalityServer::default()
+    }
+
+    /// Responds to the blinded client IDs with the server IDs.
+    pub fn respond<T: AsRef<[u8]>>(
+        &self,
+        client_blinded: &[BlindedElement],
+        ids: &[T],
+    ) -> Result<PsiCardinalityResponse, WedprError> {
+        let mut rng = rand::thread_rng();
+        let mut double_blinded = self.party.reblind_batch(client_blinded)?;
+        double_blinded.shuffle(&mut rng);
+        let mut server_blinded: Vec<BlindedElement> =
+            ids.iter().map(|id| self.party.blind(id.as_ref())).collect();
+        server_blinded.shuffle(&mut rng);
+        Ok(PsiCardinalityResponse {
+            double_blinded,
+            server_blinded,
+        })
+    }
+}
+
+/// Server of PSI-sum, which holds the payload values and learns their sum
+/// over the intersection.
+#[derive(Debug, Clone)]
+pub struct PsiSumServer {
+    party: PsiParty,
+    private_key: PaillierPrivateKey,
+}
+
+impl PsiSumServer {
+    /// Creates a server with a new blinding key and a new Paillier key pair
+    /// of the key size.
+    pub fn new(key_size_in_bits: usize) -> Result<Self, WedprError> {
+        let (_, private_key) = generate_keypair(key_size_in_bits)?;
+        Ok(PsiSumServer {
+            party: PsiParty::new(),
+            private_key,
+        })
+    }
+
+    /// Responds to the blinded client IDs with the server IDs and their
+    /// payload values.
+    pub fn respond<T: AsRef<[u8]>>(
+        &self,
+        client_blinded: &[BlindedElement],
+        items: &[(T, u64)],
+    ) -> Result<PsiSumResponse, WedprError> {
+        let mut rng = rand::thread_rng();
+        let public_key = &self.private_key.public_key;
+        let mut double_blinded = self.party.reblind_batch(client_blinded)?;
+        double_blinded.shuffle(&mut rng);
+        let mut server_items = items
+            .iter()
+            .map(|(id, value)| {
+                Ok((
+                    self.party.blind(id.as_ref()),
+                    public_key.encrypt(&BigUint::from(*value))?,
+                ))
+            })
+            .collect::<Result<Vec<_>, WedprError>>()?;
+        server_items.shuffle(&mut rng);
+        Ok(PsiSumResponse {
+            double_blinded,
+            server_items,
+            public_key: public_key.clone(),
+        })
+    }
+
+    /// Decrypts the sum of payload values over the intersection.
+    pub fn decrypt_sum(
+        &self,
+        encrypted_sum: &BigUint,
+    ) -> Result<BigUint, WedprError> {
+        self.private_key.decrypt(encrypted_sum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_paillier::MIN_PAILLIER_KEY_SIZE_IN_BITS;
+
+    #[test]
+    fn test_psi_computation() {
+        let client = PsiComputationClient::new();
+        let client_ids: Vec<String> =
+            (0..20u64).map(|i| format!("id-{}", i)).collect();
+        let client_blinded = client.blind(&client_ids);
+        let server_items: Vec<(String, u64)> =
+            (10..30u64).map(|i| (format!("id-{}", i), i)).collect();
+
+        let cardinality_server = PsiCardinalityServer::new();
+        let server_ids: Vec<&String> =
+            server_items.iter().map(|(id, _)| id).collect();
+        let response = cardinality_server
+            .respond(&client_blinded, &server_ids)
+            .unwrap();
+        assert_eq!(client.intersection_cardinality(&response).unwrap(), 10);
+
+        let sum_server =
+            PsiSumServer::new(MIN_PAILLIER_KEY_SIZE_IN_BITS).unwrap();
+        let response =
+            sum_server.respond(&client_blinded, &server_items).unwrap();
+        let (cardinality, encrypted_sum) =
+            client.intersection_sum(&response).unwrap();
+        assert_eq!(cardinality, 10);
+        assert_eq!(
+            sum_server.decrypt_sum(&encrypted_sum).unwrap(),
+            BigUint::from((10..20u64).sum::<u64>())
+        );
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! PSI revealing the intersecting IDs to the client, e.g. for deduplicating
+//! KYC records between institutions, where neither party learns the IDs
+//! held only by the other party.
+//!
+//! The session runs as follows:
+//! 1) The client blinds its IDs and sends them to the server.
+//! 2) The server reblinds the client elements in the same order, blinds its own
+//!    IDs, and returns both lists, where its own elements are shuffled.
+//! 3) The client reblinds the server elements, and gets the indexes of its IDs
+//!    whose double-blinded elements match.
+
+use crate::{BlindedElement, PsiParty, BLINDED_ELEMENT_SIZE_IN_BYTES};
+use rand::seq::SliceRandom;
+use std::collections::HashSet;
+use wedpr_l_utils::error::WedprError;
+
+/// Size of the big-endian element counts in encoded responses.
+const COUNT_SIZE_IN_BYTES: usize = 8;
+
+/// Server response of PSI.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PsiIntersectionResponse {
+    /// Client elements reblinded by the server, in the client order.
+    pub double_blinded: Vec<BlindedElement>,
+    /// Server elements in a shuffled order.
+    pub server_blinded: Vec<BlindedElement>,
+}
+
+impl PsiIntersectionResponse {
+    /// Encodes the response for sending to the client.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::with_capacity(
+            2 * COUNT_SIZE_IN_BYTES
+                + (self.double_blinded.len() + self.server_blinded.len())
+                    * BLINDED_ELEMENT_SIZE_IN_BYTES,
+        );
+        for elements in &[&self.double_blinded, &self.server_blinded] {
+            output.extend_from_slice(&(elements.len() as u64).to_be_bytes());
+            for element in elements.iter() {
+                output.extend_from_slice(element);
+            }
+        }
+        output
+    }
+
+    /// Decodes a response encoded by encode.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        let mut remaining = input;
+        let double_blinded = decode_elements(&mut remaining)?;
+        let server_blinded = decode_elements(&mut remaining)?;
+        if !remaining.is_empty() {
+            wedpr_println!("PSI response has trailing data");
+            return Err(WedprError::FormatError);
+        }
+        Ok(PsiIntersectionResponse {
+            double_blinded,
+            server_blinded,
+        })
+    }
+}
+
+fn decode_elements(
+    input: &mut &[u8],
+) -> Result<Vec<BlindedElement>, WedprError> {
+    if input.len() < COUNT_SIZE_IN_BYTES {
+        return Err(WedprError::FormatError);
+    }
+    let mut buffer = [0u8; COUNT_SIZE_IN_BYTES];
+    buffer.copy_from_slice(&input[..COUNT_SIZE_IN_BYTES]);
+    let count = u64::from_be_bytes(buffer);
+    let remaining = &input[COUNT_SIZE_IN_BYTES..];
+    if count > (remaining.len() / BLINDED_ELEMENT_SIZE_IN_BYTES) as u64 {
+        return Err(WedprError::FormatError);
+    }
+    let size = count as usize * BLINDED_ELEMENT_SIZE_IN_BYTES;
+    let elements = remaining[..size]
+        .chunks(BLINDED_ELEMENT_SIZE_IN_BYTES)
+        .map(|chunk| {
+            let mut element = [0u8; BLINDED_ELEMENT_SIZE_IN_BYTES];
+            element.copy_from_slice(chunk);
+            element
+        })
+        .collect();
+    *input = &remaining[size..];
+    Ok(elements)
+}
+
+/// Client of PSI, which learns the intersecting IDs.
+#[derive(Debug, Clone, Default)]
+pub struct PsiIntersectionClient {
+    party: PsiParty,
+}
+
+impl PsiIntersectionClient {
+    /// Creates a client with a new blinding key.
+    pub fn new() -> Self {
+        PsiIntersectionClient::default()
+    }
+
+    /// Blinds the client IDs.
+    pub fn blind<T: AsRef<[u8]>>(&self, ids: &[T]) -> Vec<BlindedElement> {
+        ids.iter().map(|id| self.party.blind(id.as_ref())).collect()
+    }
+
+    /// Gets the indexes of the blinded client IDs in the intersection, in
+    /// ascending order.
+    pub fn intersection(
+        &self,
+        response: &PsiIntersectionResponse,
+    ) -> Result<Vec<usize>, WedprError> {
+        let server_elements: HashSet<BlindedElement> = response
+            .server_blinded
+            .iter()
+            .map(|element| self.party.reblind(element))
+            .collect::<Result<_, _>>()?;
+        Ok(response
+            .double_blinded
+            .iter()
+            .enumerate()
+            .filter(|(_, element)| server_elements.contains(*element))
+            .map(|(index, _)| index)
+            .collect())
+    }
+}
+
+/// Server of PSI.
+#[derive(Debug, Clone, Default)]
+pub struct PsiIntersectionServer {
+    party: PsiParty,
+}
+
+impl PsiIntersectionServer {
+    /// Creates a server with a new blinding key.
+    pub fn new() -> Self {
+        PsiIntersectionServer::default()
+    }
+
+    /// Responds to the blinded client IDs with the server IDs.
+    pub fn respond<T: AsRef<[u8]>>(
+        &self,
+        client_blinded: &[BlindedElement],
+        ids: &[T],
+    ) -> Result<PsiIntersectionResponse, WedprError> {
+        let double_blinded = self.party.reblind_batch(client_blinded)?;
+        let mut server_blinded: Vec<BlindedElement> =
+            ids.iter().map(|id| self.party.blind(id.as_ref())).collect();
+        server_blinded.shuffle(&mut rand::thread_rng());
+        Ok(PsiIntersectionResponse {
+            double_blinded,
+            server_blinded,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_psi_intersection() {
+        let client = PsiIntersectionClient::new();
+        let client_ids: Vec<String> =
+            (0..20u64).map(|i| format!("id-{}", i * 3)).collect();
+        let server_ids: Vec<String> =
+            (0..30u64).map(|i| format!("id-{}", i * 2)).collect();
+        let server = PsiIntersectionServer::new();
+        let response = server
+            .respond(&client.blind(&client_ids), &server_ids)
+            .unwrap();
+        let response =
+            PsiIntersectionResponse::decode(&response.encode()).unwrap();
+        let intersection = client.intersection(&response).unwrap();
+        assert_eq!(intersection, (0..20).step_by(2).collect::<Vec<usize>>());
+        assert_eq!(client_ids[intersection[1]], "id-6");
+
+        let encoded = response.encode();
+        assert!(
+            PsiIntersectionResponse::decode(&encoded[..encoded.len() - 1])
+                .is_err()
+        );
+        assert!(PsiIntersectionResponse::decode(&[0u8; 7]).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Private set intersection (PSI) functions based on commutative ECDH
+//! blinding over Ristretto.
+//!
+//! Each party hashes its IDs to points and blinds them with its secret key.
+//! After both parties blind each other's elements once more, an ID held by
+//! both parties maps to the same double-blinded point H(id)^(a * b), which
+//! reveals nothing else about the IDs.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+pub mod computation;
+pub mod intersection;
+pub mod linkage;
+pub mod streaming;
+pub mod unbalanced;
+
+use curve25519_dalek::{
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+};
+use sha3::Sha3_512;
+use wedpr_l_crypto_zkp_utils::get_random_scalar;
+use wedpr_l_utils::error::WedprError;
+
+/// Domain separator of hashing IDs to points.
+const PSI_HASH_DOMAIN: &[u8] = b"WeDPR_PSI";
+
+/// Serialized data size of a blinded element.
+pub const BLINDED_ELEMENT_SIZE_IN_BYTES: usize = 32;
+
+/// Blinded element exchanged between parties.
+pub type BlindedElement = [u8; BLINDED_ELEMENT_SIZE_IN_BYTES];
+
+/// Party of a PSI session with its secret blinding key.
+#[derive(Debug, Clone)]
+pub struct PsiParty {
+    key: Scalar,
+}
+
+impl PsiParty {
+    /// Creates a party with a new random key.
+    pub fn new() -> Self {
+        PsiParty {
+            key: get_random_scalar(),
+        }
+    }
+
+    /// Hashes an ID to a point and blinds it.
+    pub fn blind(&self, id: &[u8]) -> BlindedElement {
+        (self.key * hash_to_point(id)).compress().to_bytes()
+    }
+
+    /// Blinds an element already blinded by the peer.
+    pub fn reblind(
+        &self,
+        element: &BlindedElement,
+    ) -> Result<BlindedElement, WedprError> {
+        match CompressedRistretto(*element).decompress() {
+            Some(point) => Ok((self.key * point).compress().to_bytes()),
+            None => {
+                wedpr_println!("Blinded element is not a valid point");
+                Err(WedprError::FormatError)
+            },
+        }
+    }
+
+    /// Blinds a batch of elements already blinded by the peer.
+    pub fn reblind_batch(
+        &self,
+        elements: &[BlindedElement],
+    ) -> Result<Vec<BlindedElement>, WedprError> {
+        elements
+            .iter()
+            .map(|element| self.reblind(element))
+            .collect()
+    }
+}
+
+/// Hashes an ID to a point.
+pub(crate) fn hash_to_point(id: &[u8]) -> RistrettoPoint {
+    let mut hash_vec = PSI_HASH_DOMAIN.to_vec();
+    hash_vec.extend_from_slice(id);
+    RistrettoPoint::hash_from_bytes::<Sha3_512>(&hash_vec)
+}
+
+impl Default for PsiParty {
+    fn default() -> Self {
+        PsiParty::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commutative_blinding() {
+        let alice = PsiParty::new();
+        let bob = PsiParty::new();
+        let alice_then_bob = bob.reblind(&alice.blind(b"id-1")).unwrap();
+        let bob_then_alice = alice.reblind(&bob.blind(b"id-1")).unwrap();
+        assert_eq!(alice_then_bob, bob_then_alice);
+        assert_ne!(alice_then_bob, alice.reblind(&bob.blind(b"id-2")).unwrap());
+        assert!(alice.reblind(&[0xffu8; 32]).is_err());
+    }
+}
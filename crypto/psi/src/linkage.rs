@@ -0,0 +1,239 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Privacy-preserving record linkage with fuzzy matching, where records are
+//! encoded as Bloom filters of keyed hashes of their character n-grams, also
+//! known as cryptographic long-term keys (CLK).
+//!
+//! Institutions sharing the same secret key encode their records locally, and
+//! only exchange the encodings. Records with typos still share most n-grams,
+//! so that their encodings can be matched by the Dice similarity without
+//! revealing the plaintext fields to a party without the key.
+
+use wedpr_l_utils::{error::WedprError, traits::Mac};
+
+/// Default size of an encoding in bits.
+pub const DEFAULT_ENCODING_SIZE_IN_BITS: usize = 1024;
+/// Default number of bits set for each n-gram.
+pub const DEFAULT_HASH_COUNT: usize = 20;
+/// Default size of n-grams in characters.
+pub const DEFAULT_NGRAM_SIZE: usize = 2;
+
+/// Character padding both ends of a field, so that the first and last
+/// characters appear in as many n-grams as others.
+const NGRAM_PADDING: char = '_';
+
+/// Encoder of records into Bloom filters under a secret key, which requires
+/// a MAC with tags of at least 16 bytes, e.g. HMAC-SHA256.
+#[derive(Debug, Clone)]
+pub struct LinkageEncoder<M: Mac> {
+    mac: M,
+    key: Vec<u8>,
+    size_in_bits: usize,
+    hash_count: usize,
+    ngram_size: usize,
+}
+
+impl<M: Mac> LinkageEncoder<M> {
+    /// Creates an encoder with the default parameters.
+    pub fn new(mac: M, key: &[u8]) -> Result<Self, WedprError> {
+        LinkageEncoder::with_params(
+            mac,
+            key,
+            DEFAULT_ENCODING_SIZE_IN_BITS,
+            DEFAULT_HASH_COUNT,
+            DEFAULT_NGRAM_SIZE,
+        )
+    }
+
+    /// Creates an encoder with custom parameters, where size_in_bits should
+    /// be a positive multiple of 8.
+    pub fn with_params(
+        mac: M,
+        key: &[u8],
+        size_in_bits: usize,
+        hash_count: usize,
+        ngram_size: usize,
+    ) -> Result<Self, WedprError> {
+        let empty: &[u8] = &[];
+        if size_in_bits == 0
+            || size_in_bits % 8 != 0
+            || hash_count == 0
+            || ngram_size == 0
+            || mac.mac(key, empty).len() < 16
+        {
+            wedpr_println!("Invalid record linkage parameters");
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(LinkageEncoder {
+            mac,
+            key: key.to_vec(),
+            size_in_bits,
+            hash_count,
+            ngram_size,
+        })
+    }
+
+    /// Splits a normalized field into padded n-grams.
+    fn ngrams(&self, field: &str) -> Vec<String> {
+        let normalized = field
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ")
+            .to_lowercase();
+        let padding: String = std::iter::repeat(NGRAM_PADDING)
+            .take(self.ngram_size - 1)
+            .collect();
+        let chars: Vec<char> = format!("{}{}{}", padding, normalized, padding)
+            .chars()
+            .collect();
+        if chars.len() < self.ngram_size {
+            return Vec::new();
+        }
+        chars
+            .windows(self.ngram_size)
+            .map(|v| v.iter().collect())
+            .collect()
+    }
+
+    /// Encodes the fields of a record, e.g. names and birth dates, where the
+    /// same n-gram in different fields sets different bits.
+    pub fn encode<T: AsRef<str>>(&self, fields: &[T]) -> RecordEncoding {
+        let mut bits = vec![0u8; self.size_in_bits / 8];
+        for (field_index, field) in fields.iter().enumerate() {
+            for ngram in self.ngrams(field.as_ref()) {
+                let mut message = (field_index as u32).to_be_bytes().to_vec();
+                message.extend_from_slice(ngram.as_bytes());
+                let digest = self.mac.mac(&self.key[..], &message[..]);
+                // Derives the bit positions by double hashing.
+                let mut buffer = [0u8; 8];
+                buffer.copy_from_slice(&digest[0..8]);
+                let hash1 = u64::from_be_bytes(buffer);
+                buffer.copy_from_slice(&digest[8..16]);
+                let hash2 = u64::from_be_bytes(buffer) | 1;
+                for i in 0..self.hash_count as u64 {
+                    let position = (hash1.wrapping_add(i.wrapping_mul(hash2))
+                        % self.size_in_bits as u64)
+                        as usize;
+                    bits[position / 8] |= 1 << (position % 8);
+                }
+            }
+        }
+        RecordEncoding { bits }
+    }
+}
+
+/// Bloom filter encoding of a record.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct RecordEncoding {
+    bits: Vec<u8>,
+}
+
+impl RecordEncoding {
+    /// Decodes an encoding from bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        RecordEncoding {
+            bits: bytes.to_vec(),
+        }
+    }
+
+    /// Gets the encoding bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bits
+    }
+
+    fn count_ones(bits: &[u8]) -> u32 {
+        bits.iter().map(|v| v.count_ones()).sum()
+    }
+
+    /// Computes the Dice similarity 2 * |A & B| / (|A| + |B|) in [0, 1],
+    /// which fails for encodings of different sizes.
+    pub fn dice_similarity(
+        &self,
+        other: &RecordEncoding,
+    ) -> Result<f64, WedprError> {
+        if self.bits.len() != other.bits.len() {
+            wedpr_println!("Record encodings have different sizes");
+            return Err(WedprError::ArgumentError);
+        }
+        let total = RecordEncoding::count_ones(&self.bits)
+            + RecordEncoding::count_ones(&other.bits);
+        if total == 0 {
+            return Ok(0.0);
+        }
+        let common: u32 = self
+            .bits
+            .iter()
+            .zip(&other.bits)
+            .map(|(a, b)| (a & b).count_ones())
+            .sum();
+        Ok(2.0 * common as f64 / total as f64)
+    }
+}
+
+/// Links each left record to its most similar right record, and returns the
+/// (left index, right index, similarity) of the links whose similarity is at
+/// least threshold.
+pub fn link_records(
+    left: &[RecordEncoding],
+    right: &[RecordEncoding],
+    threshold: f64,
+) -> Result<Vec<(usize, usize, f64)>, WedprError> {
+    let mut links = Vec::new();
+    for (i, left_record) in left.iter().enumerate() {
+        let mut best: Option<(usize, f64)> = None;
+        for (j, right_record) in right.iter().enumerate() {
+            let similarity = left_record.dice_similarity(right_record)?;
+            if similarity >= threshold
+                && best.map_or(true, |(_, v)| similarity > v)
+            {
+                best = Some((j, similarity));
+            }
+        }
+        if let Some((j, similarity)) = best {
+            links.push((i, j, similarity));
+        }
+    }
+    Ok(links)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_sha256::WedprSha256;
+    use wedpr_l_crypto_mac_hmac::{WedprHmac, HMAC_SHA256_BLOCK_SIZE_IN_BYTES};
+
+    #[test]
+    fn test_record_linkage() {
+        let hmac = WedprHmac::new(
+            WedprSha256::default(),
+            HMAC_SHA256_BLOCK_SIZE_IN_BYTES,
+        );
+        let bank = LinkageEncoder::new(hmac.clone(), b"shared secret").unwrap();
+        let insurer =
+            LinkageEncoder::new(hmac.clone(), b"shared secret").unwrap();
+        let bank_records = vec![
+            bank.encode(&["John Smith", "1980-01-02"]),
+            bank.encode(&["Alice Wong", "1975-12-30"]),
+        ];
+        let insurer_records = vec![
+            insurer.encode(&["Alice  WONG", "1975-12-30"]),
+            insurer.encode(&["Jon Smith", "1980-01-02"]),
+            insurer.encode(&["Bob Lee", "1990-07-07"]),
+        ];
+
+        let links = link_records(&bank_records, &insurer_records, 0.8).unwrap();
+        assert_eq!(links.len(), 2);
+        assert_eq!((links[0].0, links[0].1), (0, 1));
+        assert!(links[0].2 < 1.0);
+        assert_eq!((links[1].0, links[1].1), (1, 0));
+        assert!((links[1].2 - 1.0).abs() < 1e-9);
+
+        // Encodings under another key do not match.
+        let outsider = LinkageEncoder::new(hmac, b"guessed secret").unwrap();
+        let guessed = outsider.encode(&["John Smith", "1980-01-02"]);
+        assert!(guessed.dice_similarity(&bank_records[0]).unwrap() < 0.5);
+        assert!(guessed
+            .dice_similarity(&RecordEncoding::from_bytes(&[0u8; 8]))
+            .is_err());
+    }
+}
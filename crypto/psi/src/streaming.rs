@@ -0,0 +1,408 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Streaming PSI for very large sets, e.g. hundreds of millions of IDs,
+//! without holding both sets in memory.
+//!
+//! IDs are read from iterators and blinded in batches. The receiver
+//! partitions double-blinded elements of both sides into buckets by their
+//! leading bytes, which are kept in a BucketStore such as files, and then
+//! intersects one bucket at a time, so that its memory usage is bounded by
+//! the largest bucket.
+//!
+//! The session runs as follows:
+//! 1) The receiver blinds its IDs with blind_stream and sends the batches.
+//! 2) The sender reblinds each batch in order with PsiParty::reblind_batch, and
+//!    returns them, which the receiver adds by add_own_double_blinded.
+//! 3) The sender blinds its IDs with blind_stream, which the receiver adds by
+//!    add_peer_blinded.
+//! 4) The receiver calls finish to get the indexes of its IDs in the
+//!    intersection.
+
+use crate::{BlindedElement, PsiParty, BLINDED_ELEMENT_SIZE_IN_BYTES};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, ErrorKind, Write},
+    path::PathBuf,
+};
+use wedpr_l_utils::error::WedprError;
+
+/// Serialized data size of an ID index.
+const INDEX_SIZE_IN_BYTES: usize = 8;
+
+/// Storage of records partitioned into buckets.
+pub trait BucketStore {
+    /// Appends a record to a bucket.
+    fn append(
+        &mut self,
+        bucket: usize,
+        record: &[u8],
+    ) -> Result<(), WedprError>;
+
+    /// Removes all records of a bucket, and returns them concatenated.
+    fn take(&mut self, bucket: usize) -> Result<Vec<u8>, WedprError>;
+}
+
+/// Bucket store in memory, for sets fitting in memory and tests.
+#[derive(Default, Debug, Clone)]
+pub struct MemoryBucketStore {
+    buckets: HashMap<usize, Vec<u8>>,
+}
+
+impl MemoryBucketStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        MemoryBucketStore::default()
+    }
+}
+
+impl BucketStore for MemoryBucketStore {
+    fn append(
+        &mut self,
+        bucket: usize,
+        record: &[u8],
+    ) -> Result<(), WedprError> {
+        self.buckets
+            .entry(bucket)
+            .or_insert_with(Vec::new)
+            .extend_from_slice(record);
+        Ok(())
+    }
+
+    fn take(&mut self, bucket: usize) -> Result<Vec<u8>, WedprError> {
+        Ok(self.buckets.remove(&bucket).unwrap_or_default())
+    }
+}
+
+/// Bucket store keeping each bucket in a file of a directory.
+#[derive(Debug)]
+pub struct FileBucketStore {
+    directory: PathBuf,
+    prefix: String,
+    writers: HashMap<usize, BufWriter<File>>,
+}
+
+impl FileBucketStore {
+    /// Creates a store whose bucket files are named prefix_{bucket}.bin in
+    /// directory.
+    pub fn new<P: Into<PathBuf>>(directory: P, prefix: &str) -> Self {
+        FileBucketStore {
+            directory: directory.into(),
+            prefix: prefix.to_string(),
+            writers: HashMap::new(),
+        }
+    }
+
+    fn bucket_path(&self, bucket: usize) -> PathBuf {
+        self.directory
+            .join(format!("{}_{}.bin", self.prefix, bucket))
+    }
+}
+
+impl BucketStore for FileBucketStore {
+    fn append(
+        &mut self,
+        bucket: usize,
+        record: &[u8],
+    ) -> Result<(), WedprError> {
+        if !self.writers.contains_key(&bucket) {
+            let file = match OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.bucket_path(bucket))
+            {
+                Ok(v) => v,
+                Err(_) => {
+                    wedpr_println!("Bucket file cannot be opened");
+                    return Err(WedprError::StorageError);
+                },
+            };
+            self.writers.insert(bucket, BufWriter::new(file));
+        }
+        match self.writers.get_mut(&bucket) {
+            Some(writer) => writer
+                .write_all(record)
+                .map_err(|_| WedprError::StorageError),
+            None => Err(WedprError::StorageError),
+        }
+    }
+
+    fn take(&mut self, bucket: usize) -> Result<Vec<u8>, WedprError> {
+        if let Some(mut writer) = self.writers.remove(&bucket) {
+            if writer.flush().is_err() {
+                return Err(WedprError::StorageError);
+            }
+        }
+        let path = self.bucket_path(bucket);
+        let records = match fs::read(&path) {
+            Ok(v) => v,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(_) => return Err(WedprError::StorageError),
+        };
+        if fs::remove_file(&path).is_err() {
+            return Err(WedprError::StorageError);
+        }
+        Ok(records)
+    }
+}
+
+/// Stage of a streaming PSI session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PsiStage {
+    /// Blinding own IDs.
+    Blinding,
+    /// Intersecting buckets.
+    Intersecting,
+}
+
+/// Progress reported to callbacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PsiProgress {
+    pub stage: PsiStage,
+    /// Number of IDs blinded or buckets intersected so far.
+    pub processed: u64,
+    /// Total number to process, if known.
+    pub total: Option<u64>,
+}
+
+/// Blinds streamed IDs in batches of batch_size, passes each batch to send,
+/// and returns the number of blinded IDs.
+pub fn blind_stream<T, I, S, P>(
+    party: &PsiParty,
+    ids: I,
+    batch_size: usize,
+    mut send: S,
+    mut progress: P,
+) -> Result<u64, WedprError>
+where
+    T: AsRef<[u8]>,
+    I: IntoIterator<Item = T>,
+    S: FnMut(Vec<BlindedElement>) -> Result<(), WedprError>,
+    P: FnMut(PsiProgress),
+{
+    if batch_size == 0 {
+        return Err(WedprError::ArgumentError);
+    }
+    let mut processed = 0u64;
+    let mut batch = Vec::with_capacity(batch_size);
+    for id in ids {
+        batch.push(party.blind(id.as_ref()));
+        if batch.len() == batch_size {
+            processed += batch.len() as u64;
+            send(std::mem::replace(
+                &mut batch,
+                Vec::with_capacity(batch_size),
+            ))?;
+            progress(PsiProgress {
+                stage: PsiStage::Blinding,
+                processed,
+                total: None,
+            });
+        }
+    }
+    if !batch.is_empty() {
+        processed += batch.len() as u64;
+        send(batch)?;
+        progress(PsiProgress {
+            stage: PsiStage::Blinding,
+            processed,
+            total: None,
+        });
+    }
+    Ok(processed)
+}
+
+/// Receiver of a streaming PSI session, which learns the intersection.
+#[derive(Debug)]
+pub struct StreamingPsiReceiver<S: BucketStore> {
+    party: PsiParty,
+    bucket_count: usize,
+    own_store: S,
+    peer_store: S,
+    own_count: u64,
+}
+
+impl<S: BucketStore> StreamingPsiReceiver<S> {
+    /// Creates a receiver partitioning elements into bucket_count buckets,
+    /// where own_store and peer_store keep the elements of each side.
+    pub fn new(
+        party: PsiParty,
+        bucket_count: usize,
+        own_store: S,
+        peer_store: S,
+    ) -> Result<Self, WedprError> {
+        if bucket_count == 0 {
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(StreamingPsiReceiver {
+            party,
+            bucket_count,
+            own_store,
+            peer_store,
+            own_count: 0,
+        })
+    }
+
+    /// Gets the party for blinding own IDs.
+    pub fn party(&self) -> &PsiParty {
+        &self.party
+    }
+
+    fn bucket_of(&self, element: &BlindedElement) -> usize {
+        let mut buffer = [0u8; 8];
+        buffer.copy_from_slice(&element[..8]);
+        (u64::from_be_bytes(buffer) % self.bucket_count as u64) as usize
+    }
+
+    /// Adds a batch of own elements reblinded by the sender, which must keep
+    /// the order in which own IDs were blinded.
+    pub fn add_own_double_blinded(
+        &mut self,
+        batch: &[BlindedElement],
+    ) -> Result<(), WedprError> {
+        let mut record = Vec::with_capacity(
+            INDEX_SIZE_IN_BYTES + BLINDED_ELEMENT_SIZE_IN_BYTES,
+        );
+        for element in batch {
+            record.clear();
+            record.extend_from_slice(&self.own_count.to_be_bytes());
+            record.extend_from_slice(element);
+            self.own_store.append(self.bucket_of(element), &record)?;
+            self.own_count += 1;
+        }
+        Ok(())
+    }
+
+    /// Reblinds and adds a batch of elements blinded by the sender.
+    pub fn add_peer_blinded(
+        &mut self,
+        batch: &[BlindedElement],
+    ) -> Result<(), WedprError> {
+        for element in batch {
+            let double_blinded = self.party.reblind(element)?;
+            self.peer_store
+                .append(self.bucket_of(&double_blinded), &double_blinded)?;
+        }
+        Ok(())
+    }
+
+    /// Intersects the buckets one at a time, calls on_match with the index
+    /// of each own ID in the intersection, and returns the intersection
+    /// size.
+    pub fn finish<M: FnMut(u64), P: FnMut(PsiProgress)>(
+        mut self,
+        mut on_match: M,
+        mut progress: P,
+    ) -> Result<u64, WedprError> {
+        let mut match_count = 0u64;
+        let own_record_size =
+            INDEX_SIZE_IN_BYTES + BLINDED_ELEMENT_SIZE_IN_BYTES;
+        for bucket in 0..self.bucket_count {
+            let peer_records = self.peer_store.take(bucket)?;
+            let own_records = self.own_store.take(bucket)?;
+            if peer_records.len() % BLINDED_ELEMENT_SIZE_IN_BYTES != 0
+                || own_records.len() % own_record_size != 0
+            {
+                wedpr_println!("Bucket {} is corrupted", bucket);
+                return Err(WedprError::FormatError);
+            }
+            let peer_elements: HashSet<&[u8]> =
+                peer_records.chunks(BLINDED_ELEMENT_SIZE_IN_BYTES).collect();
+            for record in own_records.chunks(own_record_size) {
+                if peer_elements.contains(&record[INDEX_SIZE_IN_BYTES..]) {
+                    let mut buffer = [0u8; INDEX_SIZE_IN_BYTES];
+                    buffer.copy_from_slice(&record[..INDEX_SIZE_IN_BYTES]);
+                    on_match(u64::from_be_bytes(buffer));
+                    match_count += 1;
+                }
+            }
+            progress(PsiProgress {
+                stage: PsiStage::Intersecting,
+                processed: bucket as u64 + 1,
+                total: Some(self.bucket_count as u64),
+            });
+        }
+        Ok(match_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_session<S: BucketStore>(
+        own_store: S,
+        peer_store: S,
+    ) -> (Vec<u64>, Vec<PsiProgress>) {
+        let receiver_ids = (0..100u32).map(|i| format!("id-{}", i));
+        let sender_ids = (50..150u32).map(|i| format!("id-{}", i));
+        let sender = PsiParty::new();
+        let mut receiver = StreamingPsiReceiver::new(
+            PsiParty::new(),
+            8,
+            own_store,
+            peer_store,
+        )
+        .unwrap();
+
+        let mut own_batches = Vec::new();
+        blind_stream(
+            receiver.party(),
+            receiver_ids,
+            16,
+            |batch| {
+                own_batches.push(sender.reblind_batch(&batch)?);
+                Ok(())
+            },
+            |_| {},
+        )
+        .unwrap();
+        for batch in &own_batches {
+            receiver.add_own_double_blinded(batch).unwrap();
+        }
+        let mut peer_batches = Vec::new();
+        let mut blinding_progress = Vec::new();
+        blind_stream(
+            &sender,
+            sender_ids,
+            16,
+            |batch| {
+                peer_batches.push(batch);
+                Ok(())
+            },
+            |v| blinding_progress.push(v),
+        )
+        .unwrap();
+        for batch in &peer_batches {
+            receiver.add_peer_blinded(batch).unwrap();
+        }
+
+        let mut matches = Vec::new();
+        let mut progress = Vec::new();
+        let match_count = receiver
+            .finish(|index| matches.push(index), |v| progress.push(v))
+            .unwrap();
+        assert_eq!(match_count, matches.len() as u64);
+        assert_eq!(blinding_progress.last().unwrap().processed, 100);
+        matches.sort();
+        (matches, progress)
+    }
+
+    #[test]
+    fn test_streaming_psi() {
+        let expected: Vec<u64> = (50..100).collect();
+        let (matches, progress) =
+            run_session(MemoryBucketStore::new(), MemoryBucketStore::new());
+        assert_eq!(matches, expected);
+        assert_eq!(progress.len(), 8);
+        assert_eq!(progress[7].processed, 8);
+
+        let directory = std::env::temp_dir();
+        let (file_matches, _) = run_session(
+            FileBucketStore::new(&directory, "wedpr_psi_test_own"),
+            FileBucketStore::new(&directory, "wedpr_psi_test_peer"),
+        );
+        assert_eq!(file_matches, expected);
+        assert!(!directory.join("wedpr_psi_test_own_0.bin").exists());
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Unbalanced PSI for a small client set against a huge server set, e.g. a
+//! mobile client screening thousands of IDs against billions of IDs.
+//!
+//! The server evaluates an oblivious PRF (OPRF) H(x)^k on its whole set
+//! offline, and inserts the outputs into a cuckoo filter, which is sent to
+//! clients in advance and reused across sessions. Online, the client blinds
+//! its IDs as H(y)^r, the server evaluates them with its key k, and the
+//! client unblinds H(y)^k to look them up in the filter, so that the online
+//! cost only depends on the client set size.
+
+use crate::{hash_to_point, BlindedElement, PsiParty};
+use curve25519_dalek::{ristretto::CompressedRistretto, scalar::Scalar};
+use wedpr_l_crypto_zkp_utils::get_random_scalar;
+use wedpr_l_utils::error::WedprError;
+
+/// Number of fingerprint slots in a bucket of CuckooFilter.
+const SLOTS_PER_BUCKET: usize = 4;
+/// Maximum load factor of CuckooFilter for successful insertions.
+const MAX_LOAD_FACTOR: f64 = 0.95;
+/// Maximum number of evictions for inserting a fingerprint.
+const MAX_KICKS: usize = 500;
+/// Serialized data size of a fingerprint.
+const FINGERPRINT_SIZE_IN_BYTES: usize = 4;
+
+/// Cuckoo filter of OPRF outputs, storing a 32-bit fingerprint of each
+/// output, which has a false positive rate around 2^-29 per lookup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CuckooFilter {
+    slots: Vec<u32>,
+    bucket_count: usize,
+    len: usize,
+}
+
+impl CuckooFilter {
+    /// Creates an empty filter for at most capacity elements.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let min_bucket_count = (capacity as f64
+            / (SLOTS_PER_BUCKET as f64 * MAX_LOAD_FACTOR))
+            .ceil() as usize;
+        let bucket_count = min_bucket_count.max(1).next_power_of_two();
+        CuckooFilter {
+            slots: vec![0u32; bucket_count * SLOTS_PER_BUCKET],
+            bucket_count,
+            len: 0,
+        }
+    }
+
+    /// Gets the number of inserted elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks whether the filter is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn fingerprint(element: &BlindedElement) -> u32 {
+        let mut buffer = [0u8; FINGERPRINT_SIZE_IN_BYTES];
+        buffer.copy_from_slice(&element[..FINGERPRINT_SIZE_IN_BYTES]);
+        // Zero marks an empty slot.
+        u32::from_be_bytes(buffer).max(1)
+    }
+
+    fn primary_bucket(&self, element: &BlindedElement) -> usize {
+        let mut buffer = [0u8; 8];
+        buffer.copy_from_slice(&element[8..16]);
+        (u64::from_be_bytes(buffer) as usize) & (self.bucket_count - 1)
+    }
+
+    fn alternate_bucket(&self, bucket: usize, fingerprint: u32) -> usize {
+        let offset = (fingerprint as u64).wrapping_mul(0x5bd1_e995) as usize;
+        (bucket ^ offset) & (self.bucket_count - 1)
+    }
+
+    fn bucket_slots(&mut self, bucket: usize) -> &mut [u32] {
+        let start = bucket * SLOTS_PER_BUCKET;
+        &mut self.slots[start..start + SLOTS_PER_BUCKET]
+    }
+
+    fn try_put(&mut self, bucket: usize, fingerprint: u32) -> bool {
+        match self.bucket_slots(bucket).iter_mut().find(|v| **v == 0) {
+            Some(slot) => {
+                *slot = fingerprint;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Inserts an OPRF output, which fails if the filter is full.
+    pub fn insert(
+        &mut self,
+        element: &BlindedElement,
+    ) -> Result<(), WedprError> {
+        let mut fingerprint = CuckooFilter::fingerprint(element);
+        let mut bucket = self.primary_bucket(element);
+        for kick in 0..MAX_KICKS {
+            if self.try_put(bucket, fingerprint)
+                || self.try_put(
+                    self.alternate_bucket(bucket, fingerprint),
+                    fingerprint,
+                )
+            {
+                self.len += 1;
+                return Ok(());
+            }
+            // Evicts a fingerprint to its alternate bucket.
+            let slot = &mut self.bucket_slots(bucket)[kick % SLOTS_PER_BUCKET];
+            std::mem::swap(slot, &mut fingerprint);
+            bucket = self.alternate_bucket(bucket, fingerprint);
+        }
+        wedpr_println!("Cuckoo filter is full, len = {}", self.len);
+        Err(WedprError::ArgumentError)
+    }
+
+    /// Checks whether an OPRF output may have been inserted.
+    pub fn contains(&self, element: &BlindedElement) -> bool {
+        let fingerprint = CuckooFilter::fingerprint(element);
+        let bucket = self.primary_bucket(element);
+        let alternate = self.alternate_bucket(bucket, fingerprint);
+        [bucket, alternate].iter().any(|v| {
+            let start = v * SLOTS_PER_BUCKET;
+            self.slots[start..start + SLOTS_PER_BUCKET].contains(&fingerprint)
+        })
+    }
+
+    /// Encodes the filter for sending to clients.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::with_capacity(
+            16 + self.slots.len() * FINGERPRINT_SIZE_IN_BYTES,
+        );
+        output.extend_from_slice(&(self.bucket_count as u64).to_be_bytes());
+        output.extend_from_slice(&(self.len as u64).to_be_bytes());
+        for slot in &self.slots {
+            output.extend_from_slice(&slot.to_be_bytes());
+        }
+        output
+    }
+
+    /// Decodes a filter encoded by encode.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        if input.len() < 16 {
+            return Err(WedprError::FormatError);
+        }
+        let mut buffer = [0u8; 8];
+        buffer.copy_from_slice(&input[0..8]);
+        let bucket_count = u64::from_be_bytes(buffer) as usize;
+        buffer.copy_from_slice(&input[8..16]);
+        let len = u64::from_be_bytes(buffer) as usize;
+        let slot_bytes = &input[16..];
+        if !bucket_count.is_power_of_two()
+            || slot_bytes.len() / SLOTS_PER_BUCKET / FINGERPRINT_SIZE_IN_BYTES
+                != bucket_count
+            || slot_bytes.len() % (SLOTS_PER_BUCKET * FINGERPRINT_SIZE_IN_BYTES)
+                != 0
+        {
+            wedpr_println!("Cuckoo filter has an invalid size");
+            return Err(WedprError::FormatError);
+        }
+        let slots = slot_bytes
+            .chunks(FINGERPRINT_SIZE_IN_BYTES)
+            .map(|v| {
+                let mut slot_buffer = [0u8; FINGERPRINT_SIZE_IN_BYTES];
+                slot_buffer.copy_from_slice(v);
+                u32::from_be_bytes(slot_buffer)
+            })
+            .collect();
+        Ok(CuckooFilter {
+            slots,
+            bucket_count,
+            len,
+        })
+    }
+}
+
+/// Server of unbalanced PSI holding the OPRF key and the huge set.
+#[derive(Debug, Clone, Default)]
+pub struct UnbalancedPsiServer {
+    party: PsiParty,
+}
+
+impl UnbalancedPsiServer {
+    /// Creates a server with a new OPRF key.
+    pub fn new() -> Self {
+        UnbalancedPsiServer::default()
+    }
+
+    /// Preprocesses the server set offline into a filter for at most
+    /// capacity IDs, which can be sent to clients in advance.
+    pub fn preprocess<T: AsRef<[u8]>, I: IntoIterator<Item = T>>(
+        &self,
+        ids: I,
+        capacity: usize,
+    ) -> Result<CuckooFilter, WedprError> {
+        let mut filter = CuckooFilter::with_capacity(capacity);
+        for id in ids {
+            filter.insert(&self.party.blind(id.as_ref()))?;
+        }
+        Ok(filter)
+    }
+
+    /// Evaluates the OPRF on elements blinded by a client.
+    pub fn evaluate(
+        &self,
+        blinded: &[BlindedElement],
+    ) -> Result<Vec<BlindedElement>, WedprError> {
+        self.party.reblind_batch(blinded)
+    }
+}
+
+/// Client of unbalanced PSI holding the small set and the server filter.
+#[derive(Debug, Clone)]
+pub struct UnbalancedPsiClient {
+    filter: CuckooFilter,
+    blindings: Vec<Scalar>,
+}
+
+impl UnbalancedPsiClient {
+    /// Creates a client with the filter received from the server.
+    pub fn new(filter: CuckooFilter) -> Self {
+        UnbalancedPsiClient {
+            filter,
+            blindings: Vec::new(),
+        }
+    }
+
+    /// Blinds the client IDs as a query to the server.
+    pub fn blind<T: AsRef<[u8]>>(&mut self, ids: &[T]) -> Vec<BlindedElement> {
+        self.blindings = ids.iter().map(|_| get_random_scalar()).collect();
+        ids.iter()
+            .zip(&self.blindings)
+            .map(|(id, blinding)| {
+                (blinding * hash_to_point(id.as_ref()))
+                    .compress()
+                    .to_bytes()
+            })
+            .collect()
+    }
+
+    /// Unblinds the server evaluations of the last query, and returns the
+    /// indexes of the client IDs in the intersection.
+    pub fn finish(
+        &mut self,
+        evaluated: &[BlindedElement],
+    ) -> Result<Vec<usize>, WedprError> {
+        if evaluated.len() != self.blindings.len() {
+            wedpr_println!("Evaluations do not match the query");
+            return Err(WedprError::ArgumentError);
+        }
+        let blindings = std::mem::take(&mut self.blindings);
+        let mut matches = Vec::new();
+        for (i, (element, blinding)) in
+            evaluated.iter().zip(&blindings).enumerate()
+        {
+            let point = match CompressedRistretto(*element).decompress() {
+                Some(v) => v,
+                None => {
+                    wedpr_println!("Evaluated element is not a valid point");
+                    return Err(WedprError::FormatError);
+                },
+            };
+            let output = (blinding.invert() * point).compress().to_bytes();
+            if self.filter.contains(&output) {
+                matches.push(i);
+            }
+        }
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unbalanced_psi() {
+        let server = UnbalancedPsiServer::new();
+        let server_ids = (0..2000u32).map(|i| format!("id-{}", i));
+        let filter = server.preprocess(server_ids, 2000).unwrap();
+        assert_eq!(filter.len(), 2000);
+        assert!(CuckooFilter::with_capacity(1).insert(&[0u8; 32]).is_ok());
+
+        let received = CuckooFilter::decode(&filter.encode()).unwrap();
+        assert_eq!(received, filter);
+        assert!(CuckooFilter::decode(&filter.encode()[1..]).is_err());
+
+        let mut client = UnbalancedPsiClient::new(received);
+        let client_ids = ["id-5", "id-2000", "id-1999", "other"];
+        let query = client.blind(&client_ids);
+        let evaluated = server.evaluate(&query).unwrap();
+        assert_eq!(client.finish(&evaluated).unwrap(), vec![0, 2]);
+        assert!(client.finish(&evaluated).is_err());
+    }
+}
@@ -0,0 +1,540 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! RSA signature and encryption functions for interoperating with legacy
+//! systems, including RSASSA-PSS, RSASSA-PKCS1-v1_5 and RSAES-OAEP of
+//! PKCS #1 v2.2, all with SHA-256, and RSAES-PKCS1-v1_5 for OpenPGP.
+//!
+//! Private keys are PKCS #8 DER documents and public keys are X.509
+//! SubjectPublicKeyInfo DER documents, of 2048, 3072 or 4096 bits.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+use rsa::{
+    pkcs8::{
+        DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey,
+    },
+    rand_core::OsRng,
+    traits::PublicKeyParts,
+    Oaep, Pkcs1v15Encrypt, Pkcs1v15Sign, Pss, RsaPrivateKey, RsaPublicKey,
+};
+use sha2::Sha256;
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    error::WedprError,
+    policy::{check_global_algorithm, global_policy, PolicyWaiver},
+    traits::{AlgorithmInfo, Ecies, Signature},
+};
+
+/// Supported RSA key sizes.
+pub const RSA_KEY_SIZES_IN_BITS: [usize; 3] = [2048, 3072, 4096];
+/// Default RSA key size.
+pub const DEFAULT_RSA_KEY_SIZE_IN_BITS: usize = 2048;
+
+/// Size of SHA-256 message hashes signed by RSA.
+const RSA_MESSAGE_HASH_SIZE_IN_BYTES: usize = 32;
+
+/// Generates an RSA key pair of a supported size allowed by the security
+/// policy, and returns the DER encoded public and private keys.
+pub fn generate_rsa_keypair(
+    key_size_in_bits: usize,
+) -> Result<(Vec<u8>, Vec<u8>), WedprError> {
+    check_allowed_key_size(key_size_in_bits, None)?;
+    generate_supported_keypair(key_size_in_bits)
+}
+
+/// Generates an RSA key pair like generate_rsa_keypair, where a key size
+/// below the security policy is waived.
+pub fn generate_rsa_keypair_with_waiver(
+    key_size_in_bits: usize,
+    waiver: &PolicyWaiver,
+) -> Result<(Vec<u8>, Vec<u8>), WedprError> {
+    check_allowed_key_size(key_size_in_bits, Some(waiver))?;
+    generate_supported_keypair(key_size_in_bits)
+}
+
+fn generate_supported_keypair(
+    key_size_in_bits: usize,
+) -> Result<(Vec<u8>, Vec<u8>), WedprError> {
+    let private_key = match RsaPrivateKey::new(&mut OsRng, key_size_in_bits) {
+        Ok(v) => v,
+        Err(_) => {
+            wedpr_println!("Generating RSA key pair failed");
+            return Err(WedprError::ArgumentError);
+        },
+    };
+    let public_key = RsaPublicKey::from(&private_key);
+    match (private_key.to_pkcs8_der(), public_key.to_public_key_der()) {
+        (Ok(private_key_der), Ok(public_key_der)) => Ok((
+            public_key_der.as_bytes().to_vec(),
+            private_key_der.as_bytes().to_vec(),
+        )),
+        _ => Err(WedprError::FormatError),
+    }
+}
+
+fn check_key_size(key_size_in_bits: usize) -> Result<(), WedprError> {
+    if !RSA_KEY_SIZES_IN_BITS.contains(&key_size_in_bits) {
+        wedpr_println!("Unsupported RSA key size {}", key_size_in_bits);
+        return Err(WedprError::ArgumentError);
+    }
+    Ok(())
+}
+
+/// Checks a key size is supported and allowed by the security policy.
+fn check_allowed_key_size(
+    key_size_in_bits: usize,
+    waiver: Option<&PolicyWaiver>,
+) -> Result<(), WedprError> {
+    check_key_size(key_size_in_bits)?;
+    global_policy().check_rsa_key_size(key_size_in_bits, waiver)
+}
+
+/// Gets the smallest supported key size of at least the default size and
+/// the minimum size of the security policy.
+fn default_key_size() -> usize {
+    let min_key_size = global_policy()
+        .min_rsa_key_size_in_bits
+        .max(DEFAULT_RSA_KEY_SIZE_IN_BITS);
+    RSA_KEY_SIZES_IN_BITS
+        .iter()
+        .copied()
+        .find(|v| *v >= min_key_size)
+        .unwrap_or(RSA_KEY_SIZES_IN_BITS[RSA_KEY_SIZES_IN_BITS.len() - 1])
+}
+
+fn parse_private_key(private_key: &[u8]) -> Result<RsaPrivateKey, WedprError> {
+    let private_key_obj = match RsaPrivateKey::from_pkcs8_der(private_key) {
+        Ok(v) => v,
+        Err(_) => {
+            wedpr_println!("Parsing RSA private key failed");
+            return Err(WedprError::FormatError);
+        },
+    };
+    check_key_size(private_key_obj.size() * 8)?;
+    Ok(private_key_obj)
+}
+
+fn parse_public_key(public_key: &[u8]) -> Result<RsaPublicKey, WedprError> {
+    let public_key_obj = match RsaPublicKey::from_public_key_der(public_key) {
+        Ok(v) => v,
+        Err(_) => {
+            wedpr_println!("Parsing RSA public key failed");
+            return Err(WedprError::FormatError);
+        },
+    };
+    check_key_size(public_key_obj.size() * 8)?;
+    Ok(public_key_obj)
+}
+
+fn check_msg_hash(msg_hash: &[u8]) -> Result<(), WedprError> {
+    if msg_hash.len() != RSA_MESSAGE_HASH_SIZE_IN_BYTES {
+        wedpr_println!("RSA signatures require SHA-256 message hashes");
+        return Err(WedprError::ArgumentError);
+    }
+    Ok(())
+}
+
+/// Implements RSASSA-PSS with SHA-256 as a Signature instance, which is
+/// recommended for new applications.
+#[derive(Debug, Clone, Copy)]
+pub struct WedprRsaPss {
+    key_size_in_bits: usize,
+}
+
+impl Default for WedprRsaPss {
+    /// Creates an instance generating keys of the default size, or the
+    /// minimum size of the security policy if larger.
+    fn default() -> Self {
+        WedprRsaPss {
+            key_size_in_bits: default_key_size(),
+        }
+    }
+}
+
+impl WedprRsaPss {
+    /// Creates an instance generating keys of a supported size allowed by
+    /// the security policy.
+    pub fn new(key_size_in_bits: usize) -> Result<Self, WedprError> {
+        check_allowed_key_size(key_size_in_bits, None)?;
+        Ok(WedprRsaPss { key_size_in_bits })
+    }
+
+    /// Creates an instance like new, where a key size below the security
+    /// policy is waived.
+    pub fn new_with_waiver(
+        key_size_in_bits: usize,
+        waiver: &PolicyWaiver,
+    ) -> Result<Self, WedprError> {
+        check_allowed_key_size(key_size_in_bits, Some(waiver))?;
+        Ok(WedprRsaPss { key_size_in_bits })
+    }
+}
+
+impl AlgorithmInfo for WedprRsaPss {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "rsassa-pss-sha256",
+            oid: Some("1.2.840.113549.1.1.10"),
+            security_bits: 112,
+            private_key_size: None,
+            public_key_sizes: &[],
+            signature_size: None,
+            output_size: None,
+        }
+    }
+}
+
+impl Signature for WedprRsaPss {
+    fn sign<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        check_global_algorithm::<Self>()?;
+        check_msg_hash(msg_hash.as_ref())?;
+        let private_key_obj = parse_private_key(private_key.as_ref())?;
+        match private_key_obj.sign_with_rng(
+            &mut OsRng,
+            Pss::new::<Sha256>(),
+            msg_hash.as_ref(),
+        ) {
+            Ok(v) => Ok(v),
+            Err(_) => {
+                wedpr_println!("RSA-PSS signing failed");
+                Err(WedprError::ArgumentError)
+            },
+        }
+    }
+
+    fn verify<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        msg_hash: &T,
+        signature: &T,
+    ) -> bool {
+        if check_global_algorithm::<Self>().is_err() {
+            return false;
+        }
+        match parse_public_key(public_key.as_ref()) {
+            Ok(v) => v
+                .verify(
+                    Pss::new::<Sha256>(),
+                    msg_hash.as_ref(),
+                    signature.as_ref(),
+                )
+                .is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+        // The key size has been checked by the constructor.
+        match generate_supported_keypair(self.key_size_in_bits) {
+            Ok(v) => v,
+            Err(_) => unreachable!(),
+        }
+    }
+}
+
+/// Implements RSASSA-PKCS1-v1_5 with SHA-256 as a Signature instance, for
+/// systems which do not support PSS.
+#[derive(Debug, Clone, Copy)]
+pub struct WedprRsaPkcs1v15 {
+    key_size_in_bits: usize,
+}
+
+impl Default for WedprRsaPkcs1v15 {
+    /// Creates an instance generating keys of the default size, or the
+    /// minimum size of the security policy if larger.
+    fn default() -> Self {
+        WedprRsaPkcs1v15 {
+            key_size_in_bits: default_key_size(),
+        }
+    }
+}
+
+impl WedprRsaPkcs1v15 {
+    /// Creates an instance generating keys of a supported size allowed by
+    /// the security policy.
+    pub fn new(key_size_in_bits: usize) -> Result<Self, WedprError> {
+        check_allowed_key_size(key_size_in_bits, None)?;
+        Ok(WedprRsaPkcs1v15 { key_size_in_bits })
+    }
+
+    /// Creates an instance like new, where a key size below the security
+    /// policy is waived.
+    pub fn new_with_waiver(
+        key_size_in_bits: usize,
+        waiver: &PolicyWaiver,
+    ) -> Result<Self, WedprError> {
+        check_allowed_key_size(key_size_in_bits, Some(waiver))?;
+        Ok(WedprRsaPkcs1v15 { key_size_in_bits })
+    }
+}
+
+impl AlgorithmInfo for WedprRsaPkcs1v15 {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "rsassa-pkcs1-v1_5-sha256",
+            oid: Some("1.2.840.113549.1.1.11"),
+            security_bits: 112,
+            private_key_size: None,
+            public_key_sizes: &[],
+            signature_size: None,
+            output_size: None,
+        }
+    }
+}
+
+impl Signature for WedprRsaPkcs1v15 {
+    fn sign<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        check_global_algorithm::<Self>()?;
+        check_msg_hash(msg_hash.as_ref())?;
+        let private_key_obj = parse_private_key(private_key.as_ref())?;
+        match private_key_obj
+            .sign(Pkcs1v15Sign::new::<Sha256>(), msg_hash.as_ref())
+        {
+            Ok(v) => Ok(v),
+            Err(_) => {
+                wedpr_println!("RSA PKCS#1 v1.5 signing failed");
+                Err(WedprError::ArgumentError)
+            },
+        }
+    }
+
+    /// Signs a message hash, where PKCS#1 v1.5 signatures are always
+    /// deterministic.
+    fn sign_deterministic<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        self.sign(private_key, msg_hash)
+    }
+
+    fn verify<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        msg_hash: &T,
+        signature: &T,
+    ) -> bool {
+        if check_global_algorithm::<Self>().is_err() {
+            return false;
+        }
+        match parse_public_key(public_key.as_ref()) {
+            Ok(v) => v
+                .verify(
+                    Pkcs1v15Sign::new::<Sha256>(),
+                    msg_hash.as_ref(),
+                    signature.as_ref(),
+                )
+                .is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+        // The key size has been checked by the constructor.
+        match generate_supported_keypair(self.key_size_in_bits) {
+            Ok(v) => v,
+            Err(_) => unreachable!(),
+        }
+    }
+}
+
+/// Implements RSAES-OAEP with SHA-256 as an Ecies instance, where a message
+/// takes at most k - 66 bytes for a key of k bytes.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct WedprRsaOaep {}
+
+impl AlgorithmInfo for WedprRsaOaep {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "rsaes-oaep-sha256",
+            oid: Some("1.2.840.113549.1.1.7"),
+            security_bits: 112,
+            private_key_size: None,
+            public_key_sizes: &[],
+            signature_size: None,
+            output_size: None,
+        }
+    }
+}
+
+impl Ecies for WedprRsaOaep {
+    fn encrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        message: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        let public_key_obj = parse_public_key(public_key.as_ref())?;
+        match public_key_obj.encrypt(
+            &mut OsRng,
+            Oaep::new::<Sha256>(),
+            message.as_ref(),
+        ) {
+            Ok(v) => Ok(v),
+            Err(_) => {
+                wedpr_println!("RSA-OAEP encrypt failed");
+                Err(WedprError::ArgumentError)
+            },
+        }
+    }
+
+    fn decrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        ciphertext: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        let private_key_obj = parse_private_key(private_key.as_ref())?;
+        match private_key_obj
+            .decrypt(Oaep::new::<Sha256>(), ciphertext.as_ref())
+        {
+            Ok(v) => Ok(v),
+            Err(_) => {
+                wedpr_println!("RSA-OAEP decrypt failed");
+                Err(WedprError::DecodeError)
+            },
+        }
+    }
+}
+
+/// Implements RSAES-PKCS1-v1_5 as an Ecies instance, only for protocols
+/// mandating it such as OpenPGP, where a message takes at most k - 11 bytes
+/// for a key of k bytes. WedprRsaOaep is preferred for new applications.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct WedprRsaPkcs1v15Encryption {}
+
+impl AlgorithmInfo for WedprRsaPkcs1v15Encryption {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "rsaes-pkcs1-v1_5",
+            oid: Some("1.2.840.113549.1.1.1"),
+            security_bits: 112,
+            private_key_size: None,
+            public_key_sizes: &[],
+            signature_size: None,
+            output_size: None,
+        }
+    }
+}
+
+impl Ecies for WedprRsaPkcs1v15Encryption {
+    fn encrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        message: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        let public_key_obj = parse_public_key(public_key.as_ref())?;
+        match public_key_obj.encrypt(
+            &mut OsRng,
+            Pkcs1v15Encrypt,
+            message.as_ref(),
+        ) {
+            Ok(v) => Ok(v),
+            Err(_) => {
+                wedpr_println!("RSA-PKCS1-v1_5 encrypt failed");
+                Err(WedprError::ArgumentError)
+            },
+        }
+    }
+
+    fn decrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        ciphertext: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        let private_key_obj = parse_private_key(private_key.as_ref())?;
+        match private_key_obj.decrypt(Pkcs1v15Encrypt, ciphertext.as_ref()) {
+            Ok(v) => Ok(v),
+            Err(_) => {
+                wedpr_println!("RSA-PKCS1-v1_5 decrypt failed");
+                Err(WedprError::DecodeError)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_sha256::WedprSha256;
+    use wedpr_l_utils::{
+        constant::tests::BASE64_ENCODED_TEST_MESSAGE,
+        policy::{with_scoped_policy, SecurityPolicy},
+        traits::Hash,
+    };
+
+    #[test]
+    fn test_rsa() {
+        let (public_key, private_key) =
+            generate_rsa_keypair(DEFAULT_RSA_KEY_SIZE_IN_BITS).unwrap();
+        let (other_public_key, _) = WedprRsaPss::default().generate_keypair();
+        let msg_hash =
+            WedprSha256::default().hash(&BASE64_ENCODED_TEST_MESSAGE);
+        let another_hash = WedprSha256::default().hash("another message");
+
+        let pss = WedprRsaPss::default();
+        let pss_signature = pss.sign(&private_key, &msg_hash).unwrap();
+        assert_eq!(pss_signature.len(), 256);
+        assert!(pss.verify(&public_key, &msg_hash, &pss_signature));
+        assert!(!pss.verify(&public_key, &another_hash, &pss_signature));
+        assert!(!pss.verify(&other_public_key, &msg_hash, &pss_signature));
+        assert!(pss.sign(&private_key[..], &msg_hash[1..]).is_err());
+
+        let pkcs1v15 = WedprRsaPkcs1v15::default();
+        let pkcs1v15_signature = pkcs1v15
+            .sign_deterministic(&private_key, &msg_hash)
+            .unwrap();
+        assert_eq!(
+            pkcs1v15.sign(&private_key, &msg_hash).unwrap(),
+            pkcs1v15_signature
+        );
+        assert!(pkcs1v15.verify(&public_key, &msg_hash, &pkcs1v15_signature));
+        assert!(!pkcs1v15.verify(&public_key, &msg_hash, &pss_signature));
+        assert!(!pss.verify(&public_key, &msg_hash, &pkcs1v15_signature));
+
+        let oaep = WedprRsaOaep::default();
+        let message = BASE64_ENCODED_TEST_MESSAGE.to_vec();
+        let ciphertext = oaep.encrypt(&public_key, &message).unwrap();
+        assert_ne!(oaep.encrypt(&public_key, &message).unwrap(), ciphertext);
+        assert_eq!(oaep.decrypt(&private_key, &ciphertext).unwrap(), message);
+        assert!(oaep.encrypt(&public_key, &vec![0u8; 191]).is_err());
+        let mut tampered_ciphertext = ciphertext.clone();
+        tampered_ciphertext[0] ^= 1;
+        assert!(oaep.decrypt(&private_key, &tampered_ciphertext).is_err());
+
+        let pkcs1v15_encryption = WedprRsaPkcs1v15Encryption::default();
+        let ciphertext =
+            pkcs1v15_encryption.encrypt(&public_key, &message).unwrap();
+        assert_eq!(
+            pkcs1v15_encryption
+                .decrypt(&private_key, &ciphertext)
+                .unwrap(),
+            message
+        );
+        assert!(pkcs1v15_encryption
+            .encrypt(&public_key, &vec![0u8; 246])
+            .is_err());
+
+        assert!(WedprRsaPss::new(1024).is_err());
+        assert!(WedprRsaPkcs1v15::new(3072).is_ok());
+
+        let policy = SecurityPolicy {
+            min_rsa_key_size_in_bits: 3072,
+            ..SecurityPolicy::default()
+        };
+        with_scoped_policy(policy, || {
+            assert!(WedprRsaPss::new(2048).is_err());
+            assert!(generate_rsa_keypair(2048).is_err());
+            let waiver = PolicyWaiver::acknowledge("legacy HSM").unwrap();
+            assert!(WedprRsaPss::new_with_waiver(2048, &waiver).is_ok());
+            assert_eq!(WedprRsaPkcs1v15::default().key_size_in_bits, 3072);
+        });
+    }
+}
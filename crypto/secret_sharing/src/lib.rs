@@ -0,0 +1,321 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Verifiable secret sharing functions, where a dealer splits secrets into
+//! shares with Shamir secret sharing over the Ristretto scalar field, and
+//! publishes Feldman commitments of the polynomial coefficients, so that
+//! every shareholder can verify its share without learning the secrets.
+//! Any `threshold` shares recover the secrets, and fewer shares reveal
+//! nothing about them.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+use curve25519_dalek::{ristretto::RistrettoPoint, scalar::Scalar};
+use wedpr_l_crypto_zkp_utils::{
+    bytes_to_scalar, get_random_scalar, scalar_to_bytes, BASEPOINT_G1,
+};
+use wedpr_l_utils::error::WedprError;
+
+/// Size of the share index in encoded shares.
+const INDEX_SIZE_IN_BYTES: usize = 8;
+/// Size of an encoded scalar.
+const SCALAR_SIZE_IN_BYTES: usize = 32;
+/// Size of the byte chunks of a byte secret, which are always less than the
+/// scalar field order.
+const CHUNK_SIZE_IN_BYTES: usize = 31;
+
+/// Share of a shareholder, with one value for each shared secret.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct SecretShare {
+    /// Index of the shareholder in [1, share_count].
+    pub index: usize,
+    pub values: Vec<Scalar>,
+}
+
+impl SecretShare {
+    /// Encodes the share as its index followed by its values.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::with_capacity(
+            INDEX_SIZE_IN_BYTES + self.values.len() * SCALAR_SIZE_IN_BYTES,
+        );
+        output.extend_from_slice(&(self.index as u64).to_be_bytes());
+        for value in &self.values {
+            output.append(&mut scalar_to_bytes(value));
+        }
+        output
+    }
+
+    /// Decodes a share encoded by encode.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        if input.len() < INDEX_SIZE_IN_BYTES
+            || (input.len() - INDEX_SIZE_IN_BYTES) % SCALAR_SIZE_IN_BYTES != 0
+        {
+            return Err(WedprError::FormatError);
+        }
+        let mut buffer = [0u8; INDEX_SIZE_IN_BYTES];
+        buffer.copy_from_slice(&input[..INDEX_SIZE_IN_BYTES]);
+        let values = input[INDEX_SIZE_IN_BYTES..]
+            .chunks(SCALAR_SIZE_IN_BYTES)
+            .map(decode_scalar)
+            .collect::<Result<Vec<Scalar>, WedprError>>()?;
+        Ok(SecretShare {
+            index: u64::from_be_bytes(buffer) as usize,
+            values,
+        })
+    }
+}
+
+fn decode_scalar(chunk: &[u8]) -> Result<Scalar, WedprError> {
+    let mut bytes = [0u8; SCALAR_SIZE_IN_BYTES];
+    bytes.copy_from_slice(chunk);
+    match Scalar::from_canonical_bytes(bytes) {
+        Some(v) => Ok(v),
+        None => Err(WedprError::FormatError),
+    }
+}
+
+/// Feldman commitments a_m * G1 of the polynomial coefficients a_m of each
+/// shared secret, where the first commitment commits to the secret.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct FeldmanCommitments {
+    pub threshold: usize,
+    pub commitments: Vec<Vec<RistrettoPoint>>,
+}
+
+/// Splits secrets into `share_count` shares, where any `threshold` shares
+/// recover them.
+pub fn split_secrets(
+    secrets: &[Scalar],
+    threshold: usize,
+    share_count: usize,
+) -> Result<(Vec<SecretShare>, FeldmanCommitments), WedprError> {
+    if secrets.is_empty() || threshold == 0 || threshold > share_count {
+        wedpr_println!("Invalid threshold {} of {}", threshold, share_count);
+        return Err(WedprError::ArgumentError);
+    }
+    let polynomials: Vec<Vec<Scalar>> = secrets
+        .iter()
+        .map(|secret| {
+            std::iter::once(*secret)
+                .chain((1..threshold).map(|_| get_random_scalar()))
+                .collect()
+        })
+        .collect();
+    let shares = (1..=share_count)
+        .map(|index| SecretShare {
+            index,
+            values: polynomials
+                .iter()
+                .map(|coefficients| evaluate_polynomial(coefficients, index))
+                .collect(),
+        })
+        .collect();
+    let commitments = FeldmanCommitments {
+        threshold,
+        commitments: polynomials
+            .iter()
+            .map(|coefficients| {
+                coefficients
+                    .iter()
+                    .map(|coefficient| coefficient * *BASEPOINT_G1)
+                    .collect()
+            })
+            .collect(),
+    };
+    Ok((shares, commitments))
+}
+
+fn evaluate_polynomial(coefficients: &[Scalar], index: usize) -> Scalar {
+    let x = Scalar::from(index as u64);
+    coefficients
+        .iter()
+        .rev()
+        .fold(Scalar::zero(), |sum, coefficient| sum * x + coefficient)
+}
+
+/// Verifies a share against the Feldman commitments by checking
+/// value * G1 = sum_m C_m * index^m for each shared secret.
+pub fn verify_share(
+    commitments: &FeldmanCommitments,
+    share: &SecretShare,
+) -> bool {
+    if share.index == 0 || share.values.len() != commitments.commitments.len() {
+        return false;
+    }
+    let x = Scalar::from(share.index as u64);
+    share.values.iter().zip(&commitments.commitments).all(
+        |(value, coefficient_commitments)| {
+            if coefficient_commitments.len() != commitments.threshold {
+                return false;
+            }
+            let expected = coefficient_commitments
+                .iter()
+                .rev()
+                .fold(RistrettoPoint::default(), |sum, commitment| {
+                    sum * x + commitment
+                });
+            value * *BASEPOINT_G1 == expected
+        },
+    )
+}
+
+/// Recovers the secrets from at least `threshold` shares of distinct
+/// indexes by Lagrange interpolation at 0.
+pub fn recover_secrets(
+    shares: &[SecretShare],
+    threshold: usize,
+) -> Result<Vec<Scalar>, WedprError> {
+    let mut selected: Vec<&SecretShare> = Vec::new();
+    for share in shares {
+        if selected.len() == threshold {
+            break;
+        }
+        if share.index == 0 || selected.iter().any(|v| v.index == share.index) {
+            continue;
+        }
+        selected.push(share);
+    }
+    if threshold == 0 || selected.len() < threshold {
+        wedpr_println!("Not enough shares");
+        return Err(WedprError::ArgumentError);
+    }
+    let secret_count = selected[0].values.len();
+    if selected.iter().any(|v| v.values.len() != secret_count) {
+        return Err(WedprError::ArgumentError);
+    }
+    let mut secrets = vec![Scalar::zero(); secret_count];
+    for share in &selected {
+        let xi = Scalar::from(share.index as u64);
+        let mut numerator = Scalar::one();
+        let mut denominator = Scalar::one();
+        for other in &selected {
+            if other.index == share.index {
+                continue;
+            }
+            let xj = Scalar::from(other.index as u64);
+            numerator *= xj;
+            denominator *= xj - xi;
+        }
+        let coefficient = numerator * denominator.invert();
+        for (secret, value) in secrets.iter_mut().zip(&share.values) {
+            *secret += coefficient * value;
+        }
+    }
+    Ok(secrets)
+}
+
+/// Splits a byte secret, e.g. a private key for backup, as by split_secrets,
+/// where the secret length and its 31-byte chunks are shared as scalars.
+pub fn split_bytes(
+    secret: &[u8],
+    threshold: usize,
+    share_count: usize,
+) -> Result<(Vec<SecretShare>, FeldmanCommitments), WedprError> {
+    let secrets: Vec<Scalar> =
+        std::iter::once(Scalar::from(secret.len() as u64))
+            .chain(secret.chunks(CHUNK_SIZE_IN_BYTES).map(|chunk| {
+                let mut bytes = [0u8; SCALAR_SIZE_IN_BYTES];
+                bytes[..chunk.len()].copy_from_slice(chunk);
+                Scalar::from_bits(bytes)
+            }))
+            .collect();
+    split_secrets(&secrets, threshold, share_count)
+}
+
+/// Recovers a byte secret split by split_bytes.
+pub fn recover_bytes(
+    shares: &[SecretShare],
+    threshold: usize,
+) -> Result<Vec<u8>, WedprError> {
+    let secrets = recover_secrets(shares, threshold)?;
+    let length_bytes = secrets[0].to_bytes();
+    if length_bytes[INDEX_SIZE_IN_BYTES..].iter().any(|v| *v != 0) {
+        return Err(WedprError::DecodeError);
+    }
+    let mut buffer = [0u8; INDEX_SIZE_IN_BYTES];
+    buffer.copy_from_slice(&length_bytes[..INDEX_SIZE_IN_BYTES]);
+    let length = u64::from_le_bytes(buffer) as usize;
+    let chunks = &secrets[1..];
+    // The chunk count must be the minimal one for the length.
+    let capacity = chunks.len() * CHUNK_SIZE_IN_BYTES;
+    if length > capacity || length + CHUNK_SIZE_IN_BYTES <= capacity {
+        wedpr_println!("Recovered secret length is invalid");
+        return Err(WedprError::DecodeError);
+    }
+    let mut secret = Vec::with_capacity(chunks.len() * CHUNK_SIZE_IN_BYTES);
+    for chunk in chunks {
+        let bytes = chunk.to_bytes();
+        if bytes[CHUNK_SIZE_IN_BYTES] != 0 {
+            return Err(WedprError::DecodeError);
+        }
+        secret.extend_from_slice(&bytes[..CHUNK_SIZE_IN_BYTES]);
+    }
+    secret.truncate(length);
+    Ok(secret)
+}
+
+/// Converts a 32-byte secret to a scalar for split_secrets, which fails if
+/// it is not a canonical scalar.
+pub fn secret_to_scalar(secret: &[u8]) -> Result<Scalar, WedprError> {
+    let scalar = bytes_to_scalar(secret)?;
+    if scalar_to_bytes(&scalar.reduce()) != secret {
+        return Err(WedprError::FormatError);
+    }
+    Ok(scalar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_sharing() {
+        let secret = get_random_scalar();
+        assert_eq!(
+            secret_to_scalar(&scalar_to_bytes(&secret)).unwrap(),
+            secret
+        );
+        let (shares, commitments) = split_secrets(&[secret], 3, 5).unwrap();
+        assert_eq!(commitments.commitments[0][0], secret * *BASEPOINT_G1);
+        for share in &shares {
+            assert!(verify_share(&commitments, share));
+            assert_eq!(&SecretShare::decode(&share.encode()).unwrap(), share);
+        }
+        let mut tampered = shares[0].clone();
+        tampered.values[0] += Scalar::one();
+        assert!(!verify_share(&commitments, &tampered));
+
+        assert_eq!(recover_secrets(&shares[2..], 3).unwrap(), vec![secret]);
+        assert_eq!(
+            recover_secrets(
+                &[shares[4].clone(), shares[0].clone(), shares[2].clone()],
+                3
+            )
+            .unwrap(),
+            vec![secret]
+        );
+        assert_ne!(
+            recover_secrets(
+                &[tampered, shares[1].clone(), shares[2].clone()],
+                3
+            )
+            .unwrap(),
+            vec![secret]
+        );
+        // Duplicated shares do not count.
+        assert!(recover_secrets(
+            &[shares[0].clone(), shares[0].clone(), shares[1].clone()],
+            3
+        )
+        .is_err());
+        assert!(split_secrets(&[secret], 6, 5).is_err());
+
+        let key: Vec<u8> = (0..40u8).collect();
+        let (shares, commitments) = split_bytes(&key, 2, 3).unwrap();
+        assert!(shares.iter().all(|v| verify_share(&commitments, v)));
+        assert_eq!(recover_bytes(&shares[1..], 2).unwrap(), key);
+        assert!(recover_bytes(&shares[..1], 2).is_err());
+        let (shares, _) = split_bytes(&[0xffu8; 32], 2, 2).unwrap();
+        assert_eq!(recover_bytes(&shares, 2).unwrap(), vec![0xffu8; 32]);
+    }
+}
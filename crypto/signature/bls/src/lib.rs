@@ -0,0 +1,397 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! BLS signature functions over BLS12-381, following the proof-of-possession
+//! scheme of the IETF BLS signature draft with public keys in G1 and
+//! signatures in G2, as used by Ethereum 2.0.
+//!
+//! Signatures of the same message by many validators can be aggregated into
+//! one signature of constant size, and verified against the aggregated
+//! public key. Against rogue key attacks, aggregating public keys is only
+//! safe for keys whose proofs of possession have been verified.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+use blst::{
+    min_pk::{
+        AggregatePublicKey, AggregateSignature, PublicKey, SecretKey,
+        Signature as BlsSignature,
+    },
+    BLST_ERROR,
+};
+use rand::RngCore;
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    error::WedprError,
+    policy::check_global_algorithm,
+    traits::{AlgorithmInfo, Signature},
+};
+
+/// Domain separation tag of signatures.
+const BLS_SIGNATURE_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+/// Domain separation tag of proofs of possession.
+const BLS_POP_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+const PRIVATE_KEY_SIZE_IN_BYTES: usize = 32;
+const PUBLIC_KEY_SIZE_IN_BYTES: usize = 48;
+const SIGNATURE_SIZE_IN_BYTES: usize = 96;
+
+/// Implements BLS12-381 as a Signature instance, whose public keys and
+/// signatures are in the compressed form.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct WedprBls12381 {}
+
+impl AlgorithmInfo for WedprBls12381 {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "bls12-381",
+            oid: None,
+            security_bits: 128,
+            private_key_size: Some(PRIVATE_KEY_SIZE_IN_BYTES),
+            public_key_sizes: &[PUBLIC_KEY_SIZE_IN_BYTES],
+            signature_size: Some(SIGNATURE_SIZE_IN_BYTES),
+            output_size: None,
+        }
+    }
+}
+
+impl Signature for WedprBls12381 {
+    fn sign<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        check_global_algorithm::<Self>()?;
+        let secret_key = parse_private_key(private_key.as_ref())?;
+        Ok(secret_key
+            .sign(msg_hash.as_ref(), BLS_SIGNATURE_DST, &[])
+            .compress()
+            .to_vec())
+    }
+
+    /// Signs a message hash, where BLS signatures are always deterministic.
+    fn sign_deterministic<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        self.sign(private_key, msg_hash)
+    }
+
+    fn verify<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        msg_hash: &T,
+        signature: &T,
+    ) -> bool {
+        if check_global_algorithm::<Self>().is_err() {
+            return false;
+        }
+        verify_with_dst(
+            public_key.as_ref(),
+            msg_hash.as_ref(),
+            signature.as_ref(),
+            BLS_SIGNATURE_DST,
+        )
+    }
+
+    fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+        let mut ikm = [0u8; PRIVATE_KEY_SIZE_IN_BYTES];
+        rand::thread_rng().fill_bytes(&mut ikm);
+        // Key generation only fails for key materials shorter than 32 bytes.
+        let secret_key = match SecretKey::key_gen(&ikm, &[]) {
+            Ok(v) => v,
+            Err(_) => unreachable!(),
+        };
+        (
+            secret_key.sk_to_pk().compress().to_vec(),
+            secret_key.to_bytes().to_vec(),
+        )
+    }
+}
+
+impl WedprBls12381 {
+    /// Derives the public key of a private key.
+    pub fn derive_public_key(
+        self,
+        private_key: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        let secret_key = parse_private_key(private_key)?;
+        Ok(secret_key.sk_to_pk().compress().to_vec())
+    }
+
+    /// Proves the possession of the private key of a public key, by signing
+    /// the public key under a separate domain.
+    pub fn prove_possession(
+        self,
+        private_key: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        let secret_key = parse_private_key(private_key)?;
+        let public_key = secret_key.sk_to_pk().compress();
+        Ok(secret_key
+            .sign(&public_key, BLS_POP_DST, &[])
+            .compress()
+            .to_vec())
+    }
+
+    /// Verifies the proof of possession of a public key.
+    pub fn verify_possession(self, public_key: &[u8], proof: &[u8]) -> bool {
+        verify_with_dst(public_key, public_key, proof, BLS_POP_DST)
+    }
+
+    /// Aggregates signatures of the same or different messages.
+    pub fn aggregate_signatures<S: AsRef<[u8]>>(
+        self,
+        signatures: &[S],
+    ) -> Result<Vec<u8>, WedprError> {
+        let signature_objs = signatures
+            .iter()
+            .map(|v| parse_signature(v.as_ref()))
+            .collect::<Result<Vec<BlsSignature>, WedprError>>()?;
+        let signature_refs: Vec<&BlsSignature> =
+            signature_objs.iter().collect();
+        match AggregateSignature::aggregate(&signature_refs, false) {
+            Ok(v) => Ok(v.to_signature().compress().to_vec()),
+            Err(_) => {
+                wedpr_println!("Aggregating signatures failed");
+                Err(WedprError::ArgumentError)
+            },
+        }
+    }
+
+    /// Aggregates public keys whose proofs of possession have been verified,
+    /// and the aggregated public key verifies the aggregated signature of the
+    /// same message.
+    pub fn aggregate_public_keys<S: AsRef<[u8]>>(
+        self,
+        public_keys: &[S],
+    ) -> Result<Vec<u8>, WedprError> {
+        let public_key_objs = parse_public_keys(public_keys)?;
+        let public_key_refs: Vec<&PublicKey> = public_key_objs.iter().collect();
+        match AggregatePublicKey::aggregate(&public_key_refs, false) {
+            Ok(v) => Ok(v.to_public_key().compress().to_vec()),
+            Err(_) => {
+                wedpr_println!("Aggregating public keys failed");
+                Err(WedprError::ArgumentError)
+            },
+        }
+    }
+
+    /// Verifies an aggregated signature of the same message by public keys
+    /// whose proofs of possession have been verified.
+    pub fn fast_aggregate_verify<S: AsRef<[u8]>>(
+        self,
+        public_keys: &[S],
+        msg_hash: &[u8],
+        aggregated_signature: &[u8],
+    ) -> bool {
+        if check_global_algorithm::<Self>().is_err() {
+            return false;
+        }
+        let (public_key_objs, signature_obj) = match (
+            parse_public_keys(public_keys),
+            parse_signature(aggregated_signature),
+        ) {
+            (Ok(public_key_objs), Ok(signature_obj)) => {
+                (public_key_objs, signature_obj)
+            },
+            _ => return false,
+        };
+        let public_key_refs: Vec<&PublicKey> = public_key_objs.iter().collect();
+        signature_obj.fast_aggregate_verify(
+            true,
+            msg_hash,
+            BLS_SIGNATURE_DST,
+            &public_key_refs,
+        ) == BLST_ERROR::BLST_SUCCESS
+    }
+
+    /// Verifies an aggregated signature of distinct messages, where the i-th
+    /// public key signs the i-th message hash.
+    pub fn aggregate_verify<S: AsRef<[u8]>>(
+        self,
+        public_keys: &[S],
+        msg_hashes: &[S],
+        aggregated_signature: &[u8],
+    ) -> bool {
+        if check_global_algorithm::<Self>().is_err()
+            || public_keys.is_empty()
+            || public_keys.len() != msg_hashes.len()
+        {
+            return false;
+        }
+        let (public_key_objs, signature_obj) = match (
+            parse_public_keys(public_keys),
+            parse_signature(aggregated_signature),
+        ) {
+            (Ok(public_key_objs), Ok(signature_obj)) => {
+                (public_key_objs, signature_obj)
+            },
+            _ => return false,
+        };
+        let public_key_refs: Vec<&PublicKey> = public_key_objs.iter().collect();
+        let msg_hash_refs: Vec<&[u8]> =
+            msg_hashes.iter().map(|v| v.as_ref()).collect();
+        signature_obj.aggregate_verify(
+            true,
+            &msg_hash_refs,
+            BLS_SIGNATURE_DST,
+            &public_key_refs,
+            false,
+        ) == BLST_ERROR::BLST_SUCCESS
+    }
+}
+
+fn parse_private_key(private_key: &[u8]) -> Result<SecretKey, WedprError> {
+    match SecretKey::from_bytes(private_key) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Parsing private key failed");
+            Err(WedprError::FormatError)
+        },
+    }
+}
+
+/// Parses a public key, which also rejects the identity and points out of
+/// the prime-order subgroup.
+fn parse_public_key(public_key: &[u8]) -> Result<PublicKey, WedprError> {
+    match PublicKey::key_validate(public_key) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Parsing public key failed");
+            Err(WedprError::FormatError)
+        },
+    }
+}
+
+fn parse_public_keys<S: AsRef<[u8]>>(
+    public_keys: &[S],
+) -> Result<Vec<PublicKey>, WedprError> {
+    public_keys
+        .iter()
+        .map(|v| parse_public_key(v.as_ref()))
+        .collect()
+}
+
+/// Parses a signature, which also rejects points out of the prime-order
+/// subgroup.
+fn parse_signature(signature: &[u8]) -> Result<BlsSignature, WedprError> {
+    match BlsSignature::sig_validate(signature, true) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Parsing signature failed");
+            Err(WedprError::FormatError)
+        },
+    }
+}
+
+fn verify_with_dst(
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+    dst: &[u8],
+) -> bool {
+    match (parse_public_key(public_key), parse_signature(signature)) {
+        (Ok(public_key_obj), Ok(signature_obj)) => {
+            signature_obj.verify(
+                false,
+                message,
+                dst,
+                &[],
+                &public_key_obj,
+                false,
+            ) == BLST_ERROR::BLST_SUCCESS
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+    use wedpr_l_utils::{
+        constant::tests::BASE64_ENCODED_TEST_MESSAGE, traits::Hash,
+    };
+
+    #[test]
+    fn test_bls() {
+        let bls = WedprBls12381::default();
+        let (public_key, private_key) = bls.generate_keypair();
+        let msg_hash = BASE64_ENCODED_TEST_MESSAGE.to_vec();
+        let signature = bls.sign(&private_key, &msg_hash).unwrap();
+        assert!(bls.verify(&public_key, &msg_hash, &signature));
+        assert!(!bls.verify(&public_key, &msg_hash[1..].to_vec(), &signature));
+        assert_eq!(bls.derive_public_key(&private_key).unwrap(), public_key);
+        assert!(WedprBls12381::algorithm_info()
+            .check_signature(&signature)
+            .is_ok());
+
+        // Attestations of validators on the same block.
+        let keypairs: Vec<(Vec<u8>, Vec<u8>)> =
+            (0..4).map(|_| bls.generate_keypair()).collect();
+        let public_keys: Vec<Vec<u8>> =
+            keypairs.iter().map(|(v, _)| v.clone()).collect();
+        for (public_key, private_key) in &keypairs {
+            let proof = bls.prove_possession(private_key).unwrap();
+            assert!(bls.verify_possession(public_key, &proof));
+            assert!(!bls.verify_possession(&msg_hash, &proof));
+        }
+        let block_hash = WedprKeccak256::default().hash("block 100");
+        let signatures: Vec<Vec<u8>> = keypairs
+            .iter()
+            .map(|(_, v)| bls.sign(v, &block_hash).unwrap())
+            .collect();
+        let aggregated_signature =
+            bls.aggregate_signatures(&signatures).unwrap();
+        assert_eq!(aggregated_signature.len(), SIGNATURE_SIZE_IN_BYTES);
+        assert!(bls.fast_aggregate_verify(
+            &public_keys,
+            &block_hash,
+            &aggregated_signature
+        ));
+        assert!(!bls.fast_aggregate_verify(
+            &public_keys[1..],
+            &block_hash,
+            &aggregated_signature
+        ));
+        let aggregated_public_key =
+            bls.aggregate_public_keys(&public_keys).unwrap();
+        assert!(bls.verify(
+            &aggregated_public_key,
+            &block_hash,
+            &aggregated_signature
+        ));
+        // Neither a signature nor a proof of another key is a proof of
+        // possession.
+        assert!(!bls.verify_possession(&public_keys[0], &signatures[0]));
+        let other_proof = bls.prove_possession(&keypairs[1].1).unwrap();
+        assert!(!bls.verify_possession(&public_keys[0], &other_proof));
+
+        // Signatures of distinct messages.
+        let msg_hashes: Vec<Vec<u8>> = (0..4)
+            .map(|i| WedprKeccak256::default().hash(&format!("tx {}", i)))
+            .collect();
+        let signatures: Vec<Vec<u8>> = keypairs
+            .iter()
+            .zip(&msg_hashes)
+            .map(|((_, private_key), msg_hash)| {
+                bls.sign(private_key, msg_hash).unwrap()
+            })
+            .collect();
+        let aggregated_signature =
+            bls.aggregate_signatures(&signatures).unwrap();
+        assert!(bls.aggregate_verify(
+            &public_keys,
+            &msg_hashes,
+            &aggregated_signature
+        ));
+        assert!(!bls.aggregate_verify(
+            &public_keys[..3],
+            &msg_hashes[..3],
+            &aggregated_signature
+        ));
+        let empty: &[Vec<u8>] = &[];
+        assert!(bls.aggregate_signatures(empty).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Ed25519 signature functions.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+pub mod variants;
+
+use ed25519_dalek::{
+    ExpandedSecretKey, PublicKey, SecretKey, Signature as Ed25519Signature,
+    PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH, SIGNATURE_LENGTH,
+};
+use rand::RngCore;
+use std::convert::TryFrom;
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    error::WedprError,
+    policy::check_global_algorithm,
+    traits::{AlgorithmInfo, Signature},
+};
+
+/// Implements Ed25519 of RFC 8032 as a Signature instance, which signs the
+/// message hash as the message, so that inputs of any length are accepted.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct WedprEd25519 {}
+
+impl AlgorithmInfo for WedprEd25519 {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "ed25519",
+            oid: Some("1.3.101.112"),
+            security_bits: 128,
+            private_key_size: Some(SECRET_KEY_LENGTH),
+            public_key_sizes: &[PUBLIC_KEY_LENGTH],
+            signature_size: Some(SIGNATURE_LENGTH),
+            output_size: None,
+        }
+    }
+}
+
+impl Signature for WedprEd25519 {
+    fn sign<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        check_global_algorithm::<Self>()?;
+        let secret_key = match SecretKey::from_bytes(private_key.as_ref()) {
+            Ok(v) => v,
+            Err(_) => {
+                wedpr_println!("Parsing private key failed");
+                return Err(WedprError::FormatError);
+            },
+        };
+        let public_key = PublicKey::from(&secret_key);
+        let signature = ExpandedSecretKey::from(&secret_key)
+            .sign(msg_hash.as_ref(), &public_key);
+        Ok(signature.to_bytes().to_vec())
+    }
+
+    /// Signs a message hash, where Ed25519 always derives the nonce from the
+    /// private key and the message.
+    fn sign_deterministic<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        self.sign(private_key, msg_hash)
+    }
+
+    fn verify<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        msg_hash: &T,
+        signature: &T,
+    ) -> bool {
+        if check_global_algorithm::<Self>().is_err() {
+            return false;
+        }
+        let public_key_obj = match PublicKey::from_bytes(public_key.as_ref()) {
+            Ok(v) => v,
+            Err(_) => {
+                wedpr_println!("Parsing public key failed");
+                return false;
+            },
+        };
+        let signature_obj = match Ed25519Signature::try_from(signature.as_ref())
+        {
+            Ok(v) => v,
+            Err(_) => {
+                wedpr_println!("Parsing signature failed");
+                return false;
+            },
+        };
+        // Strict verification rejects malleable signatures and weak keys.
+        public_key_obj
+            .verify_strict(msg_hash.as_ref(), &signature_obj)
+            .is_ok()
+    }
+
+    fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+        let mut secret_key_bytes = [0u8; SECRET_KEY_LENGTH];
+        rand::thread_rng().fill_bytes(&mut secret_key_bytes);
+        // Any 32 bytes are a valid Ed25519 secret key.
+        let secret_key = match SecretKey::from_bytes(&secret_key_bytes) {
+            Ok(v) => v,
+            Err(_) => unreachable!(),
+        };
+        (
+            PublicKey::from(&secret_key).to_bytes().to_vec(),
+            secret_key_bytes.to_vec(),
+        )
+    }
+}
+
+impl WedprEd25519 {
+    /// Derives the public key of a private key.
+    pub fn derive_public_key(
+        self,
+        private_key: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        match SecretKey::from_bytes(private_key) {
+            Ok(v) => Ok(PublicKey::from(&v).to_bytes().to_vec()),
+            Err(_) => {
+                wedpr_println!("Parsing private key failed");
+                Err(WedprError::FormatError)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_common_coder_hex::WedprHex;
+    use wedpr_l_utils::{
+        constant::tests::BASE64_ENCODED_TEST_MESSAGE, traits::Coder,
+    };
+
+    #[test]
+    fn test_ed25519() {
+        let ed25519 = WedprEd25519::default();
+        let (public_key, private_key) = ed25519.generate_keypair();
+        let msg_hash = BASE64_ENCODED_TEST_MESSAGE.to_vec();
+        let signature = ed25519.sign(&private_key, &msg_hash).unwrap();
+        assert!(ed25519.verify(&public_key, &msg_hash, &signature));
+        assert!(!ed25519.verify(
+            &public_key,
+            &msg_hash[1..].to_vec(),
+            &signature
+        ));
+        assert!(ed25519.sign(&private_key[1..], &msg_hash[..]).is_err());
+
+        // Test vector 1 of RFC 8032.
+        let hex = WedprHex::default();
+        let private_key = hex
+            .decode(
+                &[
+                    "9d61b19deffd5a60ba844af492ec2cc4",
+                    "4449c5697b326919703bac031cae7f60",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let public_key = hex
+            .decode(
+                &[
+                    "d75a980182b10ab7d54bfed3c964073a",
+                    "0ee172f3daa62325af021a68f707511a",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let expected_signature = hex
+            .decode(
+                &[
+                    "e5564300c360ac729086e2cc806e828a",
+                    "84877f1eb8e5d974d873e06522490155",
+                    "5fb8821590a33bacc61e39701cf9b46b",
+                    "d25bf5f0595bbe24655141438e7a100b",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let empty: &[u8] = &[];
+        let signature =
+            ed25519.sign_deterministic(&private_key[..], empty).unwrap();
+        assert_eq!(signature, expected_signature);
+        assert!(ed25519.verify(&public_key[..], empty, &signature[..]));
+        assert_eq!(
+            ed25519.derive_public_key(&private_key).unwrap(),
+            public_key
+        );
+        assert!(WedprEd25519::algorithm_info()
+            .check_signature(&signature)
+            .is_ok());
+    }
+}
@@ -0,0 +1,325 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Ed25519ph and Ed25519ctx variants of RFC 8032, whose signed content is
+//! prefixed by dom2(phflag, context), where Ed25519ph signs the SHA-512
+//! digest of a message and Ed25519ctx signs a message under a non-empty
+//! context for domain separation.
+
+use crate::WedprEd25519;
+use curve25519_dalek::{
+    constants::ED25519_BASEPOINT_TABLE,
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::Scalar,
+};
+use sha2::{Digest, Sha512};
+use wedpr_l_utils::error::WedprError;
+
+/// Prefix of dom2 defined by RFC 8032.
+const DOM2_PREFIX: &[u8] = b"SigEd25519 no Ed25519 collisions";
+/// Maximum size of a context in bytes.
+pub const MAX_CONTEXT_SIZE_IN_BYTES: usize = 255;
+/// Size of a SHA-512 digest signed by Ed25519ph.
+pub const PREHASH_SIZE_IN_BYTES: usize = 64;
+
+const KEY_SIZE_IN_BYTES: usize = 32;
+const SIGNATURE_SIZE_IN_BYTES: usize = 64;
+
+/// Computes the SHA-512 digest of a message for Ed25519ph, which can be
+/// computed incrementally by callers for large messages.
+pub fn prehash_message(message: &[u8]) -> Vec<u8> {
+    Sha512::digest(message).to_vec()
+}
+
+impl WedprEd25519 {
+    /// Signs a SHA-512 digest by Ed25519ph under a context, which may be
+    /// empty.
+    pub fn sign_prehashed(
+        &self,
+        private_key: &[u8],
+        prehashed_message: &[u8],
+        context: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        check_prehash(prehashed_message)?;
+        sign_with_dom2(private_key, 1, context, prehashed_message)
+    }
+
+    /// Verifies an Ed25519ph signature of a SHA-512 digest under a context.
+    pub fn verify_prehashed(
+        &self,
+        public_key: &[u8],
+        prehashed_message: &[u8],
+        context: &[u8],
+        signature: &[u8],
+    ) -> bool {
+        check_prehash(prehashed_message).is_ok()
+            && verify_with_dom2(
+                public_key,
+                1,
+                context,
+                prehashed_message,
+                signature,
+            )
+    }
+
+    /// Signs a message by Ed25519ctx under a non-empty context.
+    pub fn sign_with_context(
+        &self,
+        private_key: &[u8],
+        message: &[u8],
+        context: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        if context.is_empty() {
+            wedpr_println!("Ed25519ctx requires a non-empty context");
+            return Err(WedprError::ArgumentError);
+        }
+        sign_with_dom2(private_key, 0, context, message)
+    }
+
+    /// Verifies an Ed25519ctx signature of a message under a non-empty
+    /// context.
+    pub fn verify_with_context(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        context: &[u8],
+        signature: &[u8],
+    ) -> bool {
+        !context.is_empty()
+            && verify_with_dom2(public_key, 0, context, message, signature)
+    }
+}
+
+fn check_prehash(prehashed_message: &[u8]) -> Result<(), WedprError> {
+    if prehashed_message.len() != PREHASH_SIZE_IN_BYTES {
+        wedpr_println!("Ed25519ph requires a SHA-512 digest");
+        return Err(WedprError::ArgumentError);
+    }
+    Ok(())
+}
+
+/// Computes SHA-512(dom2(phflag, context) || inputs) as a scalar.
+fn hash_with_dom2(phflag: u8, context: &[u8], inputs: &[&[u8]]) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.input(DOM2_PREFIX);
+    hasher.input(&[phflag, context.len() as u8]);
+    hasher.input(context);
+    for input in inputs {
+        hasher.input(input);
+    }
+    let mut digest = [0u8; 64];
+    digest.copy_from_slice(&hasher.result());
+    Scalar::from_bytes_mod_order_wide(&digest)
+}
+
+fn sign_with_dom2(
+    private_key: &[u8],
+    phflag: u8,
+    context: &[u8],
+    message: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    if private_key.len() != KEY_SIZE_IN_BYTES {
+        wedpr_println!("Parsing private key failed");
+        return Err(WedprError::FormatError);
+    }
+    if context.len() > MAX_CONTEXT_SIZE_IN_BYTES {
+        wedpr_println!("Ed25519 context is too long");
+        return Err(WedprError::ArgumentError);
+    }
+    // Expands the private key into the clamped scalar a and the prefix.
+    let expanded_key = Sha512::digest(private_key);
+    let mut scalar_bytes = [0u8; 32];
+    scalar_bytes.copy_from_slice(&expanded_key[..32]);
+    scalar_bytes[0] &= 248;
+    scalar_bytes[31] &= 127;
+    scalar_bytes[31] |= 64;
+    let secret_scalar = Scalar::from_bits(scalar_bytes);
+    let prefix = &expanded_key[32..];
+    let public_key = (&secret_scalar * &ED25519_BASEPOINT_TABLE).compress();
+
+    let nonce = hash_with_dom2(phflag, context, &[prefix, message]);
+    let nonce_point = (&nonce * &ED25519_BASEPOINT_TABLE).compress();
+    let challenge = hash_with_dom2(phflag, context, &[
+        &nonce_point.as_bytes()[..],
+        &public_key.as_bytes()[..],
+        message,
+    ]);
+    let s = &(&challenge * &secret_scalar) + &nonce;
+    let mut signature = nonce_point.as_bytes().to_vec();
+    signature.extend_from_slice(s.as_bytes());
+    Ok(signature)
+}
+
+fn decompress_point(bytes: &[u8]) -> Option<EdwardsPoint> {
+    let mut point_bytes = [0u8; 32];
+    point_bytes.copy_from_slice(bytes);
+    // Small order points are rejected like strict verification.
+    match CompressedEdwardsY(point_bytes).decompress() {
+        Some(v) if !v.is_small_order() => Some(v),
+        _ => None,
+    }
+}
+
+fn verify_with_dom2(
+    public_key: &[u8],
+    phflag: u8,
+    context: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> bool {
+    if public_key.len() != KEY_SIZE_IN_BYTES
+        || signature.len() != SIGNATURE_SIZE_IN_BYTES
+        || context.len() > MAX_CONTEXT_SIZE_IN_BYTES
+    {
+        wedpr_println!("Invalid Ed25519 verification inputs");
+        return false;
+    }
+    let (public_key_point, nonce_point) = match (
+        decompress_point(public_key),
+        decompress_point(&signature[..32]),
+    ) {
+        (Some(a), Some(r)) => (a, r),
+        _ => return false,
+    };
+    let mut s_bytes = [0u8; 32];
+    s_bytes.copy_from_slice(&signature[32..]);
+    // Non-canonical s is rejected against malleability.
+    let s = match Scalar::from_canonical_bytes(s_bytes) {
+        Some(v) => v,
+        None => return false,
+    };
+    let challenge = hash_with_dom2(phflag, context, &[
+        &signature[..32],
+        public_key,
+        message,
+    ]);
+    // Checks R = sB - kA.
+    let expected_nonce_point =
+        EdwardsPoint::vartime_double_scalar_mul_basepoint(
+            &challenge,
+            &-public_key_point,
+            &s,
+        );
+    expected_nonce_point == nonce_point
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_common_coder_hex::WedprHex;
+    use wedpr_l_utils::traits::{Coder, Signature};
+
+    #[test]
+    fn test_ed25519_variants() {
+        let ed25519 = WedprEd25519::default();
+        let hex = WedprHex::default();
+
+        // Ed25519ph test vector of RFC 8032.
+        let private_key = hex
+            .decode(
+                &[
+                    "833fe62409237b9d62ec77587520911e",
+                    "9a759cec1d19755b7da901b96dca3d42",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let public_key = hex
+            .decode(
+                &[
+                    "ec172b93ad5e563bf4932c70e1245034",
+                    "c35467ef2efd4d64ebf819683467e2bf",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let expected_signature = hex
+            .decode(
+                &[
+                    "98a70222f0b8121aa9d30f813d683f80",
+                    "9e462b469c7ff87639499bb94e6dae41",
+                    "31f85042463c2a355a2003d062adf5aa",
+                    "a10b8c61e636062aaad11c2a26083406",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let prehashed_message = prehash_message(b"abc");
+        let signature = ed25519
+            .sign_prehashed(&private_key, &prehashed_message, &[])
+            .unwrap();
+        assert_eq!(signature, expected_signature);
+        assert!(ed25519.verify_prehashed(
+            &public_key,
+            &prehashed_message,
+            &[],
+            &signature
+        ));
+        assert!(!ed25519.verify_prehashed(
+            &public_key,
+            &prehash_message(b"abd"),
+            &[],
+            &signature
+        ));
+        // Ed25519ph signatures are not valid pure Ed25519 signatures.
+        assert!(!ed25519.verify(
+            &public_key[..],
+            &prehashed_message[..],
+            &signature[..]
+        ));
+        assert!(ed25519.sign_prehashed(&private_key, b"abc", &[]).is_err());
+
+        // Ed25519ctx test vector of RFC 8032.
+        let private_key = hex
+            .decode(
+                &[
+                    "0305334e381af78f141cb666f6199f57",
+                    "bc3495335a256a95bd2a55bf546663f6",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let public_key = hex
+            .decode(
+                &[
+                    "dfc9425e4f968f7f0c29f0259cf5f9ae",
+                    "d6851c2bb4ad8bfb860cfee0ab248292",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let message = hex.decode("f726936d19c800494e3fdaff20b276a8").unwrap();
+        let expected_signature = hex
+            .decode(
+                &[
+                    "55a4cc2f70a54e04288c5f4cd1e45a7b",
+                    "b520b36292911876cada7323198dd87a",
+                    "8b36950b95130022907a7fb7c4e9b2d5",
+                    "f6cca685a587b4b21f4b888e4e7edb0d",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let signature = ed25519
+            .sign_with_context(&private_key, &message, b"foo")
+            .unwrap();
+        assert_eq!(signature, expected_signature);
+        assert!(ed25519.verify_with_context(
+            &public_key,
+            &message,
+            b"foo",
+            &signature
+        ));
+        assert!(!ed25519.verify_with_context(
+            &public_key,
+            &message,
+            b"bar",
+            &signature
+        ));
+        assert!(ed25519
+            .sign_with_context(&private_key, &message, &[])
+            .is_err());
+        assert!(ed25519
+            .sign_with_context(&private_key, &message, &[0u8; 256])
+            .is_err());
+    }
+}
@@ -0,0 +1,862 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! CMS (PKCS#7) functions of RFC 5652, including SignedData creation and
+//! verification, and EnvelopedData encryption and decryption, built on
+//! replaceable Signature, Hash, Ecies and BlockCipher algorithms.
+
+use crate::{
+    der::{
+        context_tag, decode_ecdsa_signature, decode_u64,
+        encode_ecdsa_signature, encode_oid_content, encode_sequence,
+        encode_tlv, encode_u64, DerReader, TAG_INTEGER, TAG_OCTET_STRING,
+        TAG_OID, TAG_SEQUENCE, TAG_SET,
+    },
+    timestamp::{OID_MESSAGE_DIGEST, OID_SHA256, OID_SIGNED_DATA},
+};
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{AlgorithmInfo, BlockCipher, Ecies, Hash, Signature},
+};
+
+/// DER content of OID 1.2.840.113549.1.7.1 (CMS data).
+pub const OID_DATA: &[u8] =
+    &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x01];
+/// DER content of OID 1.2.840.113549.1.7.3 (CMS enveloped data).
+pub const OID_ENVELOPED_DATA: &[u8] =
+    &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x03];
+/// DER content of OID 1.2.840.113549.1.9.3 (content type attribute).
+pub const OID_CONTENT_TYPE: &[u8] =
+    &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x03];
+/// DER content of OID 1.2.840.113549.1.1.7 (RSAES-OAEP).
+const OID_RSAES_OAEP: &[u8] =
+    &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x07];
+/// DER content of OID 1.2.840.113549.1.1.8 (MGF1).
+const OID_MGF1: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x08];
+/// DER content of OID 1.2.840.10045.4.3.2 (ecdsa-with-SHA256).
+const OID_ECDSA_WITH_SHA256: &[u8] =
+    &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+/// DER content of OID 1.2.156.10197.1.501 (SM2-with-SM3).
+const OID_SM2_WITH_SM3: &[u8] =
+    &[0x2a, 0x81, 0x1c, 0xcf, 0x55, 0x01, 0x83, 0x75];
+
+/// Tag of an implicit primitive context-specific field numbered 0.
+const TAG_IMPLICIT_PRIMITIVE_0: u8 = 0x80;
+/// Version of SignerInfo and SignedData identified by a subject key
+/// identifier.
+const CMS_SIGNER_VERSION: u64 = 3;
+/// Version of KeyTransRecipientInfo and EnvelopedData identified by a
+/// subject key identifier.
+const CMS_RECIPIENT_VERSION: u64 = 2;
+/// Size of the r and s scalars of ECDSA and SM2 signatures.
+const ECDSA_SCALAR_SIZE_IN_BYTES: usize = 32;
+/// Size of recoverable signatures r || s || v, whose recovery id v is not
+/// kept in DER signatures.
+const RECOVERABLE_SIGNATURE_SIZE_IN_BYTES: usize = 65;
+
+/// Signer parsed from a CMS SignerInfo.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct CmsSignerInfo {
+    /// Subject key identifier, or the DER IssuerAndSerialNumber of a signer
+    /// identified by its certificate.
+    pub signer_identifier: Vec<u8>,
+    /// DER content of the digest algorithm OID.
+    pub digest_algorithm: Vec<u8>,
+    /// DER content of the signature algorithm OID.
+    pub signature_algorithm: Vec<u8>,
+    /// DER encoding of signed attributes re-tagged as a SET, if present.
+    pub signed_attributes: Option<Vec<u8>>,
+    /// The content digest declared in signed attributes, if present.
+    pub message_digest: Option<Vec<u8>>,
+    pub signature: Vec<u8>,
+}
+
+/// CMS SignedData parsed from a ContentInfo.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct CmsSignedData {
+    /// Encapsulated content, which is None for a detached signature.
+    pub content: Option<Vec<u8>>,
+    pub signer_infos: Vec<CmsSignerInfo>,
+}
+
+/// Recipient of CMS EnvelopedData.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct CmsRecipient {
+    /// Subject key identifier of the recipient certificate.
+    pub key_identifier: Vec<u8>,
+    pub public_key: Vec<u8>,
+}
+
+/// Creates a DER ContentInfo of SignedData for the content, signed by one
+/// signer identified by its subject key identifier. The signature covers the
+/// content type and message digest attributes, and the content is left out
+/// of the output if detached. Secp256k1 and secp256r1 sign as
+/// ecdsa-with-SHA256 and SM2 as SM2-with-SM3 with DER ECDSA-Sig-Value
+/// signatures, where SM2 and Ed25519 sign the attributes themselves instead
+/// of their hash, and SM2 uses the default ID 1234567812345678.
+pub fn create_signed_data<
+    S: Signature + AlgorithmInfo,
+    H: Hash + AlgorithmInfo,
+>(
+    signature: &S,
+    hash: &H,
+    private_key: &[u8],
+    key_identifier: &[u8],
+    content: &[u8],
+    detached: bool,
+) -> Result<Vec<u8>, WedprError> {
+    let digest_algorithm = encode_algorithm_identifier(&algorithm_oid::<H>()?);
+    let scheme = signature_scheme::<S, H>()?;
+    let signature_algorithm = encode_algorithm_identifier(&scheme.oid);
+    let mut attributes = vec![
+        encode_sequence(&[
+            encode_tlv(TAG_OID, OID_CONTENT_TYPE),
+            encode_tlv(TAG_SET, &encode_tlv(TAG_OID, OID_DATA)),
+        ]),
+        encode_sequence(&[
+            encode_tlv(TAG_OID, OID_MESSAGE_DIGEST),
+            encode_tlv(
+                TAG_SET,
+                &encode_tlv(TAG_OCTET_STRING, &hash.hash(content)),
+            ),
+        ]),
+    ];
+    // DER sorts the elements of a SET OF by their encodings.
+    attributes.sort();
+    let attributes_content = attributes.concat();
+    let signed_input =
+        scheme.signed_input(hash, &encode_tlv(TAG_SET, &attributes_content));
+    let mut signature_bytes = signature.sign(private_key, &signed_input[..])?;
+    if scheme.der_signature {
+        // The recovery id of a recoverable signature is dropped.
+        signature_bytes =
+            match signature_bytes.get(..2 * ECDSA_SCALAR_SIZE_IN_BYTES) {
+                Some(v) => encode_ecdsa_signature(v)?,
+                None => return Err(WedprError::FormatError),
+            };
+    }
+
+    let signer_info = encode_sequence(&[
+        encode_u64(CMS_SIGNER_VERSION),
+        encode_tlv(TAG_IMPLICIT_PRIMITIVE_0, key_identifier),
+        digest_algorithm.clone(),
+        encode_tlv(context_tag(0), &attributes_content),
+        signature_algorithm,
+        encode_tlv(TAG_OCTET_STRING, &signature_bytes),
+    ]);
+    let mut encap_content = vec![encode_tlv(TAG_OID, OID_DATA)];
+    if !detached {
+        encap_content.push(encode_tlv(
+            context_tag(0),
+            &encode_tlv(TAG_OCTET_STRING, content),
+        ));
+    }
+    let signed_data = encode_sequence(&[
+        encode_u64(CMS_SIGNER_VERSION),
+        encode_tlv(TAG_SET, &digest_algorithm),
+        encode_sequence(&encap_content),
+        encode_tlv(TAG_SET, &signer_info),
+    ]);
+    Ok(encode_sequence(&[
+        encode_tlv(TAG_OID, OID_SIGNED_DATA),
+        encode_tlv(context_tag(0), &signed_data),
+    ]))
+}
+
+/// Parses a DER ContentInfo of SignedData.
+pub fn parse_signed_data(input: &[u8]) -> Result<CmsSignedData, WedprError> {
+    let mut signed_data = read_content_info(input, OID_SIGNED_DATA)?;
+    // Skips version and digest algorithms.
+    signed_data.read_expected(TAG_INTEGER)?;
+    signed_data.read_expected(TAG_SET)?;
+    let mut encap_content =
+        DerReader::new(signed_data.read_expected(TAG_SEQUENCE)?.content);
+    encap_content.read_expected(TAG_OID)?;
+    let content = match encap_content.read_optional(context_tag(0))? {
+        Some(v) => Some(
+            DerReader::new(v.content)
+                .read_expected(TAG_OCTET_STRING)?
+                .content
+                .to_vec(),
+        ),
+        None => None,
+    };
+    // Skips certificates and CRLs.
+    signed_data.read_optional(context_tag(0))?;
+    signed_data.read_optional(context_tag(1))?;
+    let mut signer_infos_reader =
+        DerReader::new(signed_data.read_expected(TAG_SET)?.content);
+    let mut signer_infos = Vec::new();
+    while !signer_infos_reader.is_empty() {
+        signer_infos.push(parse_signer_info(
+            signer_infos_reader.read_expected(TAG_SEQUENCE)?.content,
+        )?);
+    }
+    Ok(CmsSignedData {
+        content,
+        signer_infos,
+    })
+}
+
+fn parse_signer_info(input: &[u8]) -> Result<CmsSignerInfo, WedprError> {
+    let mut fields = DerReader::new(input);
+    fields.read_expected(TAG_INTEGER)?;
+    let identifier = fields.read()?;
+    let signer_identifier = if identifier.tag == TAG_IMPLICIT_PRIMITIVE_0 {
+        identifier.content.to_vec()
+    } else {
+        identifier.encoded.to_vec()
+    };
+    let mut algorithm =
+        DerReader::new(fields.read_expected(TAG_SEQUENCE)?.content);
+    let digest_algorithm = algorithm.read_expected(TAG_OID)?.content.to_vec();
+    let (signed_attributes, message_digest) =
+        match fields.read_optional(context_tag(0))? {
+            Some(v) => {
+                // Signed attributes are signed with the SET OF tag instead of
+                // the implicit context-specific tag.
+                let mut signed_attributes = v.encoded.to_vec();
+                signed_attributes[0] = TAG_SET;
+                (
+                    Some(signed_attributes),
+                    Some(find_attribute_value(v.content, OID_MESSAGE_DIGEST)?),
+                )
+            },
+            None => (None, None),
+        };
+    let mut algorithm =
+        DerReader::new(fields.read_expected(TAG_SEQUENCE)?.content);
+    let signature_algorithm =
+        algorithm.read_expected(TAG_OID)?.content.to_vec();
+    let signature = fields.read_expected(TAG_OCTET_STRING)?.content.to_vec();
+    Ok(CmsSignerInfo {
+        signer_identifier,
+        digest_algorithm,
+        signature_algorithm,
+        signed_attributes,
+        message_digest,
+        signature,
+    })
+}
+
+fn find_attribute_value(
+    attributes: &[u8],
+    oid: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let mut reader = DerReader::new(attributes);
+    while !reader.is_empty() {
+        let mut attribute =
+            DerReader::new(reader.read_expected(TAG_SEQUENCE)?.content);
+        if attribute.read_expected(TAG_OID)?.content != oid {
+            continue;
+        }
+        let mut values =
+            DerReader::new(attribute.read_expected(TAG_SET)?.content);
+        return Ok(values.read_expected(TAG_OCTET_STRING)?.content.to_vec());
+    }
+    wedpr_println!("CMS signed attribute is missing");
+    Err(WedprError::FormatError)
+}
+
+/// Verifies the signer of the key identifier in SignedData, where
+/// detached_content is required if the content is not encapsulated.
+pub fn verify_signed_data<
+    S: Signature + AlgorithmInfo,
+    H: Hash + AlgorithmInfo,
+>(
+    signature: &S,
+    hash: &H,
+    public_key: &[u8],
+    key_identifier: &[u8],
+    signed_data: &CmsSignedData,
+    detached_content: Option<&[u8]>,
+) -> bool {
+    let content = match (&signed_data.content, detached_content) {
+        (Some(v), None) => v.as_slice(),
+        (None, Some(v)) => v,
+        _ => {
+            wedpr_println!("CMS content is missing or duplicated");
+            return false;
+        },
+    };
+    let signer_info = match signed_data
+        .signer_infos
+        .iter()
+        .find(|v| v.signer_identifier == key_identifier)
+    {
+        Some(v) => v,
+        None => {
+            wedpr_println!("CMS signer is not found");
+            return false;
+        },
+    };
+    match algorithm_oid::<H>() {
+        Ok(v) if v == signer_info.digest_algorithm => (),
+        _ => {
+            wedpr_println!("CMS digest algorithm mismatched");
+            return false;
+        },
+    }
+    let scheme = match signature_scheme::<S, H>() {
+        Ok(v) if v.oid == signer_info.signature_algorithm => v,
+        _ => {
+            wedpr_println!("CMS signature algorithm mismatched");
+            return false;
+        },
+    };
+    let signed_input =
+        match (&signer_info.signed_attributes, &signer_info.message_digest) {
+            (Some(attributes), Some(message_digest)) => {
+                if *message_digest != hash.hash(content) {
+                    wedpr_println!("CMS message digest mismatched");
+                    return false;
+                }
+                scheme.signed_input(hash, attributes)
+            },
+            _ => scheme.signed_input(hash, content),
+        };
+    if !scheme.der_signature {
+        return signature.verify(
+            public_key,
+            &signed_input[..],
+            &signer_info.signature[..],
+        );
+    }
+    let raw_signature = match decode_ecdsa_signature(
+        &signer_info.signature,
+        ECDSA_SCALAR_SIZE_IN_BYTES,
+    ) {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+    if S::algorithm_info().signature_size
+        != Some(RECOVERABLE_SIGNATURE_SIZE_IN_BYTES)
+    {
+        return signature.verify(
+            public_key,
+            &signed_input[..],
+            &raw_signature[..],
+        );
+    }
+    // DER signatures do not keep recovery ids, thus all of them are tried.
+    (0..4u8).any(|recovery_id| {
+        let mut recoverable_signature = raw_signature.clone();
+        recoverable_signature.push(recovery_id);
+        signature.verify(
+            public_key,
+            &signed_input[..],
+            &recoverable_signature[..],
+        )
+    })
+}
+
+/// Usage of a signature algorithm in SignerInfo.
+struct CmsSignatureScheme {
+    /// DER content of the signature algorithm OID.
+    oid: Vec<u8>,
+    /// Whether signatures are DER ECDSA-Sig-Value instead of raw bytes.
+    der_signature: bool,
+    /// Whether the algorithm signs the data itself instead of its hash, e.g.
+    /// SM2 hashing it with the Z value of the signer.
+    signs_data: bool,
+}
+
+impl CmsSignatureScheme {
+    /// Gets the input of the signature algorithm for the signed data.
+    fn signed_input<H: Hash>(&self, hash: &H, data: &[u8]) -> Vec<u8> {
+        if self.signs_data {
+            data.to_vec()
+        } else {
+            hash.hash(data)
+        }
+    }
+}
+
+/// Gets the usage of a signature algorithm with a digest algorithm.
+fn signature_scheme<S: AlgorithmInfo, H: AlgorithmInfo>(
+) -> Result<CmsSignatureScheme, WedprError> {
+    let signature_name = S::algorithm_info().name;
+    let hash_name = H::algorithm_info().name;
+    let (oid, der_signature, signs_data) = match (signature_name, hash_name) {
+        ("secp256k1", "sha256") | ("secp256r1", "sha256") => {
+            (OID_ECDSA_WITH_SHA256.to_vec(), true, false)
+        },
+        ("sm2p256v1", "sm3") => (OID_SM2_WITH_SM3.to_vec(), true, true),
+        ("secp256k1", _) | ("secp256r1", _) | ("sm2p256v1", _) => {
+            wedpr_println!(
+                "CMS does not support {} with {}",
+                signature_name,
+                hash_name
+            );
+            return Err(WedprError::ArgumentError);
+        },
+        ("ed25519", _) => (algorithm_oid::<S>()?, false, true),
+        _ => (algorithm_oid::<S>()?, false, false),
+    };
+    Ok(CmsSignatureScheme {
+        oid,
+        der_signature,
+        signs_data,
+    })
+}
+
+/// Encrypts the content to a DER ContentInfo of EnvelopedData for the
+/// recipients, where the content key and IV should be freshly generated for
+/// the cipher. The content key is transported to every recipient by the
+/// Ecies algorithm, and the IV is encoded as an OCTET STRING parameter of
+/// the content encryption algorithm as for CBC modes.
+pub fn encrypt_enveloped_data<
+    E: Ecies + AlgorithmInfo,
+    C: BlockCipher + AlgorithmInfo,
+>(
+    ecies: &E,
+    cipher: &C,
+    recipients: &[CmsRecipient],
+    content: &[u8],
+    content_key: &[u8],
+    iv: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    if recipients.is_empty() {
+        wedpr_println!("CMS enveloped data needs at least one recipient");
+        return Err(WedprError::ArgumentError);
+    }
+    let key_encryption_algorithm =
+        encode_algorithm_identifier(&algorithm_oid::<E>()?);
+    let mut recipient_infos = Vec::new();
+    for recipient in recipients {
+        let encrypted_key =
+            ecies.encrypt(&recipient.public_key[..], content_key)?;
+        recipient_infos.push(encode_sequence(&[
+            encode_u64(CMS_RECIPIENT_VERSION),
+            encode_tlv(TAG_IMPLICIT_PRIMITIVE_0, &recipient.key_identifier),
+            key_encryption_algorithm.clone(),
+            encode_tlv(TAG_OCTET_STRING, &encrypted_key),
+        ]));
+    }
+    recipient_infos.sort();
+    let encrypted_content = cipher.encrypt(content, content_key, iv)?;
+    let encrypted_content_info = encode_sequence(&[
+        encode_tlv(TAG_OID, OID_DATA),
+        encode_sequence(&[
+            encode_tlv(TAG_OID, &algorithm_oid::<C>()?),
+            encode_tlv(TAG_OCTET_STRING, iv),
+        ]),
+        encode_tlv(TAG_IMPLICIT_PRIMITIVE_0, &encrypted_content),
+    ]);
+    let enveloped_data = encode_sequence(&[
+        encode_u64(CMS_RECIPIENT_VERSION),
+        encode_tlv(TAG_SET, &recipient_infos.concat()),
+        encrypted_content_info,
+    ]);
+    Ok(encode_sequence(&[
+        encode_tlv(TAG_OID, OID_ENVELOPED_DATA),
+        encode_tlv(context_tag(0), &enveloped_data),
+    ]))
+}
+
+/// Decrypts a DER ContentInfo of EnvelopedData as the recipient of the key
+/// identifier.
+pub fn decrypt_enveloped_data<
+    E: Ecies + AlgorithmInfo,
+    C: BlockCipher + AlgorithmInfo,
+>(
+    ecies: &E,
+    cipher: &C,
+    key_identifier: &[u8],
+    private_key: &[u8],
+    input: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let mut enveloped_data = read_content_info(input, OID_ENVELOPED_DATA)?;
+    if decode_u64(enveloped_data.read_expected(TAG_INTEGER)?.content)?
+        > CMS_RECIPIENT_VERSION
+    {
+        wedpr_println!("Unsupported CMS enveloped data version");
+        return Err(WedprError::FormatError);
+    }
+    // Skips originator info.
+    enveloped_data.read_optional(context_tag(0))?;
+    let mut recipient_infos =
+        DerReader::new(enveloped_data.read_expected(TAG_SET)?.content);
+    let mut encrypted_key = None;
+    while !recipient_infos.is_empty() {
+        let mut recipient_info = DerReader::new(
+            recipient_infos.read_expected(TAG_SEQUENCE)?.content,
+        );
+        recipient_info.read_expected(TAG_INTEGER)?;
+        let identifier = recipient_info.read()?;
+        if identifier.tag != TAG_IMPLICIT_PRIMITIVE_0
+            || identifier.content != key_identifier
+        {
+            continue;
+        }
+        let mut algorithm =
+            DerReader::new(recipient_info.read_expected(TAG_SEQUENCE)?.content);
+        let expected_algorithm = algorithm_oid::<E>()?;
+        if algorithm.read_expected(TAG_OID)?.content
+            != expected_algorithm.as_slice()
+        {
+            wedpr_println!("CMS key encryption algorithm mismatched");
+            return Err(WedprError::ArgumentError);
+        }
+        encrypted_key =
+            Some(recipient_info.read_expected(TAG_OCTET_STRING)?.content);
+        break;
+    }
+    let encrypted_key = match encrypted_key {
+        Some(v) => v,
+        None => {
+            wedpr_println!("CMS recipient is not found");
+            return Err(WedprError::ArgumentError);
+        },
+    };
+
+    let mut encrypted_content_info =
+        DerReader::new(enveloped_data.read_expected(TAG_SEQUENCE)?.content);
+    encrypted_content_info.read_expected(TAG_OID)?;
+    let mut algorithm = DerReader::new(
+        encrypted_content_info.read_expected(TAG_SEQUENCE)?.content,
+    );
+    let expected_algorithm = algorithm_oid::<C>()?;
+    if algorithm.read_expected(TAG_OID)?.content
+        != expected_algorithm.as_slice()
+    {
+        wedpr_println!("CMS content encryption algorithm mismatched");
+        return Err(WedprError::ArgumentError);
+    }
+    let iv = algorithm.read_expected(TAG_OCTET_STRING)?.content;
+    let encrypted_content = encrypted_content_info
+        .read_expected(TAG_IMPLICIT_PRIMITIVE_0)?
+        .content;
+    let content_key = ecies.decrypt(private_key, encrypted_key)?;
+    cipher.decrypt(encrypted_content, &content_key[..], iv)
+}
+
+/// Reads the explicit content of a ContentInfo of the content type.
+fn read_content_info<'a>(
+    input: &'a [u8],
+    content_type: &[u8],
+) -> Result<DerReader<'a>, WedprError> {
+    let mut reader = DerReader::new(input);
+    let mut content_info =
+        DerReader::new(reader.read_expected(TAG_SEQUENCE)?.content);
+    if content_info.read_expected(TAG_OID)?.content != content_type {
+        wedpr_println!("Unexpected CMS content type");
+        return Err(WedprError::FormatError);
+    }
+    let mut explicit_content =
+        DerReader::new(content_info.read_expected(context_tag(0))?.content);
+    Ok(DerReader::new(
+        explicit_content.read_expected(TAG_SEQUENCE)?.content,
+    ))
+}
+
+/// Gets the DER content of the OID of an algorithm.
+fn algorithm_oid<A: AlgorithmInfo>() -> Result<Vec<u8>, WedprError> {
+    match A::algorithm_info().oid {
+        Some(v) => encode_oid_content(v),
+        None => {
+            wedpr_println!("CMS needs an algorithm with a registered OID");
+            Err(WedprError::ArgumentError)
+        },
+    }
+}
+
+/// Encodes an AlgorithmIdentifier with absent parameters, except that
+/// RSAES-OAEP declares its SHA-256 hash and MGF1 explicitly.
+fn encode_algorithm_identifier(oid: &[u8]) -> Vec<u8> {
+    let mut fields = vec![encode_tlv(TAG_OID, oid)];
+    if oid == OID_RSAES_OAEP {
+        let sha256 = encode_sequence(&[encode_tlv(TAG_OID, OID_SHA256)]);
+        fields.push(encode_sequence(&[
+            encode_tlv(context_tag(0), &sha256),
+            encode_tlv(
+                context_tag(1),
+                &encode_sequence(&[encode_tlv(TAG_OID, OID_MGF1), sha256]),
+            ),
+        ]));
+    }
+    encode_sequence(&fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_common_coder_hex::WedprHex;
+    use wedpr_l_crypto_block_cipher_sm4::WedprSm4Cbc;
+    use wedpr_l_crypto_hash_sha256::WedprSha256;
+    use wedpr_l_crypto_hash_sm3::WedprSm3;
+    use wedpr_l_crypto_rsa::{
+        generate_rsa_keypair, WedprRsaOaep, DEFAULT_RSA_KEY_SIZE_IN_BITS,
+    };
+    use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+    use wedpr_l_crypto_signature_secp256r1::WedprSecp256r1;
+    use wedpr_l_crypto_signature_sm2::WedprSm2p256v1;
+    use wedpr_l_utils::traits::Coder;
+
+    #[test]
+    fn test_cms() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let sha256 = WedprSha256::default();
+        let (public_key, private_key) = secp256k1.generate_keypair();
+        let key_identifier = b"signer key id".to_vec();
+        let content = b"content exchanged by partner banks".to_vec();
+
+        for detached in &[false, true] {
+            let encoded = create_signed_data(
+                &secp256k1,
+                &sha256,
+                &private_key,
+                &key_identifier,
+                &content,
+                *detached,
+            )
+            .unwrap();
+            let signed_data = parse_signed_data(&encoded).unwrap();
+            assert_eq!(signed_data.signer_infos.len(), 1);
+            let detached_content = if *detached {
+                Some(&content[..])
+            } else {
+                assert_eq!(signed_data.content, Some(content.clone()));
+                None
+            };
+            assert!(verify_signed_data(
+                &secp256k1,
+                &sha256,
+                &public_key,
+                &key_identifier,
+                &signed_data,
+                detached_content
+            ));
+            assert!(!verify_signed_data(
+                &secp256k1,
+                &sha256,
+                &public_key,
+                b"other key id",
+                &signed_data,
+                detached_content
+            ));
+        }
+        let encoded = create_signed_data(
+            &secp256k1,
+            &sha256,
+            &private_key,
+            &key_identifier,
+            &content,
+            true,
+        )
+        .unwrap();
+        let signed_data = parse_signed_data(&encoded).unwrap();
+        assert!(!verify_signed_data(
+            &secp256k1,
+            &sha256,
+            &public_key,
+            &key_identifier,
+            &signed_data,
+            Some(&content[1..])
+        ));
+        assert!(parse_signed_data(&encoded[..encoded.len() - 1]).is_err());
+
+        let oaep = WedprRsaOaep::default();
+        let sm4 = WedprSm4Cbc::default();
+        let (rsa_public_key, rsa_private_key) =
+            generate_rsa_keypair(DEFAULT_RSA_KEY_SIZE_IN_BITS).unwrap();
+        let recipients = vec![
+            CmsRecipient {
+                key_identifier: b"first".to_vec(),
+                public_key: rsa_public_key.clone(),
+            },
+            CmsRecipient {
+                key_identifier: b"second".to_vec(),
+                public_key: rsa_public_key,
+            },
+        ];
+        let encoded = encrypt_enveloped_data(
+            &oaep,
+            &sm4,
+            &recipients,
+            &content,
+            &sm4.generate_key(),
+            &sm4.generate_iv(),
+        )
+        .unwrap();
+        for recipient in &recipients {
+            assert_eq!(
+                decrypt_enveloped_data(
+                    &oaep,
+                    &sm4,
+                    &recipient.key_identifier,
+                    &rsa_private_key,
+                    &encoded
+                )
+                .unwrap(),
+                content
+            );
+        }
+        assert!(decrypt_enveloped_data(
+            &oaep,
+            &sm4,
+            b"third",
+            &rsa_private_key,
+            &encoded
+        )
+        .is_err());
+        assert!(parse_signed_data(&encoded).is_err());
+    }
+
+    #[test]
+    fn test_cms_openssl_vectors() {
+        let hex = WedprHex::default();
+        let content = b"content signed by OpenSSL".to_vec();
+        // SignedData by `openssl cms -sign -keyid -nocerts -nodetach` of
+        // OpenSSL 3.5 with a P-256 key.
+        let p256_public_key = hex
+            .decode(
+                &[
+                    "04aa165d74267952a71258e593968f1c94629a9b259b4d4323df959c",
+                    "ac58b1adf8942591409b914df343892ffea7efab32c12a801040044f",
+                    "95b48537fe18618e82",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let p256_key_identifier = hex
+            .decode("71488528db6bf1af717b0adb63a216d90177dcb5")
+            .unwrap();
+        let p256_signed_data = hex
+            .decode(
+                &[
+                    "308201b806092a864886f70d010702a08201a9308201a5020103310d",
+                    "300b0609608648016503040201302806092a864886f70d010701a01b",
+                    "0419636f6e74656e74207369676e6564206279204f70656e53534c31",
+                    "82016530820161020103801471488528db6bf1af717b0adb63a216d9",
+                    "0177dcb5300b0609608648016503040201a081e4301806092a864886",
+                    "f70d010903310b06092a864886f70d010701301c06092a864886f70d",
+                    "010905310f170d3236313031353035313534395a302f06092a864886",
+                    "f70d0109043122042074305c29a75234abd75307277b02f83eef9899",
+                    "0431a15a10b7863af3a2aef246307906092a864886f70d01090f316c",
+                    "306a300b060960864801650304012a300b0609608648016503040116",
+                    "300b0609608648016503040102300a06082a864886f70d0307300e06",
+                    "082a864886f70d030202020080300d06082a864886f70d0302020140",
+                    "300706052b0e030207300d06082a864886f70d0302020128300a0608",
+                    "2a8648ce3d0403020446304402204f84a8cefa333d2af00b249de4c2",
+                    "e05f689543c6436cc3bb2631bcf00272d1ee022004bc85efa0bbba33",
+                    "b567b00e5abc2b94b4a494c54c88c4b5031455a0a31be4e2",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let signed_data = parse_signed_data(&p256_signed_data).unwrap();
+        assert_eq!(signed_data.content, Some(content.clone()));
+        let secp256r1 = WedprSecp256r1::default();
+        let sha256 = WedprSha256::default();
+        assert!(verify_signed_data(
+            &secp256r1,
+            &sha256,
+            &p256_public_key,
+            &p256_key_identifier,
+            &signed_data,
+            None
+        ));
+        assert!(!verify_signed_data(
+            &secp256r1,
+            &sha256,
+            &p256_public_key,
+            &p256_key_identifier,
+            &signed_data,
+            Some(&content[1..])
+        ));
+
+        // The same by an SM2 key with SM3 and
+        // `-keyopt distid:1234567812345678`, as OpenSSL 3 does not use the
+        // default ID of GB/T 32918 unless set.
+        let sm2_public_key = hex
+            .decode(
+                &[
+                    "04138b8e8b6fdc4e25f385a5c6cd61ac5dc9b50fde38365172437a9f",
+                    "3592442fbadc62bc6950270c6e2235988dadcde5bfd2568d464ca145",
+                    "ac333462a5bfe780f2",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let sm2_key_identifier = hex
+            .decode("e12bd5953ce71e18da194addb331445cd8026f4d")
+            .unwrap();
+        let sm2_signed_data = hex
+            .decode(
+                &[
+                    "308201bc06092a864886f70d010702a08201ad308201a9020103310e",
+                    "300c06082a811ccf550183110500302806092a864886f70d010701a0",
+                    "1b0419636f6e74656e74207369676e6564206279204f70656e53534c",
+                    "31820168308201640201038014e12bd5953ce71e18da194addb33144",
+                    "5cd8026f4d300c06082a811ccf550183110500a081e4301806092a86",
+                    "4886f70d010903310b06092a864886f70d010701301c06092a864886",
+                    "f70d010905310f170d3236313031353035313734375a302f06092a86",
+                    "4886f70d010904312204209aa8934536beba2881ae12646bc26e75c4",
+                    "d0c40fc69a3af3cd6f1d49d3133fa2307906092a864886f70d01090f",
+                    "316c306a300b060960864801650304012a300b060960864801650304",
+                    "0116300b0609608648016503040102300a06082a864886f70d030730",
+                    "0e06082a864886f70d030202020080300d06082a864886f70d030202",
+                    "0140300706052b0e030207300d06082a864886f70d0302020128300a",
+                    "06082a811ccf5501837504483046022100b22b1f11718b7a841fc1ce",
+                    "5ddc3b296545f4e7d850d0ef7c881ea01a0717e3b2022100ff20cee7",
+                    "5bba5997c259fae16be195f4fc89a971c1fc7b39f27528769f21e0e5",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let signed_data = parse_signed_data(&sm2_signed_data).unwrap();
+        let sm2 = WedprSm2p256v1::default();
+        let sm3 = WedprSm3::default();
+        assert!(verify_signed_data(
+            &sm2,
+            &sm3,
+            &sm2_public_key,
+            &sm2_key_identifier,
+            &signed_data,
+            None
+        ));
+        // Signatures are bound to their algorithms.
+        assert!(!verify_signed_data(
+            &secp256r1,
+            &sha256,
+            &p256_public_key,
+            &p256_key_identifier,
+            &signed_data,
+            None
+        ));
+
+        // Signatures created here use the same algorithms.
+        let (public_key, private_key) = sm2.generate_keypair();
+        let encoded = create_signed_data(
+            &sm2,
+            &sm3,
+            &private_key,
+            &sm2_key_identifier,
+            &content,
+            false,
+        )
+        .unwrap();
+        let signed_data = parse_signed_data(&encoded).unwrap();
+        assert_eq!(
+            signed_data.signer_infos[0].signature_algorithm,
+            OID_SM2_WITH_SM3.to_vec()
+        );
+        assert!(verify_signed_data(
+            &sm2,
+            &sm3,
+            &public_key,
+            &sm2_key_identifier,
+            &signed_data,
+            None
+        ));
+        assert!(create_signed_data(
+            &sm2,
+            &sha256,
+            &private_key,
+            &sm2_key_identifier,
+            &content,
+            false,
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Counter-signature envelopes, where every notary or supervisor endorses the
+//! previous signature in the chain together with its own metadata.
+
+use crate::{append_bytes, FieldReader};
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{Hash, Signature},
+};
+
+/// Domain separator of the original signature in an envelope.
+const ORIGINAL_SIGNATURE_DOMAIN: &[u8] = b"WeDPR_ENVELOPE_SIGNATURE";
+/// Domain separator of a counter-signature in an envelope.
+const COUNTER_SIGNATURE_DOMAIN: &[u8] = b"WeDPR_ENVELOPE_COUNTER_SIGNATURE";
+
+/// A single signature in an envelope chain.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct EnvelopeSignature {
+    pub public_key: Vec<u8>,
+    /// Application-defined metadata signed together, e.g. role or time.
+    pub metadata: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+/// Envelope of a message hash, which is signed by its original signer and
+/// then counter-signed in order.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct SignatureEnvelope {
+    pub msg_hash: Vec<u8>,
+    /// The original signature followed by all counter-signatures.
+    pub signatures: Vec<EnvelopeSignature>,
+}
+
+/// Computes the hash signed by the i-th signature of an envelope, where the
+/// original signature covers the message hash, and any counter-signature
+/// covers the previous signature along with its signer.
+fn chain_hash<H: Hash>(
+    hash: &H,
+    msg_hash: &[u8],
+    previous: Option<&EnvelopeSignature>,
+    public_key: &[u8],
+    metadata: &[u8],
+) -> Vec<u8> {
+    let mut hash_vec = Vec::new();
+    match previous {
+        None => {
+            hash_vec.extend_from_slice(ORIGINAL_SIGNATURE_DOMAIN);
+            append_bytes(&mut hash_vec, msg_hash);
+        },
+        Some(v) => {
+            hash_vec.extend_from_slice(COUNTER_SIGNATURE_DOMAIN);
+            append_bytes(&mut hash_vec, &v.public_key);
+            append_bytes(&mut hash_vec, &v.signature);
+        },
+    }
+    append_bytes(&mut hash_vec, public_key);
+    append_bytes(&mut hash_vec, metadata);
+    hash.hash(&hash_vec)
+}
+
+impl SignatureEnvelope {
+    /// Creates an envelope with the original signature of a message hash.
+    pub fn sign<S: Signature, H: Hash>(
+        signature: &S,
+        hash: &H,
+        private_key: &[u8],
+        public_key: &[u8],
+        msg_hash: &[u8],
+        metadata: &[u8],
+    ) -> Result<Self, WedprError> {
+        let mut envelope = SignatureEnvelope {
+            msg_hash: msg_hash.to_vec(),
+            signatures: Vec::new(),
+        };
+        envelope.append_signature(
+            signature,
+            hash,
+            private_key,
+            public_key,
+            metadata,
+        )?;
+        Ok(envelope)
+    }
+
+    /// Counter-signs the latest signature in the envelope.
+    pub fn counter_sign<S: Signature, H: Hash>(
+        &mut self,
+        signature: &S,
+        hash: &H,
+        private_key: &[u8],
+        public_key: &[u8],
+        metadata: &[u8],
+    ) -> Result<(), WedprError> {
+        if self.signatures.is_empty() {
+            wedpr_println!("Envelope has no signature to counter-sign");
+            return Err(WedprError::ArgumentError);
+        }
+        self.append_signature(
+            signature,
+            hash,
+            private_key,
+            public_key,
+            metadata,
+        )
+    }
+
+    fn append_signature<S: Signature, H: Hash>(
+        &mut self,
+        signature: &S,
+        hash: &H,
+        private_key: &[u8],
+        public_key: &[u8],
+        metadata: &[u8],
+    ) -> Result<(), WedprError> {
+        let signed_hash = chain_hash(
+            hash,
+            &self.msg_hash,
+            self.signatures.last(),
+            public_key,
+            metadata,
+        );
+        let signature_bytes = signature.sign(private_key, &signed_hash[..])?;
+        self.signatures.push(EnvelopeSignature {
+            public_key: public_key.to_vec(),
+            metadata: metadata.to_vec(),
+            signature: signature_bytes,
+        });
+        Ok(())
+    }
+
+    /// Verifies the whole signature chain. Callers still need to check
+    /// whether the public keys of the signers are trusted.
+    pub fn verify_chain<S: Signature, H: Hash>(
+        &self,
+        signature: &S,
+        hash: &H,
+    ) -> bool {
+        if self.signatures.is_empty() {
+            return false;
+        }
+        let mut previous = None;
+        for current in self.signatures.iter() {
+            let signed_hash = chain_hash(
+                hash,
+                &self.msg_hash,
+                previous,
+                &current.public_key,
+                &current.metadata,
+            );
+            if !signature.verify(
+                &current.public_key[..],
+                &signed_hash[..],
+                &current.signature[..],
+            ) {
+                wedpr_println!("Envelope signature chain is broken");
+                return false;
+            }
+            previous = Some(current);
+        }
+        true
+    }
+
+    /// Encodes an envelope to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        append_bytes(&mut output, &self.msg_hash);
+        for signature in self.signatures.iter() {
+            append_bytes(&mut output, &signature.public_key);
+            append_bytes(&mut output, &signature.metadata);
+            append_bytes(&mut output, &signature.signature);
+        }
+        output
+    }
+
+    /// Decodes an envelope from bytes.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        let mut reader = FieldReader::new(input);
+        let msg_hash = reader.read_bytes()?.to_vec();
+        let mut signatures = Vec::new();
+        while !reader.is_empty() {
+            signatures.push(EnvelopeSignature {
+                public_key: reader.read_bytes()?.to_vec(),
+                metadata: reader.read_bytes()?.to_vec(),
+                signature: reader.read_bytes()?.to_vec(),
+            });
+        }
+        Ok(SignatureEnvelope {
+            msg_hash,
+            signatures,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+    use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+    use wedpr_l_utils::constant::tests::BASE64_ENCODED_TEST_MESSAGE;
+
+    #[test]
+    fn test_counter_signature() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let keccak256 = WedprKeccak256::default();
+        let (signer_public_key, signer_private_key) =
+            secp256k1.generate_keypair();
+        let (notary_public_key, notary_private_key) =
+            secp256k1.generate_keypair();
+        let (supervisor_public_key, supervisor_private_key) =
+            secp256k1.generate_keypair();
+
+        let mut envelope = SignatureEnvelope::sign(
+            &secp256k1,
+            &keccak256,
+            &signer_private_key,
+            &signer_public_key,
+            &BASE64_ENCODED_TEST_MESSAGE,
+            b"author",
+        )
+        .unwrap();
+        assert!(envelope.verify_chain(&secp256k1, &keccak256));
+        envelope
+            .counter_sign(
+                &secp256k1,
+                &keccak256,
+                &notary_private_key,
+                &notary_public_key,
+                b"notary",
+            )
+            .unwrap();
+        envelope
+            .counter_sign(
+                &secp256k1,
+                &keccak256,
+                &supervisor_private_key,
+                &supervisor_public_key,
+                b"supervisor",
+            )
+            .unwrap();
+        assert_eq!(envelope.signatures.len(), 3);
+        assert!(envelope.verify_chain(&secp256k1, &keccak256));
+
+        let decoded_envelope =
+            SignatureEnvelope::decode(&envelope.encode()).unwrap();
+        assert_eq!(decoded_envelope, envelope);
+
+        // Tampered metadata breaks the chain.
+        let mut tampered_envelope = envelope.clone();
+        tampered_envelope.signatures[1].metadata = b"forged".to_vec();
+        assert!(!tampered_envelope.verify_chain(&secp256k1, &keccak256));
+
+        // A counter-signature cannot be moved to another envelope.
+        let mut other_envelope = SignatureEnvelope::sign(
+            &secp256k1,
+            &keccak256,
+            &signer_private_key,
+            &signer_public_key,
+            &BASE64_ENCODED_TEST_MESSAGE,
+            b"another author",
+        )
+        .unwrap();
+        other_envelope
+            .signatures
+            .push(envelope.signatures[1].clone());
+        assert!(!other_envelope.verify_chain(&secp256k1, &keccak256));
+
+        assert!(
+            !SignatureEnvelope::default().verify_chain(&secp256k1, &keccak256)
+        );
+    }
+}
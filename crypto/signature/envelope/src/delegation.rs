@@ -0,0 +1,258 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Delegation certificates, where a parent key signs a child key with its
+//! capabilities and validity window, so that services can sign with
+//! short-lived subordinate keys chained up to a trust root.
+
+use crate::{append_bytes, append_u64, FieldReader};
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{Hash, Signature},
+};
+
+/// Domain separator of the signed content of a delegation certificate.
+const DELEGATION_DOMAIN: &[u8] = b"WeDPR_DELEGATION_CERTIFICATE";
+
+/// Certificate delegating capabilities from a parent key to a child key
+/// within [not_before, not_after] in Unix seconds.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct DelegationCertificate {
+    pub parent_public_key: Vec<u8>,
+    pub child_public_key: Vec<u8>,
+    pub capabilities: Vec<String>,
+    pub not_before: u64,
+    pub not_after: u64,
+    /// Signature of the parent key, which is empty before issuing.
+    pub signature: Vec<u8>,
+}
+
+impl DelegationCertificate {
+    /// Creates an unsigned certificate to be issued by issue_delegation.
+    pub fn new(
+        parent_public_key: &[u8],
+        child_public_key: &[u8],
+        capabilities: &[&str],
+        not_before: u64,
+        not_after: u64,
+    ) -> Self {
+        DelegationCertificate {
+            parent_public_key: parent_public_key.to_vec(),
+            child_public_key: child_public_key.to_vec(),
+            capabilities: capabilities.iter().map(|v| v.to_string()).collect(),
+            not_before,
+            not_after,
+            signature: Vec::new(),
+        }
+    }
+
+    /// Checks whether the certificate grants a capability.
+    pub fn has_capability(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|v| v == capability)
+    }
+
+    fn encode_content(&self, output: &mut Vec<u8>) {
+        append_bytes(output, &self.parent_public_key);
+        append_bytes(output, &self.child_public_key);
+        append_u64(output, self.capabilities.len() as u64);
+        for capability in &self.capabilities {
+            append_bytes(output, capability.as_bytes());
+        }
+        append_u64(output, self.not_before);
+        append_u64(output, self.not_after);
+    }
+
+    /// Encodes a delegation certificate to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        self.encode_content(&mut output);
+        append_bytes(&mut output, &self.signature);
+        output
+    }
+
+    /// Decodes a delegation certificate from bytes.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        let mut reader = FieldReader::new(input);
+        let parent_public_key = reader.read_bytes()?.to_vec();
+        let child_public_key = reader.read_bytes()?.to_vec();
+        let capability_count = reader.read_u64()?;
+        let mut capabilities = Vec::new();
+        for _ in 0..capability_count {
+            capabilities.push(
+                match String::from_utf8(reader.read_bytes()?.to_vec()) {
+                    Ok(v) => v,
+                    Err(_) => {
+                        wedpr_println!("Capability is not valid UTF-8");
+                        return Err(WedprError::DecodeError);
+                    },
+                },
+            );
+        }
+        let not_before = reader.read_u64()?;
+        let not_after = reader.read_u64()?;
+        let signature = reader.read_bytes()?.to_vec();
+        reader.finish()?;
+        Ok(DelegationCertificate {
+            parent_public_key,
+            child_public_key,
+            capabilities,
+            not_before,
+            not_after,
+            signature,
+        })
+    }
+
+    /// Computes the hash actually signed by the parent key.
+    fn signed_hash<H: Hash>(&self, hash: &H) -> Vec<u8> {
+        let mut hash_vec = DELEGATION_DOMAIN.to_vec();
+        self.encode_content(&mut hash_vec);
+        hash.hash(&hash_vec)
+    }
+}
+
+/// Issues a certificate by signing it with the parent private key.
+pub fn issue_delegation<S: Signature, H: Hash>(
+    signature: &S,
+    hash: &H,
+    parent_private_key: &[u8],
+    certificate: &DelegationCertificate,
+) -> Result<DelegationCertificate, WedprError> {
+    if certificate.not_before > certificate.not_after {
+        wedpr_println!("Validity window is empty");
+        return Err(WedprError::ArgumentError);
+    }
+    let signed_hash = certificate.signed_hash(hash);
+    let mut issued_certificate = certificate.clone();
+    issued_certificate.signature =
+        signature.sign(parent_private_key, &signed_hash[..])?;
+    Ok(issued_certificate)
+}
+
+/// Verifies a certificate chain from a trust root at the given time, and
+/// returns the public key of the last certificate if it holds
+/// `required_capability`. Each certificate must be signed by the child key
+/// of its predecessor, and can only narrow its capabilities and validity
+/// window.
+pub fn verify_delegation_chain<S: Signature, H: Hash>(
+    signature: &S,
+    hash: &H,
+    trust_root: &[u8],
+    chain: &[DelegationCertificate],
+    required_capability: &str,
+    now: u64,
+) -> Result<Vec<u8>, WedprError> {
+    let mut parent: Option<&DelegationCertificate> = None;
+    let mut parent_public_key = trust_root;
+    for certificate in chain {
+        if certificate.parent_public_key != parent_public_key {
+            wedpr_println!("Delegation chain is broken");
+            return Err(WedprError::VerificationError);
+        }
+        if now < certificate.not_before || now > certificate.not_after {
+            wedpr_println!("Delegation certificate is not valid at this time");
+            return Err(WedprError::VerificationError);
+        }
+        if let Some(parent_certificate) = parent {
+            if certificate.not_before < parent_certificate.not_before
+                || certificate.not_after > parent_certificate.not_after
+                || !certificate
+                    .capabilities
+                    .iter()
+                    .all(|v| parent_certificate.has_capability(v))
+            {
+                wedpr_println!("Delegation certificate exceeds its parent");
+                return Err(WedprError::VerificationError);
+            }
+        }
+        if !signature.verify(
+            parent_public_key,
+            &certificate.signed_hash(hash)[..],
+            &certificate.signature[..],
+        ) {
+            wedpr_println!("Delegation certificate signature is invalid");
+            return Err(WedprError::VerificationError);
+        }
+        parent = Some(certificate);
+        parent_public_key = &certificate.child_public_key;
+    }
+    match parent {
+        Some(certificate)
+            if certificate.has_capability(required_capability) =>
+        {
+            Ok(certificate.child_public_key.clone())
+        },
+        _ => {
+            wedpr_println!("Missing capability {}", required_capability);
+            Err(WedprError::VerificationError)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+    use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+
+    #[test]
+    fn test_delegation_chain() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let keccak256 = WedprKeccak256::default();
+        let (root_public_key, root_private_key) = secp256k1.generate_keypair();
+        let (service_public_key, service_private_key) =
+            secp256k1.generate_keypair();
+        let (session_public_key, _) = secp256k1.generate_keypair();
+
+        let service_certificate = issue_delegation(
+            &secp256k1,
+            &keccak256,
+            &root_private_key,
+            &DelegationCertificate::new(
+                &root_public_key,
+                &service_public_key,
+                &["sign", "decrypt"],
+                1000,
+                5000,
+            ),
+        )
+        .unwrap();
+        let session_certificate = issue_delegation(
+            &secp256k1,
+            &keccak256,
+            &service_private_key,
+            &DelegationCertificate::new(
+                &service_public_key,
+                &session_public_key,
+                &["sign"],
+                2000,
+                3000,
+            ),
+        )
+        .unwrap();
+        let chain = vec![service_certificate.clone(), session_certificate];
+        let verify = |chain: &[DelegationCertificate], capability, now| {
+            verify_delegation_chain(
+                &secp256k1,
+                &keccak256,
+                &root_public_key,
+                chain,
+                capability,
+                now,
+            )
+        };
+        assert_eq!(verify(&chain, "sign", 2500).unwrap(), session_public_key);
+        assert!(verify(&chain, "decrypt", 2500).is_err());
+        assert!(verify(&chain, "sign", 3500).is_err());
+        assert!(verify(&chain[1..], "sign", 2500).is_err());
+
+        // A child cannot gain capabilities beyond its parent.
+        let mut escalated_chain = chain.clone();
+        escalated_chain[1].capabilities.push("decrypt".to_string());
+        assert!(verify(&escalated_chain, "decrypt", 2500).is_err());
+
+        assert_eq!(
+            DelegationCertificate::decode(&service_certificate.encode())
+                .unwrap(),
+            service_certificate
+        );
+    }
+}
@@ -0,0 +1,320 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Event stream signing functions, where events are grouped into rolling
+//! segments, and only the hash-chained segment headers are signed.
+
+use crate::{
+    append_bytes, append_u64,
+    merkle::{merkle_prove, merkle_root, merkle_verify, MerkleProof},
+    FieldReader,
+};
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{Hash, Signature},
+};
+
+/// Domain separator of the signed content of a segment header.
+const SEGMENT_DOMAIN: &[u8] = b"WeDPR_EVENT_STREAM_SEGMENT";
+
+/// Header of a segment, which commits to all its events and the previous
+/// segment.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct SegmentHeader {
+    pub segment_index: u64,
+    /// Sequence number of the first event in the segment.
+    pub first_sequence: u64,
+    pub event_count: u64,
+    /// Header hash of the previous segment, which is empty for the first one.
+    pub previous_hash: Vec<u8>,
+    /// Merkle root of the event hashes in the segment.
+    pub events_root: Vec<u8>,
+}
+
+impl SegmentHeader {
+    /// Computes the header hash, which is both signed and chained.
+    pub fn hash<H: Hash>(&self, hash: &H) -> Vec<u8> {
+        let mut hash_vec = SEGMENT_DOMAIN.to_vec();
+        hash_vec.append(&mut self.encode());
+        hash.hash(&hash_vec)
+    }
+
+    /// Encodes a segment header to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        append_u64(&mut output, self.segment_index);
+        append_u64(&mut output, self.first_sequence);
+        append_u64(&mut output, self.event_count);
+        append_bytes(&mut output, &self.previous_hash);
+        append_bytes(&mut output, &self.events_root);
+        output
+    }
+}
+
+/// A segment header with the signature of the stream owner.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct SignedSegment {
+    pub header: SegmentHeader,
+    pub signature: Vec<u8>,
+}
+
+impl SignedSegment {
+    /// Encodes a signed segment to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        append_bytes(&mut output, &self.header.encode());
+        append_bytes(&mut output, &self.signature);
+        output
+    }
+
+    /// Decodes a signed segment from bytes.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        let mut reader = FieldReader::new(input);
+        let mut header_reader = FieldReader::new(reader.read_bytes()?);
+        let header = SegmentHeader {
+            segment_index: header_reader.read_u64()?,
+            first_sequence: header_reader.read_u64()?,
+            event_count: header_reader.read_u64()?,
+            previous_hash: header_reader.read_bytes()?.to_vec(),
+            events_root: header_reader.read_bytes()?.to_vec(),
+        };
+        header_reader.finish()?;
+        let signature = reader.read_bytes()?.to_vec();
+        reader.finish()?;
+        Ok(SignedSegment { header, signature })
+    }
+}
+
+/// Signs an event stream segment by segment.
+pub struct EventStreamSigner<'a, S: Signature, H: Hash> {
+    signature: &'a S,
+    hash: &'a H,
+    private_key: Vec<u8>,
+    segment_size: usize,
+    next_segment_index: u64,
+    next_sequence: u64,
+    previous_hash: Vec<u8>,
+    pending_event_hashes: Vec<Vec<u8>>,
+}
+
+impl<'a, S: Signature, H: Hash> EventStreamSigner<'a, S, H> {
+    /// Creates a signer which signs a segment for every segment_size events.
+    pub fn new(
+        signature: &'a S,
+        hash: &'a H,
+        private_key: &[u8],
+        segment_size: usize,
+    ) -> Result<Self, WedprError> {
+        if segment_size == 0 {
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(EventStreamSigner {
+            signature,
+            hash,
+            private_key: private_key.to_vec(),
+            segment_size,
+            next_segment_index: 0,
+            next_sequence: 0,
+            previous_hash: Vec::new(),
+            pending_event_hashes: Vec::new(),
+        })
+    }
+
+    /// Appends an event, and returns a signed segment if the current segment
+    /// becomes full.
+    pub fn append(
+        &mut self,
+        event: &[u8],
+    ) -> Result<Option<SignedSegment>, WedprError> {
+        self.pending_event_hashes.push(self.hash.hash(event));
+        if self.pending_event_hashes.len() < self.segment_size {
+            return Ok(None);
+        }
+        self.flush()
+    }
+
+    /// Signs the pending events as a segment, e.g. periodically or before
+    /// shutdown, and returns None if there is no pending event.
+    pub fn flush(&mut self) -> Result<Option<SignedSegment>, WedprError> {
+        if self.pending_event_hashes.is_empty() {
+            return Ok(None);
+        }
+        let header = SegmentHeader {
+            segment_index: self.next_segment_index,
+            first_sequence: self.next_sequence,
+            event_count: self.pending_event_hashes.len() as u64,
+            previous_hash: self.previous_hash.clone(),
+            events_root: merkle_root(self.hash, &self.pending_event_hashes)?,
+        };
+        let header_hash = header.hash(self.hash);
+        let signature = self
+            .signature
+            .sign(&self.private_key[..], &header_hash[..])?;
+        self.next_segment_index += 1;
+        self.next_sequence += header.event_count;
+        self.previous_hash = header_hash;
+        self.pending_event_hashes.clear();
+        Ok(Some(SignedSegment { header, signature }))
+    }
+}
+
+/// Verifies the signature of a segment.
+pub fn verify_segment<S: Signature, H: Hash>(
+    signature: &S,
+    hash: &H,
+    public_key: &[u8],
+    segment: &SignedSegment,
+) -> bool {
+    let header_hash = segment.header.hash(hash);
+    signature.verify(public_key, &header_hash[..], &segment.signature[..])
+}
+
+/// Verifies consecutive segments, including their signatures and the chaining
+/// between them. The first segment can start anywhere in the stream.
+pub fn verify_segment_chain<S: Signature, H: Hash>(
+    signature: &S,
+    hash: &H,
+    public_key: &[u8],
+    segments: &[SignedSegment],
+) -> bool {
+    let mut previous: Option<&SignedSegment> = None;
+    for segment in segments.iter() {
+        if !verify_segment(signature, hash, public_key, segment) {
+            wedpr_println!("Segment signature is invalid");
+            return false;
+        }
+        if let Some(v) = previous {
+            if segment.header.segment_index != v.header.segment_index + 1
+                || segment.header.first_sequence
+                    != v.header.first_sequence + v.header.event_count
+                || segment.header.previous_hash != v.header.hash(hash)
+            {
+                wedpr_println!("Segment chain is broken");
+                return false;
+            }
+        }
+        previous = Some(segment);
+    }
+    true
+}
+
+/// Proves an event of a segment for partial reads, given all events of the
+/// segment and the offset of the event in it.
+pub fn prove_event<H: Hash, T: AsRef<[u8]>>(
+    hash: &H,
+    segment_events: &[T],
+    offset: usize,
+) -> Result<MerkleProof, WedprError> {
+    let event_hashes: Vec<Vec<u8>> = segment_events
+        .iter()
+        .map(|event| hash.hash(event.as_ref()))
+        .collect();
+    merkle_prove(hash, &event_hashes, offset)
+}
+
+/// Verifies a single event read with its sequence number, against a signed
+/// segment and the proof of the event.
+pub fn verify_event<S: Signature, H: Hash>(
+    signature: &S,
+    hash: &H,
+    public_key: &[u8],
+    segment: &SignedSegment,
+    sequence: u64,
+    event: &[u8],
+    proof: &MerkleProof,
+) -> bool {
+    let header = &segment.header;
+    if sequence < header.first_sequence
+        || proof.index != sequence - header.first_sequence
+        || proof.leaf_count != header.event_count
+    {
+        wedpr_println!("Event does not belong to the segment");
+        return false;
+    }
+    verify_segment(signature, hash, public_key, segment)
+        && merkle_verify(hash, &header.events_root, &hash.hash(event), proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+    use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+
+    #[test]
+    fn test_event_stream() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let keccak256 = WedprKeccak256::default();
+        let (public_key, private_key) = secp256k1.generate_keypair();
+        let mut signer =
+            EventStreamSigner::new(&secp256k1, &keccak256, &private_key, 4)
+                .unwrap();
+
+        let events: Vec<Vec<u8>> = (0..10u8)
+            .map(|i| format!("event {}", i).into_bytes())
+            .collect();
+        let mut segments = Vec::new();
+        for event in events.iter() {
+            if let Some(segment) = signer.append(event).unwrap() {
+                segments.push(segment);
+            }
+        }
+        segments.push(signer.flush().unwrap().unwrap());
+        assert!(signer.flush().unwrap().is_none());
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[2].header.event_count, 2);
+        assert!(verify_segment_chain(
+            &secp256k1,
+            &keccak256,
+            &public_key,
+            &segments
+        ));
+        // Partial chains are verifiable, but not reordered ones.
+        assert!(verify_segment_chain(
+            &secp256k1,
+            &keccak256,
+            &public_key,
+            &segments[1..]
+        ));
+        let reordered = vec![segments[0].clone(), segments[2].clone()];
+        assert!(!verify_segment_chain(
+            &secp256k1,
+            &keccak256,
+            &public_key,
+            &reordered
+        ));
+
+        // Verifies event 5 alone, which is in the second segment.
+        let proof = prove_event(&keccak256, &events[4..8], 1).unwrap();
+        assert!(verify_event(
+            &secp256k1,
+            &keccak256,
+            &public_key,
+            &segments[1],
+            5,
+            &events[5],
+            &proof
+        ));
+        assert!(!verify_event(
+            &secp256k1,
+            &keccak256,
+            &public_key,
+            &segments[1],
+            5,
+            &events[6],
+            &proof
+        ));
+        assert!(!verify_event(
+            &secp256k1,
+            &keccak256,
+            &public_key,
+            &segments[1],
+            6,
+            &events[5],
+            &proof
+        ));
+
+        let decoded_segment =
+            SignedSegment::decode(&segments[0].encode()).unwrap();
+        assert_eq!(decoded_segment, segments[0]);
+    }
+}
@@ -0,0 +1,303 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Large file signing functions, where a file is hashed in fixed size chunks
+//! into a Merkle root before signing, and chunks are hashed in parallel if
+//! feature wedpr_f_parallel is enabled.
+
+use crate::{append_bytes, append_u64, merkle::merkle_root, FieldReader};
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{Hash, Signature},
+};
+
+#[cfg(feature = "wedpr_f_parallel")]
+use rayon::prelude::*;
+
+/// Domain separator of the signed content of a file manifest.
+const FILE_MANIFEST_DOMAIN: &[u8] = b"WeDPR_FILE_MANIFEST";
+
+/// Manifest of a signed file, which lists the hashes of all chunks to allow
+/// verifying any chunk individually.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct FileManifest {
+    pub file_size: u64,
+    pub chunk_size: u64,
+    pub chunk_hashes: Vec<Vec<u8>>,
+    /// Merkle root of chunk_hashes.
+    pub root: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl FileManifest {
+    /// Computes the hash signed for a manifest.
+    fn signed_hash<H: Hash>(&self, hash: &H) -> Vec<u8> {
+        let mut hash_vec = FILE_MANIFEST_DOMAIN.to_vec();
+        append_u64(&mut hash_vec, self.file_size);
+        append_u64(&mut hash_vec, self.chunk_size);
+        append_bytes(&mut hash_vec, &self.root);
+        hash.hash(&hash_vec)
+    }
+
+    /// Encodes a manifest to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        append_u64(&mut output, self.file_size);
+        append_u64(&mut output, self.chunk_size);
+        append_bytes(&mut output, &self.root);
+        append_bytes(&mut output, &self.signature);
+        for chunk_hash in self.chunk_hashes.iter() {
+            append_bytes(&mut output, chunk_hash);
+        }
+        output
+    }
+
+    /// Decodes a manifest from bytes.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        let mut reader = FieldReader::new(input);
+        let file_size = reader.read_u64()?;
+        let chunk_size = reader.read_u64()?;
+        let root = reader.read_bytes()?.to_vec();
+        let signature = reader.read_bytes()?.to_vec();
+        let mut chunk_hashes = Vec::new();
+        while !reader.is_empty() {
+            chunk_hashes.push(reader.read_bytes()?.to_vec());
+        }
+        Ok(FileManifest {
+            file_size,
+            chunk_size,
+            chunk_hashes,
+            root,
+            signature,
+        })
+    }
+
+    /// Verifies the signature of the manifest, and the consistency between
+    /// its chunk hashes and root.
+    pub fn verify<S: Signature, H: Hash>(
+        &self,
+        signature: &S,
+        hash: &H,
+        public_key: &[u8],
+    ) -> bool {
+        if self.chunk_size == 0
+            || self.chunk_hashes.len() as u64
+                != chunk_count(self.file_size, self.chunk_size)
+        {
+            wedpr_println!("File manifest has an invalid chunk count");
+            return false;
+        }
+        match merkle_root(hash, &self.chunk_hashes) {
+            Ok(v) if v == self.root => (),
+            _ => {
+                wedpr_println!("File manifest root mismatched");
+                return false;
+            },
+        }
+        let signed_hash = self.signed_hash(hash);
+        signature.verify(public_key, &signed_hash[..], &self.signature[..])
+    }
+
+    /// Verifies a single chunk of the file against the manifest.
+    pub fn verify_chunk<S: Signature, H: Hash>(
+        &self,
+        signature: &S,
+        hash: &H,
+        public_key: &[u8],
+        chunk_index: usize,
+        chunk: &[u8],
+    ) -> bool {
+        let chunk_hash = match self.chunk_hashes.get(chunk_index) {
+            Some(v) => v,
+            None => return false,
+        };
+        let offset = chunk_index as u64 * self.chunk_size;
+        let expected_length =
+            self.chunk_size.min(self.file_size.saturating_sub(offset));
+        chunk.len() as u64 == expected_length
+            && hash.hash(chunk) == *chunk_hash
+            && self.verify(signature, hash, public_key)
+    }
+}
+
+/// Gets the number of chunks of a file, where an empty file still has one
+/// empty chunk.
+fn chunk_count(file_size: u64, chunk_size: u64) -> u64 {
+    if file_size == 0 {
+        1
+    } else {
+        (file_size + chunk_size - 1) / chunk_size
+    }
+}
+
+fn hash_chunk<H: Hash>(
+    hash: &H,
+    path: &Path,
+    offset: u64,
+    length: u64,
+) -> Result<Vec<u8>, WedprError> {
+    let mut chunk = vec![0u8; length as usize];
+    let mut file = match File::open(path) {
+        Ok(v) => v,
+        Err(_) => {
+            wedpr_println!("Opening file failed");
+            return Err(WedprError::StorageError);
+        },
+    };
+    if file.seek(SeekFrom::Start(offset)).is_err()
+        || file.read_exact(&mut chunk).is_err()
+    {
+        wedpr_println!("Reading file chunk failed");
+        return Err(WedprError::StorageError);
+    }
+    Ok(hash.hash(&chunk))
+}
+
+/// Hashes all chunks of a file.
+pub fn hash_file_chunks<H: Hash + Sync, P: AsRef<Path>>(
+    hash: &H,
+    path: P,
+    chunk_size: u64,
+) -> Result<(u64, Vec<Vec<u8>>), WedprError> {
+    if chunk_size == 0 {
+        return Err(WedprError::ArgumentError);
+    }
+    let path = path.as_ref();
+    let file_size = match path.metadata() {
+        Ok(v) => v.len(),
+        Err(_) => {
+            wedpr_println!("Reading file metadata failed");
+            return Err(WedprError::StorageError);
+        },
+    };
+    let count = chunk_count(file_size, chunk_size);
+    #[cfg(feature = "wedpr_f_parallel")]
+    let chunk_iter = (0..count).into_par_iter();
+    #[cfg(not(feature = "wedpr_f_parallel"))]
+    let chunk_iter = 0..count;
+    let chunk_hashes = chunk_iter
+        .map(|i| {
+            let offset = i * chunk_size;
+            hash_chunk(hash, path, offset, chunk_size.min(file_size - offset))
+        })
+        .collect::<Result<Vec<Vec<u8>>, WedprError>>()?;
+    Ok((file_size, chunk_hashes))
+}
+
+/// Signs a file by chunks of chunk_size bytes, and returns its manifest.
+pub fn sign_file<S: Signature, H: Hash + Sync, P: AsRef<Path>>(
+    signature: &S,
+    hash: &H,
+    private_key: &[u8],
+    path: P,
+    chunk_size: u64,
+) -> Result<FileManifest, WedprError> {
+    let (file_size, chunk_hashes) = hash_file_chunks(hash, path, chunk_size)?;
+    let mut manifest = FileManifest {
+        file_size,
+        chunk_size,
+        root: merkle_root(hash, &chunk_hashes)?,
+        chunk_hashes,
+        signature: Vec::new(),
+    };
+    let signed_hash = manifest.signed_hash(hash);
+    manifest.signature = signature.sign(private_key, &signed_hash[..])?;
+    Ok(manifest)
+}
+
+/// Verifies a whole file against its manifest.
+pub fn verify_file<S: Signature, H: Hash + Sync, P: AsRef<Path>>(
+    signature: &S,
+    hash: &H,
+    public_key: &[u8],
+    path: P,
+    manifest: &FileManifest,
+) -> bool {
+    if !manifest.verify(signature, hash, public_key) {
+        return false;
+    }
+    match hash_file_chunks(hash, path, manifest.chunk_size) {
+        Ok((file_size, chunk_hashes)) => {
+            file_size == manifest.file_size
+                && chunk_hashes == manifest.chunk_hashes
+        },
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+    use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+
+    #[test]
+    fn test_sign_file() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let keccak256 = WedprKeccak256::default();
+        let (public_key, private_key) = secp256k1.generate_keypair();
+        let path = std::env::temp_dir()
+            .join(format!("wedpr_sign_file_test_{}", std::process::id()));
+        let content: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        fs::write(&path, &content).unwrap();
+
+        let manifest =
+            sign_file(&secp256k1, &keccak256, &private_key, &path, 64).unwrap();
+        assert_eq!(manifest.chunk_hashes.len(), 16);
+        assert!(verify_file(
+            &secp256k1,
+            &keccak256,
+            &public_key,
+            &path,
+            &manifest
+        ));
+        assert_eq!(FileManifest::decode(&manifest.encode()).unwrap(), manifest);
+
+        // Verifies chunks individually, including the last partial one.
+        assert!(manifest.verify_chunk(
+            &secp256k1,
+            &keccak256,
+            &public_key,
+            3,
+            &content[192..256]
+        ));
+        assert!(manifest.verify_chunk(
+            &secp256k1,
+            &keccak256,
+            &public_key,
+            15,
+            &content[960..]
+        ));
+        assert!(!manifest.verify_chunk(
+            &secp256k1,
+            &keccak256,
+            &public_key,
+            4,
+            &content[192..256]
+        ));
+
+        let mut modified_content = content.clone();
+        modified_content[500] ^= 1;
+        fs::write(&path, &modified_content).unwrap();
+        assert!(!verify_file(
+            &secp256k1,
+            &keccak256,
+            &public_key,
+            &path,
+            &manifest
+        ));
+        fs::remove_file(&path).unwrap();
+        assert!(!verify_file(
+            &secp256k1,
+            &keccak256,
+            &public_key,
+            &path,
+            &manifest
+        ));
+    }
+}
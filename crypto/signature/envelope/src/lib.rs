@@ -0,0 +1,112 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Signature envelope functions built on replaceable Signature and Hash
+//! algorithms.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+pub mod cms;
+pub mod counter_signature;
+pub mod delegation;
+pub mod event_stream;
+pub mod file;
+pub mod merkle;
+pub mod plugin;
+pub mod receipt;
+pub mod time_bound;
+pub mod timestamp;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+/// DER helpers moved to wedpr_l_utils, re-exported for existing callers.
+pub use wedpr_l_utils::der;
+use wedpr_l_utils::error::WedprError;
+
+/// Gets the current Unix timestamp in seconds.
+pub fn current_timestamp() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(v) => v.as_secs(),
+        Err(_) => 0,
+    }
+}
+
+/// Appends a u64 value to a buffer in big-endian order.
+fn append_u64(buffer: &mut Vec<u8>, value: u64) {
+    buffer.extend_from_slice(&value.to_be_bytes());
+}
+
+/// Appends bytes to a buffer with a 4-byte big-endian length prefix.
+fn append_bytes(buffer: &mut Vec<u8>, value: &[u8]) {
+    buffer.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    buffer.extend_from_slice(value);
+}
+
+/// Reader of the fields appended by append_u64 and append_bytes.
+struct FieldReader<'a> {
+    input: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> FieldReader<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        FieldReader { input, offset: 0 }
+    }
+
+    fn read_fixed(&mut self, length: usize) -> Result<&'a [u8], WedprError> {
+        if self.input.len() - self.offset < length {
+            wedpr_println!("Envelope data is truncated");
+            return Err(WedprError::FormatError);
+        }
+        let field = &self.input[self.offset..self.offset + length];
+        self.offset += length;
+        Ok(field)
+    }
+
+    fn read_u64(&mut self) -> Result<u64, WedprError> {
+        let mut buffer = [0u8; 8];
+        buffer.copy_from_slice(self.read_fixed(8)?);
+        Ok(u64::from_be_bytes(buffer))
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8], WedprError> {
+        let mut buffer = [0u8; 4];
+        buffer.copy_from_slice(self.read_fixed(4)?);
+        self.read_fixed(u32::from_be_bytes(buffer) as usize)
+    }
+
+    /// Checks whether all the input has been consumed.
+    fn is_empty(&self) -> bool {
+        self.offset >= self.input.len()
+    }
+
+    /// Checks that all the input has been consumed.
+    fn finish(&self) -> Result<(), WedprError> {
+        if !self.is_empty() {
+            wedpr_println!("Envelope data has trailing bytes");
+            return Err(WedprError::FormatError);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_reader() {
+        let mut buffer = Vec::new();
+        append_u64(&mut buffer, 42);
+        append_bytes(&mut buffer, b"field");
+        let mut reader = FieldReader::new(&buffer);
+        assert_eq!(reader.read_u64().unwrap(), 42);
+        assert_eq!(reader.read_bytes().unwrap(), b"field");
+        assert!(reader.finish().is_ok());
+        assert!(reader.read_u64().is_err());
+
+        let mut truncated_reader =
+            FieldReader::new(&buffer[..buffer.len() - 1]);
+        truncated_reader.read_u64().unwrap();
+        assert!(truncated_reader.read_bytes().is_err());
+    }
+}
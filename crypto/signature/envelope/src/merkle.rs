@@ -0,0 +1,194 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Merkle tree functions for committing to a list of data hashes with a single
+//! root, where the inclusion of any data hash can be proven separately.
+//!
+//! Leaf and inner nodes are hashed with different prefixes to prevent second
+//! preimage attacks, and an unpaired node is promoted to the upper level as is.
+
+use crate::{append_bytes, append_u64, FieldReader};
+use wedpr_l_utils::{error::WedprError, traits::Hash};
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Inclusion proof of a data hash in a Merkle tree.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProof {
+    pub index: u64,
+    pub leaf_count: u64,
+    /// Sibling hashes from the leaf level to the root level, where levels
+    /// without a sibling are skipped.
+    pub siblings: Vec<Vec<u8>>,
+}
+
+impl MerkleProof {
+    /// Encodes a Merkle proof to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        append_u64(&mut output, self.index);
+        append_u64(&mut output, self.leaf_count);
+        for sibling in self.siblings.iter() {
+            append_bytes(&mut output, sibling);
+        }
+        output
+    }
+
+    /// Decodes a Merkle proof from bytes.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        let mut reader = FieldReader::new(input);
+        let index = reader.read_u64()?;
+        let leaf_count = reader.read_u64()?;
+        let mut siblings = Vec::new();
+        while !reader.is_empty() {
+            siblings.push(reader.read_bytes()?.to_vec());
+        }
+        Ok(MerkleProof {
+            index,
+            leaf_count,
+            siblings,
+        })
+    }
+}
+
+fn hash_leaf<H: Hash>(hash: &H, data_hash: &[u8]) -> Vec<u8> {
+    let mut hash_vec = vec![LEAF_PREFIX];
+    hash_vec.extend_from_slice(data_hash);
+    hash.hash(&hash_vec)
+}
+
+fn hash_node<H: Hash>(hash: &H, left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut hash_vec = vec![NODE_PREFIX];
+    hash_vec.extend_from_slice(left);
+    hash_vec.extend_from_slice(right);
+    hash.hash(&hash_vec)
+}
+
+fn next_level<H: Hash>(hash: &H, level: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_node(hash, left, right),
+            _ => pair[0].clone(),
+        })
+        .collect()
+}
+
+/// Computes the Merkle root of a non-empty list of data hashes.
+pub fn merkle_root<H: Hash, T: AsRef<[u8]>>(
+    hash: &H,
+    data_hashes: &[T],
+) -> Result<Vec<u8>, WedprError> {
+    if data_hashes.is_empty() {
+        return Err(WedprError::ArgumentError);
+    }
+    let mut level: Vec<Vec<u8>> = data_hashes
+        .iter()
+        .map(|data_hash| hash_leaf(hash, data_hash.as_ref()))
+        .collect();
+    while level.len() > 1 {
+        level = next_level(hash, &level);
+    }
+    Ok(level.remove(0))
+}
+
+/// Proves the inclusion of the data hash at the given index.
+pub fn merkle_prove<H: Hash, T: AsRef<[u8]>>(
+    hash: &H,
+    data_hashes: &[T],
+    index: usize,
+) -> Result<MerkleProof, WedprError> {
+    if index >= data_hashes.len() {
+        return Err(WedprError::ArgumentError);
+    }
+    let mut level: Vec<Vec<u8>> = data_hashes
+        .iter()
+        .map(|data_hash| hash_leaf(hash, data_hash.as_ref()))
+        .collect();
+    let mut position = index;
+    let mut siblings = Vec::new();
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(position ^ 1) {
+            siblings.push(sibling.clone());
+        }
+        level = next_level(hash, &level);
+        position /= 2;
+    }
+    Ok(MerkleProof {
+        index: index as u64,
+        leaf_count: data_hashes.len() as u64,
+        siblings,
+    })
+}
+
+/// Verifies the inclusion of a data hash under a Merkle root.
+pub fn merkle_verify<H: Hash>(
+    hash: &H,
+    root: &[u8],
+    data_hash: &[u8],
+    proof: &MerkleProof,
+) -> bool {
+    if proof.index >= proof.leaf_count {
+        return false;
+    }
+    let mut node = hash_leaf(hash, data_hash);
+    let mut position = proof.index;
+    let mut level_size = proof.leaf_count;
+    let mut siblings = proof.siblings.iter();
+    while level_size > 1 {
+        let sibling_position = position ^ 1;
+        if sibling_position < level_size {
+            let sibling = match siblings.next() {
+                Some(v) => v,
+                None => return false,
+            };
+            node = if position % 2 == 0 {
+                hash_node(hash, &node, sibling)
+            } else {
+                hash_node(hash, sibling, &node)
+            };
+        }
+        position /= 2;
+        level_size = (level_size + 1) / 2;
+    }
+    siblings.next().is_none() && node == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+
+    #[test]
+    fn test_merkle_tree() {
+        let keccak256 = WedprKeccak256::default();
+        for leaf_count in 1..10usize {
+            let data_hashes: Vec<Vec<u8>> = (0..leaf_count)
+                .map(|i| keccak256.hash(&[i as u8]))
+                .collect();
+            let root = merkle_root(&keccak256, &data_hashes).unwrap();
+            for (i, data_hash) in data_hashes.iter().enumerate() {
+                let proof = merkle_prove(&keccak256, &data_hashes, i).unwrap();
+                assert!(merkle_verify(&keccak256, &root, data_hash, &proof));
+                assert_eq!(
+                    MerkleProof::decode(&proof.encode()).unwrap(),
+                    proof
+                );
+
+                let mut wrong_index_proof = proof.clone();
+                wrong_index_proof.index = ((i + 1) % leaf_count) as u64;
+                if leaf_count > 1 {
+                    assert!(!merkle_verify(
+                        &keccak256,
+                        &root,
+                        data_hash,
+                        &wrong_index_proof
+                    ));
+                }
+            }
+            assert!(merkle_prove(&keccak256, &data_hashes, leaf_count).is_err());
+        }
+        let empty: Vec<Vec<u8>> = Vec::new();
+        assert!(merkle_root(&keccak256, &empty).is_err());
+    }
+}
@@ -0,0 +1,271 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Code-signing functions for WASM plugin bundles, where the manifest of a
+//! module is signed by its publisher and can be endorsed by further signers
+//! such as a plugin registry.
+
+use crate::{append_bytes, counter_signature::SignatureEnvelope, FieldReader};
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{Hash, Signature},
+};
+
+/// Domain separator of the hash of a plugin manifest.
+const PLUGIN_MANIFEST_DOMAIN: &[u8] = b"WeDPR_WASM_PLUGIN_MANIFEST";
+/// Magic number and version 1 at the beginning of a binary WASM module.
+const WASM_MODULE_HEADER: &[u8] = b"\0asm\x01\0\0\0";
+
+/// Manifest describing a WASM plugin module.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PluginManifest {
+    pub name: String,
+    pub version: String,
+    pub module_hash: Vec<u8>,
+    /// Application-defined metadata, e.g. required host capabilities.
+    pub metadata: Vec<u8>,
+}
+
+impl PluginManifest {
+    /// Creates a manifest for a binary WASM module.
+    pub fn new<H: Hash>(
+        hash: &H,
+        name: &str,
+        version: &str,
+        module: &[u8],
+        metadata: &[u8],
+    ) -> Result<Self, WedprError> {
+        if !module.starts_with(WASM_MODULE_HEADER) {
+            wedpr_println!("Plugin module is not a binary WASM module");
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(PluginManifest {
+            name: name.to_string(),
+            version: version.to_string(),
+            module_hash: hash.hash(module),
+            metadata: metadata.to_vec(),
+        })
+    }
+
+    /// Computes the manifest hash signed by the publisher.
+    pub fn hash<H: Hash>(&self, hash: &H) -> Vec<u8> {
+        let mut hash_vec = PLUGIN_MANIFEST_DOMAIN.to_vec();
+        hash_vec.append(&mut self.encode());
+        hash.hash(&hash_vec)
+    }
+
+    /// Encodes a manifest to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        append_bytes(&mut output, self.name.as_bytes());
+        append_bytes(&mut output, self.version.as_bytes());
+        append_bytes(&mut output, &self.module_hash);
+        append_bytes(&mut output, &self.metadata);
+        output
+    }
+
+    /// Decodes a manifest from bytes.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        let mut reader = FieldReader::new(input);
+        let name = read_string(&mut reader)?;
+        let version = read_string(&mut reader)?;
+        let module_hash = reader.read_bytes()?.to_vec();
+        let metadata = reader.read_bytes()?.to_vec();
+        reader.finish()?;
+        Ok(PluginManifest {
+            name,
+            version,
+            module_hash,
+            metadata,
+        })
+    }
+}
+
+fn read_string(reader: &mut FieldReader) -> Result<String, WedprError> {
+    match String::from_utf8(reader.read_bytes()?.to_vec()) {
+        Ok(v) => Ok(v),
+        Err(_) => Err(WedprError::FormatError),
+    }
+}
+
+/// A plugin manifest with its signer chain, starting from the publisher.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct SignedPlugin {
+    pub manifest: PluginManifest,
+    pub envelope: SignatureEnvelope,
+}
+
+impl SignedPlugin {
+    /// Signs a plugin manifest as its publisher.
+    pub fn sign<S: Signature, H: Hash>(
+        signature: &S,
+        hash: &H,
+        private_key: &[u8],
+        public_key: &[u8],
+        manifest: PluginManifest,
+    ) -> Result<Self, WedprError> {
+        let envelope = SignatureEnvelope::sign(
+            signature,
+            hash,
+            private_key,
+            public_key,
+            &manifest.hash(hash),
+            b"publisher",
+        )?;
+        Ok(SignedPlugin { manifest, envelope })
+    }
+
+    /// Endorses the plugin after the current signers, with the role of the
+    /// endorser, e.g. "registry" or "auditor".
+    pub fn endorse<S: Signature, H: Hash>(
+        &mut self,
+        signature: &S,
+        hash: &H,
+        private_key: &[u8],
+        public_key: &[u8],
+        role: &str,
+    ) -> Result<(), WedprError> {
+        self.envelope.counter_sign(
+            signature,
+            hash,
+            private_key,
+            public_key,
+            role.as_bytes(),
+        )
+    }
+
+    /// Gets the public key of the publisher.
+    pub fn publisher(&self) -> Option<&[u8]> {
+        self.envelope
+            .signatures
+            .first()
+            .map(|signature| &signature.public_key[..])
+    }
+
+    /// Verifies a WASM module against the signed plugin, where the publisher
+    /// should be one of the trusted publishers.
+    pub fn verify<S: Signature, H: Hash, P: AsRef<[u8]>>(
+        &self,
+        signature: &S,
+        hash: &H,
+        module: &[u8],
+        trusted_publishers: &[P],
+    ) -> bool {
+        let publisher = match self.publisher() {
+            Some(v) => v,
+            None => return false,
+        };
+        if !trusted_publishers
+            .iter()
+            .any(|trusted| trusted.as_ref() == publisher)
+        {
+            wedpr_println!("Plugin publisher is not trusted");
+            return false;
+        }
+        if hash.hash(module) != self.manifest.module_hash {
+            wedpr_println!("Plugin module hash mismatched");
+            return false;
+        }
+        self.envelope.msg_hash == self.manifest.hash(hash)
+            && self.envelope.verify_chain(signature, hash)
+    }
+
+    /// Encodes a signed plugin to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        append_bytes(&mut output, &self.manifest.encode());
+        append_bytes(&mut output, &self.envelope.encode());
+        output
+    }
+
+    /// Decodes a signed plugin from bytes.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        let mut reader = FieldReader::new(input);
+        let manifest = PluginManifest::decode(reader.read_bytes()?)?;
+        let envelope = SignatureEnvelope::decode(reader.read_bytes()?)?;
+        reader.finish()?;
+        Ok(SignedPlugin { manifest, envelope })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+    use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+
+    #[test]
+    fn test_signed_plugin() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let keccak256 = WedprKeccak256::default();
+        let (publisher_public_key, publisher_private_key) =
+            secp256k1.generate_keypair();
+        let (registry_public_key, registry_private_key) =
+            secp256k1.generate_keypair();
+        let mut module = WASM_MODULE_HEADER.to_vec();
+        module.extend_from_slice(b"module body");
+
+        let manifest = PluginManifest::new(
+            &keccak256,
+            "psi",
+            "1.0.0",
+            &module,
+            b"capabilities",
+        )
+        .unwrap();
+        let mut signed_plugin = SignedPlugin::sign(
+            &secp256k1,
+            &keccak256,
+            &publisher_private_key,
+            &publisher_public_key,
+            manifest,
+        )
+        .unwrap();
+        signed_plugin
+            .endorse(
+                &secp256k1,
+                &keccak256,
+                &registry_private_key,
+                &registry_public_key,
+                "registry",
+            )
+            .unwrap();
+        let trusted_publishers = vec![publisher_public_key.clone()];
+        assert!(signed_plugin.verify(
+            &secp256k1,
+            &keccak256,
+            &module,
+            &trusted_publishers
+        ));
+
+        // Untrusted publisher.
+        assert!(!signed_plugin.verify(&secp256k1, &keccak256, &module, &[
+            registry_public_key.clone()
+        ]));
+        // Modified module.
+        let mut modified_module = module.clone();
+        modified_module.push(0);
+        assert!(!signed_plugin.verify(
+            &secp256k1,
+            &keccak256,
+            &modified_module,
+            &trusted_publishers
+        ));
+        // Modified manifest.
+        let mut modified_plugin = signed_plugin.clone();
+        modified_plugin.manifest.version = "1.0.1".to_string();
+        assert!(!modified_plugin.verify(
+            &secp256k1,
+            &keccak256,
+            &module,
+            &trusted_publishers
+        ));
+
+        let decoded_plugin =
+            SignedPlugin::decode(&signed_plugin.encode()).unwrap();
+        assert_eq!(decoded_plugin, signed_plugin);
+        assert!(
+            PluginManifest::new(&keccak256, "psi", "1.0.0", b"text", b"")
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Computation receipts, which attest that an off-chain computation ran the
+//! code of a hash on committed inputs and produced an output of a hash, so
+//! that the result can be anchored on chain by the receipt hash and audited
+//! later by revealing the inputs and the output.
+
+use crate::{append_bytes, append_u64, FieldReader};
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{Hash, Signature},
+};
+
+/// Domain separator of input commitments.
+const RECEIPT_INPUT_DOMAIN: &[u8] = b"WeDPR_RECEIPT_INPUT";
+/// Domain separator of the signed content of a computation receipt.
+const RECEIPT_DOMAIN: &[u8] = b"WeDPR_COMPUTATION_RECEIPT";
+
+/// Commits to a computation input with a random salt, which hides the input
+/// until the salt is revealed.
+pub fn commit_input<H: Hash>(hash: &H, input: &[u8], salt: &[u8]) -> Vec<u8> {
+    let mut hash_vec = RECEIPT_INPUT_DOMAIN.to_vec();
+    append_bytes(&mut hash_vec, salt);
+    append_bytes(&mut hash_vec, input);
+    hash.hash(&hash_vec)
+}
+
+/// Receipt of an off-chain computation signed by the party running it.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ComputationReceipt {
+    pub input_commitments: Vec<Vec<u8>>,
+    pub code_hash: Vec<u8>,
+    pub output_hash: Vec<u8>,
+    pub timestamp: u64,
+    pub signer_public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl ComputationReceipt {
+    /// Creates an unsigned receipt.
+    pub fn new(
+        input_commitments: Vec<Vec<u8>>,
+        code_hash: &[u8],
+        output_hash: &[u8],
+        timestamp: u64,
+        signer_public_key: &[u8],
+    ) -> Self {
+        ComputationReceipt {
+            input_commitments,
+            code_hash: code_hash.to_vec(),
+            output_hash: output_hash.to_vec(),
+            timestamp,
+            signer_public_key: signer_public_key.to_vec(),
+            signature: Vec::new(),
+        }
+    }
+
+    /// Encodes the content covered by the signature.
+    fn encode_content(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        append_u64(&mut output, self.input_commitments.len() as u64);
+        for commitment in &self.input_commitments {
+            append_bytes(&mut output, commitment);
+        }
+        append_bytes(&mut output, &self.code_hash);
+        append_bytes(&mut output, &self.output_hash);
+        append_u64(&mut output, self.timestamp);
+        append_bytes(&mut output, &self.signer_public_key);
+        output
+    }
+
+    /// Computes the hash signed by the signer, which also identifies the
+    /// receipt when anchored on chain.
+    pub fn receipt_hash<H: Hash>(&self, hash: &H) -> Vec<u8> {
+        let mut hash_vec = RECEIPT_DOMAIN.to_vec();
+        hash_vec.extend_from_slice(&self.encode_content());
+        hash.hash(&hash_vec)
+    }
+
+    /// Signs the receipt by the private key of the signer.
+    pub fn sign<S: Signature, H: Hash>(
+        &mut self,
+        signature: &S,
+        hash: &H,
+        private_key: &[u8],
+    ) -> Result<(), WedprError> {
+        let receipt_hash = self.receipt_hash(hash);
+        self.signature = signature.sign(private_key, &receipt_hash[..])?;
+        Ok(())
+    }
+
+    /// Verifies the signature of the signer on the receipt.
+    pub fn verify<S: Signature, H: Hash>(
+        &self,
+        signature: &S,
+        hash: &H,
+    ) -> bool {
+        let receipt_hash = self.receipt_hash(hash);
+        signature.verify(
+            &self.signer_public_key[..],
+            &receipt_hash[..],
+            &self.signature[..],
+        )
+    }
+
+    /// Checks whether a revealed input matches the commitment at the index.
+    pub fn check_input<H: Hash>(
+        &self,
+        hash: &H,
+        index: usize,
+        input: &[u8],
+        salt: &[u8],
+    ) -> bool {
+        match self.input_commitments.get(index) {
+            Some(v) => *v == commit_input(hash, input, salt),
+            None => false,
+        }
+    }
+
+    /// Checks whether a revealed output matches the output hash.
+    pub fn check_output<H: Hash>(&self, hash: &H, output: &[u8]) -> bool {
+        self.output_hash == hash.hash(output)
+    }
+
+    /// Encodes a receipt to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = self.encode_content();
+        append_bytes(&mut output, &self.signature);
+        output
+    }
+
+    /// Decodes a receipt from bytes.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        let mut reader = FieldReader::new(input);
+        let input_count = reader.read_u64()?;
+        let mut input_commitments = Vec::new();
+        for _ in 0..input_count {
+            input_commitments.push(reader.read_bytes()?.to_vec());
+        }
+        let code_hash = reader.read_bytes()?.to_vec();
+        let output_hash = reader.read_bytes()?.to_vec();
+        let timestamp = reader.read_u64()?;
+        let signer_public_key = reader.read_bytes()?.to_vec();
+        let signature = reader.read_bytes()?.to_vec();
+        reader.finish()?;
+        Ok(ComputationReceipt {
+            input_commitments,
+            code_hash,
+            output_hash,
+            timestamp,
+            signer_public_key,
+            signature,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+    use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+
+    #[test]
+    fn test_computation_receipt() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let keccak256 = WedprKeccak256::default();
+        let (public_key, private_key) = secp256k1.generate_keypair();
+        let inputs: [&[u8]; 2] = [b"dataset of party A", b"dataset of party B"];
+        let salts: [&[u8]; 2] = [b"salt A", b"salt B"];
+        let commitments = inputs
+            .iter()
+            .zip(&salts)
+            .map(|(input, salt)| commit_input(&keccak256, input, salt))
+            .collect();
+        let output = b"joint statistics";
+
+        let mut receipt = ComputationReceipt::new(
+            commitments,
+            &keccak256.hash("wasm module of the computation"),
+            &keccak256.hash(output),
+            1600000000,
+            &public_key,
+        );
+        receipt.sign(&secp256k1, &keccak256, &private_key).unwrap();
+        assert!(receipt.verify(&secp256k1, &keccak256));
+        assert!(receipt.check_input(&keccak256, 1, inputs[1], salts[1]));
+        assert!(!receipt.check_input(&keccak256, 0, inputs[1], salts[1]));
+        assert!(!receipt.check_input(&keccak256, 2, inputs[1], salts[1]));
+        assert!(receipt.check_output(&keccak256, output));
+        assert!(!receipt.check_output(&keccak256, b"forged statistics"));
+
+        let decoded_receipt =
+            ComputationReceipt::decode(&receipt.encode()).unwrap();
+        assert_eq!(decoded_receipt, receipt);
+        assert_eq!(
+            decoded_receipt.receipt_hash(&keccak256),
+            receipt.receipt_hash(&keccak256)
+        );
+        let encoded_receipt = receipt.encode();
+        assert!(ComputationReceipt::decode(
+            &encoded_receipt[..encoded_receipt.len() - 1]
+        )
+        .is_err());
+
+        // The output hash cannot be replaced without the private key.
+        let mut forged_receipt = receipt.clone();
+        forged_receipt.output_hash = keccak256.hash("forged statistics");
+        assert!(!forged_receipt.verify(&secp256k1, &keccak256));
+    }
+}
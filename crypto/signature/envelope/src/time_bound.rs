@@ -0,0 +1,173 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Time-bound signatures, whose validity window is signed together with the
+//! message hash.
+
+use crate::{append_bytes, append_u64, FieldReader};
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{Hash, Signature},
+};
+
+/// Domain separator of the signed content of a time-bound signature.
+const TIME_BOUND_DOMAIN: &[u8] = b"WeDPR_TIME_BOUND_SIGNATURE";
+
+/// Signature valid within [not_before, not_after] in Unix seconds.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct TimeBoundSignature {
+    pub not_before: u64,
+    pub not_after: u64,
+    pub signature: Vec<u8>,
+}
+
+impl TimeBoundSignature {
+    /// Encodes a time-bound signature to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        append_u64(&mut output, self.not_before);
+        append_u64(&mut output, self.not_after);
+        append_bytes(&mut output, &self.signature);
+        output
+    }
+
+    /// Decodes a time-bound signature from bytes.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        let mut reader = FieldReader::new(input);
+        let not_before = reader.read_u64()?;
+        let not_after = reader.read_u64()?;
+        let signature = reader.read_bytes()?.to_vec();
+        reader.finish()?;
+        Ok(TimeBoundSignature {
+            not_before,
+            not_after,
+            signature,
+        })
+    }
+}
+
+/// Computes the hash actually signed by a time-bound signature.
+fn time_bound_hash<H: Hash>(
+    hash: &H,
+    msg_hash: &[u8],
+    not_before: u64,
+    not_after: u64,
+) -> Vec<u8> {
+    let mut hash_vec = TIME_BOUND_DOMAIN.to_vec();
+    append_u64(&mut hash_vec, not_before);
+    append_u64(&mut hash_vec, not_after);
+    append_bytes(&mut hash_vec, msg_hash);
+    hash.hash(&hash_vec)
+}
+
+/// Signs a message hash which is only valid within [not_before, not_after].
+pub fn sign_time_bound<S: Signature, H: Hash>(
+    signature: &S,
+    hash: &H,
+    private_key: &[u8],
+    msg_hash: &[u8],
+    not_before: u64,
+    not_after: u64,
+) -> Result<TimeBoundSignature, WedprError> {
+    if not_before > not_after {
+        wedpr_println!("Validity window is empty");
+        return Err(WedprError::ArgumentError);
+    }
+    let bound_hash = time_bound_hash(hash, msg_hash, not_before, not_after);
+    Ok(TimeBoundSignature {
+        not_before,
+        not_after,
+        signature: signature.sign(private_key, &bound_hash[..])?,
+    })
+}
+
+/// Verifies a time-bound signature at the given time, where allowed_skew
+/// extends both ends of the validity window to tolerate clock differences
+/// between the signer and the verifier.
+pub fn verify_time_bound<S: Signature, H: Hash>(
+    signature: &S,
+    hash: &H,
+    public_key: &[u8],
+    msg_hash: &[u8],
+    time_bound_signature: &TimeBoundSignature,
+    now: u64,
+    allowed_skew: u64,
+) -> bool {
+    if now.saturating_add(allowed_skew) < time_bound_signature.not_before
+        || now.saturating_sub(allowed_skew) > time_bound_signature.not_after
+    {
+        wedpr_println!("Time-bound signature is not valid at this time");
+        return false;
+    }
+    let bound_hash = time_bound_hash(
+        hash,
+        msg_hash,
+        time_bound_signature.not_before,
+        time_bound_signature.not_after,
+    );
+    signature.verify(
+        public_key,
+        &bound_hash[..],
+        &time_bound_signature.signature[..],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+    use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+    use wedpr_l_utils::constant::tests::BASE64_ENCODED_TEST_MESSAGE;
+
+    #[test]
+    fn test_time_bound_signature() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let keccak256 = WedprKeccak256::default();
+        let (public_key, private_key) = secp256k1.generate_keypair();
+        let msg_hash = BASE64_ENCODED_TEST_MESSAGE;
+
+        let time_bound_signature = sign_time_bound(
+            &secp256k1,
+            &keccak256,
+            &private_key,
+            &msg_hash,
+            1000,
+            2000,
+        )
+        .unwrap();
+        let verify_at = |signature: &TimeBoundSignature, now: u64| {
+            verify_time_bound(
+                &secp256k1,
+                &keccak256,
+                &public_key,
+                &msg_hash,
+                signature,
+                now,
+                10,
+            )
+        };
+        assert!(verify_at(&time_bound_signature, 1500));
+        // Clock skew is tolerated at both ends.
+        assert!(verify_at(&time_bound_signature, 990));
+        assert!(verify_at(&time_bound_signature, 2010));
+        assert!(!verify_at(&time_bound_signature, 989));
+        assert!(!verify_at(&time_bound_signature, 2011));
+
+        // The validity window cannot be changed without the private key.
+        let mut extended_signature = time_bound_signature.clone();
+        extended_signature.not_after = 3000;
+        assert!(!verify_at(&extended_signature, 1500));
+
+        let decoded_signature =
+            TimeBoundSignature::decode(&time_bound_signature.encode()).unwrap();
+        assert_eq!(decoded_signature, time_bound_signature);
+        assert!(sign_time_bound(
+            &secp256k1,
+            &keccak256,
+            &private_key,
+            &msg_hash,
+            2000,
+            1000
+        )
+        .is_err());
+    }
+}
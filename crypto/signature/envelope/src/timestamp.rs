@@ -0,0 +1,705 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! RFC 3161 timestamping functions, including timestamp request generation,
+//! and timestamp response parsing and verification.
+
+use crate::der::{
+    context_tag, decode_u64, encode_sequence, encode_tlv, encode_u64,
+    DerReader, TAG_BOOLEAN, TAG_GENERALIZED_TIME, TAG_INTEGER, TAG_NULL,
+    TAG_OCTET_STRING, TAG_OID, TAG_SEQUENCE, TAG_SET,
+};
+use sha1::{Digest, Sha1};
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{Hash, Signature},
+};
+
+/// DER content of OID 2.16.840.1.101.3.4.2.1 (SHA-256).
+pub const OID_SHA256: &[u8] =
+    &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+/// DER content of OID 1.2.156.10197.1.401 (SM3).
+pub const OID_SM3: &[u8] = &[0x2a, 0x81, 0x1c, 0xcf, 0x55, 0x01, 0x83, 0x11];
+/// DER content of OID 1.3.14.3.2.26 (SHA-1), which only identifies TSA
+/// certificates in ESSCertID.
+pub const OID_SHA1: &[u8] = &[0x2b, 0x0e, 0x03, 0x02, 0x1a];
+/// DER content of OID 1.2.840.113549.1.7.2 (CMS signed data).
+pub const OID_SIGNED_DATA: &[u8] =
+    &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+/// DER content of OID 1.2.840.113549.1.9.16.1.4 (timestamp token info).
+pub const OID_TST_INFO: &[u8] = &[
+    0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x01, 0x04,
+];
+/// DER content of OID 1.2.840.113549.1.9.4 (message digest attribute).
+pub const OID_MESSAGE_DIGEST: &[u8] =
+    &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x04];
+
+/// DER content of OID 1.2.840.113549.1.9.3 (content type attribute).
+pub const OID_CONTENT_TYPE: &[u8] =
+    &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x03];
+/// DER content of OID 1.2.840.113549.1.9.16.2.12 (signing certificate
+/// attribute).
+pub const OID_SIGNING_CERTIFICATE: &[u8] = &[
+    0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x02, 0x0c,
+];
+/// DER content of OID 1.2.840.113549.1.9.16.2.47 (signing certificate v2
+/// attribute).
+pub const OID_SIGNING_CERTIFICATE_V2: &[u8] = &[
+    0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x02, 0x2f,
+];
+
+/// PKIStatus values indicating a granted timestamp.
+const PKI_STATUS_GRANTED: u64 = 0;
+const PKI_STATUS_GRANTED_WITH_MODS: u64 = 1;
+
+/// Timestamp request sent to a timestamp authority (TSA).
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct TimestampRequest {
+    /// DER content of the hash algorithm OID.
+    pub hash_algorithm: Vec<u8>,
+    pub hashed_message: Vec<u8>,
+    pub nonce: Option<u64>,
+    /// Whether the TSA should include its certificate in the response.
+    pub cert_req: bool,
+}
+
+impl TimestampRequest {
+    /// Encodes a timestamp request to a DER TimeStampReq.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut fields = vec![
+            encode_u64(1),
+            encode_message_imprint(&self.hash_algorithm, &self.hashed_message),
+        ];
+        if let Some(nonce) = self.nonce {
+            fields.push(encode_u64(nonce));
+        }
+        if self.cert_req {
+            fields.push(encode_tlv(TAG_BOOLEAN, &[0xff]));
+        }
+        encode_sequence(&fields)
+    }
+}
+
+/// Timestamp information (TSTInfo) signed by a TSA.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct TimestampInfo {
+    /// DER content of the TSA policy OID.
+    pub policy: Vec<u8>,
+    /// DER content of the hash algorithm OID.
+    pub hash_algorithm: Vec<u8>,
+    pub hashed_message: Vec<u8>,
+    /// Serial number in big-endian bytes.
+    pub serial_number: Vec<u8>,
+    /// Signing time in Unix seconds.
+    pub gen_time: u64,
+    pub nonce: Option<u64>,
+}
+
+/// Timestamp token parsed from a TSA response.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct TimestampToken {
+    pub info: TimestampInfo,
+    /// DER encoding of TSTInfo, which is digested by the TSA.
+    pub encoded_info: Vec<u8>,
+    /// The digest of encoded_info declared in signed attributes.
+    pub message_digest: Vec<u8>,
+    /// DER content of the content type OID declared in signed attributes.
+    pub content_type: Vec<u8>,
+    /// DER content of the hash algorithm OID of the TSA certificate hash,
+    /// which is empty if no signing certificate attribute is present.
+    pub certificate_hash_algorithm: Vec<u8>,
+    /// Hash of the TSA certificate declared in the signing certificate
+    /// attribute.
+    pub certificate_hash: Vec<u8>,
+    /// DER encoding of signed attributes re-tagged as a SET, which is the
+    /// content actually signed by the TSA.
+    pub signed_attributes: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+fn encode_message_imprint(
+    hash_algorithm: &[u8],
+    hashed_message: &[u8],
+) -> Vec<u8> {
+    encode_sequence(&[
+        encode_sequence(&[
+            encode_tlv(TAG_OID, hash_algorithm),
+            encode_tlv(TAG_NULL, &[]),
+        ]),
+        encode_tlv(TAG_OCTET_STRING, hashed_message),
+    ])
+}
+
+/// Parses a DER TimeStampResp from a TSA, and returns its timestamp token if
+/// the timestamp is granted.
+pub fn parse_timestamp_response(
+    response: &[u8],
+) -> Result<TimestampToken, WedprError> {
+    let mut reader = DerReader::new(response);
+    let mut fields =
+        DerReader::new(reader.read_expected(TAG_SEQUENCE)?.content);
+    let mut status_info =
+        DerReader::new(fields.read_expected(TAG_SEQUENCE)?.content);
+    let status = decode_u64(status_info.read_expected(TAG_INTEGER)?.content)?;
+    if status != PKI_STATUS_GRANTED && status != PKI_STATUS_GRANTED_WITH_MODS {
+        wedpr_println!("Timestamp is rejected with status {}", status);
+        return Err(WedprError::VerificationError);
+    }
+    parse_timestamp_token(fields.read_expected(TAG_SEQUENCE)?.encoded)
+}
+
+/// Parses a DER timestamp token, i.e. a CMS ContentInfo of signed data.
+pub fn parse_timestamp_token(
+    token: &[u8],
+) -> Result<TimestampToken, WedprError> {
+    let mut reader = DerReader::new(token);
+    let mut content_info =
+        DerReader::new(reader.read_expected(TAG_SEQUENCE)?.content);
+    if content_info.read_expected(TAG_OID)?.content != OID_SIGNED_DATA {
+        wedpr_println!("Timestamp token is not signed data");
+        return Err(WedprError::FormatError);
+    }
+    let mut explicit_content =
+        DerReader::new(content_info.read_expected(context_tag(0))?.content);
+    let mut signed_data =
+        DerReader::new(explicit_content.read_expected(TAG_SEQUENCE)?.content);
+    // Skips version and digest algorithms.
+    signed_data.read_expected(TAG_INTEGER)?;
+    signed_data.read_expected(TAG_SET)?;
+
+    let mut encap_content =
+        DerReader::new(signed_data.read_expected(TAG_SEQUENCE)?.content);
+    if encap_content.read_expected(TAG_OID)?.content != OID_TST_INFO {
+        wedpr_println!("Timestamp token does not contain TSTInfo");
+        return Err(WedprError::FormatError);
+    }
+    let mut explicit_info =
+        DerReader::new(encap_content.read_expected(context_tag(0))?.content);
+    let encoded_info = explicit_info
+        .read_expected(TAG_OCTET_STRING)?
+        .content
+        .to_vec();
+    let info = parse_timestamp_info(&encoded_info)?;
+
+    // Skips certificates and CRLs.
+    signed_data.read_optional(context_tag(0))?;
+    signed_data.read_optional(context_tag(1))?;
+    let mut signer_infos =
+        DerReader::new(signed_data.read_expected(TAG_SET)?.content);
+    let mut signer_info =
+        DerReader::new(signer_infos.read_expected(TAG_SEQUENCE)?.content);
+    // Skips version, signer identifier and digest algorithm.
+    signer_info.read_expected(TAG_INTEGER)?;
+    signer_info.read()?;
+    signer_info.read_expected(TAG_SEQUENCE)?;
+    let attributes = signer_info.read_expected(context_tag(0))?;
+    let mut token = parse_signed_attributes(attributes.content)?;
+    // Skips signature algorithm.
+    signer_info.read_expected(TAG_SEQUENCE)?;
+    let signature = signer_info.read_expected(TAG_OCTET_STRING)?.content;
+
+    // Signed attributes are signed with the SET OF tag instead of the
+    // implicit context-specific tag.
+    token.signed_attributes = attributes.encoded.to_vec();
+    token.signed_attributes[0] = TAG_SET;
+    token.info = info;
+    token.encoded_info = encoded_info;
+    token.signature = signature.to_vec();
+    Ok(token)
+}
+
+fn parse_timestamp_info(
+    encoded_info: &[u8],
+) -> Result<TimestampInfo, WedprError> {
+    let mut reader = DerReader::new(encoded_info);
+    let mut fields =
+        DerReader::new(reader.read_expected(TAG_SEQUENCE)?.content);
+    // Skips version.
+    fields.read_expected(TAG_INTEGER)?;
+    let policy = fields.read_expected(TAG_OID)?.content.to_vec();
+    let mut imprint =
+        DerReader::new(fields.read_expected(TAG_SEQUENCE)?.content);
+    let mut algorithm =
+        DerReader::new(imprint.read_expected(TAG_SEQUENCE)?.content);
+    let hash_algorithm = algorithm.read_expected(TAG_OID)?.content.to_vec();
+    let hashed_message =
+        imprint.read_expected(TAG_OCTET_STRING)?.content.to_vec();
+    let serial_number = fields.read_expected(TAG_INTEGER)?.content.to_vec();
+    let gen_time = parse_generalized_time(
+        fields.read_expected(TAG_GENERALIZED_TIME)?.content,
+    )?;
+    // Skips accuracy and ordering.
+    fields.read_optional(TAG_SEQUENCE)?;
+    fields.read_optional(TAG_BOOLEAN)?;
+    let nonce = match fields.read_optional(TAG_INTEGER)? {
+        Some(v) => Some(decode_u64(v.content)?),
+        None => None,
+    };
+    Ok(TimestampInfo {
+        policy,
+        hash_algorithm,
+        hashed_message,
+        serial_number,
+        gen_time,
+        nonce,
+    })
+}
+
+/// Parses the signed attributes of a timestamp token, where the message
+/// digest and content type attributes are required.
+fn parse_signed_attributes(
+    attributes: &[u8],
+) -> Result<TimestampToken, WedprError> {
+    let mut token = TimestampToken::default();
+    let mut reader = DerReader::new(attributes);
+    while !reader.is_empty() {
+        let mut attribute =
+            DerReader::new(reader.read_expected(TAG_SEQUENCE)?.content);
+        let attribute_type = attribute.read_expected(TAG_OID)?.content;
+        let mut values =
+            DerReader::new(attribute.read_expected(TAG_SET)?.content);
+        if attribute_type == OID_MESSAGE_DIGEST {
+            token.message_digest =
+                values.read_expected(TAG_OCTET_STRING)?.content.to_vec();
+        } else if attribute_type == OID_CONTENT_TYPE {
+            token.content_type =
+                values.read_expected(TAG_OID)?.content.to_vec();
+        } else if attribute_type == OID_SIGNING_CERTIFICATE
+            || attribute_type == OID_SIGNING_CERTIFICATE_V2
+        {
+            // The first certificate identifier of SigningCertificate(V2)
+            // identifies the signing certificate.
+            let mut signing_certificate =
+                DerReader::new(values.read_expected(TAG_SEQUENCE)?.content);
+            let mut cert_ids = DerReader::new(
+                signing_certificate.read_expected(TAG_SEQUENCE)?.content,
+            );
+            let mut cert_id =
+                DerReader::new(cert_ids.read_expected(TAG_SEQUENCE)?.content);
+            token.certificate_hash_algorithm =
+                if attribute_type == OID_SIGNING_CERTIFICATE {
+                    OID_SHA1.to_vec()
+                } else {
+                    // ESSCertIDv2 defaults to SHA-256.
+                    match cert_id.read_optional(TAG_SEQUENCE)? {
+                        Some(v) => DerReader::new(v.content)
+                            .read_expected(TAG_OID)?
+                            .content
+                            .to_vec(),
+                        None => OID_SHA256.to_vec(),
+                    }
+                };
+            token.certificate_hash =
+                cert_id.read_expected(TAG_OCTET_STRING)?.content.to_vec();
+        }
+    }
+    if token.message_digest.is_empty() || token.content_type.is_empty() {
+        wedpr_println!("Message digest or content type attribute is missing");
+        return Err(WedprError::FormatError);
+    }
+    Ok(token)
+}
+
+/// Gets the number of days in a month of a year.
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if year.is_multiple_of(4)
+            && (!year.is_multiple_of(100) || year.is_multiple_of(400)) =>
+        {
+            29
+        },
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Parses a GeneralizedTime in the form of YYYYMMDDhhmmss[.fff]Z to Unix
+/// seconds, where fractional seconds are truncated.
+fn parse_generalized_time(content: &[u8]) -> Result<u64, WedprError> {
+    if content.len() < 15
+        || content[content.len() - 1] != b'Z'
+        || !content[..14].iter().all(|byte| byte.is_ascii_digit())
+    {
+        return Err(WedprError::FormatError);
+    }
+    let number = |start: usize, end: usize| {
+        content[start..end]
+            .iter()
+            .fold(0u64, |acc, byte| acc * 10 + (byte - b'0') as u64)
+    };
+    let (year, month, day) = (number(0, 4), number(4, 6), number(6, 8));
+    let (hour, minute, second) =
+        (number(8, 10), number(10, 12), number(12, 14));
+    if year < 1970
+        || !(1..=12).contains(&month)
+        || day < 1
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return Err(WedprError::FormatError);
+    }
+    // Converts the civil date to days since 1970-01-01, treating March as the
+    // first month so that the leap day is at the end of a year.
+    let (shifted_year, shifted_month) = if month > 2 {
+        (year, month - 3)
+    } else {
+        (year - 1, month + 9)
+    };
+    let era_day = 365 * shifted_year + shifted_year / 4 - shifted_year / 100
+        + shifted_year / 400
+        + (153 * shifted_month + 2) / 5
+        + day
+        - 1;
+    // 719468 is the day number of 1970-01-01 in the same calendar.
+    let days = era_day - 719468;
+    Ok(days * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+/// Verifies a timestamp token for the request it responds to, where the
+/// signature and hash algorithms should match the ones used by the TSA,
+/// certificate is the DER encoding of the TSA certificate, and public_key is
+/// the public key of that certificate. The message imprint and the nonce of
+/// the request should be kept in the token, which prevents replaying tokens
+/// of other requests or substituting the hash algorithm, and the signing
+/// certificate attribute should identify the TSA certificate.
+pub fn verify_timestamp_token<S: Signature, H: Hash>(
+    signature: &S,
+    hash: &H,
+    certificate: &[u8],
+    public_key: &[u8],
+    request: &TimestampRequest,
+    token: &TimestampToken,
+) -> bool {
+    if token.content_type != OID_TST_INFO {
+        wedpr_println!("Timestamp signed content type is not TSTInfo");
+        return false;
+    }
+    if token.info.hash_algorithm != request.hash_algorithm {
+        wedpr_println!("Timestamp hash algorithm mismatched");
+        return false;
+    }
+    if token.info.hashed_message != request.hashed_message {
+        wedpr_println!("Timestamp message imprint mismatched");
+        return false;
+    }
+    if request.nonce.is_some() && token.info.nonce != request.nonce {
+        wedpr_println!("Timestamp nonce mismatched");
+        return false;
+    }
+    if hash.hash(&token.encoded_info) != token.message_digest {
+        wedpr_println!("Timestamp message digest mismatched");
+        return false;
+    }
+    let certificate_hash = if token.certificate_hash_algorithm == OID_SHA1 {
+        Sha1::digest(certificate).to_vec()
+    } else if token.certificate_hash_algorithm == request.hash_algorithm {
+        hash.hash(certificate)
+    } else {
+        wedpr_println!(
+            "Timestamp signing certificate is missing or unsupported"
+        );
+        return false;
+    };
+    if certificate_hash != token.certificate_hash {
+        wedpr_println!("Timestamp signing certificate mismatched");
+        return false;
+    }
+    let attributes_hash = hash.hash(&token.signed_attributes);
+    signature.verify(public_key, &attributes_hash[..], &token.signature[..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_sha256::WedprSha256;
+    use wedpr_l_crypto_signature_secp256r1::WedprSecp256r1;
+
+    const TSA_CERTIFICATE: &[u8] = b"DER encoding of the TSA certificate";
+
+    fn encode_attribute(attribute_type: &[u8], value: &[u8]) -> Vec<u8> {
+        encode_sequence(&[
+            encode_tlv(TAG_OID, attribute_type),
+            encode_tlv(TAG_SET, value),
+        ])
+    }
+
+    fn content_type_attribute(content_type: &[u8]) -> Vec<u8> {
+        encode_attribute(OID_CONTENT_TYPE, &encode_tlv(TAG_OID, content_type))
+    }
+
+    // Builds a SigningCertificate attribute with ESSCertID, or a
+    // SigningCertificateV2 attribute with ESSCertIDv2 using the default
+    // SHA-256.
+    fn signing_certificate_attribute(v2: bool, certificate: &[u8]) -> Vec<u8> {
+        let (attribute_type, certificate_hash) = if v2 {
+            (
+                OID_SIGNING_CERTIFICATE_V2,
+                WedprSha256::default().hash(certificate),
+            )
+        } else {
+            (OID_SIGNING_CERTIFICATE, Sha1::digest(certificate).to_vec())
+        };
+        let cert_id =
+            encode_sequence(&[encode_tlv(TAG_OCTET_STRING, &certificate_hash)]);
+        encode_attribute(
+            attribute_type,
+            &encode_sequence(&[encode_sequence(&[cert_id])]),
+        )
+    }
+
+    // Builds a granted TSA response in the same way as a real TSA signing with
+    // raw P-256 signatures, where the message digest attribute is appended to
+    // the given signed attributes.
+    fn build_timestamp_response(
+        private_key: &[u8],
+        request: &TimestampRequest,
+        gen_time: &[u8],
+        attributes: &[Vec<u8>],
+    ) -> Vec<u8> {
+        let secp256r1 = WedprSecp256r1::default();
+        let sha256 = WedprSha256::default();
+        let mut info_fields = vec![
+            encode_u64(1),
+            encode_tlv(TAG_OID, &[0x2a, 0x03, 0x04]),
+            encode_message_imprint(
+                &request.hash_algorithm,
+                &request.hashed_message,
+            ),
+            encode_u64(12345),
+            encode_tlv(TAG_GENERALIZED_TIME, gen_time),
+        ];
+        if let Some(nonce) = request.nonce {
+            info_fields.push(encode_u64(nonce));
+        }
+        let encoded_info = encode_sequence(&info_fields);
+        let mut attributes = attributes.to_vec();
+        attributes.push(encode_attribute(
+            OID_MESSAGE_DIGEST,
+            &encode_tlv(TAG_OCTET_STRING, &sha256.hash(&encoded_info)),
+        ));
+        let attributes_content = attributes.concat();
+        let attributes_hash =
+            sha256.hash(&encode_tlv(TAG_SET, &attributes_content));
+        let tsa_signature =
+            secp256r1.sign(private_key, &attributes_hash[..]).unwrap();
+        let algorithm = encode_sequence(&[encode_tlv(TAG_OID, &[0x2a])]);
+        let signer_info = encode_sequence(&[
+            encode_u64(1),
+            encode_sequence(&[encode_u64(1)]),
+            algorithm.clone(),
+            encode_tlv(context_tag(0), &attributes_content),
+            algorithm.clone(),
+            encode_tlv(TAG_OCTET_STRING, &tsa_signature),
+        ]);
+        let signed_data = encode_sequence(&[
+            encode_u64(3),
+            encode_tlv(TAG_SET, &algorithm),
+            encode_sequence(&[
+                encode_tlv(TAG_OID, OID_TST_INFO),
+                encode_tlv(
+                    context_tag(0),
+                    &encode_tlv(TAG_OCTET_STRING, &encoded_info),
+                ),
+            ]),
+            encode_tlv(TAG_SET, &signer_info),
+        ]);
+        let token = encode_sequence(&[
+            encode_tlv(TAG_OID, OID_SIGNED_DATA),
+            encode_tlv(context_tag(0), &signed_data),
+        ]);
+        encode_sequence(&[encode_sequence(&[encode_u64(0)]), token])
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let secp256r1 = WedprSecp256r1::default();
+        let sha256 = WedprSha256::default();
+        let (public_key, private_key) = secp256r1.generate_keypair();
+        let request = TimestampRequest {
+            hash_algorithm: OID_SHA256.to_vec(),
+            hashed_message: sha256.hash(b"document to timestamp"),
+            nonce: Some(7),
+            cert_req: true,
+        };
+        let encoded_request = request.encode();
+        assert_eq!(encoded_request[0], TAG_SEQUENCE);
+
+        let attributes = vec![
+            content_type_attribute(OID_TST_INFO),
+            signing_certificate_attribute(true, TSA_CERTIFICATE),
+        ];
+        let response = build_timestamp_response(
+            &private_key,
+            &request,
+            b"20201231235959.5Z",
+            &attributes,
+        );
+        let token = parse_timestamp_response(&response).unwrap();
+        assert_eq!(token.info.gen_time, 1609459199);
+        assert_eq!(token.info.nonce, Some(7));
+        assert_eq!(token.info.hash_algorithm, OID_SHA256.to_vec());
+        assert_eq!(token.info.serial_number, vec![0x30, 0x39]);
+        assert_eq!(token.content_type, OID_TST_INFO.to_vec());
+        assert_eq!(token.certificate_hash_algorithm, OID_SHA256.to_vec());
+        assert!(verify_timestamp_token(
+            &secp256r1,
+            &sha256,
+            TSA_CERTIFICATE,
+            &public_key,
+            &request,
+            &token
+        ));
+        let other_message = TimestampRequest {
+            hashed_message: sha256.hash(b"other document"),
+            ..request.clone()
+        };
+        assert!(!verify_timestamp_token(
+            &secp256r1,
+            &sha256,
+            TSA_CERTIFICATE,
+            &public_key,
+            &other_message,
+            &token
+        ));
+        // Tokens of other requests cannot be replayed.
+        let other_nonce = TimestampRequest {
+            nonce: Some(8),
+            ..request.clone()
+        };
+        assert!(!verify_timestamp_token(
+            &secp256r1,
+            &sha256,
+            TSA_CERTIFICATE,
+            &public_key,
+            &other_nonce,
+            &token
+        ));
+        let response_without_nonce = build_timestamp_response(
+            &private_key,
+            &TimestampRequest {
+                nonce: None,
+                ..request.clone()
+            },
+            b"20201231235959Z",
+            &attributes,
+        );
+        let token_without_nonce =
+            parse_timestamp_response(&response_without_nonce).unwrap();
+        assert!(!verify_timestamp_token(
+            &secp256r1,
+            &sha256,
+            TSA_CERTIFICATE,
+            &public_key,
+            &request,
+            &token_without_nonce
+        ));
+        // The hash algorithm cannot be substituted.
+        let other_algorithm = TimestampRequest {
+            hash_algorithm: OID_SM3.to_vec(),
+            ..request.clone()
+        };
+        assert!(!verify_timestamp_token(
+            &secp256r1,
+            &sha256,
+            TSA_CERTIFICATE,
+            &public_key,
+            &other_algorithm,
+            &token
+        ));
+
+        let mut tampered_token = token.clone();
+        tampered_token.encoded_info.push(0);
+        assert!(!verify_timestamp_token(
+            &secp256r1,
+            &sha256,
+            TSA_CERTIFICATE,
+            &public_key,
+            &request,
+            &tampered_token
+        ));
+
+        let rejected_response =
+            encode_sequence(&[encode_sequence(&[encode_u64(2)])]);
+        assert!(parse_timestamp_response(&rejected_response).is_err());
+    }
+
+    #[test]
+    fn test_timestamp_signed_attributes() {
+        let secp256r1 = WedprSecp256r1::default();
+        let sha256 = WedprSha256::default();
+        let (public_key, private_key) = secp256r1.generate_keypair();
+        let request = TimestampRequest {
+            hash_algorithm: OID_SHA256.to_vec(),
+            hashed_message: sha256.hash(b"document to timestamp"),
+            nonce: None,
+            cert_req: true,
+        };
+        let verify_attributes = |attributes: &[Vec<u8>]| {
+            let response = build_timestamp_response(
+                &private_key,
+                &request,
+                b"20201231235959Z",
+                attributes,
+            );
+            let token = parse_timestamp_response(&response).unwrap();
+            verify_timestamp_token(
+                &secp256r1,
+                &sha256,
+                TSA_CERTIFICATE,
+                &public_key,
+                &request,
+                &token,
+            )
+        };
+
+        // ESSCertID identifies the TSA certificate with SHA-1.
+        assert!(verify_attributes(&[
+            content_type_attribute(OID_TST_INFO),
+            signing_certificate_attribute(false, TSA_CERTIFICATE),
+        ]));
+        // Tokens signed for other certificates are rejected.
+        assert!(!verify_attributes(&[
+            content_type_attribute(OID_TST_INFO),
+            signing_certificate_attribute(true, b"other certificate"),
+        ]));
+        assert!(!verify_attributes(&[
+            content_type_attribute(OID_TST_INFO),
+            signing_certificate_attribute(false, b"other certificate"),
+        ]));
+        assert!(!verify_attributes(&[content_type_attribute(OID_TST_INFO)]));
+        // Signed content other than TSTInfo is rejected.
+        assert!(!verify_attributes(&[
+            content_type_attribute(OID_SIGNED_DATA),
+            signing_certificate_attribute(true, TSA_CERTIFICATE),
+        ]));
+        // The content type attribute is required.
+        let response = build_timestamp_response(
+            &private_key,
+            &request,
+            b"20201231235959Z",
+            &[signing_certificate_attribute(true, TSA_CERTIFICATE)],
+        );
+        assert!(parse_timestamp_response(&response).is_err());
+    }
+
+    #[test]
+    fn test_generalized_time() {
+        assert!(parse_generalized_time(b"19691231235959Z").is_err());
+        assert_eq!(parse_generalized_time(b"19700101000000Z").unwrap(), 0);
+        assert_eq!(
+            parse_generalized_time(b"20240229000000Z").unwrap(),
+            1709164800
+        );
+        assert!(parse_generalized_time(b"20230229000000Z").is_err());
+        assert!(parse_generalized_time(b"20230231000000Z").is_err());
+        assert!(parse_generalized_time(b"20230431000000Z").is_err());
+        assert!(parse_generalized_time(b"21000229000000Z").is_err());
+        assert_eq!(
+            parse_generalized_time(b"20000229000000Z").unwrap(),
+            951782400
+        );
+        assert!(parse_generalized_time(b"20231301000000Z").is_err());
+    }
+}
@@ -0,0 +1,455 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! MuSig2-style m-of-m multi-signature functions over secp256k1, whose
+//! aggregated signatures are BIP-340 Schnorr signatures of the aggregated
+//! public key, so co-signing looks like a single signer on chain.
+//!
+//! The session runs as follows:
+//! 1) All signers build the same KeyAggContext from their public keys.
+//! 2) Every signer generates a nonce pair by generate_nonce, and sends the
+//!    public nonce to the others, which can be done before the message is
+//!    known.
+//! 3) All signers aggregate the public nonces, build the SigningSession for the
+//!    message hash, and send their partial signatures.
+//! 4) Anyone aggregates the partial signatures into the final signature, where
+//!    each partial signature can be checked by verify_partial.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+#[macro_use]
+extern crate lazy_static;
+
+use secp256k1::{All, PublicKey, Secp256k1, SecretKey};
+use wedpr_l_crypto_signature_schnorr::{
+    compute_challenge, has_even_y, reduce_scalar, tagged_hash,
+};
+use wedpr_l_utils::error::WedprError;
+
+lazy_static! {
+    // Shared secp256k1 instance initialized for all functions.
+    static ref SECP256K1_ALL: Secp256k1<All> = Secp256k1::new();
+}
+
+/// Size of a compressed public key in bytes.
+const COMPRESSED_PUBLIC_KEY_SIZE_IN_BYTES: usize = 33;
+/// Size of an encoded public nonce in bytes.
+pub const PUBLIC_NONCE_SIZE_IN_BYTES: usize =
+    2 * COMPRESSED_PUBLIC_KEY_SIZE_IN_BYTES;
+/// Size of a partial signature in bytes.
+pub const PARTIAL_SIGNATURE_SIZE_IN_BYTES: usize = 32;
+
+// Tags of the tagged hashes of key and nonce aggregation.
+const KEY_AGG_LIST_TAG: &[u8] = b"KeyAgg list";
+const KEY_AGG_COEFFICIENT_TAG: &[u8] = b"KeyAgg coefficient";
+const NONCE_COEFFICIENT_TAG: &[u8] = b"MuSig/noncecoef";
+
+fn parse_public_key(public_key: &[u8]) -> Result<PublicKey, WedprError> {
+    match PublicKey::from_slice(public_key) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Parsing public key failed");
+            Err(WedprError::FormatError)
+        },
+    }
+}
+
+fn parse_scalar(scalar: &[u8]) -> Result<SecretKey, WedprError> {
+    match SecretKey::from_slice(scalar) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Parsing scalar failed");
+            Err(WedprError::FormatError)
+        },
+    }
+}
+
+/// Computes the sum of points, which fails for the point at infinity.
+fn sum_points<'a, I: Iterator<Item = &'a PublicKey>>(
+    mut points: I,
+) -> Result<PublicKey, WedprError> {
+    let mut sum = match points.next() {
+        Some(v) => *v,
+        None => return Err(WedprError::ArgumentError),
+    };
+    for point in points {
+        sum = match sum.combine(point) {
+            Ok(v) => v,
+            Err(_) => {
+                wedpr_println!("Sum of points is infinity");
+                return Err(WedprError::VerificationError);
+            },
+        };
+    }
+    Ok(sum)
+}
+
+/// Computes point * scalar.
+fn mul_point(
+    point: &PublicKey,
+    scalar: &[u8],
+) -> Result<PublicKey, WedprError> {
+    let mut output = *point;
+    match output.mul_assign(&SECP256K1_ALL, scalar) {
+        Ok(_) => Ok(output),
+        Err(_) => Err(WedprError::ArgumentError),
+    }
+}
+
+/// Aggregation context of the public keys of all signers.
+#[derive(Debug, Clone)]
+pub struct KeyAggContext {
+    public_keys: Vec<PublicKey>,
+    coefficients: Vec<Vec<u8>>,
+    aggregated_public_key: PublicKey,
+}
+
+impl KeyAggContext {
+    /// Aggregates public keys in compressed or uncompressed forms, where all
+    /// signers should use the same order.
+    pub fn new<T: AsRef<[u8]>>(public_keys: &[T]) -> Result<Self, WedprError> {
+        if public_keys.is_empty() {
+            return Err(WedprError::ArgumentError);
+        }
+        let public_keys = public_keys
+            .iter()
+            .map(|public_key| parse_public_key(public_key.as_ref()))
+            .collect::<Result<Vec<PublicKey>, WedprError>>()?;
+        let serialized: Vec<[u8; COMPRESSED_PUBLIC_KEY_SIZE_IN_BYTES]> =
+            public_keys.iter().map(|v| v.serialize()).collect();
+        let list_hash = tagged_hash(
+            KEY_AGG_LIST_TAG,
+            &serialized.iter().map(|v| &v[..]).collect::<Vec<&[u8]>>(),
+        );
+        let coefficients: Vec<Vec<u8>> = serialized
+            .iter()
+            .map(|public_key| {
+                reduce_scalar(&tagged_hash(KEY_AGG_COEFFICIENT_TAG, &[
+                    &list_hash[..],
+                    &public_key[..],
+                ]))
+            })
+            .collect();
+        let weighted_public_keys = public_keys
+            .iter()
+            .zip(&coefficients)
+            .map(|(public_key, coefficient)| mul_point(public_key, coefficient))
+            .collect::<Result<Vec<PublicKey>, WedprError>>()?;
+        let aggregated_public_key = sum_points(weighted_public_keys.iter())?;
+        Ok(KeyAggContext {
+            public_keys,
+            coefficients,
+            aggregated_public_key,
+        })
+    }
+
+    /// Gets the x-only aggregated public key for verifying the aggregated
+    /// signatures as BIP-340 signatures.
+    pub fn aggregated_public_key(&self) -> Vec<u8> {
+        self.aggregated_public_key.serialize()[1..].to_vec()
+    }
+
+    /// Gets the number of signers.
+    pub fn signer_count(&self) -> usize {
+        self.public_keys.len()
+    }
+}
+
+/// Public nonce of a signer, or the aggregated nonce of all signers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicNonce {
+    r1: PublicKey,
+    r2: PublicKey,
+}
+
+impl PublicNonce {
+    /// Encodes the nonce as two compressed points.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = self.r1.serialize().to_vec();
+        output.extend_from_slice(&self.r2.serialize());
+        output
+    }
+
+    /// Decodes a nonce encoded by encode.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        if input.len() != PUBLIC_NONCE_SIZE_IN_BYTES {
+            return Err(WedprError::FormatError);
+        }
+        let (r1, r2) = input.split_at(COMPRESSED_PUBLIC_KEY_SIZE_IN_BYTES);
+        Ok(PublicNonce {
+            r1: parse_public_key(r1)?,
+            r2: parse_public_key(r2)?,
+        })
+    }
+}
+
+/// Secret nonce of a signer, which is consumed by signing so that it cannot
+/// be reused.
+pub struct SecretNonce {
+    k1: SecretKey,
+    k2: SecretKey,
+}
+
+/// Generates a fresh nonce pair for one signing session.
+pub fn generate_nonce() -> (SecretNonce, PublicNonce) {
+    let mut rng = rand::thread_rng();
+    let k1 = SecretKey::new(&mut rng);
+    let k2 = SecretKey::new(&mut rng);
+    let public_nonce = PublicNonce {
+        r1: PublicKey::from_secret_key(&SECP256K1_ALL, &k1),
+        r2: PublicKey::from_secret_key(&SECP256K1_ALL, &k2),
+    };
+    (SecretNonce { k1, k2 }, public_nonce)
+}
+
+/// Aggregates the public nonces of all signers.
+pub fn aggregate_nonces(
+    public_nonces: &[PublicNonce],
+) -> Result<PublicNonce, WedprError> {
+    Ok(PublicNonce {
+        r1: sum_points(public_nonces.iter().map(|v| &v.r1))?,
+        r2: sum_points(public_nonces.iter().map(|v| &v.r2))?,
+    })
+}
+
+/// Signing session of a message hash with the aggregated nonce.
+#[derive(Debug, Clone)]
+pub struct SigningSession {
+    nonce_coefficient: Vec<u8>,
+    challenge: Vec<u8>,
+    nonce_point_x: Vec<u8>,
+    /// Whether nonces are negated for the final nonce point with even y.
+    nonce_negated: bool,
+    /// Whether keys are negated for the aggregated public key with even y.
+    key_negated: bool,
+}
+
+impl SigningSession {
+    /// Creates the session, which is the same for all signers.
+    pub fn new(
+        key_agg_context: &KeyAggContext,
+        aggregated_nonce: &PublicNonce,
+        msg_hash: &[u8],
+    ) -> Result<Self, WedprError> {
+        let aggregated_public_key = key_agg_context.aggregated_public_key();
+        let nonce_coefficient =
+            reduce_scalar(&tagged_hash(NONCE_COEFFICIENT_TAG, &[
+                &aggregated_nonce.encode()[..],
+                &aggregated_public_key[..],
+                msg_hash,
+            ]));
+        let nonce_point = sum_points(
+            [
+                aggregated_nonce.r1,
+                mul_point(&aggregated_nonce.r2, &nonce_coefficient)?,
+            ]
+            .iter(),
+        )?;
+        let nonce_point_x = nonce_point.serialize()[1..].to_vec();
+        let challenge =
+            compute_challenge(&nonce_point_x, &aggregated_public_key, msg_hash);
+        Ok(SigningSession {
+            nonce_coefficient,
+            challenge,
+            nonce_point_x,
+            nonce_negated: !has_even_y(&nonce_point),
+            key_negated: !has_even_y(&key_agg_context.aggregated_public_key),
+        })
+    }
+
+    /// Creates the partial signature
+    /// s_i = k1 + b * k2 + e * a_i * d_i, where nonces and keys are negated
+    /// if needed.
+    pub fn partial_sign(
+        &self,
+        key_agg_context: &KeyAggContext,
+        secret_nonce: SecretNonce,
+        private_key: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        let mut secret_key = parse_scalar(private_key)?;
+        let public_key =
+            PublicKey::from_secret_key(&SECP256K1_ALL, &secret_key);
+        let index = match key_agg_context
+            .public_keys
+            .iter()
+            .position(|v| *v == public_key)
+        {
+            Some(v) => v,
+            None => {
+                wedpr_println!("Signer is not in the key aggregation");
+                return Err(WedprError::ArgumentError);
+            },
+        };
+        let SecretNonce { mut k1, mut k2 } = secret_nonce;
+        if self.nonce_negated {
+            k1.negate_assign();
+            k2.negate_assign();
+        }
+        if self.key_negated {
+            secret_key.negate_assign();
+        }
+        let compute = || -> Result<SecretKey, secp256k1::Error> {
+            let mut partial_signature = k2;
+            partial_signature.mul_assign(&self.nonce_coefficient)?;
+            partial_signature.add_assign(&k1[..])?;
+            let mut key_term = secret_key;
+            key_term.mul_assign(&key_agg_context.coefficients[index])?;
+            key_term.mul_assign(&self.challenge)?;
+            partial_signature.add_assign(&key_term[..])?;
+            Ok(partial_signature)
+        };
+        match compute() {
+            Ok(v) => Ok(v[..].to_vec()),
+            Err(_) => {
+                wedpr_println!("Computing partial signature failed");
+                Err(WedprError::ArgumentError)
+            },
+        }
+    }
+
+    /// Verifies the partial signature of a signer by
+    /// s_i * G = R1_i + b * R2_i + e * a_i * P_i.
+    pub fn verify_partial(
+        &self,
+        key_agg_context: &KeyAggContext,
+        signer_index: usize,
+        public_nonce: &PublicNonce,
+        partial_signature: &[u8],
+    ) -> bool {
+        let public_key = match key_agg_context.public_keys.get(signer_index) {
+            Some(v) => v,
+            None => return false,
+        };
+        let expected = || -> Result<PublicKey, WedprError> {
+            let mut nonce_point = sum_points(
+                [
+                    public_nonce.r1,
+                    mul_point(&public_nonce.r2, &self.nonce_coefficient)?,
+                ]
+                .iter(),
+            )?;
+            if self.nonce_negated {
+                nonce_point.negate_assign(&SECP256K1_ALL);
+            }
+            let mut key_point = mul_point(
+                &mul_point(
+                    public_key,
+                    &key_agg_context.coefficients[signer_index],
+                )?,
+                &self.challenge,
+            )?;
+            if self.key_negated {
+                key_point.negate_assign(&SECP256K1_ALL);
+            }
+            sum_points([nonce_point, key_point].iter())
+        };
+        let partial_scalar = match parse_scalar(partial_signature) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        match expected() {
+            Ok(v) => {
+                PublicKey::from_secret_key(&SECP256K1_ALL, &partial_scalar) == v
+            },
+            Err(_) => false,
+        }
+    }
+
+    /// Aggregates the partial signatures of all signers into a BIP-340
+    /// signature of the aggregated public key.
+    pub fn aggregate<T: AsRef<[u8]>>(
+        &self,
+        partial_signatures: &[T],
+    ) -> Result<Vec<u8>, WedprError> {
+        if partial_signatures.is_empty() {
+            return Err(WedprError::ArgumentError);
+        }
+        let mut s = parse_scalar(partial_signatures[0].as_ref())?;
+        for partial_signature in &partial_signatures[1..] {
+            let partial_scalar = parse_scalar(partial_signature.as_ref())?;
+            if s.add_assign(&partial_scalar[..]).is_err() {
+                wedpr_println!("Aggregating partial signatures failed");
+                return Err(WedprError::VerificationError);
+            }
+        }
+        let mut signature = self.nonce_point_x.clone();
+        signature.extend_from_slice(&s[..]);
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_signature_schnorr::WedprSchnorr;
+    use wedpr_l_utils::{
+        constant::tests::BASE64_ENCODED_TEST_MESSAGE, traits::Signature,
+    };
+
+    #[test]
+    fn test_musig() {
+        let keypairs: Vec<(SecretKey, PublicKey)> = (0..3)
+            .map(|_| SECP256K1_ALL.generate_keypair(&mut rand::thread_rng()))
+            .collect();
+        let public_keys: Vec<Vec<u8>> = keypairs
+            .iter()
+            .map(|(_, public_key)| public_key.serialize().to_vec())
+            .collect();
+        let key_agg_context = KeyAggContext::new(&public_keys).unwrap();
+        assert_eq!(key_agg_context.signer_count(), 3);
+
+        let (secret_nonces, public_nonces): (Vec<_>, Vec<_>) =
+            (0..3).map(|_| generate_nonce()).unzip();
+        let public_nonces: Vec<PublicNonce> = public_nonces
+            .iter()
+            .map(|v| PublicNonce::decode(&v.encode()).unwrap())
+            .collect();
+        let aggregated_nonce = aggregate_nonces(&public_nonces).unwrap();
+        let msg_hash = BASE64_ENCODED_TEST_MESSAGE.to_vec();
+        let session =
+            SigningSession::new(&key_agg_context, &aggregated_nonce, &msg_hash)
+                .unwrap();
+        let partial_signatures: Vec<Vec<u8>> = secret_nonces
+            .into_iter()
+            .zip(&keypairs)
+            .map(|(secret_nonce, (secret_key, _))| {
+                session
+                    .partial_sign(
+                        &key_agg_context,
+                        secret_nonce,
+                        &secret_key[..],
+                    )
+                    .unwrap()
+            })
+            .collect();
+        for (i, partial_signature) in partial_signatures.iter().enumerate() {
+            assert!(session.verify_partial(
+                &key_agg_context,
+                i,
+                &public_nonces[i],
+                partial_signature
+            ));
+        }
+        assert!(!session.verify_partial(
+            &key_agg_context,
+            1,
+            &public_nonces[0],
+            &partial_signatures[0]
+        ));
+
+        let signature = session.aggregate(&partial_signatures).unwrap();
+        let schnorr = WedprSchnorr::default();
+        let aggregated_public_key = key_agg_context.aggregated_public_key();
+        assert!(schnorr.verify(&aggregated_public_key, &msg_hash, &signature));
+        // Missing signers make the signature invalid.
+        let signature = session.aggregate(&partial_signatures[..2]).unwrap();
+        assert!(!schnorr.verify(&aggregated_public_key, &msg_hash, &signature));
+
+        // Signers outside the key aggregation cannot sign.
+        let (secret_nonce, _) = generate_nonce();
+        let (secret_key, _) =
+            SECP256K1_ALL.generate_keypair(&mut rand::thread_rng());
+        assert!(session
+            .partial_sign(&key_agg_context, secret_nonce, &secret_key[..])
+            .is_err());
+    }
+}
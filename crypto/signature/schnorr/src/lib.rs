@@ -0,0 +1,404 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! BIP-340 Schnorr signature functions over secp256k1.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+#[macro_use]
+extern crate lazy_static;
+
+use rand::RngCore;
+use secp256k1::{constants::CURVE_ORDER, All, PublicKey, Secp256k1, SecretKey};
+use wedpr_l_crypto_hash_sha256::WedprSha256;
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    error::WedprError,
+    policy::check_global_algorithm,
+    traits::{AlgorithmInfo, Hash, Signature},
+};
+
+lazy_static! {
+    // Shared secp256k1 instance initialized for all functions.
+    static ref SECP256K1_ALL: Secp256k1<All> = Secp256k1::new();
+}
+
+/// Size of an x-only public key in bytes.
+pub const X_ONLY_PUBLIC_KEY_SIZE_IN_BYTES: usize = 32;
+/// Size of a signature r || s in bytes.
+pub const SCHNORR_SIGNATURE_SIZE_IN_BYTES: usize = 64;
+const SCALAR_SIZE_IN_BYTES: usize = 32;
+
+// Tags of the tagged hashes defined by BIP-340.
+const BIP340_AUX_TAG: &[u8] = b"BIP0340/aux";
+const BIP340_NONCE_TAG: &[u8] = b"BIP0340/nonce";
+const BIP340_CHALLENGE_TAG: &[u8] = b"BIP0340/challenge";
+
+/// Implements BIP-340 Schnorr as a Signature instance, whose public keys are
+/// the 32-byte x coordinates of points with even y.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct WedprSchnorr {}
+
+impl AlgorithmInfo for WedprSchnorr {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "bip340-schnorr",
+            oid: None,
+            security_bits: 128,
+            private_key_size: Some(SCALAR_SIZE_IN_BYTES),
+            public_key_sizes: &[X_ONLY_PUBLIC_KEY_SIZE_IN_BYTES],
+            signature_size: Some(SCHNORR_SIGNATURE_SIZE_IN_BYTES),
+            output_size: None,
+        }
+    }
+}
+
+impl Signature for WedprSchnorr {
+    /// Signs a message hash with fresh auxiliary randomness, as recommended
+    /// by BIP-340 against side-channel attacks.
+    fn sign<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        check_global_algorithm::<Self>()?;
+        let mut aux_rand = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut aux_rand);
+        sign_with_aux_rand(private_key.as_ref(), msg_hash.as_ref(), &aux_rand)
+    }
+
+    /// Signs a message hash with all-zero auxiliary randomness, as used by
+    /// the BIP-340 test vectors, where the nonce is derived by the BIP-340
+    /// nonce function instead of RFC 6979.
+    fn sign_deterministic<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        check_global_algorithm::<Self>()?;
+        sign_with_aux_rand(private_key.as_ref(), msg_hash.as_ref(), &[0u8; 32])
+    }
+
+    fn verify<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        msg_hash: &T,
+        signature: &T,
+    ) -> bool {
+        if check_global_algorithm::<Self>().is_err() {
+            return false;
+        }
+        let signature = signature.as_ref();
+        if signature.len() != SCHNORR_SIGNATURE_SIZE_IN_BYTES {
+            wedpr_println!("Invalid Schnorr signature size");
+            return false;
+        }
+        let public_key_obj = match lift_x(public_key.as_ref()) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        let (r, s) = signature.split_at(SCALAR_SIZE_IN_BYTES);
+        let challenge =
+            compute_challenge(r, public_key.as_ref(), msg_hash.as_ref());
+        // R = sG - eP, which must have even y and x = r.
+        let nonce_point =
+            match compute_nonce_point(&public_key_obj, s, &challenge) {
+                Ok(Some(v)) => v,
+                _ => return false,
+            };
+        let nonce_point_bytes = nonce_point.serialize();
+        has_even_y(&nonce_point) && nonce_point_bytes[1..] == *r
+    }
+
+    fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+        let mut rng = rand::thread_rng();
+        let secret_key = SecretKey::new(&mut rng);
+        let public_key =
+            PublicKey::from_secret_key(&SECP256K1_ALL, &secret_key);
+        (
+            public_key.serialize()[1..].to_vec(),
+            secret_key[..].to_vec(),
+        )
+    }
+}
+
+impl WedprSchnorr {
+    /// Derives the x-only public key of a private key.
+    pub fn derive_public_key(
+        self,
+        private_key: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        let secret_key = parse_secret_key(private_key)?;
+        let public_key =
+            PublicKey::from_secret_key(&SECP256K1_ALL, &secret_key);
+        Ok(public_key.serialize()[1..].to_vec())
+    }
+}
+
+/// Signs a message hash with 32 bytes of auxiliary randomness as specified
+/// by BIP-340.
+fn sign_with_aux_rand(
+    private_key: &[u8],
+    msg_hash: &[u8],
+    aux_rand: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let mut secret_key = parse_secret_key(private_key)?;
+    let public_key = PublicKey::from_secret_key(&SECP256K1_ALL, &secret_key);
+    // The private key is negated if its public key has odd y.
+    if !has_even_y(&public_key) {
+        secret_key.negate_assign();
+    }
+    let public_key_x = &public_key.serialize()[1..];
+    let aux_hash = tagged_hash(BIP340_AUX_TAG, &[aux_rand]);
+    let masked_key: Vec<u8> = secret_key[..]
+        .iter()
+        .zip(aux_hash)
+        .map(|(a, b)| a ^ b)
+        .collect();
+    let nonce_hash = tagged_hash(BIP340_NONCE_TAG, &[
+        &masked_key[..],
+        public_key_x,
+        msg_hash,
+    ]);
+    let mut nonce = match SecretKey::from_slice(&reduce_scalar(&nonce_hash)) {
+        Ok(v) => v,
+        Err(_) => {
+            wedpr_println!("Schnorr nonce is zero");
+            return Err(WedprError::ArgumentError);
+        },
+    };
+    let nonce_point = PublicKey::from_secret_key(&SECP256K1_ALL, &nonce);
+    if !has_even_y(&nonce_point) {
+        nonce.negate_assign();
+    }
+    let nonce_point_x = &nonce_point.serialize()[1..];
+    let challenge = compute_challenge(nonce_point_x, public_key_x, msg_hash);
+    let mut signature = nonce_point_x.to_vec();
+    signature.extend_from_slice(&compute_s(&secret_key, &challenge, &nonce));
+    Ok(signature)
+}
+
+/// Computes s = k + ed mod n, where libsecp256k1 rejects zero scalars, thus
+/// zero values of ed and s are handled separately.
+fn compute_s(
+    secret_key: &SecretKey,
+    challenge: &[u8],
+    nonce: &SecretKey,
+) -> [u8; SCALAR_SIZE_IN_BYTES] {
+    let mut output = [0u8; SCALAR_SIZE_IN_BYTES];
+    if is_zero(challenge) {
+        output.copy_from_slice(&nonce[..]);
+        return output;
+    }
+    let mut s = *secret_key;
+    // Multiplying by a nonzero reduced challenge cannot fail.
+    if s.mul_assign(challenge).is_ok() && s.add_assign(&nonce[..]).is_ok() {
+        output.copy_from_slice(&s[..]);
+    }
+    // Otherwise k + ed is zero.
+    output
+}
+
+/// Computes R = sG - eP for a 32-byte s, which returns None if R is the
+/// point at infinity, and fails if s is not less than n. Unlike private keys,
+/// s = 0 is valid in BIP-340 signatures.
+fn compute_nonce_point(
+    public_key: &PublicKey,
+    s: &[u8],
+    challenge: &[u8],
+) -> Result<Option<PublicKey>, WedprError> {
+    let s_point = if is_zero(s) {
+        None
+    } else {
+        // Parsing fails if s is not less than n.
+        let s_scalar = match SecretKey::from_slice(s) {
+            Ok(v) => v,
+            Err(_) => return Err(WedprError::FormatError),
+        };
+        Some(PublicKey::from_secret_key(&SECP256K1_ALL, &s_scalar))
+    };
+    let e_point = if is_zero(challenge) {
+        None
+    } else {
+        let mut point = *public_key;
+        if point.mul_assign(&SECP256K1_ALL, challenge).is_err() {
+            return Err(WedprError::FormatError);
+        }
+        point.negate_assign(&SECP256K1_ALL);
+        Some(point)
+    };
+    match (s_point, e_point) {
+        (Some(s_point), Some(e_point)) => Ok(s_point.combine(&e_point).ok()),
+        (s_point, None) => Ok(s_point),
+        (None, e_point) => Ok(e_point),
+    }
+}
+
+fn is_zero(scalar: &[u8]) -> bool {
+    scalar.iter().all(|v| *v == 0)
+}
+
+fn parse_secret_key(private_key: &[u8]) -> Result<SecretKey, WedprError> {
+    match SecretKey::from_slice(private_key) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Parsing private key failed");
+            Err(WedprError::FormatError)
+        },
+    }
+}
+
+/// Gets the point with even y for an x-only public key.
+fn lift_x(public_key: &[u8]) -> Result<PublicKey, WedprError> {
+    if public_key.len() != X_ONLY_PUBLIC_KEY_SIZE_IN_BYTES {
+        wedpr_println!("Invalid x-only public key size");
+        return Err(WedprError::FormatError);
+    }
+    let mut compressed_public_key = vec![0x02];
+    compressed_public_key.extend_from_slice(public_key);
+    match PublicKey::from_slice(&compressed_public_key) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Parsing public key failed");
+            Err(WedprError::FormatError)
+        },
+    }
+}
+
+/// Checks whether a point has even y.
+pub fn has_even_y(point: &PublicKey) -> bool {
+    point.serialize()[0] == 0x02
+}
+
+/// Computes SHA256(SHA256(tag) || SHA256(tag) || inputs).
+pub fn tagged_hash(tag: &[u8], inputs: &[&[u8]]) -> Vec<u8> {
+    let sha256 = WedprSha256::default();
+    let tag_hash = sha256.hash(tag);
+    let mut hash_input = tag_hash.clone();
+    hash_input.extend_from_slice(&tag_hash);
+    for input in inputs {
+        hash_input.extend_from_slice(input);
+    }
+    sha256.hash(&hash_input)
+}
+
+/// Computes the challenge e = H(r || P || m) mod n.
+pub fn compute_challenge(
+    r: &[u8],
+    public_key_x: &[u8],
+    msg_hash: &[u8],
+) -> Vec<u8> {
+    reduce_scalar(&tagged_hash(BIP340_CHALLENGE_TAG, &[
+        r,
+        public_key_x,
+        msg_hash,
+    ]))
+}
+
+/// Reduces a 32-byte big-endian integer modulo n, where one subtraction is
+/// enough since 2^256 < 2n.
+pub fn reduce_scalar(value: &[u8]) -> Vec<u8> {
+    if value < &CURVE_ORDER[..] {
+        return value.to_vec();
+    }
+    let mut output = vec![0u8; SCALAR_SIZE_IN_BYTES];
+    let mut borrow = 0i16;
+    for i in (0..SCALAR_SIZE_IN_BYTES).rev() {
+        let mut digit = value[i] as i16 - CURVE_ORDER[i] as i16 - borrow;
+        borrow = 0;
+        if digit < 0 {
+            digit += 256;
+            borrow = 1;
+        }
+        output[i] = digit as u8;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_common_coder_hex::WedprHex;
+    use wedpr_l_utils::{
+        constant::tests::BASE64_ENCODED_TEST_MESSAGE, traits::Coder,
+    };
+
+    #[test]
+    fn test_schnorr() {
+        let schnorr = WedprSchnorr::default();
+        let (public_key, private_key) = schnorr.generate_keypair();
+        assert_eq!(
+            schnorr.derive_public_key(&private_key).unwrap(),
+            public_key
+        );
+        let msg_hash = BASE64_ENCODED_TEST_MESSAGE.to_vec();
+        let signature = schnorr.sign(&private_key, &msg_hash).unwrap();
+        assert!(schnorr.verify(&public_key, &msg_hash, &signature));
+        assert!(!schnorr.verify(
+            &public_key,
+            &msg_hash[1..].to_vec(),
+            &signature
+        ));
+        assert!(!schnorr.verify(
+            &public_key,
+            &msg_hash,
+            &signature[1..].to_vec()
+        ));
+        assert!(schnorr.sign(&private_key[1..], &msg_hash[..]).is_err());
+
+        // Test vector 0 of BIP-340.
+        let hex = WedprHex::default();
+        let mut private_key = [0u8; 32];
+        private_key[31] = 3;
+        let public_key = hex
+            .decode(
+                &[
+                    "f9308a019258c31049344f85f89d5229",
+                    "b531c845836f99b08601f113bce036f9",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let expected_signature = hex
+            .decode(
+                &[
+                    "e907831f80848d1069a5371b40241036",
+                    "4bdf1c5f8307b0084c55f1ce2dca8215",
+                    "25f66a4a85ea8b71e482a74f382d2ce5",
+                    "ebeee8fdb2172f477df4900d310536c0",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let msg_hash = [0u8; 32];
+        let signature = schnorr
+            .sign_deterministic(&private_key[..], &msg_hash[..])
+            .unwrap();
+        assert_eq!(signature, expected_signature);
+        assert!(schnorr.verify(&public_key[..], &msg_hash[..], &signature[..]));
+        assert!(WedprSchnorr::algorithm_info()
+            .check_signature(&signature)
+            .is_ok());
+
+        // s = 0 is a valid scalar, where R = -eP.
+        let public_key = lift_x(&public_key).unwrap();
+        let challenge = compute_challenge(&signature[..32], &[0u8; 32], &[]);
+        let mut expected_point = public_key;
+        expected_point
+            .mul_assign(&SECP256K1_ALL, &challenge)
+            .unwrap();
+        expected_point.negate_assign(&SECP256K1_ALL);
+        assert_eq!(
+            compute_nonce_point(&public_key, &[0u8; 32], &challenge).unwrap(),
+            Some(expected_point)
+        );
+        assert!(
+            compute_nonce_point(&public_key, &CURVE_ORDER, &challenge).is_err()
+        );
+        let signature_with_zero_s = [&signature[..32], &[0u8; 32]].concat();
+        assert!(!schnorr.verify(
+            &public_key.serialize()[1..],
+            &msg_hash[..],
+            &signature_with_zero_s
+        ));
+    }
+}
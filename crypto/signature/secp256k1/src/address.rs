@@ -0,0 +1,90 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Address functions of Ethereum and FISCO-BCOS accounts.
+
+use crate::{parse_public_key, recover_public_key_obj, WedprSecp256k1Recover};
+use secp256k1::PublicKey;
+use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+use wedpr_l_utils::error::WedprError;
+
+/// Size of an account address in bytes.
+pub const ADDRESS_SIZE_IN_BYTES: usize = 20;
+
+/// Derives the account address of a public key in either compressed or
+/// uncompressed form, which is the last 20 bytes of the Keccak256 digest of
+/// the uncompressed public key without its 0x04 prefix.
+pub fn public_key_to_address(public_key: &[u8]) -> Result<Vec<u8>, WedprError> {
+    Ok(public_key_obj_to_address(&parse_public_key(public_key)?).to_vec())
+}
+
+fn public_key_obj_to_address(
+    public_key: &PublicKey,
+) -> [u8; ADDRESS_SIZE_IN_BYTES] {
+    let uncompressed = public_key.serialize_uncompressed();
+    let digest = WedprKeccak256::default().hash_to_array(&uncompressed[1..]);
+    let mut address = [0u8; ADDRESS_SIZE_IN_BYTES];
+    address.copy_from_slice(&digest[digest.len() - ADDRESS_SIZE_IN_BYTES..]);
+    address
+}
+
+impl WedprSecp256k1Recover {
+    /// Recovers the account address of the signer of a message hash.
+    pub fn recover_address<T: ?Sized + AsRef<[u8]>>(
+        self,
+        msg_hash: &T,
+        signature: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        Ok(self.recover_address_array(msg_hash, signature)?.to_vec())
+    }
+
+    /// Recovers the account address as by recover_address into a fixed-size
+    /// array.
+    pub fn recover_address_array<T: ?Sized + AsRef<[u8]>>(
+        self,
+        msg_hash: &T,
+        signature: &T,
+    ) -> Result<[u8; ADDRESS_SIZE_IN_BYTES], WedprError> {
+        let public_key =
+            recover_public_key_obj(msg_hash.as_ref(), signature.as_ref())?;
+        Ok(public_key_obj_to_address(&public_key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_utils::traits::{Hash, Signature};
+
+    #[test]
+    fn test_address() {
+        // The well-known address of private key 1.
+        let mut private_key = [0u8; 32];
+        private_key[31] = 1;
+        let expected_address = [
+            0x7e, 0x5f, 0x45, 0x52, 0x09, 0x1a, 0x69, 0x12, 0x5d, 0x5d, 0xfc,
+            0xb7, 0xb8, 0xc2, 0x65, 0x90, 0x29, 0x39, 0x5b, 0xdf,
+        ];
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let msg_hash = WedprKeccak256::default().hash("WeDPR");
+        let signature =
+            secp256k1.sign(&private_key[..], &msg_hash[..]).unwrap();
+        assert_eq!(
+            secp256k1.recover_address(&msg_hash, &signature).unwrap(),
+            expected_address
+        );
+        assert_eq!(
+            secp256k1
+                .recover_address_array(&msg_hash, &signature)
+                .unwrap(),
+            expected_address
+        );
+
+        let (public_key, _) = secp256k1.generate_keypair();
+        let compressed = secp256k1.compress_public_key(&public_key).unwrap();
+        let address = public_key_to_address(&public_key).unwrap();
+        assert_eq!(address.len(), ADDRESS_SIZE_IN_BYTES);
+        assert_eq!(public_key_to_address(&compressed).unwrap(), address);
+        assert!(public_key_to_address(&public_key[1..]).is_err());
+        assert!(secp256k1.recover_address(&msg_hash, &msg_hash).is_err());
+    }
+}
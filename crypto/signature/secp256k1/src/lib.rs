@@ -1,6 +1,11 @@
 // Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
 
 //! Secp256k1 signature functions.
+//!
+//! Verification and recovery take borrowed inputs of any AsRef<[u8]>, so
+//! shared buffers, e.g. bytes::Bytes of received transactions, are used in
+//! place without copies. The *_array variants of recovery return fixed-size
+//! outputs, which are not allocated on the heap.
 
 #[macro_use]
 extern crate wedpr_l_macros;
@@ -9,10 +14,24 @@ extern crate lazy_static;
 
 extern crate secp256k1;
 use secp256k1::{
+    constants::CURVE_ORDER,
     recovery::{RecoverableSignature, RecoveryId},
-    All, Message, Secp256k1, SecretKey, VerifyOnly,
+    All, Message, PublicKey, Secp256k1, SecretKey, VerifyOnly,
 };
-use wedpr_l_utils::{error::WedprError, traits::Signature};
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    constant_time::is_valid_secret_scalar,
+    error::WedprError,
+    policy::{check_global_algorithm, global_policy},
+    traits::{AlgorithmInfo, Signature},
+};
+
+#[cfg(feature = "wedpr_f_parallel")]
+use rayon::prelude::*;
+
+pub mod address;
+pub mod malleability;
+pub mod signer_index;
 
 lazy_static! {
     // Shared secp256k1 instance initialized for verification function only.
@@ -26,40 +45,45 @@ lazy_static! {
 pub struct WedprSecp256k1Recover {}
 
 const FISCO_BCOS_SIGNATURE_DATA_LENGTH: usize = 65;
+const COMPRESSED_PUBLIC_KEY_SIZE_IN_BYTES: usize = 33;
+/// Size of an uncompressed public key in bytes.
+pub const UNCOMPRESSED_PUBLIC_KEY_SIZE_IN_BYTES: usize = 65;
 const FISCO_BCOS_SIGNATURE_END_INDEX: usize =
     FISCO_BCOS_SIGNATURE_DATA_LENGTH - 1;
 
+impl AlgorithmInfo for WedprSecp256k1Recover {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "secp256k1",
+            oid: Some("1.3.132.0.10"),
+            security_bits: 128,
+            private_key_size: Some(32),
+            public_key_sizes: &[65, 33],
+            signature_size: Some(FISCO_BCOS_SIGNATURE_DATA_LENGTH),
+            output_size: None,
+        }
+    }
+}
+
 impl Signature for WedprSecp256k1Recover {
     fn sign<T: ?Sized + AsRef<[u8]>>(
         &self,
         private_key: &T,
         msg_hash: &T,
     ) -> Result<Vec<u8>, WedprError> {
-        let secret_key = match SecretKey::from_slice(&private_key.as_ref()) {
-            Ok(v) => v,
-            Err(_) => {
-                wedpr_println!("Parsing private key failed");
-                return Err(WedprError::FormatError);
-            },
-        };
-        // Message hash length for Secp256k1 signature should be 32 bytes.
-        let msg_hash_obj = match Message::from_slice(&msg_hash.as_ref()) {
-            Ok(v) => v,
-            Err(_) => {
-                wedpr_println!("Parsing message hash failed");
-                return Err(WedprError::FormatError);
-            },
-        };
-        let signature_obj =
-            SECP256K1_ALL.sign_recoverable(&msg_hash_obj, &secret_key);
-        let (recid, signature_bytes) = &signature_obj.serialize_compact();
-        // Append recovery id to the end of signature bytes.
-        let mut signature_output = signature_bytes.to_vec();
-        signature_output.push(recid.to_i32() as u8);
-        // The signature data contains two parts:
-        // sig\[0..64\): signature for the message hash.
-        // sig\[64\]: recovery id.
-        Ok(signature_output)
+        check_global_algorithm::<Self>()?;
+        sign_recoverable(private_key.as_ref(), msg_hash.as_ref())
+    }
+
+    /// Signs a message hash with the nonce function of libsecp256k1, which
+    /// derives the nonce per RFC 6979 without any extra entropy.
+    fn sign_deterministic<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        check_global_algorithm::<Self>()?;
+        sign_recoverable(private_key.as_ref(), msg_hash.as_ref())
     }
 
     fn verify<T: ?Sized + AsRef<[u8]>>(
@@ -68,18 +92,56 @@ impl Signature for WedprSecp256k1Recover {
         msg_hash: &T,
         signature: &T,
     ) -> bool {
-        // Message hash length for Secp256k1 signature should be 32 bytes.
-        let recover_public_key = match self.recover_public_key(msg_hash, signature) {
+        if !is_allowed_by_policy(signature.as_ref()) {
+            return false;
+        }
+        // Public keys are accepted in both compressed and uncompressed forms.
+        let public_key_obj = match parse_public_key(public_key.as_ref()) {
             Ok(v) => v,
             Err(_) => return false,
         };
-        if recover_public_key.ne(&public_key.as_ref().to_vec()) {
+        // Message hash length for Secp256k1 signature should be 32 bytes.
+        let recover_public_key =
+            match recover_public_key_obj(msg_hash.as_ref(), signature.as_ref())
+            {
+                Ok(v) => v,
+                Err(_) => return false,
+            };
+        // Parsed public keys are compared without serializing them.
+        if recover_public_key != public_key_obj {
             wedpr_println!("Matching signature public key failed");
             return false;
         }
         return true;
     }
 
+    /// Verifies signatures together by a randomized linear combination,
+    /// which falls back to verifying them one by one, in parallel if feature
+    /// wedpr_f_parallel is enabled, for inputs the combination cannot cover.
+    fn batch_verify<S: AsRef<[u8]> + Sync>(
+        &self,
+        public_keys: &[S],
+        msg_hashes: &[S],
+        signatures: &[S],
+    ) -> bool {
+        if public_keys.len() != msg_hashes.len()
+            || public_keys.len() != signatures.len()
+        {
+            wedpr_println!("Batch verification inputs have different lengths");
+            return false;
+        }
+        if !signatures
+            .iter()
+            .all(|signature| is_allowed_by_policy(signature.as_ref()))
+        {
+            return false;
+        }
+        match verify_linear_combination(public_keys, msg_hashes, signatures) {
+            Some(v) => v,
+            None => self.verify_each(public_keys, msg_hashes, signatures),
+        }
+    }
+
     fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
         let mut rng = rand::thread_rng();
         loop {
@@ -97,63 +159,347 @@ impl Signature for WedprSecp256k1Recover {
     }
 }
 
+/// Checks whether verifying a signature is allowed by the global policy,
+/// which requires low s values if require_low_s is set.
+fn is_allowed_by_policy(signature: &[u8]) -> bool {
+    if check_global_algorithm::<WedprSecp256k1Recover>().is_err() {
+        return false;
+    }
+    if global_policy().require_low_s && !malleability::is_low_s(signature) {
+        wedpr_println!("Signature with a high s value is not allowed");
+        return false;
+    }
+    true
+}
+
+/// Signs a message hash, where libsecp256k1 derives the nonce per RFC 6979.
+fn sign_recoverable(
+    private_key: &[u8],
+    msg_hash: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let secret_key = parse_secret_key(private_key)?;
+    // Message hash length for Secp256k1 signature should be 32 bytes.
+    let msg_hash_obj = match Message::from_slice(msg_hash) {
+        Ok(v) => v,
+        Err(_) => {
+            wedpr_println!("Parsing message hash failed");
+            return Err(WedprError::FormatError);
+        },
+    };
+    let signature_obj =
+        SECP256K1_ALL.sign_recoverable(&msg_hash_obj, &secret_key);
+    let (recid, signature_bytes) = &signature_obj.serialize_compact();
+    // Append recovery id to the end of signature bytes.
+    let mut signature_output = signature_bytes.to_vec();
+    signature_output.push(recid.to_i32() as u8);
+    // The signature data contains two parts:
+    // sig\[0..64\): signature for the message hash.
+    // sig\[64\]: recovery id.
+    Ok(signature_output)
+}
+
+/// Parses a private key, where the secret scalar is validated in constant time
+/// instead of returning early on its content.
+fn parse_secret_key(private_key: &[u8]) -> Result<SecretKey, WedprError> {
+    if !is_valid_secret_scalar(private_key, &CURVE_ORDER) {
+        wedpr_println!("Parsing private key failed");
+        return Err(WedprError::FormatError);
+    }
+    // The scalar is already known to be valid here, and libsecp256k1 verifies
+    // secret keys in constant time.
+    Ok(SecretKey::from_slice(private_key)?)
+}
+
+/// Parses a public key in the 33-byte compressed or 65-byte uncompressed
+/// SEC1 form, where other forms such as the hybrid form are rejected.
+pub(crate) fn parse_public_key(
+    public_key: &[u8],
+) -> Result<PublicKey, WedprError> {
+    let public_key_obj = match PublicKey::from_slice(public_key) {
+        Ok(v) => v,
+        Err(_) => {
+            wedpr_println!("Parsing public key failed");
+            return Err(WedprError::FormatError);
+        },
+    };
+    let is_canonical = match public_key.len() {
+        COMPRESSED_PUBLIC_KEY_SIZE_IN_BYTES => {
+            public_key_obj.serialize()[..] == *public_key
+        },
+        UNCOMPRESSED_PUBLIC_KEY_SIZE_IN_BYTES => {
+            public_key_obj.serialize_uncompressed()[..] == *public_key
+        },
+        _ => false,
+    };
+    if !is_canonical {
+        wedpr_println!("Public key is not in a SEC1 form");
+        return Err(WedprError::FormatError);
+    }
+    Ok(public_key_obj)
+}
+
 impl WedprSecp256k1Recover {
+    /// Converts a public key in either form to the 33-byte compressed form.
+    pub fn compress_public_key<T: ?Sized + AsRef<[u8]>>(
+        self,
+        public_key: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        Ok(parse_public_key(public_key.as_ref())?.serialize().to_vec())
+    }
+
+    /// Converts a public key in either form to the 65-byte uncompressed form.
+    pub fn decompress_public_key<T: ?Sized + AsRef<[u8]>>(
+        self,
+        public_key: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        Ok(parse_public_key(public_key.as_ref())?
+            .serialize_uncompressed()
+            .to_vec())
+    }
+
     pub fn recover_public_key<T: ?Sized + AsRef<[u8]>>(
         self,
         msg_hash: &T,
         signature: &T,
     ) -> Result<Vec<u8>, WedprError> {
-        // Message hash length for Secp256k1 signature should be 32 bytes.
-        let msg_hash_obj = match Message::from_slice(&msg_hash.as_ref()) {
+        Ok(self.recover_public_key_array(msg_hash, signature)?.to_vec())
+    }
+
+    /// Recovers the uncompressed public key as by recover_public_key into a
+    /// fixed-size array.
+    pub fn recover_public_key_array<T: ?Sized + AsRef<[u8]>>(
+        self,
+        msg_hash: &T,
+        signature: &T,
+    ) -> Result<[u8; UNCOMPRESSED_PUBLIC_KEY_SIZE_IN_BYTES], WedprError> {
+        let public_key =
+            recover_public_key_obj(msg_hash.as_ref(), signature.as_ref())?;
+        Ok(public_key.serialize_uncompressed())
+    }
+
+    /// Recovers the public keys of multiple signatures over the same message
+    /// hash, e.g. the signers of a block hash, where the message hash is only
+    /// parsed once. The output has one result for each signature in the same
+    /// order, and verification is parallelized if feature wedpr_f_parallel is
+    /// enabled.
+    pub fn recover_public_keys<
+        T: ?Sized + AsRef<[u8]>,
+        S: AsRef<[u8]> + Sync,
+    >(
+        self,
+        msg_hash: &T,
+        signatures: &[S],
+    ) -> Vec<Result<Vec<u8>, WedprError>> {
+        let msg_hash_obj = match parse_message_hash(msg_hash.as_ref()) {
             Ok(v) => v,
-            Err(_) => {
-                wedpr_println!("Parsing message hash failed");
-                return Err(WedprError::DecodeError);
+            Err(e) => {
+                return signatures.iter().map(|_| Err(e.clone())).collect();
             },
         };
-        if signature.as_ref().len() != FISCO_BCOS_SIGNATURE_DATA_LENGTH {
-            wedpr_println!("Signature length is not 65");
-            return Err(WedprError::DecodeError);
+        #[cfg(feature = "wedpr_f_parallel")]
+        let signature_iter = signatures.par_iter();
+        #[cfg(not(feature = "wedpr_f_parallel"))]
+        let signature_iter = signatures.iter();
+        signature_iter
+            .map(|signature| {
+                Ok(recover_public_key_with_message(
+                    &msg_hash_obj,
+                    signature.as_ref(),
+                )?
+                .serialize_uncompressed()
+                .to_vec())
+            })
+            .collect()
+    }
+}
+
+impl WedprSecp256k1Recover {
+    fn verify_each<S: AsRef<[u8]> + Sync>(
+        self,
+        public_keys: &[S],
+        msg_hashes: &[S],
+        signatures: &[S],
+    ) -> bool {
+        #[cfg(feature = "wedpr_f_parallel")]
+        let index_iter = (0..signatures.len()).into_par_iter();
+        #[cfg(not(feature = "wedpr_f_parallel"))]
+        let mut index_iter = 0..signatures.len();
+        index_iter.all(|i| {
+            self.verify(
+                public_keys[i].as_ref(),
+                msg_hashes[i].as_ref(),
+                signatures[i].as_ref(),
+            )
+        })
+    }
+}
+
+/// Verifies signatures by checking the randomized linear combination
+/// sum(a_i * s_i * R_i) == sum(a_i * z_i) * G + sum(a_i * r_i * Q_i), where
+/// R_i is the nonce point restored from r_i and the recovery id, z_i is the
+/// message hash, Q_i is the public key, and a_i are random weights, so that
+/// any invalid signature only passes with a negligible probability. Returns
+/// None if the inputs cannot be combined, e.g. a recovery id of an
+/// overflowed r_i or an intermediate sum at infinity, which should be
+/// verified one by one instead.
+fn verify_linear_combination<S: AsRef<[u8]>>(
+    public_keys: &[S],
+    msg_hashes: &[S],
+    signatures: &[S],
+) -> Option<bool> {
+    let mut rng = rand::thread_rng();
+    let mut nonce_sum: Option<PublicKey> = None;
+    let mut key_sum: Option<PublicKey> = None;
+    let mut hash_sum: Option<SecretKey> = None;
+    for i in 0..signatures.len() {
+        let public_key_bytes = public_keys[i].as_ref();
+        let signature = signatures[i].as_ref();
+        if signature.len() != FISCO_BCOS_SIGNATURE_DATA_LENGTH
+            || msg_hashes[i].as_ref().len() != 32
+        {
+            return Some(false);
+        }
+        let rec_id = signature[FISCO_BCOS_SIGNATURE_END_INDEX];
+        match rec_id {
+            0 | 1 => (),
+            2 | 3 => return None,
+            _ => return Some(false),
+        }
+        let public_key = match parse_public_key(public_key_bytes) {
+            Ok(v) => v,
+            Err(_) => return Some(false),
         };
-        let rec_id = match RecoveryId::from_i32(
-            signature.as_ref()[FISCO_BCOS_SIGNATURE_END_INDEX] as i32,
+        let mut nonce_bytes = vec![0x02 | rec_id];
+        nonce_bytes.extend_from_slice(&signature[0..32]);
+        let (nonce, r, s) = match (
+            PublicKey::from_slice(&nonce_bytes),
+            SecretKey::from_slice(&signature[0..32]),
+            SecretKey::from_slice(&signature[32..64]),
         ) {
+            (Ok(nonce), Ok(r), Ok(s)) => (nonce, r, s),
+            _ => return Some(false),
+        };
+        // A zero or overflowed hash is reduced by ECDSA but not by SecretKey.
+        let z = SecretKey::from_slice(msg_hashes[i].as_ref()).ok()?;
+
+        let weight = SecretKey::new(&mut rng);
+        let nonce_term = weighted_point(nonce, s, &weight)?;
+        let key_term = weighted_point(public_key, r, &weight)?;
+        let mut hash_term = z;
+        hash_term.mul_assign(&weight[..]).ok()?;
+
+        nonce_sum = Some(add_point(nonce_sum, &nonce_term)?);
+        key_sum = Some(add_point(key_sum, &key_term)?);
+        hash_sum = Some(match hash_sum {
+            Some(mut v) => {
+                v.add_assign(&hash_term[..]).ok()?;
+                v
+            },
+            None => hash_term,
+        });
+    }
+    match (nonce_sum, key_sum, hash_sum) {
+        (Some(nonce_sum), Some(key_sum), Some(hash_sum)) => {
+            let hash_point =
+                PublicKey::from_secret_key(&SECP256K1_ALL, &hash_sum);
+            Some(nonce_sum == hash_point.combine(&key_sum).ok()?)
+        },
+        // An empty batch is trivially valid.
+        _ => Some(true),
+    }
+}
+
+/// Computes (scalar * weight) * point.
+fn weighted_point(
+    point: PublicKey,
+    scalar: SecretKey,
+    weight: &SecretKey,
+) -> Option<PublicKey> {
+    let mut weighted_scalar = scalar;
+    weighted_scalar.mul_assign(&weight[..]).ok()?;
+    let mut result = point;
+    result
+        .mul_assign(&SECP256K1_VERIFY, &weighted_scalar[..])
+        .ok()?;
+    Some(result)
+}
+
+/// Adds a point to a running sum, which fails at infinity.
+fn add_point(sum: Option<PublicKey>, point: &PublicKey) -> Option<PublicKey> {
+    match sum {
+        Some(v) => v.combine(point).ok(),
+        None => Some(*point),
+    }
+}
+
+/// Parses a message hash, whose length for Secp256k1 signature should be 32
+/// bytes.
+fn parse_message_hash(msg_hash: &[u8]) -> Result<Message, WedprError> {
+    match Message::from_slice(msg_hash) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Parsing message hash failed");
+            Err(WedprError::DecodeError)
+        },
+    }
+}
+
+/// Recovers the public key of a signature over a message hash.
+pub(crate) fn recover_public_key_obj(
+    msg_hash: &[u8],
+    signature: &[u8],
+) -> Result<PublicKey, WedprError> {
+    let msg_hash_obj = parse_message_hash(msg_hash)?;
+    recover_public_key_with_message(&msg_hash_obj, signature)
+}
+
+/// Recovers the public key of a signature with a parsed message hash.
+fn recover_public_key_with_message(
+    msg_hash_obj: &Message,
+    signature: &[u8],
+) -> Result<PublicKey, WedprError> {
+    if signature.len() != FISCO_BCOS_SIGNATURE_DATA_LENGTH {
+        wedpr_println!("Signature length is not 65");
+        return Err(WedprError::DecodeError);
+    };
+    let rec_id = match RecoveryId::from_i32(
+        signature[FISCO_BCOS_SIGNATURE_END_INDEX] as i32,
+    ) {
+        Ok(v) => v,
+        Err(_) => {
+            wedpr_println!("Parsing RecoveryId failed");
+            return Err(WedprError::DecodeError);
+        },
+    };
+
+    // The last byte is recovery id, we only need to get the first 64 bytes
+    // for signature data.
+    let signature_byte = &signature[0..FISCO_BCOS_SIGNATURE_END_INDEX];
+
+    let get_sign_final =
+        match RecoverableSignature::from_compact(signature_byte, rec_id) {
             Ok(v) => v,
             Err(_) => {
-                wedpr_println!("Parsing RecoveryId failed");
-                return Err(WedprError::DecodeError);
+                wedpr_println!("Signature from_compact failed");
+                return Err(WedprError::FormatError);
             },
         };
-
-        // The last byte is recovery id, we only need to get the first 64 bytes
-        // for signature data.
-        let signature_byte =
-            &signature.as_ref()[0..FISCO_BCOS_SIGNATURE_END_INDEX];
-
-        let get_sign_final =
-            match RecoverableSignature::from_compact(signature_byte, rec_id) {
-                Ok(v) => v,
-                Err(_) => {
-                    wedpr_println!("Signature from_compact failed");
-                    return Err(WedprError::FormatError);
-                },
-            };
-        let recovered_public_key =
-            match SECP256K1_VERIFY.recover(&msg_hash_obj, &get_sign_final) {
-                Ok(v) => v,
-                Err(_) => {
-                    wedpr_println!("Signature recover failed");
-                    return Err(WedprError::FormatError);
-                },
-            };
-        return Ok(recovered_public_key.serialize_uncompressed().to_vec());
+    match SECP256K1_VERIFY.recover(msg_hash_obj, &get_sign_final) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Signature recover failed");
+            Err(WedprError::FormatError)
+        },
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use wedpr_l_utils::constant::tests::BASE64_ENCODED_TEST_MESSAGE;
+    use bytes::Bytes;
+    use wedpr_l_utils::constant::tests::{
+        BASE64_ENCODED_TEST_MESSAGE, SECP256K1_TEST_SECRET_KEY,
+    };
 
     #[test]
     fn test_secp256k1_recover() {
@@ -166,13 +512,270 @@ mod tests {
 
         let signature =
             secp256k1.sign(&secret_key, &msg_hash.to_vec()).unwrap();
+        assert!(secp256k1.verify(
+            &public_key.to_vec(),
+            &msg_hash.to_vec(),
+            &signature
+        ));
+
+        // Shared buffers are verified in place.
+        let public_key = Bytes::from(public_key);
+        let msg_hash = Bytes::from_static(&BASE64_ENCODED_TEST_MESSAGE);
+        let signature = Bytes::from(signature);
+        assert!(secp256k1.verify(&public_key, &msg_hash, &signature));
+        assert!(secp256k1.batch_verify(
+            &[public_key.clone(), public_key.slice(..)],
+            &[msg_hash.clone(), msg_hash.clone()],
+            &[signature.clone(), signature.slice(..)]
+        ));
+        assert!(!secp256k1.verify(
+            &public_key,
+            &msg_hash,
+            &signature.slice(1..)
+        ));
+        let recovered_public_key = secp256k1
+            .recover_public_key_array(&msg_hash, &signature)
+            .unwrap();
+        assert_eq!(recovered_public_key[..], public_key[..]);
+    }
+
+    #[test]
+    fn test_secp256k1_algorithm_info() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let metadata = WedprSecp256k1Recover::algorithm_info();
+        let (public_key, secret_key) = secp256k1.generate_keypair();
+        let signature = secp256k1
+            .sign(&secret_key, &BASE64_ENCODED_TEST_MESSAGE.to_vec())
+            .unwrap();
+        assert!(metadata.check_private_key(&secret_key).is_ok());
+        assert!(metadata.check_public_key(&public_key).is_ok());
+        assert!(metadata.check_signature(&signature).is_ok());
+        assert!(metadata.check_signature(&signature[1..]).is_err());
+    }
+
+    #[test]
+    fn test_secp256k1_parse_secret_key() {
+        assert!(parse_secret_key(&SECP256K1_TEST_SECRET_KEY).is_ok());
+        let mut max_secret_key = CURVE_ORDER;
+        max_secret_key[31] -= 1;
+        assert!(parse_secret_key(&max_secret_key).is_ok());
+
+        assert!(parse_secret_key(&[0u8; 32]).is_err());
+        assert!(parse_secret_key(&CURVE_ORDER).is_err());
+        assert!(parse_secret_key(&[0xffu8; 32]).is_err());
+        assert!(parse_secret_key(&SECP256K1_TEST_SECRET_KEY[1..]).is_err());
+    }
+
+    #[test]
+    fn test_secp256k1_recover_public_keys() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let msg_hash = BASE64_ENCODED_TEST_MESSAGE.to_vec();
+        let keypairs: Vec<(Vec<u8>, Vec<u8>)> =
+            (0..4).map(|_| secp256k1.generate_keypair()).collect();
+        let mut signatures: Vec<Vec<u8>> = keypairs
+            .iter()
+            .map(|(_, secret_key)| {
+                secp256k1.sign(secret_key, &msg_hash).unwrap()
+            })
+            .collect();
+        // Appends an invalid signature which cannot be parsed.
+        signatures.push(vec![0u8; 10]);
+
+        let recovered_public_keys =
+            secp256k1.recover_public_keys(&msg_hash, &signatures);
+        assert_eq!(recovered_public_keys.len(), signatures.len());
+        for (i, (public_key, _)) in keypairs.iter().enumerate() {
+            assert_eq!(recovered_public_keys[i].as_ref().unwrap(), public_key);
+        }
         assert_eq!(
-            true,
-            secp256k1.verify(
-                &public_key.to_vec(),
-                &msg_hash.to_vec(),
-                &signature
-            )
+            recovered_public_keys[keypairs.len()],
+            Err(WedprError::DecodeError)
+        );
+
+        // An invalid message hash fails all recoveries.
+        let invalid_results =
+            secp256k1.recover_public_keys(&msg_hash[1..], &signatures);
+        assert_eq!(invalid_results.len(), signatures.len());
+        assert!(invalid_results.iter().all(|result| result.is_err()));
+    }
+
+    #[test]
+    fn test_secp256k1_sign_message() {
+        use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+        use wedpr_l_utils::{digest::MessageDigest, traits::Hash};
+
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let (public_key, secret_key) = secp256k1.generate_keypair();
+        let message = b"raw message of any length";
+
+        // A plain Keccak256 digest is compatible with FISCO-BCOS.
+        let fisco_digest = MessageDigest::new(WedprKeccak256::default());
+        let signature = secp256k1
+            .sign_message(&secret_key, message, &fisco_digest)
+            .unwrap();
+        assert!(secp256k1.verify_message(
+            &public_key,
+            message,
+            &signature,
+            &fisco_digest
+        ));
+        assert!(secp256k1.verify(
+            &public_key,
+            &WedprKeccak256::default().hash(message),
+            &signature
+        ));
+
+        // Signatures cannot be replayed across domains.
+        let app_digest =
+            MessageDigest::with_domain(WedprKeccak256::default(), b"app");
+        assert!(!secp256k1.verify_message(
+            &public_key,
+            message,
+            &signature,
+            &app_digest
+        ));
+    }
+
+    #[test]
+    fn test_secp256k1_public_key_compression() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let (public_key, secret_key) = secp256k1.generate_keypair();
+        let compressed = secp256k1.compress_public_key(&public_key).unwrap();
+        assert_eq!(compressed.len(), 33);
+        assert_eq!(
+            secp256k1.decompress_public_key(&compressed).unwrap(),
+            public_key
+        );
+        assert_eq!(
+            secp256k1.compress_public_key(&compressed).unwrap(),
+            compressed
+        );
+
+        let msg_hash = BASE64_ENCODED_TEST_MESSAGE.to_vec();
+        let signature = secp256k1.sign(&secret_key, &msg_hash).unwrap();
+        assert!(secp256k1.verify(&public_key, &msg_hash, &signature));
+        assert!(secp256k1.verify(&compressed, &msg_hash, &signature));
+        assert!(secp256k1.batch_verify(
+            &[compressed.clone(), public_key.clone()],
+            &[msg_hash.clone(), msg_hash.clone()],
+            &[signature.clone(), signature.clone()]
+        ));
+
+        // The hybrid form is rejected.
+        let mut hybrid = public_key.clone();
+        hybrid[0] = 0x06 | (public_key[64] & 1);
+        assert!(secp256k1.compress_public_key(&hybrid).is_err());
+        assert!(!secp256k1.verify(&hybrid, &msg_hash, &signature));
+        assert!(secp256k1.decompress_public_key(&compressed[1..]).is_err());
+    }
+
+    #[test]
+    fn test_secp256k1_sign_deterministic() {
+        use wedpr_l_crypto_hash_sha256::WedprSha256;
+        use wedpr_l_utils::traits::Hash;
+
+        let secp256k1 = WedprSecp256k1Recover::default();
+        // Test vector of RFC 6979 with private key 1.
+        let mut secret_key = [0u8; 32];
+        secret_key[31] = 1;
+        let msg_hash = WedprSha256::default().hash(b"Satoshi Nakamoto");
+        let expected_signature = [
+            "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8",
+            "2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5",
+        ]
+        .concat();
+
+        let signature = secp256k1
+            .sign_deterministic(&secret_key[..], &msg_hash[..])
+            .unwrap();
+        let signature_hex: String = signature[..64]
+            .iter()
+            .map(|v| format!("{:02x}", v))
+            .collect();
+        assert_eq!(signature_hex, expected_signature);
+        assert_eq!(
+            signature,
+            secp256k1
+                .sign_deterministic(&secret_key[..], &msg_hash[..])
+                .unwrap()
+        );
+        let public_key = secp256k1
+            .recover_public_key(&msg_hash[..], &signature[..])
+            .unwrap();
+        assert!(secp256k1.verify(
+            &public_key[..],
+            &msg_hash[..],
+            &signature[..]
+        ));
+    }
+
+    #[test]
+    fn test_secp256k1_batch_verify() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let mut public_keys = Vec::new();
+        let mut msg_hashes = Vec::new();
+        let mut signatures = Vec::new();
+        for i in 0..16u8 {
+            let (public_key, secret_key) = secp256k1.generate_keypair();
+            let msg_hash = [i + 1; 32].to_vec();
+            signatures.push(secp256k1.sign(&secret_key, &msg_hash).unwrap());
+            public_keys.push(public_key);
+            msg_hashes.push(msg_hash);
+        }
+        assert!(secp256k1.batch_verify(&public_keys, &msg_hashes, &signatures));
+        let empty: &[Vec<u8>] = &[];
+        assert!(secp256k1.batch_verify(empty, empty, empty));
+        assert!(!secp256k1.batch_verify(
+            &public_keys,
+            &msg_hashes[1..],
+            &signatures
+        ));
+
+        // Swapping signatures breaks the batch.
+        let mut swapped = signatures.clone();
+        swapped.swap(0, 1);
+        assert!(!secp256k1.batch_verify(&public_keys, &msg_hashes, &swapped));
+        // A flipped recovery id restores another nonce point.
+        let mut flipped = signatures.clone();
+        flipped[3][FISCO_BCOS_SIGNATURE_END_INDEX] ^= 1;
+        assert!(!secp256k1.batch_verify(&public_keys, &msg_hashes, &flipped));
+        // A zero message hash falls back to verifying one by one.
+        let (public_key, secret_key) = secp256k1.generate_keypair();
+        let zero_hash = vec![0u8; 32];
+        let zero_hash_signature =
+            secp256k1.sign(&secret_key, &zero_hash).unwrap();
+        assert!(secp256k1.batch_verify(
+            &[public_keys[0].clone(), public_key],
+            &[msg_hashes[0].clone(), zero_hash],
+            &[signatures[0].clone(), zero_hash_signature]
+        ));
+    }
+
+    #[cfg(feature = "wedpr_f_dudect")]
+    #[test]
+    fn test_secp256k1_parse_secret_key_timing() {
+        use rand::RngCore;
+        use wedpr_l_utils::dudect::{
+            fixed_vs_random_t_statistic, DUDECT_T_THRESHOLD,
+        };
+
+        let mut rng = rand::thread_rng();
+        // Compares an invalid key (above the curve order) against random
+        // keys, which are almost always valid.
+        let t_statistic = fixed_vs_random_t_statistic(
+            |input| is_valid_secret_scalar(input, &CURVE_ORDER),
+            &[0xffu8; 32],
+            || {
+                let mut key = vec![0u8; 32];
+                rng.fill_bytes(&mut key);
+                key
+            },
+            100000,
+        );
+        assert!(
+            t_statistic < DUDECT_T_THRESHOLD,
+            "secret key validation leaks timing, t = {}",
+            t_statistic
         );
     }
 }
@@ -0,0 +1,258 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Signature malleability corpus functions, generating known malleated and
+//! invalid variants of a valid signature for negative tests of SDKs.
+
+use crate::{
+    WedprSecp256k1Recover, FISCO_BCOS_SIGNATURE_DATA_LENGTH,
+    FISCO_BCOS_SIGNATURE_END_INDEX, UNCOMPRESSED_PUBLIC_KEY_SIZE_IN_BYTES,
+};
+use secp256k1::{constants::CURVE_ORDER, PublicKey};
+use wedpr_l_utils::{error::WedprError, migration::LegacyCanonicalizer};
+
+const SCALAR_SIZE_IN_BYTES: usize = 32;
+
+/// Kinds of signature variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MalleationKind {
+    /// s replaced by n - s with the recovery id flipped, which still recovers
+    /// the same public key and is only rejected by low-s strict verifiers.
+    HighS,
+    /// Recovery id flipped, which recovers a different public key.
+    FlippedRecoveryId,
+    /// Recovery id out of the range [0, 3].
+    InvalidRecoveryId,
+    /// Last byte removed.
+    Truncated,
+    /// Trailing zero byte appended.
+    Extended,
+    /// Lowest bit of r flipped.
+    BitFlippedR,
+    /// Lowest bit of s flipped.
+    BitFlippedS,
+    /// r set to zero.
+    ZeroR,
+    /// s set to zero.
+    ZeroS,
+}
+
+/// A variant of a valid signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MalleatedSignature {
+    pub kind: MalleationKind,
+    pub signature: Vec<u8>,
+}
+
+/// Checks whether a signature has s in the lower half of the curve order, as
+/// required by strict verifiers.
+pub fn is_low_s(signature: &[u8]) -> bool {
+    if signature.len() != FISCO_BCOS_SIGNATURE_DATA_LENGTH {
+        return false;
+    }
+    let s = &signature[SCALAR_SIZE_IN_BYTES..FISCO_BCOS_SIGNATURE_END_INDEX];
+    // Compares 2 * s with n, i.e. s <= (n - 1) / 2 since n is odd.
+    let mut doubled_s = [0u8; SCALAR_SIZE_IN_BYTES];
+    let mut carry = 0u16;
+    for i in (0..SCALAR_SIZE_IN_BYTES).rev() {
+        let value = ((s[i] as u16) << 1) | carry;
+        doubled_s[i] = value as u8;
+        carry = value >> 8;
+    }
+    carry == 0 && doubled_s[..] < CURVE_ORDER[..]
+}
+
+/// Computes n - s for a big-endian scalar s.
+fn negate_scalar(s: &[u8]) -> Vec<u8> {
+    let mut output = vec![0u8; SCALAR_SIZE_IN_BYTES];
+    let mut borrow = 0i16;
+    for i in (0..SCALAR_SIZE_IN_BYTES).rev() {
+        let mut value = CURVE_ORDER[i] as i16 - s[i] as i16 - borrow;
+        borrow = 0;
+        if value < 0 {
+            value += 256;
+            borrow = 1;
+        }
+        output[i] = value as u8;
+    }
+    output
+}
+
+/// Converts a signature with a high s value to the equivalent one with a low
+/// s value, or returns None if it is malformed or already has a low s value.
+pub fn normalize_s(signature: &[u8]) -> Option<Vec<u8>> {
+    if signature.len() != FISCO_BCOS_SIGNATURE_DATA_LENGTH
+        || signature[FISCO_BCOS_SIGNATURE_END_INDEX] > 3
+        || is_low_s(signature)
+    {
+        return None;
+    }
+    let mut output = signature[..SCALAR_SIZE_IN_BYTES].to_vec();
+    output.extend_from_slice(&negate_scalar(
+        &signature[SCALAR_SIZE_IN_BYTES..FISCO_BCOS_SIGNATURE_END_INDEX],
+    ));
+    output.push(signature[FISCO_BCOS_SIGNATURE_END_INDEX] ^ 1);
+    Some(output)
+}
+
+/// Converts high-s signatures and hybrid public keys of legacy producers.
+impl LegacyCanonicalizer for WedprSecp256k1Recover {
+    fn canonicalize_signature(&self, signature: &[u8]) -> Option<Vec<u8>> {
+        normalize_s(signature)
+    }
+
+    fn canonicalize_public_key(&self, public_key: &[u8]) -> Option<Vec<u8>> {
+        // Hybrid keys of prefix 0x06 or 0x07 also encode the parity of y.
+        if public_key.len() != UNCOMPRESSED_PUBLIC_KEY_SIZE_IN_BYTES
+            || (public_key[0] != 0x06 && public_key[0] != 0x07)
+        {
+            return None;
+        }
+        match PublicKey::from_slice(public_key) {
+            Ok(v) => Some(v.serialize_uncompressed().to_vec()),
+            Err(_) => None,
+        }
+    }
+}
+
+/// Generates all known malleated and invalid variants of a valid signature.
+pub fn generate_malleated_signatures(
+    signature: &[u8],
+) -> Result<Vec<MalleatedSignature>, WedprError> {
+    if signature.len() != FISCO_BCOS_SIGNATURE_DATA_LENGTH
+        || signature[FISCO_BCOS_SIGNATURE_END_INDEX] > 3
+    {
+        wedpr_println!("Malleating an invalid signature");
+        return Err(WedprError::ArgumentError);
+    }
+    let recovery_id = signature[FISCO_BCOS_SIGNATURE_END_INDEX];
+    let variant = |kind: MalleationKind, signature: Vec<u8>| {
+        MalleatedSignature { kind, signature }
+    };
+    let modified = |index: usize, value: u8| {
+        let mut output = signature.to_vec();
+        output[index] = value;
+        output
+    };
+
+    let mut high_s = signature[..SCALAR_SIZE_IN_BYTES].to_vec();
+    high_s.extend_from_slice(&negate_scalar(
+        &signature[SCALAR_SIZE_IN_BYTES..FISCO_BCOS_SIGNATURE_END_INDEX],
+    ));
+    high_s.push(recovery_id ^ 1);
+    let mut zero_r = signature.to_vec();
+    zero_r[..SCALAR_SIZE_IN_BYTES]
+        .copy_from_slice(&[0u8; SCALAR_SIZE_IN_BYTES]);
+    let mut zero_s = signature.to_vec();
+    zero_s[SCALAR_SIZE_IN_BYTES..FISCO_BCOS_SIGNATURE_END_INDEX]
+        .copy_from_slice(&[0u8; SCALAR_SIZE_IN_BYTES]);
+    let mut extended = signature.to_vec();
+    extended.push(0);
+
+    Ok(vec![
+        variant(MalleationKind::HighS, high_s),
+        variant(
+            MalleationKind::FlippedRecoveryId,
+            modified(FISCO_BCOS_SIGNATURE_END_INDEX, recovery_id ^ 1),
+        ),
+        variant(
+            MalleationKind::InvalidRecoveryId,
+            modified(FISCO_BCOS_SIGNATURE_END_INDEX, 4),
+        ),
+        variant(
+            MalleationKind::Truncated,
+            signature[..FISCO_BCOS_SIGNATURE_END_INDEX].to_vec(),
+        ),
+        variant(MalleationKind::Extended, extended),
+        variant(
+            MalleationKind::BitFlippedR,
+            modified(
+                SCALAR_SIZE_IN_BYTES - 1,
+                signature[SCALAR_SIZE_IN_BYTES - 1] ^ 1,
+            ),
+        ),
+        variant(
+            MalleationKind::BitFlippedS,
+            modified(
+                FISCO_BCOS_SIGNATURE_END_INDEX - 1,
+                signature[FISCO_BCOS_SIGNATURE_END_INDEX - 1] ^ 1,
+            ),
+        ),
+        variant(MalleationKind::ZeroR, zero_r),
+        variant(MalleationKind::ZeroS, zero_s),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_utils::{
+        constant::tests::BASE64_ENCODED_TEST_MESSAGE,
+        migration::{CompatibilityMode, DeprecationEvent, MigratingSignature},
+        policy::{with_scoped_policy, SecurityPolicy},
+        traits::Signature,
+    };
+
+    #[test]
+    fn test_malleated_signatures() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let (public_key, secret_key) = secp256k1.generate_keypair();
+        let msg_hash = BASE64_ENCODED_TEST_MESSAGE.to_vec();
+        let signature = secp256k1.sign(&secret_key, &msg_hash).unwrap();
+        assert!(is_low_s(&signature));
+
+        let variants = generate_malleated_signatures(&signature).unwrap();
+        assert_eq!(variants.len(), 9);
+        for variant in &variants {
+            let result =
+                secp256k1.verify(&public_key, &msg_hash, &variant.signature);
+            if variant.kind == MalleationKind::HighS {
+                // Recovery does not enforce low s, thus it is only rejected
+                // if the policy requires low s values.
+                assert!(result);
+                assert!(!is_low_s(&variant.signature));
+            } else {
+                assert!(!result, "{:?} should be rejected", variant.kind);
+            }
+        }
+        assert!(generate_malleated_signatures(&signature[1..]).is_err());
+        assert_eq!(
+            normalize_s(&variants[0].signature),
+            Some(signature.clone())
+        );
+        assert_eq!(normalize_s(&variants[0].signature[1..]), None);
+        let low_s_policy = SecurityPolicy {
+            require_low_s: true,
+            ..SecurityPolicy::default()
+        };
+        with_scoped_policy(low_s_policy, || {
+            assert!(!secp256k1.verify(
+                &public_key,
+                &msg_hash,
+                &variants[0].signature
+            ));
+            assert!(!secp256k1.batch_verify(
+                std::slice::from_ref(&public_key),
+                std::slice::from_ref(&msg_hash),
+                std::slice::from_ref(&variants[0].signature)
+            ));
+            assert!(secp256k1.verify(&public_key, &msg_hash, &signature));
+        });
+
+        // Hybrid public keys are only accepted in migration mode.
+        let mut hybrid_public_key = public_key.clone();
+        hybrid_public_key[0] = 0x06 | (public_key[64] & 1);
+        let signature = secp256k1.sign(&secret_key, &msg_hash).unwrap();
+        assert!(!secp256k1.verify(&hybrid_public_key, &msg_hash, &signature));
+        assert_eq!(
+            secp256k1.canonicalize_public_key(&hybrid_public_key),
+            Some(public_key.clone())
+        );
+        assert_eq!(secp256k1.canonicalize_public_key(&public_key), None);
+        let migrating = MigratingSignature::new(
+            secp256k1,
+            CompatibilityMode::Migration,
+            |event: &DeprecationEvent| assert_eq!(event.algorithm, "secp256k1"),
+        );
+        assert!(migrating.verify(&hybrid_public_key, &msg_hash, &signature));
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Signer index functions for tallying consensus votes.
+
+use crate::WedprSecp256k1Recover;
+use secp256k1::PublicKey;
+use std::collections::HashMap;
+
+/// Index of the validators who signed the same message hash, where bit i of
+/// the bitmap (byte i / 8, least significant bit first) is set if the i-th
+/// validator has a valid signature in the batch.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct SignerIndex {
+    pub bitmap: Vec<u8>,
+    /// Indices of signatures in the batch which cannot be recovered, do not
+    /// belong to any validator, or duplicate an earlier signer.
+    pub invalid_indices: Vec<usize>,
+}
+
+impl SignerIndex {
+    /// Checks whether the validator at the given index has signed.
+    pub fn has_signed(&self, validator_index: usize) -> bool {
+        match self.bitmap.get(validator_index / 8) {
+            Some(byte) => (byte >> (validator_index % 8)) & 1 == 1,
+            None => false,
+        }
+    }
+
+    /// Counts the validators who have signed.
+    pub fn signer_count(&self) -> usize {
+        self.bitmap
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .sum()
+    }
+}
+
+impl WedprSecp256k1Recover {
+    /// Builds a signer index for a batch of recoverable signatures over the
+    /// same message hash, given the ordered public key set of all validators.
+    /// Validator public keys can be either compressed or uncompressed, and an
+    /// invalid validator public key will never be matched.
+    pub fn index_signers<
+        T: ?Sized + AsRef<[u8]>,
+        P: AsRef<[u8]>,
+        S: AsRef<[u8]> + Sync,
+    >(
+        self,
+        msg_hash: &T,
+        validator_public_keys: &[P],
+        signatures: &[S],
+    ) -> SignerIndex {
+        // Recovered public keys are uncompressed, thus validator public keys
+        // are normalized to the same format before matching.
+        let mut validator_positions = HashMap::new();
+        for (i, public_key) in validator_public_keys.iter().enumerate() {
+            if let Ok(v) = PublicKey::from_slice(public_key.as_ref()) {
+                validator_positions
+                    .entry(v.serialize_uncompressed().to_vec())
+                    .or_insert(i);
+            }
+        }
+
+        let mut signer_index = SignerIndex {
+            bitmap: vec![0u8; validator_public_keys.len().div_ceil(8)],
+            invalid_indices: Vec::new(),
+        };
+        let recovered_public_keys =
+            self.recover_public_keys(msg_hash, signatures);
+        for (i, recovered) in recovered_public_keys.iter().enumerate() {
+            let position = match recovered {
+                Ok(v) => validator_positions.get(v),
+                Err(_) => None,
+            };
+            match position {
+                Some(&v) if !signer_index.has_signed(v) => {
+                    signer_index.bitmap[v / 8] |= 1 << (v % 8);
+                },
+                _ => signer_index.invalid_indices.push(i),
+            }
+        }
+        signer_index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_utils::{
+        constant::tests::BASE64_ENCODED_TEST_MESSAGE, traits::Signature,
+    };
+
+    #[test]
+    fn test_index_signers() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let msg_hash = BASE64_ENCODED_TEST_MESSAGE.to_vec();
+        let validators: Vec<(Vec<u8>, Vec<u8>)> =
+            (0..10).map(|_| secp256k1.generate_keypair()).collect();
+        let validator_public_keys: Vec<Vec<u8>> = validators
+            .iter()
+            .map(|(public_key, _)| public_key.clone())
+            .collect();
+
+        let signed_positions = [0usize, 3, 8, 9];
+        let mut signatures: Vec<Vec<u8>> = signed_positions
+            .iter()
+            .map(|&i| secp256k1.sign(&validators[i].1, &msg_hash).unwrap())
+            .collect();
+        // Duplicated signer.
+        signatures.push(signatures[0].clone());
+        // Unknown signer.
+        let (_, outsider_secret_key) = secp256k1.generate_keypair();
+        signatures
+            .push(secp256k1.sign(&outsider_secret_key, &msg_hash).unwrap());
+        // Malformed signature.
+        signatures.push(vec![0u8; 65]);
+
+        let signer_index = secp256k1.index_signers(
+            &msg_hash,
+            &validator_public_keys,
+            &signatures,
+        );
+        assert_eq!(signer_index.bitmap, vec![0b0000_1001, 0b0000_0011]);
+        assert_eq!(signer_index.signer_count(), signed_positions.len());
+        for i in 0..validators.len() {
+            assert_eq!(
+                signer_index.has_signed(i),
+                signed_positions.contains(&i)
+            );
+        }
+        assert!(!signer_index.has_signed(validators.len() + 8));
+        assert_eq!(signer_index.invalid_indices, vec![4, 5, 6]);
+    }
+}
@@ -0,0 +1,376 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Secp256r1 (NIST P-256) ECDSA signature functions.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+use p256::{
+    ecdsa::{
+        signature::hazmat::{PrehashSigner, PrehashVerifier},
+        Signature as P256Signature, SigningKey, VerifyingKey,
+    },
+    pkcs8::{
+        DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey,
+    },
+};
+use rand::RngCore;
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    error::WedprError,
+    migration::LegacyCanonicalizer,
+    policy::{check_global_algorithm, global_policy},
+    traits::{AlgorithmInfo, Signature},
+};
+
+const PRIVATE_KEY_SIZE_IN_BYTES: usize = 32;
+const SIGNATURE_SIZE_IN_BYTES: usize = 64;
+
+/// Implements ECDSA over secp256r1 as a Signature instance, whose signatures
+/// are r || s of 64 bytes, with nonces derived by RFC 6979. Signatures have
+/// low s values if required by the security policy.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct WedprSecp256r1 {}
+
+impl AlgorithmInfo for WedprSecp256r1 {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "secp256r1",
+            oid: Some("1.2.840.10045.3.1.7"),
+            security_bits: 128,
+            private_key_size: Some(PRIVATE_KEY_SIZE_IN_BYTES),
+            public_key_sizes: &[65, 33],
+            signature_size: Some(SIGNATURE_SIZE_IN_BYTES),
+            output_size: None,
+        }
+    }
+}
+
+impl Signature for WedprSecp256r1 {
+    fn sign<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        check_global_algorithm::<Self>()?;
+        let signing_key = parse_private_key(private_key.as_ref())?;
+        let signature: P256Signature =
+            match signing_key.sign_prehash(msg_hash.as_ref()) {
+                Ok(v) => v,
+                Err(_) => {
+                    wedpr_println!("Signing message hash failed");
+                    return Err(WedprError::ArgumentError);
+                },
+            };
+        let signature = if global_policy().require_low_s {
+            signature.normalize_s().unwrap_or(signature)
+        } else {
+            signature
+        };
+        Ok(signature.to_bytes().to_vec())
+    }
+
+    /// Signs a message hash, where nonces are always derived by RFC 6979.
+    fn sign_deterministic<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        self.sign(private_key, msg_hash)
+    }
+
+    fn verify<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        msg_hash: &T,
+        signature: &T,
+    ) -> bool {
+        if check_global_algorithm::<Self>().is_err() {
+            return false;
+        }
+        let signature_obj = match P256Signature::from_slice(signature.as_ref())
+        {
+            Ok(v) => v,
+            Err(_) => {
+                wedpr_println!("Parsing signature failed");
+                return false;
+            },
+        };
+        verify_signature(public_key.as_ref(), msg_hash.as_ref(), &signature_obj)
+    }
+
+    fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+        let mut rng = rand::thread_rng();
+        loop {
+            let mut private_key = [0u8; PRIVATE_KEY_SIZE_IN_BYTES];
+            rng.fill_bytes(&mut private_key);
+            // Retries the negligible case of a zero or overflowing scalar.
+            if let Ok(signing_key) = SigningKey::from_slice(&private_key) {
+                return (
+                    encode_public_key(signing_key.verifying_key()),
+                    private_key.to_vec(),
+                );
+            }
+        }
+    }
+}
+
+impl WedprSecp256r1 {
+    /// Derives the uncompressed public key of a private key.
+    pub fn derive_public_key(
+        self,
+        private_key: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        let signing_key = parse_private_key(private_key)?;
+        Ok(encode_public_key(signing_key.verifying_key()))
+    }
+
+    /// Converts a 64-byte signature to the ASN.1 DER form used by X.509,
+    /// TLS and WebAuthn.
+    pub fn signature_to_der(
+        self,
+        signature: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        match P256Signature::from_slice(signature) {
+            Ok(v) => Ok(v.to_der().as_bytes().to_vec()),
+            Err(_) => Err(WedprError::FormatError),
+        }
+    }
+
+    /// Converts an ASN.1 DER signature to the 64-byte form.
+    pub fn signature_from_der(
+        self,
+        der_signature: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        match P256Signature::from_der(der_signature) {
+            Ok(v) => Ok(v.to_bytes().to_vec()),
+            Err(_) => {
+                wedpr_println!("Parsing DER signature failed");
+                Err(WedprError::DecodeError)
+            },
+        }
+    }
+
+    /// Verifies an ASN.1 DER signature of a message hash.
+    pub fn verify_der(
+        self,
+        public_key: &[u8],
+        msg_hash: &[u8],
+        der_signature: &[u8],
+    ) -> bool {
+        if check_global_algorithm::<Self>().is_err() {
+            return false;
+        }
+        match P256Signature::from_der(der_signature) {
+            Ok(v) => verify_signature(public_key, msg_hash, &v),
+            Err(_) => false,
+        }
+    }
+
+    /// Encodes a private key as a PKCS#8 DER document.
+    pub fn private_key_to_pkcs8(
+        self,
+        private_key: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        let signing_key = parse_private_key(private_key)?;
+        match signing_key.to_pkcs8_der() {
+            Ok(v) => Ok(v.as_bytes().to_vec()),
+            Err(_) => Err(WedprError::FormatError),
+        }
+    }
+
+    /// Decodes a private key from a PKCS#8 DER document.
+    pub fn private_key_from_pkcs8(
+        self,
+        pkcs8_der: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        match SigningKey::from_pkcs8_der(pkcs8_der) {
+            Ok(v) => Ok(v.to_bytes().to_vec()),
+            Err(_) => {
+                wedpr_println!("Parsing PKCS#8 private key failed");
+                Err(WedprError::DecodeError)
+            },
+        }
+    }
+
+    /// Encodes a public key as an X.509 SubjectPublicKeyInfo DER document.
+    pub fn public_key_to_spki(
+        self,
+        public_key: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        let verifying_key = parse_public_key(public_key)?;
+        match verifying_key.to_public_key_der() {
+            Ok(v) => Ok(v.as_bytes().to_vec()),
+            Err(_) => Err(WedprError::FormatError),
+        }
+    }
+
+    /// Decodes an uncompressed public key from an X.509
+    /// SubjectPublicKeyInfo DER document.
+    pub fn public_key_from_spki(
+        self,
+        spki_der: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        match VerifyingKey::from_public_key_der(spki_der) {
+            Ok(v) => Ok(encode_public_key(&v)),
+            Err(_) => {
+                wedpr_println!("Parsing SPKI public key failed");
+                Err(WedprError::DecodeError)
+            },
+        }
+    }
+}
+
+/// Converts high-s signatures, which are rejected if the security policy
+/// requires low s values.
+impl LegacyCanonicalizer for WedprSecp256r1 {
+    fn canonicalize_signature(&self, signature: &[u8]) -> Option<Vec<u8>> {
+        match P256Signature::from_slice(signature) {
+            Ok(v) => v.normalize_s().map(|v| v.to_bytes().to_vec()),
+            Err(_) => None,
+        }
+    }
+}
+
+fn parse_private_key(private_key: &[u8]) -> Result<SigningKey, WedprError> {
+    match SigningKey::from_slice(private_key) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Parsing private key failed");
+            Err(WedprError::FormatError)
+        },
+    }
+}
+
+fn parse_public_key(public_key: &[u8]) -> Result<VerifyingKey, WedprError> {
+    match VerifyingKey::from_sec1_bytes(public_key) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Parsing public key failed");
+            Err(WedprError::FormatError)
+        },
+    }
+}
+
+fn encode_public_key(verifying_key: &VerifyingKey) -> Vec<u8> {
+    verifying_key.to_encoded_point(false).as_bytes().to_vec()
+}
+
+fn verify_signature(
+    public_key: &[u8],
+    msg_hash: &[u8],
+    signature: &P256Signature,
+) -> bool {
+    // A signature with a high s value can be normalized.
+    if global_policy().require_low_s && signature.normalize_s().is_some() {
+        wedpr_println!("Signature with a high s value is rejected by policy");
+        return false;
+    }
+    match parse_public_key(public_key) {
+        Ok(v) => v.verify_prehash(msg_hash, signature).is_ok(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_common_coder_hex::WedprHex;
+    use wedpr_l_crypto_hash_sha256::WedprSha256;
+    use wedpr_l_utils::{
+        constant::tests::BASE64_ENCODED_TEST_MESSAGE,
+        migration::{CompatibilityMode, DeprecationEvent, MigratingSignature},
+        policy::{with_scoped_policy, SecurityPolicy},
+        traits::{Coder, Hash},
+    };
+
+    #[test]
+    fn test_secp256r1() {
+        let secp256r1 = WedprSecp256r1::default();
+        let (public_key, private_key) = secp256r1.generate_keypair();
+        let msg_hash =
+            WedprSha256::default().hash(&BASE64_ENCODED_TEST_MESSAGE);
+        let signature = secp256r1.sign(&private_key, &msg_hash).unwrap();
+        assert!(secp256r1.verify(&public_key, &msg_hash, &signature));
+        assert!(!secp256r1.verify(
+            &public_key,
+            &msg_hash[1..].to_vec(),
+            &signature
+        ));
+
+        let der_signature = secp256r1.signature_to_der(&signature).unwrap();
+        assert!(secp256r1.verify_der(&public_key, &msg_hash, &der_signature));
+        assert_eq!(
+            secp256r1.signature_from_der(&der_signature).unwrap(),
+            signature
+        );
+        assert!(secp256r1.signature_from_der(&signature).is_err());
+
+        let pkcs8_der = secp256r1.private_key_to_pkcs8(&private_key).unwrap();
+        assert_eq!(
+            secp256r1.private_key_from_pkcs8(&pkcs8_der).unwrap(),
+            private_key
+        );
+        let spki_der = secp256r1.public_key_to_spki(&public_key).unwrap();
+        assert_eq!(
+            secp256r1.public_key_from_spki(&spki_der).unwrap(),
+            public_key
+        );
+
+        // Test vector of RFC 6979 A.2.5 with SHA-256 and message "sample".
+        let hex = WedprHex::default();
+        let private_key = hex
+            .decode(
+                &[
+                    "c9afa9d845ba75166b5c215767b1d693",
+                    "4e50c3db36e89b127b8a622b120f6721",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let expected_signature = hex
+            .decode(
+                &[
+                    "efd48b2aacb6a8fd1140dd9cd45e81d6",
+                    "9d2c877b56aaf991c34d0ea84eaf3716",
+                    "f7cb1c942d657c41d436c7a1b6e29f65",
+                    "f3e900dbb9aff4064dc4ab2f843acda8",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let msg_hash = WedprSha256::default().hash("sample");
+        let signature = secp256r1
+            .sign_deterministic(&private_key, &msg_hash)
+            .unwrap();
+        assert_eq!(signature, expected_signature);
+        let public_key = secp256r1.derive_public_key(&private_key).unwrap();
+        assert!(secp256r1.verify(&public_key, &msg_hash, &signature));
+        assert!(WedprSecp256r1::algorithm_info()
+            .check_public_key(&public_key)
+            .is_ok());
+
+        // The signature above has a high s value.
+        let low_s_policy = SecurityPolicy {
+            require_low_s: true,
+            ..SecurityPolicy::default()
+        };
+        with_scoped_policy(low_s_policy, || {
+            assert!(!secp256r1.verify(&public_key, &msg_hash, &signature));
+            let low_s_signature =
+                secp256r1.sign(&private_key, &msg_hash).unwrap();
+            assert_eq!(low_s_signature[..32], signature[..32]);
+            assert!(secp256r1.verify(&public_key, &msg_hash, &low_s_signature));
+            assert_eq!(
+                secp256r1.canonicalize_signature(&signature),
+                Some(low_s_signature)
+            );
+            let migrating = MigratingSignature::new(
+                secp256r1,
+                CompatibilityMode::Migration,
+                |_: &DeprecationEvent| {},
+            );
+            assert!(migrating.verify(&public_key, &msg_hash, &signature));
+        });
+    }
+}
@@ -0,0 +1,274 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! ASN.1 DER structures of GM/T 0009 and GM/T 0010 for SM2 signatures,
+//! ciphertexts and enveloped keys, with converters to the raw formats used by
+//! the rest of this crate.
+
+use crate::encryption::{
+    Sm2CiphertextOrder, SM2_C1_SIZE_IN_BYTES, SM2_C3_SIZE_IN_BYTES,
+};
+use wedpr_l_utils::{
+    der::{
+        decode_ecdsa_signature, decode_unsigned_integer,
+        encode_ecdsa_signature, encode_sequence, encode_tlv,
+        encode_unsigned_integer, DerReader, TAG_BIT_STRING, TAG_INTEGER,
+        TAG_NULL, TAG_OCTET_STRING, TAG_OID, TAG_SEQUENCE,
+    },
+    error::WedprError,
+};
+
+/// Size of a SM2 scalar or coordinate in bytes.
+const SM2_FIELD_SIZE_IN_BYTES: usize = 32;
+/// Size of a raw r || s signature in bytes.
+const SM2_RAW_SIGNATURE_SIZE_IN_BYTES: usize = 64;
+
+/// Encoded OID 1.2.156.10197.1.104.1 of SM4 in ECB mode, the symmetric
+/// algorithm of GM/T 0009 enveloped keys.
+pub const OID_SM4_ECB: &[u8] =
+    &[0x2a, 0x81, 0x1c, 0xcf, 0x55, 0x01, 0x68, 0x01];
+
+/// SM2EnvelopedKey of GM/T 0009, carrying a private key encrypted by a
+/// symmetric key, which is in turn encrypted by the SM2 public key of the
+/// receiver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sm2EnvelopedKey {
+    /// Encoded OID of the symmetric algorithm, such as OID_SM4_ECB.
+    pub symmetric_algorithm: Vec<u8>,
+    /// Raw SM2 ciphertext of the symmetric key in the C1C3C2 order.
+    pub encrypted_symmetric_key: Vec<u8>,
+    /// Uncompressed SM2 public key of the enveloped private key.
+    pub public_key: Vec<u8>,
+    /// Private key encrypted by the symmetric key.
+    pub encrypted_private_key: Vec<u8>,
+}
+
+impl Sm2EnvelopedKey {
+    /// Encodes this enveloped key to DER.
+    pub fn encode(&self) -> Result<Vec<u8>, WedprError> {
+        if self.public_key.len() != SM2_C1_SIZE_IN_BYTES
+            || self.public_key[0] != 0x04
+        {
+            wedpr_println!("SM2 enveloped key needs an uncompressed key");
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(encode_sequence(&[
+            encode_sequence(&[
+                encode_tlv(TAG_OID, &self.symmetric_algorithm),
+                encode_tlv(TAG_NULL, &[]),
+            ]),
+            encode_ciphertext(
+                &self.encrypted_symmetric_key,
+                Sm2CiphertextOrder::C1C3C2,
+            )?,
+            encode_bit_string(&self.public_key),
+            encode_bit_string(&self.encrypted_private_key),
+        ]))
+    }
+
+    /// Decodes an enveloped key from DER.
+    pub fn decode(input: &[u8]) -> Result<Self, WedprError> {
+        let mut fields = read_sequence(input)?;
+        let mut algorithm =
+            DerReader::new(fields.read_expected(TAG_SEQUENCE)?.content);
+        let symmetric_algorithm =
+            algorithm.read_expected(TAG_OID)?.content.to_vec();
+        let encrypted_symmetric_key = decode_ciphertext(
+            fields.read_expected(TAG_SEQUENCE)?.encoded,
+            Sm2CiphertextOrder::C1C3C2,
+        )?;
+        let public_key =
+            decode_bit_string(fields.read_expected(TAG_BIT_STRING)?.content)?;
+        let encrypted_private_key =
+            decode_bit_string(fields.read_expected(TAG_BIT_STRING)?.content)?;
+        if !fields.is_empty()
+            || public_key.len() != SM2_C1_SIZE_IN_BYTES
+            || public_key[0] != 0x04
+        {
+            return Err(WedprError::FormatError);
+        }
+        Ok(Sm2EnvelopedKey {
+            symmetric_algorithm,
+            encrypted_symmetric_key,
+            public_key,
+            encrypted_private_key,
+        })
+    }
+}
+
+/// Encodes a raw r || s signature to SM2Signature ::= SEQUENCE { r INTEGER,
+/// s INTEGER }.
+pub fn encode_signature(signature: &[u8]) -> Result<Vec<u8>, WedprError> {
+    if signature.len() != SM2_RAW_SIGNATURE_SIZE_IN_BYTES {
+        wedpr_println!("Invalid SM2 raw signature size");
+        return Err(WedprError::ArgumentError);
+    }
+    encode_ecdsa_signature(signature)
+}
+
+/// Decodes a SM2Signature to the raw r || s signature.
+pub fn decode_signature(input: &[u8]) -> Result<Vec<u8>, WedprError> {
+    decode_ecdsa_signature(input, SM2_FIELD_SIZE_IN_BYTES)
+}
+
+/// Encodes a raw ciphertext of the order to SM2Cipher ::= SEQUENCE {
+/// XCoordinate INTEGER, YCoordinate INTEGER, HASH OCTET STRING, CipherText
+/// OCTET STRING }.
+pub fn encode_ciphertext(
+    ciphertext: &[u8],
+    order: Sm2CiphertextOrder,
+) -> Result<Vec<u8>, WedprError> {
+    if ciphertext.len() <= SM2_C1_SIZE_IN_BYTES + SM2_C3_SIZE_IN_BYTES
+        || ciphertext[0] != 0x04
+    {
+        wedpr_println!("Invalid SM2 raw ciphertext");
+        return Err(WedprError::ArgumentError);
+    }
+    let (c1, rest) = ciphertext.split_at(SM2_C1_SIZE_IN_BYTES);
+    let (c2, c3) = match order {
+        Sm2CiphertextOrder::C1C3C2 => {
+            let (c3, c2) = rest.split_at(SM2_C3_SIZE_IN_BYTES);
+            (c2, c3)
+        },
+        Sm2CiphertextOrder::C1C2C3 => {
+            rest.split_at(rest.len() - SM2_C3_SIZE_IN_BYTES)
+        },
+    };
+    let (x, y) = c1[1..].split_at(SM2_FIELD_SIZE_IN_BYTES);
+    Ok(encode_sequence(&[
+        encode_unsigned_integer(x),
+        encode_unsigned_integer(y),
+        encode_tlv(TAG_OCTET_STRING, c3),
+        encode_tlv(TAG_OCTET_STRING, c2),
+    ]))
+}
+
+/// Decodes a SM2Cipher to the raw ciphertext of the order.
+pub fn decode_ciphertext(
+    input: &[u8],
+    order: Sm2CiphertextOrder,
+) -> Result<Vec<u8>, WedprError> {
+    let mut fields = read_sequence(input)?;
+    let mut ciphertext = vec![0x04];
+    ciphertext.extend_from_slice(&read_field_integer(&mut fields)?);
+    ciphertext.extend_from_slice(&read_field_integer(&mut fields)?);
+    let c3 = fields.read_expected(TAG_OCTET_STRING)?.content;
+    let c2 = fields.read_expected(TAG_OCTET_STRING)?.content;
+    if !fields.is_empty() || c3.len() != SM2_C3_SIZE_IN_BYTES || c2.is_empty() {
+        return Err(WedprError::FormatError);
+    }
+    match order {
+        Sm2CiphertextOrder::C1C3C2 => {
+            ciphertext.extend_from_slice(c3);
+            ciphertext.extend_from_slice(c2);
+        },
+        Sm2CiphertextOrder::C1C2C3 => {
+            ciphertext.extend_from_slice(c2);
+            ciphertext.extend_from_slice(c3);
+        },
+    }
+    Ok(ciphertext)
+}
+
+/// Reads the fields of the only sequence in the input.
+fn read_sequence(input: &[u8]) -> Result<DerReader<'_>, WedprError> {
+    let mut reader = DerReader::new(input);
+    let sequence = reader.read_expected(TAG_SEQUENCE)?;
+    if !reader.is_empty() {
+        return Err(WedprError::FormatError);
+    }
+    Ok(DerReader::new(sequence.content))
+}
+
+/// Reads a non-negative integer as a 32-byte big-endian value.
+fn read_field_integer(
+    fields: &mut DerReader<'_>,
+) -> Result<Vec<u8>, WedprError> {
+    decode_unsigned_integer(
+        fields.read_expected(TAG_INTEGER)?.content,
+        SM2_FIELD_SIZE_IN_BYTES,
+    )
+}
+
+/// Encodes bytes as a bit string without unused bits.
+fn encode_bit_string(value: &[u8]) -> Vec<u8> {
+    let mut content = vec![0u8];
+    content.extend_from_slice(value);
+    encode_tlv(TAG_BIT_STRING, &content)
+}
+
+/// Decodes the content of a bit string without unused bits.
+fn decode_bit_string(content: &[u8]) -> Result<Vec<u8>, WedprError> {
+    match content.split_first() {
+        Some((&0, value)) => Ok(value.to_vec()),
+        _ => Err(WedprError::FormatError),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encryption::WedprSm2Encryption, WedprSm2p256v1};
+    use wedpr_l_utils::traits::{Ecies, Signature};
+
+    #[test]
+    fn test_sm2_asn1() {
+        let sm2 = WedprSm2p256v1::default();
+        let (public_key, private_key) = sm2.generate_keypair();
+        let message = b"message of the SM2 ASN.1 structures".to_vec();
+
+        // A small r drops its leading zeros and a large s gains one.
+        let mut raw_signature = vec![0u8; 31];
+        raw_signature.push(1);
+        raw_signature.extend_from_slice(&[0xff; 32]);
+        let mut expected = vec![0x30, 0x26, 0x02, 0x01, 0x01, 0x02, 0x21, 0x00];
+        expected.extend_from_slice(&[0xff; 32]);
+        assert_eq!(encode_signature(&raw_signature).unwrap(), expected);
+        assert_eq!(decode_signature(&expected).unwrap(), raw_signature);
+        assert!(decode_signature(&expected[..expected.len() - 1]).is_err());
+
+        let signature = sm2.sign(&private_key, &message).unwrap();
+        let encoded = encode_signature(&signature).unwrap();
+        let decoded = decode_signature(&encoded).unwrap();
+        assert!(sm2.verify(&public_key, &message, &decoded));
+
+        let encryption = WedprSm2Encryption::default();
+        let ciphertext = encryption.encrypt(&public_key, &message).unwrap();
+        let encoded =
+            encode_ciphertext(&ciphertext, Sm2CiphertextOrder::C1C3C2).unwrap();
+        assert_eq!(
+            decode_ciphertext(&encoded, Sm2CiphertextOrder::C1C3C2).unwrap(),
+            ciphertext
+        );
+        // Converting through DER reorders the ciphertext.
+        let legacy =
+            decode_ciphertext(&encoded, Sm2CiphertextOrder::C1C2C3).unwrap();
+        assert_eq!(
+            encryption
+                .decrypt_with_order(
+                    &private_key,
+                    &legacy,
+                    Sm2CiphertextOrder::C1C2C3
+                )
+                .unwrap(),
+            message
+        );
+        assert!(encode_ciphertext(
+            &ciphertext[..97],
+            Sm2CiphertextOrder::C1C3C2
+        )
+        .is_err());
+
+        let (_, symmetric_key) = sm2.generate_keypair();
+        let enveloped_key = Sm2EnvelopedKey {
+            symmetric_algorithm: OID_SM4_ECB.to_vec(),
+            encrypted_symmetric_key: encryption
+                .encrypt(&public_key, &symmetric_key[..16].to_vec())
+                .unwrap(),
+            public_key: public_key.clone(),
+            encrypted_private_key: vec![7u8; 32],
+        };
+        let encoded = enveloped_key.encode().unwrap();
+        assert_eq!(Sm2EnvelopedKey::decode(&encoded).unwrap(), enveloped_key);
+        assert!(Sm2EnvelopedKey::decode(&encoded[1..]).is_err());
+    }
+}
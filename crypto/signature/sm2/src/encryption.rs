@@ -0,0 +1,304 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! SM2 public key encryption functions of GM/T 0003.4.
+//!
+//! A ciphertext consists of C1 = kG as an uncompressed point, C2 = M xor t
+//! where t = KDF(x2 || y2, |M|) for (x2, y2) = kP, and the hash value
+//! C3 = SM3(x2 || M || y2). GM/T 0003.4-2012 orders them as C1C3C2, while
+//! earlier drafts and some gm-ssl based services use C1C2C3.
+
+use crate::{parse_secret_key, SM2_CTX, SM2_ECC_CTX};
+use wedpr_l_crypto_hash_sm3::WedprSm3;
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    constant_time::ct_is_zero,
+    error::WedprError,
+    traits::{AlgorithmInfo, Ecies, Hash},
+};
+
+/// Size of the uncompressed point C1 in bytes.
+pub(crate) const SM2_C1_SIZE_IN_BYTES: usize = 65;
+/// Size of the SM3 hash value C3 in bytes.
+pub(crate) const SM2_C3_SIZE_IN_BYTES: usize = 32;
+
+/// Ordering of the components of a SM2 ciphertext.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sm2CiphertextOrder {
+    /// C1 || C3 || C2 of GM/T 0003.4-2012.
+    #[default]
+    C1C3C2,
+    /// C1 || C2 || C3 of the legacy format.
+    C1C2C3,
+}
+
+/// Implements SM2 public key encryption as an Ecies instance, whose
+/// ciphertext order is chosen at creation.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct WedprSm2Encryption {
+    order: Sm2CiphertextOrder,
+}
+
+impl AlgorithmInfo for WedprSm2Encryption {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "sm2-encryption",
+            oid: Some("1.2.156.10197.1.301.3"),
+            security_bits: 128,
+            private_key_size: Some(32),
+            public_key_sizes: &[65, 33],
+            signature_size: None,
+            output_size: None,
+        }
+    }
+}
+
+impl Ecies for WedprSm2Encryption {
+    fn encrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        message: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        self.encrypt_with_order(
+            public_key.as_ref(),
+            message.as_ref(),
+            self.order,
+        )
+    }
+
+    fn decrypt<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        ciphertext: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        self.decrypt_with_order(
+            private_key.as_ref(),
+            ciphertext.as_ref(),
+            self.order,
+        )
+    }
+}
+
+impl WedprSm2Encryption {
+    /// Creates an instance using the ciphertext order.
+    pub fn new(order: Sm2CiphertextOrder) -> Self {
+        WedprSm2Encryption { order }
+    }
+
+    /// Gets the ciphertext order of this instance.
+    pub fn order(&self) -> Sm2CiphertextOrder {
+        self.order
+    }
+
+    /// Encrypts a message with a public key into a ciphertext of the order.
+    pub fn encrypt_with_order(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        order: Sm2CiphertextOrder,
+    ) -> Result<Vec<u8>, WedprError> {
+        if message.is_empty() {
+            wedpr_println!("SM2 can not encrypt an empty message");
+            return Err(WedprError::ArgumentError);
+        }
+        let public_key_point = match SM2_CTX.load_pubkey(public_key) {
+            Ok(v) => v,
+            Err(_) => {
+                wedpr_println!("Parsing SM2 public key failed");
+                return Err(WedprError::FormatError);
+            },
+        };
+        loop {
+            // A fresh key pair gives the random k and C1 = kG.
+            let (c1_point, k) = SM2_CTX.new_keypair();
+            let shared_point = SM2_CTX.serialize_pubkey(
+                &SM2_ECC_CTX.mul(&k, &public_key_point),
+                false,
+            );
+            let (x2, y2) = shared_point[1..].split_at(32);
+            let key_stream = kdf(&shared_point[1..], message.len());
+            // Retries with another k if the key stream is all zero.
+            if bool::from(ct_is_zero(&key_stream)) {
+                continue;
+            }
+            let c2: Vec<u8> = message
+                .iter()
+                .zip(key_stream.iter())
+                .map(|(m, t)| m ^ t)
+                .collect();
+            let c3 = compute_c3(x2, message, y2);
+            let mut ciphertext = SM2_CTX.serialize_pubkey(&c1_point, false);
+            match order {
+                Sm2CiphertextOrder::C1C3C2 => {
+                    ciphertext.extend_from_slice(&c3);
+                    ciphertext.extend_from_slice(&c2);
+                },
+                Sm2CiphertextOrder::C1C2C3 => {
+                    ciphertext.extend_from_slice(&c2);
+                    ciphertext.extend_from_slice(&c3);
+                },
+            }
+            return Ok(ciphertext);
+        }
+    }
+
+    /// Decrypts a ciphertext of the order with a private key, which fails if
+    /// C1 is not on the curve or C3 does not match.
+    pub fn decrypt_with_order(
+        &self,
+        private_key: &[u8],
+        ciphertext: &[u8],
+        order: Sm2CiphertextOrder,
+    ) -> Result<Vec<u8>, WedprError> {
+        if ciphertext.len() <= SM2_C1_SIZE_IN_BYTES + SM2_C3_SIZE_IN_BYTES {
+            wedpr_println!("SM2 ciphertext is too short");
+            return Err(WedprError::FormatError);
+        }
+        let secret_key = parse_secret_key(private_key)?;
+        let (c1, rest) = ciphertext.split_at(SM2_C1_SIZE_IN_BYTES);
+        let (c2, c3) = match order {
+            Sm2CiphertextOrder::C1C3C2 => {
+                let (c3, c2) = rest.split_at(SM2_C3_SIZE_IN_BYTES);
+                (c2, c3)
+            },
+            Sm2CiphertextOrder::C1C2C3 => {
+                rest.split_at(rest.len() - SM2_C3_SIZE_IN_BYTES)
+            },
+        };
+        // Loading C1 checks that it is on the curve.
+        let c1_point = match SM2_CTX.load_pubkey(c1) {
+            Ok(v) => v,
+            Err(_) => {
+                wedpr_println!("SM2 ciphertext has an invalid C1");
+                return Err(WedprError::DecodeError);
+            },
+        };
+        let shared_point = SM2_CTX
+            .serialize_pubkey(&SM2_ECC_CTX.mul(&secret_key, &c1_point), false);
+        let (x2, y2) = shared_point[1..].split_at(32);
+        let key_stream = kdf(&shared_point[1..], c2.len());
+        if bool::from(ct_is_zero(&key_stream)) {
+            return Err(WedprError::DecodeError);
+        }
+        let message: Vec<u8> = c2
+            .iter()
+            .zip(key_stream.iter())
+            .map(|(c, t)| c ^ t)
+            .collect();
+        let difference: Vec<u8> = compute_c3(x2, &message, y2)
+            .iter()
+            .zip(c3.iter())
+            .map(|(a, b)| a ^ b)
+            .collect();
+        if !bool::from(ct_is_zero(&difference)) {
+            wedpr_println!("SM2 ciphertext hash check failed");
+            return Err(WedprError::DecodeError);
+        }
+        Ok(message)
+    }
+}
+
+/// Derives a key stream of the size by the KDF of GM/T 0003.4, which
+/// concatenates SM3(z || ct) for a 32-bit big-endian counter ct from 1.
+fn kdf(z: &[u8], size: usize) -> Vec<u8> {
+    let sm3 = WedprSm3::default();
+    let mut output = Vec::with_capacity(size + SM2_C3_SIZE_IN_BYTES);
+    let mut counter = 1u32;
+    while output.len() < size {
+        let mut hash_input = z.to_vec();
+        hash_input.extend_from_slice(&counter.to_be_bytes());
+        output.extend_from_slice(&sm3.hash(&hash_input));
+        counter += 1;
+    }
+    output.truncate(size);
+    output
+}
+
+/// Computes C3 = SM3(x2 || M || y2).
+fn compute_c3(x2: &[u8], message: &[u8], y2: &[u8]) -> Vec<u8> {
+    let mut hash_input = x2.to_vec();
+    hash_input.extend_from_slice(message);
+    hash_input.extend_from_slice(y2);
+    WedprSm3::default().hash(&hash_input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WedprSm2p256v1;
+    use wedpr_l_common_coder_hex::WedprHex;
+    use wedpr_l_utils::traits::{Coder, Signature};
+
+    #[test]
+    fn test_sm2_encryption() {
+        let (public_key, private_key) =
+            WedprSm2p256v1::default().generate_keypair();
+        let message = b"message of the SM2 encryption".to_vec();
+        let modern = WedprSm2Encryption::default();
+        let legacy = WedprSm2Encryption::new(Sm2CiphertextOrder::C1C2C3);
+        assert_eq!(modern.order(), Sm2CiphertextOrder::C1C3C2);
+
+        for encryption in &[modern, legacy] {
+            let ciphertext = encryption.encrypt(&public_key, &message).unwrap();
+            assert_eq!(ciphertext.len(), 65 + 32 + message.len());
+            assert_eq!(
+                encryption.decrypt(&private_key, &ciphertext).unwrap(),
+                message
+            );
+            let mut tampered = ciphertext.clone();
+            tampered[70] ^= 1;
+            assert!(encryption.decrypt(&private_key, &tampered).is_err());
+            let mut tampered = ciphertext.clone();
+            *tampered.last_mut().unwrap() ^= 1;
+            assert!(encryption.decrypt(&private_key, &tampered).is_err());
+        }
+        // Decrypting with the other order fails the hash check.
+        let ciphertext = modern.encrypt(&public_key, &message).unwrap();
+        assert!(legacy.decrypt(&private_key, &ciphertext).is_err());
+        assert!(modern.encrypt(&public_key, &vec![]).is_err());
+        assert!(modern
+            .decrypt(&private_key, &ciphertext[..97].to_vec())
+            .is_err());
+
+        // Fixed vector of "encryption standard" in the C1C3C2 order.
+        let hex = WedprHex::default();
+        let private_key = hex
+            .decode(
+                &[
+                    "0206ed530443c9a84bd56f0bad478a16",
+                    "031a0a83bb096aa833e37d616290a33a",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let ciphertext = hex
+            .decode(
+                &[
+                    "04942618fbc9bc20a4d50c677e64a3194f5349e51a824fda5e070746",
+                    "c0abf302687ef6bc0ebcb7d8ad96aac37eac1f46ba61ba51aa58dce0",
+                    "2210a0beb8433bf695719a9c75c1b208d7f4fff2980d4452d0333667",
+                    "6b81f10bfe4a3e9b6007de13eba33fc78d5ea76cc5e11e8e943f9897",
+                    "a3a7bb0c",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        assert_eq!(
+            modern.decrypt(&private_key, &ciphertext).unwrap(),
+            b"encryption standard".to_vec()
+        );
+        // Reordering into C1C2C3 keeps the plaintext.
+        let mut reordered = ciphertext[..65].to_vec();
+        reordered.extend_from_slice(&ciphertext[97..]);
+        reordered.extend_from_slice(&ciphertext[65..97]);
+        assert_eq!(
+            legacy
+                .decrypt_with_order(
+                    &private_key,
+                    &reordered,
+                    Sm2CiphertextOrder::C1C2C3
+                )
+                .unwrap(),
+            b"encryption standard".to_vec()
+        );
+    }
+}
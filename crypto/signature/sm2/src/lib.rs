@@ -1,6 +1,6 @@
 // Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
 
-//! SM2 signature functions.
+//! SM2 signature and public key encryption functions.
 
 #[allow(unused_imports)]
 #[macro_use]
@@ -8,34 +8,109 @@ extern crate wedpr_l_macros;
 #[macro_use]
 extern crate lazy_static;
 
-use wedpr_l_utils::{error::WedprError, traits::Signature};
+pub mod asn1;
+pub mod encryption;
 
-use wedpr_l_libsm::sm2::signature::{SigCtx, Signature as sm2Signature};
+use num_bigint::BigUint;
+use wedpr_l_crypto_hash_sm3::WedprSm3;
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    constant_time::is_valid_secret_scalar,
+    error::WedprError,
+    policy::check_global_algorithm,
+    scalar::Field256,
+    traits::{AlgorithmInfo, Hash, Signature},
+};
+
+use wedpr_l_libsm::sm2::{
+    ecc::{EccCtx, Point},
+    signature::{SigCtx, Signature as sm2Signature},
+};
 
 lazy_static! {
     // Shared sm2 instance initialized for all functions.
     static ref SM2_CTX: SigCtx = SigCtx::new();
+    // Shared curve instance for recovering public keys.
+    static ref SM2_ECC_CTX: EccCtx = EccCtx::new();
+    static ref SM2_CURVE_ORDER: BigUint =
+        BigUint::from_bytes_be(&SM2_CURVE_N);
+    // Constant-time arithmetic modulo the curve order.
+    static ref SM2_SCALAR_FIELD: Field256 =
+        Field256::new(&SM2_CURVE_N).unwrap();
 }
 
+/// Default user ID of GM/T 0003 for computing the Z value.
+pub const DEFAULT_SM2_USER_ID: &[u8] = b"1234567812345678";
+/// Maximum size of a user ID, whose size in bits is encoded in 2 bytes.
+const MAX_SM2_USER_ID_SIZE_IN_BYTES: usize = 8191;
+
+// Parameters a, b, Gx and Gy of curve sm2p256v1.
+const SM2_CURVE_A: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfc,
+];
+const SM2_CURVE_B: [u8; 32] = [
+    0x28, 0xe9, 0xfa, 0x9e, 0x9d, 0x9f, 0x5e, 0x34, 0x4d, 0x5a, 0x9e, 0x4b,
+    0xcf, 0x65, 0x09, 0xa7, 0xf3, 0x97, 0x89, 0xf5, 0x15, 0xab, 0x8f, 0x92,
+    0xdd, 0xbc, 0xbd, 0x41, 0x4d, 0x94, 0x0e, 0x93,
+];
+const SM2_CURVE_N: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0x72, 0x03, 0xdf, 0x6b, 0x21, 0xc6, 0x05, 0x2b,
+    0x53, 0xbb, 0xf4, 0x09, 0x39, 0xd5, 0x41, 0x23,
+];
+/// Exclusive upper bound n - 1 of private keys, since GB/T 32918 requires
+/// private keys in [1, n - 2] for 1 + d to be invertible.
+const SM2_PRIVATE_KEY_BOUND: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0x72, 0x03, 0xdf, 0x6b, 0x21, 0xc6, 0x05, 0x2b,
+    0x53, 0xbb, 0xf4, 0x09, 0x39, 0xd5, 0x41, 0x22,
+];
+const SM2_BASEPOINT_X: [u8; 32] = [
+    0x32, 0xc4, 0xae, 0x2c, 0x1f, 0x19, 0x81, 0x19, 0x5f, 0x99, 0x04, 0x46,
+    0x6a, 0x39, 0xc9, 0x94, 0x8f, 0xe3, 0x0b, 0xbf, 0xf2, 0x66, 0x0b, 0xe1,
+    0x71, 0x5a, 0x45, 0x89, 0x33, 0x4c, 0x74, 0xc7,
+];
+const SM2_BASEPOINT_Y: [u8; 32] = [
+    0xbc, 0x37, 0x36, 0xa2, 0xf4, 0xf6, 0x77, 0x9c, 0x59, 0xbd, 0xce, 0xe3,
+    0x6b, 0x69, 0x21, 0x53, 0xd0, 0xa9, 0x87, 0x7c, 0xc6, 0x2a, 0x47, 0x40,
+    0x02, 0xdf, 0x32, 0xe5, 0x21, 0x39, 0xf0, 0xa0,
+];
+
+/// Size of a recoverable signature r || s || v in bytes.
+const SM2_RECOVERABLE_SIGNATURE_SIZE_IN_BYTES: usize = 65;
+const SM2_SCALAR_SIZE_IN_BYTES: usize = 32;
+
 /// Implements FISCO-BCOS-compatible SM2 as a Signature instance.
 #[derive(Default, Debug, Clone)]
 pub struct WedprSm2p256v1 {}
 
+impl AlgorithmInfo for WedprSm2p256v1 {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "sm2p256v1",
+            oid: Some("1.2.156.10197.1.301"),
+            security_bits: 128,
+            private_key_size: Some(32),
+            public_key_sizes: &[65, 33],
+            signature_size: Some(64),
+            output_size: None,
+        }
+    }
+}
+
 impl Signature for WedprSm2p256v1 {
     fn sign<T: ?Sized + AsRef<[u8]>>(
         &self,
         private_key: &T,
         msg_hash: &T,
     ) -> Result<Vec<u8>, WedprError> {
-        let secret_key = match SM2_CTX.load_seckey(&private_key.as_ref()) {
-            Ok(v) => v,
-            Err(_) => {
-                return Err(WedprError::FormatError);
-            },
-        };
+        check_global_algorithm::<Self>()?;
+        let secret_key = parse_secret_key(private_key.as_ref())?;
         let derived_public_key = SM2_CTX.pk_from_sk(&secret_key);
         let signature =
-            SM2_CTX.sign(&msg_hash.as_ref(), &secret_key, &derived_public_key);
+            SM2_CTX.sign(msg_hash.as_ref(), &secret_key, &derived_public_key);
         Ok(signature.bytes_encode().to_vec())
     }
 
@@ -45,7 +120,10 @@ impl Signature for WedprSm2p256v1 {
         msg_hash: &T,
         signature: &T,
     ) -> bool {
-        let public_key_point = match SM2_CTX.load_pubkey(&public_key.as_ref()) {
+        if check_global_algorithm::<Self>().is_err() {
+            return false;
+        }
+        let public_key_point = match SM2_CTX.load_pubkey(public_key.as_ref()) {
             Ok(v) => v,
             Err(_) => {
                 return false;
@@ -58,7 +136,7 @@ impl Signature for WedprSm2p256v1 {
                 return false;
             },
         };
-        SM2_CTX.verify(&msg_hash.as_ref(), &public_key_point, &parsed_sig)
+        SM2_CTX.verify(msg_hash.as_ref(), &public_key_point, &parsed_sig)
     }
 
     fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
@@ -78,24 +156,264 @@ impl WedprSm2p256v1 {
         public_key: &T,
         msg_hash: &T,
     ) -> Result<Vec<u8>, WedprError> {
-        let secret_key = match SM2_CTX.load_seckey(&private_key.as_ref()) {
+        check_global_algorithm::<Self>()?;
+        let secret_key = parse_secret_key(private_key.as_ref())?;
+        let public_key_point = match SM2_CTX.load_pubkey(public_key.as_ref()) {
             Ok(v) => v,
             Err(_) => {
                 return Err(WedprError::FormatError);
             },
         };
-        let public_key_point = match SM2_CTX.load_pubkey(&public_key.as_ref()) {
+        let signature =
+            SM2_CTX.sign(msg_hash.as_ref(), &secret_key, &public_key_point);
+        Ok(signature.bytes_encode().to_vec())
+    }
+
+    /// Computes the Z value of GM/T 0003 binding a user ID to a public key,
+    /// Z = SM3(ENTL || ID || a || b || Gx || Gy || Ax || Ay), where ENTL is
+    /// the ID size in bits.
+    pub fn compute_z(
+        &self,
+        public_key: &[u8],
+        user_id: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        if user_id.len() > MAX_SM2_USER_ID_SIZE_IN_BYTES {
+            wedpr_println!("SM2 user ID is too long");
+            return Err(WedprError::ArgumentError);
+        }
+        let public_key_point = match SM2_CTX.load_pubkey(public_key) {
             Ok(v) => v,
             Err(_) => {
                 return Err(WedprError::FormatError);
             },
         };
-        let signature =
-            SM2_CTX.sign(&msg_hash.as_ref(), &secret_key, &public_key_point);
-        Ok(signature.bytes_encode().to_vec())
+        // Uncompressed public key bytes are 0x04 || Ax || Ay.
+        let uncompressed_public_key =
+            SM2_CTX.serialize_pubkey(&public_key_point, false);
+        let mut z_input = ((user_id.len() * 8) as u16).to_be_bytes().to_vec();
+        z_input.extend_from_slice(user_id);
+        z_input.extend_from_slice(&SM2_CURVE_A);
+        z_input.extend_from_slice(&SM2_CURVE_B);
+        z_input.extend_from_slice(&SM2_BASEPOINT_X);
+        z_input.extend_from_slice(&SM2_BASEPOINT_Y);
+        z_input.extend_from_slice(&uncompressed_public_key[1..]);
+        Ok(WedprSm3::default().hash(&z_input))
+    }
+
+    /// Computes the message hash e = SM3(Z || M) of GM/T 0003 for a message
+    /// signed by the owner of a public key and a user ID.
+    pub fn hash_message_with_id(
+        &self,
+        public_key: &[u8],
+        user_id: &[u8],
+        message: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        let mut hash_input = self.compute_z(public_key, user_id)?;
+        hash_input.extend_from_slice(message);
+        Ok(WedprSm3::default().hash(&hash_input))
+    }
+
+    /// Signs a raw message with a user ID as specified by GM/T 0003, where
+    /// public_key should be the public key of private_key.
+    pub fn sign_with_id(
+        &self,
+        private_key: &[u8],
+        public_key: &[u8],
+        user_id: &[u8],
+        message: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        check_global_algorithm::<Self>()?;
+        let secret_key = parse_secret_key(private_key)?;
+        let public_key_point = match SM2_CTX.load_pubkey(public_key) {
+            Ok(v) => v,
+            Err(_) => return Err(WedprError::FormatError),
+        };
+        // A signature bound to another public key never verifies.
+        if SM2_CTX.serialize_pubkey(&SM2_CTX.pk_from_sk(&secret_key), false)
+            != SM2_CTX.serialize_pubkey(&public_key_point, false)
+        {
+            wedpr_println!("Private key does not match the public key");
+            return Err(WedprError::ArgumentError);
+        }
+        let msg_hash =
+            self.hash_message_with_id(public_key, user_id, message)?;
+        // The hash e already binds the Z value of the user ID.
+        Ok(SM2_CTX
+            .sign_raw(&msg_hash, &secret_key)
+            .bytes_encode()
+            .to_vec())
+    }
+
+    /// Verifies a raw message signed with a user ID as specified by GM/T
+    /// 0003.
+    pub fn verify_with_id(
+        &self,
+        public_key: &[u8],
+        user_id: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> bool {
+        if check_global_algorithm::<Self>().is_err() {
+            return false;
+        }
+        let public_key_point = match SM2_CTX.load_pubkey(public_key) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        let parsed_sig = match sm2Signature::bytes_decode(signature) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        match self.hash_message_with_id(public_key, user_id, message) {
+            Ok(msg_hash) => {
+                SM2_CTX.verify_raw(&msg_hash, &public_key_point, &parsed_sig)
+            },
+            Err(_) => false,
+        }
+    }
+
+    /// Signs a message hash into a recoverable signature r || s || v, where
+    /// the recovery id v is the parity of y1 of the point (x1, y1) = kG, plus
+    /// 2 if x1 is not less than the curve order. Unlike sign, the 32-byte
+    /// message hash is used as e directly, since a Z value bound to the
+    /// public key cannot be computed before recovering the public key.
+    pub fn sign_recoverable<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        check_global_algorithm::<Self>()?;
+        let secret_key = parse_secret_key(private_key.as_ref())?;
+        if msg_hash.as_ref().len() != SM2_SCALAR_SIZE_IN_BYTES {
+            wedpr_println!("SM2 message hash must be 32 bytes");
+            return Err(WedprError::ArgumentError);
+        }
+        let public_key_point = SM2_CTX.pk_from_sk(&secret_key);
+        let public_key = SM2_CTX.serialize_pubkey(&public_key_point, false);
+        let signature = SM2_CTX
+            .sign_raw(msg_hash.as_ref(), &secret_key)
+            .bytes_encode()
+            .to_vec();
+        // The point kG is not exposed by signing, thus the recovery id is
+        // found by trying every candidate.
+        for recovery_id in 0..4u8 {
+            let mut recoverable_signature = signature.clone();
+            recoverable_signature.push(recovery_id);
+            if let Ok(v) = self.recover_public_key(
+                msg_hash.as_ref(),
+                &recoverable_signature[..],
+            ) {
+                if v == public_key {
+                    return Ok(recoverable_signature);
+                }
+            }
+        }
+        wedpr_println!("Finding SM2 recovery id failed");
+        Err(WedprError::VerificationError)
+    }
+
+    /// Recovers the uncompressed public key from a recoverable signature,
+    /// where P = (r + s)^-1 * (R - sG) for R = (x1, y1) and x1 = r - e.
+    pub fn recover_public_key<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        msg_hash: &T,
+        signature: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        check_global_algorithm::<Self>()?;
+        let signature = signature.as_ref();
+        if signature.len() != SM2_RECOVERABLE_SIGNATURE_SIZE_IN_BYTES
+            || signature[64] > 3
+        {
+            wedpr_println!("Invalid SM2 recoverable signature");
+            return Err(WedprError::FormatError);
+        }
+        let field = &*SM2_SCALAR_FIELD;
+        let r = field.decode(&signature[..32])?;
+        let s = field.decode(&signature[32..64])?;
+        if bool::from(r.is_zero() | s.is_zero()) {
+            return Err(WedprError::FormatError);
+        }
+        let recovery_id = signature[64];
+        let e = field.decode_reduced(msg_hash.as_ref())?;
+        let mut x1 = BigUint::from_bytes_be(&r.sub(&e).to_bytes());
+        if recovery_id & 2 != 0 {
+            x1 += &*SM2_CURVE_ORDER;
+        }
+        // Loading the compressed point fails if x1 is not on the curve.
+        let mut compressed_r_point = vec![0x02 | (recovery_id & 1)];
+        compressed_r_point.extend_from_slice(&to_scalar_bytes(&x1)?);
+        let r_point = match SM2_CTX.load_pubkey(&compressed_r_point) {
+            Ok(v) => v,
+            Err(_) => return Err(WedprError::VerificationError),
+        };
+        let t = r.add(&s);
+        let t_inv = match t.inverse() {
+            Ok(v) => v,
+            Err(_) => return Err(WedprError::VerificationError),
+        };
+        let g_scalar = t_inv.mul(&s).neg();
+        let public_key_point: Point = SM2_ECC_CTX.add(
+            &SM2_ECC_CTX
+                .mul(&BigUint::from_bytes_be(&t_inv.to_bytes()), &r_point),
+            &SM2_ECC_CTX.g_mul(&BigUint::from_bytes_be(&g_scalar.to_bytes())),
+        );
+        // Verification also rejects the point at infinity.
+        let parsed_sig = match sm2Signature::bytes_decode(&signature[..64]) {
+            Ok(v) => v,
+            Err(_) => return Err(WedprError::FormatError),
+        };
+        if !SM2_CTX.verify_raw(
+            msg_hash.as_ref(),
+            &public_key_point,
+            &parsed_sig,
+        ) {
+            return Err(WedprError::VerificationError);
+        }
+        Ok(SM2_CTX.serialize_pubkey(&public_key_point, false))
+    }
+
+    /// Verifies a recoverable signature by matching the recovered public key,
+    /// which accepts both compressed and uncompressed public keys.
+    pub fn verify_recoverable<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        msg_hash: &T,
+        signature: &T,
+    ) -> bool {
+        let public_key_point = match SM2_CTX.load_pubkey(public_key.as_ref()) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        match self.recover_public_key(msg_hash.as_ref(), signature.as_ref()) {
+            Ok(v) => v == SM2_CTX.serialize_pubkey(&public_key_point, false),
+            Err(_) => false,
+        }
     }
 }
 
+/// Parses a 32-byte private key in [1, n - 2], where the key content is
+/// validated in constant time.
+pub(crate) fn parse_secret_key(
+    private_key: &[u8],
+) -> Result<BigUint, WedprError> {
+    if !is_valid_secret_scalar(private_key, &SM2_PRIVATE_KEY_BOUND) {
+        wedpr_println!("Parsing private key failed");
+        return Err(WedprError::FormatError);
+    }
+    Ok(BigUint::from_bytes_be(private_key))
+}
+
+/// Encodes a scalar as 32 big-endian bytes.
+fn to_scalar_bytes(value: &BigUint) -> Result<Vec<u8>, WedprError> {
+    let bytes = value.to_bytes_be();
+    if bytes.len() > SM2_SCALAR_SIZE_IN_BYTES {
+        return Err(WedprError::FormatError);
+    }
+    let mut output = vec![0u8; SM2_SCALAR_SIZE_IN_BYTES - bytes.len()];
+    output.extend_from_slice(&bytes);
+    Ok(output)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,17 +431,197 @@ mod tests {
 
         let signature_normal =
             sm2_sign.sign(&private_key, &msg_hash.to_vec()).unwrap();
-        assert_eq!(
-            true,
-            sm2_sign.verify(&public_key, &msg_hash.to_vec(), &signature_normal)
-        );
+        assert!(sm2_sign.verify(
+            &public_key,
+            &msg_hash.to_vec(),
+            &signature_normal
+        ));
 
         let signature_fast = sm2_sign
             .sign_fast(&private_key, &public_key, &msg_hash.to_vec())
             .unwrap();
+        assert!(sm2_sign.verify(
+            &public_key,
+            &msg_hash.to_vec(),
+            &signature_fast
+        ));
+    }
+
+    #[test]
+    fn test_sm2_sign_with_id() {
+        let sm2_sign = WedprSm2p256v1::default();
+        let (public_key, private_key) = sm2_sign.generate_keypair();
+        let message = b"raw message of any length";
+
+        let z = sm2_sign
+            .compute_z(&public_key, DEFAULT_SM2_USER_ID)
+            .unwrap();
+        assert_eq!(z.len(), 32);
+        assert_ne!(z, sm2_sign.compute_z(&public_key, b"alice").unwrap());
+        assert!(sm2_sign.compute_z(&public_key[2..], b"alice").is_err());
+        assert!(sm2_sign.compute_z(&public_key, &[0u8; 8192]).is_err());
+
+        let signature = sm2_sign
+            .sign_with_id(
+                &private_key,
+                &public_key,
+                DEFAULT_SM2_USER_ID,
+                message,
+            )
+            .unwrap();
+        assert!(sm2_sign.verify_with_id(
+            &public_key,
+            DEFAULT_SM2_USER_ID,
+            message,
+            &signature
+        ));
+        assert!(!sm2_sign.verify_with_id(
+            &public_key,
+            b"alice",
+            message,
+            &signature
+        ));
+        assert!(!sm2_sign.verify_with_id(
+            &public_key,
+            DEFAULT_SM2_USER_ID,
+            b"another message",
+            &signature
+        ));
+        let (other_public_key, _) = sm2_sign.generate_keypair();
         assert_eq!(
-            true,
-            sm2_sign.verify(&public_key, &msg_hash.to_vec(), &signature_fast)
+            sm2_sign.sign_with_id(
+                &private_key,
+                &other_public_key,
+                DEFAULT_SM2_USER_ID,
+                message,
+            ),
+            Err(WedprError::ArgumentError)
+        );
+    }
+
+    #[test]
+    fn test_sm2_recoverable_signature() {
+        let sm2_sign = WedprSm2p256v1::default();
+        let (public_key, private_key) = sm2_sign.generate_keypair();
+        let msg_hash = WedprSm3::default().hash(&BASE64_ENCODED_TEST_MESSAGE);
+
+        let signature =
+            sm2_sign.sign_recoverable(&private_key, &msg_hash).unwrap();
+        assert_eq!(signature.len(), 65);
+        assert_eq!(
+            sm2_sign.recover_public_key(&msg_hash, &signature).unwrap(),
+            public_key
+        );
+        assert!(sm2_sign.verify_recoverable(
+            &public_key,
+            &msg_hash,
+            &signature
+        ));
+        // The r || s part is an SM2 signature of e = msg_hash.
+        let parsed_signature =
+            sm2Signature::bytes_decode(&signature[..64]).unwrap();
+        assert!(SM2_CTX.verify_raw(
+            &msg_hash,
+            &SM2_CTX.load_pubkey(&public_key).unwrap(),
+            &parsed_signature
+        ));
+        assert!(sm2_sign
+            .sign_recoverable(&private_key[..], &msg_hash[..31])
+            .is_err());
+
+        let another_hash = WedprSm3::default().hash("another message");
+        assert!(!sm2_sign.verify_recoverable(
+            &public_key,
+            &another_hash,
+            &signature
+        ));
+        let mut flipped_signature = signature.clone();
+        flipped_signature[64] ^= 1;
+        assert!(!sm2_sign.verify_recoverable(
+            &public_key,
+            &msg_hash,
+            &flipped_signature
+        ));
+        assert!(sm2_sign
+            .recover_public_key(&msg_hash[..], &signature[..64])
+            .is_err());
+    }
+
+    #[test]
+    fn test_sm2_sign_message() {
+        use wedpr_l_utils::digest::MessageDigest;
+
+        let sm2_sign = WedprSm2p256v1::default();
+        let (public_key, private_key) = sm2_sign.generate_keypair();
+        let message = b"raw message of any length";
+
+        let gm_digest = MessageDigest::with_domain(WedprSm3::default(), b"app");
+        let signature = sm2_sign
+            .sign_message(&private_key, message, &gm_digest)
+            .unwrap();
+        assert!(sm2_sign.verify_message(
+            &public_key,
+            message,
+            &signature,
+            &gm_digest
+        ));
+        assert!(!sm2_sign.verify_message(
+            &public_key,
+            b"another message",
+            &signature,
+            &gm_digest
+        ));
+    }
+
+    #[test]
+    fn test_sm2_parse_secret_key() {
+        let mut min_secret_key = [0u8; 32];
+        min_secret_key[31] = 1;
+        assert!(parse_secret_key(&min_secret_key).is_ok());
+        let mut max_secret_key = SM2_PRIVATE_KEY_BOUND;
+        max_secret_key[31] -= 1;
+        assert!(parse_secret_key(&max_secret_key).is_ok());
+
+        // Zero, n - 1, n and values above n are rejected.
+        assert!(parse_secret_key(&[0u8; 32]).is_err());
+        assert!(parse_secret_key(&SM2_PRIVATE_KEY_BOUND).is_err());
+        assert!(parse_secret_key(&SM2_CURVE_N).is_err());
+        assert!(parse_secret_key(&[0xffu8; 32]).is_err());
+        assert!(parse_secret_key(&max_secret_key[1..]).is_err());
+
+        let sm2_sign = WedprSm2p256v1::default();
+        let msg_hash = BASE64_ENCODED_TEST_MESSAGE.to_vec();
+        assert_eq!(
+            sm2_sign.sign(&SM2_CURVE_N.to_vec(), &msg_hash),
+            Err(WedprError::FormatError)
+        );
+    }
+
+    #[cfg(feature = "wedpr_f_dudect")]
+    #[test]
+    fn test_sm2_parse_secret_key_timing() {
+        use rand::RngCore;
+        use wedpr_l_utils::dudect::{
+            fixed_vs_random_t_statistic, DUDECT_T_THRESHOLD,
+        };
+
+        let mut rng = rand::thread_rng();
+        // Compares an invalid key (above the curve order) against random
+        // keys, which are almost always valid.
+        let t_statistic = fixed_vs_random_t_statistic(
+            |input| is_valid_secret_scalar(input, &SM2_PRIVATE_KEY_BOUND),
+            &[0xffu8; 32],
+            || {
+                let mut key = vec![0u8; 32];
+                rng.fill_bytes(&mut key);
+                key
+            },
+            100000,
+        );
+        assert!(
+            t_statistic < DUDECT_T_THRESHOLD,
+            "secret key validation leaks timing, t = {}",
+            t_statistic
         );
     }
 }
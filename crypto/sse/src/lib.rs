@@ -0,0 +1,329 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Dynamic searchable symmetric encryption (SSE) functions with forward
+//! privacy, following the Mitra construction.
+//!
+//! The client keeps an update counter per keyword, and every update is stored
+//! by the server under a fresh pseudorandom address derived from the keyword
+//! key and the counter. Addresses of future updates cannot be linked to
+//! search tokens revealed before, which gives forward privacy. The keyword key
+//! can also be rotated to revoke all previously revealed search tokens, while
+//! compacting deleted documents away from the index.
+//!
+//! Pseudorandom functions are computed as a keyed hash H(key || input), which
+//! requires a hash without length extension, e.g. Keccak256 or SM3.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+use std::collections::{BTreeSet, HashMap};
+use wedpr_l_utils::{error::WedprError, traits::Hash};
+
+/// Size of the encrypted value of an index entry.
+const ENTRY_VALUE_SIZE_IN_BYTES: usize = 9;
+const OPERATION_ADD: u8 = 0;
+const OPERATION_DELETE: u8 = 1;
+
+/// Operation of an index update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateOperation {
+    Add,
+    Delete,
+}
+
+/// Index entry to be stored by the server.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct UpdateToken {
+    pub address: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+/// Token for the server to look up all entries of a keyword.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct SearchToken {
+    pub addresses: Vec<Vec<u8>>,
+}
+
+/// Token for the server to replace all entries of a keyword after the keyword
+/// key has been rotated.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct RevocationToken {
+    pub removed_addresses: Vec<Vec<u8>>,
+    pub updates: Vec<UpdateToken>,
+}
+
+/// Client state of a keyword.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+struct KeywordState {
+    epoch: u64,
+    count: u64,
+}
+
+/// Client holding the secret key and the update counters.
+#[derive(Debug, Clone)]
+pub struct SseClient<H: Hash> {
+    hash: H,
+    key: Vec<u8>,
+    states: HashMap<Vec<u8>, KeywordState>,
+}
+
+impl<H: Hash> SseClient<H> {
+    /// Creates a client with a secret key, which should be at least 32 bytes
+    /// of random data.
+    pub fn new(hash: H, key: &[u8]) -> Result<Self, WedprError> {
+        if key.len() < 32 {
+            wedpr_println!("SSE key is too short");
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(SseClient {
+            hash,
+            key: key.to_vec(),
+            states: HashMap::new(),
+        })
+    }
+
+    fn prf(&self, key: &[u8], input: &[u8]) -> Vec<u8> {
+        let mut hash_vec = key.to_vec();
+        hash_vec.extend_from_slice(input);
+        self.hash.hash(&hash_vec)
+    }
+
+    fn keyword_key(&self, keyword: &[u8], epoch: u64) -> Vec<u8> {
+        let mut input = epoch.to_be_bytes().to_vec();
+        input.extend_from_slice(keyword);
+        self.prf(&self.key, &input)
+    }
+
+    /// Derives the address and the value pad of the i-th entry of a keyword.
+    fn entry_secrets(&self, keyword_key: &[u8], i: u64) -> (Vec<u8>, Vec<u8>) {
+        let mut address_input = i.to_be_bytes().to_vec();
+        address_input.push(0);
+        let mut pad_input = i.to_be_bytes().to_vec();
+        pad_input.push(1);
+        (
+            self.prf(keyword_key, &address_input),
+            self.prf(keyword_key, &pad_input),
+        )
+    }
+
+    fn make_update(
+        &self,
+        keyword_key: &[u8],
+        i: u64,
+        document_id: u64,
+        operation: UpdateOperation,
+    ) -> UpdateToken {
+        let (address, pad) = self.entry_secrets(keyword_key, i);
+        let mut value = vec![match operation {
+            UpdateOperation::Add => OPERATION_ADD,
+            UpdateOperation::Delete => OPERATION_DELETE,
+        }];
+        value.extend_from_slice(&document_id.to_be_bytes());
+        for (byte, pad_byte) in value.iter_mut().zip(pad.iter()) {
+            *byte ^= pad_byte;
+        }
+        UpdateToken { address, value }
+    }
+
+    /// Creates an update token adding a document to or deleting a document
+    /// from the results of a keyword.
+    pub fn update(
+        &mut self,
+        keyword: &[u8],
+        document_id: u64,
+        operation: UpdateOperation,
+    ) -> UpdateToken {
+        let mut state = self.states.get(keyword).cloned().unwrap_or_default();
+        state.count += 1;
+        let keyword_key = self.keyword_key(keyword, state.epoch);
+        let token =
+            self.make_update(&keyword_key, state.count, document_id, operation);
+        self.states.insert(keyword.to_vec(), state);
+        token
+    }
+
+    /// Creates a search token of a keyword.
+    pub fn search_token(&self, keyword: &[u8]) -> SearchToken {
+        let state = self.states.get(keyword).cloned().unwrap_or_default();
+        let keyword_key = self.keyword_key(keyword, state.epoch);
+        SearchToken {
+            addresses: (1..=state.count)
+                .map(|i| self.entry_secrets(&keyword_key, i).0)
+                .collect(),
+        }
+    }
+
+    /// Decrypts the values returned by the server for a search token of a
+    /// keyword, and gets the current document ids of the keyword.
+    pub fn resolve(
+        &self,
+        keyword: &[u8],
+        values: &[Vec<u8>],
+    ) -> Result<Vec<u64>, WedprError> {
+        let state = self.states.get(keyword).cloned().unwrap_or_default();
+        if values.len() as u64 != state.count {
+            wedpr_println!("SSE search result count mismatches");
+            return Err(WedprError::VerificationError);
+        }
+        let keyword_key = self.keyword_key(keyword, state.epoch);
+        let mut document_ids = BTreeSet::new();
+        for (i, value) in values.iter().enumerate() {
+            if value.len() != ENTRY_VALUE_SIZE_IN_BYTES {
+                return Err(WedprError::FormatError);
+            }
+            let (_, pad) = self.entry_secrets(&keyword_key, i as u64 + 1);
+            let plaintext: Vec<u8> = value
+                .iter()
+                .zip(pad.iter())
+                .map(|(byte, pad_byte)| byte ^ pad_byte)
+                .collect();
+            let mut id_bytes = [0u8; 8];
+            id_bytes.copy_from_slice(&plaintext[1..]);
+            let document_id = u64::from_be_bytes(id_bytes);
+            match plaintext[0] {
+                OPERATION_ADD => document_ids.insert(document_id),
+                OPERATION_DELETE => document_ids.remove(&document_id),
+                _ => return Err(WedprError::DecodeError),
+            };
+        }
+        Ok(document_ids.into_iter().collect())
+    }
+
+    /// Rotates the key of a keyword given its current document ids resolved
+    /// by the latest search, so that all previously revealed search tokens of
+    /// the keyword are revoked and deleted documents are compacted away.
+    pub fn revoke(
+        &mut self,
+        keyword: &[u8],
+        document_ids: &[u64],
+    ) -> RevocationToken {
+        let removed_addresses = self.search_token(keyword).addresses;
+        let state = self.states.get(keyword).cloned().unwrap_or_default();
+        let new_state = KeywordState {
+            epoch: state.epoch + 1,
+            count: document_ids.len() as u64,
+        };
+        let keyword_key = self.keyword_key(keyword, new_state.epoch);
+        let updates = document_ids
+            .iter()
+            .enumerate()
+            .map(|(i, document_id)| {
+                self.make_update(
+                    &keyword_key,
+                    i as u64 + 1,
+                    *document_id,
+                    UpdateOperation::Add,
+                )
+            })
+            .collect();
+        self.states.insert(keyword.to_vec(), new_state);
+        RevocationToken {
+            removed_addresses,
+            updates,
+        }
+    }
+}
+
+/// Server storing the encrypted index.
+#[derive(Default, Debug, Clone)]
+pub struct SseServer {
+    entries: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+impl SseServer {
+    /// Stores an index entry.
+    pub fn apply_update(&mut self, token: &UpdateToken) {
+        self.entries
+            .insert(token.address.clone(), token.value.clone());
+    }
+
+    /// Looks up the values of all entries of a search token in order, which
+    /// fails if any entry is missing, e.g. revoked.
+    pub fn search(
+        &self,
+        token: &SearchToken,
+    ) -> Result<Vec<Vec<u8>>, WedprError> {
+        token
+            .addresses
+            .iter()
+            .map(|address| match self.entries.get(address) {
+                Some(v) => Ok(v.clone()),
+                None => {
+                    wedpr_println!("SSE index entry not found");
+                    Err(WedprError::ArgumentError)
+                },
+            })
+            .collect()
+    }
+
+    /// Replaces the entries of a keyword whose key has been rotated.
+    pub fn apply_revocation(&mut self, token: &RevocationToken) {
+        for address in &token.removed_addresses {
+            self.entries.remove(address);
+        }
+        for update in &token.updates {
+            self.apply_update(update);
+        }
+    }
+
+    /// Gets the number of stored entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks whether the index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+
+    #[test]
+    fn test_dynamic_sse() {
+        let mut client =
+            SseClient::new(WedprKeccak256::default(), &[7u8; 32]).unwrap();
+        let mut server = SseServer::default();
+        let keyword = b"wedpr";
+
+        for document_id in &[3u64, 1, 2] {
+            server.apply_update(&client.update(
+                keyword,
+                *document_id,
+                UpdateOperation::Add,
+            ));
+        }
+        server.apply_update(&client.update(b"other", 9, UpdateOperation::Add));
+        server.apply_update(&client.update(
+            keyword,
+            2,
+            UpdateOperation::Delete,
+        ));
+
+        let search_token = client.search_token(keyword);
+        let values = server.search(&search_token).unwrap();
+        let document_ids = client.resolve(keyword, &values).unwrap();
+        assert_eq!(document_ids, vec![1, 3]);
+
+        // Later updates are stored under addresses unknown to earlier tokens.
+        let update = client.update(keyword, 4, UpdateOperation::Add);
+        assert!(!search_token.addresses.contains(&update.address));
+        server.apply_update(&update);
+
+        // Revocation compacts the index and invalidates earlier tokens.
+        let values = server.search(&client.search_token(keyword)).unwrap();
+        let document_ids = client.resolve(keyword, &values).unwrap();
+        assert_eq!(document_ids, vec![1, 3, 4]);
+        server.apply_revocation(&client.revoke(keyword, &document_ids));
+        assert_eq!(server.len(), 4);
+        assert!(server.search(&search_token).is_err());
+        let values = server.search(&client.search_token(keyword)).unwrap();
+        assert_eq!(client.resolve(keyword, &values).unwrap(), vec![1, 3, 4]);
+
+        assert!(client.resolve(keyword, &values[1..]).is_err());
+        assert!(SseClient::new(WedprKeccak256::default(), &[7u8; 16]).is_err());
+    }
+}
@@ -0,0 +1,258 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! ssh-agent client signer, which signs with keys held by an agent over its
+//! Unix socket without exposing the private keys.
+
+use crate::{
+    decode_signature_blob,
+    key::{decode_public_key_blob, SshPublicKey},
+    verify_signature,
+    wire::{encode_string, SshReader},
+};
+use std::{
+    env,
+    io::{Read, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+};
+use wedpr_l_utils::{error::WedprError, traits::Signature};
+
+/// Environment variable of the agent socket path.
+pub const SSH_AUTH_SOCK: &str = "SSH_AUTH_SOCK";
+
+const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+const SSH_AGENTC_SIGN_REQUEST: u8 = 13;
+const SSH_AGENT_SIGN_RESPONSE: u8 = 14;
+/// Maximum size of an agent response as limited by OpenSSH.
+const MAX_AGENT_RESPONSE_SIZE_IN_BYTES: usize = 256 * 1024;
+
+/// Implements an ssh-agent client as a Signature instance.
+///
+/// The private key of signing is the SSH public key blob of an identity in
+/// the agent, and the public key of verification is the same blob.
+/// Signatures are in the raw formats of the Ed25519 and secp256r1 backends,
+/// where ECDSA agents sign the SHA-256 hash of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshAgentSigner {
+    pub socket_path: PathBuf,
+}
+
+impl SshAgentSigner {
+    /// Creates a signer of the agent at a socket path.
+    pub fn new(socket_path: PathBuf) -> Self {
+        SshAgentSigner { socket_path }
+    }
+
+    /// Creates a signer of the agent of the SSH_AUTH_SOCK environment
+    /// variable.
+    pub fn from_env() -> Result<Self, WedprError> {
+        match env::var_os(SSH_AUTH_SOCK) {
+            Some(v) => Ok(Self::new(PathBuf::from(v))),
+            None => {
+                wedpr_println!("{} is not set", SSH_AUTH_SOCK);
+                Err(WedprError::ArgumentError)
+            },
+        }
+    }
+
+    /// Lists the identities of supported algorithms in the agent, whose blobs
+    /// can be encoded by key::encode_public_key_blob.
+    pub fn list_identities(&self) -> Result<Vec<SshPublicKey>, WedprError> {
+        let response = self.request(&[SSH_AGENTC_REQUEST_IDENTITIES])?;
+        let mut reader = SshReader::new(&response);
+        if reader.read_u8()? != SSH_AGENT_IDENTITIES_ANSWER {
+            wedpr_println!("Listing ssh-agent identities failed");
+            return Err(WedprError::TransportError);
+        }
+        let count = reader.read_u32()?;
+        let mut identities = Vec::new();
+        for _ in 0..count {
+            let blob = reader.read_string()?;
+            let comment = reader.read_string()?;
+            // Skips keys of unsupported algorithms, e.g. RSA.
+            if let Ok(mut identity) = decode_public_key_blob(blob) {
+                identity.comment = String::from_utf8_lossy(comment).to_string();
+                identities.push(identity);
+            }
+        }
+        Ok(identities)
+    }
+
+    /// Signs data by the agent identity of a public key blob, which returns
+    /// an SSH signature blob.
+    pub fn sign_data(
+        &self,
+        public_key_blob: &[u8],
+        data: &[u8],
+    ) -> Result<Vec<u8>, WedprError> {
+        let mut request = vec![SSH_AGENTC_SIGN_REQUEST];
+        request.extend_from_slice(&encode_string(public_key_blob));
+        request.extend_from_slice(&encode_string(data));
+        // No signature flags.
+        request.extend_from_slice(&0u32.to_be_bytes());
+        let response = self.request(&request)?;
+        let mut reader = SshReader::new(&response);
+        match reader.read_u8()? {
+            SSH_AGENT_SIGN_RESPONSE => Ok(reader.read_string()?.to_vec()),
+            _ => {
+                wedpr_println!("ssh-agent refused to sign");
+                Err(WedprError::TransportError)
+            },
+        }
+    }
+
+    /// Sends a request message and receives its response message.
+    fn request(&self, message: &[u8]) -> Result<Vec<u8>, WedprError> {
+        let mut stream = match UnixStream::connect(&self.socket_path) {
+            Ok(v) => v,
+            Err(_) => {
+                wedpr_println!("Connecting to ssh-agent failed");
+                return Err(WedprError::TransportError);
+            },
+        };
+        let mut length = [0u8; 4];
+        if stream.write_all(&encode_string(message)).is_err()
+            || stream.read_exact(&mut length).is_err()
+        {
+            wedpr_println!("Communicating with ssh-agent failed");
+            return Err(WedprError::TransportError);
+        }
+        let length = u32::from_be_bytes(length) as usize;
+        if length == 0 || length > MAX_AGENT_RESPONSE_SIZE_IN_BYTES {
+            wedpr_println!("Invalid ssh-agent response size");
+            return Err(WedprError::TransportError);
+        }
+        let mut response = vec![0u8; length];
+        if stream.read_exact(&mut response).is_err() {
+            wedpr_println!("Communicating with ssh-agent failed");
+            return Err(WedprError::TransportError);
+        }
+        if response[0] == SSH_AGENT_FAILURE {
+            wedpr_println!("ssh-agent request failed");
+            return Err(WedprError::TransportError);
+        }
+        Ok(response)
+    }
+}
+
+impl Signature for SshAgentSigner {
+    fn sign<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        private_key: &T,
+        msg_hash: &T,
+    ) -> Result<Vec<u8>, WedprError> {
+        let blob = self.sign_data(private_key.as_ref(), msg_hash.as_ref())?;
+        Ok(decode_signature_blob(&blob)?.1)
+    }
+
+    fn verify<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        msg_hash: &T,
+        signature: &T,
+    ) -> bool {
+        verify_signature(
+            public_key.as_ref(),
+            msg_hash.as_ref(),
+            signature.as_ref(),
+        )
+    }
+
+    /// Keys are added to the agent by ssh-add instead, so it returns empty
+    /// keys, and try_generate_keypair should be used instead.
+    fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+        wedpr_println!("Generating keys by ssh-agent is not supported");
+        (Vec::new(), Vec::new())
+    }
+
+    /// Always fails, since keys are added to the agent by ssh-add instead.
+    fn try_generate_keypair(&self) -> Result<(Vec<u8>, Vec<u8>), WedprError> {
+        wedpr_println!("Generating keys by ssh-agent is not supported");
+        Err(WedprError::ArgumentError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        encode_signature_blob, key::encode_public_key_blob, SshKeyAlgorithm,
+    };
+    use std::{os::unix::net::UnixListener, process, thread};
+    use wedpr_l_crypto_signature_ed25519::WedprEd25519;
+
+    /// Serves requests of one identity as an ssh-agent.
+    fn serve_agent(listener: UnixListener, private_key: Vec<u8>) {
+        let ed25519 = WedprEd25519::default();
+        let public_key = ed25519.derive_public_key(&private_key).unwrap();
+        let blob =
+            encode_public_key_blob(SshKeyAlgorithm::Ed25519, &public_key);
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut length = [0u8; 4];
+            stream.read_exact(&mut length).unwrap();
+            let mut request = vec![0u8; u32::from_be_bytes(length) as usize];
+            stream.read_exact(&mut request).unwrap();
+            let mut reader = SshReader::new(&request);
+            let response = match reader.read_u8().unwrap() {
+                SSH_AGENTC_REQUEST_IDENTITIES => {
+                    let mut response = vec![SSH_AGENT_IDENTITIES_ANSWER];
+                    response.extend_from_slice(&1u32.to_be_bytes());
+                    response.extend_from_slice(&encode_string(&blob));
+                    response.extend_from_slice(&encode_string(b"agent key"));
+                    response
+                },
+                _ if reader.read_string().unwrap() == blob.as_slice() => {
+                    let data = reader.read_string().unwrap();
+                    let signature =
+                        ed25519.sign(&private_key[..], data).unwrap();
+                    let mut response = vec![SSH_AGENT_SIGN_RESPONSE];
+                    response.extend_from_slice(&encode_string(
+                        &encode_signature_blob(
+                            SshKeyAlgorithm::Ed25519,
+                            &signature,
+                        )
+                        .unwrap(),
+                    ));
+                    response
+                },
+                _ => vec![SSH_AGENT_FAILURE],
+            };
+            stream.write_all(&encode_string(&response)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_agent_signer() {
+        let socket_path = env::temp_dir()
+            .join(format!("wedpr_ssh_agent_{}.sock", process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path).unwrap();
+        let (_, private_key) = WedprEd25519::default().generate_keypair();
+        thread::spawn(move || serve_agent(listener, private_key));
+
+        let signer = SshAgentSigner::new(socket_path.clone());
+        let identities = signer.list_identities().unwrap();
+        assert_eq!(identities.len(), 1);
+        assert_eq!(identities[0].comment, "agent key");
+        let blob = encode_public_key_blob(
+            identities[0].algorithm,
+            &identities[0].public_key,
+        );
+        let msg_hash = b"artifact digest".to_vec();
+        let signature = signer.sign(&blob[..], &msg_hash[..]).unwrap();
+        assert!(signer.verify(&blob[..], &msg_hash[..], &signature[..]));
+        assert!(WedprEd25519::default().verify(
+            &identities[0].public_key[..],
+            &msg_hash[..],
+            &signature[..]
+        ));
+        assert!(!signer.verify(&blob[..], &msg_hash[1..], &signature[..]));
+        // Signing with an unknown identity fails.
+        assert!(signer.sign(&blob[1..], &msg_hash[..]).is_err());
+        assert!(signer.try_generate_keypair().is_err());
+        let _ = std::fs::remove_file(&socket_path);
+    }
+}
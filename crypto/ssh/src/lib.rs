@@ -0,0 +1,219 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! OpenSSH key formats and an ssh-agent client signer for Ed25519 and ECDSA
+//! over NIST P-256 keys, whose raw keys and signatures are in the formats of
+//! the Ed25519 and secp256r1 Signature backends.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+#[cfg(unix)]
+pub mod agent;
+pub mod key;
+mod wire;
+
+use wedpr_l_crypto_hash_sha256::WedprSha256;
+use wedpr_l_crypto_signature_ed25519::WedprEd25519;
+use wedpr_l_crypto_signature_secp256r1::WedprSecp256r1;
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{Hash, Signature},
+};
+use wire::{encode_mpint, encode_string, SshReader};
+
+/// SSH name of Ed25519 keys and signatures.
+pub const SSH_ED25519: &str = "ssh-ed25519";
+/// SSH name of ECDSA over NIST P-256 keys and signatures.
+pub const SSH_ECDSA_NISTP256: &str = "ecdsa-sha2-nistp256";
+
+/// Size of each ECDSA signature component in bytes.
+const ECDSA_COMPONENT_SIZE_IN_BYTES: usize = 32;
+
+/// Algorithm of an SSH key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SshKeyAlgorithm {
+    Ed25519,
+    EcdsaNistp256,
+}
+
+impl SshKeyAlgorithm {
+    /// Gets the SSH name of the algorithm.
+    pub fn name(self) -> &'static str {
+        match self {
+            SshKeyAlgorithm::Ed25519 => SSH_ED25519,
+            SshKeyAlgorithm::EcdsaNistp256 => SSH_ECDSA_NISTP256,
+        }
+    }
+
+    /// Gets the algorithm of an SSH name.
+    pub fn from_name(name: &[u8]) -> Result<Self, WedprError> {
+        if name == SSH_ED25519.as_bytes() {
+            Ok(SshKeyAlgorithm::Ed25519)
+        } else if name == SSH_ECDSA_NISTP256.as_bytes() {
+            Ok(SshKeyAlgorithm::EcdsaNistp256)
+        } else {
+            wedpr_println!("Unsupported SSH key algorithm");
+            Err(WedprError::ArgumentError)
+        }
+    }
+
+    /// Gets the input of the Signature backend for signed data, where ECDSA
+    /// signs the SHA-256 hash of the data, and Ed25519 signs the data itself.
+    pub fn signed_input(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            SshKeyAlgorithm::Ed25519 => data.to_vec(),
+            SshKeyAlgorithm::EcdsaNistp256 => WedprSha256::default().hash(data),
+        }
+    }
+}
+
+/// Encodes a raw signature of the Signature backend as an SSH signature blob.
+pub fn encode_signature_blob(
+    algorithm: SshKeyAlgorithm,
+    signature: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let content = match algorithm {
+        SshKeyAlgorithm::Ed25519 => signature.to_vec(),
+        SshKeyAlgorithm::EcdsaNistp256 => {
+            if signature.len() != 2 * ECDSA_COMPONENT_SIZE_IN_BYTES {
+                return Err(WedprError::FormatError);
+            }
+            let (r, s) = signature.split_at(ECDSA_COMPONENT_SIZE_IN_BYTES);
+            let mut content = encode_mpint(r);
+            content.extend_from_slice(&encode_mpint(s));
+            content
+        },
+    };
+    let mut blob = encode_string(algorithm.name().as_bytes());
+    blob.extend_from_slice(&encode_string(&content));
+    Ok(blob)
+}
+
+/// Decodes an SSH signature blob to its algorithm and the raw signature of
+/// the Signature backend.
+pub fn decode_signature_blob(
+    blob: &[u8],
+) -> Result<(SshKeyAlgorithm, Vec<u8>), WedprError> {
+    let mut reader = SshReader::new(blob);
+    let algorithm = SshKeyAlgorithm::from_name(reader.read_string()?)?;
+    let content = reader.read_string()?;
+    if !reader.is_empty() {
+        return Err(WedprError::FormatError);
+    }
+    let signature = match algorithm {
+        SshKeyAlgorithm::Ed25519 => content.to_vec(),
+        SshKeyAlgorithm::EcdsaNistp256 => {
+            let mut content_reader = SshReader::new(content);
+            let mut signature =
+                content_reader.read_mpint(ECDSA_COMPONENT_SIZE_IN_BYTES)?;
+            signature.extend_from_slice(
+                &content_reader.read_mpint(ECDSA_COMPONENT_SIZE_IN_BYTES)?,
+            );
+            if !content_reader.is_empty() {
+                return Err(WedprError::FormatError);
+            }
+            signature
+        },
+    };
+    Ok((algorithm, signature))
+}
+
+/// Verifies a raw signature of data with an SSH public key blob.
+pub fn verify_signature(
+    public_key_blob: &[u8],
+    data: &[u8],
+    signature: &[u8],
+) -> bool {
+    let public_key = match key::decode_public_key_blob(public_key_blob) {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+    let signed_input = public_key.algorithm.signed_input(data);
+    match public_key.algorithm {
+        SshKeyAlgorithm::Ed25519 => WedprEd25519::default().verify(
+            &public_key.public_key[..],
+            &signed_input[..],
+            signature,
+        ),
+        SshKeyAlgorithm::EcdsaNistp256 => WedprSecp256r1::default().verify(
+            &public_key.public_key[..],
+            &signed_input[..],
+            signature,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_common_coder_hex::WedprHex;
+    use wedpr_l_utils::traits::Coder;
+
+    #[test]
+    fn test_signature_blob() {
+        let hex = WedprHex::default();
+        // Public keys and signatures of "artifact digest" by OpenSSH 9.2
+        // ssh-agent.
+        let public_key_blob = base64::decode(
+            [
+                "AAAAE2VjZHNhLXNoYTItbmlzdHAyNTYAAAAIbmlzdHAyNTYAAABBBHDTUD3P",
+                "5un7cGo4Irc0gtcFE7E02eGDjZPJNnjjIdtoeJtXMg8XoSySC3wjvsAQDJCX",
+                "Dh/CG/xYBYpcL2GnrXs=",
+            ]
+            .concat(),
+        )
+        .unwrap();
+        let blob = hex
+            .decode(
+                &[
+                    "0000001365636473612d736861322d6e697374703235360000004900",
+                    "0000202750f40dbe9c8cb3f545b0feb918a25f0ff17db654c855ce90",
+                    "f88e502acc744d0000002100830cd6253e9d6cafd0be02848e663eed",
+                    "58a9a490ca6eeca0d45db723c14e8980",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let (algorithm, signature) = decode_signature_blob(&blob).unwrap();
+        assert_eq!(algorithm, SshKeyAlgorithm::EcdsaNistp256);
+        assert_eq!(signature.len(), 64);
+        assert_eq!(encode_signature_blob(algorithm, &signature).unwrap(), blob);
+        assert!(verify_signature(
+            &public_key_blob,
+            b"artifact digest",
+            &signature
+        ));
+        assert!(!verify_signature(
+            &public_key_blob,
+            b"artifact digest!",
+            &signature
+        ));
+
+        let public_key_blob = base64::decode(
+            [
+                "AAAAC3NzaC1lZDI1NTE5AAAAIMpGorNaPAvThqwGVZnzkQyTRpYTk/dlQ7gK",
+                "LNU8xT2m",
+            ]
+            .concat(),
+        )
+        .unwrap();
+        let blob = hex
+            .decode(
+                &[
+                    "0000000b7373682d656432353531390000004053867f3026f0ed9dbf",
+                    "5af6b5d93233000fdcb56714c46189e6e411508eaf0e3d5afb4b7d6c",
+                    "0bdbaa29945b20a969ad8e43b3cc3bbc0538397e7dde6e9600dc0c",
+                ]
+                .concat(),
+            )
+            .unwrap();
+        let (algorithm, signature) = decode_signature_blob(&blob).unwrap();
+        assert_eq!(algorithm, SshKeyAlgorithm::Ed25519);
+        assert!(verify_signature(
+            &public_key_blob,
+            b"artifact digest",
+            &signature
+        ));
+        assert!(decode_signature_blob(&blob[..blob.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! SSH wire encoding functions of RFC 4251.
+
+use wedpr_l_utils::error::WedprError;
+
+/// Encodes bytes as a string with its 32-bit length.
+pub(crate) fn encode_string(value: &[u8]) -> Vec<u8> {
+    let mut output = (value.len() as u32).to_be_bytes().to_vec();
+    output.extend_from_slice(value);
+    output
+}
+
+/// Encodes a non-negative big-endian value as an mpint, which has a leading
+/// zero byte if its highest bit is set.
+pub(crate) fn encode_mpint(value: &[u8]) -> Vec<u8> {
+    let mut content: Vec<u8> = value
+        .iter()
+        .cloned()
+        .skip_while(|byte| *byte == 0)
+        .collect();
+    if content.first().map_or(false, |byte| byte & 0x80 != 0) {
+        content.insert(0, 0);
+    }
+    encode_string(&content)
+}
+
+/// Reader of fields in SSH wire encoding.
+pub(crate) struct SshReader<'a> {
+    input: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> SshReader<'a> {
+    pub(crate) fn new(input: &'a [u8]) -> Self {
+        SshReader { input, offset: 0 }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.offset >= self.input.len()
+    }
+
+    pub(crate) fn read_fixed(
+        &mut self,
+        length: usize,
+    ) -> Result<&'a [u8], WedprError> {
+        if self.input.len() - self.offset < length {
+            wedpr_println!("SSH data is truncated");
+            return Err(WedprError::FormatError);
+        }
+        let field = &self.input[self.offset..self.offset + length];
+        self.offset += length;
+        Ok(field)
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Result<u8, WedprError> {
+        Ok(self.read_fixed(1)?[0])
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Result<u32, WedprError> {
+        let mut buffer = [0u8; 4];
+        buffer.copy_from_slice(self.read_fixed(4)?);
+        Ok(u32::from_be_bytes(buffer))
+    }
+
+    pub(crate) fn read_string(&mut self) -> Result<&'a [u8], WedprError> {
+        let length = self.read_u32()? as usize;
+        self.read_fixed(length)
+    }
+
+    /// Reads a non-negative mpint as a big-endian value of a fixed size.
+    pub(crate) fn read_mpint(
+        &mut self,
+        size: usize,
+    ) -> Result<Vec<u8>, WedprError> {
+        let value = self.read_string()?;
+        if value.first().map_or(false, |byte| byte & 0x80 != 0) {
+            wedpr_println!("Negative SSH mpint is not supported");
+            return Err(WedprError::FormatError);
+        }
+        let value: Vec<u8> = value
+            .iter()
+            .cloned()
+            .skip_while(|byte| *byte == 0)
+            .collect();
+        if value.len() > size {
+            return Err(WedprError::FormatError);
+        }
+        let mut output = vec![0u8; size - value.len()];
+        output.extend_from_slice(&value);
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wire() {
+        assert_eq!(encode_string(b"ab"), vec![0, 0, 0, 2, b'a', b'b']);
+        assert_eq!(encode_mpint(&[0, 0x7f]), vec![0, 0, 0, 1, 0x7f]);
+        assert_eq!(encode_mpint(&[0, 0x80]), vec![0, 0, 0, 2, 0, 0x80]);
+        assert_eq!(encode_mpint(&[0, 0]), vec![0, 0, 0, 0]);
+
+        let mut encoded = encode_mpint(&[0x80, 1]);
+        encoded.extend_from_slice(&encode_string(b"ssh"));
+        let mut reader = SshReader::new(&encoded);
+        assert_eq!(reader.read_mpint(3).unwrap(), vec![0, 0x80, 1]);
+        assert_eq!(reader.read_string().unwrap(), b"ssh");
+        assert!(reader.is_empty());
+        assert!(reader.read_u8().is_err());
+        assert!(SshReader::new(&[0, 0, 0, 1, 0xff]).read_mpint(1).is_err());
+    }
+}
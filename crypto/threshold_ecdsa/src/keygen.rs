@@ -0,0 +1,270 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Distributed key generation of threshold ECDSA, where every party deals a
+//! random polynomial of degree t with Feldman commitments, and the key share
+//! of a party is the sum of the shares dealt to it.
+
+use crate::{
+    base_mul, bytes_to_scalar, check_parameters, evaluate_polynomial,
+    parse_point, point_mul, random_scalar, scalar_from_u64, scalar_to_bytes,
+    sum_points, verify_feldman_share, Scalar,
+};
+use secp256k1::PublicKey;
+use std::collections::BTreeMap;
+use wedpr_l_utils::error::WedprError;
+
+/// Message of key generation from a party to another party.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct KeygenMessage {
+    pub sender: usize,
+    pub recipient: usize,
+    /// Feldman commitments of the polynomial coefficients of the sender.
+    pub commitments: Vec<Vec<u8>>,
+    /// Share of the sender's polynomial evaluated at the recipient index.
+    pub share: Vec<u8>,
+}
+
+/// Key share of a party generated by the distributed key generation.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct KeyShare {
+    pub index: usize,
+    pub threshold: usize,
+    pub party_count: usize,
+    pub secret_share: Vec<u8>,
+    /// Joint public key in the uncompressed form.
+    pub public_key: Vec<u8>,
+    /// x_i * G of the secret share x_i of every party i, starting from 1.
+    pub verification_shares: Vec<Vec<u8>>,
+}
+
+/// State machine of a party in the distributed key generation.
+#[derive(Debug, Clone)]
+pub struct KeygenParty {
+    index: usize,
+    threshold: usize,
+    party_count: usize,
+    /// Commitments and shares received by the sender index, including the
+    /// party itself.
+    received: BTreeMap<usize, (Vec<PublicKey>, Scalar)>,
+}
+
+impl KeygenParty {
+    /// Creates a party of the index starting from 1, where party_count should
+    /// be at least 2 * threshold + 1 for signing.
+    pub fn new(
+        index: usize,
+        threshold: usize,
+        party_count: usize,
+    ) -> Result<Self, WedprError> {
+        check_parameters(index, threshold, party_count)?;
+        Ok(KeygenParty {
+            index,
+            threshold,
+            party_count,
+            received: BTreeMap::new(),
+        })
+    }
+
+    /// Deals a random polynomial and returns the messages to other parties,
+    /// which should be called once before handling any message.
+    pub fn start(&mut self) -> Result<Vec<KeygenMessage>, WedprError> {
+        if self.received.contains_key(&self.index) {
+            wedpr_println!("Key generation has already started");
+            return Err(WedprError::ArgumentError);
+        }
+        let coefficients: Vec<Scalar> =
+            (0..=self.threshold).map(|_| random_scalar()).collect();
+        let commitments: Vec<PublicKey> = coefficients
+            .iter()
+            .map(|v| base_mul(v).ok_or(WedprError::ArgumentError))
+            .collect::<Result<Vec<PublicKey>, WedprError>>()?;
+        let encoded_commitments: Vec<Vec<u8>> =
+            commitments.iter().map(|v| v.serialize().to_vec()).collect();
+        let messages = (1..=self.party_count)
+            .filter(|recipient| *recipient != self.index)
+            .map(|recipient| KeygenMessage {
+                sender: self.index,
+                recipient,
+                commitments: encoded_commitments.clone(),
+                share: scalar_to_bytes(&evaluate_polynomial(
+                    &coefficients,
+                    recipient,
+                )),
+            })
+            .collect();
+        let own_share = evaluate_polynomial(&coefficients, self.index);
+        self.received.insert(self.index, (commitments, own_share));
+        Ok(messages)
+    }
+
+    /// Handles a message from another party, and returns the key share once
+    /// the messages of all parties have been handled.
+    pub fn handle(
+        &mut self,
+        message: &KeygenMessage,
+    ) -> Result<Option<KeyShare>, WedprError> {
+        if !self.received.contains_key(&self.index) {
+            wedpr_println!("Key generation has not started");
+            return Err(WedprError::ArgumentError);
+        }
+        if message.recipient != self.index
+            || message.sender == 0
+            || message.sender > self.party_count
+            || self.received.contains_key(&message.sender)
+        {
+            wedpr_println!("Unexpected key generation message");
+            return Err(WedprError::ArgumentError);
+        }
+        if message.commitments.len() != self.threshold + 1 {
+            return Err(WedprError::FormatError);
+        }
+        let commitments = message
+            .commitments
+            .iter()
+            .map(|v| parse_point(v))
+            .collect::<Result<Vec<PublicKey>, WedprError>>()?;
+        let share = bytes_to_scalar(&message.share)?;
+        if !verify_feldman_share(&commitments, false, self.index, &share) {
+            wedpr_println!(
+                "Key share from party {} is invalid",
+                message.sender
+            );
+            return Err(WedprError::VerificationError);
+        }
+        self.received.insert(message.sender, (commitments, share));
+        if self.received.len() < self.party_count {
+            return Ok(None);
+        }
+        self.finish().map(Some)
+    }
+
+    fn finish(&self) -> Result<KeyShare, WedprError> {
+        let secret_share = self
+            .received
+            .values()
+            .fold(scalar_from_u64(0), |sum, (_, share)| sum.add(share));
+        let constant_commitments: Vec<PublicKey> = self
+            .received
+            .values()
+            .map(|(commitments, _)| commitments[0])
+            .collect();
+        let public_key = match sum_points(&constant_commitments) {
+            Some(v) => v,
+            None => return Err(WedprError::VerificationError),
+        };
+        // The commitments of the joint polynomial are the sums of the
+        // commitments of all parties.
+        let joint_commitments = (0..=self.threshold)
+            .map(|m| {
+                let terms: Vec<PublicKey> = self
+                    .received
+                    .values()
+                    .map(|(commitments, _)| commitments[m])
+                    .collect();
+                sum_points(&terms).ok_or(WedprError::VerificationError)
+            })
+            .collect::<Result<Vec<PublicKey>, WedprError>>()?;
+        let verification_shares = (1..=self.party_count)
+            .map(|x| {
+                evaluate_commitments(&joint_commitments, x)
+                    .map(|v| v.serialize_uncompressed().to_vec())
+                    .ok_or(WedprError::VerificationError)
+            })
+            .collect::<Result<Vec<Vec<u8>>, WedprError>>()?;
+        Ok(KeyShare {
+            index: self.index,
+            threshold: self.threshold,
+            party_count: self.party_count,
+            secret_share: scalar_to_bytes(&secret_share),
+            public_key: public_key.serialize_uncompressed().to_vec(),
+            verification_shares,
+        })
+    }
+}
+
+/// Evaluates the committed polynomial at x in the exponent.
+fn evaluate_commitments(
+    commitments: &[PublicKey],
+    x: usize,
+) -> Option<PublicKey> {
+    let x = scalar_from_u64(x as u64);
+    let mut power = scalar_from_u64(1);
+    let mut terms = Vec::with_capacity(commitments.len());
+    for commitment in commitments {
+        terms.push(point_mul(commitment, &power)?);
+        power = power.mul(&x);
+    }
+    sum_points(&terms)
+}
+
+/// Runs the distributed key generation among local parties, for testing and
+/// for simulating deployments.
+pub fn run_local_keygen(
+    threshold: usize,
+    party_count: usize,
+) -> Result<Vec<KeyShare>, WedprError> {
+    let mut parties = (1..=party_count)
+        .map(|i| KeygenParty::new(i, threshold, party_count))
+        .collect::<Result<Vec<KeygenParty>, WedprError>>()?;
+    let mut messages = Vec::new();
+    for party in parties.iter_mut() {
+        messages.extend(party.start()?);
+    }
+    let mut key_shares = vec![None; party_count];
+    for message in &messages {
+        let recipient = message.recipient - 1;
+        if let Some(v) = parties[recipient].handle(message)? {
+            key_shares[recipient] = Some(v);
+        }
+    }
+    key_shares
+        .into_iter()
+        .map(|v| v.ok_or(WedprError::VerificationError))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolate_at_zero;
+
+    #[test]
+    fn test_keygen() {
+        let key_shares = run_local_keygen(1, 3).unwrap();
+        assert_eq!(key_shares.len(), 3);
+        for key_share in &key_shares {
+            assert_eq!(key_share.public_key, key_shares[0].public_key);
+            let secret_share =
+                bytes_to_scalar(&key_share.secret_share).unwrap();
+            assert_eq!(
+                base_mul(&secret_share).unwrap().serialize_uncompressed()[..],
+                key_share.verification_shares[key_share.index - 1][..]
+            );
+        }
+        // Any 2 shares reconstruct the private key of the joint public key.
+        let points: Vec<(usize, Scalar)> = key_shares[1..]
+            .iter()
+            .map(|v| (v.index, bytes_to_scalar(&v.secret_share).unwrap()))
+            .collect();
+        let private_key = interpolate_at_zero(&points).unwrap();
+        assert_eq!(
+            base_mul(&private_key).unwrap().serialize_uncompressed()[..],
+            key_shares[0].public_key[..]
+        );
+
+        // Tampered shares are detected by the commitments.
+        let mut parties: Vec<KeygenParty> = (1..=3)
+            .map(|i| KeygenParty::new(i, 1, 3).unwrap())
+            .collect();
+        let mut messages = parties[0].start().unwrap();
+        parties[1].start().unwrap();
+        assert!(parties[0].start().is_err());
+        messages[0].share = scalar_to_bytes(&scalar_from_u64(1));
+        assert_eq!(
+            parties[1].handle(&messages[0]),
+            Err(WedprError::VerificationError)
+        );
+        assert!(parties[2].handle(&messages[0]).is_err());
+        assert!(KeygenParty::new(1, 1, 2).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Threshold ECDSA functions over secp256k1, where n parties jointly
+//! generate a key without any dealer, and any 2t + 1 of them can jointly
+//! sign, while t or fewer colluding parties learn nothing about the private
+//! key.
+//!
+//! Key generation follows the joint-Feldman distributed key generation, and
+//! signing follows the robust threshold DSS of Gennaro, Jarecki, Krawczyk
+//! and Rabin, which computes products of shares by the degree 2t
+//! interpolation instead of the Paillier-based multiplication, thus requires
+//! an honest majority of signers. The protocols tolerate semi-honest parties,
+//! where invalid key or nonce shares are detected by Feldman commitments.
+//!
+//! Both protocols are exposed as message-driven state machines, so that
+//! callers can deliver messages through their own network and in any order.
+//! Messages should be delivered over authenticated and private channels, and
+//! commitments of the same sender should be checked to be consistent among
+//! recipients, e.g. by recording them on chain.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+#[macro_use]
+extern crate lazy_static;
+
+pub mod keygen;
+pub mod signing;
+
+use secp256k1::{constants::CURVE_ORDER, All, PublicKey, Secp256k1, SecretKey};
+use wedpr_l_utils::{
+    error::WedprError,
+    scalar::{Field256, Field256Element},
+};
+
+lazy_static! {
+    // Shared secp256k1 instance initialized for all functions.
+    static ref SECP256K1_ALL: Secp256k1<All> = Secp256k1::new();
+    // Constant-time arithmetic modulo the curve order.
+    static ref SECP256K1_SCALAR_FIELD: Field256 =
+        Field256::new(&CURVE_ORDER).unwrap();
+}
+
+const SCALAR_SIZE_IN_BYTES: usize = 32;
+
+/// Scalar modulo the curve order.
+type Scalar = Field256Element;
+
+/// Checks the threshold and party count, and the index of a party starting
+/// from 1.
+fn check_parameters(
+    index: usize,
+    threshold: usize,
+    party_count: usize,
+) -> Result<(), WedprError> {
+    if threshold == 0
+        || party_count < 2 * threshold + 1
+        || index == 0
+        || index > party_count
+    {
+        wedpr_println!("Invalid threshold ECDSA parameters");
+        return Err(WedprError::ArgumentError);
+    }
+    Ok(())
+}
+
+/// Generates a random non-zero scalar.
+fn random_scalar() -> Scalar {
+    SECP256K1_SCALAR_FIELD.random_nonzero()
+}
+
+/// Gets the scalar of a small value, e.g. a party index.
+fn scalar_from_u64(value: u64) -> Scalar {
+    SECP256K1_SCALAR_FIELD.element_from_u64(value)
+}
+
+/// Encodes a scalar as 32 big-endian bytes.
+fn scalar_to_bytes(value: &Scalar) -> Vec<u8> {
+    value.to_bytes()
+}
+
+/// Decodes a scalar less than the curve order.
+fn bytes_to_scalar(bytes: &[u8]) -> Result<Scalar, WedprError> {
+    if bytes.len() != SCALAR_SIZE_IN_BYTES {
+        wedpr_println!("Invalid scalar");
+        return Err(WedprError::FormatError);
+    }
+    SECP256K1_SCALAR_FIELD.decode(bytes)
+}
+
+/// Computes value * G, which is None for zero.
+fn base_mul(value: &Scalar) -> Option<PublicKey> {
+    let secret_key = SecretKey::from_slice(&scalar_to_bytes(value)).ok()?;
+    Some(PublicKey::from_secret_key(&SECP256K1_ALL, &secret_key))
+}
+
+/// Computes value * point, which is None for zero.
+fn point_mul(point: &PublicKey, value: &Scalar) -> Option<PublicKey> {
+    let mut output = *point;
+    output
+        .mul_assign(&SECP256K1_ALL, &scalar_to_bytes(value))
+        .ok()?;
+    Some(output)
+}
+
+/// Sums points, which is None for an empty input or the point at infinity.
+fn sum_points(points: &[PublicKey]) -> Option<PublicKey> {
+    let (first, rest) = points.split_first()?;
+    rest.iter()
+        .try_fold(*first, |sum, point| sum.combine(point).ok())
+}
+
+fn parse_point(bytes: &[u8]) -> Result<PublicKey, WedprError> {
+    match PublicKey::from_slice(bytes) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Parsing point failed");
+            Err(WedprError::FormatError)
+        },
+    }
+}
+
+/// Evaluates a polynomial at x by Horner's method.
+fn evaluate_polynomial(coefficients: &[Scalar], x: usize) -> Scalar {
+    let x = scalar_from_u64(x as u64);
+    coefficients
+        .iter()
+        .rev()
+        .fold(scalar_from_u64(0), |sum, coefficient| {
+            sum.mul(&x).add(coefficient)
+        })
+}
+
+/// Checks share * G = sum(C_m * x^m) for Feldman commitments C_m of the
+/// coefficients, where the constant coefficient is known to be zero if
+/// zero_constant is true and its commitment is omitted.
+fn verify_feldman_share(
+    commitments: &[PublicKey],
+    zero_constant: bool,
+    x: usize,
+    share: &Scalar,
+) -> bool {
+    let x = scalar_from_u64(x as u64);
+    let mut power = if zero_constant {
+        x.clone()
+    } else {
+        scalar_from_u64(1)
+    };
+    let mut terms = Vec::with_capacity(commitments.len());
+    for commitment in commitments {
+        match point_mul(commitment, &power) {
+            Some(v) => terms.push(v),
+            None => return false,
+        }
+        power = power.mul(&x);
+    }
+    match (sum_points(&terms), base_mul(share)) {
+        (Some(expected), Some(actual)) => expected == actual,
+        _ => false,
+    }
+}
+
+/// Computes the Lagrange coefficient of index at 0 over distinct indexes.
+fn lagrange_coefficient(
+    index: usize,
+    indexes: &[usize],
+) -> Result<Scalar, WedprError> {
+    let mut numerator = scalar_from_u64(1);
+    let mut denominator = scalar_from_u64(1);
+    for other in indexes.iter().filter(|v| **v != index) {
+        let other = scalar_from_u64(*other as u64);
+        numerator = numerator.mul(&other);
+        denominator =
+            denominator.mul(&other.sub(&scalar_from_u64(index as u64)));
+    }
+    Ok(numerator.mul(&denominator.inverse()?))
+}
+
+/// Interpolates the value at 0 from (index, value) points of distinct
+/// indexes.
+fn interpolate_at_zero(
+    points: &[(usize, Scalar)],
+) -> Result<Scalar, WedprError> {
+    let indexes: Vec<usize> = points.iter().map(|(index, _)| *index).collect();
+    points
+        .iter()
+        .try_fold(scalar_from_u64(0), |sum, (index, value)| {
+            Ok(sum.add(&lagrange_coefficient(*index, &indexes)?.mul(value)))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shamir_helpers() {
+        let coefficients: Vec<Scalar> =
+            (0..3).map(|_| random_scalar()).collect();
+        let commitments: Vec<PublicKey> =
+            coefficients.iter().map(|v| base_mul(v).unwrap()).collect();
+        let points: Vec<(usize, Scalar)> = [2, 3, 5]
+            .iter()
+            .map(|x| (*x, evaluate_polynomial(&coefficients, *x)))
+            .collect();
+        let secret = scalar_to_bytes(&coefficients[0]);
+        assert_eq!(
+            scalar_to_bytes(&interpolate_at_zero(&points).unwrap()),
+            secret
+        );
+        assert_ne!(
+            scalar_to_bytes(&interpolate_at_zero(&points[..2]).unwrap()),
+            secret
+        );
+        assert!(verify_feldman_share(&commitments, false, 2, &points[0].1));
+        assert!(!verify_feldman_share(&commitments, false, 3, &points[0].1));
+        assert!(verify_feldman_share(
+            &commitments[1..],
+            true,
+            2,
+            &points[0].1.sub(&coefficients[0])
+        ));
+        assert_eq!(
+            scalar_to_bytes(
+                &bytes_to_scalar(&scalar_to_bytes(&coefficients[1])).unwrap()
+            ),
+            scalar_to_bytes(&coefficients[1])
+        );
+        assert_eq!(scalar_to_bytes(&scalar_from_u64(1)).len(), 32);
+        assert!(bytes_to_scalar(&CURVE_ORDER).is_err());
+        assert!(bytes_to_scalar(&secret[1..]).is_err());
+        assert!(check_parameters(1, 1, 2).is_err());
+        assert!(check_parameters(3, 1, 3).is_ok());
+    }
+}
@@ -0,0 +1,508 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Threshold ECDSA signing among 2t + 1 or more signers in three rounds:
+//! 1) Every signer deals random polynomials of degree t for the nonce k and the
+//!    mask a, and of degree 2t with a zero constant for re-randomizing the
+//!    product shares, where R = k * G is computed from the Feldman commitments
+//!    of the nonce polynomials.
+//! 2) Every signer reveals its share of mu = k * a, which reveals nothing about
+//!    k, so that every signer gets its share of k^-1 = a * mu^-1.
+//! 3) Every signer reveals its share of s = k^-1 * (e + r * x), and s is
+//!    interpolated from the shares of all signers.
+//!
+//! Signatures are r || s || v of 65 bytes with low s, which are compatible
+//! with FISCO-BCOS and can be verified by recovering the public key.
+
+use crate::{
+    base_mul, bytes_to_scalar, evaluate_polynomial, interpolate_at_zero,
+    keygen::KeyShare, parse_point, random_scalar, scalar_from_u64,
+    scalar_to_bytes, sum_points, verify_feldman_share, Scalar, SECP256K1_ALL,
+    SECP256K1_SCALAR_FIELD,
+};
+use secp256k1::{
+    constants::CURVE_ORDER, Message, PublicKey, Signature as Secp256k1Signature,
+};
+use std::collections::{BTreeMap, VecDeque};
+use wedpr_l_utils::error::WedprError;
+
+const MESSAGE_HASH_SIZE_IN_BYTES: usize = 32;
+
+/// Payload of a signing message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SigningPayload {
+    /// Round 1 shares dealt by the sender to the recipient.
+    Shares {
+        /// Feldman commitments of the nonce polynomial coefficients.
+        nonce_commitments: Vec<Vec<u8>>,
+        nonce_share: Vec<u8>,
+        mask_share: Vec<u8>,
+        product_zero_share: Vec<u8>,
+        signature_zero_share: Vec<u8>,
+    },
+    /// Round 2 share of mu = k * a of the sender.
+    ProductShare(Vec<u8>),
+    /// Round 3 share of s of the sender.
+    SignatureShare(Vec<u8>),
+}
+
+/// Message of signing from a signer to another signer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningMessage {
+    pub sender: usize,
+    pub recipient: usize,
+    pub payload: SigningPayload,
+}
+
+/// Round 1 shares received from a signer.
+#[derive(Debug, Clone)]
+struct DealtShares {
+    nonce_commitment: PublicKey,
+    nonce: Scalar,
+    mask: Scalar,
+    product_zero: Scalar,
+    signature_zero: Scalar,
+}
+
+/// Sums of the round 1 shares of all signers, and the nonce point R.
+#[derive(Debug, Clone)]
+struct NonceShares {
+    nonce_point: PublicKey,
+    mask: Scalar,
+    signature_zero: Scalar,
+}
+
+/// State machine of a signer in threshold signing.
+#[derive(Debug, Clone)]
+pub struct SigningParty {
+    index: usize,
+    threshold: usize,
+    signers: Vec<usize>,
+    secret_share: Scalar,
+    public_key: PublicKey,
+    msg_hash: Vec<u8>,
+    dealt_shares: BTreeMap<usize, DealtShares>,
+    nonce_shares: Option<NonceShares>,
+    product_shares: BTreeMap<usize, Scalar>,
+    signature_shares: BTreeMap<usize, Scalar>,
+    signature: Option<Vec<u8>>,
+}
+
+impl SigningParty {
+    /// Creates a signer of a message hash, where signers are the indexes of
+    /// at least 2 * threshold + 1 parties including this one.
+    pub fn new(
+        key_share: &KeyShare,
+        signers: &[usize],
+        msg_hash: &[u8],
+    ) -> Result<Self, WedprError> {
+        let mut sorted_signers = signers.to_vec();
+        sorted_signers.sort_unstable();
+        sorted_signers.dedup();
+        if sorted_signers.len() != signers.len()
+            || sorted_signers.len() < 2 * key_share.threshold + 1
+            || !sorted_signers.contains(&key_share.index)
+            || sorted_signers
+                .iter()
+                .any(|v| *v == 0 || *v > key_share.party_count)
+        {
+            wedpr_println!("Invalid signers of threshold signing");
+            return Err(WedprError::ArgumentError);
+        }
+        if msg_hash.len() != MESSAGE_HASH_SIZE_IN_BYTES {
+            return Err(WedprError::ArgumentError);
+        }
+        Ok(SigningParty {
+            index: key_share.index,
+            threshold: key_share.threshold,
+            signers: sorted_signers,
+            secret_share: bytes_to_scalar(&key_share.secret_share)?,
+            public_key: parse_point(&key_share.public_key)?,
+            msg_hash: msg_hash.to_vec(),
+            dealt_shares: BTreeMap::new(),
+            nonce_shares: None,
+            product_shares: BTreeMap::new(),
+            signature_shares: BTreeMap::new(),
+            signature: None,
+        })
+    }
+
+    /// Deals the round 1 shares and returns the messages to other signers,
+    /// which should be called once before handling any message.
+    pub fn start(&mut self) -> Result<Vec<SigningMessage>, WedprError> {
+        if self.dealt_shares.contains_key(&self.index) {
+            wedpr_println!("Signing has already started");
+            return Err(WedprError::ArgumentError);
+        }
+        let nonce_coefficients = random_polynomial(self.threshold, false);
+        let mask_coefficients = random_polynomial(self.threshold, false);
+        let product_zero_coefficients =
+            random_polynomial(2 * self.threshold, true);
+        let signature_zero_coefficients =
+            random_polynomial(2 * self.threshold, true);
+        let nonce_commitments = nonce_coefficients
+            .iter()
+            .map(|v| base_mul(v).ok_or(WedprError::ArgumentError))
+            .collect::<Result<Vec<PublicKey>, WedprError>>()?;
+        let encoded_commitments: Vec<Vec<u8>> = nonce_commitments
+            .iter()
+            .map(|v| v.serialize().to_vec())
+            .collect();
+        let evaluate = |x: usize| DealtShares {
+            nonce_commitment: nonce_commitments[0],
+            nonce: evaluate_polynomial(&nonce_coefficients, x),
+            mask: evaluate_polynomial(&mask_coefficients, x),
+            product_zero: evaluate_polynomial(&product_zero_coefficients, x),
+            signature_zero: evaluate_polynomial(
+                &signature_zero_coefficients,
+                x,
+            ),
+        };
+        let mut messages: Vec<SigningMessage> = self
+            .signers
+            .iter()
+            .filter(|recipient| **recipient != self.index)
+            .map(|recipient| {
+                let shares = evaluate(*recipient);
+                SigningMessage {
+                    sender: self.index,
+                    recipient: *recipient,
+                    payload: SigningPayload::Shares {
+                        nonce_commitments: encoded_commitments.clone(),
+                        nonce_share: scalar_to_bytes(&shares.nonce),
+                        mask_share: scalar_to_bytes(&shares.mask),
+                        product_zero_share: scalar_to_bytes(
+                            &shares.product_zero,
+                        ),
+                        signature_zero_share: scalar_to_bytes(
+                            &shares.signature_zero,
+                        ),
+                    },
+                }
+            })
+            .collect();
+        self.dealt_shares.insert(self.index, evaluate(self.index));
+        messages.extend(self.advance()?);
+        Ok(messages)
+    }
+
+    /// Handles a message from another signer, and returns the messages to
+    /// send in response.
+    pub fn handle(
+        &mut self,
+        message: &SigningMessage,
+    ) -> Result<Vec<SigningMessage>, WedprError> {
+        if !self.dealt_shares.contains_key(&self.index) {
+            wedpr_println!("Signing has not started");
+            return Err(WedprError::ArgumentError);
+        }
+        if message.recipient != self.index
+            || message.sender == self.index
+            || !self.signers.contains(&message.sender)
+        {
+            wedpr_println!("Unexpected signing message");
+            return Err(WedprError::ArgumentError);
+        }
+        let is_duplicate = match &message.payload {
+            SigningPayload::Shares { .. } => {
+                self.dealt_shares.contains_key(&message.sender)
+            },
+            SigningPayload::ProductShare(_) => {
+                self.product_shares.contains_key(&message.sender)
+            },
+            SigningPayload::SignatureShare(_) => {
+                self.signature_shares.contains_key(&message.sender)
+            },
+        };
+        if is_duplicate {
+            wedpr_println!("Duplicate signing message");
+            return Err(WedprError::ArgumentError);
+        }
+        match &message.payload {
+            SigningPayload::Shares {
+                nonce_commitments,
+                nonce_share,
+                mask_share,
+                product_zero_share,
+                signature_zero_share,
+            } => {
+                if nonce_commitments.len() != self.threshold + 1 {
+                    return Err(WedprError::FormatError);
+                }
+                let commitments = nonce_commitments
+                    .iter()
+                    .map(|v| parse_point(v))
+                    .collect::<Result<Vec<PublicKey>, WedprError>>()?;
+                let nonce = bytes_to_scalar(nonce_share)?;
+                if !verify_feldman_share(
+                    &commitments,
+                    false,
+                    self.index,
+                    &nonce,
+                ) {
+                    wedpr_println!(
+                        "Nonce share from party {} is invalid",
+                        message.sender
+                    );
+                    return Err(WedprError::VerificationError);
+                }
+                self.dealt_shares.insert(message.sender, DealtShares {
+                    nonce_commitment: commitments[0],
+                    nonce,
+                    mask: bytes_to_scalar(mask_share)?,
+                    product_zero: bytes_to_scalar(product_zero_share)?,
+                    signature_zero: bytes_to_scalar(signature_zero_share)?,
+                });
+            },
+            SigningPayload::ProductShare(share) => {
+                self.product_shares
+                    .insert(message.sender, bytes_to_scalar(share)?);
+            },
+            SigningPayload::SignatureShare(share) => {
+                self.signature_shares
+                    .insert(message.sender, bytes_to_scalar(share)?);
+            },
+        }
+        self.advance()
+    }
+
+    /// Gets the signature once signing has finished.
+    pub fn signature(&self) -> Option<Vec<u8>> {
+        self.signature.clone()
+    }
+
+    /// Broadcasts a payload to other signers.
+    fn broadcast(&self, payload: SigningPayload) -> Vec<SigningMessage> {
+        self.signers
+            .iter()
+            .filter(|recipient| **recipient != self.index)
+            .map(|recipient| SigningMessage {
+                sender: self.index,
+                recipient: *recipient,
+                payload: payload.clone(),
+            })
+            .collect()
+    }
+
+    /// Runs every round whose inputs are complete.
+    fn advance(&mut self) -> Result<Vec<SigningMessage>, WedprError> {
+        let field = &*SECP256K1_SCALAR_FIELD;
+        let signer_count = self.signers.len();
+        let mut messages = Vec::new();
+        if self.nonce_shares.is_none()
+            && self.dealt_shares.len() == signer_count
+        {
+            let shares: Vec<&DealtShares> =
+                self.dealt_shares.values().collect();
+            let nonce = sum_scalars(shares.iter().map(|v| &v.nonce));
+            let mask = sum_scalars(shares.iter().map(|v| &v.mask));
+            let product_zero =
+                sum_scalars(shares.iter().map(|v| &v.product_zero));
+            let signature_zero =
+                sum_scalars(shares.iter().map(|v| &v.signature_zero));
+            let nonce_commitments: Vec<PublicKey> =
+                shares.iter().map(|v| v.nonce_commitment).collect();
+            let nonce_point = match sum_points(&nonce_commitments) {
+                Some(v) => v,
+                None => return Err(WedprError::VerificationError),
+            };
+            let product = nonce.mul(&mask).add(&product_zero);
+            self.nonce_shares = Some(NonceShares {
+                nonce_point,
+                mask,
+                signature_zero,
+            });
+            messages.extend(self.broadcast(SigningPayload::ProductShare(
+                scalar_to_bytes(&product),
+            )));
+            self.product_shares.insert(self.index, product);
+        }
+        let nonce_shares = match &self.nonce_shares {
+            Some(v) => v.clone(),
+            None => return Ok(messages),
+        };
+        if self.product_shares.len() == signer_count
+            && !self.signature_shares.contains_key(&self.index)
+        {
+            let product =
+                interpolate_at_zero(&to_points(&self.product_shares))?;
+            // The product is invertible unless it is zero.
+            let product_inverse = match product.inverse() {
+                Ok(v) => v,
+                Err(_) => return Err(WedprError::VerificationError),
+            };
+            let nonce_inverse = nonce_shares.mask.mul(&product_inverse);
+            let r = field
+                .decode_reduced(&nonce_point_x(&nonce_shares.nonce_point))?;
+            let e = field.decode_reduced(&self.msg_hash)?;
+            let signature_share = nonce_inverse
+                .mul(&e.add(&r.mul(&self.secret_share)))
+                .add(&nonce_shares.signature_zero);
+            messages.extend(self.broadcast(SigningPayload::SignatureShare(
+                scalar_to_bytes(&signature_share),
+            )));
+            self.signature_shares.insert(self.index, signature_share);
+        }
+        if self.signature.is_none()
+            && self.signature_shares.len() == signer_count
+        {
+            self.signature = Some(self.combine(&nonce_shares.nonce_point)?);
+        }
+        Ok(messages)
+    }
+
+    /// Combines the signature shares into a verified signature.
+    fn combine(&self, nonce_point: &PublicKey) -> Result<Vec<u8>, WedprError> {
+        let nonce_point_x = nonce_point_x(nonce_point);
+        let r = SECP256K1_SCALAR_FIELD.decode_reduced(&nonce_point_x)?;
+        let s = interpolate_at_zero(&to_points(&self.signature_shares))?;
+        let mut recovery_id = nonce_point.serialize()[0] - 0x02;
+        // Both are 32-byte big-endian integers, whose byte order is their
+        // numeric order.
+        if nonce_point_x[..] >= CURVE_ORDER[..] {
+            recovery_id |= 2;
+        }
+        let mut compact_signature = scalar_to_bytes(&r);
+        compact_signature.extend_from_slice(&scalar_to_bytes(&s));
+        let signature_obj =
+            match Secp256k1Signature::from_compact(&compact_signature) {
+                Ok(v) => v,
+                Err(_) => return Err(WedprError::VerificationError),
+            };
+        // Normalizes s to the lower half, which negates the nonce point.
+        let mut normalized_signature = signature_obj;
+        normalized_signature.normalize_s();
+        if normalized_signature != signature_obj {
+            recovery_id ^= 1;
+        }
+        let verified = match Message::from_slice(&self.msg_hash) {
+            Ok(message) => SECP256K1_ALL
+                .verify(&message, &normalized_signature, &self.public_key)
+                .is_ok(),
+            Err(_) => false,
+        };
+        if !verified {
+            wedpr_println!("Combined threshold signature is invalid");
+            return Err(WedprError::VerificationError);
+        }
+        let mut signature = normalized_signature.serialize_compact().to_vec();
+        signature.push(recovery_id);
+        Ok(signature)
+    }
+}
+
+/// Generates random coefficients of a polynomial of the degree.
+fn random_polynomial(degree: usize, zero_constant: bool) -> Vec<Scalar> {
+    (0..=degree)
+        .map(|i| {
+            if i == 0 && zero_constant {
+                scalar_from_u64(0)
+            } else {
+                random_scalar()
+            }
+        })
+        .collect()
+}
+
+fn sum_scalars<'a, I: Iterator<Item = &'a Scalar>>(values: I) -> Scalar {
+    values.fold(scalar_from_u64(0), |sum, v| sum.add(v))
+}
+
+fn to_points(shares: &BTreeMap<usize, Scalar>) -> Vec<(usize, Scalar)> {
+    shares
+        .iter()
+        .map(|(index, value)| (*index, value.clone()))
+        .collect()
+}
+
+fn nonce_point_x(nonce_point: &PublicKey) -> Vec<u8> {
+    nonce_point.serialize()[1..].to_vec()
+}
+
+/// Runs threshold signing among local signers, for testing and for
+/// simulating deployments, where messages are delivered in order.
+pub fn run_local_signing(
+    key_shares: &[KeyShare],
+    msg_hash: &[u8],
+) -> Result<Vec<u8>, WedprError> {
+    let signers: Vec<usize> = key_shares.iter().map(|v| v.index).collect();
+    let mut parties = key_shares
+        .iter()
+        .map(|v| SigningParty::new(v, &signers, msg_hash))
+        .collect::<Result<Vec<SigningParty>, WedprError>>()?;
+    let mut queue = VecDeque::new();
+    for party in parties.iter_mut() {
+        queue.extend(party.start()?);
+    }
+    while let Some(message) = queue.pop_front() {
+        let party =
+            match parties.iter_mut().find(|v| v.index == message.recipient) {
+                Some(v) => v,
+                None => return Err(WedprError::ArgumentError),
+            };
+        queue.extend(party.handle(&message)?);
+    }
+    match parties[0].signature() {
+        Some(v) => Ok(v),
+        None => Err(WedprError::VerificationError),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keygen::run_local_keygen;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+    use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+    use wedpr_l_utils::traits::{Hash, Signature};
+
+    #[test]
+    fn test_threshold_signing() {
+        let key_shares = run_local_keygen(1, 4).unwrap();
+        let msg_hash = WedprKeccak256::default().hash("transfer 100 tokens");
+        let secp256k1 = WedprSecp256k1Recover::default();
+
+        // Any 3 of the 4 parties can sign.
+        let signers = [
+            key_shares[0].clone(),
+            key_shares[1].clone(),
+            key_shares[3].clone(),
+        ];
+        let signature = run_local_signing(&signers, &msg_hash).unwrap();
+        assert!(secp256k1.verify(
+            &key_shares[0].public_key,
+            &msg_hash,
+            &signature
+        ));
+        let signature = run_local_signing(&key_shares[1..], &msg_hash).unwrap();
+        assert!(secp256k1.verify(
+            &key_shares[2].public_key,
+            &msg_hash,
+            &signature
+        ));
+        assert!(run_local_signing(&key_shares[..2], &msg_hash).is_err());
+
+        // Messages of a signer can be handled in any order.
+        let indexes = [1, 2, 3];
+        let mut parties: Vec<SigningParty> = key_shares[..3]
+            .iter()
+            .map(|v| SigningParty::new(v, &indexes, &msg_hash).unwrap())
+            .collect();
+        let mut pending = Vec::new();
+        for party in parties.iter_mut() {
+            pending.extend(party.start().unwrap());
+        }
+        assert!(parties[0].start().is_err());
+        while let Some(message) = pending.pop() {
+            let outgoing =
+                parties[message.recipient - 1].handle(&message).unwrap();
+            pending.extend(outgoing);
+        }
+        let signature = parties[2].signature().unwrap();
+        assert_eq!(parties[0].signature().unwrap(), signature);
+        assert!(secp256k1.verify(
+            &key_shares[0].public_key,
+            &msg_hash,
+            &signature
+        ));
+    }
+}
@@ -0,0 +1,260 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Benchmark functions exporting a machine-readable report of operations per
+//! second for each primitive and thread count, which can be used by
+//! deployment tooling to size hardware for a target TPS.
+
+use std::{sync::Arc, thread, time::Instant};
+use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+use wedpr_l_crypto_hash_sha256::WedprSha256;
+use wedpr_l_crypto_hash_sm3::WedprSm3;
+use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+use wedpr_l_crypto_signature_sm2::WedprSm2p256v1;
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{Hash, Signature},
+};
+
+/// Size of the messages hashed in benchmarks.
+const BENCHMARK_MESSAGE_SIZE_IN_BYTES: usize = 256;
+
+type Operation = Arc<dyn Fn() + Send + Sync>;
+
+/// Configuration of a benchmark run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchmarkConfig {
+    /// Thread counts to measure each primitive with.
+    pub thread_counts: Vec<usize>,
+    /// Operations executed by each thread for each measurement.
+    pub iterations_per_thread: usize,
+}
+
+impl Default for BenchmarkConfig {
+    fn default() -> Self {
+        BenchmarkConfig {
+            thread_counts: vec![1, 2, 4, 8],
+            iterations_per_thread: 1000,
+        }
+    }
+}
+
+/// Measurement of a primitive with a thread count.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkResult {
+    pub primitive: &'static str,
+    pub thread_count: usize,
+    pub total_operations: u64,
+    pub elapsed_seconds: f64,
+    pub ops_per_second: f64,
+}
+
+/// Report of all measurements in a benchmark run.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct BenchmarkReport {
+    pub results: Vec<BenchmarkResult>,
+}
+
+impl BenchmarkReport {
+    /// Gets the measured operations per second of a primitive with a thread
+    /// count.
+    pub fn ops_per_second(
+        &self,
+        primitive: &str,
+        thread_count: usize,
+    ) -> Option<f64> {
+        self.results
+            .iter()
+            .find(|result| {
+                result.primitive == primitive
+                    && result.thread_count == thread_count
+            })
+            .map(|result| result.ops_per_second)
+    }
+
+    /// Encodes the report to a JSON string.
+    pub fn to_json(&self) -> String {
+        let results: Vec<String> = self
+            .results
+            .iter()
+            .map(|result| {
+                format!(
+                    concat!(
+                        "{{\"primitive\":\"{}\",\"thread_count\":{},",
+                        "\"total_operations\":{},\"elapsed_seconds\":{:.6},",
+                        "\"ops_per_second\":{:.2}}}"
+                    ),
+                    result.primitive,
+                    result.thread_count,
+                    result.total_operations,
+                    result.elapsed_seconds,
+                    result.ops_per_second
+                )
+            })
+            .collect();
+        format!("{{\"results\":[{}]}}", results.join(","))
+    }
+}
+
+/// Runs an operation on multiple threads and measures its throughput.
+fn measure(
+    primitive: &'static str,
+    operation: &Operation,
+    thread_count: usize,
+    iterations_per_thread: usize,
+) -> BenchmarkResult {
+    let start = Instant::now();
+    let handles: Vec<thread::JoinHandle<()>> = (0..thread_count)
+        .map(|_| {
+            let operation = operation.clone();
+            thread::spawn(move || {
+                for _ in 0..iterations_per_thread {
+                    (*operation)();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().expect("Benchmark thread should not panic");
+    }
+    let elapsed_seconds = start.elapsed().as_secs_f64();
+    let total_operations = (thread_count * iterations_per_thread) as u64;
+    BenchmarkResult {
+        primitive,
+        thread_count,
+        total_operations,
+        elapsed_seconds,
+        ops_per_second: if elapsed_seconds > 0.0 {
+            total_operations as f64 / elapsed_seconds
+        } else {
+            0.0
+        },
+    }
+}
+
+/// Creates the benchmarked operations of all primitives.
+fn benchmark_operations() -> Vec<(&'static str, Operation)> {
+    let message = vec![0x5au8; BENCHMARK_MESSAGE_SIZE_IN_BYTES];
+    let msg_hash = WedprKeccak256::default().hash(&message);
+
+    let secp256k1 = WedprSecp256k1Recover::default();
+    let (secp256k1_public_key, secp256k1_private_key) =
+        secp256k1.generate_keypair();
+    let secp256k1_signature = secp256k1
+        .sign(&secp256k1_private_key, &msg_hash)
+        .expect("Benchmark signing should not fail");
+    let sm2 = WedprSm2p256v1::default();
+    let (sm2_public_key, sm2_private_key) = sm2.generate_keypair();
+    let sm2_signature = sm2
+        .sign(&sm2_private_key, &msg_hash)
+        .expect("Benchmark signing should not fail");
+
+    let keccak256_message = message.clone();
+    let sha256_message = message.clone();
+    let sm3_message = message;
+    let secp256k1_sign_hash = msg_hash.clone();
+    let secp256k1_verify_hash = msg_hash.clone();
+    let sm2_sign_hash = msg_hash.clone();
+    let sm2_verify_hash = msg_hash;
+    vec![
+        (
+            "keccak256",
+            Arc::new(move || {
+                WedprKeccak256::default().hash(&keccak256_message);
+            }) as Operation,
+        ),
+        (
+            "sha256",
+            Arc::new(move || {
+                WedprSha256::default().hash(&sha256_message);
+            }) as Operation,
+        ),
+        (
+            "sm3",
+            Arc::new(move || {
+                WedprSm3::default().hash(&sm3_message);
+            }) as Operation,
+        ),
+        (
+            "secp256k1_sign",
+            Arc::new(move || {
+                let _ = secp256k1
+                    .sign(&secp256k1_private_key, &secp256k1_sign_hash);
+            }) as Operation,
+        ),
+        (
+            "secp256k1_verify",
+            Arc::new(move || {
+                secp256k1.verify(
+                    &secp256k1_public_key,
+                    &secp256k1_verify_hash,
+                    &secp256k1_signature,
+                );
+            }) as Operation,
+        ),
+        (
+            "sm2_sign",
+            Arc::new(move || {
+                let _ = WedprSm2p256v1::default()
+                    .sign(&sm2_private_key, &sm2_sign_hash);
+            }) as Operation,
+        ),
+        (
+            "sm2_verify",
+            Arc::new(move || {
+                sm2.verify(&sm2_public_key, &sm2_verify_hash, &sm2_signature);
+            }) as Operation,
+        ),
+    ]
+}
+
+/// Runs benchmarks of all primitives with all configured thread counts.
+pub fn run_benchmarks(
+    config: &BenchmarkConfig,
+) -> Result<BenchmarkReport, WedprError> {
+    if config.thread_counts.is_empty()
+        || config.thread_counts.contains(&0)
+        || config.iterations_per_thread == 0
+    {
+        return Err(WedprError::ArgumentError);
+    }
+    let mut report = BenchmarkReport::default();
+    for (primitive, operation) in benchmark_operations() {
+        for thread_count in &config.thread_counts {
+            report.results.push(measure(
+                primitive,
+                &operation,
+                *thread_count,
+                config.iterations_per_thread,
+            ));
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_benchmarks() {
+        let config = BenchmarkConfig {
+            thread_counts: vec![1, 2],
+            iterations_per_thread: 2,
+        };
+        let report = run_benchmarks(&config).unwrap();
+        assert_eq!(report.results.len(), 7 * 2);
+        assert!(report.ops_per_second("secp256k1_verify", 2).unwrap() > 0.0);
+        assert!(report.ops_per_second("secp256k1_verify", 3).is_none());
+
+        let json = report.to_json();
+        assert!(json.starts_with("{\"results\":[{\"primitive\":\"keccak256\""));
+        assert!(json.contains("\"thread_count\":2"));
+        assert!(json.ends_with("}]}"));
+
+        let invalid_config = BenchmarkConfig {
+            thread_counts: vec![0],
+            iterations_per_thread: 2,
+        };
+        assert!(run_benchmarks(&invalid_config).is_err());
+    }
+}
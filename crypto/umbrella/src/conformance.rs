@@ -0,0 +1,375 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Conformance vectors exporting a machine-readable bundle of keys, messages
+//! and outputs of each algorithm, for SDKs in other languages to check their
+//! compatibility with this library on every release.
+//!
+//! Keys are derived from fixed seeds, so that deterministic outputs, e.g.
+//! hashes and most signatures, are byte-exact across runs. Randomized
+//! outputs, e.g. SM2 signatures and ECIES ciphertexts, are marked as not
+//! deterministic, and should be verified or decrypted instead of compared.
+
+use crate::hex_api::encode_hex;
+use wedpr_l_crypto_ecies_secp256k1::WedprSecp256k1Ecies;
+use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+use wedpr_l_crypto_hash_sha256::WedprSha256;
+use wedpr_l_crypto_hash_sm3::WedprSm3;
+use wedpr_l_crypto_signature_bls::WedprBls12381;
+use wedpr_l_crypto_signature_ed25519::WedprEd25519;
+use wedpr_l_crypto_signature_schnorr::WedprSchnorr;
+use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+use wedpr_l_crypto_signature_secp256r1::WedprSecp256r1;
+use wedpr_l_crypto_signature_sm2::WedprSm2p256v1;
+use wedpr_l_crypto_vrf_curve25519::{WedprCurve25519Vrf, WedprEd25519Vrf};
+use wedpr_l_crypto_vrf_secp256k1::WedprSecp256k1Vrf;
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{AlgorithmInfo, Ecies, Hash, Signature, Vrf},
+};
+
+/// Messages of hash vectors.
+const CONFORMANCE_HASH_MESSAGES: [&[u8]; 3] =
+    [b"", b"abc", b"WeDPR conformance message"];
+/// Message of signature, encryption and VRF vectors.
+const CONFORMANCE_MESSAGE: &[u8] = b"WeDPR conformance message";
+
+/// Vector of an operation of an algorithm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceVector {
+    /// Algorithm name as in AlgorithmMetadata.
+    pub algorithm: &'static str,
+    /// Operation name, i.e. "hash", "sign", "encrypt" or "vrf".
+    pub operation: &'static str,
+    /// Whether all outputs are byte-exact, otherwise randomized outputs
+    /// should only be verified or decrypted.
+    pub deterministic: bool,
+    /// Named input and output fields.
+    pub fields: Vec<(&'static str, Vec<u8>)>,
+}
+
+impl ConformanceVector {
+    /// Gets a field by name.
+    pub fn field(&self, name: &str) -> Option<&[u8]> {
+        self.fields
+            .iter()
+            .find(|(field_name, _)| *field_name == name)
+            .map(|(_, value)| &value[..])
+    }
+}
+
+/// Bundle of conformance vectors of all algorithms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceBundle {
+    pub library_version: &'static str,
+    pub vectors: Vec<ConformanceVector>,
+}
+
+impl ConformanceBundle {
+    /// Encodes the bundle to a JSON string, where all field values are hex
+    /// strings.
+    pub fn to_json(&self) -> String {
+        let vectors: Vec<String> = self
+            .vectors
+            .iter()
+            .map(|vector| {
+                let fields: Vec<String> = vector
+                    .fields
+                    .iter()
+                    .map(|(name, value)| {
+                        format!("\"{}\":\"{}\"", name, encode_hex(value))
+                    })
+                    .collect();
+                format!(
+                    concat!(
+                        "{{\"algorithm\":\"{}\",\"operation\":\"{}\",",
+                        "\"deterministic\":{},\"fields\":{{{}}}}}"
+                    ),
+                    vector.algorithm,
+                    vector.operation,
+                    vector.deterministic,
+                    fields.join(",")
+                )
+            })
+            .collect();
+        format!(
+            "{{\"library_version\":\"{}\",\"vectors\":[{}]}}",
+            self.library_version,
+            vectors.join(",")
+        )
+    }
+}
+
+/// Derives a fixed private key of an algorithm, whose top two bits are
+/// cleared to stay below the group orders of all supported curves.
+fn seeded_private_key(algorithm: &str) -> Vec<u8> {
+    let mut private_key = WedprSha256::default()
+        .hash(&format!("WeDPR conformance key of {}", algorithm));
+    private_key[0] &= 0x3f;
+    private_key
+}
+
+fn hash_vectors<H: Hash + AlgorithmInfo>(hash: &H) -> Vec<ConformanceVector> {
+    CONFORMANCE_HASH_MESSAGES
+        .iter()
+        .map(|message| ConformanceVector {
+            algorithm: H::algorithm_info().name,
+            operation: "hash",
+            deterministic: true,
+            fields: vec![
+                ("message", message.to_vec()),
+                ("digest", hash.hash(message)),
+            ],
+        })
+        .collect()
+}
+
+fn signature_vector<S: Signature + AlgorithmInfo>(
+    signature: &S,
+    private_key: Vec<u8>,
+    public_key: Vec<u8>,
+    msg_hash: Vec<u8>,
+    deterministic: bool,
+) -> Result<ConformanceVector, WedprError> {
+    let signature_bytes = if deterministic {
+        signature.sign_deterministic(&private_key, &msg_hash)?
+    } else {
+        signature.sign(&private_key, &msg_hash)?
+    };
+    Ok(ConformanceVector {
+        algorithm: S::algorithm_info().name,
+        operation: "sign",
+        deterministic,
+        fields: vec![
+            ("private_key", private_key),
+            ("public_key", public_key),
+            ("msg_hash", msg_hash),
+            ("signature", signature_bytes),
+        ],
+    })
+}
+
+/// Generates the conformance bundle of all algorithms.
+pub fn generate_conformance_bundle() -> Result<ConformanceBundle, WedprError> {
+    let mut vectors = Vec::new();
+    vectors.append(&mut hash_vectors(&WedprKeccak256::default()));
+    vectors.append(&mut hash_vectors(&WedprSha256::default()));
+    vectors.append(&mut hash_vectors(&WedprSm3::default()));
+
+    let keccak256_hash = WedprKeccak256::default().hash(CONFORMANCE_MESSAGE);
+    let sha256_hash = WedprSha256::default().hash(CONFORMANCE_MESSAGE);
+    let sm3_hash = WedprSm3::default().hash(CONFORMANCE_MESSAGE);
+
+    // Public keys of secp256k1 and SM2 are recovered from signatures.
+    let secp256k1 = WedprSecp256k1Recover::default();
+    let secp256k1_private_key = seeded_private_key("secp256k1");
+    let secp256k1_signature =
+        secp256k1.sign(&secp256k1_private_key, &keccak256_hash)?;
+    let secp256k1_public_key =
+        secp256k1.recover_public_key(&keccak256_hash, &secp256k1_signature)?;
+    vectors.push(signature_vector(
+        &secp256k1,
+        secp256k1_private_key.clone(),
+        secp256k1_public_key.clone(),
+        keccak256_hash.clone(),
+        true,
+    )?);
+
+    let sm2 = WedprSm2p256v1::default();
+    let sm2_private_key = seeded_private_key("sm2");
+    let sm2_signature = sm2.sign_recoverable(&sm2_private_key, &sm3_hash)?;
+    let sm2_public_key = sm2.recover_public_key(&sm3_hash, &sm2_signature)?;
+    vectors.push(signature_vector(
+        &sm2,
+        sm2_private_key,
+        sm2_public_key,
+        sm3_hash,
+        false,
+    )?);
+
+    let secp256r1 = WedprSecp256r1::default();
+    let secp256r1_private_key = seeded_private_key("secp256r1");
+    vectors.push(signature_vector(
+        &secp256r1,
+        secp256r1_private_key.clone(),
+        secp256r1.derive_public_key(&secp256r1_private_key)?,
+        sha256_hash.clone(),
+        true,
+    )?);
+
+    let schnorr = WedprSchnorr::default();
+    let schnorr_private_key = seeded_private_key("schnorr");
+    vectors.push(signature_vector(
+        &schnorr,
+        schnorr_private_key.clone(),
+        schnorr.derive_public_key(&schnorr_private_key)?,
+        sha256_hash.clone(),
+        true,
+    )?);
+
+    let bls = WedprBls12381::default();
+    let bls_private_key = seeded_private_key("bls12381");
+    vectors.push(signature_vector(
+        &bls,
+        bls_private_key.clone(),
+        bls.derive_public_key(&bls_private_key)?,
+        sha256_hash,
+        true,
+    )?);
+
+    // Ed25519 signs the message itself.
+    let ed25519 = WedprEd25519::default();
+    let ed25519_private_key = seeded_private_key("ed25519");
+    vectors.push(signature_vector(
+        &ed25519,
+        ed25519_private_key.clone(),
+        ed25519.derive_public_key(&ed25519_private_key)?,
+        CONFORMANCE_MESSAGE.to_vec(),
+        true,
+    )?);
+
+    let ecies = WedprSecp256k1Ecies::default();
+    vectors.push(ConformanceVector {
+        algorithm: WedprSecp256k1Ecies::algorithm_info().name,
+        operation: "encrypt",
+        deterministic: false,
+        fields: vec![
+            (
+                "ciphertext",
+                ecies
+                    .encrypt(&secp256k1_public_key[..], CONFORMANCE_MESSAGE)?,
+            ),
+            ("private_key", secp256k1_private_key),
+            ("public_key", secp256k1_public_key),
+            ("message", CONFORMANCE_MESSAGE.to_vec()),
+        ],
+    });
+
+    // VRF proofs are randomized, but their hashes are deterministic.
+    let vrf_private_key = seeded_private_key("curve25519_vrf");
+    let proof =
+        WedprCurve25519Vrf::prove(&vrf_private_key[..], CONFORMANCE_MESSAGE)?;
+    vectors.push(ConformanceVector {
+        algorithm: WedprCurve25519Vrf::algorithm_info().name,
+        operation: "vrf",
+        deterministic: false,
+        fields: vec![
+            (
+                "public_key",
+                WedprCurve25519Vrf::derive_public_key(&vrf_private_key),
+            ),
+            ("private_key", vrf_private_key),
+            ("message", CONFORMANCE_MESSAGE.to_vec()),
+            ("proof", proof.encode_proof()),
+            ("output", proof.proof_to_hash()?),
+        ],
+    });
+
+    // RFC 9381 VRF proofs use deterministic nonces.
+    let vrf_private_key = seeded_private_key("ed25519_vrf");
+    let proof =
+        WedprEd25519Vrf::prove(&vrf_private_key[..], CONFORMANCE_MESSAGE)?;
+    vectors.push(ConformanceVector {
+        algorithm: WedprEd25519Vrf::algorithm_info().name,
+        operation: "vrf",
+        deterministic: true,
+        fields: vec![
+            (
+                "public_key",
+                WedprEd25519Vrf::derive_public_key(&vrf_private_key),
+            ),
+            ("private_key", vrf_private_key),
+            ("message", CONFORMANCE_MESSAGE.to_vec()),
+            ("proof", proof.encode_proof()),
+            ("output", proof.proof_to_hash()?),
+        ],
+    });
+
+    // Secp256k1 VRF proofs use deterministic nonces.
+    let vrf_private_key = seeded_private_key("secp256k1_vrf");
+    let proof =
+        WedprSecp256k1Vrf::prove(&vrf_private_key[..], CONFORMANCE_MESSAGE)?;
+    vectors.push(ConformanceVector {
+        algorithm: WedprSecp256k1Vrf::algorithm_info().name,
+        operation: "vrf",
+        deterministic: true,
+        fields: vec![
+            (
+                "public_key",
+                WedprSecp256k1Vrf::derive_public_key(&vrf_private_key),
+            ),
+            ("private_key", vrf_private_key),
+            ("message", CONFORMANCE_MESSAGE.to_vec()),
+            ("proof", proof.encode_proof()),
+            ("output", proof.proof_to_hash()?),
+        ],
+    });
+
+    Ok(ConformanceBundle {
+        library_version: env!("CARGO_PKG_VERSION"),
+        vectors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conformance_bundle() {
+        let bundle = generate_conformance_bundle().unwrap();
+        let another_bundle = generate_conformance_bundle().unwrap();
+        assert_eq!(bundle.vectors.len(), another_bundle.vectors.len());
+        for (vector, another_vector) in
+            bundle.vectors.iter().zip(&another_bundle.vectors)
+        {
+            if vector.deterministic {
+                assert_eq!(vector, another_vector);
+            }
+        }
+
+        let secp256k1_vector = bundle
+            .vectors
+            .iter()
+            .find(|v| v.algorithm == "secp256k1" && v.operation == "sign")
+            .unwrap();
+        assert!(WedprSecp256k1Recover::default().verify(
+            secp256k1_vector.field("public_key").unwrap(),
+            secp256k1_vector.field("msg_hash").unwrap(),
+            secp256k1_vector.field("signature").unwrap()
+        ));
+        let sm2_vector = bundle
+            .vectors
+            .iter()
+            .find(|v| v.operation == "sign" && !v.deterministic)
+            .unwrap();
+        let sm2 = WedprSm2p256v1::default();
+        assert!(sm2.verify(
+            sm2_vector.field("public_key").unwrap(),
+            sm2_vector.field("msg_hash").unwrap(),
+            sm2_vector.field("signature").unwrap()
+        ));
+        let ecies_vector = bundle
+            .vectors
+            .iter()
+            .find(|v| v.operation == "encrypt")
+            .unwrap();
+        assert_eq!(
+            WedprSecp256k1Ecies::default()
+                .decrypt(
+                    ecies_vector.field("private_key").unwrap(),
+                    ecies_vector.field("ciphertext").unwrap()
+                )
+                .unwrap(),
+            CONFORMANCE_MESSAGE
+        );
+
+        let json = bundle.to_json();
+        assert!(json.starts_with("{\"library_version\":\"1.3.0\""));
+        // Digest of "abc" by SHA-256.
+        assert!(json.contains(concat!(
+            "\"message\":\"616263\",\"digest\":",
+            "\"ba7816bf8f01cfea414140de5dae2223",
+            "b00361a396177a9cb410ff61f20015ad\""
+        )));
+    }
+}
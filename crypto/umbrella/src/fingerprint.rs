@@ -0,0 +1,98 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Algorithm-tagged fingerprints of public keys, for referencing keys of any
+//! algorithm consistently in logs, UIs and approval workflows.
+//!
+//! A fingerprint is "<algorithm name>:<multibase digest>", where the digest
+//! is SHA-256 over a domain separator, the algorithm name and the public key,
+//! and is encoded as multibase base32, i.e. "b" followed by lowercase RFC
+//! 4648 base32 without padding. Keys with several encodings, e.g. compressed
+//! and uncompressed secp256k1 keys, have different fingerprints, so callers
+//! should fingerprint one canonical form.
+
+use wedpr_l_crypto_hash_sha256::WedprSha256;
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{AlgorithmInfo, Hash},
+};
+
+/// Domain separator of public key fingerprints.
+const FINGERPRINT_DOMAIN: &[u8] = b"WeDPR_KEY_FINGERPRINT";
+/// Multibase prefix of lowercase base32 without padding.
+const MULTIBASE_BASE32_PREFIX: char = 'b';
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// Computes the fingerprint of a public key of algorithm A, which fails if
+/// the public key size does not match the algorithm.
+pub fn fingerprint<A: AlgorithmInfo>(
+    public_key: &[u8],
+) -> Result<String, WedprError> {
+    let metadata = A::algorithm_info();
+    metadata.check_public_key(public_key)?;
+    let mut hash_input = FINGERPRINT_DOMAIN.to_vec();
+    hash_input.push(metadata.name.len() as u8);
+    hash_input.extend_from_slice(metadata.name.as_bytes());
+    hash_input.extend_from_slice(public_key);
+    let digest = WedprSha256::default().hash(&hash_input);
+    Ok(format!(
+        "{}:{}{}",
+        metadata.name,
+        MULTIBASE_BASE32_PREFIX,
+        encode_base32(&digest)
+    ))
+}
+
+/// Encodes bytes as lowercase base32 without padding.
+fn encode_base32(input: &[u8]) -> String {
+    let mut output = String::with_capacity((input.len() * 8 + 4) / 5);
+    let mut buffer = 0u32;
+    let mut buffered_bits = 0;
+    for byte in input {
+        buffer = (buffer << 8) | *byte as u32;
+        buffered_bits += 8;
+        while buffered_bits >= 5 {
+            buffered_bits -= 5;
+            let index = (buffer >> buffered_bits) & 0x1f;
+            output.push(BASE32_ALPHABET[index as usize] as char);
+        }
+    }
+    if buffered_bits > 0 {
+        let index = (buffer << (5 - buffered_bits)) & 0x1f;
+        output.push(BASE32_ALPHABET[index as usize] as char);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_signature_ed25519::WedprEd25519;
+    use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+    use wedpr_l_utils::traits::Signature;
+
+    #[test]
+    fn test_fingerprint() {
+        // Test vectors of RFC 4648.
+        assert_eq!(encode_base32(b""), "");
+        assert_eq!(encode_base32(b"f"), "my");
+        assert_eq!(encode_base32(b"foob"), "mzxw6yq");
+        assert_eq!(encode_base32(b"foobar"), "mzxw6ytboi");
+
+        let (public_key, _) = WedprEd25519::default().generate_keypair();
+        let result = fingerprint::<WedprEd25519>(&public_key).unwrap();
+        assert!(result.starts_with("ed25519:b"));
+        assert_eq!(result.len(), "ed25519:b".len() + 52);
+        assert_eq!(result, fingerprint::<WedprEd25519>(&public_key).unwrap());
+        assert!(fingerprint::<WedprEd25519>(&public_key[1..]).is_err());
+
+        // Same bytes under different algorithms have different digests.
+        let tagged = fingerprint::<WedprSecp256k1Recover>(
+            &WedprSecp256k1Recover::default().generate_keypair().0,
+        )
+        .unwrap();
+        assert!(tagged.starts_with(&format!(
+            "{}:b",
+            WedprSecp256k1Recover::algorithm_info().name
+        )));
+    }
+}
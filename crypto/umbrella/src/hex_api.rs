@@ -0,0 +1,175 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Hex-encoded convenience functions for FFI and scripting layers, where all
+//! keys, messages and outputs are hex strings with an optional "0x" prefix.
+
+use wedpr_l_common_coder_hex::WedprHex;
+use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+use wedpr_l_utils::{
+    error::WedprError,
+    traits::{BlockCipher, Coder, Ecies, Hash, Signature},
+};
+
+/// Decodes a hex string with an optional "0x" prefix.
+pub fn decode_hex(input: &str) -> Result<Vec<u8>, WedprError> {
+    let trimmed = input.trim();
+    let hex_str = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed);
+    WedprHex::default().decode(hex_str)
+}
+
+/// Encodes bytes to a lowercase hex string without prefix.
+pub fn encode_hex(input: &[u8]) -> String {
+    WedprHex::default().encode(input)
+}
+
+/// Signs a hex message hash with a hex private key.
+pub fn sign_hex<S: Signature>(
+    signature: &S,
+    private_key_hex: &str,
+    msg_hash_hex: &str,
+) -> Result<String, WedprError> {
+    let private_key = decode_hex(private_key_hex)?;
+    let msg_hash = decode_hex(msg_hash_hex)?;
+    Ok(encode_hex(&signature.sign(&private_key, &msg_hash)?))
+}
+
+/// Verifies a hex signature of a hex message hash with a hex public key,
+/// where any malformed hex input fails the verification.
+pub fn verify_hex<S: Signature>(
+    signature: &S,
+    public_key_hex: &str,
+    msg_hash_hex: &str,
+    signature_hex: &str,
+) -> bool {
+    let (public_key, msg_hash, signature_bytes) = match (
+        decode_hex(public_key_hex),
+        decode_hex(msg_hash_hex),
+        decode_hex(signature_hex),
+    ) {
+        (Ok(public_key), Ok(msg_hash), Ok(signature_bytes)) => {
+            (public_key, msg_hash, signature_bytes)
+        },
+        _ => return false,
+    };
+    signature.verify(&public_key, &msg_hash, &signature_bytes)
+}
+
+/// Recovers a hex public key from a hex secp256k1 signature of a hex message
+/// hash.
+pub fn recover_public_key_hex(
+    msg_hash_hex: &str,
+    signature_hex: &str,
+) -> Result<String, WedprError> {
+    let msg_hash = decode_hex(msg_hash_hex)?;
+    let signature_bytes = decode_hex(signature_hex)?;
+    let public_key = WedprSecp256k1Recover::default()
+        .recover_public_key(&msg_hash, &signature_bytes)?;
+    Ok(encode_hex(&public_key))
+}
+
+/// Hashes a hex message to a hex hash.
+pub fn hash_hex<H: Hash>(
+    hash: &H,
+    message_hex: &str,
+) -> Result<String, WedprError> {
+    Ok(encode_hex(&hash.hash(&decode_hex(message_hex)?)))
+}
+
+/// Encrypts a hex message by ECIES with a hex public key.
+pub fn ecies_encrypt_hex<E: Ecies>(
+    ecies: &E,
+    public_key_hex: &str,
+    message_hex: &str,
+) -> Result<String, WedprError> {
+    let public_key = decode_hex(public_key_hex)?;
+    let message = decode_hex(message_hex)?;
+    Ok(encode_hex(&ecies.encrypt(&public_key, &message)?))
+}
+
+/// Decrypts a hex ciphertext by ECIES with a hex private key.
+pub fn ecies_decrypt_hex<E: Ecies>(
+    ecies: &E,
+    private_key_hex: &str,
+    ciphertext_hex: &str,
+) -> Result<String, WedprError> {
+    let private_key = decode_hex(private_key_hex)?;
+    let ciphertext = decode_hex(ciphertext_hex)?;
+    Ok(encode_hex(&ecies.decrypt(&private_key, &ciphertext)?))
+}
+
+/// Encrypts a hex message by a block cipher with a hex key and IV.
+pub fn block_cipher_encrypt_hex<B: BlockCipher>(
+    block_cipher: &B,
+    message_hex: &str,
+    key_hex: &str,
+    iv_hex: &str,
+) -> Result<String, WedprError> {
+    let message = decode_hex(message_hex)?;
+    let key = decode_hex(key_hex)?;
+    let iv = decode_hex(iv_hex)?;
+    Ok(encode_hex(&block_cipher.encrypt(&message, &key, &iv)?))
+}
+
+/// Decrypts a hex ciphertext by a block cipher with a hex key and IV.
+pub fn block_cipher_decrypt_hex<B: BlockCipher>(
+    block_cipher: &B,
+    ciphertext_hex: &str,
+    key_hex: &str,
+    iv_hex: &str,
+) -> Result<String, WedprError> {
+    let ciphertext = decode_hex(ciphertext_hex)?;
+    let key = decode_hex(key_hex)?;
+    let iv = decode_hex(iv_hex)?;
+    Ok(encode_hex(&block_cipher.decrypt(&ciphertext, &key, &iv)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_ecies_secp256k1::WedprSecp256k1Ecies;
+    use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+
+    #[test]
+    fn test_hex_api() {
+        let secp256k1 = WedprSecp256k1Recover::default();
+        let (public_key, private_key) = secp256k1.generate_keypair();
+        let public_key_hex = encode_hex(&public_key);
+        let private_key_hex = format!("0x{}", encode_hex(&private_key));
+
+        let keccak256 = WedprKeccak256::default();
+        let msg_hash_hex = hash_hex(&keccak256, "0x5c74d17c6a").unwrap();
+        assert_eq!(msg_hash_hex.len(), 64);
+
+        let signature_hex =
+            sign_hex(&secp256k1, &private_key_hex, &msg_hash_hex).unwrap();
+        assert!(verify_hex(
+            &secp256k1,
+            &public_key_hex,
+            &msg_hash_hex,
+            &signature_hex
+        ));
+        assert!(!verify_hex(
+            &secp256k1,
+            &public_key_hex,
+            &msg_hash_hex,
+            "not hex"
+        ));
+        assert_eq!(
+            recover_public_key_hex(&msg_hash_hex, &signature_hex).unwrap(),
+            public_key_hex
+        );
+        assert!(sign_hex(&secp256k1, "0xzz", &msg_hash_hex).is_err());
+
+        let ecies = WedprSecp256k1Ecies::default();
+        let ciphertext_hex =
+            ecies_encrypt_hex(&ecies, &public_key_hex, "5c74d17c6a").unwrap();
+        assert_eq!(
+            ecies_decrypt_hex(&ecies, &private_key_hex, &ciphertext_hex)
+                .unwrap(),
+            "5c74d17c6a"
+        );
+    }
+}
@@ -0,0 +1,28 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Library of WeDPR crypto functions, re-exporting the algorithm crates of
+//! this workspace under one path with consistent versions.
+
+pub use wedpr_l_common_coder_hex as coder_hex;
+pub use wedpr_l_crypto_block_cipher_aes as block_cipher_aes;
+pub use wedpr_l_crypto_block_cipher_chacha20 as block_cipher_chacha20;
+pub use wedpr_l_crypto_block_cipher_sm4 as block_cipher_sm4;
+pub use wedpr_l_crypto_ecies_secp256k1 as ecies_secp256k1;
+pub use wedpr_l_crypto_hash_keccak256 as hash_keccak256;
+pub use wedpr_l_crypto_hash_sha256 as hash_sha256;
+pub use wedpr_l_crypto_hash_sm3 as hash_sm3;
+pub use wedpr_l_crypto_signature_bls as signature_bls;
+pub use wedpr_l_crypto_signature_ed25519 as signature_ed25519;
+pub use wedpr_l_crypto_signature_schnorr as signature_schnorr;
+pub use wedpr_l_crypto_signature_secp256k1 as signature_secp256k1;
+pub use wedpr_l_crypto_signature_secp256r1 as signature_secp256r1;
+pub use wedpr_l_crypto_signature_sm2 as signature_sm2;
+pub use wedpr_l_crypto_vrf_curve25519 as vrf_curve25519;
+pub use wedpr_l_crypto_vrf_secp256k1 as vrf_secp256k1;
+pub use wedpr_l_utils as utils;
+
+pub mod benchmark;
+pub mod conformance;
+pub mod fingerprint;
+pub mod hex_api;
+pub mod prelude;
@@ -0,0 +1,85 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! WeDPR crypto prelude of common traits and types, which can be imported by
+//! `use wedpr_l_crypto::prelude::*;`.
+
+pub use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    digest::MessageDigest,
+    error::WedprError,
+    traits::{
+        Aead, AlgorithmInfo, BlockCipher, Ecies, Hash, HashStream,
+        HomomorphicEncryption, Kdf, Mac, Signature, StreamingHash, Vrf,
+    },
+};
+
+pub use wedpr_l_crypto_block_cipher_aes::WedprAes256Gcm;
+pub use wedpr_l_crypto_block_cipher_chacha20::WedprChaCha20Poly1305;
+pub use wedpr_l_crypto_block_cipher_sm4::{WedprSm4Cbc, WedprSm4Gcm};
+pub use wedpr_l_crypto_ecies_secp256k1::WedprSecp256k1Ecies;
+pub use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+pub use wedpr_l_crypto_hash_sha256::WedprSha256;
+pub use wedpr_l_crypto_hash_sm3::WedprSm3;
+pub use wedpr_l_crypto_signature_bls::WedprBls12381;
+pub use wedpr_l_crypto_signature_ed25519::WedprEd25519;
+pub use wedpr_l_crypto_signature_schnorr::WedprSchnorr;
+pub use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+pub use wedpr_l_crypto_signature_secp256r1::WedprSecp256r1;
+pub use wedpr_l_crypto_signature_sm2::{
+    encryption::{Sm2CiphertextOrder, WedprSm2Encryption},
+    WedprSm2p256v1,
+};
+pub use wedpr_l_crypto_vrf_curve25519::{WedprCurve25519Vrf, WedprEd25519Vrf};
+pub use wedpr_l_crypto_vrf_secp256k1::WedprSecp256k1Vrf;
+
+/// Signature algorithm compatible with FISCO-BCOS.
+pub type DefaultSignature = WedprSecp256k1Recover;
+/// Hash algorithm compatible with FISCO-BCOS.
+pub type DefaultHash = WedprKeccak256;
+/// Signature algorithm compatible with FISCO-BCOS GM version.
+pub type GmSignature = WedprSm2p256v1;
+/// Hash algorithm compatible with FISCO-BCOS GM version.
+pub type GmHash = WedprSm3;
+/// Authenticated block cipher algorithm of the GM cipher suite.
+pub type GmBlockCipher = WedprSm4Gcm;
+/// ECIES algorithm compatible with FISCO-BCOS.
+pub type DefaultEcies = WedprSecp256k1Ecies;
+/// VRF algorithm of WeDPR.
+pub type DefaultVrf = WedprCurve25519Vrf;
+/// Authenticated block cipher algorithm of WeDPR.
+pub type DefaultBlockCipher = WedprAes256Gcm;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prelude() {
+        let signature = DefaultSignature::default();
+        let digest = MessageDigest::new(DefaultHash::default());
+        let (public_key, private_key) = signature.generate_keypair();
+        let message = b"message";
+        let signature_bytes = signature
+            .sign_message(&private_key, message, &digest)
+            .unwrap();
+        assert!(signature.verify_message(
+            &public_key,
+            message,
+            &signature_bytes,
+            &digest
+        ));
+
+        let gm_signature = GmSignature::default();
+        let gm_digest = MessageDigest::new(GmHash::default());
+        let (public_key, private_key) = gm_signature.generate_keypair();
+        let signature_bytes = gm_signature
+            .sign_message(&private_key, message, &gm_digest)
+            .unwrap();
+        assert!(gm_signature.verify_message(
+            &public_key,
+            message,
+            &signature_bytes,
+            &gm_digest
+        ));
+    }
+}
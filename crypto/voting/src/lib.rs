@@ -0,0 +1,418 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Electronic voting functions, composed from exponential ElGamal
+//! encryption, zero-knowledge proofs and threshold decryption.
+//!
+//! A ballot encrypts a vote of 0 or 1 for each candidate as
+//! (r * G1, v * G1 + r * Y), with proofs that each vote is 0 or 1 and that
+//! the votes sum up to 1. Verified ballots are tallied homomorphically, and
+//! the tally is jointly decrypted by trustees with verifiable partial
+//! decryptions.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+pub mod threshold;
+
+use curve25519_dalek::{
+    ristretto::RistrettoPoint, scalar::Scalar, traits::MultiscalarMul,
+};
+use threshold::{
+    combine_partial_decryptions, partial_decrypt, PartialDecryption,
+    ThresholdElGamalPublicKey, TrusteeKeyShare,
+};
+use wedpr_l_crypto_zkp_shuffle_proof::elgamal::{encrypt, ElGamalCiphertext};
+use wedpr_l_crypto_zkp_utils::{
+    get_random_scalar, hash_to_scalar, point_to_bytes, BASEPOINT_G1,
+};
+use wedpr_l_utils::error::WedprError;
+
+/// Domain separator of vote validity proofs.
+const VOTE_PROOF_DOMAIN: &[u8] = b"WeDPR_VOTING_VOTE";
+/// Domain separator of ballot sum proofs.
+const BALLOT_SUM_PROOF_DOMAIN: &[u8] = b"WeDPR_VOTING_BALLOT_SUM";
+
+/// Proof of the equality of discrete logarithms, i.e. P1 = x * B1 and
+/// P2 = x * B2 for the same x.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct DleqProof {
+    pub challenge: Scalar,
+    pub response: Scalar,
+}
+
+/// Proof that a vote ciphertext encrypts 0 or 1, where the challenges of
+/// both branches sum up to the Fiat-Shamir challenge.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct VoteProof {
+    pub challenges: [Scalar; 2],
+    pub responses: [Scalar; 2],
+}
+
+/// Encrypted ballot with one vote ciphertext for each candidate.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Ballot {
+    pub votes: Vec<ElGamalCiphertext>,
+    pub vote_proofs: Vec<VoteProof>,
+    pub sum_proof: DleqProof,
+}
+
+fn dleq_challenge(
+    domain: &[u8],
+    points: &[&RistrettoPoint],
+    commitments: &[RistrettoPoint],
+) -> Scalar {
+    let mut hash_vec = domain.to_vec();
+    for point in points.iter().copied().chain(commitments) {
+        hash_vec.append(&mut point_to_bytes(point));
+    }
+    hash_to_scalar(&hash_vec)
+}
+
+/// Proves P1 = x * B1 and P2 = x * B2.
+pub(crate) fn prove_dleq(
+    domain: &[u8],
+    secret: &Scalar,
+    base1: &RistrettoPoint,
+    base2: &RistrettoPoint,
+    point1: &RistrettoPoint,
+    point2: &RistrettoPoint,
+) -> DleqProof {
+    let nonce = get_random_scalar();
+    let challenge = dleq_challenge(domain, &[base1, base2, point1, point2], &[
+        nonce * base1,
+        nonce * base2,
+    ]);
+    DleqProof {
+        challenge,
+        response: nonce + challenge * secret,
+    }
+}
+
+/// Verifies P1 = x * B1 and P2 = x * B2.
+pub(crate) fn verify_dleq(
+    domain: &[u8],
+    base1: &RistrettoPoint,
+    base2: &RistrettoPoint,
+    point1: &RistrettoPoint,
+    point2: &RistrettoPoint,
+    proof: &DleqProof,
+) -> bool {
+    let commitment1 = RistrettoPoint::multiscalar_mul(
+        &[proof.response, -proof.challenge],
+        &[*base1, *point1],
+    );
+    let commitment2 = RistrettoPoint::multiscalar_mul(
+        &[proof.response, -proof.challenge],
+        &[*base2, *point2],
+    );
+    dleq_challenge(domain, &[base1, base2, point1, point2], &[
+        commitment1,
+        commitment2,
+    ]) == proof.challenge
+}
+
+/// Gets the statement points (c1, c2 - v * G1) of a vote branch.
+fn vote_statement(
+    vote: &ElGamalCiphertext,
+    value: usize,
+) -> (RistrettoPoint, RistrettoPoint) {
+    (
+        vote.c1,
+        vote.c2 - Scalar::from(value as u64) * *BASEPOINT_G1,
+    )
+}
+
+/// Computes the nonce commitments z * (G1, Y) - e * statement of a branch.
+fn vote_branch_commitments(
+    public_key: &RistrettoPoint,
+    statement: &(RistrettoPoint, RistrettoPoint),
+    challenge: &Scalar,
+    response: &Scalar,
+) -> [RistrettoPoint; 2] {
+    [
+        RistrettoPoint::multiscalar_mul(&[*response, -challenge], &[
+            *BASEPOINT_G1,
+            statement.0,
+        ]),
+        RistrettoPoint::multiscalar_mul(&[*response, -challenge], &[
+            *public_key,
+            statement.1,
+        ]),
+    ]
+}
+
+fn vote_challenge(
+    public_key: &RistrettoPoint,
+    vote: &ElGamalCiphertext,
+    commitments: &[[RistrettoPoint; 2]; 2],
+) -> Scalar {
+    let mut hash_vec = VOTE_PROOF_DOMAIN.to_vec();
+    hash_vec.append(&mut point_to_bytes(public_key));
+    hash_vec.append(&mut point_to_bytes(&vote.c1));
+    hash_vec.append(&mut point_to_bytes(&vote.c2));
+    for point in commitments.iter().flatten() {
+        hash_vec.append(&mut point_to_bytes(point));
+    }
+    hash_to_scalar(&hash_vec)
+}
+
+fn prove_vote(
+    public_key: &RistrettoPoint,
+    vote: &ElGamalCiphertext,
+    value: usize,
+    blinding: &Scalar,
+) -> VoteProof {
+    let other_value = 1 - value;
+    let mut challenges = [Scalar::zero(); 2];
+    let mut responses = [Scalar::zero(); 2];
+    challenges[other_value] = get_random_scalar();
+    responses[other_value] = get_random_scalar();
+    let nonce = get_random_scalar();
+    let mut commitments = [[RistrettoPoint::default(); 2]; 2];
+    commitments[value] = [nonce * *BASEPOINT_G1, nonce * public_key];
+    commitments[other_value] = vote_branch_commitments(
+        public_key,
+        &vote_statement(vote, other_value),
+        &challenges[other_value],
+        &responses[other_value],
+    );
+    let challenge = vote_challenge(public_key, vote, &commitments);
+    challenges[value] = challenge - challenges[other_value];
+    responses[value] = nonce + challenges[value] * blinding;
+    VoteProof {
+        challenges,
+        responses,
+    }
+}
+
+fn verify_vote(
+    public_key: &RistrettoPoint,
+    vote: &ElGamalCiphertext,
+    proof: &VoteProof,
+) -> bool {
+    let mut commitments = [[RistrettoPoint::default(); 2]; 2];
+    for (value, commitment) in commitments.iter_mut().enumerate() {
+        *commitment = vote_branch_commitments(
+            public_key,
+            &vote_statement(vote, value),
+            &proof.challenges[value],
+            &proof.responses[value],
+        );
+    }
+    vote_challenge(public_key, vote, &commitments)
+        == proof.challenges[0] + proof.challenges[1]
+}
+
+fn sum_ciphertexts(ciphertexts: &[ElGamalCiphertext]) -> ElGamalCiphertext {
+    ciphertexts
+        .iter()
+        .fold(ElGamalCiphertext::default(), |sum, ciphertext| {
+            sum.add(ciphertext)
+        })
+}
+
+/// Encrypts a ballot choosing the candidate `choice` among
+/// `candidate_count` candidates.
+pub fn encrypt_ballot(
+    public_key: &RistrettoPoint,
+    choice: usize,
+    candidate_count: usize,
+) -> Result<Ballot, WedprError> {
+    if choice >= candidate_count {
+        wedpr_println!("Invalid choice {} of {}", choice, candidate_count);
+        return Err(WedprError::ArgumentError);
+    }
+    let mut votes = Vec::with_capacity(candidate_count);
+    let mut vote_proofs = Vec::with_capacity(candidate_count);
+    let mut blinding_sum = Scalar::zero();
+    for candidate in 0..candidate_count {
+        let value = (candidate == choice) as usize;
+        let blinding = get_random_scalar();
+        let vote = encrypt(
+            public_key,
+            &(Scalar::from(value as u64) * *BASEPOINT_G1),
+            &blinding,
+        );
+        vote_proofs.push(prove_vote(public_key, &vote, value, &blinding));
+        votes.push(vote);
+        blinding_sum += blinding;
+    }
+    let (sum_c1, sum_c2) = vote_statement(&sum_ciphertexts(&votes), 1);
+    let sum_proof = prove_dleq(
+        BALLOT_SUM_PROOF_DOMAIN,
+        &blinding_sum,
+        &BASEPOINT_G1,
+        public_key,
+        &sum_c1,
+        &sum_c2,
+    );
+    Ok(Ballot {
+        votes,
+        vote_proofs,
+        sum_proof,
+    })
+}
+
+/// Verifies that a ballot chooses exactly one of `candidate_count`
+/// candidates.
+pub fn verify_ballot(
+    public_key: &RistrettoPoint,
+    ballot: &Ballot,
+    candidate_count: usize,
+) -> bool {
+    if candidate_count == 0
+        || ballot.votes.len() != candidate_count
+        || ballot.vote_proofs.len() != candidate_count
+    {
+        return false;
+    }
+    if !ballot
+        .votes
+        .iter()
+        .zip(ballot.vote_proofs.iter())
+        .all(|(vote, proof)| verify_vote(public_key, vote, proof))
+    {
+        return false;
+    }
+    let (sum_c1, sum_c2) = vote_statement(&sum_ciphertexts(&ballot.votes), 1);
+    verify_dleq(
+        BALLOT_SUM_PROOF_DOMAIN,
+        &BASEPOINT_G1,
+        public_key,
+        &sum_c1,
+        &sum_c2,
+        &ballot.sum_proof,
+    )
+}
+
+/// Tallies ballots into one ciphertext for each candidate, rejecting any
+/// invalid ballot.
+pub fn tally_ballots(
+    public_key: &RistrettoPoint,
+    ballots: &[Ballot],
+    candidate_count: usize,
+) -> Result<Vec<ElGamalCiphertext>, WedprError> {
+    let mut tally = vec![ElGamalCiphertext::default(); candidate_count];
+    for ballot in ballots {
+        if !verify_ballot(public_key, ballot, candidate_count) {
+            wedpr_println!("Invalid ballot");
+            return Err(WedprError::VerificationError);
+        }
+        for (sum, vote) in tally.iter_mut().zip(ballot.votes.iter()) {
+            *sum = sum.add(vote);
+        }
+    }
+    Ok(tally)
+}
+
+/// Computes the partial decryptions of a tally by a trustee.
+pub fn partial_decrypt_tally(
+    key_share: &TrusteeKeyShare,
+    tally: &[ElGamalCiphertext],
+) -> Vec<PartialDecryption> {
+    tally
+        .iter()
+        .map(|ciphertext| partial_decrypt(key_share, ciphertext))
+        .collect()
+}
+
+/// Decrypts a tally from the partial decryptions of trustees, where
+/// `partial_decryptions[i]` holds the partial decryptions of all candidates
+/// by the i-th trustee. The count of each candidate is at most `max_votes`.
+pub fn decrypt_tally(
+    public_key: &ThresholdElGamalPublicKey,
+    tally: &[ElGamalCiphertext],
+    partial_decryptions: &[Vec<PartialDecryption>],
+    max_votes: u64,
+) -> Result<Vec<u64>, WedprError> {
+    let mut counts = Vec::with_capacity(tally.len());
+    for (candidate, ciphertext) in tally.iter().enumerate() {
+        let mut candidate_partials =
+            Vec::with_capacity(partial_decryptions.len());
+        for trustee_partials in partial_decryptions {
+            match trustee_partials.get(candidate) {
+                Some(v) => candidate_partials.push(v.clone()),
+                None => {
+                    wedpr_println!("Missing partial decryption");
+                    return Err(WedprError::ArgumentError);
+                },
+            }
+        }
+        let message = combine_partial_decryptions(
+            public_key,
+            ciphertext,
+            &candidate_partials,
+        )?;
+        counts.push(recover_count(&message, max_votes)?);
+    }
+    Ok(counts)
+}
+
+/// Recovers v from v * G1 for v in [0, max_votes].
+fn recover_count(
+    message: &RistrettoPoint,
+    max_votes: u64,
+) -> Result<u64, WedprError> {
+    let mut point = RistrettoPoint::default();
+    for count in 0..=max_votes {
+        if point == *message {
+            return Ok(count);
+        }
+        point += *BASEPOINT_G1;
+    }
+    wedpr_println!("Tally exceeds {} votes", max_votes);
+    Err(WedprError::DecodeError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use threshold::generate_threshold_keys;
+
+    #[test]
+    fn test_voting() {
+        let (public_key, shares) = generate_threshold_keys(2, 3).unwrap();
+        let candidate_count = 3;
+        let choices = [0, 2, 2, 1, 2];
+        let ballots: Vec<Ballot> = choices
+            .iter()
+            .map(|choice| {
+                encrypt_ballot(&public_key.public_key, *choice, candidate_count)
+                    .unwrap()
+            })
+            .collect();
+        assert!(verify_ballot(
+            &public_key.public_key,
+            &ballots[0],
+            candidate_count
+        ));
+
+        // A ballot voting twice for the same candidate is rejected.
+        let mut invalid_ballot = ballots[0].clone();
+        invalid_ballot.votes[1] = invalid_ballot.votes[0];
+        invalid_ballot.vote_proofs[1] = invalid_ballot.vote_proofs[0].clone();
+        assert!(!verify_ballot(
+            &public_key.public_key,
+            &invalid_ballot,
+            candidate_count
+        ));
+        assert!(
+            encrypt_ballot(&public_key.public_key, 3, candidate_count).is_err()
+        );
+
+        let tally =
+            tally_ballots(&public_key.public_key, &ballots, candidate_count)
+                .unwrap();
+        let partial_decryptions: Vec<Vec<PartialDecryption>> = shares[1..]
+            .iter()
+            .map(|share| partial_decrypt_tally(share, &tally))
+            .collect();
+        assert_eq!(
+            decrypt_tally(&public_key, &tally, &partial_decryptions, 5)
+                .unwrap(),
+            vec![1, 1, 3]
+        );
+        assert!(decrypt_tally(&public_key, &tally, &partial_decryptions, 2)
+            .is_err());
+    }
+}
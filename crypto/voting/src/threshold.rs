@@ -0,0 +1,210 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Threshold ElGamal decryption, where the private key is shared among
+//! trustees by a trusted dealer with Shamir secret sharing, and any
+//! `threshold` trustees jointly decrypt with verifiable partial decryptions.
+
+use crate::{prove_dleq, verify_dleq, DleqProof};
+use curve25519_dalek::{ristretto::RistrettoPoint, scalar::Scalar};
+use wedpr_l_crypto_zkp_shuffle_proof::elgamal::ElGamalCiphertext;
+use wedpr_l_crypto_zkp_utils::{get_random_scalar, BASEPOINT_G1};
+use wedpr_l_utils::error::WedprError;
+
+/// Domain separator of partial decryption proofs.
+const PARTIAL_DECRYPTION_DOMAIN: &[u8] = b"WeDPR_VOTING_PARTIAL_DECRYPTION";
+
+/// Public key of threshold ElGamal, with a verification key x_i * G1 for the
+/// share x_i of each trustee i in [1, trustee_count].
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct ThresholdElGamalPublicKey {
+    pub public_key: RistrettoPoint,
+    pub threshold: usize,
+    pub verification_keys: Vec<RistrettoPoint>,
+}
+
+/// Private key share of a trustee.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct TrusteeKeyShare {
+    pub index: usize,
+    pub share: Scalar,
+}
+
+/// Partial decryption x_i * c1 of a trustee, with a proof that it uses the
+/// same share as the verification key.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct PartialDecryption {
+    pub index: usize,
+    pub value: RistrettoPoint,
+    pub proof: DleqProof,
+}
+
+/// Generates a threshold ElGamal key, where any `threshold` of
+/// `trustee_count` trustees can decrypt.
+pub fn generate_threshold_keys(
+    threshold: usize,
+    trustee_count: usize,
+) -> Result<(ThresholdElGamalPublicKey, Vec<TrusteeKeyShare>), WedprError> {
+    if threshold == 0 || threshold > trustee_count {
+        wedpr_println!("Invalid threshold {} of {}", threshold, trustee_count);
+        return Err(WedprError::ArgumentError);
+    }
+    let coefficients: Vec<Scalar> =
+        (0..threshold).map(|_| get_random_scalar()).collect();
+    let shares: Vec<TrusteeKeyShare> = (1..=trustee_count)
+        .map(|index| {
+            let x = Scalar::from(index as u64);
+            let share = coefficients
+                .iter()
+                .rev()
+                .fold(Scalar::zero(), |sum, coefficient| sum * x + coefficient);
+            TrusteeKeyShare { index, share }
+        })
+        .collect();
+    let public_key = ThresholdElGamalPublicKey {
+        public_key: coefficients[0] * *BASEPOINT_G1,
+        threshold,
+        verification_keys: shares
+            .iter()
+            .map(|share| share.share * *BASEPOINT_G1)
+            .collect(),
+    };
+    Ok((public_key, shares))
+}
+
+/// Computes the partial decryption of a ciphertext by a trustee.
+pub fn partial_decrypt(
+    key_share: &TrusteeKeyShare,
+    ciphertext: &ElGamalCiphertext,
+) -> PartialDecryption {
+    let value = key_share.share * ciphertext.c1;
+    let verification_key = key_share.share * *BASEPOINT_G1;
+    PartialDecryption {
+        index: key_share.index,
+        value,
+        proof: prove_dleq(
+            PARTIAL_DECRYPTION_DOMAIN,
+            &key_share.share,
+            &BASEPOINT_G1,
+            &ciphertext.c1,
+            &verification_key,
+            &value,
+        ),
+    }
+}
+
+/// Verifies the partial decryption of a ciphertext by a trustee.
+pub fn verify_partial_decryption(
+    public_key: &ThresholdElGamalPublicKey,
+    ciphertext: &ElGamalCiphertext,
+    partial_decryption: &PartialDecryption,
+) -> bool {
+    let index = partial_decryption.index;
+    if index == 0 || index > public_key.verification_keys.len() {
+        return false;
+    }
+    verify_dleq(
+        PARTIAL_DECRYPTION_DOMAIN,
+        &BASEPOINT_G1,
+        &ciphertext.c1,
+        &public_key.verification_keys[index - 1],
+        &partial_decryption.value,
+        &partial_decryption.proof,
+    )
+}
+
+/// Combines verified partial decryptions of at least `threshold` distinct
+/// trustees into the message point of a ciphertext.
+pub fn combine_partial_decryptions(
+    public_key: &ThresholdElGamalPublicKey,
+    ciphertext: &ElGamalCiphertext,
+    partial_decryptions: &[PartialDecryption],
+) -> Result<RistrettoPoint, WedprError> {
+    let mut selected: Vec<&PartialDecryption> = Vec::new();
+    for partial_decryption in partial_decryptions {
+        if selected.len() == public_key.threshold {
+            break;
+        }
+        if selected
+            .iter()
+            .any(|item| item.index == partial_decryption.index)
+        {
+            continue;
+        }
+        if !verify_partial_decryption(
+            public_key,
+            ciphertext,
+            partial_decryption,
+        ) {
+            wedpr_println!(
+                "Invalid partial decryption of trustee {}",
+                partial_decryption.index
+            );
+            return Err(WedprError::VerificationError);
+        }
+        selected.push(partial_decryption);
+    }
+    if selected.len() < public_key.threshold {
+        wedpr_println!("Not enough partial decryptions");
+        return Err(WedprError::ArgumentError);
+    }
+
+    // Interpolates x * c1 at 0 by Lagrange coefficients.
+    let mut shared_secret = RistrettoPoint::default();
+    for item in &selected {
+        let xi = Scalar::from(item.index as u64);
+        let mut numerator = Scalar::one();
+        let mut denominator = Scalar::one();
+        for other in &selected {
+            if other.index == item.index {
+                continue;
+            }
+            let xj = Scalar::from(other.index as u64);
+            numerator *= xj;
+            denominator *= xj - xi;
+        }
+        shared_secret += numerator * denominator.invert() * item.value;
+    }
+    Ok(ciphertext.c2 - shared_secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_zkp_shuffle_proof::elgamal::encrypt;
+
+    #[test]
+    fn test_threshold_decryption() {
+        let (public_key, shares) = generate_threshold_keys(2, 3).unwrap();
+        let message = Scalar::from(7u64) * *BASEPOINT_G1;
+        let ciphertext =
+            encrypt(&public_key.public_key, &message, &get_random_scalar());
+        let partial_decryptions: Vec<PartialDecryption> = shares
+            .iter()
+            .map(|share| partial_decrypt(share, &ciphertext))
+            .collect();
+        assert_eq!(
+            combine_partial_decryptions(
+                &public_key,
+                &ciphertext,
+                &partial_decryptions[1..]
+            )
+            .unwrap(),
+            message
+        );
+        assert!(combine_partial_decryptions(
+            &public_key,
+            &ciphertext,
+            &partial_decryptions[..1]
+        )
+        .is_err());
+
+        let mut forged = partial_decryptions[0].clone();
+        forged.value += *BASEPOINT_G1;
+        assert!(!verify_partial_decryption(
+            &public_key,
+            &ciphertext,
+            &forged
+        ));
+        assert!(generate_threshold_keys(4, 3).is_err());
+    }
+}
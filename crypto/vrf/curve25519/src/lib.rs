@@ -1,10 +1,17 @@
 // Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
 
 //! Curve25519 VRF functions.
+//!
+//! WedprCurve25519Vrf is the VRF scheme of earlier releases over Ristretto,
+//! and WedprEd25519Vrf is ECVRF-EDWARDS25519-SHA512-TAI of RFC 9381. Their
+//! keys and proofs are not interchangeable.
 
 extern crate curve25519_dalek;
 use curve25519_dalek::{ristretto::RistrettoPoint, scalar::Scalar};
-use wedpr_l_utils::traits::{Hash, Vrf};
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    traits::{AlgorithmInfo, Hash, Vrf},
+};
 
 #[macro_use]
 extern crate wedpr_l_macros;
@@ -20,6 +27,10 @@ use wedpr_l_utils::error::WedprError;
 
 extern crate rand;
 
+pub mod rfc9381;
+
+pub use rfc9381::WedprEd25519Vrf;
+
 /// Implements Curve25519 as a VRF instance.
 #[derive(PartialEq, Debug, Clone, Default)]
 pub struct WedprCurve25519Vrf {
@@ -28,6 +39,21 @@ pub struct WedprCurve25519Vrf {
     pub s_param: [u8; 32],
 }
 
+impl AlgorithmInfo for WedprCurve25519Vrf {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "curve25519-vrf",
+            oid: None,
+            security_bits: 128,
+            // Private keys of any length are hashed to a scalar.
+            private_key_size: None,
+            public_key_sizes: &[32],
+            signature_size: Some(96),
+            output_size: Some(32),
+        }
+    }
+}
+
 impl Vrf for WedprCurve25519Vrf {
     fn encode_proof(&self) -> Vec<u8> {
         let mut proof = Vec::new();
@@ -62,35 +88,8 @@ impl Vrf for WedprCurve25519Vrf {
         private_key: &T,
         message: &T,
     ) -> Result<Self, WedprError> {
-        let public_key_bytes = Self::derive_public_key(private_key);
-        // TODO: Merge the following logic with prove_fast.
-        let private_key_hash =
-            Scalar::hash_from_bytes::<Sha3_512>(private_key.as_ref());
-        let mut hash_vec = Vec::new();
-        hash_vec.append(&mut public_key_bytes.clone());
-        hash_vec.append(&mut message.as_ref().to_vec());
-
-        let h_point = RistrettoPoint::hash_from_bytes::<Sha3_512>(&hash_vec);
-        let gamma = h_point * private_key_hash;
-        let blinding_k = Scalar::random(&mut thread_rng());
-        let base_k = *BASEPOINT_G1 * blinding_k;
-        let point_k = h_point * blinding_k;
-
-        let mut c_vec = Vec::new();
-        c_vec.append(&mut hash_vec.clone());
-        c_vec.append(&mut public_key_bytes.clone());
-        c_vec.append(&mut point_to_bytes(&gamma));
-        c_vec.append(&mut point_to_bytes(&base_k));
-        c_vec.append(&mut point_to_bytes(&point_k));
-
-        let c_scalar = Scalar::hash_from_bytes::<Sha3_512>(&c_vec);
-        let s = blinding_k - (c_scalar * private_key_hash);
-        let proof = WedprCurve25519Vrf {
-            gamma_param: point_to_slice(&gamma),
-            c_param: scalar_to_slice(&c_scalar),
-            s_param: scalar_to_slice(&s),
-        };
-        Ok(proof)
+        let public_key = Self::derive_public_key(private_key);
+        Self::prove_fast(private_key.as_ref(), &public_key, message.as_ref())
     }
 
     fn prove_fast<T: ?Sized + AsRef<[u8]>>(
@@ -181,6 +180,16 @@ impl Vrf for WedprCurve25519Vrf {
     }
 }
 
+impl WedprCurve25519Vrf {
+    /// Generates a random key pair in the (public_key, private_key) order of
+    /// Signature::generate_keypair.
+    pub fn generate_keypair() -> (Vec<u8>, Vec<u8>) {
+        let private_key =
+            scalar_to_slice(&Scalar::random(&mut thread_rng())).to_vec();
+        (Self::derive_public_key(&private_key), private_key)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -201,9 +210,12 @@ mod tests {
         let proof = WedprCurve25519Vrf::prove(&private_key, &message).unwrap();
         assert_eq!(proof.verify(&public_key, &message), true);
 
+        // Proofs are randomized but their hashes are unique.
         let proof_hash = proof.proof_to_hash().unwrap();
-        // TODO: Add the expected value here.
-        println!("hash_proof = {:?}", proof_hash);
+        let another_proof =
+            WedprCurve25519Vrf::prove(&private_key, &message).unwrap();
+        assert_ne!(another_proof, proof);
+        assert_eq!(another_proof.proof_to_hash().unwrap(), proof_hash);
 
         let invalid_private_key = string_to_bytes_utf8("invalid key");
         assert_eq!(
@@ -213,8 +225,33 @@ mod tests {
             false
         );
 
+        let (generated_public_key, generated_private_key) =
+            WedprCurve25519Vrf::generate_keypair();
+        assert!(WedprCurve25519Vrf::is_valid_public_key(
+            &generated_public_key
+        ));
+        assert!(WedprCurve25519Vrf::prove(&generated_private_key, &message)
+            .unwrap()
+            .verify(&generated_public_key, &message));
+
         let recovered_proof =
             WedprCurve25519Vrf::decode_proof(&proof.encode_proof()).unwrap();
         assert_eq!(recovered_proof.verify(&public_key, &message), true);
     }
+
+    #[test]
+    fn test_vrf_schemes_are_distinct() {
+        let message = string_to_bytes_utf8("test message");
+        let (public_key, private_key) = WedprCurve25519Vrf::generate_keypair();
+        let proof = WedprCurve25519Vrf::prove(&private_key, &message).unwrap();
+        // Proofs of one scheme are rejected explicitly by the other.
+        assert!(WedprEd25519Vrf::decode_proof(&proof.encode_proof()).is_err());
+        let ed25519_proof =
+            WedprEd25519Vrf::prove(&private_key, &message).unwrap();
+        assert!(WedprCurve25519Vrf::decode_proof(
+            &ed25519_proof.encode_proof()
+        )
+        .is_err());
+        assert!(!ed25519_proof.verify(&public_key, &message));
+    }
 }
@@ -0,0 +1,486 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! VRF functions of ECVRF-EDWARDS25519-SHA512-TAI in RFC 9381
+//! (draft-irtf-cfrg-vrf), whose key pairs are Ed25519 key pairs. Its keys and
+//! proofs are not compatible with WedprCurve25519Vrf.
+
+use curve25519_dalek::{
+    constants::ED25519_BASEPOINT_TABLE,
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::Scalar,
+};
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    traits::{AlgorithmInfo, Vrf},
+};
+
+use rand::{thread_rng, RngCore};
+use sha2::{Digest, Sha512};
+use wedpr_l_utils::error::WedprError;
+
+/// Size of a private key, public key or encoded point in bytes.
+pub const KEY_SIZE_IN_BYTES: usize = 32;
+/// Size of a challenge c in bytes.
+pub const CHALLENGE_SIZE_IN_BYTES: usize = 16;
+/// Size of a proof gamma || c || s in bytes.
+pub const PROOF_SIZE_IN_BYTES: usize =
+    KEY_SIZE_IN_BYTES + CHALLENGE_SIZE_IN_BYTES + SCALAR_SIZE_IN_BYTES;
+/// Size of a VRF output in bytes.
+pub const OUTPUT_SIZE_IN_BYTES: usize = 64;
+const SCALAR_SIZE_IN_BYTES: usize = 32;
+
+// Suite string of ECVRF-EDWARDS25519-SHA512-TAI.
+const SUITE_STRING: u8 = 0x03;
+const ENCODE_TO_CURVE_DOMAIN: u8 = 0x01;
+const CHALLENGE_DOMAIN: u8 = 0x02;
+const PROOF_TO_HASH_DOMAIN: u8 = 0x03;
+const DOMAIN_BACK: u8 = 0x00;
+
+/// Implements ECVRF-EDWARDS25519-SHA512-TAI as a VRF instance, where the
+/// private keys are 32-byte Ed25519 seeds, and the public keys are compressed
+/// Ed25519 points.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct WedprEd25519Vrf {
+    pub gamma_param: [u8; KEY_SIZE_IN_BYTES],
+    pub c_param: [u8; CHALLENGE_SIZE_IN_BYTES],
+    pub s_param: [u8; SCALAR_SIZE_IN_BYTES],
+}
+
+impl AlgorithmInfo for WedprEd25519Vrf {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "ed25519-vrf",
+            oid: None,
+            security_bits: 128,
+            private_key_size: Some(KEY_SIZE_IN_BYTES),
+            public_key_sizes: &[KEY_SIZE_IN_BYTES],
+            signature_size: Some(PROOF_SIZE_IN_BYTES),
+            output_size: Some(OUTPUT_SIZE_IN_BYTES),
+        }
+    }
+}
+
+impl Vrf for WedprEd25519Vrf {
+    fn encode_proof(&self) -> Vec<u8> {
+        let mut proof = self.gamma_param.to_vec();
+        proof.extend_from_slice(&self.c_param);
+        proof.extend_from_slice(&self.s_param);
+        proof
+    }
+
+    fn decode_proof<T: ?Sized + AsRef<[u8]>>(
+        proof: &T,
+    ) -> Result<Self, WedprError> {
+        let proof = proof.as_ref();
+        if proof.len() != PROOF_SIZE_IN_BYTES {
+            return Err(WedprError::FormatError);
+        }
+        let mut decoded = WedprEd25519Vrf::default();
+        let (gamma, rest) = proof.split_at(KEY_SIZE_IN_BYTES);
+        let (c, s) = rest.split_at(CHALLENGE_SIZE_IN_BYTES);
+        decoded.gamma_param.copy_from_slice(gamma);
+        decoded.c_param.copy_from_slice(c);
+        decoded.s_param.copy_from_slice(s);
+        decompress_point(&decoded.gamma_param)?;
+        parse_scalar(&decoded.s_param)?;
+        Ok(decoded)
+    }
+
+    fn prove<T: ?Sized + AsRef<[u8]>>(
+        private_key: &T,
+        message: &T,
+    ) -> Result<Self, WedprError> {
+        let expanded_key = ExpandedKey::new(private_key.as_ref())?;
+        let public_key = expanded_key.public_key();
+        prove_with_keys(&expanded_key, &public_key, message.as_ref())
+    }
+
+    fn prove_fast<T: ?Sized + AsRef<[u8]>>(
+        private_key: &T,
+        public_key: &T,
+        message: &T,
+    ) -> Result<Self, WedprError> {
+        let expanded_key = ExpandedKey::new(private_key.as_ref())?;
+        let public_key = parse_public_key(public_key.as_ref())?;
+        prove_with_keys(&expanded_key, &public_key, message.as_ref())
+    }
+
+    fn verify<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        message: &T,
+    ) -> bool {
+        let public_key = match parse_public_key(public_key.as_ref()) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        let (gamma, s) = match (
+            decompress_point(&self.gamma_param),
+            parse_scalar(&self.s_param),
+        ) {
+            (Ok(gamma), Ok(s)) => (gamma, s),
+            _ => return false,
+        };
+        let h_point = match encode_to_curve(&public_key, message.as_ref()) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        let c = challenge_to_scalar(&self.c_param);
+        // U = s * B - c * Y, V = s * H - c * Gamma.
+        let u = EdwardsPoint::vartime_double_scalar_mul_basepoint(
+            &c,
+            &-public_key.point,
+            &s,
+        );
+        let v = s * h_point - c * gamma;
+        compute_challenge(&public_key, &h_point, &gamma, &u, &v) == self.c_param
+    }
+
+    /// Derives a compressed public key, or an empty one if the private key
+    /// is not 32 bytes.
+    fn derive_public_key<T: ?Sized + AsRef<[u8]>>(private_key: &T) -> Vec<u8> {
+        match ExpandedKey::new(private_key.as_ref()) {
+            Ok(v) => v.public_key().bytes.to_vec(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn proof_to_hash(&self) -> Result<Vec<u8>, WedprError> {
+        let gamma = decompress_point(&self.gamma_param)?;
+        Ok(suite_hash(PROOF_TO_HASH_DOMAIN, &[gamma
+            .mul_by_cofactor()
+            .compress()
+            .as_bytes()]))
+    }
+
+    fn is_valid_public_key<T: ?Sized + AsRef<[u8]>>(public_key: &T) -> bool {
+        parse_public_key(public_key.as_ref()).is_ok()
+    }
+}
+
+impl WedprEd25519Vrf {
+    /// Generates a random key pair in the (public_key, private_key) order of
+    /// Signature::generate_keypair.
+    pub fn generate_keypair() -> (Vec<u8>, Vec<u8>) {
+        let mut private_key = vec![0u8; KEY_SIZE_IN_BYTES];
+        thread_rng().fill_bytes(&mut private_key);
+        (Self::derive_public_key(&private_key), private_key)
+    }
+}
+
+/// Secret scalar x and nonce prefix expanded from a private key as Ed25519.
+struct ExpandedKey {
+    scalar: Scalar,
+    prefix: [u8; KEY_SIZE_IN_BYTES],
+}
+
+impl ExpandedKey {
+    fn new(private_key: &[u8]) -> Result<Self, WedprError> {
+        if private_key.len() != KEY_SIZE_IN_BYTES {
+            wedpr_println!("Parsing private key failed");
+            return Err(WedprError::FormatError);
+        }
+        let expanded_key = Sha512::digest(private_key);
+        let mut scalar_bytes = [0u8; KEY_SIZE_IN_BYTES];
+        scalar_bytes.copy_from_slice(&expanded_key[..KEY_SIZE_IN_BYTES]);
+        scalar_bytes[0] &= 248;
+        scalar_bytes[31] &= 127;
+        scalar_bytes[31] |= 64;
+        let mut prefix = [0u8; KEY_SIZE_IN_BYTES];
+        prefix.copy_from_slice(&expanded_key[KEY_SIZE_IN_BYTES..]);
+        Ok(ExpandedKey {
+            scalar: Scalar::from_bits(scalar_bytes),
+            prefix,
+        })
+    }
+
+    fn public_key(&self) -> PublicKey {
+        let point = &self.scalar * &ED25519_BASEPOINT_TABLE;
+        PublicKey {
+            point,
+            bytes: point.compress().to_bytes(),
+        }
+    }
+}
+
+/// Public key point Y with its encoding, which is hashed as given.
+struct PublicKey {
+    point: EdwardsPoint,
+    bytes: [u8; KEY_SIZE_IN_BYTES],
+}
+
+/// Proves with the nonce of RFC 8032, so the same proof is produced for the
+/// same key and message.
+fn prove_with_keys(
+    expanded_key: &ExpandedKey,
+    public_key: &PublicKey,
+    message: &[u8],
+) -> Result<WedprEd25519Vrf, WedprError> {
+    let h_point = encode_to_curve(public_key, message)?;
+    let gamma = expanded_key.scalar * h_point;
+    let mut hasher = Sha512::new();
+    hasher.input(expanded_key.prefix);
+    hasher.input(h_point.compress().as_bytes());
+    let mut nonce_hash = [0u8; 64];
+    nonce_hash.copy_from_slice(&hasher.result());
+    let nonce = Scalar::from_bytes_mod_order_wide(&nonce_hash);
+    let u = &nonce * &ED25519_BASEPOINT_TABLE;
+    let v = nonce * h_point;
+    let c = compute_challenge(public_key, &h_point, &gamma, &u, &v);
+    // s = k + c * x mod q.
+    let s = nonce + challenge_to_scalar(&c) * expanded_key.scalar;
+    Ok(WedprEd25519Vrf {
+        gamma_param: gamma.compress().to_bytes(),
+        c_param: c,
+        s_param: s.to_bytes(),
+    })
+}
+
+/// Hashes a public key and a message to a point by try-and-increment.
+fn encode_to_curve(
+    public_key: &PublicKey,
+    message: &[u8],
+) -> Result<EdwardsPoint, WedprError> {
+    for counter in 0..=255u8 {
+        let hash = suite_hash(ENCODE_TO_CURVE_DOMAIN, &[
+            &public_key.bytes,
+            message,
+            &[counter],
+        ]);
+        let mut point_bytes = [0u8; KEY_SIZE_IN_BYTES];
+        point_bytes.copy_from_slice(&hash[..KEY_SIZE_IN_BYTES]);
+        if let Some(point) = CompressedEdwardsY(point_bytes).decompress() {
+            return Ok(point.mul_by_cofactor());
+        }
+    }
+    wedpr_println!("Hashing message to edwards25519 failed");
+    Err(WedprError::ArgumentError)
+}
+
+/// Computes the truncated challenge H(Y || H || Gamma || U || V).
+fn compute_challenge(
+    public_key: &PublicKey,
+    h_point: &EdwardsPoint,
+    gamma: &EdwardsPoint,
+    u: &EdwardsPoint,
+    v: &EdwardsPoint,
+) -> [u8; CHALLENGE_SIZE_IN_BYTES] {
+    let hash = suite_hash(CHALLENGE_DOMAIN, &[
+        &public_key.bytes,
+        h_point.compress().as_bytes(),
+        gamma.compress().as_bytes(),
+        u.compress().as_bytes(),
+        v.compress().as_bytes(),
+    ]);
+    let mut c = [0u8; CHALLENGE_SIZE_IN_BYTES];
+    c.copy_from_slice(&hash[..CHALLENGE_SIZE_IN_BYTES]);
+    c
+}
+
+/// Computes SHA512(suite || domain || inputs || 0x00).
+fn suite_hash(domain: u8, inputs: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = Sha512::new();
+    hasher.input([SUITE_STRING, domain]);
+    for input in inputs {
+        hasher.input(input);
+    }
+    hasher.input([DOMAIN_BACK]);
+    hasher.result().to_vec()
+}
+
+/// Converts a little-endian challenge to a scalar, which is always less than
+/// the group order.
+fn challenge_to_scalar(c: &[u8; CHALLENGE_SIZE_IN_BYTES]) -> Scalar {
+    let mut scalar_bytes = [0u8; SCALAR_SIZE_IN_BYTES];
+    scalar_bytes[..CHALLENGE_SIZE_IN_BYTES].copy_from_slice(c);
+    Scalar::from_bits(scalar_bytes)
+}
+
+fn decompress_point(bytes: &[u8]) -> Result<EdwardsPoint, WedprError> {
+    if bytes.len() != KEY_SIZE_IN_BYTES {
+        return Err(WedprError::FormatError);
+    }
+    let mut point_bytes = [0u8; KEY_SIZE_IN_BYTES];
+    point_bytes.copy_from_slice(bytes);
+    match CompressedEdwardsY(point_bytes).decompress() {
+        Some(v) => Ok(v),
+        None => {
+            wedpr_println!("Parsing point failed");
+            Err(WedprError::FormatError)
+        },
+    }
+}
+
+/// Parses a public key, where small order points are rejected as by the key
+/// validation of RFC 9381.
+fn parse_public_key(public_key: &[u8]) -> Result<PublicKey, WedprError> {
+    let point = decompress_point(public_key)?;
+    if point.is_small_order() {
+        wedpr_println!("Public key is of small order");
+        return Err(WedprError::FormatError);
+    }
+    let mut bytes = [0u8; KEY_SIZE_IN_BYTES];
+    bytes.copy_from_slice(public_key);
+    Ok(PublicKey { point, bytes })
+}
+
+/// Parses a canonical scalar s less than the group order.
+fn parse_scalar(
+    bytes: &[u8; SCALAR_SIZE_IN_BYTES],
+) -> Result<Scalar, WedprError> {
+    match Scalar::from_canonical_bytes(*bytes) {
+        Some(v) => Ok(v),
+        None => Err(WedprError::FormatError),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_common_coder_hex::WedprHex;
+    use wedpr_l_utils::traits::Coder;
+
+    #[test]
+    fn test_vrf() {
+        let hex = WedprHex::default();
+        // Examples 16, 17 and 18 of RFC 9381 for
+        // ECVRF-EDWARDS25519-SHA512-TAI.
+        let test_cases = [
+            (
+                [
+                    "9d61b19deffd5a60ba844af492ec2cc4",
+                    "4449c5697b326919703bac031cae7f60",
+                ],
+                [
+                    "d75a980182b10ab7d54bfed3c964073a",
+                    "0ee172f3daa62325af021a68f707511a",
+                ],
+                "",
+                [
+                    "8657106690b5526245a92b003bb079cc",
+                    "d1a92130477671f6fc01ad16f26f723f",
+                    "26f8a57ccaed74ee1b190bed1f479d97",
+                    "27d2d0f9b005a6e456a35d4fb0daab12",
+                    "68a1b0db10836d9826a528ca76567805",
+                ],
+                [
+                    "90cf1df3b703cce59e2a35b925d41116",
+                    "4068269d7b2d29f3301c03dd757876ff",
+                    "66b71dda49d2de59d03450451af02679",
+                    "8e8f81cd2e333de5cdf4f3e140fdd8ae",
+                ],
+            ),
+            (
+                [
+                    "4ccd089b28ff96da9db6c346ec114e0f",
+                    "5b8a319f35aba624da8cf6ed4fb8a6fb",
+                ],
+                [
+                    "3d4017c3e843895a92b70aa74d1b7ebc",
+                    "9c982ccf2ec4968cc0cd55f12af4660c",
+                ],
+                "72",
+                [
+                    "f3141cd382dc42909d19ec5110469e4f",
+                    "eae18300e94f304590abdced48aed593",
+                    "3bf0864a62558b3ed7f2fea45c92a465",
+                    "301b3bbf5e3e54ddf2d935be3b67926d",
+                    "a3ef39226bbc355bdc9850112c8f4b02",
+                ],
+                [
+                    "eb4440665d3891d668e7e0fcaf587f1b",
+                    "4bd7fbfe99d0eb2211ccec90496310eb",
+                    "5e33821bc613efb94db5e5b54c70a848",
+                    "a0bef4553a41befc57663b56373a5031",
+                ],
+            ),
+            (
+                [
+                    "c5aa8df43f9f837bedb7442f31dcb7b1",
+                    "66d38535076f094b85ce3a2e0b4458f7",
+                ],
+                [
+                    "fc51cd8e6218a1a38da47ed00230f058",
+                    "0816ed13ba3303ac5deb911548908025",
+                ],
+                "af82",
+                [
+                    "9bc0f79119cc5604bf02d23b4caede71",
+                    "393cedfbb191434dd016d30177ccbf80",
+                    "96bb474e53895c362d8628ee9f9ea3c0",
+                    "e52c7a5c691b6c18c9979866568add7a",
+                    "2d41b00b05081ed0f58ee5e31b3a970e",
+                ],
+                [
+                    "645427e5d00c62a23fb703732fa5d892",
+                    "940935942101e456ecca7bb217c61c45",
+                    "2118fec1219202a0edcf038bb6373241",
+                    "578be7217ba85a2687f7a0310b2df19f",
+                ],
+            ),
+        ];
+        for (private_key, public_key, message, proof, output) in
+            test_cases.iter()
+        {
+            let private_key = hex.decode(&private_key.concat()).unwrap();
+            let public_key = hex.decode(&public_key.concat()).unwrap();
+            let message = hex.decode(message).unwrap();
+            let expected_proof = hex.decode(&proof.concat()).unwrap();
+            assert_eq!(
+                WedprEd25519Vrf::derive_public_key(&private_key),
+                public_key
+            );
+            let proof = WedprEd25519Vrf::prove(&private_key, &message).unwrap();
+            assert_eq!(proof.encode_proof(), expected_proof);
+            assert_eq!(
+                WedprEd25519Vrf::prove_fast(
+                    &private_key,
+                    &public_key,
+                    &message
+                )
+                .unwrap(),
+                proof
+            );
+            let decoded_proof =
+                WedprEd25519Vrf::decode_proof(&expected_proof).unwrap();
+            assert!(decoded_proof.verify(&public_key, &message));
+            assert!(!decoded_proof
+                .verify(&public_key, &[message, vec![0]].concat()));
+            assert_eq!(
+                decoded_proof.proof_to_hash().unwrap(),
+                hex.decode(&output.concat()).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_vrf_keys() {
+        let (public_key, private_key) = WedprEd25519Vrf::generate_keypair();
+        let message = b"test message".to_vec();
+        assert!(WedprEd25519Vrf::is_valid_public_key(&public_key));
+        assert!(!WedprEd25519Vrf::is_valid_public_key(&public_key[1..]));
+        // Small order points are not public keys.
+        assert!(!WedprEd25519Vrf::is_valid_public_key(&[0u8; 32]));
+        let short_private_key = private_key[1..].to_vec();
+        assert!(
+            WedprEd25519Vrf::derive_public_key(&short_private_key).is_empty()
+        );
+        assert!(WedprEd25519Vrf::prove(&short_private_key, &message).is_err());
+
+        // Proofs are deterministic.
+        let proof = WedprEd25519Vrf::prove(&private_key, &message).unwrap();
+        assert_eq!(
+            WedprEd25519Vrf::prove(&private_key, &message).unwrap(),
+            proof
+        );
+        assert!(proof.verify(&public_key, &message));
+        let (another_public_key, _) = WedprEd25519Vrf::generate_keypair();
+        assert!(!proof.verify(&another_public_key, &message));
+
+        // Non-canonical s is rejected.
+        let mut encoded_proof = proof.encode_proof();
+        encoded_proof[PROOF_SIZE_IN_BYTES - 1] |= 0xf0;
+        assert!(WedprEd25519Vrf::decode_proof(&encoded_proof).is_err());
+        assert!(WedprEd25519Vrf::decode_proof(&encoded_proof[1..]).is_err());
+    }
+}
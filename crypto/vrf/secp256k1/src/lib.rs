@@ -0,0 +1,387 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Secp256k1 ECVRF functions following the construction of RFC 9381 with
+//! SHA-256 and try-and-increment hashing to the curve, whose key pairs are
+//! compatible with secp256k1 signing keys.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+#[macro_use]
+extern crate lazy_static;
+
+use secp256k1::{constants::CURVE_ORDER, All, PublicKey, Secp256k1, SecretKey};
+use wedpr_l_crypto_hash_sha256::WedprSha256;
+use wedpr_l_utils::{
+    algorithm::AlgorithmMetadata,
+    error::WedprError,
+    traits::{AlgorithmInfo, Hash, Vrf},
+};
+
+lazy_static! {
+    // Shared secp256k1 instance initialized for all functions.
+    static ref SECP256K1_ALL: Secp256k1<All> = Secp256k1::new();
+}
+
+/// Size of a compressed point in bytes.
+pub const POINT_SIZE_IN_BYTES: usize = 33;
+/// Size of a challenge c in bytes.
+pub const CHALLENGE_SIZE_IN_BYTES: usize = 16;
+/// Size of a proof gamma || c || s in bytes.
+pub const PROOF_SIZE_IN_BYTES: usize =
+    POINT_SIZE_IN_BYTES + CHALLENGE_SIZE_IN_BYTES + SCALAR_SIZE_IN_BYTES;
+const SCALAR_SIZE_IN_BYTES: usize = 32;
+
+// Suite string of ECVRF-SECP256K1-SHA256-TAI, which is not assigned by RFC
+// 9381 but widely used by other secp256k1 implementations.
+const SUITE_STRING: u8 = 0xfe;
+const ENCODE_TO_CURVE_DOMAIN: u8 = 0x01;
+const CHALLENGE_DOMAIN: u8 = 0x02;
+const PROOF_TO_HASH_DOMAIN: u8 = 0x03;
+const NONCE_DOMAIN: u8 = 0x04;
+const DOMAIN_BACK: u8 = 0x00;
+
+/// Implements secp256k1 ECVRF as a VRF instance, where the private keys are
+/// 32-byte secp256k1 scalars, and the public keys are compressed or
+/// uncompressed points.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct WedprSecp256k1Vrf {
+    pub gamma_param: Vec<u8>,
+    pub c_param: Vec<u8>,
+    pub s_param: Vec<u8>,
+}
+
+impl AlgorithmInfo for WedprSecp256k1Vrf {
+    fn algorithm_info() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "secp256k1-vrf",
+            oid: None,
+            security_bits: 128,
+            private_key_size: Some(SCALAR_SIZE_IN_BYTES),
+            public_key_sizes: &[33, 65],
+            signature_size: Some(PROOF_SIZE_IN_BYTES),
+            output_size: Some(32),
+        }
+    }
+}
+
+impl Vrf for WedprSecp256k1Vrf {
+    fn encode_proof(&self) -> Vec<u8> {
+        let mut proof = self.gamma_param.clone();
+        proof.extend_from_slice(&self.c_param);
+        proof.extend_from_slice(&self.s_param);
+        proof
+    }
+
+    fn decode_proof<T: ?Sized + AsRef<[u8]>>(
+        proof: &T,
+    ) -> Result<Self, WedprError> {
+        let proof = proof.as_ref();
+        if proof.len() != PROOF_SIZE_IN_BYTES {
+            return Err(WedprError::FormatError);
+        }
+        let (gamma, rest) = proof.split_at(POINT_SIZE_IN_BYTES);
+        let (c, s) = rest.split_at(CHALLENGE_SIZE_IN_BYTES);
+        parse_public_key(gamma)?;
+        parse_secret_key(s)?;
+        Ok(WedprSecp256k1Vrf {
+            gamma_param: gamma.to_vec(),
+            c_param: c.to_vec(),
+            s_param: s.to_vec(),
+        })
+    }
+
+    fn prove<T: ?Sized + AsRef<[u8]>>(
+        private_key: &T,
+        message: &T,
+    ) -> Result<Self, WedprError> {
+        let secret_key = parse_secret_key(private_key.as_ref())?;
+        let public_key =
+            PublicKey::from_secret_key(&SECP256K1_ALL, &secret_key);
+        prove_with_keys(&secret_key, &public_key, message.as_ref())
+    }
+
+    fn prove_fast<T: ?Sized + AsRef<[u8]>>(
+        private_key: &T,
+        public_key: &T,
+        message: &T,
+    ) -> Result<Self, WedprError> {
+        let secret_key = parse_secret_key(private_key.as_ref())?;
+        let public_key = parse_public_key(public_key.as_ref())?;
+        prove_with_keys(&secret_key, &public_key, message.as_ref())
+    }
+
+    fn verify<T: ?Sized + AsRef<[u8]>>(
+        &self,
+        public_key: &T,
+        message: &T,
+    ) -> bool {
+        let public_key = match parse_public_key(public_key.as_ref()) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        let (gamma, s) = match (
+            parse_public_key(&self.gamma_param),
+            parse_secret_key(&self.s_param),
+        ) {
+            (Ok(gamma), Ok(s)) => (gamma, s),
+            _ => return false,
+        };
+        if self.c_param.len() != CHALLENGE_SIZE_IN_BYTES {
+            return false;
+        }
+        let h_point = match encode_to_curve(&public_key, message.as_ref()) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        let c = challenge_to_scalar(&self.c_param);
+        // U = s * B - c * Y, V = s * H - c * Gamma.
+        let s_base = PublicKey::from_secret_key(&SECP256K1_ALL, &s);
+        let (u, v) = match (
+            subtract_multiple(&s_base, &public_key, &c),
+            multiply(&h_point, &s[..])
+                .and_then(|s_h| subtract_multiple(&s_h, &gamma, &c)),
+        ) {
+            (Ok(u), Ok(v)) => (u, v),
+            _ => return false,
+        };
+        compute_challenge(&public_key, &h_point, &gamma, &u, &v) == self.c_param
+    }
+
+    /// Derives a compressed public key, or an empty one if the private key
+    /// is invalid.
+    fn derive_public_key<T: ?Sized + AsRef<[u8]>>(private_key: &T) -> Vec<u8> {
+        match parse_secret_key(private_key.as_ref()) {
+            Ok(v) => PublicKey::from_secret_key(&SECP256K1_ALL, &v)
+                .serialize()
+                .to_vec(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn proof_to_hash(&self) -> Result<Vec<u8>, WedprError> {
+        // The cofactor of secp256k1 is 1.
+        let gamma = parse_public_key(&self.gamma_param)?;
+        Ok(suite_hash(PROOF_TO_HASH_DOMAIN, &[&gamma.serialize()]))
+    }
+
+    fn is_valid_public_key<T: ?Sized + AsRef<[u8]>>(public_key: &T) -> bool {
+        PublicKey::from_slice(public_key.as_ref()).is_ok()
+    }
+}
+
+impl WedprSecp256k1Vrf {
+    /// Generates a random key pair in the (public_key, private_key) order of
+    /// Signature::generate_keypair.
+    pub fn generate_keypair() -> (Vec<u8>, Vec<u8>) {
+        let secret_key = SecretKey::new(&mut rand::thread_rng());
+        let public_key =
+            PublicKey::from_secret_key(&SECP256K1_ALL, &secret_key);
+        (public_key.serialize().to_vec(), secret_key[..].to_vec())
+    }
+}
+
+/// Proves with a deterministic nonce, so the same proof is produced for the
+/// same key and message.
+fn prove_with_keys(
+    secret_key: &SecretKey,
+    public_key: &PublicKey,
+    message: &[u8],
+) -> Result<WedprSecp256k1Vrf, WedprError> {
+    let h_point = encode_to_curve(public_key, message)?;
+    let gamma = multiply(&h_point, &secret_key[..])?;
+    let nonce_hash =
+        suite_hash(NONCE_DOMAIN, &[&secret_key[..], &h_point.serialize()]);
+    let nonce = match SecretKey::from_slice(&reduce_scalar(&nonce_hash)) {
+        Ok(v) => v,
+        Err(_) => {
+            wedpr_println!("VRF nonce is zero");
+            return Err(WedprError::ArgumentError);
+        },
+    };
+    let u = PublicKey::from_secret_key(&SECP256K1_ALL, &nonce);
+    let v = multiply(&h_point, &nonce[..])?;
+    let c = compute_challenge(public_key, &h_point, &gamma, &u, &v);
+    // s = k + c * x mod n.
+    let mut s = *secret_key;
+    if s.mul_assign(&challenge_to_scalar(&c)).is_err()
+        || s.add_assign(&nonce[..]).is_err()
+    {
+        wedpr_println!("Computing VRF proof failed");
+        return Err(WedprError::ArgumentError);
+    }
+    Ok(WedprSecp256k1Vrf {
+        gamma_param: gamma.serialize().to_vec(),
+        c_param: c,
+        s_param: s[..].to_vec(),
+    })
+}
+
+/// Hashes a public key and a message to a point by try-and-increment.
+fn encode_to_curve(
+    public_key: &PublicKey,
+    message: &[u8],
+) -> Result<PublicKey, WedprError> {
+    let public_key_bytes = public_key.serialize();
+    for counter in 0..=255u8 {
+        let hash = suite_hash(ENCODE_TO_CURVE_DOMAIN, &[
+            &public_key_bytes,
+            message,
+            &[counter],
+        ]);
+        let mut point_bytes = vec![0x02];
+        point_bytes.extend_from_slice(&hash);
+        if let Ok(point) = PublicKey::from_slice(&point_bytes) {
+            return Ok(point);
+        }
+    }
+    wedpr_println!("Hashing message to secp256k1 failed");
+    Err(WedprError::ArgumentError)
+}
+
+/// Computes the truncated challenge H(Y || H || Gamma || U || V).
+fn compute_challenge(
+    public_key: &PublicKey,
+    h_point: &PublicKey,
+    gamma: &PublicKey,
+    u: &PublicKey,
+    v: &PublicKey,
+) -> Vec<u8> {
+    let mut hash = suite_hash(CHALLENGE_DOMAIN, &[
+        &public_key.serialize(),
+        &h_point.serialize(),
+        &gamma.serialize(),
+        &u.serialize(),
+        &v.serialize(),
+    ]);
+    hash.truncate(CHALLENGE_SIZE_IN_BYTES);
+    hash
+}
+
+/// Computes SHA256(suite || domain || inputs || 0x00).
+fn suite_hash(domain: u8, inputs: &[&[u8]]) -> Vec<u8> {
+    let mut hash_input = vec![SUITE_STRING, domain];
+    for input in inputs {
+        hash_input.extend_from_slice(input);
+    }
+    hash_input.push(DOMAIN_BACK);
+    WedprSha256::default().hash(&hash_input)
+}
+
+/// Left pads a challenge to a 32-byte scalar.
+fn challenge_to_scalar(c: &[u8]) -> Vec<u8> {
+    let mut scalar = vec![0u8; SCALAR_SIZE_IN_BYTES - c.len()];
+    scalar.extend_from_slice(c);
+    scalar
+}
+
+fn multiply(point: &PublicKey, scalar: &[u8]) -> Result<PublicKey, WedprError> {
+    let mut output = *point;
+    match output.mul_assign(&SECP256K1_ALL, scalar) {
+        Ok(_) => Ok(output),
+        Err(_) => Err(WedprError::ArgumentError),
+    }
+}
+
+/// Computes a - scalar * b.
+fn subtract_multiple(
+    a: &PublicKey,
+    b: &PublicKey,
+    scalar: &[u8],
+) -> Result<PublicKey, WedprError> {
+    let mut negated = multiply(b, scalar)?;
+    negated.negate_assign(&SECP256K1_ALL);
+    match a.combine(&negated) {
+        Ok(v) => Ok(v),
+        Err(_) => Err(WedprError::ArgumentError),
+    }
+}
+
+fn parse_secret_key(private_key: &[u8]) -> Result<SecretKey, WedprError> {
+    match SecretKey::from_slice(private_key) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Parsing private key failed");
+            Err(WedprError::FormatError)
+        },
+    }
+}
+
+fn parse_public_key(public_key: &[u8]) -> Result<PublicKey, WedprError> {
+    match PublicKey::from_slice(public_key) {
+        Ok(v) => Ok(v),
+        Err(_) => {
+            wedpr_println!("Parsing public key failed");
+            Err(WedprError::FormatError)
+        },
+    }
+}
+
+/// Reduces a 32-byte big-endian integer modulo n, where one subtraction is
+/// enough since 2^256 < 2n.
+fn reduce_scalar(value: &[u8]) -> Vec<u8> {
+    if value < &CURVE_ORDER[..] {
+        return value.to_vec();
+    }
+    let mut output = vec![0u8; SCALAR_SIZE_IN_BYTES];
+    let mut borrow = 0i16;
+    for i in (0..SCALAR_SIZE_IN_BYTES).rev() {
+        let mut digit = value[i] as i16 - CURVE_ORDER[i] as i16 - borrow;
+        borrow = 0;
+        if digit < 0 {
+            digit += 256;
+            borrow = 1;
+        }
+        output[i] = digit as u8;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_signature_secp256k1::WedprSecp256k1Recover;
+    use wedpr_l_crypto_vrf_curve25519::WedprCurve25519Vrf;
+    use wedpr_l_utils::traits::Signature;
+
+    /// Proves and verifies with any VRF backend.
+    fn prove_and_verify<V: Vrf>(private_key: &[u8]) -> Vec<u8> {
+        let public_key = V::derive_public_key(private_key);
+        assert!(V::is_valid_public_key(&public_key));
+        let message = b"randomness beacon round 1".to_vec();
+        let proof =
+            V::prove_fast(private_key, &public_key[..], &message[..]).unwrap();
+        let decoded_proof = V::decode_proof(&proof.encode_proof()).unwrap();
+        assert!(decoded_proof.verify(&public_key[..], &message[..]));
+        assert!(!decoded_proof.verify(&public_key[..], &message[1..]));
+        proof.proof_to_hash().unwrap()
+    }
+
+    #[test]
+    fn test_vrf() {
+        // Reuses a secp256k1 signing key pair.
+        let (public_key, private_key) =
+            WedprSecp256k1Recover::default().generate_keypair();
+        let output = prove_and_verify::<WedprSecp256k1Vrf>(&private_key);
+        let message = b"message".to_vec();
+        let proof =
+            WedprSecp256k1Vrf::prove(&private_key[..], &message[..]).unwrap();
+        assert!(proof.verify(&public_key[..], &message[..]));
+        // Proofs are deterministic.
+        assert_eq!(
+            proof,
+            WedprSecp256k1Vrf::prove(&private_key[..], &message[..]).unwrap()
+        );
+        assert_ne!(proof.proof_to_hash().unwrap(), output);
+
+        let (another_public_key, _) = WedprSecp256k1Vrf::generate_keypair();
+        assert!(!proof.verify(&another_public_key[..], &message[..]));
+        assert!(WedprSecp256k1Vrf::decode_proof(&proof.encode_proof()[1..])
+            .is_err());
+        assert!(WedprSecp256k1Vrf::derive_public_key(&[0u8; 32]).is_empty());
+
+        assert_eq!(
+            Some(prove_and_verify::<WedprCurve25519Vrf>(&private_key).len()),
+            WedprCurve25519Vrf::algorithm_info().output_size
+        );
+    }
+}
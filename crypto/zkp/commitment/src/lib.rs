@@ -0,0 +1,129 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Pedersen commitment functions over Ristretto.
+//!
+//! A value v is committed as C = v * G1 + r * G2 with a blinding r, where G1
+//! and G2 are the same generators as range proofs use, so that a commitment
+//! can be proved to be in a range directly. Commitments are additively
+//! homomorphic: C(v1, r1) + C(v2, r2) = C(v1 + v2, r1 + r2).
+
+use curve25519_dalek::{
+    ristretto::RistrettoPoint,
+    scalar::Scalar,
+    traits::{Identity, MultiscalarMul},
+};
+use wedpr_l_crypto_zkp_utils::{get_random_scalar, BASEPOINT_G1, BASEPOINT_G2};
+
+/// Computes the commitment v * G1 + r * G2 of a scalar value.
+pub fn commit_scalar(value: &Scalar, blinding: &Scalar) -> RistrettoPoint {
+    RistrettoPoint::multiscalar_mul(&[*value, *blinding], &[
+        *BASEPOINT_G1,
+        *BASEPOINT_G2,
+    ])
+}
+
+/// Computes the commitment v * G1 + r * G2 of a value.
+pub fn commit_value(value: u64, blinding: &Scalar) -> RistrettoPoint {
+    commit_scalar(&Scalar::from(value), blinding)
+}
+
+/// Commits a value with a random blinding. It returns:
+/// 1) the point representing the commitment.
+/// 2) the random blinding value used in the above commitment.
+pub fn commit_value_with_random_blinding(
+    value: u64,
+) -> (RistrettoPoint, Scalar) {
+    let blinding = get_random_scalar();
+    (commit_value(value, &blinding), blinding)
+}
+
+/// Verifies that a commitment opens to a scalar value with a blinding.
+pub fn verify_opening(
+    commitment: &RistrettoPoint,
+    value: &Scalar,
+    blinding: &Scalar,
+) -> bool {
+    *commitment == commit_scalar(value, blinding)
+}
+
+/// Verifies that a commitment opens to a value with a blinding.
+pub fn verify_value_opening(
+    commitment: &RistrettoPoint,
+    value: u64,
+    blinding: &Scalar,
+) -> bool {
+    verify_opening(commitment, &Scalar::from(value), blinding)
+}
+
+/// Adds two commitments, which opens to the sums of the values and the
+/// blindings.
+pub fn add_commitments(
+    commitment1: &RistrettoPoint,
+    commitment2: &RistrettoPoint,
+) -> RistrettoPoint {
+    commitment1 + commitment2
+}
+
+/// Subtracts a commitment from another, which opens to the differences of the
+/// values and the blindings.
+pub fn subtract_commitments(
+    commitment1: &RistrettoPoint,
+    commitment2: &RistrettoPoint,
+) -> RistrettoPoint {
+    commitment1 - commitment2
+}
+
+/// Sums commitments, where the sum of none is the commitment of zero with a
+/// zero blinding.
+pub fn sum_commitments(commitments: &[RistrettoPoint]) -> RistrettoPoint {
+    commitments
+        .iter()
+        .fold(RistrettoPoint::identity(), |sum, commitment| {
+            sum + commitment
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_zkp_range_proof::{
+        prove_value_range_with_blinding, verify_value_range,
+    };
+
+    #[test]
+    fn test_commitment() {
+        let (commitment1, blinding1) = commit_value_with_random_blinding(30);
+        let (commitment2, blinding2) = commit_value_with_random_blinding(12);
+        assert!(verify_value_opening(&commitment1, 30, &blinding1));
+        assert!(!verify_value_opening(&commitment1, 31, &blinding1));
+        assert!(!verify_value_opening(&commitment1, 30, &blinding2));
+
+        let sum = add_commitments(&commitment1, &commitment2);
+        assert!(verify_value_opening(&sum, 42, &(blinding1 + blinding2)));
+        let difference = subtract_commitments(&commitment1, &commitment2);
+        assert!(verify_value_opening(
+            &difference,
+            18,
+            &(blinding1 - blinding2)
+        ));
+        // A negative difference opens to a negated scalar.
+        let difference = subtract_commitments(&commitment2, &commitment1);
+        assert!(verify_opening(
+            &difference,
+            &-Scalar::from(18u64),
+            &(blinding2 - blinding1)
+        ));
+        assert_eq!(sum_commitments(&[commitment1, commitment2]), sum);
+        assert!(verify_value_opening(
+            &sum_commitments(&[]),
+            0,
+            &Scalar::zero()
+        ));
+
+        // Commitments are compatible with range proofs.
+        let (proof, range_commitment) =
+            prove_value_range_with_blinding(30, &blinding1);
+        assert_eq!(range_commitment, commitment1);
+        assert!(verify_value_range(&commitment1, &proof));
+    }
+}
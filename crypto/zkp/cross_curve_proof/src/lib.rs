@@ -0,0 +1,500 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Zero-knowledge proof (ZKP) functions proving that a Pedersen commitment on
+//! Ristretto and a Pedersen commitment on secp256k1 hide the same value.
+//!
+//! The value v of 64 bits is decomposed into bits, and each bit b_i is
+//! committed on both curves. An OR proof shows that both bit commitments
+//! hide 0 or both hide 1, where the challenges are 128-bit integers shared
+//! by both curves. The bit blindings are chosen to sum up to the blindings
+//! of the full commitments, so that the verifier checks
+//! C = sum_i 2^i * C_i and D = sum_i 2^i * D_i.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+#[macro_use]
+extern crate lazy_static;
+
+use curve25519_dalek::{ristretto::RistrettoPoint, scalar::Scalar};
+use num_bigint::BigUint;
+use rand::Rng;
+use secp256k1::{constants::CURVE_ORDER, All, PublicKey, Secp256k1, SecretKey};
+use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+use wedpr_l_crypto_zkp_commitment::commit_value;
+use wedpr_l_crypto_zkp_utils::{
+    get_random_scalar, point_to_bytes, BASEPOINT_G1, BASEPOINT_G2,
+};
+use wedpr_l_utils::{
+    error::WedprError,
+    scalar::{Field256, Field256Element},
+    traits::Hash,
+};
+
+/// Size of the committed values in bits.
+pub const CROSS_CURVE_VALUE_SIZE_IN_BITS: usize = 64;
+/// Size of a secp256k1 scalar in bytes.
+const SECP256K1_SCALAR_SIZE_IN_BYTES: usize = 32;
+/// Domain separator of the secp256k1 blinding basepoint.
+const SECP256K1_BLINDING_BASEPOINT_DOMAIN: &[u8] =
+    b"WeDPR_SECP256K1_BLINDING_BASEPOINT";
+/// Domain separator of the Fiat-Shamir challenge.
+const CROSS_CURVE_PROOF_DOMAIN: &[u8] = b"WeDPR_CROSS_CURVE_EQUALITY";
+
+lazy_static! {
+    static ref SECP256K1_ALL: Secp256k1<All> = Secp256k1::new();
+    /// Constant-time arithmetic modulo the order of the secp256k1 group.
+    static ref SECP256K1_SCALAR_FIELD: Field256 = Field256::new(&CURVE_ORDER)
+        .expect("secp256k1 order should be valid");
+    /// Value basepoint G' of secp256k1 commitments.
+    pub static ref SECP256K1_VALUE_BASEPOINT: PublicKey = {
+        let mut one = [0u8; SECP256K1_SCALAR_SIZE_IN_BYTES];
+        one[SECP256K1_SCALAR_SIZE_IN_BYTES - 1] = 1;
+        PublicKey::from_secret_key(
+            &SECP256K1_ALL,
+            &SecretKey::from_slice(&one).expect("one should be a valid key"),
+        )
+    };
+    /// Blinding basepoint H' of secp256k1 commitments, whose discrete
+    /// logarithm to G' is unknown.
+    pub static ref SECP256K1_BLINDING_BASEPOINT: PublicKey =
+        hash_to_secp256k1_point(SECP256K1_BLINDING_BASEPOINT_DOMAIN);
+}
+
+/// Proof that a pair of bit commitments on both curves hide the same bit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitEqualityProof {
+    pub ristretto_commitment: RistrettoPoint,
+    pub secp256k1_commitment: PublicKey,
+    /// Challenges of the branches of bit 0 and bit 1, whose XOR is the
+    /// Fiat-Shamir challenge.
+    pub challenges: [u128; 2],
+    pub ristretto_responses: [Scalar; 2],
+    pub secp256k1_responses: [BigUint; 2],
+}
+
+/// Proof that commitments on Ristretto and secp256k1 hide the same value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrossCurveEqualityProof {
+    pub bit_proofs: Vec<BitEqualityProof>,
+}
+
+/// Maps bytes to a secp256k1 point by try-and-increment.
+fn hash_to_secp256k1_point(input: &[u8]) -> PublicKey {
+    let hash = WedprKeccak256::default();
+    let mut counter = 0u32;
+    loop {
+        let mut hash_vec = input.to_vec();
+        hash_vec.extend_from_slice(&counter.to_be_bytes());
+        let mut point_bytes = vec![0x02];
+        point_bytes.append(&mut hash.hash(&hash_vec));
+        if let Ok(point) = PublicKey::from_slice(&point_bytes) {
+            return point;
+        }
+        counter += 1;
+    }
+}
+
+/// Generates a random secp256k1 scalar.
+fn random_secp256k1_scalar() -> Field256Element {
+    SECP256K1_SCALAR_FIELD.random()
+}
+
+/// Gets the secp256k1 scalar of a challenge.
+fn challenge_to_secp256k1_scalar(
+    challenge: u128,
+) -> Result<Field256Element, WedprError> {
+    SECP256K1_SCALAR_FIELD.decode_reduced(&challenge.to_be_bytes())
+}
+
+/// Decodes a response, which fails if it is not less than the group order.
+fn biguint_to_secp256k1_scalar(
+    value: &BigUint,
+) -> Result<Field256Element, WedprError> {
+    let bytes = value.to_bytes_be();
+    if bytes.len() > SECP256K1_SCALAR_SIZE_IN_BYTES {
+        return Err(WedprError::FormatError);
+    }
+    SECP256K1_SCALAR_FIELD.decode(&bytes)
+}
+
+fn secp256k1_scalar_to_biguint(scalar: &Field256Element) -> BigUint {
+    BigUint::from_bytes_be(&scalar.to_bytes())
+}
+
+/// Computes scalar * point, which fails if the result is the infinity.
+fn secp256k1_mul(
+    point: &PublicKey,
+    scalar: &Field256Element,
+) -> Result<PublicKey, WedprError> {
+    if bool::from(scalar.is_zero()) {
+        return Err(WedprError::ArgumentError);
+    }
+    let mut result = *point;
+    match result.mul_assign(&SECP256K1_ALL, &scalar.to_bytes()) {
+        Ok(_) => Ok(result),
+        Err(_) => Err(WedprError::ArgumentError),
+    }
+}
+
+/// Computes point1 + point2, which fails if the result is the infinity.
+fn secp256k1_add(
+    point1: &PublicKey,
+    point2: &PublicKey,
+) -> Result<PublicKey, WedprError> {
+    match point1.combine(point2) {
+        Ok(v) => Ok(v),
+        Err(_) => Err(WedprError::ArgumentError),
+    }
+}
+
+fn secp256k1_neg(point: &PublicKey) -> PublicKey {
+    let mut result = *point;
+    result.negate_assign(&SECP256K1_ALL);
+    result
+}
+
+/// Computes scalar1 * point1 - scalar2 * point2.
+fn secp256k1_mul_sub(
+    scalar1: &Field256Element,
+    point1: &PublicKey,
+    scalar2: &Field256Element,
+    point2: &PublicKey,
+) -> Result<PublicKey, WedprError> {
+    secp256k1_add(
+        &secp256k1_mul(point1, scalar1)?,
+        &secp256k1_neg(&secp256k1_mul(point2, scalar2)?),
+    )
+}
+
+/// Computes the Pedersen commitment v * G' + s * H' of a value on secp256k1,
+/// where the blinding s is a 32-byte big-endian scalar.
+pub fn commit_value_secp256k1(
+    value: u64,
+    blinding: &[u8],
+) -> Result<PublicKey, WedprError> {
+    if blinding.len() != SECP256K1_SCALAR_SIZE_IN_BYTES {
+        return Err(WedprError::FormatError);
+    }
+    commit_secp256k1(
+        &SECP256K1_SCALAR_FIELD.element_from_u64(value),
+        &SECP256K1_SCALAR_FIELD.decode_reduced(blinding)?,
+    )
+}
+
+fn commit_secp256k1(
+    value: &Field256Element,
+    blinding: &Field256Element,
+) -> Result<PublicKey, WedprError> {
+    let blinding_point =
+        secp256k1_mul(&SECP256K1_BLINDING_BASEPOINT, blinding)?;
+    if bool::from(value.is_zero()) {
+        return Ok(blinding_point);
+    }
+    secp256k1_add(
+        &secp256k1_mul(&SECP256K1_VALUE_BASEPOINT, value)?,
+        &blinding_point,
+    )
+}
+
+/// Computes the Pedersen commitment v * G1 + r * G2 of a value on Ristretto.
+pub fn commit_value_ristretto(value: u64, blinding: &Scalar) -> RistrettoPoint {
+    commit_value(value, blinding)
+}
+
+/// Gets the bit commitments with the value of a bit subtracted, i.e. the
+/// statement points of the branch of the bit.
+fn bit_statement(
+    proof: &BitEqualityProof,
+    bit: usize,
+) -> Result<(RistrettoPoint, PublicKey), WedprError> {
+    if bit == 0 {
+        return Ok((proof.ristretto_commitment, proof.secp256k1_commitment));
+    }
+    Ok((
+        proof.ristretto_commitment - *BASEPOINT_G1,
+        secp256k1_add(
+            &proof.secp256k1_commitment,
+            &secp256k1_neg(&SECP256K1_VALUE_BASEPOINT),
+        )?,
+    ))
+}
+
+fn bit_challenge(
+    index: usize,
+    ristretto_commitment: &RistrettoPoint,
+    secp256k1_commitment: &PublicKey,
+    proof: &BitEqualityProof,
+    nonce_commitments: &[(RistrettoPoint, PublicKey); 2],
+) -> u128 {
+    let mut hash_vec = CROSS_CURVE_PROOF_DOMAIN.to_vec();
+    hash_vec.extend_from_slice(&(index as u32).to_be_bytes());
+    hash_vec.append(&mut point_to_bytes(ristretto_commitment));
+    hash_vec.extend_from_slice(&secp256k1_commitment.serialize());
+    hash_vec.append(&mut point_to_bytes(&proof.ristretto_commitment));
+    hash_vec.extend_from_slice(&proof.secp256k1_commitment.serialize());
+    for (ristretto_nonce, secp256k1_nonce) in nonce_commitments {
+        hash_vec.append(&mut point_to_bytes(ristretto_nonce));
+        hash_vec.extend_from_slice(&secp256k1_nonce.serialize());
+    }
+    let hash = WedprKeccak256::default().hash(&hash_vec);
+    let mut challenge_bytes = [0u8; 16];
+    challenge_bytes.copy_from_slice(&hash[..16]);
+    u128::from_be_bytes(challenge_bytes)
+}
+
+/// Recomputes the nonce commitments of a branch from its challenge and
+/// responses, i.e. z * H - e * X on both curves.
+fn branch_nonce_commitments(
+    statement: &(RistrettoPoint, PublicKey),
+    challenge: u128,
+    ristretto_response: &Scalar,
+    secp256k1_response: &Field256Element,
+) -> Result<(RistrettoPoint, PublicKey), WedprError> {
+    Ok((
+        ristretto_response * *BASEPOINT_G2
+            - Scalar::from(challenge) * statement.0,
+        secp256k1_mul_sub(
+            secp256k1_response,
+            &SECP256K1_BLINDING_BASEPOINT,
+            &challenge_to_secp256k1_scalar(challenge)?,
+            &statement.1,
+        )?,
+    ))
+}
+
+/// Splits a blinding into bit blindings b_i satisfying
+/// sum_i 2^i * b_i = blinding, for Ristretto.
+fn split_ristretto_blinding(blinding: &Scalar) -> Vec<Scalar> {
+    let mut blindings: Vec<Scalar> = (0..CROSS_CURVE_VALUE_SIZE_IN_BITS - 1)
+        .map(|_| get_random_scalar())
+        .collect();
+    let mut remainder = *blinding;
+    let mut power = Scalar::one();
+    for bit_blinding in &blindings {
+        remainder -= power * bit_blinding;
+        power += power;
+    }
+    blindings.push(remainder * power.invert());
+    blindings
+}
+
+/// Splits a blinding into bit blindings b_i satisfying
+/// sum_i 2^i * b_i = blinding, for secp256k1.
+fn split_secp256k1_blinding(
+    blinding: &Field256Element,
+) -> Result<Vec<Field256Element>, WedprError> {
+    let mut blindings = (0..CROSS_CURVE_VALUE_SIZE_IN_BITS - 1)
+        .map(|_| random_secp256k1_scalar())
+        .collect::<Vec<Field256Element>>();
+    let mut remainder = blinding.clone();
+    let mut power = SECP256K1_SCALAR_FIELD.element_from_u64(1);
+    for bit_blinding in &blindings {
+        remainder = remainder.sub(&power.mul(bit_blinding));
+        power = power.add(&power);
+    }
+    blindings.push(remainder.mul(&power.inverse()?));
+    Ok(blindings)
+}
+
+/// Proves that the commitments of a value on Ristretto with the given
+/// blinding and on secp256k1 with the given 32-byte big-endian blinding hide
+/// the same value.
+pub fn prove_cross_curve_equality(
+    value: u64,
+    ristretto_blinding: &Scalar,
+    secp256k1_blinding: &[u8],
+) -> Result<CrossCurveEqualityProof, WedprError> {
+    let ristretto_commitment =
+        commit_value_ristretto(value, ristretto_blinding);
+    let secp256k1_commitment =
+        commit_value_secp256k1(value, secp256k1_blinding)?;
+    let ristretto_blindings = split_ristretto_blinding(ristretto_blinding);
+    let secp256k1_blindings = split_secp256k1_blinding(
+        &SECP256K1_SCALAR_FIELD.decode_reduced(secp256k1_blinding)?,
+    )?;
+    let mut rng = rand::thread_rng();
+
+    let mut bit_proofs = Vec::with_capacity(CROSS_CURVE_VALUE_SIZE_IN_BITS);
+    for i in 0..CROSS_CURVE_VALUE_SIZE_IN_BITS {
+        let bit = ((value >> i) & 1) as usize;
+        let r = &ristretto_blindings[i];
+        let s = &secp256k1_blindings[i];
+        let mut proof = BitEqualityProof {
+            ristretto_commitment: commit_value_ristretto(bit as u64, r),
+            secp256k1_commitment: commit_secp256k1(
+                &SECP256K1_SCALAR_FIELD.element_from_u64(bit as u64),
+                s,
+            )?,
+            challenges: [0; 2],
+            ristretto_responses: [Scalar::zero(); 2],
+            secp256k1_responses: [BigUint::default(), BigUint::default()],
+        };
+
+        // Simulates the branch of the other bit.
+        let other_bit = 1 - bit;
+        let other_challenge: u128 = rng.gen();
+        let other_ristretto_response = get_random_scalar();
+        let other_secp256k1_response = random_secp256k1_scalar();
+        let other_nonce_commitments = branch_nonce_commitments(
+            &bit_statement(&proof, other_bit)?,
+            other_challenge,
+            &other_ristretto_response,
+            &other_secp256k1_response,
+        )?;
+
+        // Commits nonces for the branch of the real bit.
+        let ristretto_nonce = get_random_scalar();
+        let secp256k1_nonce = random_secp256k1_scalar();
+        let nonce_commitments = (
+            ristretto_nonce * *BASEPOINT_G2,
+            secp256k1_mul(&SECP256K1_BLINDING_BASEPOINT, &secp256k1_nonce)?,
+        );
+        let all_nonce_commitments = if bit == 0 {
+            [nonce_commitments, other_nonce_commitments]
+        } else {
+            [other_nonce_commitments, nonce_commitments]
+        };
+
+        let challenge = bit_challenge(
+            i,
+            &ristretto_commitment,
+            &secp256k1_commitment,
+            &proof,
+            &all_nonce_commitments,
+        );
+        let branch_challenge = challenge ^ other_challenge;
+        proof.challenges[bit] = branch_challenge;
+        proof.challenges[other_bit] = other_challenge;
+        proof.ristretto_responses[bit] =
+            ristretto_nonce + Scalar::from(branch_challenge) * r;
+        proof.secp256k1_responses[bit] = secp256k1_scalar_to_biguint(
+            &secp256k1_nonce
+                .add(&challenge_to_secp256k1_scalar(branch_challenge)?.mul(s)),
+        );
+        proof.ristretto_responses[other_bit] = other_ristretto_response;
+        proof.secp256k1_responses[other_bit] =
+            secp256k1_scalar_to_biguint(&other_secp256k1_response);
+        bit_proofs.push(proof);
+    }
+    Ok(CrossCurveEqualityProof { bit_proofs })
+}
+
+/// Verifies that a commitment on Ristretto and a commitment on secp256k1
+/// hide the same value.
+pub fn verify_cross_curve_equality(
+    ristretto_commitment: &RistrettoPoint,
+    secp256k1_commitment: &PublicKey,
+    proof: &CrossCurveEqualityProof,
+) -> bool {
+    if proof.bit_proofs.len() != CROSS_CURVE_VALUE_SIZE_IN_BITS {
+        return false;
+    }
+    match verify_bit_proofs(ristretto_commitment, secp256k1_commitment, proof) {
+        Ok(v) => v,
+        Err(_) => {
+            wedpr_println!("Cross-curve equality proof is malformed");
+            false
+        },
+    }
+}
+
+fn verify_bit_proofs(
+    ristretto_commitment: &RistrettoPoint,
+    secp256k1_commitment: &PublicKey,
+    proof: &CrossCurveEqualityProof,
+) -> Result<bool, WedprError> {
+    let mut ristretto_sum = RistrettoPoint::default();
+    let mut secp256k1_sum: Option<PublicKey> = None;
+    let mut power = Scalar::one();
+    for (i, bit_proof) in proof.bit_proofs.iter().enumerate() {
+        let mut nonce_commitments = Vec::with_capacity(2);
+        for bit in 0..2 {
+            let secp256k1_response = match biguint_to_secp256k1_scalar(
+                &bit_proof.secp256k1_responses[bit],
+            ) {
+                Ok(v) => v,
+                Err(_) => return Ok(false),
+            };
+            nonce_commitments.push(branch_nonce_commitments(
+                &bit_statement(bit_proof, bit)?,
+                bit_proof.challenges[bit],
+                &bit_proof.ristretto_responses[bit],
+                &secp256k1_response,
+            )?);
+        }
+        let challenge = bit_challenge(
+            i,
+            ristretto_commitment,
+            secp256k1_commitment,
+            bit_proof,
+            &[nonce_commitments[0], nonce_commitments[1]],
+        );
+        if bit_proof.challenges[0] ^ bit_proof.challenges[1] != challenge {
+            return Ok(false);
+        }
+
+        ristretto_sum += power * bit_proof.ristretto_commitment;
+        power += power;
+        let secp256k1_term = secp256k1_mul(
+            &bit_proof.secp256k1_commitment,
+            &SECP256K1_SCALAR_FIELD.element_from_u64(1 << i),
+        )?;
+        secp256k1_sum = match secp256k1_sum {
+            Some(sum) => Some(secp256k1_add(&sum, &secp256k1_term)?),
+            None => Some(secp256k1_term),
+        };
+    }
+    Ok(ristretto_sum == *ristretto_commitment
+        && secp256k1_sum.as_ref() == Some(secp256k1_commitment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cross_curve_equality_proof() {
+        let value = 0x1234_5678_9abc_def0u64;
+        let ristretto_blinding = get_random_scalar();
+        let secp256k1_blinding = random_secp256k1_scalar().to_bytes();
+        let ristretto_commitment =
+            commit_value_ristretto(value, &ristretto_blinding);
+        let secp256k1_commitment =
+            commit_value_secp256k1(value, &secp256k1_blinding).unwrap();
+        let proof = prove_cross_curve_equality(
+            value,
+            &ristretto_blinding,
+            &secp256k1_blinding,
+        )
+        .unwrap();
+        assert!(verify_cross_curve_equality(
+            &ristretto_commitment,
+            &secp256k1_commitment,
+            &proof
+        ));
+
+        let other_commitment =
+            commit_value_ristretto(value + 1, &ristretto_blinding);
+        assert!(!verify_cross_curve_equality(
+            &other_commitment,
+            &secp256k1_commitment,
+            &proof
+        ));
+        let mut tampered_proof = proof.clone();
+        tampered_proof.bit_proofs[0].challenges[0] ^= 1;
+        assert!(!verify_cross_curve_equality(
+            &ristretto_commitment,
+            &secp256k1_commitment,
+            &tampered_proof
+        ));
+        let mut tampered_proof = proof.clone();
+        tampered_proof.bit_proofs[0].secp256k1_responses[0] =
+            BigUint::from_bytes_be(&CURVE_ORDER);
+        assert!(!verify_cross_curve_equality(
+            &ristretto_commitment,
+            &secp256k1_commitment,
+            &tampered_proof
+        ));
+        assert!(commit_value_secp256k1(value, &[0u8; 31]).is_err());
+    }
+}
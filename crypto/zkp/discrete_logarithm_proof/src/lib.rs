@@ -2,6 +2,11 @@
 
 //! Zero-knowledge proof (ZKP) functions based on DLP construction.
 
+#[macro_use]
+extern crate wedpr_l_macros;
+
+pub mod sigma;
+
 use curve25519_dalek::{
     ristretto::RistrettoPoint, scalar::Scalar, traits::MultiscalarMul,
 };
@@ -305,18 +310,15 @@ mod tests {
             &[value_basepoint, blinding_basepoint],
         );
 
-        assert_eq!(
-            true,
-            verify_sum_relationship(
-                &c1_point,
-                &c2_point,
-                &c3_point,
-                &proof,
-                &value_basepoint,
-                &blinding_basepoint
-            )
-            .unwrap()
-        );
+        assert!(verify_sum_relationship(
+            &c1_point,
+            &c2_point,
+            &c3_point,
+            &proof,
+            &value_basepoint,
+            &blinding_basepoint
+        )
+        .unwrap());
     }
 
     #[test]
@@ -352,17 +354,14 @@ mod tests {
             &[value_basepoint, blinding_basepoint],
         );
 
-        assert_eq!(
-            true,
-            verify_product_relationship(
-                &c1_point,
-                &c2_point,
-                &c3_point,
-                &proof,
-                &value_basepoint,
-                &blinding_basepoint
-            )
-            .unwrap()
-        );
+        assert!(verify_product_relationship(
+            &c1_point,
+            &c2_point,
+            &c3_point,
+            &proof,
+            &value_basepoint,
+            &blinding_basepoint
+        )
+        .unwrap());
     }
 }
@@ -0,0 +1,370 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Fiat-Shamir transformed sigma proofs of discrete logarithm relations, i.e.
+//! knowledge of a discrete logarithm, equality of discrete logarithms across
+//! bases, and linear relations between values of Pedersen commitments, e.g.
+//! for selective disclosure of committed credential attributes.
+
+use curve25519_dalek::{
+    ristretto::RistrettoPoint, scalar::Scalar, traits::MultiscalarMul,
+};
+use wedpr_l_crypto_zkp_utils::{
+    get_random_scalar, hash_to_scalar, point_to_bytes, scalar_to_bytes,
+};
+use wedpr_l_utils::error::WedprError;
+
+/// Domain separators of the Fiat-Shamir challenges.
+const KNOWLEDGE_PROOF_DOMAIN: &[u8] = b"WeDPR_DLOG_KNOWLEDGE";
+const EQUALITY_PROOF_DOMAIN: &[u8] = b"WeDPR_DLOG_EQUALITY";
+const LINEAR_RELATION_PROOF_DOMAIN: &[u8] = b"WeDPR_LINEAR_RELATION";
+
+/// Sigma proof of a challenge and a response, where the response is
+/// blinding - challenge * secret.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct SigmaProof {
+    pub challenge: Scalar,
+    pub response: Scalar,
+}
+
+/// Computes the challenge over the domain and points in order.
+fn sigma_challenge(domain: &[u8], points: &[RistrettoPoint]) -> Scalar {
+    let mut hash_vec = domain.to_vec();
+    for point in points {
+        hash_vec.append(&mut point_to_bytes(point));
+    }
+    hash_to_scalar(&hash_vec)
+}
+
+/// Proves the knowledge of secret for the public point secret * basepoint.
+pub fn prove_knowledge(
+    secret: &Scalar,
+    basepoint: &RistrettoPoint,
+) -> SigmaProof {
+    let public_point = secret * basepoint;
+    let blinding = get_random_scalar();
+    let challenge = sigma_challenge(KNOWLEDGE_PROOF_DOMAIN, &[
+        *basepoint,
+        public_point,
+        blinding * basepoint,
+    ]);
+    SigmaProof {
+        challenge,
+        response: blinding - challenge * secret,
+    }
+}
+
+/// Verifies the knowledge of the discrete logarithm of public_point to
+/// basepoint.
+pub fn verify_knowledge(
+    public_point: &RistrettoPoint,
+    proof: &SigmaProof,
+    basepoint: &RistrettoPoint,
+) -> bool {
+    let nonce_commitment =
+        RistrettoPoint::multiscalar_mul(&[proof.response, proof.challenge], &[
+            *basepoint,
+            *public_point,
+        ]);
+    let computed = sigma_challenge(KNOWLEDGE_PROOF_DOMAIN, &[
+        *basepoint,
+        *public_point,
+        nonce_commitment,
+    ]);
+    computed.eq(&proof.challenge)
+}
+
+/// Proves that public points secret * basepoint1 and secret * basepoint2 have
+/// the same discrete logarithm secret.
+pub fn prove_equality(
+    secret: &Scalar,
+    basepoint1: &RistrettoPoint,
+    basepoint2: &RistrettoPoint,
+) -> SigmaProof {
+    let blinding = get_random_scalar();
+    let challenge = sigma_challenge(EQUALITY_PROOF_DOMAIN, &[
+        *basepoint1,
+        secret * basepoint1,
+        *basepoint2,
+        secret * basepoint2,
+        blinding * basepoint1,
+        blinding * basepoint2,
+    ]);
+    SigmaProof {
+        challenge,
+        response: blinding - challenge * secret,
+    }
+}
+
+/// Verifies that the discrete logarithm of point1 to basepoint1 equals that
+/// of point2 to basepoint2.
+pub fn verify_equality(
+    point1: &RistrettoPoint,
+    point2: &RistrettoPoint,
+    proof: &SigmaProof,
+    basepoint1: &RistrettoPoint,
+    basepoint2: &RistrettoPoint,
+) -> bool {
+    let nonce_commitment1 =
+        RistrettoPoint::multiscalar_mul(&[proof.response, proof.challenge], &[
+            *basepoint1,
+            *point1,
+        ]);
+    let nonce_commitment2 =
+        RistrettoPoint::multiscalar_mul(&[proof.response, proof.challenge], &[
+            *basepoint2,
+            *point2,
+        ]);
+    let computed = sigma_challenge(EQUALITY_PROOF_DOMAIN, &[
+        *basepoint1,
+        *point1,
+        *basepoint2,
+        *point2,
+        nonce_commitment1,
+        nonce_commitment2,
+    ]);
+    computed.eq(&proof.challenge)
+}
+
+/// Computes sum_i coefficients[i] * C_i - constant * value_basepoint, which
+/// commits to 0 with the blinding sum_i coefficients[i] * r_i if the linear
+/// relation holds.
+fn linear_relation_point(
+    commitments: &[RistrettoPoint],
+    coefficients: &[Scalar],
+    constant: &Scalar,
+    value_basepoint: &RistrettoPoint,
+) -> RistrettoPoint {
+    let scalars = coefficients
+        .iter()
+        .copied()
+        .chain(std::iter::once(-constant));
+    let points = commitments
+        .iter()
+        .copied()
+        .chain(std::iter::once(*value_basepoint));
+    RistrettoPoint::multiscalar_mul(scalars, points)
+}
+
+fn linear_relation_challenge(
+    commitments: &[RistrettoPoint],
+    coefficients: &[Scalar],
+    constant: &Scalar,
+    value_basepoint: &RistrettoPoint,
+    blinding_basepoint: &RistrettoPoint,
+    nonce_commitment: &RistrettoPoint,
+) -> Scalar {
+    let mut hash_vec = LINEAR_RELATION_PROOF_DOMAIN.to_vec();
+    hash_vec.append(&mut point_to_bytes(value_basepoint));
+    hash_vec.append(&mut point_to_bytes(blinding_basepoint));
+    for (commitment, coefficient) in commitments.iter().zip(coefficients) {
+        hash_vec.append(&mut point_to_bytes(commitment));
+        hash_vec.append(&mut scalar_to_bytes(coefficient));
+    }
+    hash_vec.append(&mut scalar_to_bytes(constant));
+    hash_vec.append(&mut point_to_bytes(nonce_commitment));
+    hash_to_scalar(&hash_vec)
+}
+
+/// Proves that the values embedded in commitments
+/// C_i = values[i] * value_basepoint + blindings[i] * blinding_basepoint
+/// satisfy the linear relation sum_i coefficients[i] * values[i] = constant,
+/// where revealing a single value is the relation of one coefficient 1.
+pub fn prove_linear_relation(
+    values: &[Scalar],
+    blindings: &[Scalar],
+    coefficients: &[Scalar],
+    constant: &Scalar,
+    value_basepoint: &RistrettoPoint,
+    blinding_basepoint: &RistrettoPoint,
+) -> Result<SigmaProof, WedprError> {
+    if values.is_empty()
+        || values.len() != blindings.len()
+        || values.len() != coefficients.len()
+    {
+        return Err(WedprError::ArgumentError);
+    }
+    let value_sum = values
+        .iter()
+        .zip(coefficients)
+        .fold(Scalar::zero(), |sum, (value, coefficient)| {
+            sum + coefficient * value
+        });
+    if value_sum != *constant {
+        wedpr_println!("Linear relation does not hold");
+        return Err(WedprError::ArgumentError);
+    }
+    let commitments: Vec<RistrettoPoint> = values
+        .iter()
+        .zip(blindings)
+        .map(|(value, blinding)| {
+            RistrettoPoint::multiscalar_mul(&[*value, *blinding], &[
+                *value_basepoint,
+                *blinding_basepoint,
+            ])
+        })
+        .collect();
+    let blinding_sum = blindings
+        .iter()
+        .zip(coefficients)
+        .fold(Scalar::zero(), |sum, (blinding, coefficient)| {
+            sum + coefficient * blinding
+        });
+    let blinding = get_random_scalar();
+    let challenge = linear_relation_challenge(
+        &commitments,
+        coefficients,
+        constant,
+        value_basepoint,
+        blinding_basepoint,
+        &(blinding * blinding_basepoint),
+    );
+    Ok(SigmaProof {
+        challenge,
+        response: blinding - challenge * blinding_sum,
+    })
+}
+
+/// Verifies that the values embedded in commitments satisfy the linear
+/// relation sum_i coefficients[i] * values[i] = constant.
+pub fn verify_linear_relation(
+    commitments: &[RistrettoPoint],
+    coefficients: &[Scalar],
+    constant: &Scalar,
+    proof: &SigmaProof,
+    value_basepoint: &RistrettoPoint,
+    blinding_basepoint: &RistrettoPoint,
+) -> bool {
+    if commitments.is_empty() || commitments.len() != coefficients.len() {
+        return false;
+    }
+    let relation_point = linear_relation_point(
+        commitments,
+        coefficients,
+        constant,
+        value_basepoint,
+    );
+    let nonce_commitment =
+        RistrettoPoint::multiscalar_mul(&[proof.response, proof.challenge], &[
+            *blinding_basepoint,
+            relation_point,
+        ]);
+    let computed = linear_relation_challenge(
+        commitments,
+        coefficients,
+        constant,
+        value_basepoint,
+        blinding_basepoint,
+        &nonce_commitment,
+    );
+    computed.eq(&proof.challenge)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wedpr_l_crypto_zkp_utils::{BASEPOINT_G1, BASEPOINT_G2};
+
+    #[test]
+    fn test_sigma_proofs() {
+        let value_basepoint = *BASEPOINT_G1;
+        let blinding_basepoint = *BASEPOINT_G2;
+        let secret = get_random_scalar();
+        let public_point = secret * value_basepoint;
+        let proof = prove_knowledge(&secret, &value_basepoint);
+        assert!(verify_knowledge(&public_point, &proof, &value_basepoint));
+        assert!(!verify_knowledge(
+            &public_point,
+            &proof,
+            &blinding_basepoint
+        ));
+
+        let proof =
+            prove_equality(&secret, &value_basepoint, &blinding_basepoint);
+        let point2 = secret * blinding_basepoint;
+        assert!(verify_equality(
+            &public_point,
+            &point2,
+            &proof,
+            &value_basepoint,
+            &blinding_basepoint
+        ));
+        assert!(!verify_equality(
+            &public_point,
+            &(get_random_scalar() * blinding_basepoint),
+            &proof,
+            &value_basepoint,
+            &blinding_basepoint
+        ));
+
+        // 2 * v1 + 3 * v2 - v3 = 0
+        let values =
+            vec![Scalar::from(1u64), Scalar::from(2u64), Scalar::from(8u64)];
+        let blindings: Vec<Scalar> =
+            (0..values.len()).map(|_| get_random_scalar()).collect();
+        let coefficients =
+            vec![Scalar::from(2u64), Scalar::from(3u64), -Scalar::one()];
+        let commitments: Vec<RistrettoPoint> = values
+            .iter()
+            .zip(&blindings)
+            .map(|(value, blinding)| {
+                value * value_basepoint + blinding * blinding_basepoint
+            })
+            .collect();
+        let proof = prove_linear_relation(
+            &values,
+            &blindings,
+            &coefficients,
+            &Scalar::zero(),
+            &value_basepoint,
+            &blinding_basepoint,
+        )
+        .unwrap();
+        assert!(verify_linear_relation(
+            &commitments,
+            &coefficients,
+            &Scalar::zero(),
+            &proof,
+            &value_basepoint,
+            &blinding_basepoint
+        ));
+        assert!(!verify_linear_relation(
+            &commitments,
+            &coefficients,
+            &Scalar::one(),
+            &proof,
+            &value_basepoint,
+            &blinding_basepoint
+        ));
+        assert_eq!(
+            prove_linear_relation(
+                &values,
+                &blindings,
+                &coefficients,
+                &Scalar::one(),
+                &value_basepoint,
+                &blinding_basepoint,
+            )
+            .unwrap_err(),
+            WedprError::ArgumentError
+        );
+
+        // Discloses v2 = 2 only.
+        let proof = prove_linear_relation(
+            &values[1..2],
+            &blindings[1..2],
+            &[Scalar::one()],
+            &Scalar::from(2u64),
+            &value_basepoint,
+            &blinding_basepoint,
+        )
+        .unwrap();
+        assert!(verify_linear_relation(
+            &commitments[1..2],
+            &[Scalar::one()],
+            &Scalar::from(2u64),
+            &proof,
+            &value_basepoint,
+            &blinding_basepoint
+        ));
+    }
+}
@@ -0,0 +1,363 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Zero-knowledge proof (ZKP) functions for Paillier ciphertexts.
+//!
+//! A sigma protocol proves that a Paillier ciphertext
+//! c = (1 + n)^v * rho^n mod n^2 and a Pedersen commitment
+//! C = v * G1 + r * G2 hide the same value v, which also proves the knowledge
+//! of the plaintext and the randomness of c. Combined with range proofs of
+//! the commitment, it proves that a ciphertext encrypts a value in a range.
+//! The protocols are made non-interactive by the Fiat-Shamir heuristic.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+use curve25519_dalek::{
+    ristretto::RistrettoPoint, scalar::Scalar, traits::MultiscalarMul,
+};
+use num_bigint::BigUint;
+use num_traits::Zero;
+use wedpr_l_crypto_hash_keccak256::WedprKeccak256;
+use wedpr_l_crypto_paillier::{
+    mod_mul, mod_pow, random_bits, random_unit, PaillierPublicKey,
+};
+use wedpr_l_crypto_zkp_range_proof::{
+    prove_value_range_with_blinding, verify_value_range,
+};
+use wedpr_l_crypto_zkp_utils::{
+    get_random_scalar, point_to_bytes, BASEPOINT_G1, BASEPOINT_G2,
+};
+use wedpr_l_utils::{error::WedprError, traits::Hash};
+
+/// Size of the Fiat-Shamir challenge in bits, which is below the smallest
+/// prime factor of any supported Paillier modulus.
+const CHALLENGE_SIZE_IN_BITS: usize = 128;
+/// Statistical security parameter of the responses in bits.
+const STATISTICAL_SECURITY_IN_BITS: usize = 80;
+/// Size of the committed values in bits.
+const VALUE_SIZE_IN_BITS: usize = 64;
+/// Size of the range proven by a single range proof in bits.
+const RANGE_SIZE_IN_BITS: u32 = 32;
+/// Domain separator of the Fiat-Shamir challenge.
+const PAILLIER_PROOF_DOMAIN: &[u8] = b"WeDPR_PAILLIER_PEDERSEN_EQUALITY";
+
+/// Proof that a Paillier ciphertext and a Pedersen commitment hide the same
+/// value.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct EncryptionEqualityProof {
+    /// (1 + n)^alpha * beta^n mod n^2.
+    pub ciphertext_commitment: BigUint,
+    /// alpha * G1 + gamma * G2.
+    pub point_commitment: RistrettoPoint,
+    /// alpha + e * v.
+    pub value_response: BigUint,
+    /// beta * rho^e mod n.
+    pub randomness_response: BigUint,
+    /// gamma + e * r.
+    pub blinding_response: Scalar,
+}
+
+/// Proof that a Paillier ciphertext encrypts a value in [lower, upper].
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct EncryptionRangeProof {
+    pub commitment: RistrettoPoint,
+    pub equality_proof: EncryptionEqualityProof,
+    /// Range proof of v - lower.
+    pub lower_range_proof: Vec<u8>,
+    /// Range proof of upper - v.
+    pub upper_range_proof: Vec<u8>,
+}
+
+/// Converts a non-negative integer to Scalar modulo the group order.
+fn biguint_to_scalar(value: &BigUint) -> Scalar {
+    let mut bytes = [0u8; 64];
+    let value_bytes = value.to_bytes_le();
+    bytes[..value_bytes.len()].copy_from_slice(&value_bytes);
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+/// Computes the Pedersen commitment v * G1 + r * G2 of a value.
+pub fn commit_value(value: u64, blinding: &Scalar) -> RistrettoPoint {
+    RistrettoPoint::multiscalar_mul(&[Scalar::from(value), *blinding], &[
+        *BASEPOINT_G1,
+        *BASEPOINT_G2,
+    ])
+}
+
+fn equality_challenge(
+    public_key: &PaillierPublicKey,
+    ciphertext: &BigUint,
+    commitment: &RistrettoPoint,
+    ciphertext_commitment: &BigUint,
+    point_commitment: &RistrettoPoint,
+) -> BigUint {
+    let mut hash_vec = PAILLIER_PROOF_DOMAIN.to_vec();
+    for item in &[&public_key.n, ciphertext, ciphertext_commitment] {
+        let bytes = item.to_bytes_be();
+        hash_vec.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        hash_vec.extend_from_slice(&bytes);
+    }
+    hash_vec.append(&mut point_to_bytes(commitment));
+    hash_vec.append(&mut point_to_bytes(point_commitment));
+    let hash = WedprKeccak256::default().hash(&hash_vec);
+    BigUint::from_bytes_be(&hash[..CHALLENGE_SIZE_IN_BITS / 8])
+}
+
+/// Proves that a ciphertext encrypted with the given randomness and a
+/// commitment with the given blinding hide the same value.
+pub fn prove_encryption_equality(
+    public_key: &PaillierPublicKey,
+    value: u64,
+    randomness: &BigUint,
+    blinding: &Scalar,
+) -> Result<EncryptionEqualityProof, WedprError> {
+    let value_biguint = BigUint::from(value);
+    if value_biguint >= public_key.n
+        || randomness.is_zero()
+        || randomness >= &public_key.n
+    {
+        wedpr_println!("Invalid Paillier encryption witness");
+        return Err(WedprError::ArgumentError);
+    }
+    let ciphertext =
+        public_key.encrypt_with_randomness(&value_biguint, randomness);
+    let commitment = commit_value(value, blinding);
+
+    let alpha = random_bits(
+        VALUE_SIZE_IN_BITS
+            + CHALLENGE_SIZE_IN_BITS
+            + STATISTICAL_SECURITY_IN_BITS,
+    );
+    let beta = random_unit(&public_key.n);
+    let gamma = get_random_scalar();
+    let ciphertext_commitment =
+        public_key.encrypt_with_randomness(&alpha, &beta);
+    let point_commitment = RistrettoPoint::multiscalar_mul(
+        &[biguint_to_scalar(&alpha), gamma],
+        &[*BASEPOINT_G1, *BASEPOINT_G2],
+    );
+    let challenge = equality_challenge(
+        public_key,
+        &ciphertext,
+        &commitment,
+        &ciphertext_commitment,
+        &point_commitment,
+    );
+    Ok(EncryptionEqualityProof {
+        ciphertext_commitment,
+        point_commitment,
+        value_response: alpha + &challenge * value_biguint,
+        randomness_response: mod_mul(
+            &beta,
+            &mod_pow(randomness, &challenge, &public_key.n),
+            &public_key.n,
+        ),
+        blinding_response: gamma + biguint_to_scalar(&challenge) * blinding,
+    })
+}
+
+/// Verifies that a ciphertext and a commitment hide the same value.
+pub fn verify_encryption_equality(
+    public_key: &PaillierPublicKey,
+    ciphertext: &BigUint,
+    commitment: &RistrettoPoint,
+    proof: &EncryptionEqualityProof,
+) -> bool {
+    let n_squared = &public_key.n_squared;
+    if ciphertext.is_zero()
+        || ciphertext >= n_squared
+        || proof.ciphertext_commitment.is_zero()
+        || &proof.ciphertext_commitment >= n_squared
+        || proof.randomness_response.is_zero()
+        || proof.randomness_response >= public_key.n
+        || proof.value_response.bits() as usize
+            > VALUE_SIZE_IN_BITS
+                + CHALLENGE_SIZE_IN_BITS
+                + STATISTICAL_SECURITY_IN_BITS
+                + 1
+    {
+        return false;
+    }
+    let challenge = equality_challenge(
+        public_key,
+        ciphertext,
+        commitment,
+        &proof.ciphertext_commitment,
+        &proof.point_commitment,
+    );
+    // (1 + n)^z1 * z2^n = a * c^e mod n^2.
+    let expected_ciphertext = (&proof.ciphertext_commitment
+        * ciphertext.modpow(&challenge, n_squared))
+        % n_squared;
+    if public_key.encrypt_with_randomness(
+        &proof.value_response,
+        &proof.randomness_response,
+    ) != expected_ciphertext
+    {
+        return false;
+    }
+    // z1 * G1 + z3 * G2 = B + e * C.
+    RistrettoPoint::multiscalar_mul(
+        &[
+            biguint_to_scalar(&proof.value_response),
+            proof.blinding_response,
+        ],
+        &[*BASEPOINT_G1, *BASEPOINT_G2],
+    ) == proof.point_commitment + biguint_to_scalar(&challenge) * commitment
+}
+
+/// Proves that a ciphertext encrypted with the given randomness encrypts a
+/// value in [lower, upper], where upper - lower < 2^32.
+pub fn prove_encryption_range(
+    public_key: &PaillierPublicKey,
+    value: u64,
+    randomness: &BigUint,
+    lower: u64,
+    upper: u64,
+) -> Result<EncryptionRangeProof, WedprError> {
+    if value < lower
+        || value > upper
+        || upper - lower >= (1u64 << RANGE_SIZE_IN_BITS)
+    {
+        wedpr_println!("Value is out of the range to prove");
+        return Err(WedprError::ArgumentError);
+    }
+    let blinding = get_random_scalar();
+    let equality_proof =
+        prove_encryption_equality(public_key, value, randomness, &blinding)?;
+    let (lower_range_proof, _) =
+        prove_value_range_with_blinding(value - lower, &blinding);
+    let (upper_range_proof, _) =
+        prove_value_range_with_blinding(upper - value, &-blinding);
+    Ok(EncryptionRangeProof {
+        commitment: commit_value(value, &blinding),
+        equality_proof,
+        lower_range_proof,
+        upper_range_proof,
+    })
+}
+
+/// Verifies that a ciphertext encrypts a value in [lower, upper].
+pub fn verify_encryption_range(
+    public_key: &PaillierPublicKey,
+    ciphertext: &BigUint,
+    lower: u64,
+    upper: u64,
+    proof: &EncryptionRangeProof,
+) -> bool {
+    if lower > upper {
+        return false;
+    }
+    // C - lower * G1 commits to v - lower, and upper * G1 - C commits to
+    // upper - v.
+    let lower_commitment =
+        proof.commitment - Scalar::from(lower) * *BASEPOINT_G1;
+    let upper_commitment =
+        Scalar::from(upper) * *BASEPOINT_G1 - proof.commitment;
+    verify_encryption_equality(
+        public_key,
+        ciphertext,
+        &proof.commitment,
+        &proof.equality_proof,
+    ) && verify_value_range(&lower_commitment, &proof.lower_range_proof)
+        && verify_value_range(&upper_commitment, &proof.upper_range_proof)
+}
+
+/// Encrypts a value and returns the ciphertext with its randomness, which is
+/// the witness for proving.
+pub fn encrypt_with_witness(
+    public_key: &PaillierPublicKey,
+    value: u64,
+) -> Result<(BigUint, BigUint), WedprError> {
+    let value_biguint = BigUint::from(value);
+    if value_biguint >= public_key.n {
+        return Err(WedprError::ArgumentError);
+    }
+    let randomness = random_unit(&public_key.n);
+    let ciphertext =
+        public_key.encrypt_with_randomness(&value_biguint, &randomness);
+    Ok((ciphertext, randomness))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_traits::One;
+    use wedpr_l_crypto_paillier::generate_keypair;
+
+    #[test]
+    fn test_paillier_proof() {
+        let (public_key, private_key) = generate_keypair(512).unwrap();
+        let value = 1000u64;
+        let (ciphertext, randomness) =
+            encrypt_with_witness(&public_key, value).unwrap();
+        assert_eq!(
+            private_key.decrypt(&ciphertext).unwrap(),
+            BigUint::from(value)
+        );
+
+        let blinding = get_random_scalar();
+        let commitment = commit_value(value, &blinding);
+        let proof = prove_encryption_equality(
+            &public_key,
+            value,
+            &randomness,
+            &blinding,
+        )
+        .unwrap();
+        assert!(verify_encryption_equality(
+            &public_key,
+            &ciphertext,
+            &commitment,
+            &proof
+        ));
+        let other_commitment = commit_value(value + 1, &blinding);
+        assert!(!verify_encryption_equality(
+            &public_key,
+            &ciphertext,
+            &other_commitment,
+            &proof
+        ));
+        let other_ciphertext =
+            public_key.add_plaintext(&ciphertext, &BigUint::one());
+        assert!(!verify_encryption_equality(
+            &public_key,
+            &other_ciphertext,
+            &commitment,
+            &proof
+        ));
+
+        let range_proof =
+            prove_encryption_range(&public_key, value, &randomness, 500, 2000)
+                .unwrap();
+        assert!(verify_encryption_range(
+            &public_key,
+            &ciphertext,
+            500,
+            2000,
+            &range_proof
+        ));
+        assert!(!verify_encryption_range(
+            &public_key,
+            &ciphertext,
+            1001,
+            2000,
+            &range_proof
+        ));
+        assert!(!verify_encryption_range(
+            &public_key,
+            &other_ciphertext,
+            500,
+            2000,
+            &range_proof
+        ));
+        assert!(prove_encryption_range(
+            &public_key,
+            value,
+            &randomness,
+            0,
+            999
+        )
+        .is_err());
+    }
+}
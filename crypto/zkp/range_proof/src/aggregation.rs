@@ -0,0 +1,286 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Aggregated range proofs of multiple values, which are split into groups of
+//! an aggregation arity, and each group is proved by one bulletproof.
+//!
+//! An aggregated bulletproof of m values only grows logarithmically in m, but
+//! bulletproofs requires m to be a power of 2, so groups of other sizes are
+//! handled by a padding rule.
+
+use crate::{
+    check_range_size, run_profiled, run_prover, DEFAULT_BYTES_MESSAGE,
+};
+use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
+use curve25519_dalek::{
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+    traits::Identity,
+};
+use merlin::Transcript;
+use wedpr_l_utils::error::WedprError;
+
+/// Maximum number of values aggregated in one proof.
+pub const MAX_AGGREGATION_ARITY: usize = 64;
+
+/// Padding rule of a group whose size is not a power of 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregationPadding {
+    /// Rejects groups which need padding.
+    Reject,
+    /// Pads groups with zero values of zero blindings, whose commitments are
+    /// the identity point, so verifiers can append them in the same way.
+    ZeroValue,
+}
+
+/// Gets the padded size of each group for proving a number of values, where
+/// arity should be a power of 2 up to MAX_AGGREGATION_ARITY, and every group
+/// except the last one has arity values.
+pub fn padded_group_sizes(
+    value_count: usize,
+    arity: usize,
+    padding: AggregationPadding,
+) -> Result<Vec<usize>, WedprError> {
+    if value_count == 0
+        || !arity.is_power_of_two()
+        || arity > MAX_AGGREGATION_ARITY
+    {
+        wedpr_println!("Invalid aggregation arity {}", arity);
+        return Err(WedprError::ArgumentError);
+    }
+    (0..value_count)
+        .step_by(arity)
+        .map(|start| std::cmp::min(arity, value_count - start))
+        .map(|size| match padding {
+            _ if size.is_power_of_two() => Ok(size),
+            AggregationPadding::ZeroValue => Ok(size.next_power_of_two()),
+            AggregationPadding::Reject => {
+                wedpr_println!("Aggregation group size {} needs padding", size);
+                Err(WedprError::ArgumentError)
+            },
+        })
+        .collect()
+}
+
+/// Proves whether all values in the list belong to
+/// [0, 2^range_size_in_bits) by one aggregated proof per group of arity
+/// values, and create commitments for them with provided random blinding
+/// values and blinding basepoint.
+/// It returns:
+/// 1) the encoded strings for the proofs of groups.
+/// 2) the point list representing the commitments created for the values,
+///    excluding padding.
+pub fn prove_value_ranges_aggregated(
+    values: &[u64],
+    blindings: &[Scalar],
+    blinding_basepoint: &RistrettoPoint,
+    range_size_in_bits: usize,
+    arity: usize,
+    padding: AggregationPadding,
+) -> Result<(Vec<Vec<u8>>, Vec<RistrettoPoint>), WedprError> {
+    if values.len() != blindings.len() {
+        return Err(WedprError::ArgumentError);
+    }
+    check_range_size(range_size_in_bits)?;
+    let group_sizes = padded_group_sizes(values.len(), arity, padding)?;
+    // Allow replacing the blinding basepoint for customized protocol design.
+    let pc_gens = PedersenGens {
+        B_blinding: *blinding_basepoint,
+        ..PedersenGens::default()
+    };
+    let bp_gens = BulletproofGens::new(range_size_in_bits, arity);
+
+    let mut proofs = Vec::with_capacity(group_sizes.len());
+    let mut commitments = Vec::with_capacity(values.len());
+    for ((group_values, group_blindings), group_size) in values
+        .chunks(arity)
+        .zip(blindings.chunks(arity))
+        .zip(group_sizes)
+    {
+        let mut padded_values = group_values.to_vec();
+        padded_values.resize(group_size, 0);
+        let mut padded_blindings = group_blindings.to_vec();
+        padded_blindings.resize(group_size, Scalar::zero());
+        let (proof, group_commitments) =
+            run_prover("prove_value_ranges_aggregated", || {
+                let mut prover_transcript =
+                    Transcript::new(DEFAULT_BYTES_MESSAGE);
+                match RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut prover_transcript,
+                    &padded_values,
+                    &padded_blindings,
+                    range_size_in_bits,
+                ) {
+                    Ok((proof, committed_value)) => {
+                        Ok((proof.to_bytes(), committed_value))
+                    },
+                    Err(_) => {
+                        wedpr_println!("prove_value_ranges_aggregated failed");
+                        Err(WedprError::FormatError)
+                    },
+                }
+            })?;
+        proofs.push(proof);
+        commitments.extend(group_commitments[..group_values.len()].iter().map(
+            |i| {
+                i.decompress()
+                    .expect("CompressedRistretto decompress should not fail")
+            },
+        ));
+    }
+    Ok((proofs, commitments))
+}
+
+/// Verifies whether all values embedded in the commitment list belong to
+/// [0, 2^range_size_in_bits), where proofs are grouped by the same arity and
+/// padding rule as proving.
+pub fn verify_value_ranges_aggregated(
+    commitments: &[RistrettoPoint],
+    proofs: &[Vec<u8>],
+    blinding_basepoint: &RistrettoPoint,
+    range_size_in_bits: usize,
+    arity: usize,
+    padding: AggregationPadding,
+) -> bool {
+    let group_sizes =
+        match padded_group_sizes(commitments.len(), arity, padding) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+    if group_sizes.len() != proofs.len()
+        || check_range_size(range_size_in_bits).is_err()
+    {
+        return false;
+    }
+    run_profiled("verify_value_ranges_aggregated", || {
+        // Allow replacing the blinding basepoint for customized protocol
+        // design.
+        let pc_gens = PedersenGens {
+            B_blinding: *blinding_basepoint,
+            ..PedersenGens::default()
+        };
+        let bp_gens = BulletproofGens::new(range_size_in_bits, arity);
+        commitments.chunks(arity).zip(proofs).zip(group_sizes).all(
+            |((group_commitments, proof_bytes), group_size)| {
+                let mut padded_commitments: Vec<CompressedRistretto> =
+                    group_commitments.iter().map(|i| i.compress()).collect();
+                padded_commitments
+                    .resize(group_size, CompressedRistretto::identity());
+                let proof = match RangeProof::from_bytes(proof_bytes) {
+                    Ok(v) => v,
+                    Err(_) => return false,
+                };
+                let mut verifier_transcript =
+                    Transcript::new(DEFAULT_BYTES_MESSAGE);
+                proof
+                    .verify_multiple(
+                        &bp_gens,
+                        &pc_gens,
+                        &mut verifier_transcript,
+                        &padded_commitments,
+                        range_size_in_bits,
+                    )
+                    .is_ok()
+            },
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prove_value_range;
+    use wedpr_l_crypto_zkp_utils::{get_random_scalar, BASEPOINT_G2};
+
+    #[test]
+    fn test_aggregated_range_proof() {
+        assert_eq!(
+            padded_group_sizes(11, 4, AggregationPadding::ZeroValue).unwrap(),
+            vec![4, 4, 4]
+        );
+        assert_eq!(
+            padded_group_sizes(9, 4, AggregationPadding::Reject).unwrap(),
+            vec![4, 4, 1]
+        );
+        assert!(padded_group_sizes(11, 4, AggregationPadding::Reject).is_err());
+        assert!(
+            padded_group_sizes(4, 3, AggregationPadding::ZeroValue).is_err()
+        );
+
+        let values: Vec<u64> = (1..=7).collect();
+        let blindings: Vec<Scalar> =
+            (0..values.len()).map(|_| get_random_scalar()).collect();
+        let (proofs, commitments) = prove_value_ranges_aggregated(
+            &values,
+            &blindings,
+            &BASEPOINT_G2,
+            32,
+            8,
+            AggregationPadding::ZeroValue,
+        )
+        .unwrap();
+        assert_eq!(proofs.len(), 1);
+        assert_eq!(commitments.len(), values.len());
+        assert!(verify_value_ranges_aggregated(
+            &commitments,
+            &proofs,
+            &BASEPOINT_G2,
+            32,
+            8,
+            AggregationPadding::ZeroValue
+        ));
+        // One aggregated proof is much smaller than single proofs.
+        let (single_proof, _, _) = prove_value_range(1);
+        assert!(proofs[0].len() < 2 * single_proof.len());
+        // Verifying with another arity or a missing commitment fails.
+        assert!(!verify_value_ranges_aggregated(
+            &commitments,
+            &proofs,
+            &BASEPOINT_G2,
+            32,
+            4,
+            AggregationPadding::ZeroValue
+        ));
+        assert!(!verify_value_ranges_aggregated(
+            &commitments[1..],
+            &proofs,
+            &BASEPOINT_G2,
+            32,
+            8,
+            AggregationPadding::ZeroValue
+        ));
+        assert_eq!(
+            WedprError::ArgumentError,
+            prove_value_ranges_aggregated(
+                &values,
+                &blindings,
+                &BASEPOINT_G2,
+                32,
+                8,
+                AggregationPadding::Reject,
+            )
+            .unwrap_err()
+        );
+
+        // Groups of arity 2 without padding.
+        let (proofs, commitments) = prove_value_ranges_aggregated(
+            &values[..5],
+            &blindings[..5],
+            &BASEPOINT_G2,
+            64,
+            2,
+            AggregationPadding::Reject,
+        )
+        .unwrap();
+        assert_eq!(proofs.len(), 3);
+        assert!(verify_value_ranges_aggregated(
+            &commitments,
+            &proofs,
+            &BASEPOINT_G2,
+            64,
+            2,
+            AggregationPadding::Reject
+        ));
+    }
+}
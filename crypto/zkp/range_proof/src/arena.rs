@@ -0,0 +1,143 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Deterministic bump-allocated memory arena for proof generation, mainly
+//! used in browser environments where allocator fragmentation during large
+//! Bulletproof generation leads to out-of-memory failures.
+//!
+//! The arena takes effect only after the application installs
+//! ProofArenaAllocator as its global allocator, e.g.
+//! `#[global_allocator] static ALLOCATOR: ProofArenaAllocator =
+//! ProofArenaAllocator;`. While a proof is being generated, every allocation
+//! of the process is served from the arena, so this module is only available
+//! for single-threaded wasm32 targets.
+//!
+//! Arena memory is never handed out again while any arena allocation is still
+//! alive. The arena is rewound only after all of its allocations have been
+//! freed, so allocations escaping from proof generation, e.g. lazily
+//! initialized globals, only keep the arena from being reused, and later
+//! proofs fall back to the system allocator when the arena is exhausted.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::UnsafeCell,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+/// Size of the preallocated arena region.
+pub const ARENA_SIZE_IN_BYTES: usize = 8 * 1024 * 1024;
+
+struct ArenaRegion(UnsafeCell<[u8; ARENA_SIZE_IN_BYTES]>);
+
+// The region is only accessed through disjoint bump allocations.
+unsafe impl Sync for ArenaRegion {}
+
+static ARENA_REGION: ArenaRegion =
+    ArenaRegion(UnsafeCell::new([0u8; ARENA_SIZE_IN_BYTES]));
+static ARENA_ACTIVE: AtomicBool = AtomicBool::new(false);
+static ARENA_OFFSET: AtomicUsize = AtomicUsize::new(0);
+static ARENA_LIVE_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ARENA_PEAK_OFFSET: AtomicUsize = AtomicUsize::new(0);
+
+fn arena_base() -> usize {
+    ARENA_REGION.0.get() as usize
+}
+
+fn is_in_arena(ptr: *mut u8) -> bool {
+    let address = ptr as usize;
+    address >= arena_base() && address < arena_base() + ARENA_SIZE_IN_BYTES
+}
+
+/// Bumps the arena offset for an allocation, or returns None if the arena is
+/// exhausted.
+fn bump_allocate(layout: Layout) -> Option<*mut u8> {
+    let base = arena_base();
+    let offset = ARENA_OFFSET.load(Ordering::Relaxed);
+    let start = (base + offset + layout.align() - 1) & !(layout.align() - 1);
+    let end = start - base + layout.size();
+    if end > ARENA_SIZE_IN_BYTES {
+        return None;
+    }
+    ARENA_OFFSET.store(end, Ordering::Relaxed);
+    ARENA_LIVE_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ARENA_PEAK_OFFSET.fetch_max(end, Ordering::Relaxed);
+    Some(start as *mut u8)
+}
+
+/// Releases an arena allocation, and rewinds the arena once no allocation in
+/// it is alive.
+fn bump_release() {
+    if ARENA_LIVE_ALLOCATIONS.fetch_sub(1, Ordering::Relaxed) == 1 {
+        ARENA_OFFSET.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Global allocator serving allocations from the arena while a proof is being
+/// generated, and from the system allocator otherwise or when the arena is
+/// exhausted.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct ProofArenaAllocator;
+
+unsafe impl GlobalAlloc for ProofArenaAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if ARENA_ACTIVE.load(Ordering::Relaxed) {
+            if let Some(v) = bump_allocate(layout) {
+                return v;
+            }
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if is_in_arena(ptr) {
+            bump_release()
+        } else {
+            System.dealloc(ptr, layout)
+        }
+    }
+}
+
+/// Runs a proving function within the arena, and returns a copy of its
+/// output detached from the arena, whose Clone implementation should deep
+/// copy all heap data. Nested calls share the outermost arena.
+pub fn with_proof_arena<T: Clone, F: FnOnce() -> T>(prove: F) -> T {
+    if ARENA_ACTIVE.swap(true, Ordering::Relaxed) {
+        return prove();
+    }
+    let arena_output = prove();
+    ARENA_ACTIVE.store(false, Ordering::Relaxed);
+    // The arena is rewound when the last allocation in it is dropped.
+    arena_output.clone()
+}
+
+/// Gets the peak arena usage in bytes, for tuning memory limits.
+pub fn arena_peak_usage() -> usize {
+    ARENA_PEAK_OFFSET.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proof_arena() {
+        let aligned_layout = Layout::from_size_align(24, 64).unwrap();
+        let first = bump_allocate(aligned_layout).unwrap();
+        assert!(is_in_arena(first));
+        assert_eq!(first as usize % 64, 0);
+        let second = bump_allocate(aligned_layout).unwrap();
+        assert!(second as usize > first as usize);
+        let oversized_layout =
+            Layout::from_size_align(ARENA_SIZE_IN_BYTES + 1, 1).unwrap();
+        assert!(bump_allocate(oversized_layout).is_none());
+
+        // The arena is not reused while any allocation in it is alive.
+        bump_release();
+        let third = bump_allocate(aligned_layout).unwrap();
+        assert!(third as usize > second as usize);
+        bump_release();
+        bump_release();
+        assert_eq!(bump_allocate(aligned_layout).unwrap(), first);
+        bump_release();
+        assert!(arena_peak_usage() >= third as usize + 24 - arena_base());
+    }
+}
@@ -15,12 +15,73 @@ use curve25519_dalek::{
 };
 use wedpr_l_crypto_zkp_utils::{get_random_scalar, BASEPOINT_G2};
 
+#[cfg(feature = "profiling")]
+#[macro_use]
+extern crate lazy_static;
+
+pub mod aggregation;
+#[cfg(all(
+    feature = "wasm",
+    any(test, all(target_arch = "wasm32", not(target_feature = "atomics")))
+))]
+pub mod arena;
+#[cfg(feature = "profiling")]
+pub mod profiling;
+
 /// Uses a smaller value to reduce time cost of using range proofs.
 /// Uses a larger value to increase value limit of using range proofs.
 /// This is a critical parameter which is recommended to be fixed to
 /// prevent unexpected proof validity issues.
 const RANGE_SIZE_IN_BITS: usize = 32;
 const DEFAULT_BYTES_MESSAGE: &[u8] = b"WeDPR";
+/// Range sizes in bits supported by bulletproofs.
+pub const SUPPORTED_RANGE_SIZES_IN_BITS: [usize; 4] = [8, 16, 32, 64];
+
+/// Checks whether a range size in bits is supported.
+fn check_range_size(range_size_in_bits: usize) -> Result<(), WedprError> {
+    if !SUPPORTED_RANGE_SIZES_IN_BITS.contains(&range_size_in_bits) {
+        wedpr_println!("Unsupported range size {} bits", range_size_in_bits);
+        return Err(WedprError::ArgumentError);
+    }
+    Ok(())
+}
+
+/// Runs a proving function, within the proof arena if the `wasm` feature is
+/// enabled for a single-threaded wasm32 target.
+fn run_prover<T: Clone, F: FnOnce() -> T>(operation: &str, prove: F) -> T {
+    run_profiled(operation, || {
+        #[cfg(all(
+            feature = "wasm",
+            target_arch = "wasm32",
+            not(target_feature = "atomics")
+        ))]
+        {
+            arena::with_proof_arena(prove)
+        }
+        #[cfg(not(all(
+            feature = "wasm",
+            target_arch = "wasm32",
+            not(target_feature = "atomics")
+        )))]
+        {
+            prove()
+        }
+    })
+}
+
+/// Runs a function, and records its heap usage under the operation name if
+/// the `profiling` feature is enabled.
+fn run_profiled<T, F: FnOnce() -> T>(operation: &str, run: F) -> T {
+    #[cfg(feature = "profiling")]
+    {
+        profiling::profile_operation(operation, run)
+    }
+    #[cfg(not(feature = "profiling"))]
+    {
+        let _ = operation;
+        run()
+    }
+}
 
 /// Proves whether a value belongs to (0, 2^RANGE_SIZE_IN_BITS - 1], and create
 /// a commitment for the value with provided random blinding value and blinding
@@ -32,28 +93,59 @@ pub fn prove_value_range_with_blinding_and_blinding_basepoint(
     blinding: &Scalar,
     blinding_basepoint: &RistrettoPoint,
 ) -> (Vec<u8>, RistrettoPoint) {
+    prove_value_range_in_bits(
+        value,
+        blinding,
+        blinding_basepoint,
+        RANGE_SIZE_IN_BITS,
+    )
+    .expect("RangeProof prove_single should not fail")
+}
+
+/// Proves whether a value belongs to [0, 2^range_size_in_bits), and create a
+/// commitment for the value with provided random blinding value and blinding
+/// basepoint, where range_size_in_bits should be one of
+/// SUPPORTED_RANGE_SIZES_IN_BITS. It returns:
+/// 1) the encoded string for the proof.
+/// 2) the point representing the commitment created for the value.
+pub fn prove_value_range_in_bits(
+    value: u64,
+    blinding: &Scalar,
+    blinding_basepoint: &RistrettoPoint,
+    range_size_in_bits: usize,
+) -> Result<(Vec<u8>, RistrettoPoint), WedprError> {
+    check_range_size(range_size_in_bits)?;
     let mut pc_gens = PedersenGens::default();
     // Allow replacing the blinding basepoint for customized protocol design.
     pc_gens.B_blinding = blinding_basepoint.clone();
-    let bp_gens = BulletproofGens::new(RANGE_SIZE_IN_BITS, 1);
     let secret_value = value;
-    let mut prover_transcript = Transcript::new(DEFAULT_BYTES_MESSAGE);
-    let (proof, committed_value) = RangeProof::prove_single(
-        &bp_gens,
-        &pc_gens,
-        &mut prover_transcript,
-        secret_value,
-        &blinding,
-        RANGE_SIZE_IN_BITS,
-    )
-    .expect("RangeProof prove_single should not fail");
+    let (proof, committed_value) = run_prover("prove_value_range", || {
+        let bp_gens = BulletproofGens::new(range_size_in_bits, 1);
+        let mut prover_transcript = Transcript::new(DEFAULT_BYTES_MESSAGE);
+        match RangeProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut prover_transcript,
+            secret_value,
+            &blinding,
+            range_size_in_bits,
+        ) {
+            Ok((proof, committed_value)) => {
+                Ok((proof.to_bytes(), committed_value))
+            },
+            Err(_) => {
+                wedpr_println!("prove_value_range failed");
+                Err(WedprError::FormatError)
+            },
+        }
+    })?;
 
-    (
-        proof.to_bytes(),
+    Ok((
+        proof,
         committed_value
             .decompress()
             .expect("CompressedRistretto decompress should not fail"),
-    )
+    ))
 }
 
 /// Proves whether a value belongs to (0, 2^RANGE_SIZE_IN_BITS - 1], and create
@@ -95,28 +187,69 @@ pub fn verify_value_range_with_blinding_basepoint(
     proof_bytes: &[u8],
     blinding_basepoint: &RistrettoPoint,
 ) -> bool {
+    verify_value_range_in_bits(
+        commitment,
+        proof_bytes,
+        blinding_basepoint,
+        RANGE_SIZE_IN_BITS,
+    )
+}
+
+/// Verifies whether a value embedded in the commentment belongs to
+/// [0, 2^range_size_in_bits), and use provided blinding basepoint.
+pub fn verify_value_range_in_bits(
+    commitment: &RistrettoPoint,
+    proof_bytes: &[u8],
+    blinding_basepoint: &RistrettoPoint,
+    range_size_in_bits: usize,
+) -> bool {
+    verify_value_ranges_in_bits(
+        &[*commitment],
+        &[proof_bytes.to_vec()],
+        blinding_basepoint,
+        range_size_in_bits,
+    )
+}
+
+/// Verifies multiple single value range proofs, where the i-th proof is for
+/// the i-th commitment, and all values belong to [0, 2^range_size_in_bits).
+/// It returns false if any proof is invalid.
+pub fn verify_value_ranges_in_bits(
+    commitments: &[RistrettoPoint],
+    proofs: &[Vec<u8>],
+    blinding_basepoint: &RistrettoPoint,
+    range_size_in_bits: usize,
+) -> bool {
+    if commitments.len() != proofs.len()
+        || check_range_size(range_size_in_bits).is_err()
+    {
+        return false;
+    }
     let mut pc_gens = PedersenGens::default();
     // Allow replacing the blinding basepoint for customized protocol design.
     pc_gens.B_blinding = blinding_basepoint.clone();
-    let bp_gens = BulletproofGens::new(RANGE_SIZE_IN_BITS, 1);
-    let mut verifier_transcript = Transcript::new(DEFAULT_BYTES_MESSAGE);
-    let commitment_value = commitment.compress();
-
-    let proof = match RangeProof::from_bytes(proof_bytes) {
-        Ok(v) => v,
-        Err(_) => return false,
-    };
-
-    match proof.verify_single(
-        &bp_gens,
-        &pc_gens,
-        &mut verifier_transcript,
-        &commitment_value,
-        RANGE_SIZE_IN_BITS,
-    ) {
-        Ok(_) => true,
-        Err(_) => false,
-    }
+    // Generators are shared by all proofs.
+    let bp_gens = BulletproofGens::new(range_size_in_bits, 1);
+    commitments
+        .iter()
+        .zip(proofs)
+        .all(|(commitment, proof_bytes)| {
+            let proof = match RangeProof::from_bytes(proof_bytes) {
+                Ok(v) => v,
+                Err(_) => return false,
+            };
+            let mut verifier_transcript =
+                Transcript::new(DEFAULT_BYTES_MESSAGE);
+            proof
+                .verify_single(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut verifier_transcript,
+                    &commitment.compress(),
+                    range_size_in_bits,
+                )
+                .is_ok()
+        })
 }
 
 /// Verifies whether a value embedded in the commentment belongs to
@@ -146,22 +279,28 @@ pub fn prove_value_range_in_batch(
     let mut pc_gens = PedersenGens::default();
     // Allow replacing the blinding basepoint for customized protocol design.
     pc_gens.B_blinding = blinding_basepoint.clone();
-    let bp_gens = BulletproofGens::new(RANGE_SIZE_IN_BITS, values.len());
-    let mut prover_transcript = Transcript::new(DEFAULT_BYTES_MESSAGE);
-    let (proof, committed_value) = match RangeProof::prove_multiple(
-        &bp_gens,
-        &pc_gens,
-        &mut prover_transcript,
-        values,
-        &blindings,
-        RANGE_SIZE_IN_BITS,
-    ) {
-        Ok(v) => v,
-        Err(_) => {
-            wedpr_println!("prove_value_range_in_batch failed");
-            return Err(WedprError::FormatError);
-        },
-    };
+    let (proof, committed_value) =
+        run_prover("prove_value_range_in_batch", || {
+            let bp_gens =
+                BulletproofGens::new(RANGE_SIZE_IN_BITS, values.len());
+            let mut prover_transcript = Transcript::new(DEFAULT_BYTES_MESSAGE);
+            match RangeProof::prove_multiple(
+                &bp_gens,
+                &pc_gens,
+                &mut prover_transcript,
+                values,
+                &blindings,
+                RANGE_SIZE_IN_BITS,
+            ) {
+                Ok((proof, committed_value)) => {
+                    Ok((proof.to_bytes(), committed_value))
+                },
+                Err(_) => {
+                    wedpr_println!("prove_value_range_in_batch failed");
+                    Err(WedprError::FormatError)
+                },
+            }
+        })?;
     let vector_commitment = committed_value
         .iter()
         .map(|i| {
@@ -169,7 +308,7 @@ pub fn prove_value_range_in_batch(
                 .expect("CompressedRistretto decompress should not fail")
         })
         .collect();
-    Ok((proof.to_bytes(), vector_commitment))
+    Ok((proof, vector_commitment))
 }
 
 /// Verifies whether all values embedded in the commentment list belongs to
@@ -179,29 +318,33 @@ pub fn verify_value_range_in_batch(
     proof_bytes: &[u8],
     blinding_basepoint: &RistrettoPoint,
 ) -> bool {
-    let mut pc_gens = PedersenGens::default();
-    // Allow replacing the blinding basepoint for customized protocol design.
-    pc_gens.B_blinding = blinding_basepoint.clone();
-    let bp_gens = BulletproofGens::new(RANGE_SIZE_IN_BITS, commitments.len());
-    let mut verifier_transcript = Transcript::new(DEFAULT_BYTES_MESSAGE);
-    // The length of decode_proof_result should be a multiple of 32 bytes.
-    let decode_commit: Vec<CompressedRistretto> =
-        commitments.iter().map(|i| i.compress()).collect();
-
-    let proof = match RangeProof::from_bytes(proof_bytes) {
-        Ok(v) => v,
-        Err(_) => return false,
-    };
-    match proof.verify_multiple(
-        &bp_gens,
-        &pc_gens,
-        &mut verifier_transcript,
-        &decode_commit,
-        RANGE_SIZE_IN_BITS,
-    ) {
-        Ok(_) => true,
-        Err(_) => false,
-    }
+    run_profiled("verify_value_range_in_batch", || {
+        let mut pc_gens = PedersenGens::default();
+        // Allow replacing the blinding basepoint for customized protocol
+        // design.
+        pc_gens.B_blinding = blinding_basepoint.clone();
+        let bp_gens =
+            BulletproofGens::new(RANGE_SIZE_IN_BITS, commitments.len());
+        let mut verifier_transcript = Transcript::new(DEFAULT_BYTES_MESSAGE);
+        // The length of decode_proof_result should be a multiple of 32 bytes.
+        let decode_commit: Vec<CompressedRistretto> =
+            commitments.iter().map(|i| i.compress()).collect();
+
+        let proof = match RangeProof::from_bytes(proof_bytes) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        match proof.verify_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut verifier_transcript,
+            &decode_commit,
+            RANGE_SIZE_IN_BITS,
+        ) {
+            Ok(_) => true,
+            Err(_) => false,
+        }
+    })
 }
 
 #[cfg(test)]
@@ -219,6 +362,77 @@ mod tests {
         let (proof_c2, c2_point, _) = prove_value_range(-1i64 as u64);
         assert_eq!(false, verify_value_range(&c2_point, &proof_c2));
 
+        // Range proofs in configurable bits, and verified together.
+        let blindings: Vec<Scalar> =
+            (0..3).map(|_| get_random_scalar()).collect();
+        let (proof_byte, byte_point) =
+            prove_value_range_in_bits(255, &blindings[0], &BASEPOINT_G2, 8)
+                .unwrap();
+        assert!(verify_value_range_in_bits(
+            &byte_point,
+            &proof_byte,
+            &BASEPOINT_G2,
+            8
+        ));
+        assert!(!verify_value_range_in_bits(
+            &byte_point,
+            &proof_byte,
+            &BASEPOINT_G2,
+            16
+        ));
+        let (proof_overflow, overflow_point) =
+            prove_value_range_in_bits(256, &blindings[1], &BASEPOINT_G2, 8)
+                .unwrap();
+        assert!(!verify_value_range_in_bits(
+            &overflow_point,
+            &proof_overflow,
+            &BASEPOINT_G2,
+            8
+        ));
+        let (proof_u64, u64_point) = prove_value_range_in_bits(
+            u64::max_value(),
+            &blindings[2],
+            &BASEPOINT_G2,
+            64,
+        )
+        .unwrap();
+        assert!(verify_value_range_in_bits(
+            &u64_point,
+            &proof_u64,
+            &BASEPOINT_G2,
+            64
+        ));
+        assert!(!verify_value_ranges_in_bits(
+            &[u64_point, c1_point],
+            &[proof_u64, proof_c1.clone()],
+            &BASEPOINT_G2,
+            64
+        ));
+        let (proof_c3, c3_point, _) = prove_value_range(3);
+        assert!(verify_value_ranges_in_bits(
+            &[c1_point, c3_point],
+            &[proof_c1.clone(), proof_c3],
+            &BASEPOINT_G2,
+            32
+        ));
+        assert!(!verify_value_ranges_in_bits(
+            &[c1_point, byte_point],
+            &[proof_c1, proof_byte],
+            &BASEPOINT_G2,
+            32
+        ));
+        assert!(!verify_value_ranges_in_bits(
+            &[byte_point],
+            &[],
+            &BASEPOINT_G2,
+            8
+        ));
+        assert_eq!(
+            WedprError::ArgumentError,
+            prove_value_range_in_bits(1, &blindings[0], &BASEPOINT_G2, 12)
+                .unwrap_err()
+        );
+
         // Range proof for a list of values.
         let blinding_basepoint = *BASEPOINT_G2;
         let values: Vec<u64> = vec![1, 2, 3, 4];
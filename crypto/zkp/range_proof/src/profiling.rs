@@ -0,0 +1,135 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Allocation-counting instrumentation of proof generation and batch
+//! verification, which reports the peak heap usage of each operation so that
+//! embedders can set container memory limits accurately.
+//!
+//! The counters take effect only after the application installs
+//! ProfilingAllocator as its global allocator, e.g.
+//! `#[global_allocator] static ALLOCATOR: ProfilingAllocator =
+//! ProfilingAllocator;`. The counters are global, so operations running
+//! concurrently from multiple threads are reported with their combined
+//! usage.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    static ref OPERATION_PROFILES: Mutex<BTreeMap<String, OperationProfile>> =
+        Mutex::new(BTreeMap::new());
+}
+
+/// Global allocator counting the allocated bytes of the system allocator.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct ProfilingAllocator;
+
+unsafe impl GlobalAlloc for ProfilingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED_BYTES
+                .fetch_add(layout.size(), Ordering::Relaxed)
+                + layout.size();
+            PEAK_ALLOCATED_BYTES.fetch_max(allocated, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+/// Heap usage statistics of an operation.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperationProfile {
+    pub calls: u64,
+    /// Peak heap usage of the last call in bytes, above the usage before the
+    /// call.
+    pub last_peak_bytes: usize,
+    /// Maximum peak heap usage of all calls in bytes.
+    pub max_peak_bytes: usize,
+}
+
+/// Runs an operation, and records its peak heap usage under its name.
+/// Nested operations are recorded separately, and also counted in the
+/// peak usage of the outer operation.
+pub fn profile_operation<T, F: FnOnce() -> T>(operation: &str, run: F) -> T {
+    let base = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let outer_peak = PEAK_ALLOCATED_BYTES.swap(base, Ordering::Relaxed);
+    let output = run();
+    let peak = PEAK_ALLOCATED_BYTES.fetch_max(outer_peak, Ordering::Relaxed);
+    let peak_bytes = peak.saturating_sub(base);
+    if let Ok(mut profiles) = OPERATION_PROFILES.lock() {
+        let profile = profiles.entry(operation.to_string()).or_default();
+        profile.calls += 1;
+        profile.last_peak_bytes = peak_bytes;
+        profile.max_peak_bytes = profile.max_peak_bytes.max(peak_bytes);
+    }
+    output
+}
+
+/// Gets the heap usage statistics of all recorded operations.
+pub fn operation_profiles() -> BTreeMap<String, OperationProfile> {
+    match OPERATION_PROFILES.lock() {
+        Ok(v) => v.clone(),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+/// Gets the number of bytes currently allocated.
+pub fn allocated_bytes() -> usize {
+    ALLOCATED_BYTES.load(Ordering::Relaxed)
+}
+
+/// Removes all recorded statistics.
+pub fn reset_operation_profiles() {
+    if let Ok(mut v) = OPERATION_PROFILES.lock() {
+        v.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prove_value_range_in_batch, verify_value_range_in_batch};
+    use wedpr_l_crypto_zkp_utils::{get_random_scalar, BASEPOINT_G2};
+
+    #[test]
+    fn test_profile_operation() {
+        let layout = Layout::from_size_align(4096, 8).unwrap();
+        let output = profile_operation("test_allocation", || unsafe {
+            let ptr = ProfilingAllocator.alloc(layout);
+            ProfilingAllocator.dealloc(ptr, layout);
+            7
+        });
+        assert_eq!(output, 7);
+        let profile = operation_profiles()["test_allocation"];
+        assert_eq!(profile.calls, 1);
+        assert!(profile.max_peak_bytes >= 4096);
+
+        let values = [1u64, 2];
+        let blindings = [get_random_scalar(), get_random_scalar()];
+        let (proof, commitments) =
+            prove_value_range_in_batch(&values, &blindings, &BASEPOINT_G2)
+                .unwrap();
+        assert!(verify_value_range_in_batch(
+            &commitments,
+            &proof,
+            &BASEPOINT_G2
+        ));
+        let profiles = operation_profiles();
+        assert!(profiles["prove_value_range_in_batch"].calls >= 1);
+        assert!(profiles["verify_value_range_in_batch"].calls >= 1);
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! ElGamal encryption of Ristretto points, where a message point M is
+//! encrypted as (r * G1, M + r * Y) under the public key Y = x * G1.
+
+use curve25519_dalek::{
+    ristretto::RistrettoPoint, scalar::Scalar, traits::MultiscalarMul,
+};
+use wedpr_l_crypto_zkp_utils::{get_random_scalar, BASEPOINT_G1};
+
+/// ElGamal ciphertext (c1, c2) = (r * G1, M + r * Y).
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct ElGamalCiphertext {
+    pub c1: RistrettoPoint,
+    pub c2: RistrettoPoint,
+}
+
+impl ElGamalCiphertext {
+    /// Adds two ciphertexts, which encrypts the sum of their messages.
+    pub fn add(&self, other: &ElGamalCiphertext) -> ElGamalCiphertext {
+        ElGamalCiphertext {
+            c1: self.c1 + other.c1,
+            c2: self.c2 + other.c2,
+        }
+    }
+
+    /// Multiplies a ciphertext by a scalar, which encrypts the scaled
+    /// message.
+    pub fn mul_scalar(&self, scalar: &Scalar) -> ElGamalCiphertext {
+        ElGamalCiphertext {
+            c1: scalar * self.c1,
+            c2: scalar * self.c2,
+        }
+    }
+}
+
+/// Generates an ElGamal key pair (Y, x).
+pub fn generate_keypair() -> (RistrettoPoint, Scalar) {
+    let private_key = get_random_scalar();
+    (private_key * *BASEPOINT_G1, private_key)
+}
+
+/// Encrypts a message point with the given randomness.
+pub fn encrypt(
+    public_key: &RistrettoPoint,
+    message: &RistrettoPoint,
+    blinding: &Scalar,
+) -> ElGamalCiphertext {
+    ElGamalCiphertext {
+        c1: blinding * *BASEPOINT_G1,
+        c2: message + blinding * public_key,
+    }
+}
+
+/// Encrypts the zero message, i.e. the identity point, with the given
+/// randomness.
+pub fn encrypt_zero(
+    public_key: &RistrettoPoint,
+    blinding: &Scalar,
+) -> ElGamalCiphertext {
+    ElGamalCiphertext {
+        c1: blinding * *BASEPOINT_G1,
+        c2: blinding * public_key,
+    }
+}
+
+/// Decrypts a ciphertext to its message point.
+pub fn decrypt(
+    private_key: &Scalar,
+    ciphertext: &ElGamalCiphertext,
+) -> RistrettoPoint {
+    ciphertext.c2 - private_key * ciphertext.c1
+}
+
+/// Re-randomizes a ciphertext with the given randomness, which keeps its
+/// message.
+pub fn rerandomize(
+    public_key: &RistrettoPoint,
+    ciphertext: &ElGamalCiphertext,
+    blinding: &Scalar,
+) -> ElGamalCiphertext {
+    ciphertext.add(&encrypt_zero(public_key, blinding))
+}
+
+/// Computes sum_i scalars[i] * ciphertexts[i].
+pub fn multi_exponentiation(
+    scalars: &[Scalar],
+    ciphertexts: &[ElGamalCiphertext],
+) -> ElGamalCiphertext {
+    ElGamalCiphertext {
+        c1: RistrettoPoint::multiscalar_mul(
+            scalars,
+            ciphertexts.iter().map(|ciphertext| ciphertext.c1),
+        ),
+        c2: RistrettoPoint::multiscalar_mul(
+            scalars,
+            ciphertexts.iter().map(|ciphertext| ciphertext.c2),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elgamal() {
+        let (public_key, private_key) = generate_keypair();
+        let message = Scalar::from(5u64) * *BASEPOINT_G1;
+        let ciphertext = encrypt(&public_key, &message, &get_random_scalar());
+        assert_eq!(decrypt(&private_key, &ciphertext), message);
+
+        let rerandomized =
+            rerandomize(&public_key, &ciphertext, &get_random_scalar());
+        assert_ne!(rerandomized, ciphertext);
+        assert_eq!(decrypt(&private_key, &rerandomized), message);
+
+        let combined =
+            multi_exponentiation(&[Scalar::from(2u64), Scalar::from(3u64)], &[
+                ciphertext,
+                rerandomized,
+            ]);
+        assert_eq!(
+            decrypt(&private_key, &combined),
+            Scalar::from(25u64) * *BASEPOINT_G1
+        );
+    }
+}
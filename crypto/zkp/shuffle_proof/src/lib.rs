@@ -0,0 +1,473 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Zero-knowledge proof (ZKP) functions of verifiable shuffles over ElGamal
+//! ciphertexts, following the construction of Bayer and Groth.
+//!
+//! A shuffle outputs C'_i = C_pi(i) + Enc(0; rho_i) for a secret permutation
+//! pi. The prover commits to a = (pi(i)) and, after a challenge x, to
+//! b = (x^pi(i)). A product argument shows that
+//! prod_i (y * a_i + b_i - z) = prod_i (y * i + x^i - z), i.e. that (a, b) is
+//! a permutation of (i, x^i), and a multi-exponentiation argument shows that
+//! sum_i x^i * C_i = sum_i b_i * C'_i + Enc(0; rho) for some rho.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+pub mod elgamal;
+
+use curve25519_dalek::{
+    ristretto::RistrettoPoint, scalar::Scalar, traits::MultiscalarMul,
+};
+use elgamal::{encrypt_zero, multi_exponentiation, ElGamalCiphertext};
+use rand::seq::SliceRandom;
+use std::iter::once;
+use wedpr_l_crypto_zkp_utils::{
+    generators::{
+        derive_generator, indexed_generator_seed, GeneratorTranscript,
+    },
+    get_random_scalar, hash_to_scalar, point_to_bytes, scalar_to_bytes,
+    BASEPOINT_G2,
+};
+use wedpr_l_utils::error::WedprError;
+
+/// Domain separator of the vector commitment generators.
+const SHUFFLE_GENERATOR_DOMAIN: &[u8] = b"WeDPR_SHUFFLE_GENERATOR";
+/// Domain separator of the Fiat-Shamir transcript.
+const SHUFFLE_PROOF_DOMAIN: &[u8] = b"WeDPR_SHUFFLE_PROOF";
+
+/// Argument that a committed vector has a given product.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct ProductArgument {
+    pub nonce_commitment: RistrettoPoint,
+    pub delta_commitment: RistrettoPoint,
+    pub difference_commitment: RistrettoPoint,
+    pub value_responses: Vec<Scalar>,
+    pub product_responses: Vec<Scalar>,
+    pub value_blinding_response: Scalar,
+    pub difference_blinding_response: Scalar,
+}
+
+/// Argument that a multi-exponentiation of the shuffled ciphertexts by a
+/// committed vector re-encrypts a given ciphertext.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct MultiExponentiationArgument {
+    pub nonce_commitment: RistrettoPoint,
+    pub nonce_ciphertext: ElGamalCiphertext,
+    pub exponent_responses: Vec<Scalar>,
+    pub blinding_response: Scalar,
+    pub randomness_response: Scalar,
+}
+
+/// Proof that a list of ciphertexts is a shuffle of another list.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct ShuffleProof {
+    pub permutation_commitment: RistrettoPoint,
+    pub exponent_commitment: RistrettoPoint,
+    pub product_argument: ProductArgument,
+    pub multi_exponentiation_argument: MultiExponentiationArgument,
+}
+
+/// Gets n generators of vector commitments, whose discrete logarithms to
+/// each other are unknown.
+pub fn get_commitment_generators(n: usize) -> Vec<RistrettoPoint> {
+    (0..n)
+        .map(|i| {
+            derive_generator(&indexed_generator_seed(
+                SHUFFLE_GENERATOR_DOMAIN,
+                i as u64,
+            ))
+        })
+        .collect()
+}
+
+/// Gets the derivation transcript of the n generators returned by
+/// get_commitment_generators for auditing.
+pub fn commitment_generator_transcript(n: usize) -> GeneratorTranscript {
+    let mut transcript = GeneratorTranscript::new();
+    transcript.derive_indexed("shuffle_generator", SHUFFLE_GENERATOR_DOMAIN, n);
+    transcript
+}
+
+/// Computes the vector commitment sum_i values[i] * g_i + blinding * G2.
+fn commit_vector(
+    generators: &[RistrettoPoint],
+    values: &[Scalar],
+    blinding: &Scalar,
+) -> RistrettoPoint {
+    RistrettoPoint::multiscalar_mul(
+        values.iter().chain(once(blinding)),
+        generators[..values.len()]
+            .iter()
+            .chain(once(&*BASEPOINT_G2)),
+    )
+}
+
+/// Appends points to the transcript and derives the next challenge from it.
+fn transcript_challenge(
+    transcript: &mut Vec<u8>,
+    points: &[RistrettoPoint],
+) -> Scalar {
+    for point in points {
+        transcript.append(&mut point_to_bytes(point));
+    }
+    let challenge = hash_to_scalar(&transcript[..]);
+    transcript.append(&mut scalar_to_bytes(&challenge));
+    challenge
+}
+
+fn initial_transcript(
+    public_key: &RistrettoPoint,
+    input_ciphertexts: &[ElGamalCiphertext],
+    output_ciphertexts: &[ElGamalCiphertext],
+) -> Vec<u8> {
+    let mut transcript = SHUFFLE_PROOF_DOMAIN.to_vec();
+    transcript.append(&mut point_to_bytes(public_key));
+    for ciphertext in input_ciphertexts.iter().chain(output_ciphertexts) {
+        transcript.append(&mut point_to_bytes(&ciphertext.c1));
+        transcript.append(&mut point_to_bytes(&ciphertext.c2));
+    }
+    transcript
+}
+
+/// Computes (1, x, x^2, ..., x^(n - 1)).
+fn challenge_powers(x: &Scalar, n: usize) -> Vec<Scalar> {
+    let mut powers = Vec::with_capacity(n);
+    let mut power = Scalar::one();
+    for _ in 0..n {
+        powers.push(power);
+        power *= x;
+    }
+    powers
+}
+
+/// Shuffles ciphertexts by a random permutation and re-randomizes them.
+/// It returns the shuffled ciphertexts, the permutation pi where the i-th
+/// output re-encrypts the pi(i)-th input, and the re-encryption randomness.
+pub fn shuffle_ciphertexts(
+    public_key: &RistrettoPoint,
+    ciphertexts: &[ElGamalCiphertext],
+) -> (Vec<ElGamalCiphertext>, Vec<usize>, Vec<Scalar>) {
+    let mut permutation: Vec<usize> = (0..ciphertexts.len()).collect();
+    permutation.shuffle(&mut rand::thread_rng());
+    let randomness: Vec<Scalar> =
+        permutation.iter().map(|_| get_random_scalar()).collect();
+    let shuffled = permutation
+        .iter()
+        .zip(randomness.iter())
+        .map(|(index, blinding)| {
+            ciphertexts[*index].add(&encrypt_zero(public_key, blinding))
+        })
+        .collect();
+    (shuffled, permutation, randomness)
+}
+
+fn check_permutation(permutation: &[usize]) -> bool {
+    let mut seen = vec![false; permutation.len()];
+    for index in permutation {
+        if *index >= seen.len() || seen[*index] {
+            return false;
+        }
+        seen[*index] = true;
+    }
+    true
+}
+
+/// Proves the product of a committed vector `values` with the commitment
+/// blinding `blinding`.
+fn prove_product(
+    transcript: &mut Vec<u8>,
+    generators: &[RistrettoPoint],
+    values: &[Scalar],
+    blinding: &Scalar,
+) -> ProductArgument {
+    let n = values.len();
+    let mut partial_products = Vec::with_capacity(n);
+    let mut partial_product = Scalar::one();
+    for value in values {
+        partial_product *= value;
+        partial_products.push(partial_product);
+    }
+    let nonces: Vec<Scalar> = (0..n).map(|_| get_random_scalar()).collect();
+    let mut deltas: Vec<Scalar> = (0..n).map(|_| get_random_scalar()).collect();
+    deltas[0] = nonces[0];
+    deltas[n - 1] = Scalar::zero();
+    let nonce_blinding = get_random_scalar();
+    let delta_blinding = get_random_scalar();
+    let difference_blinding = get_random_scalar();
+
+    let delta_values: Vec<Scalar> =
+        (0..n - 1).map(|i| -deltas[i] * nonces[i + 1]).collect();
+    let difference_values: Vec<Scalar> = (0..n - 1)
+        .map(|i| {
+            deltas[i + 1]
+                - values[i + 1] * deltas[i]
+                - partial_products[i] * nonces[i + 1]
+        })
+        .collect();
+    let nonce_commitment = commit_vector(generators, &nonces, &nonce_blinding);
+    let delta_commitment =
+        commit_vector(generators, &delta_values, &delta_blinding);
+    let difference_commitment =
+        commit_vector(generators, &difference_values, &difference_blinding);
+
+    let x = transcript_challenge(transcript, &[
+        nonce_commitment,
+        delta_commitment,
+        difference_commitment,
+    ]);
+    ProductArgument {
+        nonce_commitment,
+        delta_commitment,
+        difference_commitment,
+        value_responses: (0..n).map(|i| x * values[i] + nonces[i]).collect(),
+        product_responses: (0..n)
+            .map(|i| x * partial_products[i] + deltas[i])
+            .collect(),
+        value_blinding_response: x * blinding + nonce_blinding,
+        difference_blinding_response: x * difference_blinding + delta_blinding,
+    }
+}
+
+/// Verifies that the vector in `commitment` has the product `product`.
+fn verify_product(
+    transcript: &mut Vec<u8>,
+    generators: &[RistrettoPoint],
+    commitment: &RistrettoPoint,
+    product: &Scalar,
+    argument: &ProductArgument,
+) -> bool {
+    let n = generators.len();
+    if argument.value_responses.len() != n
+        || argument.product_responses.len() != n
+    {
+        return false;
+    }
+    let x = transcript_challenge(transcript, &[
+        argument.nonce_commitment,
+        argument.delta_commitment,
+        argument.difference_commitment,
+    ]);
+    let a = &argument.value_responses;
+    let b = &argument.product_responses;
+    let combined_values: Vec<Scalar> =
+        (0..n - 1).map(|i| x * b[i + 1] - b[i] * a[i + 1]).collect();
+    x * commitment + argument.nonce_commitment
+        == commit_vector(generators, a, &argument.value_blinding_response)
+        && x * argument.difference_commitment + argument.delta_commitment
+            == commit_vector(
+                generators,
+                &combined_values,
+                &argument.difference_blinding_response,
+            )
+        && b[0] == a[0]
+        && b[n - 1] == x * product
+}
+
+/// Proves that output ciphertexts are a shuffle of input ciphertexts by
+/// the permutation and re-encryption randomness returned from
+/// shuffle_ciphertexts. At least two ciphertexts are required.
+pub fn prove_shuffle(
+    public_key: &RistrettoPoint,
+    input_ciphertexts: &[ElGamalCiphertext],
+    output_ciphertexts: &[ElGamalCiphertext],
+    permutation: &[usize],
+    randomness: &[Scalar],
+) -> Result<ShuffleProof, WedprError> {
+    let n = input_ciphertexts.len();
+    if n < 2
+        || output_ciphertexts.len() != n
+        || permutation.len() != n
+        || randomness.len() != n
+        || !check_permutation(permutation)
+    {
+        wedpr_println!("Invalid shuffle witness");
+        return Err(WedprError::ArgumentError);
+    }
+    let generators = get_commitment_generators(n);
+    let mut transcript =
+        initial_transcript(public_key, input_ciphertexts, output_ciphertexts);
+
+    // Commits to the permutation.
+    let permutation_values: Vec<Scalar> = permutation
+        .iter()
+        .map(|index| Scalar::from(*index as u64))
+        .collect();
+    let permutation_blinding = get_random_scalar();
+    let permutation_commitment =
+        commit_vector(&generators, &permutation_values, &permutation_blinding);
+    let x = transcript_challenge(&mut transcript, &[permutation_commitment]);
+
+    // Commits to the permuted challenge powers.
+    let powers = challenge_powers(&x, n);
+    let exponents: Vec<Scalar> =
+        permutation.iter().map(|index| powers[*index]).collect();
+    let exponent_blinding = get_random_scalar();
+    let exponent_commitment =
+        commit_vector(&generators, &exponents, &exponent_blinding);
+    let y = transcript_challenge(&mut transcript, &[exponent_commitment]);
+    let z = transcript_challenge(&mut transcript, &[]);
+
+    // Proves prod_i (y * a_i + b_i - z) = prod_i (y * i + x^i - z).
+    let product_values: Vec<Scalar> = (0..n)
+        .map(|i| y * permutation_values[i] + exponents[i] - z)
+        .collect();
+    let product_blinding = y * permutation_blinding + exponent_blinding;
+    let product_argument = prove_product(
+        &mut transcript,
+        &generators,
+        &product_values,
+        &product_blinding,
+    );
+
+    // Proves sum_i x^i * C_i = sum_i b_i * C'_i + Enc(0; rho).
+    let rerandomization = -exponents
+        .iter()
+        .zip(randomness.iter())
+        .fold(Scalar::zero(), |sum, (b, rho)| sum + b * rho);
+    let exponent_nonces: Vec<Scalar> =
+        (0..n).map(|_| get_random_scalar()).collect();
+    let blinding_nonce = get_random_scalar();
+    let randomness_nonce = get_random_scalar();
+    let nonce_commitment =
+        commit_vector(&generators, &exponent_nonces, &blinding_nonce);
+    let nonce_ciphertext =
+        multi_exponentiation(&exponent_nonces, output_ciphertexts)
+            .add(&encrypt_zero(public_key, &randomness_nonce));
+    let e = transcript_challenge(&mut transcript, &[
+        nonce_commitment,
+        nonce_ciphertext.c1,
+        nonce_ciphertext.c2,
+    ]);
+    let multi_exponentiation_argument = MultiExponentiationArgument {
+        nonce_commitment,
+        nonce_ciphertext,
+        exponent_responses: (0..n)
+            .map(|i| e * exponents[i] + exponent_nonces[i])
+            .collect(),
+        blinding_response: e * exponent_blinding + blinding_nonce,
+        randomness_response: e * rerandomization + randomness_nonce,
+    };
+
+    Ok(ShuffleProof {
+        permutation_commitment,
+        exponent_commitment,
+        product_argument,
+        multi_exponentiation_argument,
+    })
+}
+
+/// Verifies that output ciphertexts are a shuffle of input ciphertexts.
+pub fn verify_shuffle(
+    public_key: &RistrettoPoint,
+    input_ciphertexts: &[ElGamalCiphertext],
+    output_ciphertexts: &[ElGamalCiphertext],
+    proof: &ShuffleProof,
+) -> Result<bool, WedprError> {
+    let n = input_ciphertexts.len();
+    if n < 2 || output_ciphertexts.len() != n {
+        wedpr_println!("Invalid shuffle ciphertexts");
+        return Err(WedprError::ArgumentError);
+    }
+    let generators = get_commitment_generators(n);
+    let mut transcript =
+        initial_transcript(public_key, input_ciphertexts, output_ciphertexts);
+    let x =
+        transcript_challenge(&mut transcript, &[proof.permutation_commitment]);
+    let y = transcript_challenge(&mut transcript, &[proof.exponent_commitment]);
+    let z = transcript_challenge(&mut transcript, &[]);
+
+    // Checks the product argument.
+    let powers = challenge_powers(&x, n);
+    let product = (0..n).fold(Scalar::one(), |product, i| {
+        product * (y * Scalar::from(i as u64) + powers[i] - z)
+    });
+    let offset: Vec<Scalar> = (0..n).map(|_| -z).collect();
+    let product_commitment = y * proof.permutation_commitment
+        + proof.exponent_commitment
+        + commit_vector(&generators, &offset, &Scalar::zero());
+    if !verify_product(
+        &mut transcript,
+        &generators,
+        &product_commitment,
+        &product,
+        &proof.product_argument,
+    ) {
+        return Ok(false);
+    }
+
+    // Checks the multi-exponentiation argument.
+    let argument = &proof.multi_exponentiation_argument;
+    if argument.exponent_responses.len() != n {
+        return Ok(false);
+    }
+    let e = transcript_challenge(&mut transcript, &[
+        argument.nonce_commitment,
+        argument.nonce_ciphertext.c1,
+        argument.nonce_ciphertext.c2,
+    ]);
+    let expected = multi_exponentiation(&powers, input_ciphertexts)
+        .mul_scalar(&e)
+        .add(&argument.nonce_ciphertext);
+    let computed =
+        multi_exponentiation(&argument.exponent_responses, output_ciphertexts)
+            .add(&encrypt_zero(public_key, &argument.randomness_response));
+    Ok(e * proof.exponent_commitment + argument.nonce_commitment
+        == commit_vector(
+            &generators,
+            &argument.exponent_responses,
+            &argument.blinding_response,
+        )
+        && computed == expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elgamal::{decrypt, encrypt, generate_keypair};
+    use wedpr_l_crypto_zkp_utils::BASEPOINT_G1;
+
+    #[test]
+    fn test_shuffle_proof() {
+        let (public_key, private_key) = generate_keypair();
+        let messages: Vec<RistrettoPoint> =
+            (0..5u64).map(|i| Scalar::from(i) * *BASEPOINT_G1).collect();
+        let input: Vec<ElGamalCiphertext> = messages
+            .iter()
+            .map(|message| encrypt(&public_key, message, &get_random_scalar()))
+            .collect();
+        let (output, permutation, randomness) =
+            shuffle_ciphertexts(&public_key, &input);
+        for (ciphertext, index) in output.iter().zip(permutation.iter()) {
+            assert_eq!(decrypt(&private_key, ciphertext), messages[*index]);
+        }
+
+        let proof = prove_shuffle(
+            &public_key,
+            &input,
+            &output,
+            &permutation,
+            &randomness,
+        )
+        .unwrap();
+        assert!(verify_shuffle(&public_key, &input, &output, &proof).unwrap());
+
+        // Replaces one output ciphertext.
+        let mut tampered_output = output.clone();
+        tampered_output[0] = encrypt(&public_key, &messages[0], &Scalar::one());
+        assert!(
+            !verify_shuffle(&public_key, &input, &tampered_output, &proof)
+                .unwrap()
+        );
+        assert!(prove_shuffle(
+            &public_key,
+            &input,
+            &output,
+            &[0, 0, 1, 2, 3],
+            &randomness
+        )
+        .is_err());
+        assert!(commitment_generator_transcript(input.len())
+            .audit_generators(&get_commitment_generators(input.len()))
+            .is_ok());
+    }
+}
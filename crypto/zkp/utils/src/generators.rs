@@ -0,0 +1,173 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Nothing-up-my-sleeve generator derivation for ZKP protocols.
+//!
+//! Every derived generator is hashed to the curve from a public seed, so
+//! that nobody knows its discrete logarithm to other generators. A
+//! derivation transcript records the seed of each generator, which can be
+//! published and re-derived by auditors.
+
+use crate::{point_to_bytes, BASEPOINT_G1};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use sha3::Sha3_512;
+use wedpr_l_utils::error::WedprError;
+
+/// Derives a generator by hashing a seed to the curve.
+pub fn derive_generator(seed: &[u8]) -> RistrettoPoint {
+    RistrettoPoint::hash_from_bytes::<Sha3_512>(seed)
+}
+
+/// Gets the seed of the generator at index within a domain.
+pub fn indexed_generator_seed(domain: &[u8], index: u64) -> Vec<u8> {
+    let mut seed = domain.to_vec();
+    seed.extend_from_slice(&index.to_be_bytes());
+    seed
+}
+
+/// Record of a generator derived from a seed.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct GeneratorDerivation {
+    pub label: String,
+    pub seed: Vec<u8>,
+    pub point: RistrettoPoint,
+}
+
+/// Transcript of generator derivations for auditing.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct GeneratorTranscript {
+    pub derivations: Vec<GeneratorDerivation>,
+}
+
+impl GeneratorTranscript {
+    /// Creates an empty transcript.
+    pub fn new() -> Self {
+        GeneratorTranscript::default()
+    }
+
+    /// Derives a generator from a seed, and records it with a label.
+    pub fn derive(&mut self, label: &str, seed: &[u8]) -> RistrettoPoint {
+        let point = derive_generator(seed);
+        self.derivations.push(GeneratorDerivation {
+            label: label.to_string(),
+            seed: seed.to_vec(),
+            point,
+        });
+        point
+    }
+
+    /// Derives count generators of a domain, labelled as label[index].
+    pub fn derive_indexed(
+        &mut self,
+        label: &str,
+        domain: &[u8],
+        count: usize,
+    ) -> Vec<RistrettoPoint> {
+        (0..count)
+            .map(|i| {
+                self.derive(
+                    &format!("{}[{}]", label, i),
+                    &indexed_generator_seed(domain, i as u64),
+                )
+            })
+            .collect()
+    }
+
+    /// Gets a recorded generator by its label.
+    pub fn get(&self, label: &str) -> Option<RistrettoPoint> {
+        self.derivations
+            .iter()
+            .find(|v| v.label == label)
+            .map(|v| v.point)
+    }
+
+    /// Formats the transcript for publishing, with one
+    /// "label seed_hex point_hex" line per generator.
+    pub fn to_text(&self) -> String {
+        self.derivations
+            .iter()
+            .map(|v| {
+                format!(
+                    "{} {} {}\n",
+                    v.label,
+                    to_hex(&v.seed),
+                    to_hex(&point_to_bytes(&v.point))
+                )
+            })
+            .collect()
+    }
+
+    /// Re-derives every recorded generator from its seed, and checks that it
+    /// matches the recorded point.
+    pub fn audit(&self) -> Result<(), WedprError> {
+        for derivation in &self.derivations {
+            if derive_generator(&derivation.seed) != derivation.point {
+                wedpr_println!(
+                    "Generator derivation mismatch, label={}",
+                    derivation.label
+                );
+                return Err(WedprError::VerificationError);
+            }
+        }
+        Ok(())
+    }
+
+    /// Audits the transcript, and checks that it records exactly the given
+    /// generators in order, e.g. the ones used by a protocol.
+    pub fn audit_generators(
+        &self,
+        generators: &[RistrettoPoint],
+    ) -> Result<(), WedprError> {
+        self.audit()?;
+        if self.derivations.len() != generators.len()
+            || self
+                .derivations
+                .iter()
+                .zip(generators.iter())
+                .any(|(derivation, generator)| derivation.point != *generator)
+        {
+            wedpr_println!("Generators do not match the transcript");
+            return Err(WedprError::VerificationError);
+        }
+        Ok(())
+    }
+}
+
+fn to_hex(input: &[u8]) -> String {
+    input.iter().map(|v| format!("{:02x}", v)).collect()
+}
+
+/// Gets the derivation transcript of the shared base point G2, which is
+/// hashed from the standard Ristretto base point G1.
+pub fn basepoint_transcript() -> GeneratorTranscript {
+    let mut transcript = GeneratorTranscript::new();
+    transcript.derive("G2", &point_to_bytes(&BASEPOINT_G1));
+    transcript
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BASEPOINT_G2;
+
+    #[test]
+    fn test_generator_transcript() {
+        let transcript = basepoint_transcript();
+        assert!(transcript.audit_generators(&[*BASEPOINT_G2]).is_ok());
+        assert_eq!(transcript.get("G2"), Some(*BASEPOINT_G2));
+
+        let mut indexed_transcript = GeneratorTranscript::new();
+        let generators =
+            indexed_transcript.derive_indexed("H", b"WeDPR_TEST", 3);
+        assert!(indexed_transcript.audit_generators(&generators).is_ok());
+        assert!(indexed_transcript
+            .audit_generators(&generators[..2])
+            .is_err());
+        assert_eq!(indexed_transcript.to_text().lines().count(), 3);
+        assert!(indexed_transcript.to_text().starts_with("H[0] "));
+
+        // A generator with a known discrete logarithm fails the audit.
+        let mut forged_transcript = indexed_transcript.clone();
+        forged_transcript.derivations[1].point = generators[0] + generators[0];
+        assert!(forged_transcript.audit().is_err());
+    }
+}
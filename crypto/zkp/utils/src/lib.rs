@@ -17,6 +17,7 @@ extern crate wedpr_l_macros;
 extern crate lazy_static;
 
 mod config;
+pub mod generators;
 use config::HASH;
 use sha3::Sha3_512;
 use std::convert::TryInto;
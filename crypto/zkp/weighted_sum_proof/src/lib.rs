@@ -0,0 +1,271 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Zero-knowledge proof (ZKP) functions proving that a published aggregate
+//! equals the weighted sum of committed responses, where each response is in
+//! a valid range, e.g. for confidential surveys.
+//!
+//! Each response v_i is committed as C_i = v_i * G1 + r_i * G2. For public
+//! weights w_i and a published aggregate A = sum_i w_i * v_i, the prover
+//! shows knowledge of R = sum_i w_i * r_i satisfying
+//! sum_i w_i * C_i - A * G1 = R * G2, together with range proofs of each
+//! response.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+use curve25519_dalek::{
+    ristretto::RistrettoPoint, scalar::Scalar, traits::MultiscalarMul,
+};
+use wedpr_l_crypto_zkp_commitment::commit_value;
+use wedpr_l_crypto_zkp_range_proof::{
+    prove_value_range_with_blinding, verify_value_range,
+};
+use wedpr_l_crypto_zkp_utils::{
+    get_random_scalar, hash_to_scalar, point_to_bytes, BASEPOINT_G1,
+    BASEPOINT_G2,
+};
+use wedpr_l_utils::error::WedprError;
+
+/// Size of the range covered by a range proof in bits.
+const RANGE_SIZE_IN_BITS: u32 = 32;
+/// Domain separator of the Fiat-Shamir challenge.
+const WEIGHTED_SUM_PROOF_DOMAIN: &[u8] = b"WeDPR_WEIGHTED_SUM";
+
+/// Proof that a committed response is in [lower, upper].
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct ResponseRangeProof {
+    /// Range proof of v - lower.
+    pub lower_range_proof: Vec<u8>,
+    /// Range proof of upper - v.
+    pub upper_range_proof: Vec<u8>,
+}
+
+/// Proof that an aggregate equals the weighted sum of committed responses in
+/// a valid range.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct WeightedSumProof {
+    pub range_proofs: Vec<ResponseRangeProof>,
+    pub challenge: Scalar,
+    pub response: Scalar,
+}
+
+/// Computes the commitment v * G1 + r * G2 of a response.
+pub fn commit_response(value: u64, blinding: &Scalar) -> RistrettoPoint {
+    commit_value(value, blinding)
+}
+
+/// Computes the aggregate sum_i weights[i] * values[i], which fails on
+/// overflow.
+pub fn compute_weighted_sum(
+    values: &[u64],
+    weights: &[u64],
+) -> Result<u64, WedprError> {
+    if values.len() != weights.len() {
+        return Err(WedprError::ArgumentError);
+    }
+    let mut sum = 0u64;
+    for (value, weight) in values.iter().zip(weights.iter()) {
+        sum = match value
+            .checked_mul(*weight)
+            .and_then(|term| sum.checked_add(term))
+        {
+            Some(v) => v,
+            None => {
+                wedpr_println!("Weighted sum overflows");
+                return Err(WedprError::ArgumentError);
+            },
+        };
+    }
+    Ok(sum)
+}
+
+/// Computes sum_i w_i * C_i - A * G1, which commits to 0 with the blinding
+/// sum_i w_i * r_i for a valid aggregate.
+fn aggregate_difference(
+    commitments: &[RistrettoPoint],
+    weights: &[u64],
+    aggregate: u64,
+) -> RistrettoPoint {
+    let scalars = weights
+        .iter()
+        .map(|weight| Scalar::from(*weight))
+        .chain(std::iter::once(-Scalar::from(aggregate)));
+    let points = commitments
+        .iter()
+        .copied()
+        .chain(std::iter::once(*BASEPOINT_G1));
+    RistrettoPoint::multiscalar_mul(scalars, points)
+}
+
+fn weighted_sum_challenge(
+    commitments: &[RistrettoPoint],
+    weights: &[u64],
+    aggregate: u64,
+    nonce_commitment: &RistrettoPoint,
+) -> Scalar {
+    let mut hash_vec = WEIGHTED_SUM_PROOF_DOMAIN.to_vec();
+    for (commitment, weight) in commitments.iter().zip(weights.iter()) {
+        hash_vec.append(&mut point_to_bytes(commitment));
+        hash_vec.extend_from_slice(&weight.to_be_bytes());
+    }
+    hash_vec.extend_from_slice(&aggregate.to_be_bytes());
+    hash_vec.append(&mut point_to_bytes(nonce_commitment));
+    hash_to_scalar(&hash_vec)
+}
+
+fn check_range(lower: u64, upper: u64) -> bool {
+    lower <= upper && upper - lower < (1u64 << RANGE_SIZE_IN_BITS)
+}
+
+/// Proves that the aggregate computed by compute_weighted_sum equals the
+/// weighted sum of the committed responses, and that each response is in
+/// [lower, upper], where upper - lower < 2^32.
+pub fn prove_weighted_sum(
+    values: &[u64],
+    blindings: &[Scalar],
+    weights: &[u64],
+    lower: u64,
+    upper: u64,
+) -> Result<WeightedSumProof, WedprError> {
+    if values.len() != blindings.len()
+        || !check_range(lower, upper)
+        || values.iter().any(|value| *value < lower || *value > upper)
+    {
+        wedpr_println!("Invalid survey responses");
+        return Err(WedprError::ArgumentError);
+    }
+    let aggregate = compute_weighted_sum(values, weights)?;
+    let commitments: Vec<RistrettoPoint> = values
+        .iter()
+        .zip(blindings.iter())
+        .map(|(value, blinding)| commit_response(*value, blinding))
+        .collect();
+    let range_proofs = values
+        .iter()
+        .zip(blindings.iter())
+        .map(|(value, blinding)| {
+            let (lower_range_proof, _) =
+                prove_value_range_with_blinding(value - lower, blinding);
+            let (upper_range_proof, _) =
+                prove_value_range_with_blinding(upper - value, &-blinding);
+            ResponseRangeProof {
+                lower_range_proof,
+                upper_range_proof,
+            }
+        })
+        .collect();
+
+    let blinding_sum = blindings
+        .iter()
+        .zip(weights.iter())
+        .fold(Scalar::zero(), |sum, (blinding, weight)| {
+            sum + Scalar::from(*weight) * blinding
+        });
+    let nonce = get_random_scalar();
+    let nonce_commitment = nonce * *BASEPOINT_G2;
+    let challenge = weighted_sum_challenge(
+        &commitments,
+        weights,
+        aggregate,
+        &nonce_commitment,
+    );
+    Ok(WeightedSumProof {
+        range_proofs,
+        challenge,
+        response: nonce + challenge * blinding_sum,
+    })
+}
+
+/// Verifies that the aggregate equals the weighted sum of the committed
+/// responses, and that each response is in [lower, upper].
+pub fn verify_weighted_sum(
+    commitments: &[RistrettoPoint],
+    weights: &[u64],
+    aggregate: u64,
+    lower: u64,
+    upper: u64,
+    proof: &WeightedSumProof,
+) -> bool {
+    if commitments.len() != weights.len()
+        || commitments.len() != proof.range_proofs.len()
+        || !check_range(lower, upper)
+    {
+        return false;
+    }
+    let lower_point = Scalar::from(lower) * *BASEPOINT_G1;
+    let upper_point = Scalar::from(upper) * *BASEPOINT_G1;
+    // C - lower * G1 commits to v - lower, and upper * G1 - C commits to
+    // upper - v.
+    for (commitment, range_proof) in
+        commitments.iter().zip(proof.range_proofs.iter())
+    {
+        if !verify_value_range(
+            &(commitment - lower_point),
+            &range_proof.lower_range_proof,
+        ) || !verify_value_range(
+            &(upper_point - commitment),
+            &range_proof.upper_range_proof,
+        ) {
+            return false;
+        }
+    }
+
+    let difference = aggregate_difference(commitments, weights, aggregate);
+    let nonce_commitment = RistrettoPoint::multiscalar_mul(
+        &[proof.response, -proof.challenge],
+        &[*BASEPOINT_G2, difference],
+    );
+    weighted_sum_challenge(commitments, weights, aggregate, &nonce_commitment)
+        == proof.challenge
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weighted_sum_proof() {
+        let values = [3u64, 5, 1, 4];
+        let weights = [2u64, 1, 3, 1];
+        let blindings: Vec<Scalar> =
+            values.iter().map(|_| get_random_scalar()).collect();
+        let commitments: Vec<RistrettoPoint> = values
+            .iter()
+            .zip(blindings.iter())
+            .map(|(value, blinding)| commit_response(*value, blinding))
+            .collect();
+        let aggregate = compute_weighted_sum(&values, &weights).unwrap();
+        assert_eq!(aggregate, 18);
+
+        let proof =
+            prove_weighted_sum(&values, &blindings, &weights, 1, 5).unwrap();
+        assert!(verify_weighted_sum(
+            &commitments,
+            &weights,
+            aggregate,
+            1,
+            5,
+            &proof
+        ));
+        assert!(!verify_weighted_sum(
+            &commitments,
+            &weights,
+            aggregate + 1,
+            1,
+            5,
+            &proof
+        ));
+        assert!(!verify_weighted_sum(
+            &commitments,
+            &weights,
+            aggregate,
+            2,
+            5,
+            &proof
+        ));
+        assert!(
+            prove_weighted_sum(&values, &blindings, &weights, 2, 5).is_err()
+        );
+        assert!(compute_weighted_sum(&[u64::MAX], &[2]).is_err());
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! API key function wrappers.
+
+use libc::c_char;
+use std::{ffi::CString, panic, ptr};
+
+#[cfg(feature = "wedpr_f_base64")]
+use wedpr_ffi_common_base64::api_key::{
+    enable_api_keys, mint_api_key, use_api_key,
+};
+#[cfg(feature = "wedpr_f_base64")]
+use wedpr_ffi_common_base64::utils::{
+    bytes_to_string, c_char_pointer_to_string, string_to_bytes, FAILURE,
+    SUCCESS,
+};
+
+#[cfg(feature = "wedpr_f_hex")]
+use wedpr_ffi_common_hex::api_key::{
+    enable_api_keys, mint_api_key, use_api_key,
+};
+#[cfg(feature = "wedpr_f_hex")]
+use wedpr_ffi_common_hex::utils::{
+    bytes_to_string, c_char_pointer_to_string, string_to_bytes, FAILURE,
+    SUCCESS,
+};
+
+#[no_mangle]
+/// C interface for 'wedpr_api_key_enable', which returns the master secret
+/// for minting API keys, and can only succeed once per process.
+pub extern "C" fn wedpr_api_key_enable() -> *mut c_char {
+    let result = panic::catch_unwind(|| {
+        let master_secret = match enable_api_keys() {
+            Ok(v) => v,
+            Err(_) => return ptr::null_mut(),
+        };
+        c_safe_bytes_to_c_char_pointer!(&master_secret)
+    });
+    c_safe_return!(result)
+}
+
+#[no_mangle]
+/// C interface for 'wedpr_api_key_mint', where utf8_capabilities is a
+/// comma-separated list of capabilities.
+pub extern "C" fn wedpr_api_key_mint(
+    encoded_master_secret: *const c_char,
+    utf8_capabilities: *const c_char,
+) -> *mut c_char {
+    let result = panic::catch_unwind(|| {
+        let master_secret =
+            c_safe_c_char_pointer_to_bytes!(encoded_master_secret);
+        let capabilities = c_safe_c_char_pointer_to_string!(utf8_capabilities);
+
+        let capability_list: Vec<&str> = capabilities.split(',').collect();
+        let api_key = match mint_api_key(&master_secret, &capability_list) {
+            Ok(v) => v,
+            Err(_) => return ptr::null_mut(),
+        };
+        c_safe_bytes_to_c_char_pointer!(&api_key)
+    });
+    c_safe_return!(result)
+}
+
+#[no_mangle]
+/// C interface for 'wedpr_api_key_use', which presents an API key for the
+/// calls of the current thread.
+pub extern "C" fn wedpr_api_key_use(encoded_api_key: *const c_char) -> i8 {
+    let result = panic::catch_unwind(|| {
+        let api_key = c_safe_c_char_pointer_to_bytes_with_error_value!(
+            encoded_api_key,
+            FAILURE
+        );
+
+        match use_api_key(&api_key) {
+            Ok(_) => SUCCESS,
+            Err(_) => FAILURE,
+        }
+    });
+    c_safe_return_with_error_value!(result, FAILURE)
+}
@@ -12,11 +12,15 @@ use crate::config::ECIES_SECP256K1;
 use libc::c_char;
 use std::{ffi::CString, panic, ptr};
 
+#[cfg(feature = "wedpr_f_base64")]
+use wedpr_ffi_common_base64::api_key::{check_capability, CAPABILITY_ECIES};
 #[cfg(feature = "wedpr_f_base64")]
 use wedpr_ffi_common_base64::utils::{
     bytes_to_string, c_char_pointer_to_string, string_to_bytes,
 };
 
+#[cfg(feature = "wedpr_f_hex")]
+use wedpr_ffi_common_hex::api_key::{check_capability, CAPABILITY_ECIES};
 #[cfg(feature = "wedpr_f_hex")]
 use wedpr_ffi_common_hex::utils::{
     bytes_to_string, c_char_pointer_to_string, string_to_bytes,
@@ -33,6 +37,8 @@ pub extern "C" fn wedpr_secp256k1_ecies_encrypt(
     encoded_plaintext: *mut c_char,
 ) -> *mut c_char {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability!(CAPABILITY_ECIES);
+
         let public_key = c_safe_c_char_pointer_to_bytes!(encoded_public_key);
         let encoded_message =
             c_safe_c_char_pointer_to_bytes!(encoded_plaintext);
@@ -63,6 +69,8 @@ pub extern "C" fn wedpr_secp256k1_ecies_decrypt(
     encoded_ciphertext: *mut c_char,
 ) -> *mut c_char {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability!(CAPABILITY_ECIES);
+
         let private_key = c_safe_c_char_pointer_to_bytes!(encoded_private_key);
         let ciphertext = c_safe_c_char_pointer_to_bytes!(encoded_ciphertext);
 
@@ -15,11 +15,15 @@ use crate::config::HASH_SM3;
 use libc::c_char;
 use std::{ffi::CString, panic, ptr};
 
+#[cfg(feature = "wedpr_f_base64")]
+use wedpr_ffi_common_base64::api_key::{check_capability, CAPABILITY_HASH};
 #[cfg(feature = "wedpr_f_base64")]
 use wedpr_ffi_common_base64::utils::{
     bytes_to_string, c_char_pointer_to_string, string_to_bytes,
 };
 
+#[cfg(feature = "wedpr_f_hex")]
+use wedpr_ffi_common_hex::api_key::{check_capability, CAPABILITY_HASH};
 #[cfg(feature = "wedpr_f_hex")]
 use wedpr_ffi_common_hex::utils::{
     bytes_to_string, c_char_pointer_to_string, string_to_bytes,
@@ -35,6 +39,8 @@ pub extern "C" fn wedpr_keccak256_hash(
     encoded_message: *mut c_char,
 ) -> *mut c_char {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability!(CAPABILITY_HASH);
+
         let message = c_safe_c_char_pointer_to_bytes!(encoded_message);
 
         let msg_hash = bytes_to_string(&HASH_KECCAK256.hash(&message));
@@ -50,6 +56,8 @@ pub extern "C" fn wedpr_keccak256_hash(
 /// C interface for 'wedpr_sm3_hash'.
 pub extern "C" fn wedpr_sm3_hash(encoded_message: *mut c_char) -> *mut c_char {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability!(CAPABILITY_HASH);
+
         let message = c_safe_c_char_pointer_to_bytes!(encoded_message);
 
         let msg_hash = bytes_to_string(&HASH_SM3.hash(&message));
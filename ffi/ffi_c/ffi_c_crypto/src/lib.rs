@@ -25,6 +25,7 @@ extern crate wedpr_l_macros;
 #[macro_use]
 extern crate lazy_static;
 
+pub mod api_key;
 mod config;
 pub mod ecies;
 pub mod hash;
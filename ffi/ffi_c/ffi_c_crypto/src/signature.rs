@@ -21,12 +21,18 @@ use wedpr_l_protos::generated::common;
 
 use protobuf::{self, Message};
 
+#[cfg(feature = "wedpr_f_base64")]
+use wedpr_ffi_common_base64::api_key::{
+    check_capability, CAPABILITY_SIGNATURE,
+};
 #[cfg(feature = "wedpr_f_base64")]
 use wedpr_ffi_common_base64::utils::{
     bytes_to_string, c_char_pointer_to_string, string_to_bytes, FAILURE,
     SUCCESS,
 };
 
+#[cfg(feature = "wedpr_f_hex")]
+use wedpr_ffi_common_hex::api_key::{check_capability, CAPABILITY_SIGNATURE};
 #[cfg(feature = "wedpr_f_hex")]
 use wedpr_ffi_common_hex::utils::{
     bytes_to_string, c_char_pointer_to_string, string_to_bytes, FAILURE,
@@ -40,6 +46,8 @@ use wedpr_ffi_common_hex::utils::{
 /// C interface for 'wedpr_secp256k1_gen_key_pair'.
 pub extern "C" fn wedpr_secp256k1_gen_key_pair() -> *mut c_char {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability!(CAPABILITY_SIGNATURE);
+
         let (pk, sk) = SIGNATURE_SECP256K1.generate_keypair();
         let mut keypair = common::Keypair::new();
         keypair.set_private_key(sk);
@@ -62,6 +70,8 @@ pub extern "C" fn wedpr_secp256k1_sign(
     encoded_message_hash: *mut c_char,
 ) -> *mut c_char {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability!(CAPABILITY_SIGNATURE);
+
         let private_key = c_safe_c_char_pointer_to_bytes!(encoded_private_key);
         let message_hash =
             c_safe_c_char_pointer_to_bytes!(encoded_message_hash);
@@ -87,6 +97,11 @@ pub extern "C" fn wedpr_secp256k1_verify(
     encoded_signature: *mut c_char,
 ) -> i8 {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability_with_error_value!(
+            CAPABILITY_SIGNATURE,
+            FAILURE
+        );
+
         let public_key = c_safe_c_char_pointer_to_bytes_with_error_value!(
             encoded_public_key,
             FAILURE
@@ -117,15 +132,18 @@ pub extern "C" fn wedpr_secp256k1_recover_public_key(
     encoded_signature: *mut c_char,
 ) -> *mut c_char {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability!(CAPABILITY_SIGNATURE);
+
         let message_hash =
             c_safe_c_char_pointer_to_bytes!(encoded_message_hash);
         let signature = c_safe_c_char_pointer_to_bytes!(encoded_signature);
 
-        let result =
-            match SIGNATURE_SECP256K1.recover_public_key(&message_hash, &signature) {
-                Ok(v) => v,
-                Err(_) => return ptr::null_mut(),
-            };
+        let result = match SIGNATURE_SECP256K1
+            .recover_public_key(&message_hash, &signature)
+        {
+            Ok(v) => v,
+            Err(_) => return ptr::null_mut(),
+        };
         c_safe_bytes_to_c_char_pointer!(&result)
     });
     c_safe_return!(result)
@@ -138,6 +156,8 @@ pub extern "C" fn wedpr_secp256k1_recover_public_key(
 /// C interface for 'wedpr_sm2_gen_key_pair'.
 pub extern "C" fn wedpr_sm2_gen_key_pair() -> *mut c_char {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability!(CAPABILITY_SIGNATURE);
+
         let (pk, sk) = SIGNATURE_SM2.generate_keypair();
         let mut keypair = common::Keypair::new();
         keypair.set_private_key(sk);
@@ -160,6 +180,8 @@ pub extern "C" fn wedpr_sm2_sign(
     encoded_message_hash: *mut c_char,
 ) -> *mut c_char {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability!(CAPABILITY_SIGNATURE);
+
         let private_key = c_safe_c_char_pointer_to_bytes!(encoded_private_key);
         let message_hash =
             c_safe_c_char_pointer_to_bytes!(encoded_message_hash);
@@ -184,6 +206,8 @@ pub extern "C" fn wedpr_sm2_sign_fast(
     encoded_message_hash: *mut c_char,
 ) -> *mut c_char {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability!(CAPABILITY_SIGNATURE);
+
         let private_key = c_safe_c_char_pointer_to_bytes!(encoded_private_key);
         let public_key = c_safe_c_char_pointer_to_bytes!(encoded_public_key);
         let message_hash =
@@ -213,6 +237,11 @@ pub extern "C" fn wedpr_sm2_verify(
     encoded_signature: *mut c_char,
 ) -> i8 {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability_with_error_value!(
+            CAPABILITY_SIGNATURE,
+            FAILURE
+        );
+
         let public_key = c_safe_c_char_pointer_to_bytes_with_error_value!(
             encoded_public_key,
             FAILURE
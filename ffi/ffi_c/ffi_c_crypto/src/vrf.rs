@@ -7,12 +7,16 @@
 use libc::c_char;
 use std::{ffi::CString, panic, ptr};
 
+#[cfg(feature = "wedpr_f_base64")]
+use wedpr_ffi_common_base64::api_key::{check_capability, CAPABILITY_VRF};
 #[cfg(feature = "wedpr_f_base64")]
 use wedpr_ffi_common_base64::utils::{
     bytes_to_string, c_char_pointer_to_string, string_to_bytes, FAILURE,
     SUCCESS,
 };
 
+#[cfg(feature = "wedpr_f_hex")]
+use wedpr_ffi_common_hex::api_key::{check_capability, CAPABILITY_VRF};
 #[cfg(feature = "wedpr_f_hex")]
 use wedpr_ffi_common_hex::utils::{
     bytes_to_string, c_char_pointer_to_string, string_to_bytes, FAILURE,
@@ -31,6 +35,8 @@ pub extern "C" fn wedpr_curve25519_vrf_derive_public_key(
     encoded_private_key: *const c_char,
 ) -> *mut c_char {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability!(CAPABILITY_VRF);
+
         let private_key = c_safe_c_char_pointer_to_bytes!(encoded_private_key);
 
         let encrypt_data = WedprCurve25519Vrf::derive_public_key(&private_key);
@@ -46,6 +52,8 @@ pub extern "C" fn wedpr_curve25519_vrf_prove_utf8(
     utf8_message: *const c_char,
 ) -> *mut c_char {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability!(CAPABILITY_VRF);
+
         let private_key = c_safe_c_char_pointer_to_bytes!(encoded_private_key);
         let message = c_safe_c_char_pointer_to_bytes_utf8!(utf8_message);
 
@@ -68,6 +76,8 @@ pub extern "C" fn wedpr_curve25519_vrf_prove_fast_utf8(
     utf8_message: *const c_char,
 ) -> *mut c_char {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability!(CAPABILITY_VRF);
+
         let private_key = c_safe_c_char_pointer_to_bytes!(encoded_private_key);
         let public_key = c_safe_c_char_pointer_to_bytes!(encoded_public_key);
         let message = c_safe_c_char_pointer_to_bytes_utf8!(utf8_message);
@@ -95,6 +105,8 @@ pub extern "C" fn wedpr_curve25519_vrf_verify_utf8(
     encoded_proof: *const c_char,
 ) -> i8 {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability_with_error_value!(CAPABILITY_VRF, FAILURE);
+
         let public_key = c_safe_c_char_pointer_to_bytes_with_error_value!(
             encoded_public_key,
             FAILURE
@@ -128,6 +140,8 @@ pub extern "C" fn wedpr_curve25519_vrf_proof_to_hash(
     encoded_proof: *const c_char,
 ) -> *mut c_char {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability!(CAPABILITY_VRF);
+
         let proof_bytes = c_safe_c_char_pointer_to_bytes!(encoded_proof);
         let proof = match WedprCurve25519Vrf::decode_proof(&proof_bytes) {
             Ok(v) => v,
@@ -153,6 +167,8 @@ pub extern "C" fn wedpr_curve25519_vrf_is_valid_public_key(
     encoded_public_key: *const c_char,
 ) -> i8 {
     let result = panic::catch_unwind(|| {
+        c_safe_require_capability_with_error_value!(CAPABILITY_VRF, FAILURE);
+
         let public_key = c_safe_c_char_pointer_to_bytes_with_error_value!(
             encoded_public_key,
             FAILURE
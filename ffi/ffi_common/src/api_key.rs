@@ -0,0 +1,172 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Capability-scoped API keys for FFI functions.
+//!
+//! Once the host application enables API keys, it receives a master secret
+//! and mints API keys listing the capabilities granted to each plugin. A
+//! plugin presents its API key for the current thread, and every exported
+//! function checks that the presented API key holds its capability. Before
+//! API keys are enabled, all functions are allowed.
+
+use rand::RngCore;
+use std::{cell::RefCell, sync::RwLock};
+use wedpr_l_crypto_hash_sha256::WedprSha256;
+use wedpr_l_crypto_mac_hmac::{WedprHmac, HMAC_SHA256_BLOCK_SIZE_IN_BYTES};
+use wedpr_l_utils::{error::WedprError, traits::Mac};
+
+/// Capability of hash functions.
+pub const CAPABILITY_HASH: &str = "hash";
+/// Capability of signature functions.
+pub const CAPABILITY_SIGNATURE: &str = "signature";
+/// Capability of ECIES functions.
+pub const CAPABILITY_ECIES: &str = "ecies";
+/// Capability of VRF functions.
+pub const CAPABILITY_VRF: &str = "vrf";
+
+const MASTER_SECRET_SIZE_IN_BYTES: usize = 32;
+const CAPABILITY_SEPARATOR: char = ',';
+
+lazy_static! {
+    static ref HMAC_SHA256: WedprHmac<WedprSha256> =
+        WedprHmac::new(WedprSha256::default(), HMAC_SHA256_BLOCK_SIZE_IN_BYTES);
+    static ref MASTER_SECRET: RwLock<Option<Vec<u8>>> = RwLock::new(None);
+}
+
+thread_local! {
+    static CURRENT_CAPABILITIES: RefCell<Vec<String>> = RefCell::new(Vec::new());
+}
+
+/// Enables API keys and returns the master secret for minting them, which
+/// can only succeed once per process.
+pub fn enable_api_keys() -> Result<Vec<u8>, WedprError> {
+    let mut master_secret = match MASTER_SECRET.write() {
+        Ok(v) => v,
+        Err(_) => return Err(WedprError::ArgumentError),
+    };
+    if master_secret.is_some() {
+        wedpr_println!("API keys have already been enabled");
+        return Err(WedprError::ArgumentError);
+    }
+    let mut secret = vec![0u8; MASTER_SECRET_SIZE_IN_BYTES];
+    rand::thread_rng().fill_bytes(&mut secret);
+    *master_secret = Some(secret.clone());
+    Ok(secret)
+}
+
+fn enabled_master_secret() -> Option<Vec<u8>> {
+    match MASTER_SECRET.read() {
+        Ok(v) => v.clone(),
+        Err(_) => None,
+    }
+}
+
+/// Mints an API key granting capabilities, where master_secret is returned
+/// by enable_api_keys.
+pub fn mint_api_key(
+    master_secret: &[u8],
+    capabilities: &[&str],
+) -> Result<Vec<u8>, WedprError> {
+    let expected_secret = match enabled_master_secret() {
+        Some(v) => v,
+        None => {
+            wedpr_println!("API keys are not enabled");
+            return Err(WedprError::ArgumentError);
+        },
+    };
+    // Compares the secrets through their tags in constant time.
+    let empty: &[u8] = &[];
+    if !HMAC_SHA256.verify_mac(
+        &expected_secret[..],
+        empty,
+        &HMAC_SHA256.mac(master_secret, empty)[..],
+    ) {
+        wedpr_println!("Invalid API key master secret");
+        return Err(WedprError::VerificationError);
+    }
+    if capabilities
+        .iter()
+        .any(|v| v.is_empty() || v.contains(CAPABILITY_SEPARATOR))
+    {
+        wedpr_println!("Invalid API key capability");
+        return Err(WedprError::ArgumentError);
+    }
+    let mut api_key = capabilities
+        .join(&CAPABILITY_SEPARATOR.to_string())
+        .into_bytes();
+    let mut tag = HMAC_SHA256.mac(&expected_secret[..], &api_key[..]);
+    api_key.append(&mut tag);
+    Ok(api_key)
+}
+
+/// Presents an API key for the FFI calls of the current thread.
+pub fn use_api_key(api_key: &[u8]) -> Result<(), WedprError> {
+    let master_secret = match enabled_master_secret() {
+        Some(v) => v,
+        None => {
+            wedpr_println!("API keys are not enabled");
+            return Err(WedprError::ArgumentError);
+        },
+    };
+    let empty: &[u8] = &[];
+    let tag_size = HMAC_SHA256.mac(empty, empty).len();
+    if api_key.len() < tag_size {
+        return Err(WedprError::FormatError);
+    }
+    let (capabilities, tag) = api_key.split_at(api_key.len() - tag_size);
+    if !HMAC_SHA256.verify_mac(&master_secret[..], capabilities, tag) {
+        wedpr_println!("Invalid API key");
+        return Err(WedprError::VerificationError);
+    }
+    let capabilities = match std::str::from_utf8(capabilities) {
+        Ok(v) => v,
+        Err(_) => return Err(WedprError::DecodeError),
+    };
+    CURRENT_CAPABILITIES.with(|current| {
+        *current.borrow_mut() = capabilities
+            .split(CAPABILITY_SEPARATOR)
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string())
+            .collect();
+    });
+    Ok(())
+}
+
+/// Checks whether the current thread may call a function requiring a
+/// capability.
+pub fn check_capability(capability: &str) -> bool {
+    if enabled_master_secret().is_none() {
+        return true;
+    }
+    CURRENT_CAPABILITIES
+        .with(|current| current.borrow().iter().any(|v| v == capability))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_key() {
+        assert!(check_capability(CAPABILITY_HASH));
+        assert!(mint_api_key(&[0u8; 32], &[CAPABILITY_HASH]).is_err());
+
+        let master_secret = enable_api_keys().unwrap();
+        assert!(enable_api_keys().is_err());
+        assert!(!check_capability(CAPABILITY_HASH));
+
+        let api_key =
+            mint_api_key(&master_secret, &[CAPABILITY_HASH, CAPABILITY_VRF])
+                .unwrap();
+        use_api_key(&api_key).unwrap();
+        assert!(check_capability(CAPABILITY_HASH));
+        assert!(check_capability(CAPABILITY_VRF));
+        assert!(!check_capability(CAPABILITY_SIGNATURE));
+
+        // Capabilities cannot be added without the master secret.
+        let mut forged_api_key = b"signature,".to_vec();
+        forged_api_key.extend_from_slice(&api_key);
+        assert!(use_api_key(&forged_api_key).is_err());
+        assert!(mint_api_key(&[0u8; 32], &[CAPABILITY_SIGNATURE]).is_err());
+        assert!(mint_api_key(&master_secret, &["hash,ecies"]).is_err());
+    }
+}
@@ -2,6 +2,7 @@
 
 //! Library of utilities for FFI (adapting Rust to other programming languages).
 
+pub mod api_key;
 #[cfg(not(tarpaulin_include))]
 pub mod utils;
 
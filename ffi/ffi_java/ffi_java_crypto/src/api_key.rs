@@ -0,0 +1,131 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! API key function wrappers.
+
+use crate::get_result_jobject;
+
+use jni::{
+    objects::{JClass, JObject, JString, JValue},
+    sys::jobject,
+    JNIEnv,
+};
+
+#[cfg(feature = "wedpr_f_base64")]
+use wedpr_ffi_common_base64::api_key::{
+    enable_api_keys, mint_api_key, use_api_key,
+};
+#[cfg(feature = "wedpr_f_base64")]
+use wedpr_ffi_common_base64::utils::{
+    bytes_to_string, java_jstring_to_bytes, java_jstring_to_string,
+    java_set_error_field_and_extract_jobject,
+};
+
+#[cfg(feature = "wedpr_f_hex")]
+use wedpr_ffi_common_hex::api_key::{
+    enable_api_keys, mint_api_key, use_api_key,
+};
+#[cfg(feature = "wedpr_f_hex")]
+use wedpr_ffi_common_hex::utils::{
+    bytes_to_string, java_jstring_to_bytes, java_jstring_to_string,
+    java_set_error_field_and_extract_jobject,
+};
+
+#[no_mangle]
+/// Java interface for
+/// 'com.webank.wedpr.crypto.NativeInterface->apiKeyEnable'.
+pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_apiKeyEnable(
+    _env: JNIEnv,
+    _class: JClass,
+) -> jobject {
+    let result_jobject = get_result_jobject(&_env);
+
+    let master_secret = match enable_api_keys() {
+        Ok(v) => v,
+        Err(_) => {
+            return java_set_error_field_and_extract_jobject(
+                &_env,
+                &result_jobject,
+                "API keys have already been enabled",
+            )
+        },
+    };
+
+    java_safe_set_string_field!(
+        _env,
+        result_jobject,
+        bytes_to_string(&master_secret),
+        "masterSecret"
+    );
+    result_jobject.into_inner()
+}
+
+#[no_mangle]
+/// Java interface for
+/// 'com.webank.wedpr.crypto.NativeInterface->apiKeyMint', where
+/// utf8_capabilities is a comma-separated list of capabilities.
+pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_apiKeyMint(
+    _env: JNIEnv,
+    _class: JClass,
+    encoded_master_secret_jstring: JString,
+    utf8_capabilities_jstring: JString,
+) -> jobject {
+    let result_jobject = get_result_jobject(&_env);
+
+    let master_secret = java_safe_jstring_to_bytes!(
+        _env,
+        result_jobject,
+        encoded_master_secret_jstring
+    );
+    let capabilities = java_safe_jstring_to_string!(
+        _env,
+        result_jobject,
+        utf8_capabilities_jstring
+    );
+
+    let capability_list: Vec<&str> = capabilities.split(',').collect();
+    let api_key = match mint_api_key(&master_secret, &capability_list) {
+        Ok(v) => v,
+        Err(_) => {
+            return java_set_error_field_and_extract_jobject(
+                &_env,
+                &result_jobject,
+                &format!("API key mint failed, capabilities={}", capabilities),
+            )
+        },
+    };
+
+    java_safe_set_string_field!(
+        _env,
+        result_jobject,
+        bytes_to_string(&api_key),
+        "apiKey"
+    );
+    result_jobject.into_inner()
+}
+
+#[no_mangle]
+/// Java interface for
+/// 'com.webank.wedpr.crypto.NativeInterface->apiKeyUse', which presents an
+/// API key for the calls of the current thread.
+pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_apiKeyUse(
+    _env: JNIEnv,
+    _class: JClass,
+    encoded_api_key_jstring: JString,
+) -> jobject {
+    let result_jobject = get_result_jobject(&_env);
+
+    let api_key = java_safe_jstring_to_bytes!(
+        _env,
+        result_jobject,
+        encoded_api_key_jstring
+    );
+
+    if use_api_key(&api_key).is_err() {
+        return java_set_error_field_and_extract_jobject(
+            &_env,
+            &result_jobject,
+            "API key use failed",
+        );
+    }
+    result_jobject.into_inner()
+}
@@ -27,12 +27,16 @@ use jni::{
     JNIEnv,
 };
 
+#[cfg(feature = "wedpr_f_base64")]
+use wedpr_ffi_common_base64::api_key::{check_capability, CAPABILITY_ECIES};
 #[cfg(feature = "wedpr_f_base64")]
 use wedpr_ffi_common_base64::utils::{
     bytes_to_string, java_jstring_to_bytes,
     java_set_error_field_and_extract_jobject,
 };
 
+#[cfg(feature = "wedpr_f_hex")]
+use wedpr_ffi_common_hex::api_key::{check_capability, CAPABILITY_ECIES};
 #[cfg(feature = "wedpr_f_hex")]
 use wedpr_ffi_common_hex::utils::{
     bytes_to_string, java_jstring_to_bytes,
@@ -52,6 +56,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_secp256k1Eci
     message_hash_jstring: JString,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_ECIES);
 
     let public_key =
         java_safe_jstring_to_bytes!(_env, result_jobject, public_key_jstring);
@@ -95,6 +100,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_secp256k1Eci
     ciphertext_jstring: JString,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_ECIES);
 
     let private_key =
         java_safe_jstring_to_bytes!(_env, result_jobject, private_key_jstring);
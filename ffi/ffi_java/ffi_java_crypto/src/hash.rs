@@ -20,12 +20,16 @@ use jni::{
     JNIEnv,
 };
 
+#[cfg(feature = "wedpr_f_base64")]
+use wedpr_ffi_common_base64::api_key::{check_capability, CAPABILITY_HASH};
 #[cfg(feature = "wedpr_f_base64")]
 use wedpr_ffi_common_base64::utils::{
     bytes_to_string, java_jstring_to_bytes,
     java_set_error_field_and_extract_jobject,
 };
 
+#[cfg(feature = "wedpr_f_hex")]
+use wedpr_ffi_common_hex::api_key::{check_capability, CAPABILITY_HASH};
 #[cfg(feature = "wedpr_f_hex")]
 use wedpr_ffi_common_hex::utils::{
     bytes_to_string, java_jstring_to_bytes,
@@ -45,6 +49,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_keccak256Has
     encoded_message_jstring: JString,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_HASH);
 
     let encoded_message_bytes = java_safe_jstring_to_bytes!(
         _env,
@@ -75,6 +80,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_sm3Hash(
     encoded_message_jstring: JString,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_HASH);
 
     let encoded_message_bytes = java_safe_jstring_to_bytes!(
         _env,
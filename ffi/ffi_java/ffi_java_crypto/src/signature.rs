@@ -23,12 +23,18 @@ use jni::{
     JNIEnv,
 };
 
+#[cfg(feature = "wedpr_f_base64")]
+use wedpr_ffi_common_base64::api_key::{
+    check_capability, CAPABILITY_SIGNATURE,
+};
 #[cfg(feature = "wedpr_f_base64")]
 use wedpr_ffi_common_base64::utils::{
     bytes_to_string, java_jstring_to_bytes,
     java_set_error_field_and_extract_jobject,
 };
 
+#[cfg(feature = "wedpr_f_hex")]
+use wedpr_ffi_common_hex::api_key::{check_capability, CAPABILITY_SIGNATURE};
 #[cfg(feature = "wedpr_f_hex")]
 use wedpr_ffi_common_hex::utils::{
     bytes_to_string, java_jstring_to_bytes,
@@ -46,6 +52,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_secp256k1Gen
     _class: JClass,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_SIGNATURE);
 
     let (pk, sk) = SIGNATURE_SECP256K1.generate_keypair();
 
@@ -76,6 +83,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_secp256k1Sig
     msg_hash_jstring: JString,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_SIGNATURE);
 
     let private_key =
         java_safe_jstring_to_bytes!(_env, result_jobject, private_key_jstring);
@@ -117,6 +125,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_secp256k1Ver
     signature_jstring: JString,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_SIGNATURE);
 
     let public_key =
         java_safe_jstring_to_bytes!(_env, result_jobject, public_key_jstring);
@@ -142,25 +151,27 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_secp256k1Rec
     signature_jstring: JString,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_SIGNATURE);
 
     let msg_hash =
         java_safe_jstring_to_bytes!(_env, result_jobject, msg_hash_jstring);
     let signature =
         java_safe_jstring_to_bytes!(_env, result_jobject, signature_jstring);
 
-    let result = match SIGNATURE_SECP256K1.recover_public_key(&msg_hash, &signature) {
-        Ok(v) => v,
-        Err(_) => {
-            return java_set_error_field_and_extract_jobject(
-                &_env,
-                &result_jobject,
-                &format!(
-                    "secp256k1 recover failed, msg_hash={}",
-                    bytes_to_string(&msg_hash)
-                ),
-            )
-        },
-    };
+    let result =
+        match SIGNATURE_SECP256K1.recover_public_key(&msg_hash, &signature) {
+            Ok(v) => v,
+            Err(_) => {
+                return java_set_error_field_and_extract_jobject(
+                    &_env,
+                    &result_jobject,
+                    &format!(
+                        "secp256k1 recover failed, msg_hash={}",
+                        bytes_to_string(&msg_hash)
+                    ),
+                )
+            },
+        };
 
     java_safe_set_bytes_field!(_env, result_jobject, result, "publicKey");
     result_jobject.into_inner()
@@ -177,6 +188,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_sm2GenKeyPai
     _class: JClass,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_SIGNATURE);
 
     let (pk, sk) = SIGNATURE_SM2.generate_keypair();
 
@@ -206,6 +218,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_sm2Sign(
     msg_hash_jstring: JString,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_SIGNATURE);
 
     let private_key =
         java_safe_jstring_to_bytes!(_env, result_jobject, private_key_jstring);
@@ -247,6 +260,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_sm2SignFast(
     msg_hash_jstring: JString,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_SIGNATURE);
 
     let private_key =
         java_safe_jstring_to_bytes!(_env, result_jobject, private_key_jstring);
@@ -291,6 +305,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_sm2Verify(
     signature_jstring: JString,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_SIGNATURE);
 
     let public_key =
         java_safe_jstring_to_bytes!(_env, result_jobject, public_key_jstring);
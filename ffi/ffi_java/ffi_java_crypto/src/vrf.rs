@@ -12,12 +12,16 @@ use jni::{
     JNIEnv,
 };
 
+#[cfg(feature = "wedpr_f_base64")]
+use wedpr_ffi_common_base64::api_key::{check_capability, CAPABILITY_VRF};
 #[cfg(feature = "wedpr_f_base64")]
 use wedpr_ffi_common_base64::utils::{
     bytes_to_string, java_jstring_to_bytes, java_jstring_to_string,
     java_set_error_field_and_extract_jobject,
 };
 
+#[cfg(feature = "wedpr_f_hex")]
+use wedpr_ffi_common_hex::api_key::{check_capability, CAPABILITY_VRF};
 #[cfg(feature = "wedpr_f_hex")]
 use wedpr_ffi_common_hex::utils::{
     bytes_to_string, java_jstring_to_bytes, java_jstring_to_string,
@@ -41,6 +45,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_curve25519Vr
     utf8_message_jstring: JString,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_VRF);
 
     let private_key_bytes = java_safe_jstring_to_bytes!(
         _env,
@@ -91,6 +96,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_curve25519Vr
     utf8_message_jstring: JString,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_VRF);
 
     let private_key_bytes = java_safe_jstring_to_bytes!(
         _env,
@@ -149,6 +155,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_curve25519Vr
     encoded_proof_jstring: JString,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_VRF);
 
     let public_key_bytes = java_safe_jstring_to_bytes!(
         _env,
@@ -195,6 +202,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_curve25519Vr
     encoded_private_key_jstring: JString,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_VRF);
 
     let private_key_bytes = java_safe_jstring_to_bytes!(
         _env,
@@ -218,6 +226,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_curve25519Vr
     encoded_proof_jstring: JString,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_VRF);
 
     let proof_bytes = java_safe_jstring_to_bytes!(
         _env,
@@ -266,6 +275,7 @@ pub extern "system" fn Java_com_webank_wedpr_crypto_NativeInterface_curve25519Vr
     encoded_public_key_jstring: JString,
 ) -> jobject {
     let result_jobject = get_result_jobject(&_env);
+    java_safe_require_capability!(_env, result_jobject, CAPABILITY_VRF);
 
     let public_key_bytes = java_safe_jstring_to_bytes!(
         _env,
@@ -293,6 +293,23 @@ macro_rules! java_safe_set_encoded_pb_field {
     };
 }
 
+// Access control section.
+
+/// Checks that the current thread holds a capability, and returns an error
+/// object if not.
+#[macro_export]
+macro_rules! java_safe_require_capability {
+    ($_env:expr, $result_jobject:expr, $capability:expr) => {
+        if !check_capability($capability) {
+            return java_set_error_field_and_extract_jobject(
+                &$_env,
+                &$result_jobject,
+                &format!("Missing API key capability {}", $capability),
+            );
+        }
+    };
+}
+
 // C/C++ FFI macros.
 
 /// Converts C char pointer to Rust bytes without decoding, and returns a
@@ -486,3 +503,26 @@ macro_rules! c_safe_return {
         c_safe_return_with_error_value!($result, ptr::null_mut())
     };
 }
+
+/// Checks that the current thread holds a capability, and returns a specified
+/// error value if not.
+#[macro_export]
+macro_rules! c_safe_require_capability_with_error_value {
+    ($capability:expr, $error_value:expr) => {
+        if !check_capability($capability) {
+            return $error_value;
+        }
+    };
+}
+
+/// Checks that the current thread holds a capability, and returns NULL if
+/// not.
+#[macro_export]
+macro_rules! c_safe_require_capability {
+    ($capability:expr) => {
+        c_safe_require_capability_with_error_value!(
+            $capability,
+            ptr::null_mut()
+        )
+    };
+}
@@ -0,0 +1,297 @@
+// Copyright 2020 WeDPR Lab Project Authors. Licensed under Apache-2.0.
+
+//! Scenario solution of two-party credit scoring, where a model owner, e.g.
+//! a bank, evaluates its private scorecard on private features held by a
+//! feature holder, e.g. a data provider, by Paillier homomorphic encryption.
+//!
+//! A scorecard assigns points to each bin of each feature, and the score is
+//! the base points plus the points of the bin each feature falls into. The
+//! bin counts of features form the public schema of the scorecard.
+//!
+//! The protocol runs as follows, against semi-honest parties:
+//! 1) The feature holder encrypts a one-hot indicator of the bin of each
+//!    feature under its own Paillier key, and sends a ScoringRequest.
+//! 2) The model owner computes the encrypted score homomorphically, adds a
+//!    random mask, and returns a ScoringResponse.
+//! 3) The feature holder decrypts the masked score, which reveals nothing about
+//!    the score, and returns a MaskedScore.
+//! 4) The model owner removes the mask to get the score.
+//!
+//! The model owner learns only the score, and the feature holder learns
+//! nothing about the scorecard except its schema.
+
+#[macro_use]
+extern crate wedpr_l_macros;
+
+use num_bigint::BigUint;
+use num_traits::{ToPrimitive, Zero};
+use wedpr_l_crypto_paillier::{
+    generate_keypair, random_below, PaillierPrivateKey, PaillierPublicKey,
+    MIN_PAILLIER_KEY_SIZE_IN_BITS,
+};
+use wedpr_l_utils::error::WedprError;
+
+/// Scorecard model held by the model owner.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Scorecard {
+    pub base_points: i64,
+    /// Points of each bin of each feature.
+    pub points: Vec<Vec<i64>>,
+}
+
+impl Scorecard {
+    /// Gets the bin counts of features, which are shared with the feature
+    /// holder.
+    pub fn schema(&self) -> Vec<usize> {
+        self.points.iter().map(|v| v.len()).collect()
+    }
+
+    /// Evaluates the scorecard on plaintext bins, for testing models.
+    pub fn evaluate(&self, bins: &[usize]) -> Result<i64, WedprError> {
+        check_bins(&self.schema(), bins)?;
+        Ok(self.base_points
+            + self
+                .points
+                .iter()
+                .zip(bins)
+                .map(|(points, bin)| points[*bin])
+                .sum::<i64>())
+    }
+}
+
+fn check_bins(schema: &[usize], bins: &[usize]) -> Result<(), WedprError> {
+    if schema.len() != bins.len()
+        || schema.iter().zip(bins).any(|(count, bin)| bin >= count)
+    {
+        wedpr_println!("Feature bins do not match the scorecard schema");
+        return Err(WedprError::ArgumentError);
+    }
+    Ok(())
+}
+
+/// Request from the feature holder with its encrypted features.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ScoringRequest {
+    pub public_key: PaillierPublicKey,
+    /// Ciphertexts of the one-hot bin indicator of each feature.
+    pub encrypted_bins: Vec<Vec<BigUint>>,
+}
+
+/// Response from the model owner with the encrypted masked score.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ScoringResponse {
+    pub encrypted_masked_score: BigUint,
+}
+
+/// Masked score decrypted by the feature holder.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct MaskedScore {
+    pub value: BigUint,
+}
+
+/// Feature holder keeping its features private.
+#[derive(Debug, Clone)]
+pub struct FeatureHolder {
+    private_key: PaillierPrivateKey,
+}
+
+impl FeatureHolder {
+    /// Creates a feature holder with a new Paillier key pair of the key size.
+    pub fn new(key_size_in_bits: usize) -> Result<Self, WedprError> {
+        let (_, private_key) = generate_keypair(key_size_in_bits)?;
+        Ok(FeatureHolder { private_key })
+    }
+
+    /// Creates a request with the bin of each feature under the schema.
+    pub fn request(
+        &self,
+        schema: &[usize],
+        bins: &[usize],
+    ) -> Result<ScoringRequest, WedprError> {
+        check_bins(schema, bins)?;
+        let public_key = &self.private_key.public_key;
+        let mut encrypted_bins = Vec::with_capacity(schema.len());
+        for (count, bin) in schema.iter().zip(bins) {
+            let mut indicators = Vec::with_capacity(*count);
+            for i in 0..*count {
+                let indicator = BigUint::from((i == *bin) as u32);
+                indicators.push(public_key.encrypt(&indicator)?);
+            }
+            encrypted_bins.push(indicators);
+        }
+        Ok(ScoringRequest {
+            public_key: public_key.clone(),
+            encrypted_bins,
+        })
+    }
+
+    /// Decrypts the masked score of a response.
+    pub fn decrypt(
+        &self,
+        response: &ScoringResponse,
+    ) -> Result<MaskedScore, WedprError> {
+        Ok(MaskedScore {
+            value: self
+                .private_key
+                .decrypt(&response.encrypted_masked_score)?,
+        })
+    }
+}
+
+/// Model owner keeping its scorecard private.
+#[derive(Debug, Clone)]
+pub struct ModelOwner {
+    scorecard: Scorecard,
+    /// Mask and modulus of the pending request.
+    pending: Option<(BigUint, BigUint)>,
+}
+
+impl ModelOwner {
+    /// Creates a model owner with a scorecard.
+    pub fn new(scorecard: Scorecard) -> Self {
+        ModelOwner {
+            scorecard,
+            pending: None,
+        }
+    }
+
+    /// Gets the schema of the scorecard.
+    pub fn schema(&self) -> Vec<usize> {
+        self.scorecard.schema()
+    }
+
+    /// Evaluates the scorecard on a request, and returns the masked score.
+    pub fn respond(
+        &mut self,
+        request: &ScoringRequest,
+    ) -> Result<ScoringResponse, WedprError> {
+        let public_key = &request.public_key;
+        if public_key.n.bits() < MIN_PAILLIER_KEY_SIZE_IN_BITS as u64
+            || public_key.n_squared != &public_key.n * &public_key.n
+        {
+            wedpr_println!("Paillier public key of the request is invalid");
+            return Err(WedprError::ArgumentError);
+        }
+        let schema = self.schema();
+        if request.encrypted_bins.iter().map(|v| v.len()).ne(schema) {
+            wedpr_println!("Request does not match the scorecard schema");
+            return Err(WedprError::ArgumentError);
+        }
+        let mask = random_below(&public_key.n);
+        let mut encrypted_score = public_key.encrypt(&signed_to_plaintext(
+            self.scorecard.base_points,
+            &public_key.n,
+        ))?;
+        for (points, indicators) in
+            self.scorecard.points.iter().zip(&request.encrypted_bins)
+        {
+            for (point, indicator) in points.iter().zip(indicators) {
+                if indicator.is_zero() || indicator >= &public_key.n_squared {
+                    wedpr_println!("Request has an invalid ciphertext");
+                    return Err(WedprError::FormatError);
+                }
+                let weighted = public_key.mul_plaintext(
+                    indicator,
+                    &signed_to_plaintext(*point, &public_key.n),
+                );
+                encrypted_score = public_key.add(&encrypted_score, &weighted);
+            }
+        }
+        let encrypted_masked_score = public_key
+            .rerandomize(&public_key.add_plaintext(&encrypted_score, &mask));
+        self.pending = Some((mask, public_key.n.clone()));
+        Ok(ScoringResponse {
+            encrypted_masked_score,
+        })
+    }
+
+    /// Removes the mask of the pending request to get the score.
+    pub fn finish(
+        &mut self,
+        masked_score: &MaskedScore,
+    ) -> Result<i64, WedprError> {
+        let (mask, n) = match self.pending.take() {
+            Some(v) => v,
+            None => {
+                wedpr_println!("No pending scoring request");
+                return Err(WedprError::ArgumentError);
+            },
+        };
+        if masked_score.value >= n {
+            return Err(WedprError::FormatError);
+        }
+        let score = (&masked_score.value + &n - &mask) % &n;
+        plaintext_to_signed(&score, &n)
+    }
+}
+
+/// Encodes a signed value as a plaintext modulo n.
+fn signed_to_plaintext(value: i64, n: &BigUint) -> BigUint {
+    let magnitude = BigUint::from(value.unsigned_abs());
+    if value < 0 {
+        (n - magnitude % n) % n
+    } else {
+        magnitude % n
+    }
+}
+
+/// Decodes a signed value from a plaintext modulo n, where plaintexts above
+/// n / 2 are negative.
+fn plaintext_to_signed(
+    value: &BigUint,
+    n: &BigUint,
+) -> Result<i64, WedprError> {
+    let decoded = if value > &(n >> 1) {
+        (n - value).to_i64().map(|v| -v)
+    } else {
+        value.to_i64()
+    };
+    match decoded {
+        Some(v) => Ok(v),
+        None => {
+            wedpr_println!("Score is out of range");
+            Err(WedprError::FormatError)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credit_scoring() {
+        // Age, income and overdue count bins.
+        let scorecard = Scorecard {
+            base_points: 600,
+            points: vec![vec![-20, 10, 30], vec![-10, 25], vec![
+                15, -40, -120, -300,
+            ]],
+        };
+        let mut model_owner = ModelOwner::new(scorecard.clone());
+        let feature_holder =
+            FeatureHolder::new(MIN_PAILLIER_KEY_SIZE_IN_BITS).unwrap();
+
+        for bins in &[[1, 1, 0], [0, 0, 3]] {
+            let request =
+                feature_holder.request(&model_owner.schema(), bins).unwrap();
+            let response = model_owner.respond(&request).unwrap();
+            let masked_score = feature_holder.decrypt(&response).unwrap();
+            assert_eq!(
+                model_owner.finish(&masked_score).unwrap(),
+                scorecard.evaluate(bins).unwrap()
+            );
+        }
+        assert_eq!(scorecard.evaluate(&[0, 0, 3]).unwrap(), 270);
+        assert!(model_owner.finish(&MaskedScore::default()).is_err());
+        assert!(feature_holder
+            .request(&model_owner.schema(), &[3, 0, 0])
+            .is_err());
+
+        let mut request = feature_holder
+            .request(&model_owner.schema(), &[0, 0, 0])
+            .unwrap();
+        request.encrypted_bins.pop();
+        assert!(model_owner.respond(&request).is_err());
+    }
+}